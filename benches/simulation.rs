@@ -0,0 +1,97 @@
+use std::{collections::HashMap, fs, hint::black_box, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nalgebra::DMatrix;
+
+use sally_mc::models::{class_graph::{ClassGraph, StateClass}, lbl, model_storage::ModelStorage, petri::{CompiledPetriNet, PetriStructure}, tapn::{tapn_edge::TAPNEdgeData, tapn_place::TAPNPlace, tapn_token::{TAPNPlaceList, TAPNPlaceListAccessor, TAPNToken}, tapn_transition::TAPNTransition}, time::{ClockValue, TimeBound, TimeInterval}, Edge, Model, ModelState};
+use sally_mc::computation::DBM;
+use sally_mc::verification::text_query_parser::parse_query;
+
+// Models are checked in under benches/models, as serialized Petri structures
+fn load_net(name : &str) -> CompiledPetriNet {
+    let path = format!("{}/benches/models/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+    let structure : PetriStructure = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    structure.compile().unwrap()
+}
+
+fn marked(net : &CompiledPetriNet, marking : &[(&str, i32)]) -> ModelState {
+    let marking : HashMap<_, _> = marking.iter().map(|(p, k)| (lbl(p), *k)).collect();
+    net.context().make_initial_state(net, marking)
+}
+
+fn ring() -> (CompiledPetriNet, ModelState) {
+    let net = load_net("ring");
+    let state = marked(&net, &[("s0", 1), ("s3", 1), ("s6", 1), ("s9", 1), ("resource", 1)]);
+    (net, state)
+}
+
+fn petri_firing(c : &mut Criterion) {
+    for (name, marking) in [("workflow", vec![("p0", 1)]), ("ring", vec![("s0", 1), ("s3", 1), ("s6", 1), ("s9", 1), ("resource", 1)])] {
+        let net = load_net(name);
+        let state = marked(&net, &marking);
+        let enabled : Vec<_> = net.enabled_transitions(&state).iter().map(|t| t.index).collect();
+        c.bench_function(&format!("petri_enabling/{}", name), |b| b.iter(|| {
+            black_box(net.available_actions(black_box(&state)))
+        }));
+        c.bench_function(&format!("petri_fire/{}", name), |b| b.iter(|| {
+            for t in enabled.iter() {
+                black_box(net.fire(state.clone(), *t));
+            }
+        }));
+    }
+}
+
+// firing_dates is left out while arc_dates is unfinished, fireable token combinations are measured instead
+fn tapn_firing(c : &mut Criterion) {
+    let place = Arc::new(TAPNPlace::new(lbl("p")));
+    let transition = Arc::new(TAPNTransition::new(lbl("t"), vec![lbl("p")], vec![]));
+    let data = TAPNEdgeData { interval : TimeInterval(TimeBound::Large(2), TimeBound::Large(5)), weight : 3 };
+    transition.add_input_edge(Edge::data_edge(&place, &transition, data));
+    let tokens = (0..24).map(|i| TAPNToken { count : 1 + i % 3, age : ClockValue::from(i as f64 * 0.25) }).collect();
+    let mut storage = ModelStorage::from(TAPNPlaceList { places : vec![tokens] });
+    c.bench_function("tapn_fireable_tokens", |b| b.iter(|| {
+        black_box(transition.fireable_tokens(TAPNPlaceListAccessor::from(&mut storage)))
+    }));
+}
+
+fn dbm_canonicalization(c : &mut Criterion) {
+    let n = 12;
+    let mut constraints = DMatrix::from_element(n + 1, n + 1, TimeBound::Infinite);
+    for i in 0..=n {
+        constraints[(i, i)] = TimeBound::Large(0);
+    }
+    for i in 1..=n {
+        constraints[(i, 0)] = TimeBound::Large(10 + i as i32);
+        constraints[(0, i)] = TimeBound::Large(-(i as i32));
+        constraints[(i, i % n + 1)] = TimeBound::Strict(3);
+    }
+    let dbm = DBM::from(constraints);
+    c.bench_function("dbm_canonical", |b| b.iter(|| {
+        let mut zone = dbm.clone();
+        zone.make_canonical();
+        black_box(zone)
+    }));
+}
+
+fn class_successors(c : &mut Criterion) {
+    let (net, state) = ring();
+    let class = Arc::new(StateClass::compute_class(&net, &state));
+    let enabled = class.enabled_clocks();
+    c.bench_function("class_successor/ring", |b| b.iter(|| {
+        for t in enabled.iter() {
+            black_box(ClassGraph::successor(&net, &class, *t));
+        }
+    }));
+}
+
+fn condition_evaluation(c : &mut Criterion) {
+    let (net, state) = ring();
+    let mut query = parse_query(String::from("E <> (s3 & resource) | s0 + s6 + s9 >= 2 & !deadlock")).unwrap();
+    query.apply_to(net.context()).unwrap();
+    c.bench_function("condition_evaluation/ring", |b| b.iter(|| {
+        black_box(query.condition.evaluate(black_box(&state)))
+    }));
+}
+
+criterion_group!(benches, petri_firing, tapn_firing, dbm_canonicalization, class_successors, condition_evaluation);
+criterion_main!(benches);
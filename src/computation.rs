@@ -1,12 +1,18 @@
 mod bit_set;
 mod dbm;
+mod parametric;
+mod pdbm;
 
 pub mod virtual_memory;
 pub mod combinatory;
 pub mod intervals;
+pub mod statistics;
+pub mod random;
 
 pub use bit_set::BitSet;
-pub use dbm::DBM;
+pub use dbm::{DBM, DBMConstraint, DatesVector};
+pub use parametric::{LinearExpr, ParameterConstraint, ParameterConstraints};
+pub use pdbm::{PDBM, ParametricBound};
 
 #[macro_export]
 macro_rules! flag {
@@ -1,3 +1,4 @@
+use std::hash::{Hash, Hasher};
 use std::ops::{BitAnd, BitOr, Not};
 use std::cmp::min;
 
@@ -6,7 +7,8 @@ use std::cmp::min;
 const CELL_SIZE : usize = 64; 
 
 // Structure for fast operations on boolean sets : And, Or, Not... Complexity O(n) to retrieve indexs after computation
-#[derive(Clone)]
+// Sets are equal when they enable the same indexes, whatever the number of cells allocated
+#[derive(Debug, Clone, Default)]
 pub struct BitSet {
     enabled: Vec<u64>
 }
@@ -101,6 +103,49 @@ impl BitSet {
         return true;
     }
 
+    // Number of enabled indexes
+    pub fn len(&self) -> usize {
+        self.enabled.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    // Every index enabled in other is enabled here
+    pub fn includes(&self, other : &BitSet) -> bool {
+        other.enabled.iter().enumerate().all(|(i, b)| {
+            b & !self.enabled.get(i).copied().unwrap_or(0) == 0
+        })
+    }
+
+    pub fn intersects(&self, other : &BitSet) -> bool {
+        self.enabled.iter().zip(other.enabled.iter()).any(|(a, b)| a & b != 0)
+    }
+
+    // Indexes enabled here and not in other
+    pub fn difference(&self, other : &BitSet) -> BitSet {
+        BitSet::from(self.enabled.iter().enumerate().map(|(i, b)| {
+            b & !other.enabled.get(i).copied().unwrap_or(0)
+        }).collect())
+    }
+
+    // Cells up to the last non-zero one
+    fn significant_cells(&self) -> &[u64] {
+        let len = self.enabled.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        &self.enabled[..len]
+    }
+
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other : &Self) -> bool {
+        self.significant_cells() == other.significant_cells()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H : Hasher>(&self, state : &mut H) {
+        self.significant_cells().hash(state);
+    }
 }
 
 impl BitOr for BitSet {
@@ -3,11 +3,75 @@ use std::{cmp::min, ops::{Index, IndexMut}};
 
 use nalgebra::{DMatrix, DVector};
 use num_traits::{Bounded, Zero};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::models::time::{ClockValue, TimeBound, TimeInterval};
+use crate::models::{time::{ClockValue, TimeBound, TimeInterval}, ClockId};
 
-use super::intervals::Convex;
+use super::intervals::{Convex, Measurable};
+
+/// Values of the variables of a zone, the reference one excluded
+pub type DatesVector = Vec<f64>;
+
+// Rejection sampling attempts before falling back to sequential sampling
+const SAMPLING_ATTEMPTS : usize = 100;
+
+fn draw<R : Rng + ?Sized>(rng : &mut R, (low, high) : (f64, f64)) -> f64 {
+    if low < high { rng.gen_range(low..high) } else { low }
+}
+
+// Unbounded sides are capped at the given distance from the other one
+fn capped(low : f64, high : f64, horizon : f64) -> (f64, f64) {
+    match (low.is_finite(), high.is_finite()) {
+        (true, true) => (low, high),
+        (true, false) => (low, low + horizon),
+        (false, true) => (high - horizon, high),
+        (false, false) => (0.0, horizon)
+    }
+}
+
+/// Single DBM constraint x_i - x_j < c (or <= c), variable 0 being the constant zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DBMConstraint {
+    pub i : usize,
+    pub j : usize,
+    pub bound : TimeBound
+}
+
+impl DBMConstraint {
+
+    pub fn new(i : usize, j : usize, bound : TimeBound) -> Self {
+        DBMConstraint { i, j, bound }
+    }
+
+    // x_i < c (or <= c)
+    pub fn upper(i : usize, bound : TimeBound) -> Self {
+        DBMConstraint { i, j : 0, bound }
+    }
+
+    // x_i > c (or >= c), given as -x_i < -c
+    pub fn lower(i : usize, bound : TimeBound) -> Self {
+        DBMConstraint { i : 0, j : i, bound : -bound }
+    }
+
+    pub fn is_satisfied(&self, point : &[f64]) -> bool {
+        let value = |k : usize| if k == 0 { 0.0 } else { point[k - 1] };
+        let diff = value(self.i) - value(self.j);
+        match self.bound {
+            TimeBound::Strict(c) => diff < c as f64,
+            TimeBound::Large(c) => diff <= c as f64,
+            TimeBound::Infinite => true,
+            TimeBound::MinusInfinite => false
+        }
+    }
+
+}
+
+impl fmt::Display for DBMConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x{} - x{} {}", self.i, self.j, self.bound)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DBM {
@@ -155,6 +219,201 @@ impl DBM {
         }
     }
 
+    // Zone defined by a list of constraints on the given number of variables, in canonical form
+    pub fn from_constraints(vars : usize, constraints : impl IntoIterator<Item = DBMConstraint>) -> Self {
+        let mut dbm = DBM::new(vars);
+        for c in constraints {
+            dbm[(c.i, c.j)] = dbm[(c.i, c.j)].intersection(c.bound);
+        }
+        dbm.make_canonical();
+        dbm
+    }
+
+    // Non trivial constraints of the matrix
+    pub fn constraints(&self) -> impl Iterator<Item = DBMConstraint> + '_ {
+        let n = self.constraints.nrows();
+        (0..n).flat_map(move |i| (0..n).map(move |j| DBMConstraint::new(i, j, self.constraints[(i, j)])))
+            .filter(|c| c.i != c.j && c.bound != TimeBound::Infinite)
+    }
+
+    pub fn to_constraints(&self) -> Vec<DBMConstraint> {
+        self.constraints().collect()
+    }
+
+    // Tightest interval of each variable (excluding the reference one), other constraints being taken into account
+    pub fn bounds(&self) -> Vec<TimeInterval> {
+        let canonical = self.get_canonical();
+        (1..=self.vars_count()).map(|i| canonical.rectangulars(i)).collect()
+    }
+
+    pub fn contains_point(&self, point : &[f64]) -> bool {
+        point.len() == self.vars_count() && !self.is_empty() && self.constraints().all(|c| c.is_satisfied(point))
+    }
+
+    // Values of x_i compatible with the already assigned variables, as ((low, low strict), (high, high strict))
+    fn value_range(&self, assigned : &[Option<f64>], i : usize) -> ((f64, bool), (f64, bool)) {
+        let (mut low, mut high) = ((f64::NEG_INFINITY, false), (f64::INFINITY, false));
+        for (j, value) in assigned.iter().enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            let lower = (value - self.constraints[(j, i)].float(), matches!(self.constraints[(j, i)], TimeBound::Strict(_)));
+            if lower.0 > low.0 || (lower.0 == low.0 && lower.1) {
+                low = lower;
+            }
+            let upper = (value + self.constraints[(i, j)].float(), matches!(self.constraints[(i, j)], TimeBound::Strict(_)));
+            if upper.0 < high.0 || (upper.0 == high.0 && upper.1) {
+                high = upper;
+            }
+        }
+        (low, high)
+    }
+
+    // A point of the zone, away from its strict borders. Variables are fixed one at a time, which canonical form allows.
+    pub fn sample_point(&self) -> Option<DatesVector> {
+        let canonical = self.get_canonical();
+        if canonical.is_empty() {
+            return None;
+        }
+        let n = self.vars_count();
+        let mut assigned : Vec<Option<f64>> = vec![None ; n + 1];
+        assigned[0] = Some(0.0);
+        for i in 1..=n {
+            let ((low, low_strict), (high, _)) = canonical.value_range(&assigned, i);
+            assigned[i] = Some(match (low.is_finite(), high.is_finite()) {
+                (true, _) if !low_strict => low,
+                (true, true) => (low + high) / 2.0,
+                (true, false) => low + 1.0,
+                (false, true) => high - 1.0,
+                (false, false) => 0.0
+            });
+        }
+        Some(assigned.into_iter().skip(1).flatten().collect())
+    }
+
+    // Largest finite constant of the zone, at least one time unit
+    fn horizon(&self) -> f64 {
+        self.constraints().map(|c| c.bound.float().abs()).filter(|x| x.is_finite()).fold(1.0, f64::max)
+    }
+
+    // Point of the zone drawn uniformly, by rejection from its bounding box. Unbounded variables are capped at the largest
+    // constant of the zone past their finite bound. Thin zones, where rejection keeps failing, fall back to drawing variables
+    // one at a time in the range left by the previous ones : well spread, but not uniform.
+    pub fn sample_uniform<R : Rng + ?Sized>(&self, rng : &mut R) -> Option<DatesVector> {
+        let canonical = self.get_canonical();
+        if canonical.is_empty() {
+            return None;
+        }
+        let n = self.vars_count();
+        let horizon = canonical.horizon();
+        let bounding_box : Vec<(f64, f64)> = canonical.bounds().iter().map(|b| capped(b.0.float(), b.1.float(), horizon)).collect();
+        for _ in 0..SAMPLING_ATTEMPTS {
+            let point : DatesVector = bounding_box.iter().map(|range| draw(rng, *range)).collect();
+            if canonical.contains_point(&point) {
+                return Some(point);
+            }
+        }
+        let mut assigned : Vec<Option<f64>> = vec![None ; n + 1];
+        assigned[0] = Some(0.0);
+        for i in 1..=n {
+            let ((low, _), (high, _)) = canonical.value_range(&assigned, i);
+            assigned[i] = Some(draw(rng, capped(low, high, horizon)));
+        }
+        let point : DatesVector = assigned.into_iter().skip(1).flatten().collect();
+        if canonical.contains_point(&point) {
+            Some(point)
+        } else {
+            canonical.sample_point()
+        }
+    }
+
+    // Vertices of the closure of the zone. Each one is reached by fixing variables in some order, every variable taking
+    // an extreme value : exponential in the number of variables, meant for small zones.
+    pub fn vertices(&self) -> Vec<DatesVector> {
+        let canonical = self.get_canonical();
+        let mut vertices = Vec::new();
+        if canonical.is_empty() {
+            return vertices;
+        }
+        let mut assigned : Vec<Option<f64>> = vec![None ; self.vars_count() + 1];
+        assigned[0] = Some(0.0);
+        canonical.extreme_points(&mut assigned, &mut vertices);
+        vertices
+    }
+
+    fn extreme_points(&self, assigned : &mut Vec<Option<f64>>, vertices : &mut Vec<Vec<f64>>) {
+        if assigned.iter().all(Option::is_some) {
+            let vertex : Vec<f64> = assigned.iter().skip(1).flatten().cloned().collect();
+            if !vertices.contains(&vertex) {
+                vertices.push(vertex);
+            }
+            return;
+        }
+        for i in 1..assigned.len() {
+            if assigned[i].is_some() {
+                continue;
+            }
+            let ((low, _), (high, _)) = self.value_range(assigned, i);
+            let extremes = if low == high { vec![low] } else { vec![low, high] };
+            for value in extremes.into_iter().filter(|v| v.is_finite()) {
+                assigned[i] = Some(value);
+                self.extreme_points(assigned, vertices);
+            }
+            assigned[i] = None;
+        }
+    }
+
+    // Topological closure, strict bounds being made large
+    pub fn closure(&self) -> DBM {
+        DBM {
+            constraints : self.constraints.map(|b| match b {
+                TimeBound::Strict(x) => TimeBound::Large(x),
+                b => b
+            })
+        }
+    }
+
+    // Exact volume of the zone, infinite if unbounded. Strict bounds don't change the measure, the closure is used.
+    pub fn volume(&self) -> f64 {
+        self.closure().get_canonical().closed_volume()
+    }
+
+    // Cone decomposition from a point v of the zone : each facet x_i - x_j = c contributes (c - (v_i - v_j)) / n times
+    // the volume of its projection along x_i (x_j if i is the reference), a zone with one less variable.
+    // Normalization factors of the height and of the projection cancel out. Exponential in the number of variables.
+    fn closed_volume(&self) -> f64 {
+        let n = self.vars_count();
+        if self.is_empty() {
+            return 0.0;
+        }
+        if n == 0 {
+            return 1.0;
+        }
+        if (1..=n).any(|i| self.constraints[(i, 0)] == TimeBound::Infinite || self.constraints[(0, i)] == TimeBound::Infinite) {
+            return f64::INFINITY;
+        }
+        let Some(point) = self.sample_point() else {
+            return 0.0;
+        };
+        let value = |k : usize| if k == 0 { 0.0 } else { point[k - 1] };
+        let mut volume = 0.0;
+        for c in self.constraints() {
+            let height = c.bound.float() - (value(c.i) - value(c.j));
+            if height <= 0.0 {
+                continue;
+            }
+            let mut facet = self.clone();
+            facet[(c.j, c.i)] = min(facet[(c.j, c.i)], -c.bound);
+            facet.make_canonical();
+            if facet.is_empty() {
+                continue;
+            }
+            facet.remove_var(if c.i == 0 { c.j } else { c.i });
+            volume += height * facet.closed_volume();
+        }
+        volume / n as f64
+    }
+
     pub fn time_closure(&self) -> DBM { 
         let mut res = self.clone();
         let max_delta = self.constraints.column(0).iter().min().unwrap().clone();
@@ -180,6 +439,27 @@ impl IndexMut<(usize,usize)> for DBM {
     }
 }
 
+impl Index<(ClockId, ClockId)> for DBM {
+    type Output = TimeBound;
+    fn index(&self, (i, j): (ClockId, ClockId)) -> &Self::Output {
+        &self.constraints[(i.index(), j.index())]
+    }
+}
+
+impl IndexMut<(ClockId, ClockId)> for DBM {
+    fn index_mut(&mut self, (i, j): (ClockId, ClockId)) -> &mut Self::Output {
+        &mut self.constraints[(i.index(), j.index())]
+    }
+}
+
+impl Measurable for DBM {
+
+    fn len(&self) -> f64 {
+        self.volume()
+    }
+
+}
+
 impl fmt::Display for DBM {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "DBM{}", self.constraints)
@@ -0,0 +1,215 @@
+use std::{fmt, ops::{Add, Neg, Sub}};
+
+use serde::{Deserialize, Serialize};
+
+/// Linear expression over parameters p_0..p_n : sum of coefficients[k] * p_k, plus a constant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LinearExpr {
+    pub coefficients : Vec<i32>,
+    pub constant : i32,
+}
+
+impl LinearExpr {
+
+    pub fn constant(constant : i32) -> Self {
+        LinearExpr { coefficients : Vec::new(), constant }
+    }
+
+    pub fn parameter(k : usize) -> Self {
+        let mut coefficients = vec![0 ; k + 1];
+        coefficients[k] = 1;
+        LinearExpr { coefficients, constant : 0 }
+    }
+
+    pub fn coefficient(&self, k : usize) -> i32 {
+        self.coefficients.get(k).cloned().unwrap_or(0)
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.coefficients.iter().all(|a| *a == 0)
+    }
+
+    pub fn evaluate(&self, values : &[i32]) -> i32 {
+        self.coefficients.iter().enumerate().map(|(k, a)| a * values[k]).sum::<i32>() + self.constant
+    }
+
+    fn combine(&self, other : &LinearExpr, op : fn(i32, i32) -> i32) -> LinearExpr {
+        let len = self.coefficients.len().max(other.coefficients.len());
+        let mut coefficients : Vec<i32> = (0..len).map(|k| op(self.coefficient(k), other.coefficient(k))).collect();
+        while coefficients.last() == Some(&0) {
+            coefficients.pop();
+        }
+        LinearExpr { coefficients, constant : op(self.constant, other.constant) }
+    }
+
+}
+
+impl Add for LinearExpr {
+    type Output = LinearExpr;
+    fn add(self, rhs : LinearExpr) -> LinearExpr {
+        self.combine(&rhs, |a, b| a + b)
+    }
+}
+
+impl Sub for LinearExpr {
+    type Output = LinearExpr;
+    fn sub(self, rhs : LinearExpr) -> LinearExpr {
+        self.combine(&rhs, |a, b| a - b)
+    }
+}
+
+impl Neg for LinearExpr {
+    type Output = LinearExpr;
+    fn neg(self) -> LinearExpr {
+        LinearExpr::constant(0) - self
+    }
+}
+
+impl From<i32> for LinearExpr {
+    fn from(value : i32) -> Self {
+        LinearExpr::constant(value)
+    }
+}
+
+impl fmt::Display for LinearExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms : Vec<String> = self.coefficients.iter().enumerate().filter(|(_, a)| **a != 0).map(|(k, a)| match a {
+            1 => format!("p{}", k),
+            -1 => format!("-p{}", k),
+            a => format!("{}*p{}", a, k)
+        }).collect();
+        if self.constant != 0 || terms.is_empty() {
+            terms.push(self.constant.to_string());
+        }
+        write!(f, "{}", terms.join(" + ").replace("+ -", "- "))
+    }
+}
+
+/// Linear constraint on parameters : expr <= 0, or expr < 0 if strict
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ParameterConstraint {
+    pub expr : LinearExpr,
+    pub strict : bool,
+}
+
+impl ParameterConstraint {
+
+    // e1 <= e2
+    pub fn le(e1 : LinearExpr, e2 : LinearExpr) -> Self {
+        ParameterConstraint { expr : e1 - e2, strict : false }
+    }
+
+    // e1 < e2
+    pub fn ls(e1 : LinearExpr, e2 : LinearExpr) -> Self {
+        ParameterConstraint { expr : e1 - e2, strict : true }
+    }
+
+    pub fn negation(&self) -> Self {
+        ParameterConstraint { expr : -self.expr.clone(), strict : !self.strict }
+    }
+
+    pub fn is_satisfied(&self, values : &[i32]) -> bool {
+        let value = self.expr.evaluate(values);
+        if self.strict { value < 0 } else { value <= 0 }
+    }
+
+}
+
+impl fmt::Display for ParameterConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} 0", self.expr, if self.strict { "<" } else { "<=" })
+    }
+}
+
+// Row of a Fourier-Motzkin system : coefficients, constant and strictness
+type Inequality = (Vec<i64>, i64, bool);
+
+fn gcd(a : i64, b : i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+// Scales an inequality down, dividing by a positive number keeps its direction
+fn normalize((mut coefficients, mut constant, strict) : Inequality) -> Inequality {
+    let divisor = coefficients.iter().fold(constant, |g, a| gcd(g, *a));
+    if divisor > 1 {
+        coefficients.iter_mut().for_each(|a| *a /= divisor);
+        constant /= divisor;
+    }
+    (coefficients, constant, strict)
+}
+
+/// Conjunction of linear constraints on non-negative parameters, the parameter domain of a PDBM
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ParameterConstraints {
+    pub parameters : usize,
+    pub constraints : Vec<ParameterConstraint>,
+}
+
+impl ParameterConstraints {
+
+    pub fn new(parameters : usize) -> Self {
+        ParameterConstraints { parameters, constraints : Vec::new() }
+    }
+
+    pub fn add(&mut self, constraint : ParameterConstraint) {
+        if !self.constraints.contains(&constraint) {
+            self.constraints.push(constraint);
+        }
+    }
+
+    pub fn is_satisfied(&self, values : &[i32]) -> bool {
+        values.len() == self.parameters && values.iter().all(|v| *v >= 0) && self.constraints.iter().all(|c| c.is_satisfied(values))
+    }
+
+    // Rational satisfiability, by Fourier-Motzkin elimination of the parameters one after the other
+    pub fn is_satisfiable(&self) -> bool {
+        let n = self.parameters;
+        let mut system : Vec<Inequality> = self.constraints.iter().map(|c| {
+            let coefficients = (0..n).map(|k| c.expr.coefficient(k) as i64).collect();
+            normalize((coefficients, c.expr.constant as i64, c.strict))
+        }).collect();
+        for k in 0..n {
+            system.push(normalize(((0..n).map(|i| if i == k { -1 } else { 0 }).collect(), 0, false)));
+        }
+        for k in 0..n {
+            let (positive, rest) : (Vec<Inequality>, Vec<Inequality>) = system.into_iter().partition(|c| c.0[k] > 0);
+            let (negative, mut next) : (Vec<Inequality>, Vec<Inequality>) = rest.into_iter().partition(|c| c.0[k] < 0);
+            for p in positive.iter() {
+                for q in negative.iter() {
+                    let (a, b) = (p.0[k], -q.0[k]);
+                    let coefficients = (0..n).map(|i| p.0[i] * b + q.0[i] * a).collect();
+                    let combined = normalize((coefficients, p.1 * b + q.1 * a, p.2 || q.2));
+                    if !next.contains(&combined) {
+                        next.push(combined);
+                    }
+                }
+            }
+            system = next;
+        }
+        system.iter().all(|(_, c, strict)| if *strict { *c < 0 } else { *c <= 0 })
+    }
+
+    pub fn implies(&self, constraint : &ParameterConstraint) -> bool {
+        let mut negated = self.clone();
+        negated.add(constraint.negation());
+        !negated.is_satisfiable()
+    }
+
+    // Domain restricted by the constraint, None if no parameter valuation is left
+    pub fn refine(&self, constraint : ParameterConstraint) -> Option<Self> {
+        if self.implies(&constraint) {
+            return Some(self.clone());
+        }
+        let mut refined = self.clone();
+        refined.add(constraint);
+        if refined.is_satisfiable() { Some(refined) } else { None }
+    }
+
+}
+
+impl fmt::Display for ParameterConstraints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let constraints : Vec<String> = self.constraints.iter().map(ParameterConstraint::to_string).collect();
+        write!(f, "[{}]", constraints.join(", "))
+    }
+}
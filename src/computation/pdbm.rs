@@ -0,0 +1,244 @@
+use std::{fmt, ops::{Add, Index, IndexMut}};
+
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+
+use crate::models::time::TimeBound;
+
+use super::{parametric::{LinearExpr, ParameterConstraint, ParameterConstraints}, DBM};
+
+use ParametricBound::*;
+
+/// "</<=" constraint whose bound is a linear expression over parameters
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ParametricBound {
+    #[serde(rename="<")]
+    Strict(LinearExpr),
+    #[serde(rename="<=")]
+    Large(LinearExpr),
+    #[serde(rename="+inf")]
+    Infinite,
+}
+
+impl ParametricBound {
+
+    pub fn zero() -> Self {
+        Large(LinearExpr::constant(0))
+    }
+
+    pub fn instantiate(&self, values : &[i32]) -> TimeBound {
+        match self {
+            Strict(e) => TimeBound::Strict(e.evaluate(values)),
+            Large(e) => TimeBound::Large(e.evaluate(values)),
+            Infinite => TimeBound::Infinite
+        }
+    }
+
+    // Parameter constraint for self to be at least as tight as other, or its constant truth value
+    pub fn tighter_than(&self, other : &ParametricBound) -> Result<ParameterConstraint, bool> {
+        match (self, other) {
+            (_, Infinite) => Err(true),
+            (Infinite, _) => Err(false),
+            (Large(e1), Strict(e2)) => Ok(ParameterConstraint::ls(e1.clone(), e2.clone())),
+            (Large(e1), Large(e2)) | (Strict(e1), Large(e2)) | (Strict(e1), Strict(e2))
+                => Ok(ParameterConstraint::le(e1.clone(), e2.clone())),
+        }
+    }
+
+}
+
+impl Add for ParametricBound {
+    type Output = ParametricBound;
+    fn add(self, rhs : ParametricBound) -> ParametricBound {
+        match (self, rhs) {
+            (Infinite, _) | (_, Infinite) => Infinite,
+            (Large(e1), Large(e2)) => Large(e1 + e2),
+            (Large(e1), Strict(e2)) | (Strict(e1), Large(e2)) | (Strict(e1), Strict(e2)) => Strict(e1 + e2),
+        }
+    }
+}
+
+impl fmt::Display for ParametricBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strict(e) => write!(f, "<{}", e),
+            Large(e) => write!(f, "<={}", e),
+            Infinite => write!(f, "<inf"),
+        }
+    }
+}
+
+/// Parametric DBM : difference constraints whose bounds depend on parameters, valid on a parameter domain.
+/// Comparing bounds may depend on the parameters values, so operations split the domain and return one PDBM for
+/// each part, every one of them being non empty for some valuation of its domain.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PDBM {
+    constraints : DMatrix<ParametricBound>,
+    pub parameters : ParameterConstraints,
+}
+
+impl PDBM {
+
+    pub fn new(vars : usize, parameters : usize) -> Self {
+        PDBM {
+            constraints : DMatrix::from_fn(vars + 1, vars + 1, |i, j| {
+                if i == j { ParametricBound::zero() } else { Infinite }
+            }),
+            parameters : ParameterConstraints::new(parameters)
+        }
+    }
+
+    // Empty zones are given a negative cycle on the reference variable
+    pub fn from_dbm(dbm : &DBM, parameters : usize) -> Self {
+        let n = dbm.vars_count() + 1;
+        let mut pdbm = PDBM {
+            constraints : DMatrix::from_fn(n, n, |i, j| match dbm[(i, j)] {
+                TimeBound::Strict(x) => Strict(x.into()),
+                TimeBound::Large(x) => Large(x.into()),
+                _ => Infinite
+            }),
+            parameters : ParameterConstraints::new(parameters)
+        };
+        if dbm.is_empty() {
+            pdbm[(0, 0)] = Strict(LinearExpr::constant(0));
+        }
+        pdbm
+    }
+
+    pub fn vars_count(&self) -> usize {
+        self.constraints.nrows() - 1
+    }
+
+    pub fn parameters_count(&self) -> usize {
+        self.parameters.parameters
+    }
+
+    // Parts of the parameter domain where the first bound is the tightest, and where the second one is
+    fn split(&self, b1 : &ParametricBound, b2 : &ParametricBound) -> (Option<ParameterConstraints>, Option<ParameterConstraints>) {
+        match b1.tighter_than(b2) {
+            Err(true) => (Some(self.parameters.clone()), None),
+            Err(false) => (None, Some(self.parameters.clone())),
+            Ok(c) => (self.parameters.refine(c.clone()), self.parameters.refine(c.negation()))
+        }
+    }
+
+    // x_i - x_j bounded by the given bound, in canonical form
+    pub fn with_constraint(&self, i : usize, j : usize, bound : ParametricBound) -> Vec<PDBM> {
+        let (keep, replace) = self.split(&self[(i, j)], &bound);
+        let mut res = Vec::new();
+        if let Some(parameters) = keep {
+            res.push(PDBM { constraints : self.constraints.clone(), parameters });
+        }
+        if let Some(parameters) = replace {
+            let mut pdbm = PDBM { constraints : self.constraints.clone(), parameters };
+            pdbm[(i, j)] = bound;
+            res.push(pdbm);
+        }
+        res.iter().flat_map(PDBM::canonical).collect()
+    }
+
+    pub fn intersection(&self, other : &PDBM) -> Vec<PDBM> {
+        let mut res = self.clone();
+        for c in other.parameters.constraints.iter() {
+            res.parameters.add(c.clone());
+        }
+        if !res.parameters.is_satisfiable() {
+            return Vec::new();
+        }
+        let n = self.constraints.nrows();
+        let mut parts = vec![res];
+        for i in 0..n {
+            for j in 0..n {
+                parts = parts.into_iter().flat_map(|p| {
+                    let (keep, replace) = p.split(&p[(i, j)], &other[(i, j)]);
+                    let mut split = Vec::new();
+                    if let Some(parameters) = keep {
+                        split.push(PDBM { constraints : p.constraints.clone(), parameters });
+                    }
+                    if let Some(parameters) = replace {
+                        let mut q = PDBM { constraints : p.constraints.clone(), parameters };
+                        q[(i, j)] = other[(i, j)].clone();
+                        split.push(q);
+                    }
+                    split
+                }).collect();
+            }
+        }
+        parts.iter().flat_map(PDBM::canonical).collect()
+    }
+
+    // Floyd-Warshall closure, a branch being explored for each undecided comparison.
+    // Branches with a negative cycle are dropped, the others having their domain restricted to the non empty part.
+    pub fn canonical(&self) -> Vec<PDBM> {
+        let n = self.constraints.nrows();
+        let mut res = Vec::new();
+        let mut pending = vec![(self.clone(), 0)];
+        while let Some((mut pdbm, mut step)) = pending.pop() {
+            let mut empty = false;
+            while step < n * n * n && !empty {
+                let (k, i, j) = (step / (n * n), (step / n) % n, step % n);
+                step += 1;
+                let candidate = pdbm[(i, k)].clone() + pdbm[(k, j)].clone();
+                match pdbm.split(&pdbm[(i, j)], &candidate) {
+                    (Some(parameters), None) => pdbm.parameters = parameters,
+                    (None, Some(parameters)) => {
+                        pdbm.parameters = parameters;
+                        pdbm[(i, j)] = candidate;
+                    },
+                    (Some(keep), Some(replace)) => {
+                        let mut branch = PDBM { constraints : pdbm.constraints.clone(), parameters : replace };
+                        branch[(i, j)] = candidate;
+                        pending.push((branch, step));
+                        pdbm.parameters = keep;
+                    },
+                    (None, None) => empty = true // Unsatisfiable domain
+                }
+            }
+            for i in 0..n {
+                if empty {
+                    break;
+                }
+                match pdbm.split(&ParametricBound::zero(), &pdbm[(i, i)]) {
+                    (Some(parameters), _) => pdbm.parameters = parameters,
+                    (None, _) => empty = true
+                }
+            }
+            if !empty {
+                res.push(pdbm);
+            }
+        }
+        res
+    }
+
+    pub fn remove_var(&mut self, var_i : usize) {
+        self.constraints = self.constraints.clone().remove_column(var_i).remove_row(var_i);
+    }
+
+    // Concrete zone for the given parameter values, None if they are out of the domain
+    pub fn instantiate(&self, values : &[i32]) -> Option<DBM> {
+        if !self.parameters.is_satisfied(values) {
+            return None;
+        }
+        Some(DBM::from(self.constraints.map(|b| b.instantiate(values))))
+    }
+
+}
+
+impl Index<(usize, usize)> for PDBM {
+    type Output = ParametricBound;
+    fn index(&self, index: (usize, usize)) -> &Self::Output {
+        &self.constraints[index]
+    }
+}
+
+impl IndexMut<(usize, usize)> for PDBM {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+        &mut self.constraints[index]
+    }
+}
+
+impl fmt::Display for PDBM {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PDBM{}Parameters : {}", self.constraints, self.parameters)
+    }
+}
@@ -0,0 +1,158 @@
+use rand::{rngs::SmallRng, Error, Rng, RngCore, SeedableRng};
+
+/// Transformation of the draws of a simulation generator, to correlate runs and reduce the variance of estimates.
+/// Draws keep their uniform distribution, so that each run taken alone is left unbiased.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrawTransform {
+    #[default]
+    Identity,
+    // Every draw u is mirrored to 1 - u
+    Antithetic,
+    // The next draw is confined to the j-th of k equal strata, the following ones being left untouched
+    Stratum(u64, u64),
+}
+
+impl DrawTransform {
+
+    fn apply_u64(&self, x : u64) -> u64 {
+        match self {
+            DrawTransform::Identity => x,
+            DrawTransform::Antithetic => !x,
+            DrawTransform::Stratum(j, k) => ((((*j as u128) << 64) | x as u128) / (*k as u128)) as u64,
+        }
+    }
+
+    fn apply_u32(&self, x : u32) -> u32 {
+        match self {
+            DrawTransform::Identity => x,
+            DrawTransform::Antithetic => !x,
+            DrawTransform::Stratum(j, k) => ((((*j) << 32) | x as u64) / *k) as u32,
+        }
+    }
+
+}
+
+/// Random generator of a simulation, handed to the models sampling their runs (see Model::random_next) instead of
+/// thread_rng, so that the same seed always gives the same simulation. Draws are counted : a simulation which did not
+/// change the count was deterministic.
+#[derive(Debug, Clone)]
+pub struct SimulationRng {
+    generator : SmallRng,
+    transform : DrawTransform,
+    draws : u64,
+}
+
+impl SimulationRng {
+
+    pub fn seeded(seed : u64) -> Self {
+        SimulationRng { generator : SmallRng::seed_from_u64(seed), transform : DrawTransform::Identity, draws : 0 }
+    }
+
+    pub fn from_entropy() -> Self {
+        SimulationRng { generator : SmallRng::from_entropy(), transform : DrawTransform::Identity, draws : 0 }
+    }
+
+    // Transform applied to the following draws
+    pub fn with_transform(mut self, transform : DrawTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+
+    // Transform of the next draw, strata only applying once
+    fn take_transform(&mut self) -> DrawTransform {
+        self.draws = self.draws.wrapping_add(1);
+        let transform = self.transform;
+        if let DrawTransform::Stratum(_, _) = transform {
+            self.transform = DrawTransform::Identity;
+        }
+        transform
+    }
+
+}
+
+impl RngCore for SimulationRng {
+
+    fn next_u32(&mut self) -> u32 {
+        let transform = self.take_transform();
+        transform.apply_u32(self.generator.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let transform = self.take_transform();
+        transform.apply_u64(self.generator.next_u64())
+    }
+
+    // Bytes are only mirrored, strata being meant for numeric draws
+    fn fill_bytes(&mut self, dest : &mut [u8]) {
+        let transform = self.take_transform();
+        self.generator.fill_bytes(dest);
+        if transform == DrawTransform::Antithetic {
+            dest.iter_mut().for_each(|b| *b = !*b);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest : &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+
+}
+
+// Uniform choice drawn from a single float, so that mirrored or stratified draws give mirrored or stratified choices
+// (integer sampling rejects some draws, which would shift the draws of correlated runs). Single items take no draw
+pub fn choose_uniform<'a, T>(items : &'a [T], rng : &mut SimulationRng) -> Option<&'a T> {
+    match items.len() {
+        0 => None,
+        1 => items.first(),
+        n => {
+            let u : f64 = rng.gen();
+            items.get(((u * n as f64) as usize).min(n - 1))
+        }
+    }
+}
+
+/// Seeds of the runs of a verification. With a simulation seed, the k-th run always gets the same seed, whatever
+/// the thread or the process executing it. Runs draw their seed from entropy otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RunSeeds {
+    seed : Option<u64>,
+    runs : u64,
+}
+
+impl RunSeeds {
+
+    pub fn new(seed : Option<u64>) -> Self {
+        RunSeeds { seed, runs : 0 }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    // Seed of the run of the given index
+    pub fn run_seed(&self, run : u64) -> u64 {
+        match self.seed {
+            Some(seed) => split_mix(seed ^ split_mix(run)),
+            None => rand::random()
+        }
+    }
+
+    pub fn next_seed(&mut self) -> u64 {
+        let seed = self.run_seed(self.runs);
+        self.runs += 1;
+        seed
+    }
+
+}
+
+// SplitMix64 finalizer, spreading close seeds over unrelated streams
+fn split_mix(x : u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
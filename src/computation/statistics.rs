@@ -0,0 +1,170 @@
+// Quantile function of the standard normal distribution (Acklam's rational approximation, relative error < 1.15e-9)
+pub fn normal_quantile(p : f64) -> f64 {
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+    const A : [f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02, 1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+    const B : [f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02, 6.680131188771972e+01, -1.328068155288572e+01];
+    const C : [f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00, -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+    const D : [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00, 3.754408661907416e+00];
+    const P_LOW : f64 = 0.02425;
+    if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0]*q + C[1])*q + C[2])*q + C[3])*q + C[4])*q + C[5]) /
+            ((((D[0]*q + D[1])*q + D[2])*q + D[3])*q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0]*r + A[1])*r + A[2])*r + A[3])*r + A[4])*r + A[5])*q /
+            (((((B[0]*r + B[1])*r + B[2])*r + B[3])*r + B[4])*r + 1.0)
+    } else {
+        -normal_quantile(1.0 - p)
+    }
+}
+
+// Mean and unbiased variance of a sample
+pub fn mean_variance(values : &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    if values.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+// Standard error of the mean of a sample
+pub fn standard_error(variance : f64, n : usize) -> f64 {
+    (variance / n as f64).sqrt()
+}
+
+// Half width of the normal confidence interval of the mean of a sample
+pub fn mean_half_width(variance : f64, n : usize, confidence : f64) -> f64 {
+    let z = normal_quantile(1.0 - (1.0 - confidence) / 2.0);
+    z * standard_error(variance, n)
+}
+
+// Logarithm of the gamma function (Lanczos approximation, g = 7), for positive arguments
+pub fn ln_gamma(x : f64) -> f64 {
+    const G : [f64; 9] = [0.9999999999998099, 676.5203681218851, -1259.1392167224028, 771.3234287776531, -176.6150291621406,
+        12.507343278686905, -0.13857109526572012, 9.984369578019572e-06, 1.5056327351493116e-07];
+    if x < 0.5 {
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = G[0] + G.iter().enumerate().skip(1).map(|(i, g)| g / (x + i as f64)).sum::<f64>();
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// Regularized incomplete beta function I_x(a, b), by its continued fraction (modified Lentz's method)
+pub fn incomplete_beta(x : f64, a : f64, b : f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    // The fraction converges quickly for x < (a+1)/(a+b+2) only, the symmetry I_x(a,b) = 1 - I_1-x(b,a) is used otherwise
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - incomplete_beta(1.0 - x, b, a);
+    }
+    const TINY : f64 = 1e-300;
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+    let mut f = d;
+    for m in 1..300 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0))
+        ] {
+            d = 1.0 + numerator * d;
+            d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            f *= c * d;
+        }
+        if (c * d - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    front * f
+}
+
+// Quantile of the beta distribution, by bisection on the incomplete beta function
+pub fn beta_quantile(p : f64, a : f64, b : f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if incomplete_beta(mid, a, b) < p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// Exact binomial interval of a proportion of successes, never narrower than the confidence requires
+pub fn clopper_pearson_interval(successes : usize, n : usize, confidence : f64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let alpha = 1.0 - confidence;
+    let (k, n) = (successes as f64, n as f64);
+    let low = if successes == 0 { 0.0 } else { beta_quantile(alpha / 2.0, k, n - k + 1.0) };
+    let high = if k == n { 1.0 } else { beta_quantile(1.0 - alpha / 2.0, k + 1.0, n - k) };
+    (low, high)
+}
+
+// Wilson score interval of a proportion of successes, which stays meaningful for proportions close to 0 or 1
+pub fn wilson_interval(successes : usize, n : usize, confidence : f64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let z = normal_quantile(1.0 - (1.0 - confidence) / 2.0);
+    let (p, n) = (successes as f64 / n as f64, n as f64);
+    let denominator = 1.0 + z * z / n;
+    let center = (p + z * z / (2.0 * n)) / denominator;
+    let half_width = z / denominator * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt();
+    ((center - half_width).max(0.0), (center + half_width).min(1.0))
+}
+
+// Lag-1 autocorrelation of a series, close to zero for independent values
+pub fn lag1_autocorrelation(values : &[f64]) -> f64 {
+    let (mean, _) = mean_variance(values);
+    let covariance : f64 = values.windows(2).map(|w| (w[0] - mean) * (w[1] - mean)).sum();
+    let variance : f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    if variance == 0.0 { 0.0 } else { covariance / variance }
+}
+
+// Warm-up of a series by the MSER rule : the number of leading values to drop minimizing the standard error of the
+// mean of the remaining ones, searched over the first half of the series
+pub fn mser_truncation(values : &[f64]) -> usize {
+    let n = values.len();
+    let (mut sum, mut squares) = (0.0, 0.0);
+    let mut best = (0, f64::INFINITY);
+    for d in (0..n).rev() {
+        sum += values[d];
+        squares += values[d] * values[d];
+        let m = (n - d) as f64;
+        if d <= n / 2 {
+            let statistic = (squares - sum * sum / m).max(0.0) / (m * m);
+            if statistic <= best.1 {
+                best = (d, statistic);
+            }
+        }
+    }
+    best.0
+}
@@ -31,7 +31,7 @@ impl VirtualMemory {
         let value : T;
         unsafe {
             let var_ptr = storage.add(address) as *const T;
-            value = *var_ptr;
+            value = var_ptr.read_unaligned();
         }
         value
     }
@@ -44,7 +44,7 @@ impl VirtualMemory {
         let storage = self.storage.as_mut_ptr();
         unsafe {
             let var_ptr = storage.add(address) as *mut T;
-            *var_ptr = value;
+            var_ptr.write_unaligned(value);
         }
     }
 
@@ -88,6 +88,14 @@ impl VirtualMemory {
         self.storage.is_empty()
     }
 
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.storage
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.storage
+    }
+
     pub fn define(&mut self, var : &mut ModelVar, var_type : VarType) {
         if var.is_mapped() {
             panic!("Can't redefine already mapped var !");
@@ -151,6 +159,11 @@ impl VariableDefiner {
         self.size += var.size();
     }
 
+    pub fn define_array(&mut self, var : &mut ModelVar, var_type : VarType, length : usize) {
+        var.set_length(length);
+        self.define(var, var_type);
+    }
+
     pub fn size(&self) -> usize {
         self.size
     }
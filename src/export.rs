@@ -0,0 +1,40 @@
+mod batch;
+mod markov_csv;
+mod mermaid;
+mod model_file;
+mod model_documentation;
+mod run_bundle;
+#[cfg(feature = "parquet")]
+mod run_table;
+
+use std::fmt::Display;
+
+pub use batch::{convert_directory, BatchReport, ModelFormat};
+pub use markov_csv::{parse_markov_csv, read_markov_csv};
+pub use mermaid::{MermaidDiagram, MermaidExport, MermaidShape, MermaidWriter};
+pub use model_file::{write_file, read_file, load_file};
+pub use model_documentation::{DocumentFormat, ModelDocumentation};
+pub use run_bundle::{write_bundle, write_bundle_csv, bundle_schema};
+#[cfg(feature = "parquet")]
+pub use run_table::{write_runs_parquet, write_bundle_parquet};
+
+#[derive(Debug, Clone)]
+pub struct ExportError(pub String);
+pub type ExportResult = Result<(), ExportError>;
+impl Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Export error : {}", self.0)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(value: std::io::Error) -> Self {
+        ExportError(value.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(value: serde_json::Error) -> Self {
+        ExportError(value.to_string())
+    }
+}
@@ -0,0 +1,162 @@
+use std::{fmt, fs, path::{Path, PathBuf}};
+
+use crate::models::{markov::markov_chain::MarkovChain, model_context::ModelContext, model_solving_graph::ModelSolvingGraph, petri::{PetriNet, PetriStructure}, Model};
+
+use super::{read_markov_csv, ExportError, ExportResult, MermaidExport};
+
+use crate::log::*;
+
+type Reader<M> = fn(&Path) -> Result<M, ExportError>;
+type Writer<M> = fn(&M, &Path) -> ExportResult;
+
+/// File format of a model type, identified by its extension. Formats may only be read or only be written.
+/// Formats are registered on the ModelSolvingGraph, where conversions look them up.
+pub struct ModelFormat<M> {
+    pub name : &'static str,
+    pub extension : &'static str,
+    pub read : Option<Reader<M>>,
+    pub write : Option<Writer<M>>,
+}
+
+impl<M> ModelFormat<M> {
+
+    pub fn new(name : &'static str, extension : &'static str) -> Self {
+        ModelFormat { name, extension, read : None, write : None }
+    }
+
+    pub fn with_reader(mut self, read : Reader<M>) -> Self {
+        self.read = Some(read);
+        self
+    }
+
+    pub fn with_writer(mut self, write : Writer<M>) -> Self {
+        self.write = Some(write);
+        self
+    }
+
+}
+
+impl ModelFormat<PetriNet> {
+
+    // Petri structure as JSON, the format of the benchmark models
+    pub fn petri_json() -> Self {
+        Self::new("Petri JSON", "json")
+            .with_reader(|path| {
+                let structure : PetriStructure = serde_json::from_str(&fs::read_to_string(path)?)?;
+                Ok(PetriNet::from(structure))
+            })
+            .with_writer(|net, path| {
+                fs::write(path, serde_json::to_string_pretty(&net.get_structure())?)?;
+                Ok(())
+            })
+    }
+
+    pub fn petri_mermaid() -> Self {
+        Self::new("Mermaid", "mmd").with_writer(|net, path| Ok(fs::write(path, net.mermaid())?))
+    }
+
+}
+
+impl ModelFormat<MarkovChain> {
+
+    pub fn markov_json() -> Self {
+        Self::new("Markov JSON", "json")
+            .with_reader(|path| Ok(serde_json::from_str(&fs::read_to_string(path)?)?))
+            .with_writer(|chain, path| {
+                fs::write(path, serde_json::to_string_pretty(chain)?)?;
+                Ok(())
+            })
+    }
+
+    pub fn markov_csv() -> Self {
+        Self::new("Markov CSV", "csv").with_reader(|path| read_markov_csv(path))
+    }
+
+    pub fn markov_mermaid() -> Self {
+        Self::new("Mermaid", "mmd").with_writer(|chain, path| Ok(fs::write(path, chain.mermaid())?))
+    }
+
+}
+
+/// Outcome of a batch conversion : files converted, and files that failed along with the reason
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub converted : Vec<PathBuf>,
+    pub failures : Vec<(PathBuf, ExportError)>,
+}
+
+impl BatchReport {
+
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn log(&self) {
+        if self.is_success() {
+            positive(format!("{} models converted", self.converted.len()));
+            return;
+        }
+        warning(format!("{} models converted, {} failed", self.converted.len(), self.failures.len()));
+        for (path, e) in self.failures.iter() {
+            continue_info(format!("{} : {}", path.display(), e));
+        }
+    }
+
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} converted, {} failed", self.converted.len(), self.failures.len())
+    }
+}
+
+// Read models are compiled in a fresh context before being written, so that invalid models are not converted.
+// A file is never written over itself, which happens when converting in place to the same extension
+fn convert_file<M : Model + Clone>(path : &Path, output : &Path, from : &ModelFormat<M>, to : &ModelFormat<M>) -> Result<PathBuf, ExportError> {
+    let (Some(read), Some(write)) = (from.read, to.write) else {
+        return Err(ExportError(format!("Unable to convert from {} to {}", from.name, to.name)));
+    };
+    let stem = path.file_stem().unwrap_or_default();
+    let target = output.join(stem).with_extension(to.extension);
+    if fs::canonicalize(output)?.join(target.file_name().unwrap_or_default()) == fs::canonicalize(path)? {
+        return Err(ExportError(String::from("Conversion would overwrite the source file")));
+    }
+    let model = read(path)?;
+    let mut ctx = ModelContext::new();
+    if model.clone().compile(&mut ctx).is_err() {
+        return Err(ExportError(String::from("Model does not compile")));
+    }
+    write(&model, &target)?;
+    Ok(target)
+}
+
+/// Converts every file of the input directory having the source extension, writing the converted models to the output
+/// directory under the same names. Formats are those registered on the solver for the model type.
+/// Failing files are reported without stopping the batch.
+pub fn convert_directory<M : Model + Clone + 'static>(solver : &ModelSolvingGraph, input : impl AsRef<Path>, output : impl AsRef<Path>, from : &str, to : &str) -> Result<BatchReport, ExportError> {
+    let model_name = M::get_meta().name;
+    let Some(from) = solver.reader::<M>(from) else {
+        return Err(ExportError(format!("No format reading {} models from .{} files", model_name, from)));
+    };
+    let Some(to) = solver.writer::<M>(to) else {
+        return Err(ExportError(format!("No format writing {} models to .{} files", model_name, to)));
+    };
+    let input = input.as_ref();
+    let output = output.as_ref();
+    info(format!("Converting {} models from {} to {}", from.name, input.display(), to.name));
+    fs::create_dir_all(output)?;
+    let mut paths : Vec<PathBuf> = fs::read_dir(input)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == from.extension))
+        .collect();
+    paths.sort();
+    let mut report = BatchReport::default();
+    for path in paths {
+        match convert_file(&path, output, from, to) {
+            Ok(target) => report.converted.push(target),
+            Err(e) => report.failures.push((path, e))
+        }
+    }
+    report.log();
+    Ok(report)
+}
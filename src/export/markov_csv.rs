@@ -0,0 +1,71 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::models::{action::Action, lbl, markov::{markov_chain::MarkovChain, markov_node::MarkovNode}, Label};
+
+use super::ExportError;
+
+type Outputs = HashMap<Label, Vec<(Label, f64)>>;
+
+fn line_error(line : usize, message : &str) -> ExportError {
+    ExportError(format!("Line {} : {}", line, message))
+}
+
+/// Builds a Markov chain from an edge list, one `from,to,prob[,action]` edge per line.
+/// Fields are separated by tabs if the line contains any, by commas otherwise. Empty lines and lines starting with '#'
+/// are ignored, as well as a header line whose probability can't be read. Nodes are created in order of appearance,
+/// probabilities are normalized per action, and nodes with several actions become decision nodes.
+pub fn parse_markov_csv(text : &str) -> Result<MarkovChain, ExportError> {
+    let epsilon = Label::from(Action::Epsilon.to_string());
+    let mut order : Vec<Label> = Vec::new();
+    let mut outputs : HashMap<Label, Outputs> = HashMap::new();
+    let mut first = true;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let separator = if line.contains('\t') { '\t' } else { ',' };
+        let fields : Vec<&str> = line.split(separator).map(str::trim).collect();
+        if fields.len() < 3 || fields.len() > 4 {
+            return Err(line_error(i + 1, "expected from,to,prob[,action]"));
+        }
+        let prob = match fields[2].parse::<f64>() {
+            Ok(p) => p,
+            Err(_) if first => {
+                first = false;
+                continue;
+            },
+            Err(_) => return Err(line_error(i + 1, "invalid probability")),
+        };
+        first = false;
+        if !prob.is_finite() || prob < 0.0 {
+            return Err(line_error(i + 1, "probabilities must be non-negative"));
+        }
+        let (from, to) = (lbl(fields[0]), lbl(fields[1]));
+        let action = match fields.get(3) {
+            Some(a) if !a.is_empty() => lbl(a),
+            _ => epsilon.clone()
+        };
+        for node in [&from, &to] {
+            if !outputs.contains_key(node) {
+                order.push(node.clone());
+                outputs.insert(node.clone(), HashMap::new());
+            }
+        }
+        outputs.get_mut(&from).unwrap().entry(action).or_default().push((to, prob));
+    }
+    for (label, node_outputs) in outputs.iter() {
+        if node_outputs.values().any(|c| c.iter().all(|(_, p)| *p == 0.0)) {
+            return Err(ExportError(format!("Null probability distribution on node {}", label)));
+        }
+    }
+    let nodes = order.into_iter().map(|label| {
+        let node_outputs = outputs.remove(&label).unwrap();
+        MarkovNode::choice(label, node_outputs)
+    }).collect();
+    Ok(MarkovChain::new(nodes))
+}
+
+pub fn read_markov_csv(path : impl AsRef<Path>) -> Result<MarkovChain, ExportError> {
+    parse_markov_csv(&fs::read_to_string(path)?)
+}
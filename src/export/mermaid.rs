@@ -0,0 +1,324 @@
+use std::{collections::HashMap, fmt::{self, Display}};
+
+use crate::models::{class_graph::ClassGraph, digraph::Digraph, markov::markov_chain::MarkovChain, petri::PetriNet, ClassId, Label, Node, NodeMetadata};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MermaidDiagram {
+    StateDiagram,
+    Flowchart
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MermaidShape {
+    Rectangle,
+    Rounded,
+    Circle,
+    Rhombus
+}
+
+/// Builds mermaid diagrams text, to be embedded in markdown reports or rendered in browsers
+#[derive(Debug, Clone)]
+pub struct MermaidWriter {
+    pub diagram : MermaidDiagram,
+    pub title : Option<String>,
+    lines : Vec<String>,
+}
+
+impl MermaidWriter {
+
+    pub fn new(diagram : MermaidDiagram) -> Self {
+        MermaidWriter {
+            diagram,
+            title : None,
+            lines : Vec::new(),
+        }
+    }
+
+    pub fn state_diagram() -> Self {
+        Self::new(MermaidDiagram::StateDiagram)
+    }
+
+    pub fn flowchart() -> Self {
+        Self::new(MermaidDiagram::Flowchart)
+    }
+
+    pub fn with_title(mut self, title : impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    // Mermaid identifiers must only contain alphanumeric chars and underscores
+    pub fn identifier(id : &Label) -> String {
+        id.to_string().chars().map(|c| {
+            if c.is_ascii_alphanumeric() { c } else { '_' }
+        }).collect()
+    }
+
+    fn escape(text : &str) -> String {
+        text.replace('"', "#quot;")
+    }
+
+    pub fn node(&mut self, id : &Label, text : &str, shape : MermaidShape) {
+        let id = Self::identifier(id);
+        let text = Self::escape(text);
+        let line = match self.diagram {
+            MermaidDiagram::StateDiagram => format!("{} : {}", id, text),
+            MermaidDiagram::Flowchart => match shape {
+                MermaidShape::Rectangle => format!("{}[\"{}\"]", id, text),
+                MermaidShape::Rounded => format!("{}(\"{}\")", id, text),
+                MermaidShape::Circle => format!("{}((\"{}\"))", id, text),
+                MermaidShape::Rhombus => format!("{}{{\"{}\"}}", id, text),
+            }
+        };
+        self.lines.push(line);
+    }
+
+    pub fn edge(&mut self, from : &Label, to : &Label, text : Option<&str>) {
+        let from = Self::identifier(from);
+        let to = Self::identifier(to);
+        let line = match (self.diagram, text) {
+            (MermaidDiagram::StateDiagram, None) => format!("{} --> {}", from, to),
+            (MermaidDiagram::StateDiagram, Some(t)) => format!("{} --> {} : {}", from, to, Self::escape(t)),
+            (MermaidDiagram::Flowchart, None) => format!("{} --> {}", from, to),
+            (MermaidDiagram::Flowchart, Some(t)) => format!("{} -->|\"{}\"| {}", from, Self::escape(t), to),
+        };
+        self.lines.push(line);
+    }
+
+    // Edge ending with a circle, as inhibitor arcs are drawn. State diagrams have no such edge, it is labelled instead
+    pub fn inhibitor_edge(&mut self, from : &Label, to : &Label, weight : i32) {
+        let from = Self::identifier(from);
+        let to = Self::identifier(to);
+        let line = match self.diagram {
+            MermaidDiagram::StateDiagram => format!("{} --> {} : inhibitor {}", from, to, weight),
+            MermaidDiagram::Flowchart if weight == 1 => format!("{} --o {}", from, to),
+            MermaidDiagram::Flowchart => format!("{} --o|\"{}\"| {}", from, weight, to),
+        };
+        self.lines.push(line);
+    }
+
+    // Edge without arrow, as read arcs are drawn
+    pub fn read_edge(&mut self, from : &Label, to : &Label, weight : i32) {
+        let from = Self::identifier(from);
+        let to = Self::identifier(to);
+        let line = match self.diagram {
+            MermaidDiagram::StateDiagram => format!("{} --> {} : read {}", from, to, weight),
+            MermaidDiagram::Flowchart if weight == 1 => format!("{} --- {}", from, to),
+            MermaidDiagram::Flowchart => format!("{} ---|\"{}\"| {}", from, weight, to),
+        };
+        self.lines.push(line);
+    }
+
+    // Edge ending with a cross, as reset arcs are drawn
+    pub fn reset_edge(&mut self, from : &Label, to : &Label) {
+        let from = Self::identifier(from);
+        let to = Self::identifier(to);
+        let line = match self.diagram {
+            MermaidDiagram::StateDiagram => format!("{} --> {} : reset", from, to),
+            MermaidDiagram::Flowchart => format!("{} --x {}", from, to),
+        };
+        self.lines.push(line);
+    }
+
+    pub fn initial(&mut self, id : &Label) {
+        let id = Self::identifier(id);
+        match self.diagram {
+            MermaidDiagram::StateDiagram => self.lines.push(format!("[*] --> {}", id)),
+            MermaidDiagram::Flowchart => self.lines.push(format!("style {} stroke-width:3px", id)),
+        }
+    }
+
+    pub fn comment(&mut self, text : &str) {
+        self.lines.push(format!("%% {}", text.replace('\n', " ")));
+    }
+
+    // Node text with its unit, the rest of the metadata being kept as a comment since mermaid has no layout hints
+    pub fn documented_node(&mut self, id : &Label, text : &str, shape : MermaidShape, metadata : Option<&NodeMetadata>) {
+        let Some(metadata) = metadata.filter(|m| !m.is_empty()) else {
+            return self.node(id, text, shape);
+        };
+        match &metadata.unit {
+            Some(unit) => self.node(id, &format!("{} [{}]", text, unit), shape),
+            None => self.node(id, text, shape)
+        }
+        self.comment(&format!("{} : {}", Self::identifier(id), metadata));
+    }
+
+    pub fn write(mut self, model : &impl MermaidExport) -> String {
+        model.to_mermaid(&mut self);
+        self.to_string()
+    }
+
+    pub fn markdown(&self) -> String {
+        format!("```mermaid\n{}```\n", self)
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+}
+
+impl Display for MermaidWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(title) = &self.title {
+            write!(f, "---\ntitle: {}\n---\n", title)?;
+        }
+        match self.diagram {
+            MermaidDiagram::StateDiagram => writeln!(f, "stateDiagram-v2")?,
+            MermaidDiagram::Flowchart => writeln!(f, "flowchart LR")?,
+        };
+        for line in self.lines.iter() {
+            writeln!(f, "    {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Models that can be drawn as a mermaid diagram
+pub trait MermaidExport {
+
+    fn to_mermaid(&self, writer : &mut MermaidWriter);
+
+    fn mermaid_diagram(&self) -> MermaidDiagram {
+        MermaidDiagram::StateDiagram
+    }
+
+    fn mermaid(&self) -> String where Self : Sized {
+        MermaidWriter::new(self.mermaid_diagram()).write(self)
+    }
+
+}
+
+impl MermaidExport for PetriNet {
+
+    fn mermaid_diagram(&self) -> MermaidDiagram {
+        MermaidDiagram::Flowchart
+    }
+
+    fn to_mermaid(&self, writer : &mut MermaidWriter) {
+        let place_id = |l : &Label| lbl_prefix("place_", l);
+        let transition_id = |l : &Label| lbl_prefix("transition_", l);
+        for place in self.places.iter() {
+            let label = place.get_label();
+            let text = match place.capacity {
+                Some(capacity) => format!("{} (max {})", label, capacity),
+                None => label.to_string()
+            };
+            writer.documented_node(&place_id(&label), &text, MermaidShape::Circle, place.get_metadata());
+        }
+        for transition in self.transitions.iter() {
+            let label = transition.get_label();
+            let text = format!("{} {}", label, transition.interval);
+            writer.documented_node(&transition_id(&label), &text, MermaidShape::Rectangle, transition.get_metadata());
+        }
+        for transition in self.transitions.iter() {
+            let t_id = transition_id(&transition.get_label());
+            for place in transition.from.iter() {
+                writer.edge(&place_id(place), &t_id, None);
+            }
+            for place in transition.to.iter() {
+                writer.edge(&t_id, &place_id(place), None);
+            }
+            for (place, weight) in transition.inhibitors.iter() {
+                writer.inhibitor_edge(&place_id(place), &t_id, *weight);
+            }
+            for (place, weight) in transition.reads.iter() {
+                writer.read_edge(&place_id(place), &t_id, *weight);
+            }
+            for place in transition.flushes.iter() {
+                writer.reset_edge(&t_id, &place_id(place));
+            }
+            for (place, weight) in transition.input_weights.iter() {
+                writer.edge(&place_id(place), &t_id, Some(&weight.to_string()));
+            }
+            for (place, weight) in transition.output_weights.iter() {
+                writer.edge(&t_id, &place_id(place), Some(&weight.to_string()));
+            }
+        }
+    }
+
+}
+
+impl MermaidExport for ClassGraph {
+
+    fn to_mermaid(&self, writer : &mut MermaidWriter) {
+        let actions : HashMap<_,_> = self.transitions.iter().map(|t| {
+            (t.get_action(), t.get_label())
+        }).collect();
+        let class_id = |i : ClassId| Label::from(format!("class_{}", i.index()));
+        if !self.classes.is_empty() {
+            writer.initial(&class_id(self.classes[0].index));
+        }
+        for class in self.classes.iter() {
+            let enabled : Vec<String> = class.from_dbm_index.iter().skip(1).map(|t| {
+                self.transitions[t.index()].get_label().to_string()
+            }).collect();
+            let text = format!("Class {} [{}]", class.index.index(), enabled.join(","));
+            writer.node(&class_id(class.index), &text, MermaidShape::Rounded);
+        }
+        for class in self.classes.iter() {
+            for (pred, action) in class.predecessors.read().unwrap().iter() {
+                let Some(pred) = pred.upgrade() else {
+                    continue;
+                };
+                let text = match actions.get(action) {
+                    Some(l) => l.to_string(),
+                    None => action.to_string()
+                };
+                writer.edge(&class_id(pred.index), &class_id(class.index), Some(&text));
+            }
+        }
+    }
+
+}
+
+impl MermaidExport for MarkovChain {
+
+    fn to_mermaid(&self, writer : &mut MermaidWriter) {
+        for node in self.nodes.iter() {
+            let label = node.get_label();
+            writer.documented_node(&label, &label.to_string(), MermaidShape::Rounded, node.get_metadata());
+        }
+        for node in self.nodes.iter() {
+            let choice = node.is_choice();
+            for (action, outputs) in node.outputs.iter() {
+                for (target, proba) in outputs.iter() {
+                    let text = if choice {
+                        format!("{} : {}", action, proba)
+                    } else {
+                        proba.to_string()
+                    };
+                    writer.edge(&node.get_label(), target, Some(&text));
+                }
+            }
+        }
+    }
+
+}
+
+impl<T : ToString, U : Display> MermaidExport for Digraph<T, U> {
+
+    fn mermaid_diagram(&self) -> MermaidDiagram {
+        MermaidDiagram::Flowchart
+    }
+
+    fn to_mermaid(&self, writer : &mut MermaidWriter) {
+        let node_id = |i : usize| Label::from(format!("node_{}", i));
+        for node in self.nodes.iter() {
+            writer.node(&node_id(node.index), &node.get_label().to_string(), MermaidShape::Rounded);
+        }
+        for edge in self.edges.iter() {
+            if !edge.has_source() || !edge.has_target() {
+                continue;
+            }
+            let text = edge.weight.to_string();
+            writer.edge(&node_id(edge.get_node_from().index), &node_id(edge.get_node_to().index), Some(&text));
+        }
+    }
+
+}
+
+fn lbl_prefix(prefix : &str, label : &Label) -> Label {
+    Label::from(prefix) + label
+}
@@ -0,0 +1,275 @@
+use std::{fs, path::Path};
+
+use crate::{models::{model_characteristics::characteristics_label, model_context::ModelContext, model_project::ModelProject, Label, Model, ModelVisitor, Node, VisitableModel}, verification::{query::Query, VerificationReport}};
+
+use super::ExportResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Markdown,
+    Html
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DocBlock {
+    Paragraph(String),
+    Table(Vec<String>, Vec<Vec<String>>),
+}
+
+/// Human-readable description of a model project : structure, parameters, queries and their results when
+/// available. Rendered as Markdown or as a standalone HTML page, to share verification artifacts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelDocumentation {
+    pub title : String,
+    sections : Vec<(String, Vec<DocBlock>)>,
+}
+
+// Collects the nodes and edges of the model, in visit order
+#[derive(Default)]
+struct StructureCollector {
+    places : Vec<(Label, String)>,
+    transitions : Vec<(Label, String)>,
+    locations : Vec<(Label, String)>,
+    edges : Vec<(Label, Label, String)>,
+}
+
+impl StructureCollector {
+
+    fn describe(node : &dyn Node) -> (Label, String) {
+        (node.get_label(), node.get_metadata().map(|m| m.to_string()).unwrap_or_default())
+    }
+
+    fn neighbours(&self, node : &Label, incoming : bool) -> String {
+        let names : Vec<String> = self.edges.iter().filter_map(|(from, to, _)| {
+            if incoming && to == node { Some(from.to_string()) }
+            else if !incoming && from == node { Some(to.to_string()) }
+            else { None }
+        }).collect();
+        names.join(", ")
+    }
+
+}
+
+impl ModelVisitor for StructureCollector {
+
+    fn visit_place(&mut self, place : &dyn Node) {
+        self.places.push(Self::describe(place));
+    }
+
+    fn visit_transition(&mut self, transition : &dyn Node) {
+        self.transitions.push(Self::describe(transition));
+    }
+
+    fn visit_location(&mut self, location : &dyn Node) {
+        self.locations.push(Self::describe(location));
+    }
+
+    fn visit_edge(&mut self, from : &Label, to : &Label, label : &str) {
+        self.edges.push((from.clone(), to.clone(), label.to_string()));
+    }
+
+}
+
+impl ModelDocumentation {
+
+    pub fn new(title : impl Into<String>) -> Self {
+        ModelDocumentation { title : title.into(), sections : Vec::new() }
+    }
+
+    // Model description and structure tables, followed by the parameters of the project
+    pub fn of_project<S, M : Model + VisitableModel>(project : &ModelProject<S>, model : &M, ctx : &ModelContext) -> Self {
+        let meta = model.get_model_meta();
+        let mut doc = Self::new(meta.name.to_string());
+        let mut overview = Vec::new();
+        if !meta.description.is_empty() {
+            overview.push(DocBlock::Paragraph(meta.description.clone()));
+        }
+        overview.push(DocBlock::Table(
+            vec![String::from("Characteristic"), String::from("Value")],
+            vec![
+                vec![String::from("Kind"), characteristics_label(meta.characteristics).to_string()],
+                vec![String::from("Variables"), ctx.n_vars().to_string()],
+                vec![String::from("Clocks"), ctx.n_clocks().to_string()],
+                vec![String::from("Actions"), ctx.n_actions().to_string()],
+            ]
+        ));
+        doc.add_section("Overview", overview);
+        doc.add_structure(model, project);
+        doc.add_parameters(project);
+        doc
+    }
+
+    fn add_section(&mut self, title : &str, blocks : Vec<DocBlock>) {
+        if !blocks.is_empty() {
+            self.sections.push((title.to_string(), blocks));
+        }
+    }
+
+    fn add_structure<S>(&mut self, model : &impl VisitableModel, project : &ModelProject<S>) {
+        let mut collector = StructureCollector::default();
+        model.accept(&mut collector);
+        let mut blocks = Vec::new();
+        if !collector.places.is_empty() {
+            let rows = collector.places.iter().map(|(name, description)| vec![
+                name.to_string(),
+                project.initial_marking.values.get(name).copied().unwrap_or(0).to_string(),
+                description.clone()
+            ]).collect();
+            blocks.push(DocBlock::Paragraph(String::from("Places :")));
+            blocks.push(DocBlock::Table(vec![String::from("Place"), String::from("Initial marking"), String::from("Description")], rows));
+        }
+        if !collector.transitions.is_empty() {
+            let rows = collector.transitions.iter().map(|(name, description)| vec![
+                name.to_string(),
+                collector.neighbours(name, true),
+                collector.neighbours(name, false),
+                description.clone()
+            ]).collect();
+            blocks.push(DocBlock::Paragraph(String::from("Transitions :")));
+            blocks.push(DocBlock::Table(vec![String::from("Transition"), String::from("Inputs"), String::from("Outputs"), String::from("Description")], rows));
+        }
+        if !collector.locations.is_empty() {
+            let rows = collector.locations.iter().map(|(name, description)| vec![name.to_string(), description.clone()]).collect();
+            blocks.push(DocBlock::Paragraph(String::from("Locations :")));
+            blocks.push(DocBlock::Table(vec![String::from("Location"), String::from("Description")], rows));
+            let rows = collector.edges.iter().map(|(from, to, label)| vec![from.to_string(), to.to_string(), label.clone()]).collect();
+            blocks.push(DocBlock::Paragraph(String::from("Edges :")));
+            blocks.push(DocBlock::Table(vec![String::from("From"), String::from("To"), String::from("Label")], rows));
+        }
+        self.add_section("Structure", blocks);
+    }
+
+    fn add_parameters<S>(&mut self, project : &ModelProject<S>) {
+        let mut blocks = Vec::new();
+        let mut marking : Vec<(&Label, &i32)> = project.initial_marking.values.iter().collect();
+        marking.sort();
+        if !marking.is_empty() {
+            blocks.push(DocBlock::Paragraph(String::from("Initial marking (null variables omitted) :")));
+            blocks.push(DocBlock::Table(
+                vec![String::from("Variable"), String::from("Value")],
+                marking.into_iter().map(|(var, value)| vec![var.to_string(), value.to_string()]).collect()
+            ));
+        }
+        let mut parameters : Vec<(&Label, &i32)> = project.parameters.iter().collect();
+        parameters.sort();
+        if !parameters.is_empty() {
+            blocks.push(DocBlock::Paragraph(String::from("Generated from a template with :")));
+            blocks.push(DocBlock::Table(
+                vec![String::from("Parameter"), String::from("Value")],
+                parameters.into_iter().map(|(name, value)| vec![name.to_string(), value.to_string()]).collect()
+            ));
+        }
+        if let Some(seed) = project.seed {
+            blocks.push(DocBlock::Paragraph(format!("Simulations are seeded with {}.", seed)));
+        }
+        let mut predicates : Vec<(&Label, String)> = project.predicates.iter().map(|(name, cond)| (name, cond.to_string())).collect();
+        predicates.sort();
+        if !predicates.is_empty() {
+            blocks.push(DocBlock::Paragraph(String::from("Predicates :")));
+            blocks.push(DocBlock::Table(
+                vec![String::from("Predicate"), String::from("Definition")],
+                predicates.into_iter().map(|(name, definition)| vec![name.to_string(), definition]).collect()
+            ));
+        }
+        self.add_section("Parameters", blocks);
+    }
+
+    // Queries, along with the reports of their verification when available
+    pub fn with_queries<'a>(mut self, queries : impl IntoIterator<Item = (&'a Query, Option<&'a VerificationReport>)>) -> Self {
+        let rows : Vec<Vec<String>> = queries.into_iter().map(|(query, report)| match report {
+            Some(report) => {
+                let mut details = Vec::new();
+                if let Some(runs) = report.runs {
+                    details.push(format!("{} runs", runs));
+                }
+                if let Some(states) = report.states {
+                    details.push(format!("{} states", states));
+                }
+                details.push(format!("{:.3}s", report.wall_time));
+                vec![query.to_string(), report.result.clone(), details.join(", ")]
+            },
+            None => vec![query.to_string(), String::from("Not verified"), String::new()]
+        }).collect();
+        if !rows.is_empty() {
+            self.add_section("Queries", vec![DocBlock::Table(vec![String::from("Query"), String::from("Result"), String::from("Details")], rows)]);
+        }
+        self
+    }
+
+    // Free text section, such as a mermaid diagram or conclusions
+    pub fn with_section(mut self, title : impl Into<String>, text : impl Into<String>) -> Self {
+        self.sections.push((title.into(), vec![DocBlock::Paragraph(text.into())]));
+        self
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let cell = |text : &str| text.replace('|', "\\|").replace('\n', " ");
+        let mut doc = format!("# {}\n", self.title);
+        for (title, blocks) in self.sections.iter() {
+            doc += &format!("\n## {}\n", title);
+            for block in blocks.iter() {
+                match block {
+                    DocBlock::Paragraph(text) => doc += &format!("\n{}\n", text),
+                    DocBlock::Table(headers, rows) => {
+                        doc += &format!("\n| {} |\n", headers.join(" | "));
+                        doc += &format!("|{}\n", "---|".repeat(headers.len()));
+                        for row in rows.iter() {
+                            let row : Vec<String> = row.iter().map(|c| cell(c)).collect();
+                            doc += &format!("| {} |\n", row.join(" | "));
+                        }
+                    }
+                }
+            }
+        }
+        doc
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut doc = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n", escape_html(&self.title));
+        doc += "<style>body { font-family : sans-serif; } table { border-collapse : collapse; } th, td { border : 1px solid #999; padding : 4px 8px; }</style>\n";
+        doc += &format!("</head>\n<body>\n<h1>{}</h1>\n", escape_html(&self.title));
+        for (title, blocks) in self.sections.iter() {
+            doc += &format!("<h2>{}</h2>\n", escape_html(title));
+            for block in blocks.iter() {
+                match block {
+                    DocBlock::Paragraph(text) if text.contains('\n') => doc += &format!("<pre>{}</pre>\n", escape_html(text)),
+                    DocBlock::Paragraph(text) => doc += &format!("<p>{}</p>\n", escape_html(text)),
+                    DocBlock::Table(headers, rows) => {
+                        doc += "<table>\n<tr>";
+                        for header in headers.iter() {
+                            doc += &format!("<th>{}</th>", escape_html(header));
+                        }
+                        doc += "</tr>\n";
+                        for row in rows.iter() {
+                            doc += "<tr>";
+                            for cell in row.iter() {
+                                doc += &format!("<td>{}</td>", escape_html(cell));
+                            }
+                            doc += "</tr>\n";
+                        }
+                        doc += "</table>\n";
+                    }
+                }
+            }
+        }
+        doc += "</body>\n</html>\n";
+        doc
+    }
+
+    pub fn render(&self, format : DocumentFormat) -> String {
+        match format {
+            DocumentFormat::Markdown => self.to_markdown(),
+            DocumentFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn write(&self, path : impl AsRef<Path>, format : DocumentFormat) -> ExportResult {
+        fs::write(path, self.render(format))?;
+        Ok(())
+    }
+
+}
+
+fn escape_html(text : &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,30 @@
+use std::{fs::File, io::{BufReader, BufWriter, Write}, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::models::{model_context::ModelContext, model_project::ModelProject, Model};
+
+use super::{ExportError, ExportResult};
+
+/// Writes a model project as JSON, initial marking included
+pub fn write_file<S : Serialize>(path : impl AsRef<Path>, project : &ModelProject<S>) -> ExportResult {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, project)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a model project, the initial state is left empty until the model is compiled
+pub fn read_file<S : DeserializeOwned>(path : impl AsRef<Path>) -> Result<ModelProject<S>, ExportError> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Reads a model project and builds the compiled model, with the initial state reconstructed from the marking
+pub fn load_file<S, M>(path : impl AsRef<Path>) -> Result<(M, ModelContext, ModelProject<S>), ExportError>
+    where S : DeserializeOwned + Clone, M : Model + From<S>
+{
+    let mut project : ModelProject<S> = read_file(path)?;
+    let (model, ctx) = project.make();
+    Ok((model, ctx, project))
+}
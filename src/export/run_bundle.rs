@@ -0,0 +1,99 @@
+use std::{fs::{self, File}, io::{BufWriter, Write}, path::Path};
+
+use crate::verification::smc::{RunBundle, RunRecord};
+
+use super::ExportResult;
+
+const RUNS_FILE : &str = "runs.csv";
+const DELAYS_FILE : &str = "delays.csv";
+const SCHEMA_FILE : &str = "schema.md";
+#[cfg(feature = "parquet")]
+const PARQUET_FILE : &str = "runs.parquet";
+
+const FIXED_COLUMNS : [(&str, &str, &str); 7] = [
+    ("run", "integer", "Index of the run, in execution order"),
+    ("steps", "integer", "Number of discrete steps taken"),
+    ("time", "float", "Total time elapsed"),
+    ("verdict", "string", "Run verdict : verified, unverified or maybe"),
+    ("maximal", "boolean", "Whether the run ended in a deadlock or maximal state"),
+    ("state_digest", "integer", "Hash of the last state of the run, equal digests denote (almost surely) equal states"),
+    ("seed", "integer", "Seed the run was simulated from, to replay it"),
+];
+
+// Quotes a CSV field when needed
+fn csv_field(field : &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_row(record : &RunRecord) -> String {
+    let mut fields = vec![
+        record.run.to_string(),
+        record.steps.to_string(),
+        record.time.to_string(),
+        record.verdict().to_string(),
+        record.maximal.to_string(),
+        record.state_digest.to_string(),
+        record.seed.to_string(),
+    ];
+    fields.extend(record.observations.iter().map(f64::to_string));
+    fields.join(",")
+}
+
+/// Writes the run summaries as CSV, one row per run, and the delays in long format (run, index, delay)
+pub fn write_bundle_csv(dir : impl AsRef<Path>, bundle : &RunBundle) -> ExportResult {
+    let dir = dir.as_ref();
+    let mut runs = BufWriter::new(File::create(dir.join(RUNS_FILE))?);
+    let header : Vec<String> = FIXED_COLUMNS.iter().map(|(name, _, _)| name.to_string())
+        .chain(bundle.columns.iter().map(|c| csv_field(&c.name)))
+        .collect();
+    writeln!(runs, "{}", header.join(","))?;
+    for record in bundle.records.iter() {
+        writeln!(runs, "{}", csv_row(record))?;
+    }
+    runs.flush()?;
+
+    let mut delays = BufWriter::new(File::create(dir.join(DELAYS_FILE))?);
+    writeln!(delays, "run,index,delay")?;
+    for record in bundle.records.iter() {
+        for (i, delay) in record.delays.iter().enumerate() {
+            writeln!(delays, "{},{},{}", record.run, i, delay)?;
+        }
+    }
+    delays.flush()?;
+    Ok(())
+}
+
+/// Markdown documentation of the bundle tables, generated from the registered monitors
+pub fn bundle_schema(bundle : &RunBundle) -> String {
+    let mut doc = String::from("# SMC run bundle\n\n");
+    doc += &format!("{} runs recorded.\n\n", bundle.len());
+    doc += &format!("## `{}`\n\nOne row per run.\n\n", RUNS_FILE);
+    doc += "| Column | Type | Description |\n|---|---|---|\n";
+    for (name, col_type, description) in FIXED_COLUMNS {
+        doc += &format!("| `{}` | {} | {} |\n", name, col_type, description);
+    }
+    for column in bundle.columns.iter() {
+        doc += &format!("| `{}` | float | {} |\n", column.name, column.description);
+    }
+    doc += &format!("\n## `{}`\n\nOne row per non-zero delay.\n\n", DELAYS_FILE);
+    doc += "| Column | Type | Description |\n|---|---|---|\n";
+    doc += "| `run` | integer | Index of the run, joins with the runs table |\n";
+    doc += "| `index` | integer | Position of the delay in the run |\n";
+    doc += "| `delay` | float | Time elapsed before the step |\n";
+    doc
+}
+
+/// Writes a full bundle to a directory : CSV tables, schema documentation and a Parquet table if enabled
+pub fn write_bundle(dir : impl AsRef<Path>, bundle : &RunBundle) -> ExportResult {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    write_bundle_csv(dir, bundle)?;
+    fs::write(dir.join(SCHEMA_FILE), bundle_schema(bundle))?;
+    #[cfg(feature = "parquet")]
+    super::write_bundle_parquet(dir.join(PARQUET_FILE), bundle)?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::{fs::File, path::Path, sync::Arc};
+
+use parquet::{data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type}, errors::ParquetError, file::{properties::WriterProperties, writer::SerializedFileWriter}, schema::parser::parse_message_type};
+
+use crate::verification::smc::{MonitorColumn, RunBundle, RunRecord};
+
+use super::{ExportError, ExportResult};
+
+// Fixed columns, monitor columns are inserted before the delays
+const RUNS_COLUMNS : &str = "
+        REQUIRED INT64 run;
+        REQUIRED INT64 steps;
+        REQUIRED DOUBLE time;
+        REQUIRED BYTE_ARRAY verdict (UTF8);
+        REQUIRED BOOLEAN maximal;
+        REQUIRED INT64 state_digest;
+        REQUIRED INT64 seed;
+";
+const FIXED_COLUMNS : usize = 7;
+
+// Maximum number of runs written in a single row group
+const ROW_GROUP_SIZE : usize = 1 << 16;
+
+impl From<ParquetError> for ExportError {
+    fn from(value: ParquetError) -> Self {
+        ExportError(value.to_string())
+    }
+}
+
+fn runs_schema(columns : &[MonitorColumn]) -> String {
+    let mut schema = String::from("message smc_runs {");
+    schema += RUNS_COLUMNS;
+    for column in columns {
+        schema += &format!("        REQUIRED DOUBLE {};\n", column.name);
+    }
+    schema += "        REPEATED DOUBLE delays;\n}";
+    schema
+}
+
+/// Writes SMC run records as a Parquet table, one row per run
+pub fn write_runs_parquet(path : impl AsRef<Path>, records : &[RunRecord]) -> ExportResult {
+    write_table(path, records, &[])
+}
+
+/// Writes a run bundle as a Parquet table, with one column per monitored value
+pub fn write_bundle_parquet(path : impl AsRef<Path>, bundle : &RunBundle) -> ExportResult {
+    write_table(path, &bundle.records, &bundle.columns)
+}
+
+fn write_table(path : impl AsRef<Path>, records : &[RunRecord], columns : &[MonitorColumn]) -> ExportResult {
+    let schema = Arc::new(parse_message_type(&runs_schema(columns))?);
+    let props = Arc::new(WriterProperties::builder().build());
+    let file = File::create(path)?;
+    let mut writer = SerializedFileWriter::new(file, schema, props)?;
+    for chunk in records.chunks(ROW_GROUP_SIZE) {
+        let mut row_group = writer.next_row_group()?;
+        let mut column_index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            match column_index {
+                0 => {
+                    let values : Vec<i64> = chunk.iter().map(|r| r.run as i64).collect();
+                    column.typed::<Int64Type>().write_batch(&values, None, None)?;
+                },
+                1 => {
+                    let values : Vec<i64> = chunk.iter().map(|r| r.steps as i64).collect();
+                    column.typed::<Int64Type>().write_batch(&values, None, None)?;
+                },
+                2 => {
+                    let values : Vec<f64> = chunk.iter().map(|r| r.time).collect();
+                    column.typed::<DoubleType>().write_batch(&values, None, None)?;
+                },
+                3 => {
+                    let values : Vec<ByteArray> = chunk.iter().map(|r| ByteArray::from(r.verdict())).collect();
+                    column.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+                },
+                4 => {
+                    let values : Vec<bool> = chunk.iter().map(|r| r.maximal).collect();
+                    column.typed::<BoolType>().write_batch(&values, None, None)?;
+                },
+                5 => {
+                    // Digests are stored with the same bits, as Parquet has no unsigned 64 bits physical type
+                    let values : Vec<i64> = chunk.iter().map(|r| r.state_digest as i64).collect();
+                    column.typed::<Int64Type>().write_batch(&values, None, None)?;
+                },
+                6 => {
+                    let values : Vec<i64> = chunk.iter().map(|r| r.seed as i64).collect();
+                    column.typed::<Int64Type>().write_batch(&values, None, None)?;
+                },
+                i if i < FIXED_COLUMNS + columns.len() => {
+                    let obs = i - FIXED_COLUMNS;
+                    let values : Vec<f64> = chunk.iter().map(|r| r.observations[obs]).collect();
+                    column.typed::<DoubleType>().write_batch(&values, None, None)?;
+                },
+                _ => {
+                    // Repeated column : definition level 0 encodes an empty list, repetition level 1 continues the current one
+                    let mut values = Vec::new();
+                    let mut def_levels = Vec::new();
+                    let mut rep_levels = Vec::new();
+                    for record in chunk {
+                        if record.delays.is_empty() {
+                            def_levels.push(0);
+                            rep_levels.push(0);
+                            continue;
+                        }
+                        for (i, delay) in record.delays.iter().enumerate() {
+                            values.push(*delay);
+                            def_levels.push(1);
+                            rep_levels.push(if i == 0 { 0 } else { 1 });
+                        }
+                    }
+                    column.typed::<DoubleType>().write_batch(&values, Some(&def_levels), Some(&rep_levels))?;
+                }
+            }
+            column.close()?;
+            column_index += 1;
+        }
+        row_group.close()?;
+    }
+    writer.close()?;
+    Ok(())
+}
@@ -1,3 +1,9 @@
 mod strategy;
+mod imported_strategy;
+mod restricted_model;
+mod worst_case_delay;
 
-pub use strategy::Strategy;
\ No newline at end of file
+pub use strategy::Strategy;
+pub use imported_strategy::{ImportedStrategy, StrategyRule, StrategyImportError, StrategyImportResult};
+pub use restricted_model::RestrictedModel;
+pub use worst_case_delay::WorstCaseDelay;
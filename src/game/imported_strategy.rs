@@ -0,0 +1,129 @@
+use std::{collections::HashSet, fmt::Display, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{models::{action::Action, expressions::Condition, model_context::ModelContext, model_var::{MappingError, MappingResult}, Label, ModelState}, verification::text_query_parser::parse_condition};
+
+use super::Strategy;
+
+#[derive(Debug, Clone)]
+pub struct StrategyImportError(pub String);
+pub type StrategyImportResult<T> = Result<T, StrategyImportError>;
+impl Display for StrategyImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Strategy import error : {}", self.0)
+    }
+}
+
+/// When the condition holds, only the given actions can be played
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategyRule {
+    pub condition : Condition,
+    pub actions : Vec<Label>,
+    #[serde(skip)]
+    pub mapped_actions : HashSet<Action>,
+}
+
+/// Memoryless strategy produced by an external tool. Rules are tried in order, the first one whose condition holds applies.
+/// Actions never mentioned by the strategy are considered uncontrollable, and are always allowed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportedStrategy {
+    pub rules : Vec<StrategyRule>,
+    // Whether controllable actions are allowed in states covered by no rule
+    pub permissive : bool,
+    #[serde(skip)]
+    pub controllable : HashSet<Action>,
+}
+
+impl ImportedStrategy {
+
+    pub fn new(permissive : bool) -> Self {
+        ImportedStrategy { permissive, ..Default::default() }
+    }
+
+    pub fn add_rule(&mut self, condition : Condition, actions : Vec<Label>) {
+        self.rules.push(StrategyRule { condition, actions, mapped_actions : HashSet::new() });
+    }
+
+    // Text format, one rule per line : `condition -> action1, action2`. Lines starting with # are comments.
+    pub fn from_text(text : &str) -> StrategyImportResult<Self> {
+        let mut strategy = ImportedStrategy::new(false);
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((condition, actions)) = line.rsplit_once("->") else {
+                return Err(StrategyImportError(format!("Line {} : missing '->'", i + 1)));
+            };
+            let condition = parse_condition(condition.trim()).map_err(|e| {
+                StrategyImportError(format!("Line {} : unable to parse condition ({})", i + 1, e.message))
+            })?;
+            let actions = actions.split(',')
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(Label::from)
+                .collect();
+            strategy.add_rule(condition, actions);
+        }
+        Ok(strategy)
+    }
+
+    pub fn from_json(json : &str) -> StrategyImportResult<Self> {
+        serde_json::from_str(json).map_err(|e| StrategyImportError(e.to_string()))
+    }
+
+    // Format is chosen from the file extension, json or text
+    pub fn load(path : impl AsRef<Path>) -> StrategyImportResult<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| StrategyImportError(e.to_string()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&content),
+            _ => Self::from_text(&content)
+        }
+    }
+
+    pub fn apply_to(&mut self, ctx : &ModelContext) -> MappingResult<()> {
+        self.controllable.clear();
+        for rule in self.rules.iter_mut() {
+            rule.condition = rule.condition.apply_to(ctx)?;
+            rule.mapped_actions = rule.actions.iter().map(|a| {
+                ctx.get_action(a).ok_or(MappingError(a.clone()))
+            }).collect::<MappingResult<HashSet<Action>>>()?;
+            self.controllable.extend(rule.mapped_actions.iter().cloned());
+        }
+        Ok(())
+    }
+
+    pub fn is_controllable(&self, action : &Action) -> bool {
+        self.controllable.contains(&action.base())
+    }
+
+    // Controllable actions allowed in the state, None if no rule applies
+    pub fn allowed_actions(&self, state : &ModelState) -> Option<&HashSet<Action>> {
+        self.rules.iter()
+            .find(|r| r.condition.is_true(state))
+            .map(|r| &r.mapped_actions)
+    }
+
+    pub fn allows(&self, state : &ModelState, action : &Action) -> bool {
+        if !self.is_controllable(action) {
+            return true;
+        }
+        match self.allowed_actions(state) {
+            None => self.permissive,
+            Some(actions) => actions.contains(&action.base())
+        }
+    }
+
+}
+
+impl Strategy for ImportedStrategy {
+    type Input = ModelState;
+    type Output = HashSet<Action>;
+
+    fn play(&mut self, from : Self::Input) -> Self::Output {
+        self.allowed_actions(&from).cloned().unwrap_or_default()
+    }
+
+}
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use crate::computation::random::SimulationRng;
+use crate::models::{action::Action, lbl, model_characteristics::CONTROLLABLE, model_context::ModelContext, time::ClockValue, CompilationError, CompilationResult, Model, ModelMeta, ModelState};
+
+use super::ImportedStrategy;
+
+/// Closed-loop system : the model, restricted to the actions allowed by a strategy
+pub struct RestrictedModel<M : Model> {
+    pub model : M,
+    pub strategy : ImportedStrategy,
+}
+
+impl<M : Model> RestrictedModel<M> {
+
+    pub fn new(model : M, strategy : ImportedStrategy) -> Self {
+        RestrictedModel { model, strategy }
+    }
+
+    fn restrict(&self, state : &ModelState, actions : HashSet<Action>) -> HashSet<Action> {
+        actions.into_iter().filter(|a| self.strategy.allows(state, a)).collect()
+    }
+
+}
+
+impl<M : Model> Model for RestrictedModel<M> {
+
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        if !self.strategy.allows(&state, &action) {
+            return None;
+        }
+        let (next, actions) = self.model.next(state, action)?;
+        let actions = self.restrict(&next, actions);
+        Some((next, actions))
+    }
+
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        if !self.strategy.allows(&state, &action) {
+            return None;
+        }
+        let (next, actions) = self.model.sample_next(state, action, rng)?;
+        let actions = self.restrict(&next, actions);
+        Some((next, actions))
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        self.restrict(state, self.model.available_actions(state))
+    }
+
+    fn available_delay(&self, state : &ModelState) -> ClockValue {
+        self.model.available_delay(state)
+    }
+
+    fn delay(&self, state : ModelState, dt : ClockValue) -> Option<ModelState> {
+        self.model.delay(state, dt)
+    }
+
+    fn init_initial_clocks(&self, state : ModelState) -> ModelState {
+        self.model.init_initial_clocks(state)
+    }
+
+    fn init_initial_storage(&self, state : ModelState) -> ModelState {
+        self.model.init_initial_storage(state)
+    }
+
+    fn get_meta() -> ModelMeta {
+        ModelMeta {
+            name : lbl("RestrictedModel"),
+            description : String::from("Model whose controllable actions are restricted by an imported strategy"),
+            characteristics : CONTROLLABLE
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        self.model.is_timed()
+    }
+
+    fn is_stochastic(&self) -> bool {
+        self.model.is_stochastic()
+    }
+
+    fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        self.model.compile(context)?;
+        self.strategy.apply_to(context).map_err(|_| CompilationError)
+    }
+
+    fn get_id(&self) -> usize {
+        self.model.get_id()
+    }
+
+}
@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use crate::{models::{class_graph::{ClassGraph, StateClass}, expressions::Condition, ClockId, TransitionId}, verification::VerificationStatus};
+
+use super::ImportedStrategy;
+
+// Transition firable from a class, with the range of its firing delay
+struct Move {
+    target : usize,
+    earliest : f64,
+    latest : f64,
+    controllable : bool,
+}
+
+/// Worst-case time to reach a goal on a class graph, seen as a game : the environment fires uncontrollable transitions
+/// and picks firing dates to delay the goal as much as it can, while the controller fires the controllable transitions
+/// allowed by a strategy (any of them without strategy) as early as it can. Classes where the goal is not surely
+/// verified (undecided clock constraints) are not goals.
+/// The regret of a class is the time lost compared to a cooperative environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorstCaseDelay {
+    pub worst : Vec<f64>,
+    pub best : Vec<f64>,
+}
+
+impl WorstCaseDelay {
+
+    pub fn compute(cg : &ClassGraph, goal : &Condition, strategy : Option<&ImportedStrategy>) -> Self {
+        let moves = Self::moves(cg, strategy);
+        let goals : Vec<bool> = cg.classes.iter().map(|c| cg.evaluate_symbolic(c, goal) == VerificationStatus::Verified).collect();
+        WorstCaseDelay {
+            worst : Self::fixpoint(&goals, &moves, Self::game_value),
+            best : Self::fixpoint(&goals, &moves, Self::cooperative_value)
+        }
+    }
+
+    // Worst-case delay from the initial class, infinite if the environment can prevent the goal
+    pub fn initial_delay(&self) -> f64 {
+        self.worst.first().cloned().unwrap_or(f64::INFINITY)
+    }
+
+    // Infinite if the goal can be prevented, even when it can't be reached at all
+    pub fn regret(&self, class : usize) -> f64 {
+        if self.worst[class].is_infinite() {
+            return f64::INFINITY;
+        }
+        self.worst[class] - self.best[class]
+    }
+
+    // Firing delays of a transition in a class, the latest one being limited by the urgency of every enabled transition
+    fn delays(class : &StateClass, t_index : TransitionId) -> Option<(f64, f64)> {
+        let k = class.clock_of(t_index)?;
+        let earliest = (-class.dbm[(ClockId::REFERENCE, k)]).float();
+        let latest = (1..class.from_dbm_index.len()).map(|u| class.dbm[(ClockId(u), ClockId::REFERENCE)].float()).fold(f64::INFINITY, f64::min);
+        Some((earliest, latest.max(earliest)))
+    }
+
+    // Controllable transitions forbidden by the strategy are never fired
+    fn moves(cg : &ClassGraph, strategy : Option<&ImportedStrategy>) -> Vec<Vec<Move>> {
+        let mut moves : Vec<Vec<Move>> = cg.classes.iter().map(|_| Vec::new()).collect();
+        for class in cg.classes.iter() {
+            for (pred, action) in class.predecessors.read().unwrap().iter() {
+                let Some(pred) = pred.upgrade() else {
+                    continue;
+                };
+                let Some(t_index) = cg.transitions.iter().position(|t| t.get_action() == *action) else {
+                    continue;
+                };
+                let controllable = cg.transitions[t_index].controllable;
+                if controllable && strategy.is_some_and(|s| !s.allows(&pred.generate_image_state(), action)) {
+                    continue;
+                }
+                let Some((earliest, latest)) = Self::delays(&pred, TransitionId(t_index)) else {
+                    continue;
+                };
+                moves[pred.index.index()].push(Move { target : class.index.index(), earliest, latest, controllable });
+            }
+        }
+        moves
+    }
+
+    // The controller fires its best transition as soon as possible, unless the environment fires one of its own before.
+    // Without controllable transition, the environment fires the latest it can.
+    fn game_value(moves : &[Move], values : &[f64]) -> f64 {
+        let environment = |deadline : f64| moves.iter()
+            .filter(|m| !m.controllable && m.earliest <= deadline)
+            .map(|m| m.latest.min(deadline) + values[m.target])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let controls : Vec<&Move> = moves.iter().filter(|m| m.controllable).collect();
+        if controls.is_empty() {
+            return if moves.is_empty() { f64::INFINITY } else { environment(f64::INFINITY) };
+        }
+        controls.iter()
+            .map(|c| (c.earliest + values[c.target]).max(environment(c.earliest)))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn cooperative_value(moves : &[Move], values : &[f64]) -> f64 {
+        moves.iter().map(|m| m.earliest + values[m.target]).fold(f64::INFINITY, f64::min)
+    }
+
+    // Backward fixpoint from the goal classes, starting from infinite delays elsewhere : after k iterations, values are
+    // the ones of plays reaching the goal within k steps, so the number of classes bounds the iterations needed.
+    fn fixpoint(goals : &[bool], moves : &[Vec<Move>], value : fn(&[Move], &[f64]) -> f64) -> Vec<f64> {
+        let mut values : Vec<f64> = goals.iter().map(|g| if *g { 0.0 } else { f64::INFINITY }).collect();
+        for _ in 0..=goals.len() {
+            let next : Vec<f64> = (0..goals.len()).map(|i| {
+                if goals[i] { 0.0 } else { value(&moves[i], &values) }
+            }).collect();
+            if next == values {
+                break;
+            }
+            values = next;
+        }
+        values
+    }
+
+}
+
+impl Display for WorstCaseDelay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (worst, best)) in self.worst.iter().zip(self.best.iter()).enumerate() {
+            writeln!(f, "Class {} : worst {}, best {}, regret {}", i, worst, best, self.regret(i))?;
+        }
+        Ok(())
+    }
+}
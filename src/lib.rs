@@ -0,0 +1,10 @@
+pub mod models;
+pub mod computation;
+pub mod game;
+pub mod translation;
+pub mod verification;
+pub mod solution;
+pub mod log;
+pub mod export;
+
+use verification::query::*;
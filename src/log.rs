@@ -35,4 +35,8 @@ pub fn positive<S: AsRef<str>>(msg : S) {
 pub fn negative<S: AsRef<str>>(msg : S) {
     let msg = msg.as_ref();
     println!(" [-] {}", msg);
-}
\ No newline at end of file
+}
+pub fn nested<S: AsRef<str>>(depth : usize, msg : S) {
+    let msg = msg.as_ref();
+    println!(" |{} - {}", "   ".repeat(depth), msg);
+}
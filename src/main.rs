@@ -1,39 +1,64 @@
-pub mod models;
-pub mod computation;
-pub mod game;
-pub mod translation;
-pub mod verification;
-pub mod solution;
-pub mod log;
+use sally_mc::{models, computation, translation, verification, solution, log, export};
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use computation::intervals::Convex;
+use computation::random::SimulationRng;
 use models::digraph::Digraph;
 use models::expressions::{Condition, Expr};
-use models::lbl;
-use models::markov::markov_chain::MarkovChain;
+use models::{lbl, Label, NodeMetadata};
+use models::action::Action;
+use models::markov::markov_chain::{MarkovChain, MarkovChainMaker};
+use models::ModelMaker;
+use models::markov::markov_automaton::{MAState, MarkovAutomaton};
 use models::markov::markov_node::MarkovNode;
-use models::model_var::var;
-use models::petri::{PetriPlace, PetriTransition, PetriStructure};
-use models::time::{TimeInterval, TimeBound::*};
+use models::markov::mdp::{MDPState, MDP};
+use models::beliefs_graph::{BeliefsGraph, POMDP};
+use models::markov::scheduler::{MemorylessScheduler, Scheduler, UniformScheduler};
+use models::model_var::{var, VarType};
+use models::program::Program;
+use models::hybrid::{HybridAutomaton, HybridEdge, HybridLocation, LinearConstraint};
+use models::initial_marking::MarkingValue;
+use models::model_param::{substitute_parameters, ModelTemplate, ParameterValuation};
+use models::word::{RegularExpression, WeightedWord};
+use models::petri::{PetriPlace, PetriTransition, PetriStructure, SubstitutionTransition};
+use models::time::{ClockValue, TimeInterval, TimeBound::*};
+use models::tapn::{TAPN, tapn_place::TAPNPlace};
 use solution::ClassGraphReachability;
-use translation::observation::{ObservationFunction, PartialObservation};
+use translation::observation::ObservationFunction;
 
 use crate::models::class_graph::ClassGraph;
 use crate::models::model_solving_graph::ModelSolvingGraph;
+use crate::models::model_context::ModelContext;
+use crate::models::model_network::ModelNetwork;
 use crate::models::petri::{PetriMaker, PetriNet};
-use crate::translation::{PetriClassGraphTranslation, Translation};
+use crate::translation::{ColoredUnfolding, MarkovAutomatonSubclassTranslation, ObserverSynthesis, PetriClassGraphTranslation, Translation, UntimedProjection};
+use crate::models::colored_petri::{ArcExpression, Color, ColorGuard, ColorTerm, ColorType, ColoredPetriNet, ColoredPlace, ColoredTransition};
 use crate::models::Model;
-use crate::solution::{ClassGraphReachabilitySynthesis, Solution};
-use crate::verification::text_query_parser::parse_query;
-use crate::verification::{query::*, VerificationBound};
-use crate::verification::smc::{ProbabilityEstimation, SMCMaxSeen, SMCQueryVerification};
+use crate::models::reward_structure::RewardStructure;
+use crate::models::ModelStatistics;
+use crate::models::model_project::ModelProject;
+use crate::solution::{ClassGraphReachabilitySynthesis, PetriStructuralBoundedness, SafeNetReachability, LtlModelChecking, MarkovExpectedReward, MarkovQuantile, MarkovSteadyState, Solution, Explanation};
+use crate::verification::text_query_parser::{parse_condition, parse_expression, parse_query};
+use crate::verification::sharded_exploration::ShardedExploration;
+use crate::export::{convert_directory, MermaidExport, ModelFormat, ModelDocumentation, DocumentFormat};
+use crate::verification::{batch_mode::{BatchVerification, EXIT_ERROR}, coverage, query::*, VerificationBound, VerificationReport};
+use crate::verification::smc::{IntervalMethod, ProbabilityEstimation, ProbabilityFloatComparison, SMCMaxSeen, SMCNestedResolver, RunMonitor, FiringMonitor, ExpectedRewardEstimation, QuantileEstimation, ConditionalEstimation, SMCQueryVerification, SMCHandle, RandomRunIterator, SPRT, ImportanceSplitting, ImportanceSampling, TraceClustering, TraceFeature, log_clusters, Statistics, RunDebugger, ExpectedValueEstimation, SamplingStrategy, RunCache, SteadyStateEstimation};
 
 use log::*;
 
 fn main() {
 
+    let args : Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|a| a == "--batch") {
+        std::process::exit(batch_main(&args[2..]));
+    }
+    #[cfg(feature = "distributed")]
+    if args.get(1).is_some_and(|a| a == "--worker") {
+        std::process::exit(worker_main(&args[2..]));
+    }
+
     println!(" [#] Sally Model Checker - v.1.0");
     lf();
     println!(" [.] Features :");
@@ -44,7 +69,7 @@ fn main() {
     lf();
 
     pending("Building Model Solving Graph...");
-    let solver = build_solver();
+    let mut solver = build_solver();
     positive(format!("Models loaded : \t[{}]", solver.models.len()));
     positive(format!("Translations : \t[{}]", solver.translations.len()));
     positive(format!("Solutions : \t[{}]", solver.solutions.len()));
@@ -54,6 +79,10 @@ fn main() {
     let ctx = net.singleton();
     println!("{}", ctx);
     println!("{}", net.get_model_meta());
+    println!("{}", net.mermaid());
+    info(format!("Structure : {}", ModelStatistics::of(&net)));
+    let corpus = std::env::temp_dir().join("sally_corpus");
+    let _ = convert_directory::<PetriNet>(&solver, "benches/models", &corpus, "json", "mmd");
     lf();
 
     let mut translation = PetriClassGraphTranslation::new();
@@ -70,13 +99,86 @@ fn main() {
     for c in cg.classes.iter() {
         println!("{}", c);
     }
+    println!("{}", cg.mermaid());
 
-    let mut solution = ClassGraphReachability::new();
+    let mut solution = ClassGraphReachability::new().with_certificates();
     let mut query = sample_query();
     query.apply_to(&ctx).unwrap();
     if solution.is_compatible(cg, &ctx, &query) {
         positive("Solution compatible, ready to solve !");
-        solution.solve(cg, &ctx, &query);
+        println!("{}", solution.solve(cg, &ctx, &query));
+    }
+    if let Some(certificate) = solution.certificate() {
+        println!("{}", certificate);
+        positive(format!("Certificate checked : {}", certificate.check(&net, &initial_state, &query)));
+    }
+    for text in ["E <> p3 & p5", "A [] p3 + p5 <= 1"] {
+        let mut certified = parse_query(String::from(text)).unwrap();
+        certified.apply_to(&ctx).unwrap();
+        solution.solve(cg, &ctx, &certified);
+        if let Some(certificate) = solution.certificate() {
+            positive(format!("{} : {}, checked : {}", text, certificate, certificate.check(&net, &initial_state, &certified)));
+        }
+    }
+    for text in ["E <> (p5 & deadlock)", "A [] p3 + p5 <= 1", "A [] p3 + p5 <= 0"] {
+        let mut explained = parse_query(String::from(text)).unwrap();
+        explained.apply_to(&ctx).unwrap();
+        info(format!("Why {} ?", text));
+        Explanation::explain(cg, &explained).log();
+    }
+    lf();
+
+    let (result, provenance) = solver.solve(&net, &lbl("TPN"), &ctx, &initial_state, &query);
+    provenance.log();
+    positive(format!("Routed result : {}", result));
+    let report = VerificationReport::new(&query, &result)
+        .with_provenance(&provenance)
+        .with_query_text("E <> (p5 & deadlock)")
+        .with_states(cg.classes.len());
+    report.log();
+    println!("{}", report.to_json().unwrap());
+
+    for text in ["E <> p5", "A [] p3 + p5 <= 1"] {
+        let mut observed = parse_query(String::from(text)).unwrap();
+        observed.apply_to(&ctx).unwrap();
+        let mut observer = ObserverSynthesis::new(&observed).unwrap();
+        observer.translate(&net, &ctx, &initial_state).unwrap();
+        let reduced = observer.reduced_query();
+        let (observed_net, observed_ctx, observed_state) = observer.get_translated();
+        let (result, _) = solver.solve(observed_net, &lbl("TPN"), observed_ctx, observed_state, &reduced);
+        positive(format!("{} with observer : {}", text, result));
+    }
+
+    let (result, _) = solver.solve(&net, &lbl("TPN"), &ctx, &initial_state, &Query::deadlock_free());
+    positive(format!("Deadlock free : {}", result));
+    let bounded = Query::k_bounded(&ctx, 1);
+    let mut structural = PetriStructuralBoundedness::new(initial_state.clone());
+    if structural.is_compatible(&net, &ctx, &bounded) {
+        positive(format!("1-bounded : {}", structural.solve(&net, &ctx, &bounded)));
+    }
+    for (transition, mut live) in Query::quasi_liveness(&net) {
+        live.apply_to(&ctx).unwrap();
+        let (result, _) = solver.solve(&net, &lbl("TPN"), &ctx, &initial_state, &live);
+        continue_info(format!("{} can fire : {}", transition, result));
+    }
+    let compiled = net.get_structure().compile().unwrap();
+    let compiled_state = compiled.context().make_initial_state(&compiled, HashMap::from([
+        (lbl("p0"), 1),
+    ]));
+    let handle = compiled.clone();
+    let fired = std::thread::spawn(move || {
+        let first = handle.transition_id(&lbl("t0")).unwrap();
+        handle.fire(handle.init_initial_clocks(compiled_state), first).0
+    }).join().unwrap();
+    info(format!("Fired t0 on a shared compiled net : {}", fired.discrete));
+    lf();
+
+    let mut ltl_solution = LtlModelChecking::new();
+    for text in ["A <> deadlock", "A <> p3", "E <> p3", "A [] (p3 + p5 < 2)", "A X (p1 & p4)", "A ((p0 | p1) U p2)"] {
+        let mut query = parse_query(String::from(text)).unwrap();
+        query.apply_to(&ctx).unwrap();
+        let res = ltl_solution.solve(cg, &ctx, &query);
+        println!("{} : {:?}", text, res);
     }
     lf();
 
@@ -92,6 +194,60 @@ fn main() {
     let res = estim.parallel_verify(&net, &initial_state, &query);
     println!("{:?}", res);
 
+    let mut estim  = ProbabilityEstimation::new(0.95, 0.05);
+    let handle = SMCHandle::new().with_interval(500).with_callback(|p| continue_info(format!("Progress : {}", p)));
+    println!("{:?}", estim.verify_with_handle(&net, &initial_state, &query, &handle));
+    let mut estim  = ProbabilityEstimation::fixed_runs(100000, 0.95);
+    let token = SMCHandle::new();
+    let canceller = token.clone();
+    let handle = token.with_interval(1000).with_callback(move |p| if p.runs >= 5000 { canceller.cancel() });
+    println!("{:?}", estim.parallel_verify_with_handle(&net, &initial_state, &query, &handle));
+
+    let mut batch = Vec::new();
+    for text in ["P <> p3", "P <> p5", "P [] (p3 + p5 < 2)", "P <> [#<=2] p2"] {
+        let mut query = parse_query(String::from(text)).unwrap();
+        query.apply_to(&ctx).unwrap();
+        batch.push(query);
+    }
+    let mut methods = vec![ProbabilityEstimation::new(0.95, 0.05) ; batch.len()];
+    for (query, res) in batch.iter().zip(ProbabilityEstimation::verify_batch(&mut methods, &net, &initial_state, &batch)) {
+        println!("{:?} : {:?}", query.condition, res);
+    }
+
+    let mut estim  = ProbabilityEstimation::fixed_runs(1000, 0.95);
+    let mut monitors : Vec<Box<dyn RunMonitor>> = vec![Box::new(FiringMonitor::from_context(&ctx))];
+    coverage::start_coverage();
+    for transition in net.transitions.iter() {
+        coverage::register_coverage(&transition.label, &transition.compiled_guard);
+    }
+    coverage::register_coverage("query", &query.condition);
+    let (_, bundle) = estim.verify_monitored(&net, &initial_state, &query, &mut monitors);
+    println!("{}", coverage::stop_coverage());
+    for (column, mean) in bundle.means() {
+        println!("{} : {}", column, mean);
+    }
+    let failures = TraceClustering::new(TraceFeature::ActionSequence, 0.3).failures(&bundle.records);
+    log_clusters(&failures, &ctx);
+    if let Some(record) = failures.first().map(|c| &c.representative) {
+        let replayed : Vec<usize> = RandomRunIterator::generate(&net, &initial_state, query.run_bound.clone()).with_seed(record.seed)
+            .filter_map(|(_, _, action)| action.filter(|a| !a.is_epsilon()).map(|a| a.get_id()))
+            .take(record.actions.len()).collect();
+        positive(format!("Run {} replayed from seed {} : {}", record.run, record.seed, replayed == record.actions));
+    }
+    Statistics::from_records(&bundle.records, &ctx).log(8);
+    if let Some(record) = failures.first().map(|c| &c.representative) {
+        let mut debugger = RunDebugger::replay(&net, &ctx, &initial_state, record, query.run_bound.clone());
+        debugger.jump(debugger.len() - 1).unwrap();
+        debugger.log_frame();
+        debugger.jump(0).unwrap();
+        if let Ok(Some(frame)) = debugger.run_until("p4 > 0") {
+            println!("p4 marked at {}", frame);
+        }
+        println!("p1 + p4 = {:?}, deadlock : {:?}", debugger.evaluate("p1 + p4"), debugger.check("deadlock"));
+        let branch = debugger.branch(query.run_bound.clone());
+        println!("Branch from frame {} : {}", debugger.position(), branch.frames().iter().map(|f| f.to_string()).collect::<Vec<String>>().join(", "));
+    }
+
     let estim  = SMCMaxSeen::new(100000);
     let res = estim.estimate_max(&net, &ctx, &initial_state, VerificationBound::StepsRunBound(1000));
     println!("{:?}", res);
@@ -99,21 +255,158 @@ fn main() {
     let res = estim.parallel_estimate_max(&net, &ctx, &initial_state, VerificationBound::StepsRunBound(1000));
     println!("{:?}", res);
 
+    let tokens = parse_expression("p1 + p2 + p4").unwrap().apply_to(&ctx).unwrap();
+    let peak = ExpectedValueEstimation::maximum(10000, 0.95);
+    println!("E[max p1 + p2 + p4] : {}", peak.estimate(&net, &initial_state, &tokens, VerificationBound::StepsRunBound(1000)));
+    let goal = parse_condition("p3 | p5").unwrap().apply_to(&ctx).unwrap();
+    let at_goal = ExpectedValueEstimation::at_goal(10000, 0.95, goal);
+    println!("E[p1 + p2 + p4 at F (p3 | p5)] : {}", at_goal.estimate(&net, &initial_state, &tokens, VerificationBound::StepsRunBound(1000)));
+
     let json_net = serde_json::to_string(&net.get_structure()).unwrap();
     println!("{}", json_net);
     let new_net : PetriStructure = serde_json::from_str(&json_net).unwrap();
     let mut new_net = PetriNet::from(new_net);
     println!("{}", new_net.singleton());
 
+    let project_path = std::env::temp_dir().join("sally_project.json");
+    let mut project = ModelProject::from_state(net.get_structure(), &ctx, &initial_state).with_seed(2024);
+    project.define_predicate("def busy := p1 + p2 >= 1").unwrap();
+    project.define_predicate("def finished := (p3 | p5) & !busy").unwrap();
+    if let Err(e) = project.define_predicate("def busy := finished") {
+        warning(e.message);
+    }
+    export::write_file(&project_path, &project).unwrap();
+    let (loaded_net, loaded_ctx, loaded) : (PetriNet, _, ModelProject<PetriStructure>) = export::load_file(&project_path).unwrap();
+    println!("Loaded {} with marking {:?}", loaded_net, loaded.initial_marking.values);
+    println!("Same initial state : {}", loaded.initial_state.as_ref().map(|s| &s.discrete) == Some(&initial_state.discrete) && loaded_ctx.n_vars() == ctx.n_vars());
+    let mut finished = loaded.parse_query(&loaded_ctx, "E <> finished").unwrap();
+    finished.apply_to(&loaded_ctx).unwrap();
+    let finished_result = ClassGraphReachability::new().solve(cg, &loaded_ctx, &finished);
+    println!("E <> finished : {}", finished_result);
+    #[cfg(feature = "distributed")]
+    distributed_demo(&loaded);
+    let finished_report = VerificationReport::new(&finished, &finished_result);
+    let documentation = ModelDocumentation::of_project(&loaded, &loaded_net, &loaded_ctx)
+        .with_queries([(&finished, Some(&finished_report)), (&query, None)]);
+    println!("{}", documentation.to_markdown());
+    documentation.write(std::env::temp_dir().join("sally_project.html"), DocumentFormat::Html).unwrap();
+
     let json_q = serde_json::to_string(&query).unwrap();
     println!("{}", json_q);
 
     let q1 = parse_query(String::from("P <> [t <= 100] (P2 | deadlock) & P5 ^ 2 % 5")).unwrap();
     println!("-> {:#?}", q1);
+    println!("-> {} (parsed back : {})", q1, parse_query(q1.to_string()).is_ok_and(|q| q == q1));
     println!("-> {:#?}", serde_json::to_string(&q1).unwrap());
 
+    let mut network = ModelNetwork::new();
+    network.add_model(lbl("plant"), Box::new(sample_petri()));
+    network.add_model(lbl("operator"), Box::new(sample_markov()));
+    network.set_interface(lbl("operator"), vec![lbl("done")], vec![lbl("request"), lbl("cancel")]);
+    network.stub_component(&lbl("operator"), &HashMap::from([(lbl("request"), 3.0)]));
+    let network_ctx = network.singleton();
+    let mut network_state = network_ctx.make_initial_state(&network, HashMap::from([(lbl("plant.p0"), 1)]));
+    info(format!("Operator stubbed : {} stub(s)", network.stubs().count()));
+    let mut rng = SimulationRng::from_entropy();
+    for _ in 0..6 {
+        let (next, delay, action) = network.random_next(network_state.clone(), &mut rng);
+        let Some(next) = next else { break };
+        continue_info(format!("{} after {}", action.map(|a| a.to_string()).unwrap_or(String::from("_")), delay));
+        network_state = next;
+    }
+    lf();
+
+    let request = PetriTransition::new(lbl("req!"), vec![lbl("ready")], vec![lbl("waiting")], TimeInterval(Large(1), Large(2)));
+    let acknowledged = PetriTransition::new(lbl("ack?"), vec![lbl("waiting")], vec![lbl("ready")], TimeInterval(Large(0), Large(3)));
+    let mut channels = ModelNetwork::new();
+    channels.add_model(lbl("sender"), Box::new(PetriNet::new(vec![PetriPlace::new(lbl("ready")), PetriPlace::new(lbl("waiting"))], vec![request, acknowledged])));
+    channels.add_model(lbl("receiver"), Box::new(MDP::new(vec![
+        MDPState::new(lbl("idle"), HashMap::from([(lbl("req?"), vec![(lbl("busy"), 1.0)])])),
+        MDPState::new(lbl("busy"), HashMap::from([(lbl("ack!"), vec![(lbl("idle"), 1.0)])])),
+    ])));
+    let channels_ctx = channels.singleton();
+    let action_names : HashMap<usize, Label> = channels_ctx.get_actions().into_iter().map(|(l, a)| (a.get_id(), l)).collect();
+    let mut channels_state = channels_ctx.make_initial_state(&channels, HashMap::from([(lbl("sender.ready"), 1), (lbl("receiver.idle"), 1)]));
+    info("Sender and receiver synchronized on channels :");
+    let mut rng = SimulationRng::from_entropy();
+    for _ in 0..6 {
+        let (next, delay, action) = channels.random_next(channels_state.clone(), &mut rng);
+        let Some(next) = next else { break };
+        let described = match action {
+            Some(Action::Sync(channel, input, output)) => format!("{} ({} -> {})", action_names[&channel], action_names[&output.get_id()], action_names[&input.get_id()]),
+            Some(action) => action.to_string(),
+            None => String::from("_")
+        };
+        continue_info(format!("{} after {}", described, delay));
+        channels_state = next;
+    }
+    lf();
+
+    let alarm = PetriTransition::new(lbl("alarm!"), vec![lbl("armed")], vec![lbl("armed")], TimeInterval(Large(2), Large(4)));
+    let listener = || MDP::new(vec![
+        MDPState::new(lbl("quiet"), HashMap::from([(lbl("alarm?"), vec![(lbl("alerted"), 1.0)])])),
+        MDPState::new(lbl("alerted"), HashMap::from([(lbl("calm"), vec![(lbl("quiet"), 0.5), (lbl("alerted"), 0.5)])])),
+    ]);
+    let mut sensors = ModelNetwork::new();
+    sensors.add_model(lbl("sensor"), Box::new(PetriNet::new(vec![PetriPlace::new(lbl("armed"))], vec![alarm])));
+    sensors.add_model(lbl("left"), Box::new(listener()));
+    sensors.add_model(lbl("right"), Box::new(listener()));
+    sensors.add_broadcast_channel(lbl("alarm"));
+    let sensors_ctx = sensors.singleton();
+    let action_names : HashMap<usize, Label> = sensors_ctx.get_actions().into_iter().map(|(l, a)| (a.get_id(), l)).collect();
+    let mut sensors_state = sensors_ctx.make_initial_state(&sensors, HashMap::from([
+        (lbl("sensor.armed"), 1), (lbl("left.quiet"), 1), (lbl("right.quiet"), 1)
+    ]));
+    info("Sensor broadcasting to its listeners :");
+    let mut rng = SimulationRng::from_entropy();
+    for _ in 0..8 {
+        let (next, delay, action) = sensors.random_next(sensors_state.clone(), &mut rng);
+        let Some(next) = next else { break };
+        let described = match action {
+            Some(Action::Broadcast(channel, output, inputs)) => {
+                let receivers : Vec<String> = inputs.iter().map(|i| action_names[&i.get_id()].to_string()).collect();
+                format!("{} ({} -> [{}])", action_names[&channel], action_names[&output.get_id()], receivers.join(", "))
+            },
+            Some(action) => action_names.get(&action.get_id()).map(Label::to_string).unwrap_or(action.to_string()),
+            None => String::from("_")
+        };
+        continue_info(format!("{} after {}", described, delay));
+        sensors_state = next;
+    }
+    lf();
+
+    let process = |turn : i32| {
+        let mut enter = PetriTransition::new(lbl("enter"), vec![lbl("idle")], vec![lbl("crit")], TimeInterval(Large(0), Large(2)));
+        enter.guard = parse_condition(&format!("turn == {}", turn)).unwrap();
+        let leave = PetriTransition::new(lbl("leave"), vec![lbl("crit")], vec![lbl("idle")], TimeInterval(Large(1), Large(2)));
+        PetriNet::new(vec![PetriPlace::new(lbl("idle")), PetriPlace::new(lbl("crit"))], vec![enter, leave])
+    };
+    let mut shared = ModelNetwork::new();
+    shared.add_model(lbl("first"), Box::new(process(0)));
+    shared.add_model(lbl("second"), Box::new(process(1)));
+    shared.add_global(lbl("turn"), VarType::VarU8);
+    shared.set_update(lbl("first.leave"), Program::Update(var("turn"), parse_expression("1").unwrap()));
+    shared.set_update(lbl("second.leave"), Program::Update(var("turn"), parse_expression("0").unwrap()));
+    let shared_ctx = shared.singleton();
+    let action_names : HashMap<usize, Label> = shared_ctx.get_actions().into_iter().map(|(l, a)| (a.get_id(), l)).collect();
+    let turn = shared_ctx.get_var(&lbl("turn")).unwrap();
+    let mut shared_state = shared_ctx.make_initial_state(&shared, HashMap::from([
+        (lbl("first.idle"), 1), (lbl("second.idle"), 1), (lbl("turn"), 0)
+    ]));
+    info("Processes taking turns through a global variable :");
+    let mut rng = SimulationRng::from_entropy();
+    for _ in 0..6 {
+        let (next, delay, action) = shared.random_next(shared_state.clone(), &mut rng);
+        let Some(next) = next else { break };
+        let described = action.map(|a| action_names[&a.get_id()].to_string()).unwrap_or(String::from("_"));
+        continue_info(format!("{} after {}, turn = {}", described, delay, next.get_var(&turn)));
+        shared_state = next;
+    }
+    lf();
+
     let mut chain = sample_markov();
-    let markov_ctx = chain.singleton();
+    let mut markov_ctx = chain.singleton();
+    info(format!("Structure : {}", ModelStatistics::of(&chain)));
     let state = markov_ctx.make_initial_state(&chain, HashMap::from([
         (lbl("m1"), 1),
     ]));
@@ -122,8 +415,487 @@ fn main() {
     let mut estim  = ProbabilityEstimation::fixed_runs(100000, 0.95);
     let res = estim.parallel_verify(&chain, &state, &query);
     println!("{:?}", res);
+    for method in [IntervalMethod::Wilson, IntervalMethod::ClopperPearson] {
+        let mut estim = ProbabilityEstimation::fixed_runs(1000, 0.99).with_interval(method);
+        println!("{:?} : {}", method, estim.verify(&chain, &state, &query));
+    }
+    let mut estim  = ProbabilityEstimation::fixed_runs(100000, 0.95);
+    let maker = MarkovChainMaker::create_maker(chain.clone());
+    let res = estim.parallel_verify_with(&maker, 4, &state, &query);
+    println!("{:?}", res);
     println!("{:?}", serde_json::to_string(&chain));
 
+    let mut ladder = sample_ladder(200, 0.5);
+    let ladder_ctx = ladder.singleton();
+    let ladder_state = ladder_ctx.make_initial_state(&ladder, HashMap::from([(lbl("l0"), 1)]));
+    let mut climb = parse_query(String::from("P <> [# <= 10] l3")).unwrap();
+    climb.apply_to(&ladder_ctx).unwrap();
+    for sampling in [SamplingStrategy::Independent, SamplingStrategy::Antithetic, SamplingStrategy::Stratified(8)] {
+        let mut estim = ProbabilityEstimation::fixed_runs(4000, 0.95).with_sampling(sampling);
+        positive(format!("{} : {}", sampling, estim.verify(&ladder, &ladder_state, &climb)));
+    }
+    let sharded = ShardedExploration::new(4);
+    sharded.explore(&MarkovChainMaker::create_maker(ladder), &ladder_state, None).log();
+    let mut untimed_net = sample_petri().untimed();
+    let untimed_ctx = untimed_net.singleton();
+    let untimed_state = untimed_ctx.make_initial_state(&untimed_net, HashMap::from([(lbl("p0"), 1)]));
+    let mut query = parse_query(String::from("A G !(p3 & p5)")).unwrap();
+    query.apply_to(&untimed_ctx).unwrap();
+    positive(format!("{} : {}", query, sharded.verify(&PetriMaker::create_maker(untimed_net), &untimed_state, &query)));
+    let mut timed_net = sample_petri();
+    let timed_ctx = timed_net.singleton();
+    let timed_state = timed_ctx.make_initial_state(&timed_net, HashMap::from([(lbl("p0"), 1)]));
+    let mut query = parse_query(String::from("P <> [t <= 10] p5")).unwrap();
+    query.apply_to(&timed_ctx).unwrap();
+    let mut estim = ProbabilityEstimation::fixed_runs(20000, 0.95);
+    positive(format!("Sequential : {}", estim.verify(&timed_net, &timed_state, &query)));
+    let mut estim = ProbabilityEstimation::fixed_runs(20000, 0.95);
+    positive(format!("Lockstep : {}", estim.verify_lockstep(&timed_net, &timed_state, &query, 256)));
+
+    let mut query = parse_query(String::from("P>=0.9 <> [# <= 10] m3")).unwrap();
+    query.apply_to(&markov_ctx).unwrap();
+    let mut sprt = ProbabilityFloatComparison::for_query(&query, 0.05, 0.05, 0.01).unwrap();
+    let res = sprt.verify(&chain, &state, &query);
+    println!("{:?}", res);
+    let mut spinner = sample_spinner(0.3);
+    let spinner_ctx = spinner.singleton();
+    let spinner_state = spinner_ctx.make_initial_state(&spinner, HashMap::from([(lbl("gate"), 1)]));
+    let mut cache = RunCache::new();
+    let mut query = parse_query(String::from("P <> [# <= 200] goal")).unwrap();
+    query.apply_to(&spinner_ctx).unwrap();
+    let mut estim = ProbabilityEstimation::fixed_runs(4000, 0.95);
+    positive(format!("Simulated : {}", estim.verify(&spinner, &spinner_state, &query)));
+    let mut estim = ProbabilityEstimation::fixed_runs(4000, 0.95);
+    positive(format!("Cached : {}", estim.verify_cached(&spinner, &spinner_state, &query, &mut cache)));
+    let mut query = parse_query(String::from("P <> goal")).unwrap();
+    query.apply_to(&spinner_ctx).unwrap();
+    let mut estim = ProbabilityEstimation::fixed_runs(4000, 0.95);
+    positive(format!("Unbounded : {}", estim.verify_cached(&spinner, &spinner_state, &query, &mut cache)));
+
+    let mut query = parse_query(String::from("P>=0.5 ( F [# <= 10] (P>=0.8 ( F [# <= 5] m3 )) )")).unwrap();
+    query.apply_to(&markov_ctx).unwrap();
+    let mut sprt = ProbabilityFloatComparison::for_query(&query, 0.05, 0.05, 0.01).unwrap();
+    let mut resolver = SMCNestedResolver::new(&chain, 0.05, 0.05, 0.01);
+    let res = sprt.verify_nested(&chain, &state, &query, &mut resolver);
+    println!("{:?}", res);
+
+    markov_ctx.add_reward(RewardStructure::new(lbl("occupancy"))
+        .with_state_reward(Condition::Evaluation(Expr::Var(var("m3"))), 1.0)).unwrap();
+    markov_ctx.add_reward(RewardStructure::new(lbl("steps"))
+        .with_state_reward(Condition::True, 1.0)).unwrap();
+    let mut reward_solution = MarkovExpectedReward::new();
+    for text in ["E{occupancy}[C <= 10]", "E{steps}[F (m2 | m3)]"] {
+        let mut query = parse_query(String::from(text)).unwrap();
+        query.apply_to(&markov_ctx).unwrap();
+        let estim = ExpectedRewardEstimation::fixed_runs(10000, 0.95);
+        println!("{} : {:?}", text, estim.estimate(&chain, &markov_ctx, &state, &query));
+        println!("{} : {:?}", text, reward_solution.solve(&chain, &markov_ctx, &query));
+    }
+    let mut routed_reward = parse_query(String::from("E{occupancy}[C <= 10]")).unwrap();
+    routed_reward.apply_to(&markov_ctx).unwrap();
+    let (result, provenance) = solver.solve(&chain, &lbl("MarkovChain"), &markov_ctx, &state, &routed_reward);
+    provenance.log();
+    positive(format!("Routed E{{occupancy}}[C <= 10] : {}", result));
+    for text in ["P <> [#<=10, {occupancy}<=2.0] m2", "P <> [t<=10, #<=3] m3"] {
+        let mut query = parse_query(String::from(text)).unwrap();
+        query.apply_to(&markov_ctx).unwrap();
+        let mut estim = ProbabilityEstimation::fixed_runs(10000, 0.95);
+        println!("{} : {}", query, estim.verify(&chain, &state, &query));
+    }
+    // Failures cost 5 each, every step spent down costs 2
+    let mut machine = MarkovChain::new(vec![
+        MarkovNode::probabilistic(lbl("up"), vec![(lbl("up"), 0.9), (lbl("down"), 0.1)]).with_edge_reward(lbl("down"), 5.0),
+        MarkovNode::probabilistic(lbl("down"), vec![(lbl("up"), 0.5), (lbl("down"), 0.5)]).with_reward(2.0),
+    ]);
+    let machine_ctx = machine.singleton();
+    let machine_state = machine_ctx.make_initial_state(&machine, HashMap::from([(lbl("up"), 1)]));
+    let mut machine_query = parse_query(String::from("E{rewards}[C <= 20]")).unwrap();
+    machine_query.apply_to(&machine_ctx).unwrap();
+    info("Machine costs carried by the chain, E[C <= 20] :");
+    continue_info(format!("Analytic : {}", MarkovExpectedReward::from_node(lbl("up")).solve(&machine, &machine_ctx, &machine_query)));
+    continue_info(format!("Simulated : {:?}", ExpectedRewardEstimation::fixed_runs(10000, 0.95).estimate(&machine, &machine_ctx, &machine_state, &machine_query)));
+
+    let mut steady_query = parse_query(String::from("S>=0.5 [m2 | m3]")).unwrap();
+    steady_query.apply_to(&markov_ctx).unwrap();
+    println!("S>=0.5 [m2 | m3] : {}", MarkovSteadyState::new().solve(&chain, &markov_ctx, &steady_query));
+    let batch_means = SteadyStateEstimation::new(100000.0, 0.95);
+    println!("S [m2 | m3] ~ {}", batch_means.estimate(&chain, &state, &steady_query));
+    println!("S>=0.5 [m2 | m3] : {}", batch_means.verify(&chain, &state, &steady_query));
+    let mut repairable = MarkovAutomaton::new(vec![
+        MAState::markovian(lbl("up"), vec![(lbl("down"), 1.0)]),
+        MAState::markovian(lbl("down"), vec![(lbl("up"), 3.0)]),
+    ]);
+    let repairable_ctx = repairable.singleton();
+    let repairable_state = repairable_ctx.make_initial_state(&repairable, HashMap::from([(lbl("up"), 1)]));
+    let mut availability = parse_query(String::from("S>=0.7 [up]")).unwrap();
+    availability.apply_to(&repairable_ctx).unwrap();
+    println!("{} (exact 0.75) : {}", availability, batch_means.verify(&repairable, &repairable_state, &availability));
+    let mut quantile_query = parse_query(String::from("Q>=0.15 [F [#<=100] m3]")).unwrap();
+    quantile_query.apply_to(&markov_ctx).unwrap();
+    println!("Q>=0.15 [F m3] : {:?}", QuantileEstimation::new(0.95, 0.05).estimate(&chain, &state, &quantile_query));
+    println!("Q>=0.15 [F m3] : {}", MarkovQuantile::new().solve(&chain, &markov_ctx, &quantile_query));
+    let mut timed_query = parse_query(String::from("Q>=0.15 [F [t<=100] m3]")).unwrap();
+    timed_query.apply_to(&markov_ctx).unwrap();
+    let (result, provenance) = solver.solve(&chain, &MarkovChain::get_meta().name, &markov_ctx, &state, &timed_query);
+    provenance.log();
+    positive(format!("Q>=0.15 [F [t<=100] m3] : {}", result));
+    let mut strict_solver = build_solver().with_strictness(true);
+    let (result, _) = strict_solver.solve(&chain, &MarkovChain::get_meta().name, &markov_ctx, &state, &timed_query);
+    warning(format!("Strict solver : {}", result));
+    let mut unbounded_query = parse_query(String::from("P <> m3")).unwrap();
+    unbounded_query.apply_to(&markov_ctx).unwrap();
+    if let Some((bounded_query, inferred)) = unbounded_query.with_inferred_bound(&chain, &state) {
+        info(format!("Inferred run bound : {}", inferred));
+        let mut estim = ProbabilityEstimation::with_precision(0.05, 0.05, Some(0.1));
+        let result = estim.verify(&chain, &state, &bounded_query);
+        VerificationReport::new(&bounded_query, &result)
+            .with_query_text("P <> m3")
+            .with_guarantee(estim.guarantee.as_ref().unwrap())
+            .log();
+    }
+    let (_, provenance) = solver.solve(&chain, &MarkovChain::get_meta().name, &markov_ctx, &state, &unbounded_query);
+    provenance.log();
+    let mut threshold_query = parse_query(String::from("P>=0.1 <> m3")).unwrap();
+    threshold_query.apply_to(&markov_ctx).unwrap();
+    let (result, provenance) = solver.solve(&chain, &MarkovChain::get_meta().name, &markov_ctx, &state, &threshold_query);
+    provenance.log();
+    positive(format!("P>=0.1 <> m3 : {}", result));
+    let mut conditional_query = parse_query(String::from("P(F [#<=10] m3 | G !m2)")).unwrap();
+    conditional_query.apply_to(&markov_ctx).unwrap();
+    println!("P(F m3 | G !m2) : {:?}", ConditionalEstimation::new(0.95, 0.05).estimate(&chain, &state, &conditional_query));
+
+    let mut ladder = sample_ladder(6, 0.2);
+    let ladder_ctx = ladder.singleton();
+    let ladder_state = ladder_ctx.make_initial_state(&ladder, HashMap::from([(lbl("l0"), 1)]));
+    let mut rare_query = parse_query(String::from("P <> [# <= 20] l6")).unwrap();
+    rare_query.apply_to(&ladder_ctx).unwrap();
+    let vars : Vec<_> = ladder.get_vars().cloned().collect();
+    let splitting = ImportanceSplitting::new(Box::new(move |s| s.argmax(vars.iter()) as f64), (2..7).map(|l| l as f64).collect(), 2000, 0.95);
+    println!("P <> l6 (exact {}) : {}", 0.2f64.powi(6), splitting.estimate(&ladder, &ladder_state, &rare_query));
+    let mut sampling = ImportanceSampling::new(10000, 0.95).with_cross_entropy(3, 1000);
+    println!("P <> l6 (exact {}) : {}", 0.2f64.powi(6), sampling.estimate(&ladder, &ladder_state, &rare_query));
+
+    let word = WeightedWord::new().with(lbl("t0"), 1.0, 2.0).with(lbl("t1"), 0.5, 1.0);
+    let retimed = WeightedWord::new().with(lbl("t0"), 1.5, 2.0).with(lbl("t1"), 0.5, 3.0);
+    let other = WeightedWord::new().with(lbl("t0"), 1.0, 2.0).with(lbl("t2"), 0.5, 1.0).with(lbl("t1"), 0.0, 0.0);
+    info(format!("Words : {} / {} / {}", word, retimed, other));
+    continue_info(format!("Timed distance : {}", word.timed_distance(&retimed, 1.0)));
+    continue_info(format!("Edit distance : {}", word.edit_distance(&other)));
+
+    let tapn = TAPN { id : 0, storage_index : 0, places : vec![Arc::new(TAPNPlace::new(lbl("waiting")))], transitions : Vec::new() };
+    let tapn = tapn.into_compiled(&mut ModelContext::new()).unwrap();
+    let tapn_ctx = tapn.context();
+    let waiting = tapn_ctx.make_initial_state(&tapn, HashMap::from([(lbl("waiting"), 2)]));
+    let waiting = tapn.delay(waiting, ClockValue::from(3.0)).unwrap();
+    info("Token ages after a delay of 3 :");
+    for text in ["oldest(waiting) >= 3", "aged(waiting, 0, 2) = 0"] {
+        let mut age_query = parse_query(String::from(text)).unwrap();
+        age_query.apply_to(tapn_ctx).unwrap();
+        continue_info(format!("{} : {}", text, age_query.condition.is_true(&waiting)));
+    }
+
+    let produce = PetriTransition::new(lbl("produce"), vec![], vec![lbl("buffer")], TimeInterval(Large(1), Large(2)))
+        .with_inhibitor(lbl("buffer"), 3);
+    let consume = PetriTransition::new(lbl("consume"), vec![lbl("buffer")], vec![], TimeInterval(Large(2), Large(4)));
+    let mut bounded_net = PetriNet::new(vec![PetriPlace::new(lbl("buffer"))], vec![produce, consume]);
+    let bounded_ctx = bounded_net.singleton();
+    let bounded_state = bounded_ctx.make_initial_state(&bounded_net, HashMap::new());
+    let bounded_cg = ClassGraph::compute(&bounded_net, &bounded_state);
+    info(format!("Inhibited producer : {} classes", bounded_cg.classes.len()));
+    for text in ["A [] buffer <= 3", "E <> buffer = 3"] {
+        let mut bounded_query = parse_query(String::from(text)).unwrap();
+        bounded_query.apply_to(&bounded_ctx).unwrap();
+        continue_info(format!("{} : {}", text, ClassGraphReachability::new().solve(&bounded_cg, &bounded_ctx, &bounded_query)));
+    }
+
+    let blink = PetriTransition::new(lbl("blink"), vec![lbl("flag")], vec![lbl("flag")], TimeInterval(Large(1), Large(1)));
+    let tick = PetriTransition::new(lbl("tick"), vec![lbl("ready")], vec![lbl("done")], TimeInterval(Large(3), Large(3)))
+        .with_read(lbl("flag"), 1);
+    let looped = PetriTransition::new(lbl("tick"), vec![lbl("ready"), lbl("flag")], vec![lbl("done"), lbl("flag")], TimeInterval(Large(3), Large(3)));
+    let places = || vec![PetriPlace::new(lbl("flag")), PetriPlace::new(lbl("ready")), PetriPlace::new(lbl("done"))];
+    info("Read arc against self-loop, E <> done :");
+    for (name, tick) in [("read arc", tick), ("self-loop", looped)] {
+        let mut read_net = PetriNet::new(places(), vec![blink.clone(), tick]);
+        let read_ctx = read_net.singleton();
+        let read_state = read_ctx.make_initial_state(&read_net, HashMap::from([(lbl("flag"), 1), (lbl("ready"), 1)]));
+        let read_cg = ClassGraph::compute(&read_net, &read_state);
+        let mut read_query = parse_query(String::from("E <> done")).unwrap();
+        read_query.apply_to(&read_ctx).unwrap();
+        continue_info(format!("{} : {}", name, ClassGraphReachability::new().solve(&read_cg, &read_ctx, &read_query)));
+    }
+
+    let arrive = PetriTransition::new(lbl("arrive"), vec![], vec![lbl("queue")], TimeInterval(Large(1), Large(1)))
+        .with_read(lbl("up"), 1)
+        .with_inhibitor(lbl("queue"), 3);
+    let crash = PetriTransition::new(lbl("crash"), vec![lbl("up")], vec![lbl("down")], TimeInterval(Large(2), Large(5)))
+        .with_flush(lbl("queue"));
+    let mut flushed_net = PetriNet::new(vec![PetriPlace::new(lbl("up")), PetriPlace::new(lbl("queue")), PetriPlace::new(lbl("down"))], vec![arrive, crash]);
+    let flushed_ctx = flushed_net.singleton();
+    let flushed_state = flushed_ctx.make_initial_state(&flushed_net, HashMap::from([(lbl("up"), 1)]));
+    let flushed_cg = ClassGraph::compute(&flushed_net, &flushed_state);
+    info(format!("Queue flushed on crash : {} classes", flushed_cg.classes.len()));
+    for text in ["E <> queue = 3", "E <> down & queue > 0"] {
+        let mut flushed_query = parse_query(String::from(text)).unwrap();
+        flushed_query.apply_to(&flushed_ctx).unwrap();
+        continue_info(format!("{} : {}", text, ClassGraphReachability::new().solve(&flushed_cg, &flushed_ctx, &flushed_query)));
+    }
+
+    let fill = PetriTransition::new(lbl("fill"), vec![], vec![lbl("slots")], TimeInterval(Large(1), Large(1)));
+    let drain = PetriTransition::new(lbl("drain"), vec![lbl("slots")], vec![], TimeInterval(Large(3), Large(3)));
+    let buffer_net = PetriNet::new(vec![PetriPlace::new(lbl("slots")).with_capacity(2)], vec![fill, drain]);
+    let buffer_marking = HashMap::from([(lbl("slots"), 1)]);
+    info("Bounded buffer, A [] slots <= 2 :");
+    let complemented = (buffer_net.with_complementary_places().unwrap(), buffer_net.complementary_marking(&buffer_marking).unwrap());
+    for (name, (mut buffer, marking)) in [("capacity", (buffer_net.clone(), buffer_marking)), ("complementary places", complemented)] {
+        let buffer_ctx = buffer.singleton();
+        let buffer_state = buffer_ctx.make_initial_state(&buffer, marking);
+        let buffer_cg = ClassGraph::compute(&buffer, &buffer_state);
+        let mut buffer_query = parse_query(String::from("A [] slots <= 2")).unwrap();
+        buffer_query.apply_to(&buffer_ctx).unwrap();
+        continue_info(format!("{} ({} classes) : {}", name, buffer_cg.classes.len(), ClassGraphReachability::new().solve(&buffer_cg, &buffer_ctx, &buffer_query)));
+    }
+
+    info("Immediate transition of higher priority, E <> late :");
+    for priority in [0, 1] {
+        let urgent = PetriTransition::new(lbl("urgent"), vec![lbl("decide")], vec![lbl("fast")], TimeInterval(Large(0), Large(0)))
+            .with_priority(priority);
+        let slow = PetriTransition::new(lbl("slow"), vec![lbl("decide")], vec![lbl("late")], TimeInterval(Large(0), Large(2)));
+        let mut priority_net = PetriNet::new(vec![PetriPlace::new(lbl("decide")), PetriPlace::new(lbl("fast")), PetriPlace::new(lbl("late"))], vec![urgent, slow]);
+        let priority_ctx = priority_net.singleton();
+        let priority_state = priority_ctx.make_initial_state(&priority_net, HashMap::from([(lbl("decide"), 1)]));
+        let priority_cg = ClassGraph::compute(&priority_net, &priority_state);
+        let mut priority_query = parse_query(String::from("E <> late")).unwrap();
+        priority_query.apply_to(&priority_ctx).unwrap();
+        let result = ClassGraphReachability::new().solve(&priority_cg, &priority_ctx, &priority_query);
+        continue_info(format!("priority {} : {}, fireable : {}", priority, result, priority_net.available_actions(&priority_state).len()));
+    }
+
+    let arrival = PetriTransition::new_untimed(lbl("arrival"), vec![], vec![lbl("incoming")]).with_rate(2.0);
+    let fast_lane = PetriTransition::new_untimed(lbl("fast_lane"), vec![lbl("incoming")], vec![lbl("queue")]).with_weight(3.0);
+    let drop_job = PetriTransition::new_untimed(lbl("drop_job"), vec![lbl("incoming")], vec![]).with_weight(1.0);
+    let service = PetriTransition::new_untimed(lbl("service"), vec![lbl("queue")], vec![]).with_rate(3.0);
+    let mut gspn = PetriNet::new(
+        vec![PetriPlace::new(lbl("incoming")), PetriPlace::new(lbl("queue")).with_capacity(3)],
+        vec![arrival, fast_lane, drop_job, service]
+    );
+    let gspn_ctx = gspn.singleton();
+    let gspn_state = gspn_ctx.make_initial_state(&gspn, HashMap::new());
+    let mut full_query = parse_query(String::from("S<=0.1 [queue = 3]")).unwrap();
+    full_query.apply_to(&gspn_ctx).unwrap();
+    info(format!("GSPN queue, S [queue = 3] (exact {:.4}) :", 1.0 / 15.0));
+    println!("{}", SteadyStateEstimation::new(20000.0, 0.95).estimate(&gspn, &gspn_state, &full_query));
+    let mut routed_query = parse_query(String::from("P <> [# <= 3] queue = 1")).unwrap();
+    routed_query.apply_to(&gspn_ctx).unwrap();
+    let mut routed = ProbabilityEstimation::new(0.95, 0.02);
+    println!("P <> [# <= 3] queue = 1 (exact 0.75) : {}", routed.verify(&gspn, &gspn_state, &routed_query));
+
+    let gamble = MDPState::new(lbl("start"), HashMap::from([
+        (lbl("safe"), vec![(lbl("goal"), 0.5), (lbl("start"), 0.5)]),
+        (lbl("risky"), vec![(lbl("goal"), 0.9), (lbl("fail"), 0.1)]),
+    ]));
+    let mdp_states = vec![gamble, MDPState::absorbing(lbl("goal")), MDPState::absorbing(lbl("fail"))];
+    info("MDP, P <> [# <= 10] goal under each scheduler :");
+    let schedulers : Vec<(&str, Arc<dyn Scheduler>)> = vec![
+        ("uniform", Arc::new(UniformScheduler)),
+        ("always safe", Arc::new(MemorylessScheduler::new(HashMap::from([(lbl("start"), lbl("safe"))])))),
+        ("always risky", Arc::new(MemorylessScheduler::new(HashMap::from([(lbl("start"), lbl("risky"))])))),
+    ];
+    for (name, scheduler) in schedulers {
+        let mut mdp = MDP::new(mdp_states.clone());
+        mdp.scheduler = scheduler;
+        let mdp_ctx = mdp.singleton();
+        let mdp_state = mdp_ctx.make_initial_state(&mdp, HashMap::from([(lbl("start"), 1)]));
+        let mut mdp_query = parse_query(String::from("P <> [# <= 10] goal")).unwrap();
+        mdp_query.apply_to(&mdp_ctx).unwrap();
+        let result = ProbabilityEstimation::new(0.95, 0.02).verify(&mdp, &mdp_state, &mdp_query);
+        continue_info(format!("{} : {}", name, result));
+    }
+
+    let processes = ColorType::Range(1, 3);
+    let x = ColorTerm::var("x");
+    let enter = ColoredTransition::new_untimed(lbl("enter"))
+        .with_input(lbl("idle"), ArcExpression::of(x.clone()))
+        .with_input(lbl("token"), ArcExpression::of(x.clone()))
+        .with_output(lbl("critical"), ArcExpression::of(x.clone()))
+        .with_guard(ColorGuard::Ne(x.clone(), ColorTerm::Constant(Color::Int(2))));
+    let leave = ColoredTransition::new_untimed(lbl("leave"))
+        .with_input(lbl("critical"), ArcExpression::of(x.clone()))
+        .with_output(lbl("idle"), ArcExpression::of(x.clone()))
+        .with_output(lbl("token"), ArcExpression::of(x.clone().successor()));
+    let pass = ColoredTransition::new_untimed(lbl("pass"))
+        .with_input(lbl("idle"), ArcExpression::of(x.clone()))
+        .with_input(lbl("token"), ArcExpression::of(x.clone()))
+        .with_output(lbl("idle"), ArcExpression::of(x.clone()))
+        .with_output(lbl("token"), ArcExpression::of(x.successor()));
+    let mut ring = ColoredPetriNet::new(
+        vec![(lbl("x"), processes.clone())],
+        vec![ColoredPlace::new(lbl("idle"), processes.clone()), ColoredPlace::new(lbl("critical"), processes.clone()), ColoredPlace::new(lbl("token"), processes)],
+        vec![enter, leave, pass]
+    );
+    let ring_ctx = ring.singleton();
+    let ring_marking = HashMap::from([
+        (lbl("idle"), vec![(Color::Int(1), 1), (Color::Int(2), 1), (Color::Int(3), 1)]),
+        (lbl("token"), vec![(Color::Int(1), 1)]),
+    ]);
+    let ring_state = ring_ctx.make_initial_state(&ring, ring.unfold_marking(&ring_marking));
+    let mut unfolding = ColoredUnfolding::new();
+    unfolding.translate(&ring, &ring_ctx, &ring_state).unwrap();
+    let (unfolded, unfolded_ctx, unfolded_state) = unfolding.get_translated();
+    info(format!("Colored token ring, unfolded into {} transitions :", ring.unfolded.as_ref().unwrap().transitions.len()));
+    for text in ["A [] critical.1 + critical.2 + critical.3 <= 1", "E <> critical.2", "E <> critical.3"] {
+        let mut ring_query = parse_query(String::from(text)).unwrap();
+        ring_query.apply_to(unfolded_ctx).unwrap();
+        let (result, _) = solver.solve(unfolded, &lbl("TPN"), unfolded_ctx, unfolded_state, &ring_query);
+        continue_info(format!("{} : {}", text, result));
+    }
+
+    let clients = ModelTemplate::new(|values : &ParameterValuation| {
+        let mut request = PetriTransition::new(lbl("request"), vec![lbl("idle")], vec![lbl("buffer")], TimeInterval(Large(1), Large(2)));
+        request.guard = substitute_parameters(&parse_condition("buffer < K").unwrap(), values);
+        let serve = PetriTransition::new(lbl("serve"), vec![lbl("buffer")], vec![lbl("idle")], TimeInterval(Large(2), Large(3)));
+        let structure = PetriNet::new(vec![PetriPlace::new(lbl("idle")), PetriPlace::new(lbl("buffer"))], vec![request, serve]).get_structure();
+        ModelProject::new(structure, HashMap::from([(lbl("idle"), values[&lbl("N")])]).into())
+    }).with_parameter(lbl("N"), 2).with_parameter(lbl("K"), 1);
+    info("Clients template, E <> buffer = 2 :");
+    for mut instance in clients.sweep(&HashMap::from([(lbl("N"), MarkingValue::Range(1, 3)), (lbl("K"), MarkingValue::Set(vec![1, 2]))])).unwrap() {
+        let (instance_net, instance_ctx) = instance.make::<PetriNet>();
+        let instance_cg = ClassGraph::compute(&instance_net, instance.initial_state.as_ref().unwrap());
+        let mut instance_query = instance.parse_query(&instance_ctx, "E <> buffer = 2").unwrap();
+        instance_query.apply_to(&instance_ctx).unwrap();
+        let result = ClassGraphReachability::new().solve(&instance_cg, &instance_ctx, &instance_query);
+        continue_info(format!("N = {}, K = {} ({} classes) : {}", instance.parameters[&lbl("N")], instance.parameters[&lbl("K")], instance_cg.classes.len(), result));
+    }
+
+    let mut thermostat = HybridAutomaton::new(
+        vec![(lbl("temp"), 20.0)],
+        vec![
+            HybridLocation::new(lbl("heating")).with_rate(lbl("temp"), 2.0).with_invariant(LinearConstraint::var_le(lbl("temp"), 22.0)),
+            HybridLocation::new(lbl("cooling")).with_rate(lbl("temp"), -1.0).with_invariant(LinearConstraint::var_ge(lbl("temp"), 18.0)),
+        ],
+        vec![
+            HybridEdge::new(lbl("off"), lbl("heating"), lbl("cooling")).with_guard(LinearConstraint::var_ge(lbl("temp"), 21.0)),
+            HybridEdge::new(lbl("on"), lbl("cooling"), lbl("heating")).with_guard(LinearConstraint::var_le(lbl("temp"), 19.0)),
+        ]
+    );
+    let thermostat_ctx = thermostat.singleton();
+    let temp = thermostat_ctx.get_clock(&lbl("temp")).unwrap();
+    let mut thermostat_state = thermostat_ctx.make_initial_state(&thermostat, HashMap::from([(lbl("heating"), 1)]));
+    info("Thermostat with constant heating and cooling rates :");
+    let mut rng = SimulationRng::from_entropy();
+    for _ in 0..6 {
+        let (next, delay, action) = thermostat.random_next(thermostat_state.clone(), &mut rng);
+        let Some(next) = next else { break };
+        let edge = action.and_then(|a| thermostat.get_edge(&a)).map(|e| e.label.to_string()).unwrap_or(String::from("_"));
+        continue_info(format!("{} after {:.3}, temp = {:.3}", edge, delay.float(), next.get_clock_value(&temp).float()));
+        thermostat_state = next;
+    }
+
+    // Dispatcher moving the whole queue at once, one more token going to the log for each batch
+    let arrive = PetriTransition::new(lbl("arrive"), vec![], vec![lbl("queue")], TimeInterval(Large(1), Large(1)))
+        .with_inhibitor(lbl("queue"), 3);
+    let mut dispatch = PetriTransition::new(lbl("dispatch"), vec![], vec![], TimeInterval(Large(2), Large(2)))
+        .with_input_weight(lbl("queue"), parse_expression("queue").unwrap())
+        .with_output_weight(lbl("served"), parse_expression("queue").unwrap())
+        .with_output_weight(lbl("log"), parse_expression("queue + 1").unwrap());
+    dispatch.guard = parse_condition("queue > 0").unwrap();
+    let mut balancing_net = PetriNet::new(vec![PetriPlace::new(lbl("queue")), PetriPlace::new(lbl("served")).with_capacity(6), PetriPlace::new(lbl("log"))], vec![arrive, dispatch]);
+    let balancing_ctx = balancing_net.singleton();
+    let balancing_state = balancing_ctx.make_initial_state(&balancing_net, HashMap::new());
+    let balancing_cg = ClassGraph::compute(&balancing_net, &balancing_state);
+    info(format!("Marking-dependent dispatch : {} classes", balancing_cg.classes.len()));
+    for text in ["E <> served = 6", "E <> log = 8", "E <> served = 4"] {
+        let mut balancing_query = parse_query(String::from(text)).unwrap();
+        balancing_query.apply_to(&balancing_ctx).unwrap();
+        continue_info(format!("{} : {}", text, ClassGraphReachability::new().solve(&balancing_cg, &balancing_ctx, &balancing_query)));
+    }
+
+    // Two machining cells sharing one subnet, the output of the first one feeding the second
+    let cell = PetriStructure {
+        places : vec![PetriPlace::new(lbl("in")), PetriPlace::new(lbl("busy")), PetriPlace::new(lbl("out"))],
+        transitions : vec![
+            PetriTransition::new(lbl("start"), vec![lbl("in")], vec![lbl("busy")], TimeInterval(Large(1), Large(2))),
+            PetriTransition::new(lbl("finish"), vec![lbl("busy")], vec![lbl("out")], TimeInterval(Large(2), Large(3))),
+        ],
+        race_policy : Default::default(),
+        substitutions : Vec::new(),
+    };
+    let mut line = PetriNet::new(vec![PetriPlace::new(lbl("raw")), PetriPlace::new(lbl("half")), PetriPlace::new(lbl("done"))], vec![])
+        .with_substitution(SubstitutionTransition::new(lbl("cell1"), cell.clone())
+            .with_binding(lbl("in"), lbl("raw")).with_binding(lbl("out"), lbl("half")))
+        .with_substitution(SubstitutionTransition::new(lbl("cell2"), cell)
+            .with_binding(lbl("in"), lbl("half")).with_binding(lbl("out"), lbl("done")));
+    let line_ctx = line.singleton();
+    let line_state = line_ctx.make_initial_state(&line, HashMap::from([(lbl("raw"), 2)]));
+    let line_cg = ClassGraph::compute(&line, &line_state);
+    let line_places : Vec<String> = line.places.iter().map(|p| p.name.to_string()).collect();
+    info(format!("Flattened production line : [{}], {} classes", line_places.join(", "), line_cg.classes.len()));
+    for text in ["E <> done = 2", "E <> cell1.busy = 1 & cell2.busy = 1"] {
+        let mut line_query = parse_query(String::from(text)).unwrap();
+        line_query.apply_to(&line_ctx).unwrap();
+        continue_info(format!("{} : {}", text, ClassGraphReachability::new().solve(&line_cg, &line_ctx, &line_query)));
+    }
+
+    // Machine wearing out unnoticed, only its breakdown raising an alarm
+    let wear = MDP::new(vec![
+        MDPState::new(lbl("good"), HashMap::from([
+            (lbl("run"), vec![(lbl("good"), 0.8), (lbl("worn"), 0.2)]),
+            (lbl("repair"), vec![(lbl("good"), 1.0)]),
+        ])),
+        MDPState::new(lbl("worn"), HashMap::from([
+            (lbl("run"), vec![(lbl("worn"), 0.7), (lbl("broken"), 0.3)]),
+            (lbl("repair"), vec![(lbl("good"), 1.0)]),
+        ])),
+        MDPState::new(lbl("broken"), HashMap::from([(lbl("repair"), vec![(lbl("good"), 1.0)])])),
+    ]);
+    let sensor = ObservationFunction {
+        vars : HashMap::from([(lbl("good"), lbl("ok")), (lbl("worn"), lbl("ok")), (lbl("broken"), lbl("alarm"))]),
+        ..Default::default()
+    };
+    let mut pomdp = POMDP::new(wear, sensor)
+        .with_initial_belief(vec![(lbl("good"), 1.0)])
+        .with_scheduler(MemorylessScheduler::new(HashMap::from([(lbl("ok"), lbl("run")), (lbl("alarm"), lbl("repair"))])));
+    let pomdp_ctx = pomdp.singleton();
+    let mut pomdp_state = pomdp_ctx.make_initial_state(&pomdp, HashMap::from([(lbl("good"), 1)]));
+    let pomdp_beliefs = BeliefsGraph::compute(&pomdp, &pomdp_state, 20);
+    info(format!("Hidden wear : {} beliefs explored, complete : {}", pomdp_beliefs.beliefs.len(), pomdp_beliefs.complete));
+    let run_action = pomdp_ctx.get_action(&lbl("run")).unwrap();
+    for step in 1..=3 {
+        let Some((next, _)) = pomdp.next(pomdp_state.clone(), run_action.clone()) else {
+            break;
+        };
+        pomdp_state = next;
+        continue_info(format!("run {} : observed {}, P(worn) = {:.4}", step, pomdp.observe(&pomdp_state), pomdp.belief_of(&pomdp_state, &lbl("worn"))));
+    }
+    let pomdp_state = pomdp_ctx.make_initial_state(&pomdp, HashMap::from([(lbl("good"), 1)]));
+    let mut pomdp_query = parse_query(String::from("P <> [# <= 10] broken")).unwrap();
+    pomdp_query.apply_to(&pomdp_ctx).unwrap();
+    continue_info(format!("P <> [# <= 10] broken : {}", ProbabilityEstimation::new(0.95, 0.02).verify(&pomdp, &pomdp_state, &pomdp_query)));
+
+    // Protocol where every request is acknowledged, against a specification tolerating retries
+    let protocol = RegularExpression::parse("(req ack)*").unwrap().to_dfa();
+    let specification = RegularExpression::parse("(req+ ack)*").unwrap().to_dfa();
+    let faulty = RegularExpression::parse("(req ack | ack)*").unwrap().to_dfa();
+    info(format!("Regular protocols : {} and {} states once minimized", protocol.n_states(), specification.n_states()));
+    continue_info(format!("(req ack)* within (req+ ack)* : {}", protocol.is_included_in(&specification)));
+    let counterexample = faulty.inclusion_counterexample(&specification).unwrap_or_default();
+    let counterexample : Vec<String> = counterexample.iter().map(Label::to_string).collect();
+    continue_info(format!("(req ack | ack)* counterexample : [{}]", counterexample.join(", ")));
+    let retried = WeightedWord::new().with(lbl("req"), 1.0, 0.0).with(lbl("req"), 2.0, 0.0).with(lbl("ack"), 0.5, 0.0);
+    continue_info(format!("Accepts {} : {}", retried, specification.accepts_word(&retried)));
+
+    let mut untimed_net = net.untimed();
+    let untimed_ctx = untimed_net.singleton();
+    let untimed_state = untimed_ctx.make_initial_state(&untimed_net, HashMap::from([(lbl("p0"), 1)]));
+    info(format!("Untimed sample net, 1-safe : {}", untimed_net.is_one_safe(&untimed_state)));
+    for text in ["E <> p5", "A [] p3 + p5 <= 1"] {
+        let mut safe_query = parse_query(String::from(text)).unwrap();
+        safe_query.apply_to(&untimed_ctx).unwrap();
+        let mut safe_solution = SafeNetReachability::new(untimed_state.clone());
+        if safe_solution.is_compatible(&untimed_net, &untimed_ctx, &safe_query) {
+            positive(format!("{} : {}", text, safe_solution.solve(&untimed_net, &untimed_ctx, &safe_query)));
+        }
+    }
+
     let test = TimeInterval(Large(3),Strict(10));
     
     println!("{}", test);
@@ -142,20 +914,137 @@ fn main() {
 
 }
 
+// sally --batch <project.json> <queries.txt> [--summary <summary.json>] [--lenient] [--confidence <c>]
+// Verifies every query of the file on the Petri net project, the exit code reporting the worst outcome
+fn batch_main(args : &[String]) -> i32 {
+    let (Some(project_path), Some(queries_path)) = (args.first(), args.get(1)) else {
+        error("Usage : sally --batch <project.json> <queries.txt> [--summary <summary.json>] [--lenient] [--confidence <c>]");
+        return EXIT_ERROR;
+    };
+    let mut batch = match BatchVerification::read(queries_path) {
+        Ok(batch) => batch,
+        Err(e) => {
+            error(format!("Unable to read queries : {}", e));
+            return EXIT_ERROR;
+        }
+    };
+    let mut summary_path = None;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--summary" => summary_path = options.next(),
+            "--lenient" => batch = batch.lenient(),
+            "--confidence" => match options.next().and_then(|c| c.parse::<f64>().ok()) {
+                Some(confidence) => batch = batch.with_min_confidence(confidence),
+                None => {
+                    error("--confidence expects a number");
+                    return EXIT_ERROR;
+                }
+            },
+            _ => {
+                error(format!("Unknown option {}", option));
+                return EXIT_ERROR;
+            }
+        }
+    }
+    let (net, ctx, project) : (PetriNet, _, ModelProject<PetriStructure>) = match export::load_file(project_path) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error(format!("Unable to load project : {}", e));
+            return EXIT_ERROR;
+        }
+    };
+    let initial_state = project.initial_state.clone().unwrap();
+    let mut solver = build_solver();
+    let summary = batch.run(&mut solver, &net, &PetriNet::get_meta().name, &ctx, &initial_state, &project.predicates);
+    summary.log();
+    if let Some(path) = summary_path {
+        if let Err(e) = summary.write(path) {
+            error(format!("Unable to write summary : {}", e));
+            return EXIT_ERROR;
+        }
+    }
+    summary.exit_code
+}
+
+#[cfg(feature = "distributed")]
+fn worker_main(args : &[String]) -> i32 {
+    use crate::verification::smc::distributed::SMCWorker;
+    let Some(address) = args.first() else {
+        error("Usage : sally --worker <address:port> [--threads <n>]");
+        return EXIT_ERROR;
+    };
+    let worker = match (args.get(1).map(|a| a.as_str()), args.get(2).and_then(|n| n.parse::<usize>().ok())) {
+        (Some("--threads"), Some(threads)) => SMCWorker::new(threads),
+        (None, _) => SMCWorker::available(),
+        _ => {
+            error("--threads expects a number");
+            return EXIT_ERROR;
+        }
+    };
+    match worker.serve::<PetriStructure, PetriNet>(address) {
+        Ok(()) => 0,
+        Err(e) => {
+            error(e.to_string());
+            EXIT_ERROR
+        }
+    }
+}
+
+// Two local workers, as started by sally --worker
+#[cfg(feature = "distributed")]
+fn distributed_demo(project : &ModelProject<PetriStructure>) {
+    use crate::verification::smc::distributed::{DistributedSMC, SMCWorker};
+    let listeners : Vec<std::net::TcpListener> = (0..2).map(|_| std::net::TcpListener::bind("127.0.0.1:0").unwrap()).collect();
+    let addresses = listeners.iter().map(|l| l.local_addr().unwrap().to_string()).collect();
+    std::thread::scope(|s| {
+        for listener in listeners {
+            s.spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                SMCWorker::new(2).handle::<PetriStructure, PetriNet>(stream).unwrap()
+            });
+        }
+        let mut estim = ProbabilityEstimation::fixed_runs(10000, 0.95);
+        match DistributedSMC::new(addresses).with_batch_size(500).verify(&mut estim, project, "P <> [t <= 10] finished") {
+            Ok(result) => positive(format!("Distributed : {}", result)),
+            Err(e) => error(e.to_string())
+        }
+    });
+}
+
 fn build_solver() -> ModelSolvingGraph {
     let mut solver = ModelSolvingGraph::new();
     solver.register_model(PetriNet::get_meta());
+    solver.register_model(ColoredPetriNet::get_meta());
     solver.register_model(ClassGraph::get_meta());
     solver.register_model(MarkovChain::get_meta());
+    solver.register_model(MarkovAutomaton::get_meta());
+    solver.register_model(MDP::get_meta());
+    solver.register_model(POMDP::get_meta());
+    solver.register_model(HybridAutomaton::get_meta());
     solver.register_translation(Box::new(PetriClassGraphTranslation::new()));
+    solver.register_translation(Box::new(MarkovAutomatonSubclassTranslation::new()));
+    solver.register_translation(Box::new(UntimedProjection::new()));
+    solver.register_translation(Box::new(ColoredUnfolding::new()));
     solver.register_solution(Box::new(ClassGraphReachability::new()));
     solver.register_solution(Box::new(ClassGraphReachabilitySynthesis::new()));
+    solver.register_solution(Box::new(LtlModelChecking::new()));
+    solver.register_solution(Box::new(MarkovExpectedReward::new()));
+    solver.register_solution(Box::new(MarkovSteadyState::new()));
+    solver.register_solution(Box::new(MarkovQuantile::new()));
+    solver.register_solution(Box::new(SPRT::new(0.05, 0.05, 0.01).with_max_runs(100000)));
+    solver.register_format(ModelFormat::petri_json());
+    solver.register_format(ModelFormat::petri_mermaid());
+    solver.register_format(ModelFormat::markov_json());
+    solver.register_format(ModelFormat::markov_csv());
+    solver.register_format(ModelFormat::markov_mermaid());
     solver.compile();
     solver
 }
 
 fn sample_petri() -> PetriNet {
-    let p0 = PetriPlace::new(lbl("p0"));
+    let p0 = PetriPlace::new(lbl("p0"))
+        .with_metadata(NodeMetadata::new().with_description("Initial place").with_unit("jobs").with_position(0.0, 0.0));
     let p1 = PetriPlace::new(lbl("p1"));
     let p2 = PetriPlace::new(lbl("p2"));
     let p3 = PetriPlace::new(lbl("p3"));
@@ -218,6 +1107,28 @@ fn sample_markov() -> MarkovChain {
     MarkovChain::new(vec![m1,m2,m3])
 }
 
+// Climbs one rung with probability p, falls otherwise. The fall node comes first, to be the lowest level
+// Reaches the goal with probability p, or spins forever
+fn sample_spinner(p : f64) -> MarkovChain {
+    MarkovChain::new(vec![
+        MarkovNode::probabilistic(lbl("gate"), vec![(lbl("goal"), p), (lbl("spin0"), 1.0 - p)]),
+        MarkovNode::probabilistic(lbl("spin0"), vec![(lbl("spin1"), 1.0)]),
+        MarkovNode::probabilistic(lbl("spin1"), vec![(lbl("spin0"), 1.0)]),
+        MarkovNode::probabilistic(lbl("goal"), vec![(lbl("goal"), 1.0)]),
+    ])
+}
+
+fn sample_ladder(rungs : usize, p : f64) -> MarkovChain {
+    let mut nodes = vec![MarkovNode::probabilistic(lbl("fall"), vec![(lbl("fall"), 1.0)])];
+    for i in 0..rungs {
+        nodes.push(MarkovNode::probabilistic(lbl(&format!("l{}", i)), vec![
+            (lbl(&format!("l{}", i + 1)), p), (lbl("fall"), 1.0 - p)
+        ]));
+    }
+    nodes.push(MarkovNode::probabilistic(lbl(&format!("l{}", rungs)), vec![(lbl(&format!("l{}", rungs)), 1.0)]));
+    MarkovChain::new(nodes)
+}
+
 fn sample_query() -> Query {
     let condition = Condition::And(
         Box::new(Condition::Evaluation(Expr::Var(var("p5")))),
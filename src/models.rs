@@ -2,15 +2,23 @@ mod label;
 mod node;
 mod edge;
 mod model_state;
+mod model_visitor;
+mod index;
+mod state_batch;
 
 use std::{any::Any, collections::HashSet};
 
 pub use label::{lbl, Label};
 pub use model_state::ModelState;
-pub use node::Node;
+pub use node::{Node, NodeMetadata, hash_without_metadata};
 pub use edge::Edge;
+pub use index::{PlaceId, TransitionId, ClassId, ClockId};
+pub use model_visitor::{ModelVisitor, VisitableModel, ModelStatistics, accept_any};
+pub use state_batch::{StateBatch, BatchedState};
 use num_traits::Zero;
-use rand::{thread_rng, Rng, seq::SliceRandom};
+use rand::Rng;
+
+use crate::computation::random::{choose_uniform, SimulationRng};
 
 pub mod time;
 pub mod model_var;
@@ -21,6 +29,8 @@ pub mod model_context;
 pub mod expressions;
 pub mod program;
 pub mod petri;
+pub mod colored_petri;
+pub mod hybrid;
 pub mod class_graph;
 pub mod model_solving_graph;
 pub mod digraph;
@@ -28,6 +38,12 @@ pub mod tapn;
 pub mod model_network;
 pub mod markov;
 pub mod run;
+pub mod initial_marking;
+pub mod model_project;
+pub mod model_param;
+pub mod beliefs_graph;
+pub mod reward_structure;
+pub mod word;
 
 use self::{action::Action, model_characteristics::*, model_context::ModelContext, time::ClockValue};
 
@@ -108,6 +124,14 @@ pub trait Model : Any {
 
     fn available_actions(&self, state : &ModelState) -> HashSet<Action>;
 
+    // Every discrete successor of a state, delays being ignored. Stochastic models should enumerate the whole
+    // support of their distributions, instead of sampling it like next does
+    fn successors(&self, state : &ModelState) -> Vec<ModelState> {
+        self.available_actions(state).into_iter()
+            .filter_map(|action| self.next(state.clone(), action).map(|(next, _)| next))
+            .collect()
+    }
+
     fn available_delay(&self, state : &ModelState) -> ClockValue {
         let _ = state;
         ClockValue::zero()
@@ -127,6 +151,13 @@ pub trait Model : Any {
         state
     }
 
+    // Called once variables shared with other models have been written, so that models depending on them can update
+    // their state, such as the enabling of guarded transitions
+    fn vars_written(&self, state : ModelState, vars : &[model_var::ModelVar]) -> ModelState {
+        let _ = vars;
+        state
+    }
+
     fn get_meta() -> ModelMeta where Self : Sized;
 
     fn get_model_meta(&self) -> ModelMeta where Self : Sized { // Same as before but instance
@@ -137,10 +168,20 @@ pub trait Model : Any {
 
     fn is_stochastic(&self) -> bool;
 
-    // Default implementation of random_next sampler for SMC. 
+    // Name of the scheduler resolving nondeterministic choices when simulating, None if runs don't depend on one
+    fn scheduler(&self) -> Option<Label> {
+        None
+    }
+
+    // Same as next, probabilistic outcomes being drawn from the given generator. Models whose successors only depend
+    // on the action keep the default, probabilistic ones implement next through it.
+    fn sample_next(&self, state : ModelState, action : Action, _ : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        self.next(state, action)
+    }
+
+    // Default implementation of random_next sampler for SMC, every draw being made from the generator of the run.
     // Should be overrided by stochastic models with a more relevant behaviour !
-    fn random_next(&self, state : ModelState) -> (Option<ModelState>, ClockValue, Option<Action>) {
-        let mut rng = thread_rng();
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
         let max_delay = self.available_delay(&state);
         let mut delayed_state = state;
         let mut delay = ClockValue::zero();
@@ -149,19 +190,34 @@ pub trait Model : Any {
             delay = rng.gen_range(delay_range);
             delayed_state = self.delay(delayed_state, delay).unwrap();
         }
-        let actions : Vec<Action> = self.available_actions(&delayed_state).into_iter().collect();
-        let action = actions.choose(&mut rng);
+        let mut actions : Vec<Action> = self.available_actions(&delayed_state).into_iter().collect();
+        actions.sort_by_key(|a| a.get_id());
+        let action = choose_uniform(&actions, rng);
         if action.is_none() {
             return (Some(delayed_state), delay, None)
         }
         let action = action.unwrap().clone();
-        let next = self.next(delayed_state, action.clone());
+        let next = self.sample_next(delayed_state, action.clone(), rng);
         if next.is_none() {
             return (None, delay, Some(action));
         }
         (Some(next.unwrap().0), delay, Some(action))
     }
 
+    // Steps every active state of the batch in place, returning the delay and action of each step, or None if the run
+    // is inactive or the step failed. Runs are stepped one by one through random_next unless overriden by models
+    // able to check enabledness over whole columns of the batch
+    fn batch_random_next(&self, batch : &mut StateBatch, active : &[bool], rng : &mut SimulationRng) -> Vec<Option<(ClockValue, Option<Action>)>> {
+        (0..batch.len()).map(|i| {
+            if !active[i] {
+                return None;
+            }
+            let (next, delay, action) = self.random_next(batch.get(i), rng);
+            batch.set(i, &next?);
+            Some((delay, action))
+        }).collect()
+    }
+
     fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()>;
 
     fn singleton(&mut self) -> ModelContext {
@@ -172,6 +228,11 @@ pub trait Model : Any {
 
     fn get_id(&self) -> usize;
 
+    // Hash of the model structure, used to detect modified components. None if the model can't be hashed
+    fn structure_hash(&self) -> Option<u64> {
+        None
+    }
+
 }
 
 // Trait that should implement Send and Sync, to be shared amongst threads and do parallel verification by creating local models
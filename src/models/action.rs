@@ -13,6 +13,8 @@ pub enum Action {
     Epsilon,
     Internal(usize),
     Sync(usize, Box<Action>, Box<Action>),
+    // Output of a broadcast channel, along with the inputs of every receiver
+    Broadcast(usize, Box<Action>, Vec<Action>),
     WithData(usize, ModelStorage)
 }
 
@@ -23,6 +25,7 @@ impl Action {
             Self::Epsilon => usize::MAX,
             Self::Internal(i) => *i,
             Self::Sync(i, _, _) => *i,
+            Self::Broadcast(i, _, _) => *i,
             Self::WithData(i, _) => *i
         }
     }
@@ -47,6 +50,10 @@ impl Action {
         Self::Sync(self.get_id(), Box::new(a), Box::new(b))
     }
 
+    pub fn broadcast(&self, output : Action, inputs : Vec<Action>) -> Action {
+        Self::Broadcast(self.get_id(), Box::new(output), inputs)
+    }
+
     pub fn has_data(&self) -> bool {
         match self {
             Self::WithData(_, _) => true,
@@ -61,6 +68,10 @@ impl Action {
         }
     }
 
+    pub fn is_broadcast(&self) -> bool {
+        matches!(self, Self::Broadcast(_, _, _))
+    }
+
 }
 
 impl Default for Action {
@@ -75,6 +86,10 @@ impl Display for Action {
             Self::Epsilon => write!(f, "_"),
             Self::Internal(i) => write!(f, "Action({})", i),
             Self::Sync(id, i, j) => write!(f, "Sync({},{},{})", id, i, j),
+            Self::Broadcast(id, o, inputs) => {
+                let inputs : Vec<String> = inputs.iter().map(Action::to_string).collect();
+                write!(f, "Broadcast({},{},[{}])", id, o, inputs.join(","))
+            },
             Self::WithData(i, d) => write!(f, "WithData({})", i)
         }
     }
@@ -100,6 +115,19 @@ impl ActionPairs {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty() || self.1.is_empty()
     }
+
+    pub fn inputs(&self) -> &HashSet<Action> {
+        &self.0
+    }
+
+    pub fn outputs(&self) -> &HashSet<Action> {
+        &self.1
+    }
+
+    pub fn contains(&self, action : &Action) -> bool {
+        let base = action.base();
+        self.0.contains(&base) || self.1.contains(&base)
+    }
     
     pub fn enabled(&self, set : &HashSet<Action>) -> ActionPairs {
         let mut inputs = HashSet::new();
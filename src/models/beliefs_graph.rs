@@ -0,0 +1,289 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, sync::Arc};
+
+use num_traits::Zero;
+
+use crate::{computation::random::SimulationRng, translation::observation::ObservationFunction};
+
+use super::{action::Action, lbl, markov::{mdp::{MDPState, MDP}, scheduler::{Scheduler, UniformScheduler}}, model_characteristics::*, model_context::ModelContext, model_storage::ModelStorage, time::ClockValue, CompilationResult, Label, Model, ModelMeta, ModelState};
+
+// Beliefs equal up to this precision are merged when exploring
+const BELIEF_PRECISION : f64 = 1e-9;
+
+/// Distribution over the states of a POMDP, by index
+pub type Belief = Vec<f64>;
+
+/// Outcomes of an action from a belief : successor beliefs by index, with the probability of their observation
+pub type BeliefOutcomes = Vec<(usize, f64)>;
+
+/// Partially observable MDP : the controller only sees the observation of the current state, given by the vars of the
+/// observation function (states are the vars of the MDP, unobserved ones sharing the empty observation). It keeps a
+/// belief, updated by Bayes' rule after each action and observation, stored in the storage of the model while runs
+/// follow the true state. The scheduler resolves actions from the observation instead of the state.
+#[derive(Debug, Clone)]
+pub struct POMDP {
+    pub mdp : MDP,
+    pub observation : ObservationFunction,
+    pub scheduler : Arc<dyn Scheduler>,
+    // Initial distribution, uniform over the states observed as the initial one if empty
+    pub initial_belief : Vec<(Label, f64)>,
+
+    observation_of : Vec<Label>,
+    storage_index : usize,
+}
+
+impl POMDP {
+
+    pub fn new(mdp : MDP, observation : ObservationFunction) -> Self {
+        POMDP {
+            mdp,
+            observation,
+            scheduler : Arc::new(UniformScheduler),
+            initial_belief : Vec::new(),
+            observation_of : Vec::new(),
+            storage_index : usize::MAX,
+        }
+    }
+
+    pub fn with_scheduler(mut self, scheduler : impl Scheduler + 'static) -> Self {
+        self.scheduler = Arc::new(scheduler);
+        self
+    }
+
+    pub fn with_initial_belief(mut self, belief : Vec<(Label, f64)>) -> Self {
+        self.initial_belief = belief;
+        self
+    }
+
+    pub fn observe(&self, state : &ModelState) -> Label {
+        let index = state.argmax(self.mdp.get_vars());
+        self.observation_of[index].clone()
+    }
+
+    pub fn belief(&self, state : &ModelState) -> Belief {
+        match state.storage(&self.storage_index) {
+            ModelStorage::Vector(belief) => belief.iter().map(|p| p.clone().float()).collect(),
+            _ => vec![0.0 ; self.mdp.states.len()]
+        }
+    }
+
+    pub fn belief_of(&self, state : &ModelState, label : &Label) -> f64 {
+        self.mdp.states_dic.get(label).map_or(0.0, |i| self.belief(state)[*i])
+    }
+
+    fn set_belief(&self, state : &mut ModelState, belief : &[f64]) {
+        *state.mut_storage(&self.storage_index) = ModelStorage::Vector(belief.iter().map(|p| ModelStorage::Float(*p)).collect());
+    }
+
+    // Belief after taking the action and making the observation, along with the probability of the observation.
+    // None if the observation can't be made
+    pub fn successor_belief(&self, belief : &[f64], action : &Action, observation : &Label) -> Option<(Belief, f64)> {
+        let mut next = vec![0.0 ; belief.len()];
+        for (i, b) in belief.iter().enumerate().filter(|(_, b)| **b > 0.0) {
+            let Some(choice) = self.mdp.states[i].compiled_actions.get(action) else {
+                continue;
+            };
+            for (j, p) in choice.0.iter() {
+                if self.observation_of[*j] == *observation {
+                    next[*j] += b * p;
+                }
+            }
+        }
+        let total : f64 = next.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        Some((next.into_iter().map(|p| p / total).collect(), total))
+    }
+
+    // Observations that can follow the action
+    fn observations_after(&self, belief : &[f64], action : &Action) -> Vec<Label> {
+        let mut observations : Vec<Label> = belief.iter().enumerate().filter(|(_, b)| **b > 0.0)
+            .filter_map(|(i, _)| self.mdp.states[i].compiled_actions.get(action))
+            .flat_map(|choice| choice.0.iter().filter(|(_, p)| *p > 0.0).map(|(j, _)| self.observation_of[*j].clone()))
+            .collect::<HashSet<Label>>().into_iter().collect();
+        observations.sort();
+        observations
+    }
+
+    fn initial(&self, state : &ModelState) -> Belief {
+        let n = self.mdp.states.len();
+        let mut belief = vec![0.0 ; n];
+        if self.initial_belief.is_empty() {
+            let observed = self.observe(state);
+            for (i, o) in self.observation_of.iter().enumerate() {
+                if *o == observed {
+                    belief[i] = 1.0;
+                }
+            }
+        } else {
+            for (label, p) in self.initial_belief.iter() {
+                if let Some(i) = self.mdp.states_dic.get(label) {
+                    belief[*i] += p;
+                }
+            }
+        }
+        let total : f64 = belief.iter().sum();
+        belief.into_iter().map(|p| p / total).collect()
+    }
+
+}
+
+impl Model for POMDP {
+
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        self.sample_next(state, action, &mut SimulationRng::from_entropy())
+    }
+
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        let belief = self.belief(&state);
+        let (mut next, actions) = self.mdp.sample_next(state, action.clone(), rng)?;
+        let observed = self.observe(&next);
+        let (updated, _) = self.successor_belief(&belief, &action, &observed)?;
+        self.set_belief(&mut next, &updated);
+        Some((next, actions))
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        self.mdp.available_actions(state)
+    }
+
+    fn init_initial_storage(&self, mut state : ModelState) -> ModelState {
+        let belief = self.initial(&state);
+        self.set_belief(&mut state, &belief);
+        state
+    }
+
+    // Actions are scheduled from what the controller observes, the true state only deciding the outcome
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let current = self.mdp.get_current_state(&state);
+        if current.compiled_actions.is_empty() {
+            return (Some(state), ClockValue::zero(), None);
+        }
+        let choice = self.scheduler.schedule(&self.observe(&state), &current.action_labels(), rng);
+        let Some(action) = choice.and_then(|a| self.mdp.actions_dic.get(&a).cloned()) else {
+            return (None, ClockValue::zero(), None);
+        };
+        let next = self.sample_next(state, action.clone(), rng).map(|(s, _)| s);
+        (next, ClockValue::zero(), Some(action))
+    }
+
+    fn get_meta() -> ModelMeta {
+        ModelMeta {
+            name : lbl("POMDP"),
+            description : String::from("Partially observable MDP, the controller keeping a belief over the states"),
+            characteristics : CONTROLLABLE | STOCHASTIC
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        false
+    }
+
+    fn is_stochastic(&self) -> bool {
+        true
+    }
+
+    fn scheduler(&self) -> Option<Label> {
+        if self.mdp.is_nondeterministic() {
+            Some(self.scheduler.get_name())
+        } else {
+            None
+        }
+    }
+
+    fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        self.mdp.compile(context)?;
+        self.storage_index = context.add_storage();
+        self.observation_of = self.mdp.states.iter().map(|s| {
+            self.observation.vars.get(&s.label).cloned().unwrap_or_default()
+        }).collect();
+        Ok(())
+    }
+
+    fn get_id(&self) -> usize {
+        self.mdp.get_id()
+    }
+
+}
+
+/// Beliefs of a POMDP reachable from its initial one, forming the belief MDP : for an action, a belief leads to the
+/// belief updated by each possible observation, with the probability of the observation. Beliefs may be infinitely
+/// many, so that exploration stops at a given number of them, beliefs left unexplored having no successor.
+#[derive(Debug, Clone)]
+pub struct BeliefsGraph {
+    pub beliefs : Vec<Belief>,
+    pub observations : Vec<Label>,
+    // Successors of each belief, by action label
+    pub edges : Vec<Vec<(Label, BeliefOutcomes)>>,
+    pub complete : bool,
+}
+
+impl BeliefsGraph {
+
+    pub fn compute(pomdp : &POMDP, initial_state : &ModelState, max_beliefs : usize) -> Self {
+        let key = |belief : &Belief| -> Vec<i64> {
+            belief.iter().map(|p| (p / BELIEF_PRECISION).round() as i64).collect()
+        };
+        let initial = pomdp.belief(initial_state);
+        let mut graph = BeliefsGraph {
+            beliefs : vec![initial.clone()],
+            observations : vec![pomdp.observe(initial_state)],
+            edges : vec![Vec::new()],
+            complete : true,
+        };
+        let mut known : HashMap<Vec<i64>, usize> = HashMap::from([(key(&initial), 0)]);
+        let mut to_see : VecDeque<usize> = VecDeque::from([0]);
+        while let Some(index) = to_see.pop_front() {
+            let belief = graph.beliefs[index].clone();
+            let mut labels : Vec<Label> = belief.iter().enumerate().filter(|(_, b)| **b > 0.0)
+                .flat_map(|(i, _)| pomdp.mdp.states[i].actions.keys().cloned())
+                .collect::<HashSet<Label>>().into_iter().collect();
+            labels.sort();
+            for label in labels {
+                let action = &pomdp.mdp.actions_dic[&label];
+                let mut successors = Vec::new();
+                for observation in pomdp.observations_after(&belief, action) {
+                    let Some((next, p)) = pomdp.successor_belief(&belief, action, &observation) else {
+                        continue;
+                    };
+                    let next_key = key(&next);
+                    let next_index = match known.get(&next_key) {
+                        Some(i) => *i,
+                        None if graph.beliefs.len() >= max_beliefs => {
+                            graph.complete = false;
+                            continue;
+                        },
+                        None => {
+                            let i = graph.beliefs.len();
+                            graph.beliefs.push(next);
+                            graph.observations.push(observation);
+                            graph.edges.push(Vec::new());
+                            known.insert(next_key, i);
+                            to_see.push_back(i);
+                            i
+                        }
+                    };
+                    successors.push((next_index, p));
+                }
+                graph.edges[index].push((label, successors));
+            }
+        }
+        graph
+    }
+
+    pub fn belief_label(index : usize) -> Label {
+        Label::from(format!("b{}", index))
+    }
+
+    // Belief MDP, to be analysed or scheduled as any MDP. Mass leading to beliefs beyond the exploration bound is lost,
+    // remaining outcomes being normalized
+    pub fn to_mdp(&self) -> MDP {
+        MDP::new(self.edges.iter().enumerate().map(|(i, edges)| {
+            let actions = edges.iter().filter(|(_, successors)| !successors.is_empty()).map(|(action, successors)| {
+                (action.clone(), successors.iter().map(|(j, p)| (Self::belief_label(*j), *p)).collect())
+            }).collect();
+            MDPState::new(Self::belief_label(i), actions)
+        }).collect())
+    }
+
+}
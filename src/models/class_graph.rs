@@ -1,8 +1,12 @@
 mod state_class;
+mod clock_constraints;
+mod witness;
 pub use state_class::StateClass;
+pub use witness::{FiringConstraint, FiringConstraints};
 
 use core::panic;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::sync::{Arc, RwLock, Weak};
 
 use num_traits::Zero;
@@ -14,9 +18,10 @@ use crate::verification::Verifiable;
 use super::action::Action;
 use super::model_context::ModelContext;
 use super::model_var::{ModelVar, VarType};
-use super::time::ClockValue;
-use super::{lbl, Edge, Label, Model, ModelMeta, ModelState, CONTROLLABLE, SYMBOLIC, TIMED};
-use super::petri::{PetriNet, PetriTransition};
+use super::model_clock::ModelClock;
+use super::time::{ClockValue, TimeBound};
+use super::{lbl, ClassId, ClockId, Edge, Label, Model, ModelMeta, ModelState, PlaceId, TransitionId, CONTROLLABLE, SYMBOLIC, TIMED};
+use super::petri::{PetriNet, PetriPlace, PetriTransition};
 
 const CLASS_LIMIT : usize = u16::MAX as usize;
 
@@ -25,46 +30,62 @@ pub struct ClassGraph {
     pub id : usize,
     pub classes : Vec<Arc<StateClass>>,
     pub edges : Vec<Edge<Action, StateClass, StateClass>>,
-    pub places_dic : HashMap<Label, usize>,
+    pub places_dic : HashMap<Label, PlaceId>,
     pub current_class : ModelVar,
-    pub transitions : Vec<Arc<PetriTransition>>
+    pub places : Vec<Arc<PetriPlace>>,
+    pub transitions : Vec<Arc<PetriTransition>>,
+    pub declared_clocks : Vec<ModelClock>
 }
 
 impl ClassGraph {
 
-    pub fn compute(p_net : &PetriNet, initial_state : &ModelState) -> Self {
+    // Graph of a net without any class, enough to evaluate conditions on classes built elsewhere
+    pub fn empty(p_net : &PetriNet) -> Self {
         let mut cg = ClassGraph {
             id : usize::MAX,
             classes : Vec::new(),
             edges : Vec::new(),
             places_dic : p_net.places_dic.clone(),
             current_class : ModelVar::name(lbl("CurrentClass")),
-            transitions : p_net.transitions.clone()
+            places : p_net.places.clone(),
+            transitions : p_net.transitions.clone(),
+            declared_clocks : p_net.declared_clocks.clone()
         };
         cg.current_class.set_type(VarType::VarU16);
-        let mut seen : HashMap<u64, usize> = HashMap::new();
-        let mut to_see : VecDeque<usize> = VecDeque::new();
-        let initial_class = StateClass::compute_class(p_net, initial_state);
-        seen.insert(initial_class.get_hash(), 0);
+        cg
+    }
+
+    pub fn compute(p_net : &PetriNet, initial_state : &ModelState) -> Self {
+        Self::compute_from(p_net, StateClass::compute_class(p_net, initial_state))
+    }
+
+    // Graph of the classes reachable from the given one, which may cover several initial states
+    pub fn compute_from(p_net : &PetriNet, initial_class : StateClass) -> Self {
+        let mut cg = ClassGraph::empty(p_net);
+        let mut seen : HashMap<u64, ClassId> = HashMap::new();
+        let mut to_see : VecDeque<ClassId> = VecDeque::new();
+        seen.insert(initial_class.get_hash(), ClassId(0));
         cg.classes.push(Arc::new(initial_class));
-        to_see.push_back(0);
+        to_see.push_back(ClassId(0));
         while !to_see.is_empty() {
             let class_index = to_see.pop_back().unwrap();
-            let class = Arc::clone(&cg.classes[class_index]);
-            let clocks = class.enabled_clocks();
+            let class = Arc::clone(cg.class(class_index));
+            // Successors are explored in transition order, so classes are numbered the same way on every run
+            let mut clocks : Vec<TransitionId> = class.enabled_clocks().into_iter().collect();
+            clocks.sort();
             for t_index in clocks {
                 let next_class = ClassGraph::successor(p_net, &class, t_index);
-                let action = cg.transitions[t_index].get_action();
+                let action = cg.transitions[t_index.index()].get_action();
                 if next_class.is_none() {
                     continue;
                 }
                 let mut next_class = next_class.unwrap();
                 let new_hash = next_class.get_hash();
                 if seen.contains_key(&new_hash) {
-                    cg.classes[seen[&new_hash]].predecessors.write().unwrap().push((Arc::downgrade(&class), action));
+                    cg.class(seen[&new_hash]).predecessors.write().unwrap().push((Arc::downgrade(&class), action));
                     continue;
                 }
-                let new_index = cg.classes.len();
+                let new_index = ClassId(cg.classes.len());
                 next_class.index = new_index;
                 seen.insert(new_hash, new_index);
                 cg.classes.push(Arc::new(next_class));
@@ -77,53 +98,86 @@ impl ClassGraph {
         cg
     }
 
-    pub fn successor(petri : &PetriNet, class : &Arc<StateClass>, t_index : usize) -> Option<StateClass> {
+    pub fn class(&self, id : ClassId) -> &Arc<StateClass> {
+        &self.classes[id.index()]
+    }
+
+    // Declared clocks are carried by the firing domain as variables equal to minus their value : they drift like
+    // persistent transitions, and a reset sets them back to zero. Guards on them restrict the firing date of the transition.
+    pub fn successor(petri : &PetriNet, class : &Arc<StateClass>, t_index : TransitionId) -> Option<StateClass> {
         let image_state = class.generate_image_state();
         let (next_state, newen, pers) = petri.fire(image_state, t_index);
 
-        let vars = newen.len() + pers.len();
-        let mut next_dbm = DBM::new(vars);
-        let mut to_dbm : Vec<usize> = vec![0 ; petri.transitions.len()];
-        let mut from_dbm : Vec<usize> = vec![0];
+        let fired = petri.transition(t_index);
         let prev_to_dbm = &class.to_dbm_index;
-        let fired_i = prev_to_dbm[t_index];
+        let fired_i = class.clock_of(t_index)?;
+        // The fired transition has to be the first one to fire, and to satisfy its clock guards.
+        // It fires strictly before enabled transitions of higher priority : exact for immediate ones, which are
+        // fireable as soon as enabled, an over-approximation otherwise since they might be fireable earlier.
+        let mut dbm = class.dbm.clone();
+        for other in class.from_dbm_index.iter().skip(1) {
+            let other_i = prev_to_dbm[other.index()];
+            let bound = if petri.transition(*other).priority > fired.priority { TimeBound::Strict(0) } else { TimeBound::zero() };
+            dbm[(fired_i, other_i)] = dbm[(fired_i, other_i)].intersection(bound);
+        }
+        for (clock, interval) in fired.compiled_clock_guards.iter() {
+            let clock_i = class.clock_dbm_index[petri.declared_clock_position(clock)?];
+            dbm[(fired_i, clock_i)] = dbm[(fired_i, clock_i)].intersection(interval.1);
+            dbm[(clock_i, fired_i)] = dbm[(clock_i, fired_i)].intersection(-interval.0);
+        }
+        dbm.make_canonical();
+        if dbm.is_empty() {
+            return None;
+        }
+
+        let vars = newen.len() + pers.len() + petri.declared_clocks.len();
+        let mut next_dbm = DBM::new(vars);
+        let mut to_dbm : Vec<ClockId> = vec![ClockId::REFERENCE ; petri.transitions.len()];
+        let mut from_dbm : Vec<TransitionId> = vec![TransitionId(0)];
+        let mut carried : Vec<(ClockId, ClockId)> = Vec::new();
         let discrete = next_state.discrete;
-        let dbm = &class.dbm;
         let action = petri.get_transition_action(t_index);
+        let zero = ClockId::REFERENCE;
 
-        for transi in 0..petri.transitions.len() {
+        for transi in (0..petri.transitions.len()).map(TransitionId) {
             if pers.contains(&transi) {
-                let dbm_index = from_dbm.len();
-                to_dbm[transi] = dbm_index;
+                let dbm_index = ClockId(from_dbm.len());
+                to_dbm[transi.index()] = dbm_index;
                 from_dbm.push(transi);
-                let previous_index = prev_to_dbm[transi];
-                if dbm[(previous_index, 0)] < dbm[(fired_i, 0)] {
-                    return None
-                }
-                next_dbm[(dbm_index, 0)] = dbm[(previous_index, fired_i)];
-                next_dbm[(0, dbm_index)] = dbm[(fired_i, previous_index)];
+                let previous_index = prev_to_dbm[transi.index()];
+                next_dbm[(dbm_index, zero)] = dbm[(previous_index, fired_i)];
+                next_dbm[(zero, dbm_index)] = dbm[(fired_i, previous_index)];
+                carried.push((dbm_index, previous_index));
             } else if newen.contains(&transi) {
-                let dbm_index = from_dbm.len();
-                to_dbm[transi] = dbm_index;
+                let dbm_index = ClockId(from_dbm.len());
+                to_dbm[transi.index()] = dbm_index;
                 from_dbm.push(transi);
-                next_dbm[(dbm_index, 0)] = petri.transitions[transi].interval.1;
-                next_dbm[(0, dbm_index)] = -petri.transitions[transi].interval.0;
+                next_dbm[(dbm_index, zero)] = petri.transition(transi).interval.1;
+                next_dbm[(zero, dbm_index)] = -petri.transition(transi).interval.0;
             } else {
                 continue;
             }
         }
 
-        for pers1 in 0..petri.transitions.len() {
-            for pers2 in (pers1 + 1)..petri.transitions.len() {
-                if (!pers.contains(&pers1)) || (!pers.contains(&pers2)) {
-                    continue;
-                }
-                let prev_index_1 = prev_to_dbm[pers1];
-                let prev_index_2 = prev_to_dbm[pers2];
-                let index_1 = to_dbm[pers1];
-                let index_2 = to_dbm[pers2];
-                next_dbm[(index_1, index_2)] = dbm[(prev_index_1, prev_index_2)];
-                next_dbm[(index_2, index_1)] = dbm[(prev_index_2, prev_index_1)];
+        let mut clock_dbm : Vec<ClockId> = Vec::new();
+        for (k, clock) in petri.declared_clocks.iter().enumerate() {
+            let dbm_index = ClockId(from_dbm.len() + k);
+            clock_dbm.push(dbm_index);
+            if fired.compiled_resets.iter().any(|c| c.get_index() == clock.get_index()) {
+                next_dbm[(dbm_index, zero)] = TimeBound::zero();
+                next_dbm[(zero, dbm_index)] = TimeBound::zero();
+            } else {
+                let previous_index = class.clock_dbm_index[k];
+                next_dbm[(dbm_index, zero)] = dbm[(previous_index, fired_i)];
+                next_dbm[(zero, dbm_index)] = dbm[(fired_i, previous_index)];
+                carried.push((dbm_index, previous_index));
+            }
+        }
+
+        for (a, (index_1, prev_index_1)) in carried.iter().enumerate() {
+            for (index_2, prev_index_2) in carried.iter().skip(a + 1) {
+                next_dbm[(*index_1, *index_2)] = dbm[(*prev_index_1, *prev_index_2)];
+                next_dbm[(*index_2, *index_1)] = dbm[(*prev_index_2, *prev_index_1)];
             }
         }
 
@@ -133,16 +187,41 @@ impl ClassGraph {
             return None;
         }
 
+        ClassGraph::extrapolate_clocks(petri, &mut next_dbm, &clock_dbm);
+
         Some(StateClass {
             discrete,
             dbm : next_dbm,
             to_dbm_index : to_dbm,
             from_dbm_index : from_dbm,
+            clock_dbm_index : clock_dbm,
             predecessors : RwLock::new(vec![(Arc::downgrade(&class), action)]),
-            index : 0
+            index : ClassId(0)
         })
     }
 
+    // Once a declared clock exceeds every constant it is compared to, its exact value is forgotten, which keeps the graph finite
+    fn extrapolate_clocks(petri : &PetriNet, dbm : &mut DBM, clock_dbm : &[ClockId]) {
+        let mut changed = false;
+        for (clock, dbm_index) in petri.declared_clocks.iter().zip(clock_dbm.iter().copied()) {
+            let max_constant = petri.max_clock_constant(clock);
+            if dbm[(dbm_index, ClockId::REFERENCE)] >= TimeBound::Large(-max_constant) {
+                continue;
+            }
+            for i in (0..=dbm.vars_count()).map(ClockId) {
+                if i != dbm_index {
+                    dbm[(i, dbm_index)] = TimeBound::Infinite;
+                    dbm[(dbm_index, i)] = TimeBound::Infinite;
+                }
+            }
+            dbm[(dbm_index, ClockId::REFERENCE)] = TimeBound::Strict(-max_constant);
+            changed = true;
+        }
+        if changed {
+            dbm.make_canonical();
+        }
+    }
+
 }
 
 impl Model for ClassGraph {
@@ -157,8 +236,8 @@ impl Model for ClassGraph {
 
     // Not optimized AT ALL ! Class graph is made for back-propagation
     fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
-        let mut next_index : Option<usize> = None;
-        let class_index = state.evaluate_var(&self.current_class) as usize;
+        let mut next_index : Option<ClassId> = None;
+        let class_index = ClassId(state.evaluate_var(&self.current_class) as usize);
         for e in self.edges.iter() {
             if !e.has_source() || !e.has_target() {
                 continue;
@@ -171,17 +250,17 @@ impl Model for ClassGraph {
             return None;
         }
         let next_index = next_index.unwrap();
-        let next_class = &self.classes[next_index];
+        let next_class = self.class(next_index);
         let mut next_state = next_class.generate_image_state();
         next_state.discrete.size_delta(self.current_class.size());
-        next_state.discrete.set(&self.current_class, next_index as EvaluationType);
+        next_state.discrete.set(&self.current_class, next_index.index() as EvaluationType);
         let actions = self.available_actions(&next_state);
         Some((next_state, actions))
     }
 
     fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
         let mut actions = HashSet::new();
-        let class_index = state.evaluate_var(&self.current_class) as usize;
+        let class_index = ClassId(state.evaluate_var(&self.current_class) as usize);
         for e in self.edges.iter() {
             if !e.has_source() {
                 continue;
@@ -198,13 +277,16 @@ impl Model for ClassGraph {
     }
 
     fn init_initial_clocks(&self, mut state : ModelState) -> ModelState {
-        let current_class = state.evaluate_var(&self.current_class) as usize;
-        let class = &self.classes[current_class];
+        let current_class = ClassId(state.evaluate_var(&self.current_class) as usize);
+        let class = self.class(current_class);
         for t in class.from_dbm_index.iter().skip(1) {
-            let transi = &self.transitions[*t];
+            let transi = &self.transitions[t.index()];
             let clock = transi.get_clock();
             state.enable_clock(clock, ClockValue::zero());
         }
+        for clock in self.declared_clocks.iter() {
+            state.enable_clock(clock, ClockValue::zero());
+        }
         state
     }
 
@@ -240,4 +322,43 @@ impl Model for ClassGraph {
         self.id
     }
 
-}
\ No newline at end of file
+}
+
+// Canonical text form : markings list places by name, firing domains list transitions by index, and edges are sorted
+impl fmt::Display for ClassGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut places : Vec<&Arc<PetriPlace>> = self.places.iter().filter(|p| p.get_var().is_mapped()).collect();
+        places.sort_by(|p1, p2| p1.name.cmp(&p2.name));
+        writeln!(f, "ClassGraph")?;
+        writeln!(f, "Classes :")?;
+        for class in self.classes.iter() {
+            let marking : Vec<String> = places.iter().map(|p| format!("{}={}", p.name, class.discrete.evaluate(p.get_var()))).collect();
+            let variables : Vec<String> = class.from_dbm_index.iter().skip(1).map(|t| self.transitions[t.index()].label.to_string())
+                .chain(self.declared_clocks.iter().map(|c| c.name.to_string())).collect();
+            let dbm : Vec<String> = (0..=class.dbm.vars_count()).map(|i| {
+                let row : Vec<String> = (0..=class.dbm.vars_count()).map(|j| class.dbm[(i, j)].to_string()).collect();
+                format!("[{}]", row.join(" "))
+            }).collect();
+            writeln!(f, "  {} : {{{}}} [{}] {}", class.index, marking.join(", "), variables.join(", "), dbm.join(""))?;
+        }
+        write!(f, "Edges :")?;
+        let mut edges : Vec<(usize, String, usize)> = Vec::new();
+        for class in self.classes.iter() {
+            for (pred, action) in class.predecessors.read().unwrap().iter() {
+                let Some(pred) = pred.upgrade() else {
+                    continue;
+                };
+                let label = match self.transitions.iter().find(|t| t.get_action() == *action) {
+                    Some(t) => t.label.to_string(),
+                    None => action.to_string()
+                };
+                edges.push((pred.index.index(), label, class.index.index()));
+            }
+        }
+        edges.sort();
+        for (from, label, to) in edges {
+            write!(f, "\n  {} -{}-> {}", from, label, to)?;
+        }
+        Ok(())
+    }
+}
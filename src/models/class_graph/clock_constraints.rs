@@ -0,0 +1,56 @@
+use crate::{models::{expressions::{Condition, Expr, PropositionType}, model_clock::ModelClock, ClockId, TransitionId}, verification::VerificationStatus};
+
+use super::{ClassGraph, StateClass};
+
+use VerificationStatus::*;
+
+impl ClassGraph {
+
+    // Over-approximation of the values of a transition clock in a class, None if the transition is disabled.
+    // A state with clock c has firing domain [max(0, a-c), b-c], so the domain bounds [L, U] of the class give c >= b-U, and c <= a-L when L > 0.
+    // Declared clocks are stored as their opposite, and are always enabled.
+    pub fn clock_interval(&self, class : &StateClass, clock : &ModelClock) -> Option<(f64, f64)> {
+        if let Some(k) = self.declared_clocks.iter().position(|c| c.get_index() == clock.get_index()) {
+            let dbm_index = class.clock_dbm_index[k];
+            return Some((-class.dbm[(dbm_index, ClockId::REFERENCE)].float(), class.dbm[(ClockId::REFERENCE, dbm_index)].float()));
+        }
+        let t_index = self.transitions.iter().position(|t| t.get_clock().get_index() == clock.get_index())?;
+        let dbm_index = class.clock_of(TransitionId(t_index))?;
+        let (a, b) = (self.transitions[t_index].interval.0.float(), self.transitions[t_index].interval.1.float());
+        let upper = class.dbm[(dbm_index, ClockId::REFERENCE)].float();
+        let lower = -class.dbm[(ClockId::REFERENCE, dbm_index)].float();
+        let low = if b.is_infinite() { 0.0 } else { (b - upper).max(0.0) };
+        let high = if lower > 0.0 { a - lower } else { b };
+        Some((low, high))
+    }
+
+    // Three-valued evaluation of a condition on a class, clock comparisons being undecided when the clock interval straddles the constant
+    pub fn evaluate_symbolic(&self, class : &StateClass, condition : &Condition) -> VerificationStatus {
+        if !condition.contains_clock_proposition() {
+            return condition.evaluate(class).0;
+        }
+        match condition {
+            Condition::And(c1, c2) => self.evaluate_symbolic(class, c1) & self.evaluate_symbolic(class, c2),
+            Condition::Or(c1, c2) => self.evaluate_symbolic(class, c1) | self.evaluate_symbolic(class, c2),
+            Condition::Implies(c1, c2) => (!self.evaluate_symbolic(class, c1)) | self.evaluate_symbolic(class, c2),
+            Condition::Not(c) => !self.evaluate_symbolic(class, c),
+            Condition::Evaluation(Expr::ClockComparison(p_type, clock, value)) => {
+                let Some((low, high)) = self.clock_interval(class, clock) else {
+                    return Unverified;
+                };
+                let k = *value as f64;
+                let (always, never) = match p_type {
+                    PropositionType::LE => (high <= k, low > k),
+                    PropositionType::LS => (high < k, low >= k),
+                    PropositionType::GE => (low >= k, high < k),
+                    PropositionType::GS => (low > k, high <= k),
+                    PropositionType::EQ => (low == k && high == k, k < low || k > high),
+                    PropositionType::NE => (k < low || k > high, low == k && high == k),
+                };
+                if always { Verified } else if never { Unverified } else { Maybe }
+            },
+            _ => Maybe
+        }
+    }
+
+}
@@ -1,20 +1,24 @@
 use core::fmt;
-use std::{collections::{HashMap, HashSet}, hash::{DefaultHasher, Hash, Hasher}, sync::{RwLock, Weak}};
+use std::{cmp::min, collections::{HashMap, HashSet}, hash::{DefaultHasher, Hash, Hasher}, sync::{RwLock, Weak}};
 
 use nalgebra::DVector;
 use num_traits::Zero;
 use serde::{Deserialize, Serialize};
 
-use crate::{computation::{virtual_memory::{EvaluationType, VirtualMemory}, DBM}, models::{action::Action, model_var::ModelVar, petri::PetriNet, time::ClockValue, Label, ModelState, Node}, verification::Verifiable};
+use crate::{computation::{intervals::Convex, virtual_memory::{EvaluationType, VirtualMemory}, DBM}, models::{action::Action, model_var::ModelVar, petri::PetriNet, time::{ClockValue, TimeBound, TimeInterval}, ClassId, ClockId, Label, ModelState, Node, TransitionId}, verification::Verifiable};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StateClass {
     
     pub discrete : VirtualMemory,
     pub dbm : DBM,
-    pub to_dbm_index : Vec<usize>,
-    pub from_dbm_index : Vec<usize>,
-    pub index : usize,
+    // DBM variable of each transition, the reference one if disabled
+    pub to_dbm_index : Vec<ClockId>,
+    // Transition of each DBM variable, the first one being a placeholder for the reference variable
+    pub from_dbm_index : Vec<TransitionId>,
+    // DBM index of each declared clock, stored as the opposite of its value to drift like firing times
+    pub clock_dbm_index : Vec<ClockId>,
+    pub index : ClassId,
 
     #[serde(skip)]
     pub predecessors : RwLock<Vec<(Weak<StateClass>, Action)>>,
@@ -25,13 +29,13 @@ impl StateClass {
     
     pub fn generate_image_state(&self) -> ModelState {
         let deadlocked = self.is_deadlocked();
-        let clocks : Vec<ClockValue> = self.to_dbm_index.iter().enumerate().map(|(_, i)| {
-            if *i == 0 {
+        let clocks : Vec<ClockValue> = self.to_dbm_index.iter().map(|i| {
+            if i.is_reference() {
                 ClockValue::disabled()
             } else {
                 ClockValue::zero()
             }
-        }).collect();
+        }).chain(self.clock_dbm_index.iter().map(|_| ClockValue::zero())).collect();
         ModelState {
             discrete : self.discrete.clone(),
             clocks : DVector::from(clocks),
@@ -40,7 +44,24 @@ impl StateClass {
         }
     }
 
-    pub fn enabled_clocks(&self) -> HashSet<usize> {
+    pub fn id(&self) -> ClassId {
+        self.index
+    }
+
+    // DBM variable of the firing date of a transition, None if it is disabled
+    pub fn clock_of(&self, transition : TransitionId) -> Option<ClockId> {
+        self.to_dbm_index.get(transition.index()).copied().filter(|c| !c.is_reference())
+    }
+
+    // Transition whose firing date is the given DBM variable, None for the reference and declared clocks
+    pub fn transition_of(&self, clock : ClockId) -> Option<TransitionId> {
+        if clock.is_reference() {
+            return None;
+        }
+        self.from_dbm_index.get(clock.index()).copied()
+    }
+
+    pub fn enabled_clocks(&self) -> HashSet<TransitionId> {
         let mut res = HashSet::new();
         if self.from_dbm_index.len() <= 1 {
             return res;
@@ -51,30 +72,58 @@ impl StateClass {
         res
     }
 
+    // Declared clocks are assumed to start at zero
     pub fn compute_class(petri : &PetriNet, state : &ModelState) -> Self {
+        Self::compute_class_with_clocks(petri, state, &HashMap::new()).unwrap()
+    }
+
+    // Class of every state with the given discrete part, clocks (named after their transition or declaration) starting
+    // anywhere in their interval, others at zero. A transition enabled since c in [l, u] fires within [a - u, b - l] :
+    // each firing date only depending on its own clock, the firing domain stays a box. None if no clock value is valid.
+    pub fn compute_class_with_clocks(petri : &PetriNet, state : &ModelState, clocks : &HashMap<Label, TimeInterval>) -> Option<Self> {
         let discrete = state.discrete.clone();
-        let enabled_clocks = state.enabled_clocks().len();
-        let mut dbm = DBM::new(enabled_clocks);
-        let mut to_dbm = vec![0; petri.transitions.len()];
-        let mut from_dbm = vec![0];
-        for (i, transi) in petri.transitions.iter().enumerate() {
+        let enabled_clocks = petri.transitions.iter().filter(|t| state.is_enabled(t.get_clock())).count();
+        let mut dbm = DBM::new(enabled_clocks + petri.declared_clocks.len());
+        let mut to_dbm = vec![ClockId::REFERENCE; petri.transitions.len()];
+        let mut from_dbm = vec![TransitionId(0)];
+        let zero = TimeInterval(TimeBound::zero(), TimeBound::zero());
+        for transi in petri.transitions.iter() {
             if !state.is_enabled(transi.get_clock()) {
                 continue;
             }
             let dbm_index = from_dbm.len();
-            to_dbm[i] = dbm_index;
-            from_dbm.push(i);
-            dbm.add(dbm_index, 0, transi.interval.1);
-            dbm.add(0, dbm_index, -transi.interval.0);
+            to_dbm[transi.index.index()] = ClockId(dbm_index);
+            from_dbm.push(transi.index);
+            // Clocks can't go past the upper bound of their transition
+            let elapsed = clocks.get(&transi.get_label()).copied().unwrap_or(zero)
+                .intersection(TimeInterval(TimeBound::zero(), transi.interval.1));
+            if elapsed.is_empty() {
+                return None;
+            }
+            dbm.add(dbm_index, 0, transi.interval.1 + (-elapsed.0));
+            dbm.add(0, dbm_index, min(-transi.interval.0 + elapsed.1, TimeBound::zero()));
         }
-        StateClass {
+        let clock_dbm : Vec<ClockId> = (0..petri.declared_clocks.len()).map(|k| ClockId(from_dbm.len() + k)).collect();
+        for (dbm_index, clock) in clock_dbm.iter().zip(petri.declared_clocks.iter()) {
+            let value = clocks.get(&clock.name).copied().unwrap_or(zero).intersection(TimeInterval(TimeBound::zero(), TimeBound::Infinite));
+            if value.is_empty() {
+                return None;
+            }
+            dbm.add(dbm_index.index(), 0, -value.0);
+            dbm.add(0, dbm_index.index(), value.1);
+        }
+        if dbm.is_empty() {
+            return None;
+        }
+        Some(StateClass {
             discrete,
             dbm,
             to_dbm_index : to_dbm,
             from_dbm_index : from_dbm,
+            clock_dbm_index : clock_dbm,
             predecessors : Default::default(),
-            index : 0,
-        }
+            index : ClassId(0),
+        })
     }
 
     pub fn get_hash(&self) -> u64 {
@@ -92,7 +141,7 @@ impl Verifiable for StateClass {
     }
 
     fn is_deadlocked(&self) -> bool {
-        self.dbm.vars_count() == 0 || self.dbm.is_empty() // DBM should not be empty in a state class !
+        self.from_dbm_index.len() <= 1 || self.dbm.is_empty() // DBM should not be empty in a state class !
     }
 
 }
@@ -122,15 +171,15 @@ impl fmt::Display for StateClass {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut transitions = String::from("");
         if self.from_dbm_index.len() > 1 {
-            transitions = self.from_dbm_index[1..].iter().map(|i| i.to_string()).collect::<Vec<String>>().join(",");
+            transitions = self.from_dbm_index[1..].iter().map(|i| i.index().to_string()).collect::<Vec<String>>().join(",");
         }
-        write!(f, "Class_{}\n- Marking {}\n- Transitions\n  [{}]\n\n- {}", self.index, self.discrete, transitions, self.dbm)
+        write!(f, "{}\n- Marking {}\n- Transitions\n  [{}]\n\n- {}", self.index, self.discrete, transitions, self.dbm)
     }
 }
 
 impl Node for StateClass {
     fn get_label(&self) -> Label {
-        Label::from("Class_:".to_owned() + &self.index.to_string())
+        Label::from("Class_:".to_owned() + &self.index.index().to_string())
     }
 }
 
@@ -141,6 +190,7 @@ impl Clone for StateClass {
             dbm : self.dbm.clone(),
             to_dbm_index : self.to_dbm_index.clone(),
             from_dbm_index : self.from_dbm_index.clone(),
+            clock_dbm_index : self.clock_dbm_index.clone(),
             index : self.index,
             predecessors : Default::default(),
         }
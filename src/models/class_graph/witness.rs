@@ -0,0 +1,289 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, fmt};
+
+use rand::Rng;
+
+use crate::{computation::DBM, models::{model_clock::ModelClock, time::TimeBound, ClassId, Label, TransitionId}, solution::TimedTrace};
+
+use super::{ClassGraph, StateClass};
+
+/// Constraint between two firing dates of a symbolic run : low <= d_step - d_since <= high, d_0 = 0 being the start of the run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FiringConstraint {
+    pub step : usize,
+    pub since : usize,
+    pub low : TimeBound,
+    pub high : TimeBound,
+}
+
+/// Firing dates a path of the class graph can be run with : for each step, the transition fired and the
+/// constraints on its date, those implied by the bounds of the absolute dates being left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiringConstraints {
+    pub transitions : Vec<Label>,
+    pub constraints : Vec<FiringConstraint>,
+}
+
+impl FiringConstraints {
+
+    pub fn step(&self, step : usize) -> Vec<&FiringConstraint> {
+        self.constraints.iter().filter(|c| c.step == step).collect()
+    }
+
+}
+
+impl fmt::Display for FiringConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let date = if self.since == 0 { format!("d{}", self.step) } else { format!("d{} - d{}", self.step, self.since) };
+        match (self.low, self.high) {
+            (TimeBound::Large(a), TimeBound::Large(b)) if a == b => return write!(f, "{} = {}", date, a),
+            (TimeBound::Large(a), _) => write!(f, "{} <= ", a)?,
+            (TimeBound::Strict(a), _) => write!(f, "{} < ", a)?,
+            _ => ()
+        }
+        write!(f, "{}", date)?;
+        match self.high {
+            TimeBound::Large(b) => write!(f, " <= {}", b),
+            TimeBound::Strict(b) => write!(f, " < {}", b),
+            _ => Ok(())
+        }
+    }
+}
+
+// One line per step : the transition fired at date d_k, and the constraints on d_k
+impl fmt::Display for FiringConstraints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (k, transition) in self.transitions.iter().enumerate() {
+            let constraints : Vec<String> = self.step(k + 1).iter().map(|c| c.to_string()).collect();
+            if k > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} at d{} : {}", transition, k + 1, constraints.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl ClassGraph {
+
+    // Transitions fired from the initial class to reach the given one, following the exploration tree
+    pub fn path_to(&self, class_index : ClassId) -> Option<Vec<TransitionId>> {
+        let mut path = Vec::new();
+        let mut current = class_index;
+        while current != ClassId(0) {
+            let class = self.classes.get(current.index())?;
+            let (pred, action) = class.predecessors.read().unwrap().first().cloned()?;
+            let t_index = self.transitions.iter().position(|t| t.get_action() == action)?;
+            path.push(TransitionId(t_index));
+            current = pred.upgrade()?.index;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    // Shortest path, in number of firings, from the initial class to a class verifying the predicate.
+    // The exploration tree followed by path_to is depth first, this one is breadth first over every edge of the graph.
+    pub fn shortest_path_to(&self, target : impl Fn(&StateClass) -> bool) -> Option<(ClassId, Vec<TransitionId>)> {
+        let mut successors : Vec<Vec<(ClassId, TransitionId)>> = vec![Vec::new(); self.classes.len()];
+        for class in self.classes.iter() {
+            for (pred, action) in class.predecessors.read().unwrap().iter() {
+                let (Some(pred), Some(t_index)) = (pred.upgrade(), self.transitions.iter().position(|t| t.get_action() == *action)) else {
+                    continue;
+                };
+                successors[pred.index.index()].push((class.index, TransitionId(t_index)));
+            }
+        }
+        let mut reached_by : Vec<Option<(ClassId, TransitionId)>> = vec![None; self.classes.len()];
+        let mut visited = vec![false; self.classes.len()];
+        let mut to_see = VecDeque::from([ClassId(0)]);
+        visited[0] = true;
+        while let Some(current) = to_see.pop_front() {
+            if target(&self.classes[current.index()]) {
+                let mut path = Vec::new();
+                let mut class = current;
+                while let Some((pred, t_index)) = reached_by[class.index()] {
+                    path.push(t_index);
+                    class = pred;
+                }
+                path.reverse();
+                return Some((current, path));
+            }
+            for (next, t_index) in successors[current.index()].iter() {
+                if !visited[next.index()] {
+                    visited[next.index()] = true;
+                    reached_by[next.index()] = Some((current, *t_index));
+                    to_see.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+
+    // Transitions whose clock is reset when firing the given one, i.e. the fired one and those downstream of a changed place.
+    // Transitions reading a changed place keep their clock, unless disabled.
+    fn reset_transitions(&self, t_index : TransitionId) -> HashSet<TransitionId> {
+        let transition = &self.transitions[t_index.index()];
+        let mut places = Vec::new();
+        for edge in transition.input_edges.read().unwrap().iter() {
+            places.push(edge.get_node_from());
+        }
+        for edge in transition.output_edges.read().unwrap().iter() {
+            places.push(edge.get_node_to());
+        }
+        for edge in transition.flush_edges.read().unwrap().iter() {
+            places.push(edge.get_node_to());
+        }
+        for edge in transition.weighted_input_edges.read().unwrap().iter() {
+            places.push(edge.get_node_from());
+        }
+        for edge in transition.weighted_output_edges.read().unwrap().iter() {
+            places.push(edge.get_node_to());
+        }
+        places.iter().flat_map(|p| p.get_downstream_transitions()).map(|t| t.index).chain([t_index]).collect()
+    }
+
+    // Concrete delays realizing a path of the class graph. Firing dates d_1..d_n are constrained in a DBM
+    // (d_0 = 0 being the reference) : each transition fires within its interval since its enabling date,
+    // and no enabled transition can exceed its upper bound. Guards on declared clocks are measured from their last reset.
+    // A point of the zone is then picked date by date.
+    pub fn concrete_trace(&self, class_index : ClassId) -> Option<TimedTrace> {
+        self.path_trace(&self.path_to(class_index)?)
+    }
+
+    pub fn path_trace(&self, path : &[TransitionId]) -> Option<TimedTrace> {
+        let zone = self.dates_zone(path)?;
+        Some(self.timed_trace(path, &Self::pick_dates(&zone)))
+    }
+
+    // Same as concrete_trace, firing dates being drawn uniformly in the zone instead of the earliest ones
+    pub fn random_trace<R : Rng + ?Sized>(&self, class_index : ClassId, rng : &mut R) -> Option<TimedTrace> {
+        let path = self.path_to(class_index)?;
+        let zone = self.dates_zone(&path)?;
+        let dates : Vec<f64> = [0.0].into_iter().chain(zone.sample_uniform(rng)?).collect();
+        Some(self.timed_trace(&path, &dates))
+    }
+
+    // Readable constraints on the firing dates of a path, None if the path can't be realized. Absolute dates are
+    // always given, relative ones only when tighter than the difference of the absolute bounds (the zone being canonical).
+    pub fn firing_constraints(&self, path : &[TransitionId]) -> Option<FiringConstraints> {
+        let zone = self.dates_zone(path)?;
+        let mut constraints = Vec::new();
+        for step in 1..=path.len() {
+            constraints.push(FiringConstraint { step, since : 0, low : -zone[(0, step)], high : zone[(step, 0)] });
+            for since in 1..step {
+                let implied_high = zone[(step, since)] == zone[(step, 0)] + zone[(0, since)];
+                let implied_low = zone[(since, step)] == zone[(since, 0)] + zone[(0, step)];
+                if !implied_high || !implied_low {
+                    constraints.push(FiringConstraint { step, since, low : -zone[(since, step)], high : zone[(step, since)] });
+                }
+            }
+        }
+        Some(FiringConstraints {
+            transitions : path.iter().map(|t| self.transitions[t.index()].label.clone()).collect(),
+            constraints
+        })
+    }
+
+    // Zone of the firing dates of a path, None if the path can't be realized
+    pub fn dates_zone(&self, path : &[TransitionId]) -> Option<DBM> {
+        let n = path.len();
+        let mut zone = DBM::new(n);
+        let mut enabled_since : HashMap<TransitionId, usize> = self.classes[0].enabled_clocks().into_iter().map(|t| (t, 0)).collect();
+        let mut reset_at : Vec<usize> = vec![0 ; self.declared_clocks.len()];
+        let mut class = ClassId(0);
+        for (k, t_fired) in path.iter().enumerate() {
+            let step = k + 1;
+            let fired = &self.transitions[t_fired.index()];
+            for (clock, interval) in fired.compiled_clock_guards.iter() {
+                let reset = reset_at[self.declared_clock_position(clock)?];
+                zone[(step, reset)] = zone[(step, reset)].intersection(interval.1);
+                zone[(reset, step)] = zone[(reset, step)].intersection(-interval.0);
+            }
+            for clock in fired.compiled_resets.iter() {
+                reset_at[self.declared_clock_position(clock)?] = step;
+            }
+            // Enabled transitions of higher priority must not be fireable yet
+            for (t, since) in enabled_since.iter() {
+                let transition = &self.transitions[t.index()];
+                let interval = &transition.interval;
+                zone[(step, *since)] = zone[(step, *since)].intersection(interval.1);
+                if t == t_fired {
+                    zone[(*since, step)] = zone[(*since, step)].intersection(-interval.0);
+                } else if transition.priority > fired.priority {
+                    zone[(step, *since)] = zone[(step, *since)].intersection(!interval.0);
+                }
+            }
+            zone[(step - 1, step)] = zone[(step - 1, step)].intersection(TimeBound::Large(0));
+            let next_class = self.successor_index(class, *t_fired)?;
+            let resets = self.reset_transitions(*t_fired);
+            let enabled = self.class(next_class).enabled_clocks();
+            enabled_since.retain(|t, _| enabled.contains(t) && !resets.contains(t));
+            for t in enabled {
+                enabled_since.entry(t).or_insert(step);
+            }
+            class = next_class;
+        }
+        zone.make_canonical();
+        if zone.is_empty() {
+            return None;
+        }
+        Some(zone)
+    }
+
+    // Delays between consecutive firing dates, the first one being the reference date 0
+    fn timed_trace(&self, path : &[TransitionId], dates : &[f64]) -> TimedTrace {
+        let mut trace = TimedTrace::new();
+        for (k, t) in path.iter().enumerate() {
+            trace.push(dates[k + 1] - dates[k], self.transitions[t.index()].label.clone());
+        }
+        trace
+    }
+
+    fn declared_clock_position(&self, clock : &ModelClock) -> Option<usize> {
+        self.declared_clocks.iter().position(|c| c.get_index() == clock.get_index())
+    }
+
+    // Class reached by firing a transition, according to the exploration tree
+    fn successor_index(&self, class_index : ClassId, t_index : TransitionId) -> Option<ClassId> {
+        let action = self.transitions[t_index.index()].get_action();
+        self.classes.iter().find(|c| {
+            c.predecessors.read().unwrap().iter().any(|(pred, a)| {
+                *a == action && pred.upgrade().is_some_and(|p| p.index == class_index)
+            })
+        }).map(|c| c.index)
+    }
+
+    // Earliest dates of a canonical zone, strict bounds being satisfied with a half time unit margin at most
+    fn pick_dates(zone : &DBM) -> Vec<f64> {
+        let n = zone.vars_count();
+        let mut dates = vec![0.0; n + 1];
+        for i in 1..=n {
+            let (mut low, mut low_strict) = (f64::NEG_INFINITY, false);
+            let (mut high, mut high_strict) = (f64::INFINITY, false);
+            for j in 0..i {
+                let lower_bound = zone[(j, i)];
+                let candidate = dates[j] - lower_bound.float();
+                let strict = matches!(lower_bound, TimeBound::Strict(_));
+                if candidate > low || (candidate == low && strict) {
+                    (low, low_strict) = (candidate, strict);
+                }
+                let upper_bound = zone[(i, j)];
+                let candidate = dates[j] + upper_bound.float();
+                let strict = matches!(upper_bound, TimeBound::Strict(_));
+                if candidate < high || (candidate == high && strict) {
+                    (high, high_strict) = (candidate, strict);
+                }
+            }
+            let date = if !low_strict {
+                low
+            } else if high.is_infinite() {
+                low + 0.5
+            } else {
+                low + ((high - low) / 2.0).min(0.5)
+            };
+            // A strict upper bound is never reached, the date being taken halfway between the bounds instead
+            dates[i] = if high_strict && date >= high { (low + high) / 2.0 } else { date };
+        }
+        dates
+    }
+
+}
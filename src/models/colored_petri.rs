@@ -0,0 +1,241 @@
+use std::{collections::{HashMap, HashSet}, fmt, hash::{DefaultHasher, Hash, Hasher}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::computation::{combinatory::CartesianProduct, random::SimulationRng, virtual_memory::EvaluationType};
+
+use super::{action::Action, lbl, model_characteristics::*, model_context::ModelContext, model_var::ModelVar, petri::{PetriNet, PetriPlace, PetriTransition}, time::ClockValue, CompilationError, CompilationResult, Label, Model, ModelMeta, ModelState, StateBatch};
+
+mod color;
+mod color_expression;
+mod colored_place;
+mod colored_transition;
+
+pub use color::{Color, ColorType};
+pub use color_expression::{ArcExpression, ArcTerm, Binding, ColorGuard, ColorTerm, ColorVariables};
+pub use colored_place::ColoredPlace;
+pub use colored_transition::ColoredTransition;
+
+/// Tokens of each color, for every place of a colored net
+pub type ColoredMarking = HashMap<Label, Vec<(Color, EvaluationType)>>;
+
+/// Colored Petri net : tokens carry colors, arcs consume and produce multisets of colors depending on the binding of
+/// the transition variables, and guards restrict the allowed bindings. The net is simulated and verified through its
+/// unfolding into a plain PetriNet, computed when compiled : place p holding color c becomes place "p.c", and each
+/// binding b of transition t satisfying its guard becomes transition "t.b".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColoredPetriNet {
+    #[serde(skip)]
+    pub id : usize,
+    pub variables : Vec<(Label, ColorType)>,
+    pub places : Vec<ColoredPlace>,
+    pub transitions : Vec<ColoredTransition>,
+    #[serde(skip)]
+    pub unfolded : Option<PetriNet>,
+}
+
+impl ColoredPetriNet {
+
+    pub fn new(variables : Vec<(Label, ColorType)>, places : Vec<ColoredPlace>, transitions : Vec<ColoredTransition>) -> Self {
+        ColoredPetriNet {
+            id : usize::MAX,
+            variables,
+            places,
+            transitions,
+            unfolded : None
+        }
+    }
+
+    pub fn unfolded_place(place : &Label, color : &Color) -> Label {
+        place.clone() + "." + color
+    }
+
+    pub fn get_variables(&self) -> ColorVariables {
+        self.variables.iter().cloned().collect()
+    }
+
+    pub fn place_type(&self, place : &Label) -> Option<&ColorType> {
+        self.places.iter().find(|p| p.name == *place).map(|p| &p.color_type)
+    }
+
+    // Bindings of the transition variables satisfying its guard
+    pub fn bindings(&self, transition : &ColoredTransition) -> CompilationResult<Vec<Binding>> {
+        let variables = self.get_variables();
+        let names : Vec<Label> = transition.vars().into_iter().collect();
+        let domains = names.iter().map(|name| {
+            variables.get(name).map(ColorType::colors).ok_or(CompilationError)
+        }).collect::<CompilationResult<Vec<Vec<Color>>>>()?;
+        let bindings : Vec<Binding> = if names.is_empty() {
+            vec![Binding::new()]
+        } else if domains.iter().any(Vec::is_empty) {
+            Vec::new()
+        } else {
+            CartesianProduct::of(&domains).map(|colors| {
+                names.iter().cloned().zip(colors.into_iter().cloned()).collect()
+            }).collect()
+        };
+        Ok(bindings.into_iter().filter(|binding| transition.guard.is_satisfied(binding, &variables)).collect())
+    }
+
+    fn unfold_arcs(&self, arcs : &[(Label, ArcExpression)], binding : &Binding, variables : &ColorVariables) -> CompilationResult<Vec<Label>> {
+        let mut places = Vec::new();
+        for (place, expression) in arcs.iter() {
+            let place_type = self.place_type(place).ok_or(CompilationError)?;
+            let tokens = expression.evaluate(binding, variables, place_type).ok_or(CompilationError)?;
+            for (color, multiplicity) in tokens {
+                let unfolded = Self::unfolded_place(place, &color);
+                places.extend(std::iter::repeat_n(unfolded, multiplicity as usize));
+            }
+        }
+        Ok(places)
+    }
+
+    // Equivalent plain net, fails if an arc refers to an unknown place or variable, or produces a color out of its place type
+    pub fn unfold(&self) -> CompilationResult<PetriNet> {
+        let variables = self.get_variables();
+        let places : Vec<PetriPlace> = self.places.iter().flat_map(|place| {
+            place.color_type.colors().into_iter().map(|color| {
+                PetriPlace::new(Self::unfolded_place(&place.name, &color)).with_metadata(place.metadata.clone())
+            })
+        }).collect();
+        let mut transitions : Vec<PetriTransition> = Vec::new();
+        for transition in self.transitions.iter() {
+            for binding in self.bindings(transition)? {
+                let mut label = transition.label.clone();
+                for var in transition.vars() {
+                    label += format!(".{}", binding[&var]);
+                }
+                let from = self.unfold_arcs(&transition.inputs, &binding, &variables)?;
+                let to = self.unfold_arcs(&transition.outputs, &binding, &variables)?;
+                let mut unfolded = PetriTransition::new(label, from, to, transition.interval)
+                    .with_metadata(transition.metadata.clone());
+                unfolded.controllable = transition.controllable;
+                transitions.push(unfolded);
+            }
+        }
+        Ok(PetriNet::new(places, transitions))
+    }
+
+    // Marking of the unfolded net
+    pub fn unfold_marking(&self, marking : &ColoredMarking) -> HashMap<Label, EvaluationType> {
+        let mut unfolded : HashMap<Label, EvaluationType> = HashMap::new();
+        for (place, tokens) in marking.iter() {
+            for (color, count) in tokens.iter() {
+                *unfolded.entry(Self::unfolded_place(place, color)).or_insert(0) += *count;
+            }
+        }
+        unfolded
+    }
+
+    fn unfolded(&self) -> &PetriNet {
+        match &self.unfolded {
+            None => panic!("Colored Petri net must be compiled before use !"),
+            Some(net) => net
+        }
+    }
+
+}
+
+impl Model for ColoredPetriNet {
+
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        self.unfolded().next(state, action)
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        self.unfolded().available_actions(state)
+    }
+
+    fn successors(&self, state : &ModelState) -> Vec<ModelState> {
+        self.unfolded().successors(state)
+    }
+
+    fn available_delay(&self, state : &ModelState) -> ClockValue {
+        self.unfolded().available_delay(state)
+    }
+
+    fn delay(&self, state : ModelState, dt : ClockValue) -> Option<ModelState> {
+        self.unfolded().delay(state, dt)
+    }
+
+    fn init_initial_clocks(&self, state : ModelState) -> ModelState {
+        self.unfolded().init_initial_clocks(state)
+    }
+
+    fn init_initial_storage(&self, state : ModelState) -> ModelState {
+        self.unfolded().init_initial_storage(state)
+    }
+
+    fn vars_written(&self, state : ModelState, vars : &[ModelVar]) -> ModelState {
+        self.unfolded().vars_written(state, vars)
+    }
+
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        self.unfolded().random_next(state, rng)
+    }
+
+    fn batch_random_next(&self, batch : &mut StateBatch, active : &[bool], rng : &mut SimulationRng) -> Vec<Option<(ClockValue, Option<Action>)>> {
+        self.unfolded().batch_random_next(batch, active, rng)
+    }
+
+    fn get_meta() -> ModelMeta {
+        ModelMeta {
+            name : lbl("CPN"),
+            description : String::from("Colored Petri net, tokens carry colors and transitions fire for bindings of their variables."),
+            characteristics : TIMED | CONTROLLABLE,
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        true
+    }
+
+    fn is_stochastic(&self) -> bool {
+        false
+    }
+
+    fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        let mut unfolded = self.unfold()?;
+        unfolded.compile(context)?;
+        self.id = unfolded.get_id();
+        self.unfolded = Some(unfolded);
+        Ok(())
+    }
+
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn structure_hash(&self) -> Option<u64> {
+        let json = serde_json::to_string(self).ok()?;
+        let mut s = DefaultHasher::new();
+        json.hash(&mut s);
+        Some(s.finish())
+    }
+
+}
+
+impl fmt::Display for ColoredPetriNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ColoredPetriNet")?;
+        writeln!(f, "Variables :")?;
+        for (name, color_type) in self.variables.iter() {
+            writeln!(f, "  {} : {}", name, color_type)?;
+        }
+        writeln!(f, "Places :")?;
+        for place in self.places.iter() {
+            writeln!(f, "  {} : {}", place.name, place.color_type)?;
+        }
+        writeln!(f, "Transitions :")?;
+        for transition in self.transitions.iter() {
+            let inputs : Vec<String> = transition.inputs.iter().map(|(p, _)| p.to_string()).collect();
+            let outputs : Vec<String> = transition.outputs.iter().map(|(p, _)| p.to_string()).collect();
+            write!(f, "  {} {} : [{}] -> [{}]", transition.label, transition.interval, inputs.join(", "), outputs.join(", "))?;
+            if transition.guard != ColorGuard::True {
+                write!(f, " | guard {:?}", transition.guard)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{computation::combinatory::CartesianProduct, models::Label};
+
+/// Value of a colored token
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Color {
+    Dot,
+    Named(Label),
+    Int(i32),
+    Tuple(Vec<Color>),
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Color::Dot => write!(f, "dot"),
+            Color::Named(name) => write!(f, "{}", name),
+            Color::Int(value) => write!(f, "{}", value),
+            Color::Tuple(colors) => {
+                let colors : Vec<String> = colors.iter().map(Color::to_string).collect();
+                write!(f, "{}", colors.join("."))
+            }
+        }
+    }
+}
+
+/// Finite set of colors a place can hold or a variable can be bound to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorType {
+    Dot,
+    Enumeration(Vec<Label>),
+    Range(i32, i32),
+    Product(Vec<ColorType>),
+}
+
+impl ColorType {
+
+    // Every color of the type, in declaration order
+    pub fn colors(&self) -> Vec<Color> {
+        match self {
+            ColorType::Dot => vec![Color::Dot],
+            ColorType::Enumeration(names) => names.iter().cloned().map(Color::Named).collect(),
+            ColorType::Range(low, high) => (*low..=*high).map(Color::Int).collect(),
+            ColorType::Product(types) => {
+                let components : Vec<Vec<Color>> = types.iter().map(ColorType::colors).collect();
+                if components.is_empty() {
+                    return vec![Color::Tuple(Vec::new())];
+                }
+                if components.iter().any(Vec::is_empty) {
+                    return Vec::new();
+                }
+                CartesianProduct::of(&components).map(|tuple| {
+                    Color::Tuple(tuple.into_iter().cloned().collect())
+                }).collect()
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            ColorType::Dot => 1,
+            ColorType::Enumeration(names) => names.len(),
+            ColorType::Range(low, high) => (*high - *low + 1).max(0) as usize,
+            ColorType::Product(types) => types.iter().map(ColorType::size).product(),
+        }
+    }
+
+    pub fn contains(&self, color : &Color) -> bool {
+        match (self, color) {
+            (ColorType::Dot, Color::Dot) => true,
+            (ColorType::Enumeration(names), Color::Named(name)) => names.contains(name),
+            (ColorType::Range(low, high), Color::Int(value)) => low <= value && value <= high,
+            (ColorType::Product(types), Color::Tuple(colors)) => {
+                types.len() == colors.len() && types.iter().zip(colors.iter()).all(|(t, c)| t.contains(c))
+            },
+            _ => false
+        }
+    }
+
+    // Next color of the type, cyclically. Tuples have no successor
+    pub fn successor(&self, color : &Color) -> Option<Color> {
+        self.shift(color, 1)
+    }
+
+    pub fn predecessor(&self, color : &Color) -> Option<Color> {
+        self.shift(color, -1)
+    }
+
+    fn shift(&self, color : &Color, offset : i32) -> Option<Color> {
+        if !self.contains(color) {
+            return None;
+        }
+        match (self, color) {
+            (ColorType::Dot, _) => Some(Color::Dot),
+            (ColorType::Enumeration(names), Color::Named(name)) => {
+                let index = names.iter().position(|n| n == name)? as i32;
+                let next = (index + offset).rem_euclid(names.len() as i32);
+                Some(Color::Named(names[next as usize].clone()))
+            },
+            (ColorType::Range(low, high), Color::Int(value)) => {
+                let next = (value - low + offset).rem_euclid(high - low + 1);
+                Some(Color::Int(low + next))
+            },
+            _ => None
+        }
+    }
+
+}
+
+impl fmt::Display for ColorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorType::Dot => write!(f, "dot"),
+            ColorType::Enumeration(names) => {
+                let names : Vec<String> = names.iter().map(Label::to_string).collect();
+                write!(f, "{{{}}}", names.join(", "))
+            },
+            ColorType::Range(low, high) => write!(f, "[{}..{}]", low, high),
+            ColorType::Product(types) => {
+                let types : Vec<String> = types.iter().map(ColorType::to_string).collect();
+                write!(f, "{}", types.join(" * "))
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Label;
+
+use super::{Color, ColorType};
+
+/// Colors bound to the variables of a transition
+pub type Binding = HashMap<Label, Color>;
+
+/// Declared variables and their types
+pub type ColorVariables = HashMap<Label, ColorType>;
+
+/// Expression designating a single color, given a binding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ColorTerm {
+    Var(Label),
+    Constant(Color),
+    Successor(Box<ColorTerm>),
+    Predecessor(Box<ColorTerm>),
+    Tuple(Vec<ColorTerm>),
+}
+
+impl ColorTerm {
+
+    pub fn var(name : &str) -> Self {
+        ColorTerm::Var(Label::from(name))
+    }
+
+    pub fn successor(self) -> Self {
+        ColorTerm::Successor(Box::new(self))
+    }
+
+    pub fn predecessor(self) -> Self {
+        ColorTerm::Predecessor(Box::new(self))
+    }
+
+    pub fn vars(&self) -> BTreeSet<Label> {
+        match self {
+            ColorTerm::Var(name) => BTreeSet::from([name.clone()]),
+            ColorTerm::Constant(_) => BTreeSet::new(),
+            ColorTerm::Successor(term) | ColorTerm::Predecessor(term) => term.vars(),
+            ColorTerm::Tuple(terms) => terms.iter().flat_map(ColorTerm::vars).collect(),
+        }
+    }
+
+    // Type of the term, inferred from its variables. Constants alone have no type
+    pub fn color_type(&self, variables : &ColorVariables) -> Option<ColorType> {
+        match self {
+            ColorTerm::Var(name) => variables.get(name).cloned(),
+            ColorTerm::Constant(_) => None,
+            ColorTerm::Successor(term) | ColorTerm::Predecessor(term) => term.color_type(variables),
+            ColorTerm::Tuple(terms) => {
+                let types : Option<Vec<ColorType>> = terms.iter().map(|t| t.color_type(variables)).collect();
+                Some(ColorType::Product(types?))
+            }
+        }
+    }
+
+    pub fn evaluate(&self, binding : &Binding, variables : &ColorVariables) -> Option<Color> {
+        match self {
+            ColorTerm::Var(name) => binding.get(name).cloned(),
+            ColorTerm::Constant(color) => Some(color.clone()),
+            ColorTerm::Successor(term) => term.color_type(variables)?.successor(&term.evaluate(binding, variables)?),
+            ColorTerm::Predecessor(term) => term.color_type(variables)?.predecessor(&term.evaluate(binding, variables)?),
+            ColorTerm::Tuple(terms) => {
+                let colors : Option<Vec<Color>> = terms.iter().map(|t| t.evaluate(binding, variables)).collect();
+                Some(Color::Tuple(colors?))
+            }
+        }
+    }
+
+}
+
+/// Term of an arc expression : k tokens of a color, or k tokens of every color of the place
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ArcTerm {
+    Term(i32, ColorTerm),
+    All(i32),
+}
+
+/// Multiset of colors consumed or produced by an arc, as a sum of terms
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArcExpression(pub Vec<ArcTerm>);
+
+impl ArcExpression {
+
+    pub fn new() -> Self {
+        ArcExpression(Vec::new())
+    }
+
+    // Single token of the given color
+    pub fn of(term : ColorTerm) -> Self {
+        ArcExpression(vec![ArcTerm::Term(1, term)])
+    }
+
+    pub fn dot() -> Self {
+        Self::of(ColorTerm::Constant(Color::Dot))
+    }
+
+    pub fn with_term(mut self, multiplicity : i32, term : ColorTerm) -> Self {
+        self.0.push(ArcTerm::Term(multiplicity, term));
+        self
+    }
+
+    pub fn with_all(mut self, multiplicity : i32) -> Self {
+        self.0.push(ArcTerm::All(multiplicity));
+        self
+    }
+
+    pub fn vars(&self) -> BTreeSet<Label> {
+        self.0.iter().flat_map(|term| match term {
+            ArcTerm::Term(_, term) => term.vars(),
+            ArcTerm::All(_) => BTreeSet::new()
+        }).collect()
+    }
+
+    // Tokens of each color, None if a term is ill-typed for the place or has a negative multiplicity
+    pub fn evaluate(&self, binding : &Binding, variables : &ColorVariables, place_type : &ColorType) -> Option<Vec<(Color, i32)>> {
+        let mut tokens : Vec<(Color, i32)> = Vec::new();
+        let mut add = |color : Color, multiplicity : i32| {
+            match tokens.iter_mut().find(|(c, _)| *c == color) {
+                Some((_, count)) => *count += multiplicity,
+                None => tokens.push((color, multiplicity))
+            }
+        };
+        for term in self.0.iter() {
+            match term {
+                ArcTerm::Term(multiplicity, term) => {
+                    let color = term.evaluate(binding, variables)?;
+                    if *multiplicity < 0 || !place_type.contains(&color) {
+                        return None;
+                    }
+                    add(color, *multiplicity);
+                },
+                ArcTerm::All(multiplicity) => {
+                    if *multiplicity < 0 {
+                        return None;
+                    }
+                    for color in place_type.colors() {
+                        add(color, *multiplicity);
+                    }
+                }
+            }
+        }
+        tokens.retain(|(_, count)| *count > 0);
+        Some(tokens)
+    }
+
+}
+
+/// Condition over the binding of a transition. Order comparisons only hold between integer colors
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ColorGuard {
+    #[default]
+    True,
+    Eq(ColorTerm, ColorTerm),
+    Ne(ColorTerm, ColorTerm),
+    Less(ColorTerm, ColorTerm),
+    And(Box<ColorGuard>, Box<ColorGuard>),
+    Or(Box<ColorGuard>, Box<ColorGuard>),
+    Not(Box<ColorGuard>),
+}
+
+impl ColorGuard {
+
+    pub fn and(self, other : ColorGuard) -> Self {
+        ColorGuard::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other : ColorGuard) -> Self {
+        ColorGuard::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn negate(self) -> Self {
+        ColorGuard::Not(Box::new(self))
+    }
+
+    pub fn vars(&self) -> BTreeSet<Label> {
+        match self {
+            ColorGuard::True => BTreeSet::new(),
+            ColorGuard::Eq(t1, t2) | ColorGuard::Ne(t1, t2) | ColorGuard::Less(t1, t2) => {
+                t1.vars().union(&t2.vars()).cloned().collect()
+            },
+            ColorGuard::And(g1, g2) | ColorGuard::Or(g1, g2) => g1.vars().union(&g2.vars()).cloned().collect(),
+            ColorGuard::Not(g) => g.vars(),
+        }
+    }
+
+    // Terms that can't be evaluated make their comparison false
+    pub fn is_satisfied(&self, binding : &Binding, variables : &ColorVariables) -> bool {
+        let eval = |t : &ColorTerm| t.evaluate(binding, variables);
+        match self {
+            ColorGuard::True => true,
+            ColorGuard::Eq(t1, t2) => matches!((eval(t1), eval(t2)), (Some(c1), Some(c2)) if c1 == c2),
+            ColorGuard::Ne(t1, t2) => matches!((eval(t1), eval(t2)), (Some(c1), Some(c2)) if c1 != c2),
+            ColorGuard::Less(t1, t2) => matches!((eval(t1), eval(t2)), (Some(Color::Int(x1)), Some(Color::Int(x2))) if x1 < x2),
+            ColorGuard::And(g1, g2) => g1.is_satisfied(binding, variables) && g2.is_satisfied(binding, variables),
+            ColorGuard::Or(g1, g2) => g1.is_satisfied(binding, variables) || g2.is_satisfied(binding, variables),
+            ColorGuard::Not(g) => !g.is_satisfied(binding, variables),
+        }
+    }
+
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Label, Node, NodeMetadata};
+
+use super::ColorType;
+
+/// Place of a colored net, holding tokens of a single color type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColoredPlace {
+    pub name : Label,
+    pub color_type : ColorType,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+}
+
+impl ColoredPlace {
+
+    pub fn new(name : Label, color_type : ColorType) -> Self {
+        ColoredPlace { name, color_type, metadata : Default::default() }
+    }
+
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+}
+
+impl Node for ColoredPlace {
+
+    fn get_label(&self) -> Label {
+        self.name.clone()
+    }
+
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{computation::intervals::Convex, models::{time::TimeInterval, Label, Node, NodeMetadata}};
+
+use super::{ArcExpression, ColorGuard};
+
+/// Transition of a colored net. Each binding of its variables satisfying the guard is a plain transition once unfolded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColoredTransition {
+    pub label : Label,
+    pub inputs : Vec<(Label, ArcExpression)>,
+    pub outputs : Vec<(Label, ArcExpression)>,
+    #[serde(default)]
+    pub guard : ColorGuard,
+    pub interval : TimeInterval,
+    pub controllable : bool,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+}
+
+impl ColoredTransition {
+
+    pub fn new(label : Label, interval : TimeInterval) -> Self {
+        ColoredTransition {
+            label,
+            inputs : Vec::new(),
+            outputs : Vec::new(),
+            guard : ColorGuard::True,
+            interval,
+            controllable : true,
+            metadata : Default::default()
+        }
+    }
+
+    pub fn new_untimed(label : Label) -> Self {
+        Self::new(label, TimeInterval::full())
+    }
+
+    pub fn with_input(mut self, place : Label, expression : ArcExpression) -> Self {
+        self.inputs.push((place, expression));
+        self
+    }
+
+    pub fn with_output(mut self, place : Label, expression : ArcExpression) -> Self {
+        self.outputs.push((place, expression));
+        self
+    }
+
+    pub fn with_guard(mut self, guard : ColorGuard) -> Self {
+        self.guard = guard;
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    // Variables of the arcs and of the guard, in name order
+    pub fn vars(&self) -> BTreeSet<Label> {
+        self.inputs.iter().chain(self.outputs.iter())
+            .flat_map(|(_, expression)| expression.vars())
+            .chain(self.guard.vars())
+            .collect()
+    }
+
+}
+
+impl Node for ColoredTransition {
+
+    fn get_label(&self) -> Label {
+        self.label.clone()
+    }
+
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
+}
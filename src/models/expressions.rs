@@ -1,8 +1,18 @@
+mod simplification;
+mod numeric;
+mod past;
+mod mapping;
+mod printing;
+pub use numeric::{Numeric, FloatValue};
+pub use past::PastMemory;
+pub use mapping::ContextMapper;
+pub use simplification::Simplifier;
+
 use std::{collections::HashSet, hash::Hash, ops::Not};
 
-use crate::QueryVisitor;
+use crate::{QueryVisitor, QueryTransformer, Query};
 
-use crate::verification::{Verifiable, VerificationStatus};
+use crate::verification::{coverage::record_literal, Verifiable, VerificationStatus};
 use serde::{Deserialize, Serialize};
 use VerificationStatus::*;
 
@@ -17,11 +27,20 @@ use PropositionType::*;
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Expr {
     Var(ModelVar),
+    // Element of an array variable
+    Index(ModelVar, Box<Expr>),
+    // Sum of the elements of an array variable
+    Sum(ModelVar),
     Constant(i32),
+    FloatConstant(FloatValue),
     ClockComparison(PropositionType, ModelClock, i32),
+    // Ages of the tokens of a timed-arc place : age of the oldest one, number of tokens aged in [a, b]
+    OldestAge(TokenPlace),
+    AgedTokens(TokenPlace, i32, i32),
     Plus(Box<Expr>, Box<Expr>),
     Minus(Box<Expr>, Box<Expr>),
     Multiply(Box<Expr>, Box<Expr>),
+    Divide(Box<Expr>, Box<Expr>),
     Negative(Box<Expr>),
     Modulo(Box<Expr>, Box<Expr>),
     Pow(Box<Expr>, Box<Expr>)
@@ -31,24 +50,37 @@ use Expr::*;
 
 impl Expr {
 
-    pub fn evaluate(&self, state : &impl Verifiable) -> i32 {
+    pub fn evaluate(&self, state : &impl Verifiable) -> Numeric {
         match self {
-            Constant(i) => *i,
-            Var(x) => x.evaluate(state),
-            ClockComparison(prop_type, clock, value) => match prop_type {
+            Constant(i) => Numeric::Int(*i),
+            FloatConstant(x) => Numeric::Float(x.0),
+            Var(x) => Numeric::Int(x.evaluate(state)),
+            // Elements out of the array are undefined, as NaN : they are neither equal, lower nor greater than any value
+            Index(x, i) => {
+                let index = i.evaluate(state).as_int();
+                match usize::try_from(index).ok().and_then(|i| x.element(i)) {
+                    Some(element) => Numeric::Int(element.evaluate(state)),
+                    None => Numeric::Float(f64::NAN)
+                }
+            },
+            Sum(x) => Numeric::Int(x.elements().iter().map(|e| e.evaluate(state)).sum()),
+            OldestAge(p) => Numeric::Float(TokenPlace::oldest_age(&state.evaluate_tokens(p))),
+            AgedTokens(p, low, high) => Numeric::Int(TokenPlace::aged_tokens(&state.evaluate_tokens(p), *low, *high)),
+            ClockComparison(prop_type, clock, value) => Numeric::Int(match prop_type {
                 EQ => (state.evaluate_clock(clock) == (*value as f64)) as i32,
                 NE => (state.evaluate_clock(clock) != (*value as f64)) as i32,
                 LE => (state.evaluate_clock(clock) <= (*value as f64)) as i32,
                 GE => (state.evaluate_clock(clock) >= (*value as f64)) as i32,
                 LS => (state.evaluate_clock(clock) < (*value as f64)) as i32,
                 GS => (state.evaluate_clock(clock) > (*value as f64)) as i32,
-            }
+            }),
             Plus(e1, e2) => e1.evaluate(state) + e2.evaluate(state),
             Minus(e1, e2) => e1.evaluate(state) - e2.evaluate(state),
             Multiply(e1, e2) => e1.evaluate(state) * e2.evaluate(state),
+            Divide(e1, e2) => e1.evaluate(state) / e2.evaluate(state),
             Negative(e) => -e.evaluate(state),
             Modulo(e1, e2) => e1.evaluate(state) % e2.evaluate(state),
-            Pow(e1, e2) => e1.evaluate(state).pow(e2.evaluate(state) as u32)
+            Pow(e1, e2) => e1.evaluate(state).pow(e2.evaluate(state))
         }
     }
 
@@ -57,36 +89,37 @@ impl Expr {
             Plus(e1,e2) | 
             Minus(e1, e2) | 
             Multiply(e1,e2) |
+            Divide(e1, e2) |
             Modulo(e1,e2) |
             Pow(e1, e2)
                 => e1.contains_clock_proposition() || e2.contains_clock_proposition(),
-            Negative(e) => e.contains_clock_proposition(),
-            ClockComparison(_,_,_) => true,
+            Negative(e) | Index(_, e) => e.contains_clock_proposition(),
+            ClockComparison(_,_,_) | OldestAge(_) | AgedTokens(_,_,_) => true,
             _ => false,
         }
     }
 
     // Translate Name(x) to Object(m[x])
     pub fn apply_to(&self, ctx : &ModelContext) -> MappingResult<Expr> {
+        let mut expr = self.clone();
+        ContextMapper::new(ctx).map_expression(&mut expr)?;
+        Ok(expr)
+    }
+
+    pub fn transform_children(&mut self, transformer : &mut (impl QueryTransformer + ?Sized)) {
         match self {
-            Var(x) => Ok(Var(x.apply_to(ctx)?)),
-            Plus(e1, e2) => Ok(Plus(
-                Box::new(e1.apply_to(ctx)?), Box::new(e2.apply_to(ctx)?)
-            )),
-            Minus(e1, e2) => Ok(Minus(
-                Box::new(e1.apply_to(ctx)?), Box::new(e2.apply_to(ctx)?)
-            )),
-            Multiply(e1, e2) => Ok(Multiply(
-                Box::new(e1.apply_to(ctx)?), Box::new(e2.apply_to(ctx)?)
-            )),
-            Modulo(e1, e2) => Ok(Modulo(
-                Box::new(e1.apply_to(ctx)?), Box::new(e2.apply_to(ctx)?)
-            )),
-            Pow(e1, e2) => Ok(Pow(
-                Box::new(e1.apply_to(ctx)?), Box::new(e2.apply_to(ctx)?)
-            )),
-            Negative(e) => Ok(Negative(Box::new(e.apply_to(ctx)?))),
-            _ => Ok(self.clone())
+            Plus(e1, e2) |
+            Minus(e1, e2) |
+            Multiply(e1, e2) |
+            Divide(e1, e2) |
+            Modulo(e1, e2) |
+            Pow(e1, e2)
+                => {
+                transformer.transform_expression(e1);
+                transformer.transform_expression(e2);
+            },
+            Negative(e) | Index(_, e) => transformer.transform_expression(e),
+            _ => ()
         }
     }
 
@@ -95,6 +128,7 @@ impl Expr {
             Plus(e1, e2) |
             Minus(e1, e2) |
             Multiply(e1, e2) |
+            Divide(e1, e2) |
             Modulo(e1, e2) |
             Pow(e1, e2)
                 => {
@@ -102,6 +136,10 @@ impl Expr {
                 e1.accept(visitor);
                 e2.accept(visitor);
             },
+            Index(_, e) => {
+                visitor.visit_expression(self);
+                e.accept(visitor);
+            },
             _ => visitor.visit_expression(self)
         }
     }
@@ -121,30 +159,58 @@ pub enum Condition {
     Implies(Box<Condition>, Box<Condition>),
     Next(Box<Condition>),
     Until(Box<Condition>, Box<Condition>),
+    // c1 R c2 : c2 holds up to the first state where c1 holds, if any. c1 W c2 : c1 U c2, c2 being allowed to never hold
+    Release(Box<Condition>, Box<Condition>),
+    WeakUntil(Box<Condition>, Box<Condition>),
+    // Past-time operators, evaluated over the run prefix : c1 S c2, O c (once), H c (historically), Y c (previously)
+    Since(Box<Condition>, Box<Condition>),
+    Once(Box<Condition>),
+    Historically(Box<Condition>),
+    Previously(Box<Condition>),
+    // Probabilistic sub-query, evaluated from the current state by a nested verification
+    Nested(Box<Query>),
 }
 
 use Condition::*;
 
-use super::{model_clock::ModelClock, model_context::ModelContext, model_var::{MappingResult, ModelVar}};
+use super::{model_clock::ModelClock, model_context::ModelContext, model_var::{MappingResult, ModelVar}, tapn::tapn_token::TokenPlace};
 
 impl Condition {
 
     pub fn contains_until(&self) -> bool {
         match self {
-            Until(_, _) => true,
-            Not(c) | Next(c) => c.contains_until(),
+            Until(_, _) | Release(_, _) | WeakUntil(_, _) => true,
+            Not(c) | Next(c) | Once(c) | Historically(c) | Previously(c) => c.contains_until(),
             And(c1,c2) | 
             Or(c1, c2) | 
+            Since(c1, c2) |
             Implies(c1, c2)
                 => c1.contains_until() || c2.contains_until(),
             _ => false
         }
     }
 
+    pub fn contains_nested(&self) -> bool {
+        match self {
+            Nested(_) => true,
+            Not(c) | Next(c) | Once(c) | Historically(c) | Previously(c) => c.contains_nested(),
+            And(c1,c2) | 
+            Or(c1, c2) | 
+            Until(c1, c2) |
+            Release(c1, c2) |
+            WeakUntil(c1, c2) |
+            Since(c1, c2) |
+            Implies(c1, c2)
+                => c1.contains_nested() || c2.contains_nested(),
+            _ => false
+        }
+    }
+
     pub fn is_state_condition(&self) -> bool {
         match self {
-            Until(_, _) => false,
+            Until(_, _) | Release(_, _) | WeakUntil(_, _) => false,
             Next(_) => false,
+            Since(_, _) | Once(_) | Historically(_) | Previously(_) => false,
             Not(c) => c.is_state_condition(),
             And(c1,c2) | 
             Or(c1, c2) | 
@@ -156,59 +222,76 @@ impl Condition {
 
     pub fn contains_clock_proposition(&self) -> bool {
         match self {
-            Next(c) | Not(c) => c.contains_clock_proposition(),
+            Next(c) | Not(c) | Once(c) | Historically(c) | Previously(c) => c.contains_clock_proposition(),
             And(c1,c2) | 
             Or(c1, c2) | 
             Until(c1, c2) |
+            Release(c1, c2) |
+            WeakUntil(c1, c2) |
+            Since(c1, c2) |
             Implies(c1, c2)
                 => c1.contains_clock_proposition() || c2.contains_clock_proposition(),
             Evaluation(e) => e.contains_clock_proposition(),
             Proposition(_, e1, e2) => e1.contains_clock_proposition() || e2.contains_clock_proposition(),
+            Nested(q) => q.condition.contains_clock_proposition(),
             _ => false
         }
     }
 
     pub fn apply_to(&self, ctx : &ModelContext) -> MappingResult<Condition> {
+        let mut condition = self.clone();
+        ContextMapper::new(ctx).map_condition(&mut condition)?;
+        Ok(condition)
+    }
+
+    pub fn transform_children(&mut self, transformer : &mut (impl QueryTransformer + ?Sized)) {
         match self {
-            Evaluation(e) => Ok(Evaluation(e.apply_to(ctx)?)),
-            Proposition(p_type, e1, e2) => Ok(Proposition(
-                *p_type, e1.apply_to(ctx)?, e2.apply_to(ctx)?
-            )),
-            And(c1, c2) => Ok(And(
-                Box::new(c1.apply_to(ctx)?), Box::new(c2.apply_to(ctx)?)
-            )),
-            Or(c1, c2) => Ok(Or(
-                Box::new(c1.apply_to(ctx)?), Box::new(c2.apply_to(ctx)?)
-            )),
-            Not(c) => Ok(Not(Box::new(c.apply_to(ctx)?))),
-            Implies(c1, c2) => Ok(Implies(
-                Box::new(c1.apply_to(ctx)?), Box::new(c2.apply_to(ctx)?)
-            )),
-            Next(c) => Ok(Next(Box::new(c.apply_to(ctx)?))),
-            Until(c1, c2) => Ok(Until(
-                Box::new(c1.apply_to(ctx)?), Box::new(c2.apply_to(ctx)?)
-            )),
-            _ =>Ok(self.clone())
+            Not(c) | Next(c) | Once(c) | Historically(c) | Previously(c) => transformer.transform_condition(c),
+            And(c1,c2) | 
+            Or(c1, c2) | 
+            Until(c1, c2) |
+            Release(c1, c2) |
+            WeakUntil(c1, c2) |
+            Since(c1, c2) |
+            Implies(c1, c2)
+                => {
+                    transformer.transform_condition(c1);
+                    transformer.transform_condition(c2);
+                },
+            Evaluation(e) => transformer.transform_expression(e),
+            Proposition(_, e1, e2) => {
+                transformer.transform_expression(e1);
+                transformer.transform_expression(e2);
+            },
+            Nested(q) => transformer.transform_query(q),
+            _ => ()
         }
     }
 
     pub fn evaluate(&self, state : &impl Verifiable) -> (VerificationStatus, Option<Condition>) {
+        self.evaluate_with(state, &mut |_| Maybe)
+    }
+
+    // Nested queries are delegated to the given resolver, which is expected to answer them from the evaluated state.
+    // Unresolved nested queries (Maybe) are kept pending.
+    pub fn evaluate_with(&self, state : &impl Verifiable, nested : &mut dyn FnMut(&Query) -> VerificationStatus) -> (VerificationStatus, Option<Condition>) {
+        self.evaluate_in(state, nested, &[])
+    }
+
+    // Same as evaluate_with, past-time operators using their values on the previous state of the run
+    pub fn evaluate_in(&self, state : &impl Verifiable, nested : &mut dyn FnMut(&Query) -> VerificationStatus, memory : &[(Condition, bool)]) -> (VerificationStatus, Option<Condition>) {
         match self {
             True => (Verified, None),
             False => (Unverified, None),
             Deadlock => {
-                if state.is_deadlocked() {
-                    (Verified, None)
-                } else {
-                    (Unverified, None)
-                }
+                let status = if state.is_deadlocked() { Verified } else { Unverified };
+                record_literal(self, status);
+                (status, None)
             },
             Evaluation(e) => {
-                if e.evaluate(state) > 0 {
-                    (Verified, None)
-                } else {
-                    (Unverified, None)
-                }
+                let status = if e.evaluate(state).is_true() { Verified } else { Unverified };
+                record_literal(self, status);
+                (status, None)
             },
             Proposition(t, e1, e2) => {
                 let res1 = e1.evaluate(state);
@@ -221,15 +304,13 @@ impl Condition {
                     LS => res1 < res2,
                     GS => res1 > res2,
                 };
-                if prop_res {
-                    (Verified, None)
-                } else {
-                    (Unverified, None)
-                }
+                let status = if prop_res { Verified } else { Unverified };
+                record_literal(self, status);
+                (status, None)
             },
             And(c1, c2) => { 
-                let res1 = c1.evaluate(state);
-                let res2 = c2.evaluate(state);
+                let res1 = c1.evaluate_in(state, nested, memory);
+                let res2 = c2.evaluate_in(state, nested, memory);
                 let status = res1.0 & res2.0;
                 match status {
                     Maybe => (Maybe, match (res1.1, res2.1) {
@@ -243,8 +324,8 @@ impl Condition {
                 
             },
             Or(c1, c2) => {
-                let res1 = c1.evaluate(state);
-                let res2 = c2.evaluate(state);
+                let res1 = c1.evaluate_in(state, nested, memory);
+                let res2 = c2.evaluate_in(state, nested, memory);
                 let status = res1.0 | res2.0;
                 match status {
                     Maybe => (Maybe, match (res1.1, res2.1) {
@@ -257,7 +338,7 @@ impl Condition {
                 }
             },
             Not(c) => {
-                let (status, sub_c) = c.evaluate(state);
+                let (status, sub_c) = c.evaluate_in(state, nested, memory);
                 let status = !status;
                 match status {
                     Maybe => (Maybe, Some(Not(Box::new(sub_c.unwrap())))),
@@ -265,8 +346,8 @@ impl Condition {
                 }
             },
             Implies(c1, c2) => {
-                let res1 = c1.evaluate(state);
-                let res2 = c2.evaluate(state);
+                let res1 = c1.evaluate_in(state, nested, memory);
+                let res2 = c2.evaluate_in(state, nested, memory);
                 let status = (!res1.0) | res2.0;
                 match status {
                     Maybe => (Maybe, match (res1.1, res2.1) {
@@ -283,9 +364,16 @@ impl Condition {
                 }
             },
             Next(c1) => (Maybe, Some(*c1.clone())),
-            Until(c1, c2) => {
-                let res1 = c1.evaluate(state);
-                let res2 = c2.evaluate(state);
+            // Unfolded once, using their values on the previous state (none on the first state of a run)
+            Since(_, _) | Once(_) | Historically(_) | Previously(_) => self.with_past(memory).evaluate_in(state, nested, memory),
+            Nested(q) => match nested(q) {
+                Maybe => (Maybe, Some(self.clone())),
+                status => (status, None)
+            },
+            // Same unfolding for both, only the end of the run tells them apart (see end_status)
+            Until(c1, c2) | WeakUntil(c1, c2) => {
+                let res1 = c1.evaluate_in(state, nested, memory);
+                let res2 = c2.evaluate_in(state, nested, memory);
                 match (res1.0, res2.0) {
                     (_, Verified) => (Verified, None),
                     (Unverified, Unverified) => (Unverified, None),
@@ -309,19 +397,62 @@ impl Condition {
                             Box::new(self.clone())
                         )))
                 }
+            },
+            // Dual of Until : c1 R c2 = c2 & (c1 | X(c1 R c2))
+            Release(c1, c2) => {
+                let res1 = c1.evaluate_in(state, nested, memory);
+                let res2 = c2.evaluate_in(state, nested, memory);
+                match (res1.0, res2.0) {
+                    (_, Unverified) => (Unverified, None),
+                    (Verified, Verified) => (Verified, None),
+                    (Unverified, Verified) => (Maybe, Some(self.clone())),
+                    (Maybe, Verified) => (Maybe, Some(
+                        Or(
+                            Box::new(res1.1.unwrap()),
+                            Box::new(self.clone())
+                        ))),
+                    (Maybe, Maybe) => (Maybe, Some(
+                        And(
+                            Box::new(res2.1.unwrap()),
+                            Box::new(Or(
+                                Box::new(res1.1.unwrap()),
+                                Box::new(self.clone())
+                            ))
+                        ))),
+                    (Verified, Maybe) => (Maybe, Some(res2.1.unwrap())),
+                    (Unverified, Maybe) => (Maybe, Some(And(
+                            Box::new(res2.1.unwrap()),
+                            Box::new(self.clone())
+                        )))
+                }
             }
         }
     }
 
+    // Status of a pending condition when the run ends : weak operators hold, other obligations are left undecided
+    pub fn end_status(&self) -> VerificationStatus {
+        match self {
+            Release(_, _) | WeakUntil(_, _) => Verified,
+            And(c1, c2) => c1.end_status() & c2.end_status(),
+            Or(c1, c2) => c1.end_status() | c2.end_status(),
+            Implies(c1, c2) => (!c1.end_status()) | c2.end_status(),
+            Not(c) => !c.end_status(),
+            _ => Maybe
+        }
+    }
+
     pub fn accept(&self, visitor : &mut impl QueryVisitor) {
         match self {
-            Not(c) | Next(c) => {
+            Not(c) | Next(c) | Once(c) | Historically(c) | Previously(c) => {
                 visitor.visit_condition(self);
                 c.accept(visitor);
             },
             And(c1,c2) | 
             Or(c1, c2) | 
             Until(c1, c2) |
+            Release(c1, c2) |
+            WeakUntil(c1, c2) |
+            Since(c1, c2) |
             Implies(c1, c2)
                 => {
                     visitor.visit_condition(self);
@@ -337,6 +468,10 @@ impl Condition {
                 e1.accept(visitor);
                 e2.accept(visitor);
             },
+            Nested(q) => {
+                visitor.visit_condition(self);
+                q.condition.accept(visitor);
+            },
             _ => visitor.visit_condition(self)
         }
     }
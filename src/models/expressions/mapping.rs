@@ -0,0 +1,87 @@
+use crate::{models::{model_context::ModelContext, model_var::{MappingError, MappingResult, ModelVar}}, Query, QueryTransformer};
+
+use crate::models::Label;
+
+use super::{Condition, Expr};
+
+use Expr::*;
+
+/// Maps variables and clocks of a query, given by name, to the objects of a context.
+/// The walk stops changing the tree at the first mapping error, which is kept.
+/// Constant array indices are checked against the length of their array.
+pub struct ContextMapper<'a> {
+    ctx : &'a ModelContext,
+    error : Option<MappingError>
+}
+
+impl<'a> ContextMapper<'a> {
+
+    pub fn new(ctx : &'a ModelContext) -> Self {
+        ContextMapper { ctx, error : None }
+    }
+
+    pub fn map_expression(&mut self, expr : &mut Expr) -> MappingResult<()> {
+        self.transform_expression(expr);
+        self.result()
+    }
+
+    pub fn map_condition(&mut self, condition : &mut Condition) -> MappingResult<()> {
+        self.transform_condition(condition);
+        self.result()
+    }
+
+    fn result(&mut self) -> MappingResult<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(())
+        }
+    }
+
+    fn check_index(array : ModelVar, index : &Expr) -> MappingResult<ModelVar> {
+        match index.simplify() {
+            Constant(i) if usize::try_from(i).map_or(true, |i| i >= array.length()) =>
+                Err(MappingError(Label::from(format!("{}[{}] (index out of bounds)", array.name, i)))),
+            _ => Ok(array)
+        }
+    }
+
+    fn keep<T>(&mut self, target : &mut T, mapped : MappingResult<T>) {
+        match mapped {
+            Ok(mapped) => *target = mapped,
+            Err(e) => self.error = Some(e)
+        }
+    }
+
+}
+
+impl QueryTransformer for ContextMapper<'_> {
+
+    // Nested queries have their own bounds to map
+    fn transform_query(&mut self, query : &mut Query) {
+        if self.error.is_none() {
+            if let Err(e) = query.apply_to(self.ctx) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn transform_expression(&mut self, expr : &mut Expr) {
+        if self.error.is_some() {
+            return;
+        }
+        let ctx = self.ctx;
+        match expr {
+            Var(x) => self.keep(x, x.apply_to(ctx)),
+            Index(x, i) => {
+                let mapped = x.apply_to_array(ctx).and_then(|x| Self::check_index(x, i));
+                self.keep(x, mapped)
+            },
+            Sum(x) => self.keep(x, x.apply_to_array(ctx)),
+            ClockComparison(_, c, _) => self.keep(c, c.apply_to(ctx)),
+            OldestAge(p) | AgedTokens(p, _, _) => self.keep(p, p.apply_to(ctx)),
+            _ => ()
+        }
+        expr.transform_children(self);
+    }
+
+}
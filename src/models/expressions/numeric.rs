@@ -0,0 +1,152 @@
+use std::{cmp::Ordering, fmt::Display, hash::{Hash, Hasher}, ops::{Add, Div, Mul, Neg, Rem, Sub}};
+
+use serde::{Deserialize, Serialize};
+
+/// Result of an expression evaluation. Integer arithmetic is kept as long as both operands are integers,
+/// a float operand makes the whole operation a float one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Numeric {
+    Int(i32),
+    Float(f64)
+}
+
+use Numeric::*;
+
+impl Numeric {
+
+    pub fn as_float(&self) -> f64 {
+        match self {
+            Int(i) => *i as f64,
+            Float(x) => *x
+        }
+    }
+
+    // Floats are truncated toward zero
+    pub fn as_int(&self) -> i32 {
+        match self {
+            Int(i) => *i,
+            Float(x) => *x as i32
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Float(_))
+    }
+
+    // Truth value of a numeric condition, as for integer evaluations
+    pub fn is_true(&self) -> bool {
+        match self {
+            Int(i) => *i > 0,
+            Float(x) => *x > 0.0
+        }
+    }
+
+    pub fn pow(self, exponent : Numeric) -> Numeric {
+        match (self, exponent) {
+            (Int(a), Int(b)) if b >= 0 => Int(a.pow(b as u32)),
+            (a, b) => Float(a.as_float().powf(b.as_float()))
+        }
+    }
+
+}
+
+// Integer operations, or float ones if any operand is a float
+macro_rules! numeric_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for Numeric {
+            type Output = Numeric;
+            fn $method(self, rhs : Numeric) -> Numeric {
+                match (self, rhs) {
+                    (Int(a), Int(b)) => Int(a $op b),
+                    (a, b) => Float(a.as_float() $op b.as_float())
+                }
+            }
+        }
+    };
+}
+
+numeric_op!(Add, add, +);
+numeric_op!(Sub, sub, -);
+numeric_op!(Mul, mul, *);
+
+// Integer division truncates, a null integer divisor falls back to float division (infinite or NaN)
+impl Div for Numeric {
+    type Output = Numeric;
+    fn div(self, rhs : Numeric) -> Numeric {
+        match (self, rhs) {
+            (Int(a), Int(b)) if b != 0 => Int(a / b),
+            (a, b) => Float(a.as_float() / b.as_float())
+        }
+    }
+}
+
+impl Rem for Numeric {
+    type Output = Numeric;
+    fn rem(self, rhs : Numeric) -> Numeric {
+        match (self, rhs) {
+            (Int(a), Int(b)) if b != 0 => Int(a % b),
+            (a, b) => Float(a.as_float() % b.as_float())
+        }
+    }
+}
+
+impl Neg for Numeric {
+    type Output = Numeric;
+    fn neg(self) -> Numeric {
+        match self {
+            Int(i) => Int(-i),
+            Float(x) => Float(-x)
+        }
+    }
+}
+
+impl PartialEq for Numeric {
+    fn eq(&self, other : &Self) -> bool {
+        match (self, other) {
+            (Int(a), Int(b)) => a == b,
+            (a, b) => a.as_float() == b.as_float()
+        }
+    }
+}
+
+impl PartialOrd for Numeric {
+    fn partial_cmp(&self, other : &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Int(a), Int(b)) => a.partial_cmp(b),
+            (a, b) => a.as_float().partial_cmp(&b.as_float())
+        }
+    }
+}
+
+impl From<i32> for Numeric {
+    fn from(value : i32) -> Self {
+        Int(value)
+    }
+}
+
+impl From<f64> for Numeric {
+    fn from(value : f64) -> Self {
+        Float(value)
+    }
+}
+
+impl Display for Numeric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Int(i) => write!(f, "{}", i),
+            Float(x) => write!(f, "{}", x)
+        }
+    }
+}
+
+/// Float constant of an expression, f64 wrapper to be hashed with queries
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct FloatValue(pub f64);
+
+impl Eq for FloatValue { }
+
+impl Hash for FloatValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
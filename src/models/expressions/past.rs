@@ -0,0 +1,87 @@
+use crate::verification::Verifiable;
+
+use super::Condition;
+
+use Condition::*;
+
+/// Value of each past-time subformula on the previous state of a run
+pub type PastMemory = Vec<(Condition, bool)>;
+
+fn recall(memory : &[(Condition, bool)], condition : &Condition, default : bool) -> Condition {
+    let value = memory.iter().find(|(c, _)| c == condition).map_or(default, |(_, v)| *v);
+    if value { True } else { False }
+}
+
+impl Condition {
+
+    pub fn is_past_operator(&self) -> bool {
+        matches!(self, Since(_, _) | Once(_) | Historically(_) | Previously(_))
+    }
+
+    // Nested queries are not considered, they are verified on their own runs
+    pub fn contains_past(&self) -> bool {
+        match self {
+            Since(_, _) | Once(_) | Historically(_) | Previously(_) => true,
+            Not(c) | Next(c) => c.contains_past(),
+            And(c1, c2) |
+            Or(c1, c2) |
+            Until(c1, c2) |
+            Release(c1, c2) |
+            WeakUntil(c1, c2) |
+            Implies(c1, c2)
+                => c1.contains_past() || c2.contains_past(),
+            _ => false
+        }
+    }
+
+    // Past-time subformulas, innermost first, without duplicates
+    pub fn past_subformulas(&self) -> Vec<Condition> {
+        let mut subformulas = Vec::new();
+        self.collect_past(&mut subformulas);
+        subformulas
+    }
+
+    fn collect_past(&self, subformulas : &mut Vec<Condition>) {
+        match self {
+            Not(c) | Next(c) | Once(c) | Historically(c) | Previously(c) => c.collect_past(subformulas),
+            And(c1, c2) | Or(c1, c2) | Until(c1, c2) | Release(c1, c2) | WeakUntil(c1, c2) | Implies(c1, c2) | Since(c1, c2) => {
+                c1.collect_past(subformulas);
+                c2.collect_past(subformulas);
+            },
+            _ => ()
+        }
+        if self.is_past_operator() && !subformulas.contains(self) {
+            subformulas.push(self.clone());
+        }
+    }
+
+    // Past-time operators unfolded once, given their values on the previous state :
+    // c1 S c2 = c2 | (c1 & Y(c1 S c2)), O c = c | Y(O c), H c = c & Y(H c) where Y(H c) is true on the first state
+    pub fn with_past(&self, memory : &[(Condition, bool)]) -> Condition {
+        let unfold = |c : &Condition| Box::new(c.with_past(memory));
+        match self {
+            Since(c1, c2) => Or(unfold(c2), Box::new(And(unfold(c1), Box::new(recall(memory, self, false))))),
+            Once(c) => Or(unfold(c), Box::new(recall(memory, self, false))),
+            Historically(c) => And(unfold(c), Box::new(recall(memory, self, true))),
+            Previously(_) => recall(memory, self, false),
+            Not(c) => Not(unfold(c)),
+            And(c1, c2) => And(unfold(c1), unfold(c2)),
+            Or(c1, c2) => Or(unfold(c1), unfold(c2)),
+            Implies(c1, c2) => Implies(unfold(c1), unfold(c2)),
+            c => c.clone()
+        }
+    }
+
+    // Values of the past-time subformulas on the current state, to be used as the memory on the next one.
+    // Y c remembers the current value of c.
+    pub fn update_past(&self, state : &impl Verifiable, memory : &[(Condition, bool)]) -> PastMemory {
+        self.past_subformulas().into_iter().map(|p| {
+            let value = match &p {
+                Previously(c) => c.with_past(memory).is_true(state),
+                _ => p.with_past(memory).is_true(state)
+            };
+            (p, value)
+        }).collect()
+    }
+
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+
+use super::{Condition, Expr, PropositionType};
+
+// Expressions and conditions are written in the syntax of the text query parser, binary operands being
+// parenthesized when they are themselves binary, so that the text can be parsed back
+
+impl fmt::Display for PropositionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            PropositionType::EQ => "==",
+            PropositionType::NE => "!=",
+            PropositionType::LE => "<=",
+            PropositionType::GE => ">=",
+            PropositionType::LS => "<",
+            PropositionType::GS => ">",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl Expr {
+
+    fn is_binary(&self) -> bool {
+        matches!(self, Expr::Plus(_, _) | Expr::Minus(_, _) | Expr::Multiply(_, _) | Expr::Divide(_, _) | Expr::Modulo(_, _) | Expr::Pow(_, _))
+    }
+
+    fn operand(&self) -> String {
+        if self.is_binary() || matches!(self, Expr::Negative(_)) { format!("({})", self) } else { self.to_string() }
+    }
+
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let binary = |f : &mut fmt::Formatter<'_>, a : &Expr, op : &str, b : &Expr| write!(f, "{} {} {}", a.operand(), op, b.operand());
+        match self {
+            Expr::Var(var) => write!(f, "{}", var.name),
+            Expr::Index(var, index) => write!(f, "{}[{}]", var.name, index),
+            Expr::Sum(var) => write!(f, "sum({})", var.name),
+            Expr::Constant(i) => write!(f, "{}", i),
+            Expr::FloatConstant(x) => write!(f, "{:?}", x.0),
+            Expr::ClockComparison(op, clock, value) => write!(f, "{}.clock {} {}", clock.name, op, value),
+            Expr::OldestAge(place) => write!(f, "oldest({})", place.var.name),
+            Expr::AgedTokens(place, low, high) => write!(f, "aged({}, {}, {})", place.var.name, low, high),
+            Expr::Plus(a, b) => binary(f, a, "+", b),
+            Expr::Minus(a, b) => binary(f, a, "-", b),
+            Expr::Multiply(a, b) => binary(f, a, "*", b),
+            Expr::Divide(a, b) => binary(f, a, "/", b),
+            Expr::Modulo(a, b) => binary(f, a, "%", b),
+            Expr::Pow(a, b) => binary(f, a, "^", b),
+            Expr::Negative(e) => write!(f, "-{}", e.operand()),
+        }
+    }
+}
+
+impl Condition {
+
+    fn operand(&self) -> String {
+        match self {
+            Condition::And(_, _) | Condition::Or(_, _) | Condition::Implies(_, _) | Condition::Until(_, _) |
+            Condition::Release(_, _) | Condition::WeakUntil(_, _) | Condition::Since(_, _) |
+            Condition::Proposition(_, _, _) | Condition::Nested(_) => format!("({})", self),
+            _ => self.to_string()
+        }
+    }
+
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let binary = |f : &mut fmt::Formatter<'_>, a : &Condition, op : &str, b : &Condition| write!(f, "{} {} {}", a.operand(), op, b.operand());
+        match self {
+            Condition::True => write!(f, "true"),
+            Condition::False => write!(f, "false"),
+            Condition::Deadlock => write!(f, "deadlock"),
+            Condition::Evaluation(e) => write!(f, "{}", e),
+            Condition::Proposition(op, a, b) => write!(f, "{} {} {}", a, op, b),
+            Condition::And(a, b) => binary(f, a, "&", b),
+            Condition::Or(a, b) => binary(f, a, "|", b),
+            Condition::Implies(a, b) => binary(f, a, "=>", b),
+            Condition::Until(a, b) => binary(f, a, "U", b),
+            Condition::Release(a, b) => binary(f, a, "R", b),
+            Condition::WeakUntil(a, b) => binary(f, a, "W", b),
+            Condition::Since(a, b) => binary(f, a, "S", b),
+            Condition::Not(c) => write!(f, "!{}", c.operand()),
+            Condition::Next(c) => write!(f, "X {}", c.operand()),
+            Condition::Once(c) => write!(f, "O {}", c.operand()),
+            Condition::Historically(c) => write!(f, "H {}", c.operand()),
+            Condition::Previously(c) => write!(f, "Y {}", c.operand()),
+            Condition::Nested(query) => write!(f, "{}", query),
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use std::ops::{Add, Div, Mul, Rem, Sub};
+
+use crate::QueryTransformer;
+
+use super::{Condition, Expr, FloatValue, Numeric, PropositionType};
+use super::super::model_var::ModelVar;
+
+use Condition::*;
+use Expr::*;
+use PropositionType::*;
+
+// Integer interval [lo, hi], bounds included, with i64 bounds standing for infinity
+type Range = (i64, i64);
+const FULL : Range = (i64::MIN, i64::MAX);
+
+fn constant(expr : &Expr) -> Option<Numeric> {
+    match expr {
+        Constant(i) => Some(Numeric::Int(*i)),
+        FloatConstant(x) => Some(Numeric::Float(x.0)),
+        _ => None
+    }
+}
+
+fn from_numeric(value : Numeric) -> Expr {
+    match value {
+        Numeric::Int(i) => Constant(i),
+        Numeric::Float(x) => FloatConstant(FloatValue(x))
+    }
+}
+
+// Folds an operation on two constants, unless integer arithmetic fails (overflow, null divisor...)
+fn fold(e1 : &Expr, e2 : &Expr, int_op : fn(i32, i32) -> Option<i32>, op : fn(Numeric, Numeric) -> Numeric) -> Option<Expr> {
+    match (constant(e1)?, constant(e2)?) {
+        (Numeric::Int(a), Numeric::Int(b)) => int_op(a, b).map(Constant),
+        (a, b) => Some(from_numeric(op(a, b)))
+    }
+}
+
+impl Expr {
+
+    // Constant folding and neutral elements. Operations that would overflow are kept as is.
+    pub fn simplify(&self) -> Expr {
+        match self {
+            Plus(e1, e2) => {
+                let (e1, e2) = (e1.simplify(), e2.simplify());
+                fold(&e1, &e2, i32::checked_add, Numeric::add).unwrap_or_else(|| match (e1, e2) {
+                    (Constant(0), e) | (e, Constant(0)) => e,
+                    (e1, e2) => Plus(Box::new(e1), Box::new(e2))
+                })
+            },
+            Minus(e1, e2) => {
+                let (e1, e2) = (e1.simplify(), e2.simplify());
+                fold(&e1, &e2, i32::checked_sub, Numeric::sub).unwrap_or_else(|| match (e1, e2) {
+                    (e, Constant(0)) => e,
+                    (e1, e2) => Minus(Box::new(e1), Box::new(e2))
+                })
+            },
+            Multiply(e1, e2) => {
+                let (e1, e2) = (e1.simplify(), e2.simplify());
+                fold(&e1, &e2, i32::checked_mul, Numeric::mul).unwrap_or_else(|| match (e1, e2) {
+                    (Constant(0), _) | (_, Constant(0)) => Constant(0),
+                    (Constant(1), e) | (e, Constant(1)) => e,
+                    (e1, e2) => Multiply(Box::new(e1), Box::new(e2))
+                })
+            },
+            Divide(e1, e2) => {
+                let (e1, e2) = (e1.simplify(), e2.simplify());
+                fold(&e1, &e2, i32::checked_div, Numeric::div).unwrap_or_else(|| match (e1, e2) {
+                    (e, Constant(1)) => e,
+                    (e1, e2) => Divide(Box::new(e1), Box::new(e2))
+                })
+            },
+            Modulo(e1, e2) => {
+                let (e1, e2) = (e1.simplify(), e2.simplify());
+                fold(&e1, &e2, i32::checked_rem, Numeric::rem).unwrap_or_else(|| Modulo(Box::new(e1), Box::new(e2)))
+            },
+            Pow(e1, e2) => {
+                let (e1, e2) = (e1.simplify(), e2.simplify());
+                fold(&e1, &e2, |a, b| a.checked_pow(u32::try_from(b).ok()?), Numeric::pow).unwrap_or_else(|| match (e1, e2) {
+                    (e, Constant(1)) => e,
+                    (e1, e2) => Pow(Box::new(e1), Box::new(e2))
+                })
+            },
+            Negative(e) => match e.simplify() {
+                Constant(a) if a != i32::MIN => Constant(-a),
+                FloatConstant(x) => FloatConstant(FloatValue(-x.0)),
+                Negative(e) => *e,
+                e => Negative(Box::new(e))
+            },
+            Index(x, i) => Index(x.clone(), Box::new(i.simplify())),
+            e => e.clone()
+        }
+    }
+
+}
+
+fn compare(p_type : PropositionType, a : Numeric, b : Numeric) -> bool {
+    match p_type {
+        EQ => a == b,
+        NE => a != b,
+        LE => a <= b,
+        GE => a >= b,
+        LS => a < b,
+        GS => a > b,
+    }
+}
+
+fn negate(p_type : PropositionType) -> PropositionType {
+    match p_type {
+        EQ => NE,
+        NE => EQ,
+        LE => GS,
+        GE => LS,
+        LS => GE,
+        GS => LE,
+    }
+}
+
+fn mirror(p_type : PropositionType) -> PropositionType {
+    match p_type {
+        LE => GE,
+        GE => LE,
+        LS => GS,
+        GS => LS,
+        p => p
+    }
+}
+
+// Values of a variable satisfying a proposition x ~ c, or c ~ x. NE can't be represented by an interval.
+fn as_range(condition : &Condition) -> Option<(ModelVar, Range)> {
+    let Proposition(p_type, e1, e2) = condition else {
+        return None;
+    };
+    let (var, p_type, c) = match (e1, e2) {
+        (Var(x), Constant(c)) => (x, *p_type, *c as i64),
+        (Constant(c), Var(x)) => (x, mirror(*p_type), *c as i64),
+        _ => return None
+    };
+    let range = match p_type {
+        EQ => (c, c),
+        LE => (i64::MIN, c),
+        LS => (i64::MIN, c - 1),
+        GE => (c, i64::MAX),
+        GS => (c + 1, i64::MAX),
+        NE => return None,
+    };
+    Some((var.clone(), range))
+}
+
+// Variables being evaluated as i32, bounds reaching past the i32 range are unbounded, and ranges outside of it are empty
+fn from_range(var : &ModelVar, range : Range) -> Condition {
+    let prop = |p_type, c : i64| Proposition(p_type, Var(var.clone()), Constant(c as i32));
+    let (min, max) = (i32::MIN as i64, i32::MAX as i64);
+    if range.0 > max || range.1 < min {
+        return False;
+    }
+    let range = (
+        if range.0 <= min { i64::MIN } else { range.0 },
+        if range.1 >= max { i64::MAX } else { range.1 }
+    );
+    match range {
+        (lo, hi) if lo > hi => False,
+        FULL => True,
+        (lo, hi) if lo == hi => prop(EQ, lo),
+        (i64::MIN, hi) => prop(LE, hi),
+        (lo, i64::MAX) => prop(GE, lo),
+        (lo, hi) => And(Box::new(prop(GE, lo)), Box::new(prop(LE, hi))),
+    }
+}
+
+impl Condition {
+
+    // Equivalent condition, usually smaller : constants are folded, double negations removed, nested And / Or flattened,
+    // redundant or absorbed operands dropped, and bounds on a same variable merged into a single interval.
+    pub fn simplify(&self) -> Condition {
+        match self {
+            Evaluation(e) => {
+                let e = e.simplify();
+                match constant(&e) {
+                    Some(c) => if c.is_true() { True } else { False },
+                    None => Evaluation(e)
+                }
+            },
+            Proposition(p_type, e1, e2) => {
+                let (e1, e2) = (e1.simplify(), e2.simplify());
+                match (constant(&e1), constant(&e2)) {
+                    (Some(a), Some(b)) => if compare(*p_type, a, b) { True } else { False },
+                    _ => Proposition(*p_type, e1, e2)
+                }
+            },
+            Not(c) => match c.simplify() {
+                True => False,
+                False => True,
+                Not(c) => *c,
+                Proposition(p_type, e1, e2) => Proposition(negate(p_type), e1, e2),
+                Until(c1, c2) => Release(Box::new(Not(c1).simplify()), Box::new(Not(c2).simplify())),
+                Release(c1, c2) => Until(Box::new(Not(c1).simplify()), Box::new(Not(c2).simplify())),
+                Once(c) => Historically(Box::new(Not(c).simplify())),
+                Historically(c) => Once(Box::new(Not(c).simplify())),
+                c => Not(Box::new(c))
+            },
+            Implies(c1, c2) => match (c1.simplify(), c2.simplify()) {
+                (True, c) => c,
+                (False, _) | (_, True) => True,
+                (c, False) => Not(Box::new(c)).simplify(),
+                (c1, c2) if c1 == c2 => True,
+                (c1, c2) => Implies(Box::new(c1), Box::new(c2))
+            },
+            And(_, _) => Self::simplify_junction(self, true),
+            Or(_, _) => Self::simplify_junction(self, false),
+            Next(c) => Next(Box::new(c.simplify())),
+            Until(c1, c2) => match (c1.simplify(), c2.simplify()) {
+                (_, True) => True,
+                (_, False) => False,
+                (c1, c2) => Until(Box::new(c1), Box::new(c2))
+            },
+            Release(c1, c2) => match (c1.simplify(), c2.simplify()) {
+                (_, True) => True,
+                (_, False) => False,
+                (True, c) => c,
+                (c1, c2) => Release(Box::new(c1), Box::new(c2))
+            },
+            WeakUntil(c1, c2) => match (c1.simplify(), c2.simplify()) {
+                (True, _) | (_, True) => True,
+                (False, c) => c,
+                (c1, c2) => WeakUntil(Box::new(c1), Box::new(c2))
+            },
+            Since(c1, c2) => match (c1.simplify(), c2.simplify()) {
+                (_, True) => True,
+                (_, False) => False,
+                (c1, c2) => Since(Box::new(c1), Box::new(c2))
+            },
+            Once(c) => match c.simplify() {
+                c @ (True | False) => c,
+                c => Once(Box::new(c))
+            },
+            Historically(c) => match c.simplify() {
+                c @ (True | False) => c,
+                c => Historically(Box::new(c))
+            },
+            Previously(c) => match c.simplify() {
+                False => False,
+                c => Previously(Box::new(c))
+            },
+            Nested(query) => {
+                let mut query = query.clone();
+                query.condition = query.condition.simplify();
+                Nested(query)
+            },
+            c => c.clone()
+        }
+    }
+
+    fn operands(&self, conjunction : bool, operands : &mut Vec<Condition>) {
+        match (self, conjunction) {
+            (And(c1, c2), true) | (Or(c1, c2), false) => {
+                c1.operands(conjunction, operands);
+                c2.operands(conjunction, operands);
+            },
+            (c, _) => operands.push(c.clone())
+        }
+    }
+
+    // Flattened conjunction (or disjunction), simplified as a whole
+    fn simplify_junction(&self, conjunction : bool) -> Condition {
+        let (neutral, absorbing) = if conjunction { (True, False) } else { (False, True) };
+        let mut raw = Vec::new();
+        self.operands(conjunction, &mut raw);
+        let mut operands : Vec<Condition> = Vec::new();
+        for operand in raw {
+            let mut flattened = Vec::new();
+            operand.simplify().operands(conjunction, &mut flattened);
+            for c in flattened {
+                if c == absorbing {
+                    return absorbing;
+                }
+                if c != neutral && !operands.contains(&c) {
+                    operands.push(c);
+                }
+            }
+        }
+
+        // Interval merging, intersection for conjunctions, union of overlapping intervals for disjunctions
+        let mut ranges : HashMap<ModelVar, Vec<Range>> = HashMap::new();
+        let mut merged : Vec<Option<Condition>> = Vec::new();
+        let mut first_use : HashMap<ModelVar, usize> = HashMap::new();
+        for c in operands {
+            match as_range(&c) {
+                Some((var, range)) => {
+                    first_use.entry(var.clone()).or_insert_with(|| {
+                        merged.push(None);
+                        merged.len() - 1
+                    });
+                    let var_ranges = ranges.entry(var).or_default();
+                    if conjunction {
+                        match var_ranges.first_mut() {
+                            Some(r) => *r = (r.0.max(range.0), r.1.min(range.1)),
+                            None => var_ranges.push(range)
+                        }
+                    } else {
+                        var_ranges.push(range);
+                    }
+                },
+                None => merged.push(Some(c))
+            }
+        }
+        for (var, position) in first_use {
+            let mut var_ranges = ranges.remove(&var).unwrap();
+            if !conjunction {
+                var_ranges = union(var_ranges);
+            }
+            let conditions : Vec<Condition> = var_ranges.into_iter().map(|r| from_range(&var, r)).filter(|c| *c != neutral).collect();
+            if conditions.contains(&absorbing) {
+                return absorbing;
+            }
+            merged[position] = Some(build(conditions, conjunction, neutral.clone()));
+        }
+        let operands : Vec<Condition> = merged.into_iter().flatten().filter(|c| *c != neutral).collect();
+
+        // Complementary operands, and absorption : a & (a | b) = a, a | (a & b) = a
+        for c in operands.iter() {
+            if let Not(inner) = c {
+                if operands.contains(inner) {
+                    return absorbing;
+                }
+            }
+        }
+        let kept : Vec<Condition> = operands.iter().filter(|c| {
+            let mut inner = Vec::new();
+            c.operands(!conjunction, &mut inner);
+            inner.len() <= 1 || !inner.iter().any(|i| operands.contains(i))
+        }).cloned().collect();
+        build(kept, conjunction, neutral)
+    }
+
+}
+
+// Union of integer intervals, adjacent ones being merged
+fn union(mut ranges : Vec<Range>) -> Vec<Range> {
+    ranges.sort();
+    let mut res : Vec<Range> = Vec::new();
+    for range in ranges {
+        match res.last_mut() {
+            Some(last) if last.1 == i64::MAX || range.0 <= last.1 + 1 => last.1 = last.1.max(range.1),
+            _ => res.push(range)
+        }
+    }
+    res
+}
+
+fn build(operands : Vec<Condition>, conjunction : bool, neutral : Condition) -> Condition {
+    operands.into_iter().reduce(|c1, c2| {
+        if conjunction {
+            And(Box::new(c1), Box::new(c2))
+        } else {
+            Or(Box::new(c1), Box::new(c2))
+        }
+    }).unwrap_or(neutral)
+}
+
+/// Simplification as a query pass, to be chained with other transformers
+pub struct Simplifier;
+
+impl QueryTransformer for Simplifier {
+
+    fn transform_condition(&mut self, condition : &mut Condition) {
+        *condition = condition.simplify();
+    }
+
+    fn transform_expression(&mut self, expr : &mut Expr) {
+        *expr = expr.simplify();
+    }
+
+}
@@ -0,0 +1,227 @@
+use std::{collections::{HashMap, HashSet}, fmt, hash::{DefaultHasher, Hash, Hasher}};
+
+use num_traits::Zero;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::computation::{intervals::Convex, random::{choose_uniform, SimulationRng}};
+
+use super::{action::Action, lbl, model_characteristics::*, model_clock::ModelClock, model_context::ModelContext, time::ClockValue, CompilationError, CompilationResult, Label, Model, ModelMeta, ModelState, Node};
+
+mod linear_constraint;
+mod hybrid_location;
+mod hybrid_edge;
+
+pub use linear_constraint::{conjunction_delays, LinearConstraint};
+pub use hybrid_location::HybridLocation;
+pub use hybrid_edge::HybridEdge;
+
+/// Linear hybrid automaton : continuous variables evolve with constant derivatives depending on the location, under
+/// linear guards and invariants. States are concrete valuations, stored as clocks of the context. Constraints being
+/// linear in the delay for constant rates, the delays during which they hold are computed exactly as intervals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridAutomaton {
+    #[serde(skip)]
+    pub id : usize,
+    // Continuous variables and their initial value
+    pub variables : Vec<(Label, f64)>,
+    pub locations : Vec<HybridLocation>,
+    pub edges : Vec<HybridEdge>,
+
+    #[serde(skip)]
+    locations_dic : HashMap<Label, usize>,
+    #[serde(skip)]
+    actions_dic : HashMap<Action, usize>,
+    #[serde(skip)]
+    clocks : Vec<ModelClock>,
+}
+
+impl HybridAutomaton {
+
+    pub fn new(variables : Vec<(Label, f64)>, locations : Vec<HybridLocation>, edges : Vec<HybridEdge>) -> Self {
+        HybridAutomaton {
+            id : usize::MAX,
+            variables,
+            locations,
+            edges,
+            locations_dic : HashMap::new(),
+            actions_dic : HashMap::new(),
+            clocks : Vec::new()
+        }
+    }
+
+    pub fn get_current_location(&self, state : &ModelState) -> &HybridLocation {
+        let index = state.argmax(self.locations.iter().map(HybridLocation::get_var));
+        &self.locations[index]
+    }
+
+    pub fn get_edge(&self, action : &Action) -> Option<&HybridEdge> {
+        self.actions_dic.get(&action.base()).map(|i| &self.edges[*i])
+    }
+
+    fn outgoing_edges<'a>(&'a self, location : &'a HybridLocation) -> impl Iterator<Item = &'a HybridEdge> {
+        self.edges.iter().filter(|e| e.from == location.name)
+    }
+
+    fn is_fireable(&self, state : &ModelState, edge : &HybridEdge) -> bool {
+        let target = &self.locations[self.locations_dic[&edge.to]];
+        edge.is_guard_satisfied(state) && target.is_invariant_satisfied(&edge.reset(state.clone()))
+    }
+
+    // Delays after which the edge can be taken from the current location : the invariant holds meanwhile, and the guard
+    // along with the invariant of the target, reset variables keeping their new value, hold then
+    pub fn firing_delays(&self, state : &ModelState, edge : &HybridEdge) -> (f64, f64) {
+        let current = self.get_current_location(state);
+        let target = &self.locations[self.locations_dic[&edge.to]];
+        let rates = current.get_rates();
+        let mut reset_rates = rates.clone();
+        reset_rates.retain(|x, _| !self.clocks.iter().any(|c| c.get_index() == *x && edge.is_reset(c)));
+        current.invariant_delays(state)
+            .intersection(conjunction_delays(&edge.guard, state, rates))
+            .intersection(conjunction_delays(&target.invariant, &edge.reset(state.clone()), &reset_rates))
+    }
+
+}
+
+impl Model for HybridAutomaton {
+
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        let edge = self.get_edge(&action)?;
+        let current = self.get_current_location(&state);
+        if edge.from != current.name || !self.is_fireable(&state, edge) {
+            return None;
+        }
+        let target = &self.locations[self.locations_dic[&edge.to]];
+        let mut next_state = edge.reset(state);
+        next_state.unmark(current.get_var(), 1);
+        next_state.mark(target.get_var(), 1);
+        let actions = self.available_actions(&next_state);
+        next_state.deadlocked = actions.is_empty() && self.available_delay(&next_state).is_zero();
+        Some((next_state, actions))
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        let current = self.get_current_location(state);
+        self.outgoing_edges(current).filter(|e| self.is_fireable(state, e)).map(|e| e.action.clone()).collect()
+    }
+
+    fn available_delay(&self, state : &ModelState) -> ClockValue {
+        let (_, max_delay) = self.get_current_location(state).invariant_delays(state);
+        ClockValue::from(max_delay.max(0.0))
+    }
+
+    // Delays beyond the invariant of the current location are refused
+    fn delay(&self, mut state : ModelState, dt : ClockValue) -> Option<ModelState> {
+        let current = self.get_current_location(&state);
+        if !current.invariant_delays(&state).contains(&dt.float()) {
+            return None;
+        }
+        for (index, rate) in current.get_rates().iter() {
+            state.clocks[*index] += ClockValue::from(rate * dt.float());
+        }
+        Some(state)
+    }
+
+    fn init_initial_clocks(&self, mut state : ModelState) -> ModelState {
+        for (clock, (_, value)) in self.clocks.iter().zip(self.variables.iter()) {
+            state.enable_clock(clock, ClockValue::from(*value));
+        }
+        state
+    }
+
+    // An edge able to fire at some point is chosen uniformly, then the delay uniformly amongst those it can fire
+    // after, or its earliest one if they are unbounded
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let current = self.get_current_location(&state);
+        let mut candidates : Vec<(&HybridEdge, (f64, f64))> = self.outgoing_edges(current)
+            .map(|e| (e, self.firing_delays(&state, e)))
+            .filter(|(_, delays)| !delays.is_empty())
+            .collect();
+        candidates.sort_by_key(|(e, _)| e.action.get_id());
+        let Some((edge, (earliest, latest))) = choose_uniform(&candidates, rng).cloned() else {
+            return (Some(state), ClockValue::zero(), None);
+        };
+        let delay = if latest.is_infinite() || latest <= earliest {
+            earliest
+        } else {
+            rng.gen_range(earliest..latest)
+        };
+        let delay = ClockValue::from(delay);
+        let Some(delayed) = self.delay(state, delay) else {
+            return (None, delay, Some(edge.action.clone()));
+        };
+        let next = self.next(delayed, edge.action.clone()).map(|(s, _)| s);
+        (next, delay, Some(edge.action.clone()))
+    }
+
+    fn get_meta() -> ModelMeta {
+        ModelMeta {
+            name : lbl("LHA"),
+            description : String::from("Linear hybrid automaton, continuous variables with constant rates per location"),
+            characteristics : TIMED | CONTROLLABLE
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        true
+    }
+
+    fn is_stochastic(&self) -> bool {
+        false
+    }
+
+    fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        self.id = context.new_model();
+        self.clocks = self.variables.iter().map(|(x, _)| context.add_clock(x.clone())).collect();
+        self.locations_dic.clear();
+        for (i, location) in self.locations.iter_mut().enumerate() {
+            location.index = i;
+            location.compile(context)?;
+            self.locations_dic.insert(location.get_label(), i);
+        }
+        self.actions_dic.clear();
+        for (i, edge) in self.edges.iter_mut().enumerate() {
+            if !self.locations_dic.contains_key(&edge.from) || !self.locations_dic.contains_key(&edge.to) {
+                return Err(CompilationError);
+            }
+            edge.compile(context)?;
+            self.actions_dic.insert(edge.action.clone(), i);
+        }
+        Ok(())
+    }
+
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn structure_hash(&self) -> Option<u64> {
+        let json = serde_json::to_string(self).ok()?;
+        let mut s = DefaultHasher::new();
+        json.hash(&mut s);
+        Some(s.finish())
+    }
+
+}
+
+impl fmt::Display for HybridAutomaton {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "HybridAutomaton")?;
+        writeln!(f, "Variables :")?;
+        for (name, value) in self.variables.iter() {
+            writeln!(f, "  {} = {}", name, value)?;
+        }
+        writeln!(f, "Locations :")?;
+        for location in self.locations.iter() {
+            let rates : Vec<String> = location.rates.iter().map(|(x, r)| format!("{}' = {}", x, r)).collect();
+            let invariant : Vec<String> = location.invariant.iter().map(LinearConstraint::to_string).collect();
+            writeln!(f, "  {} : [{}] | inv {}", location.name, rates.join(", "), invariant.join(" & "))?;
+        }
+        writeln!(f, "Edges :")?;
+        for edge in self.edges.iter() {
+            let guard : Vec<String> = edge.guard.iter().map(LinearConstraint::to_string).collect();
+            let resets : Vec<String> = edge.resets.iter().map(|(x, v)| format!("{} := {}", x, v)).collect();
+            writeln!(f, "  {} : {} -> {} | guard {} | {}", edge.label, edge.from, edge.to, guard.join(" & "), resets.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{action::Action, model_clock::ModelClock, model_context::ModelContext, time::ClockValue, CompilationError, CompilationResult, Label, ModelState, Node, NodeMetadata};
+
+use super::LinearConstraint;
+
+/// Edge of a hybrid automaton, taken when its guard holds. Reset variables are set to constants, the others keep their value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridEdge {
+    pub label : Label,
+    pub from : Label,
+    pub to : Label,
+    pub guard : Vec<LinearConstraint>,
+    pub resets : Vec<(Label, f64)>,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+
+    #[serde(skip)]
+    pub action : Action,
+    #[serde(skip)]
+    compiled_resets : Vec<(ModelClock, f64)>,
+}
+
+impl HybridEdge {
+
+    pub fn new(label : Label, from : Label, to : Label) -> Self {
+        HybridEdge {
+            label,
+            from,
+            to,
+            guard : Vec::new(),
+            resets : Vec::new(),
+            metadata : Default::default(),
+            action : Action::Epsilon,
+            compiled_resets : Vec::new()
+        }
+    }
+
+    pub fn with_guard(mut self, constraint : LinearConstraint) -> Self {
+        self.guard.push(constraint);
+        self
+    }
+
+    pub fn with_reset(mut self, var : Label, value : f64) -> Self {
+        self.resets.push((var, value));
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn is_guard_satisfied(&self, state : &ModelState) -> bool {
+        self.guard.iter().all(|c| c.is_satisfied(state))
+    }
+
+    pub fn is_reset(&self, clock : &ModelClock) -> bool {
+        self.compiled_resets.iter().any(|(c, _)| c.get_index() == clock.get_index())
+    }
+
+    pub fn reset(&self, mut state : ModelState) -> ModelState {
+        for (clock, value) in self.compiled_resets.iter() {
+            state.set_clock(clock, ClockValue::from(*value));
+        }
+        state
+    }
+
+    pub fn compile(&mut self, ctx : &mut ModelContext) -> CompilationResult<()> {
+        for constraint in self.guard.iter_mut() {
+            constraint.compile(ctx)?;
+        }
+        self.compiled_resets = self.resets.iter().map(|(x, value)| {
+            ctx.get_clock(x).map(|c| (c, *value)).ok_or(CompilationError)
+        }).collect::<CompilationResult<Vec<(ModelClock, f64)>>>()?;
+        self.action = ctx.add_action(self.label.clone());
+        Ok(())
+    }
+
+}
+
+impl Node for HybridEdge {
+
+    fn get_label(&self) -> Label {
+        self.label.clone()
+    }
+
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
+}
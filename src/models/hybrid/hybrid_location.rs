@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{model_context::ModelContext, model_var::{ModelVar, VarType}, CompilationError, CompilationResult, Label, ModelState, Node, NodeMetadata};
+
+use super::{conjunction_delays, LinearConstraint};
+
+/// Location of a hybrid automaton : derivatives of the continuous variables, 0 unless given, and invariant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridLocation {
+    pub name : Label,
+    pub rates : Vec<(Label, f64)>,
+    pub invariant : Vec<LinearConstraint>,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+
+    #[serde(skip)]
+    pub index : usize,
+    #[serde(skip)]
+    var : ModelVar,
+    #[serde(skip)]
+    compiled_rates : HashMap<usize, f64>,
+}
+
+impl HybridLocation {
+
+    pub fn new(name : Label) -> Self {
+        HybridLocation {
+            name,
+            rates : Vec::new(),
+            invariant : Vec::new(),
+            metadata : Default::default(),
+            index : 0,
+            var : ModelVar::new(),
+            compiled_rates : HashMap::new()
+        }
+    }
+
+    pub fn with_rate(mut self, var : Label, rate : f64) -> Self {
+        self.rates.push((var, rate));
+        self
+    }
+
+    pub fn with_invariant(mut self, constraint : LinearConstraint) -> Self {
+        self.invariant.push(constraint);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn get_var(&self) -> &ModelVar {
+        &self.var
+    }
+
+    // Derivatives by clock index
+    pub fn get_rates(&self) -> &HashMap<usize, f64> {
+        &self.compiled_rates
+    }
+
+    pub fn is_invariant_satisfied(&self, state : &ModelState) -> bool {
+        self.invariant.iter().all(|c| c.is_satisfied(state))
+    }
+
+    // Delays the automaton can stay in the location
+    pub fn invariant_delays(&self, state : &ModelState) -> (f64, f64) {
+        conjunction_delays(&self.invariant, state, &self.compiled_rates)
+    }
+
+    // Continuous variables have to be added to the context beforehand
+    pub fn compile(&mut self, ctx : &mut ModelContext) -> CompilationResult<()> {
+        self.var = ctx.add_var(self.name.clone(), VarType::VarU8);
+        self.compiled_rates = self.rates.iter().map(|(x, rate)| {
+            ctx.get_clock(x).map(|c| (c.get_index(), *rate)).ok_or(CompilationError)
+        }).collect::<CompilationResult<HashMap<usize, f64>>>()?;
+        for constraint in self.invariant.iter_mut() {
+            constraint.compile(ctx)?;
+        }
+        Ok(())
+    }
+
+}
+
+impl Node for HybridLocation {
+
+    fn get_label(&self) -> Label {
+        self.name.clone()
+    }
+
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
+}
@@ -0,0 +1,86 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{computation::intervals::Convex, models::{model_clock::ModelClock, model_context::ModelContext, CompilationError, CompilationResult, Label, ModelState}};
+
+// Tolerance on the satisfaction of constraints, absorbing rounding errors of the delays
+const TOLERANCE : f64 = 1e-9;
+
+/// Linear constraint over continuous variables : sum of a_i * x_i <= bound. Strict comparisons aren't distinguished,
+/// the boundary of a guard being reached with probability zero when simulating
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinearConstraint {
+    pub coefficients : Vec<(Label, f64)>,
+    pub bound : f64,
+
+    #[serde(skip)]
+    compiled : Vec<(ModelClock, f64)>,
+}
+
+impl LinearConstraint {
+
+    pub fn le(coefficients : Vec<(Label, f64)>, bound : f64) -> Self {
+        LinearConstraint { coefficients, bound, compiled : Vec::new() }
+    }
+
+    pub fn ge(coefficients : Vec<(Label, f64)>, bound : f64) -> Self {
+        Self::le(coefficients.into_iter().map(|(x, a)| (x, -a)).collect(), -bound)
+    }
+
+    // x <= bound
+    pub fn var_le(var : Label, bound : f64) -> Self {
+        Self::le(vec![(var, 1.0)], bound)
+    }
+
+    // x >= bound
+    pub fn var_ge(var : Label, bound : f64) -> Self {
+        Self::ge(vec![(var, 1.0)], bound)
+    }
+
+    pub fn compile(&mut self, ctx : &ModelContext) -> CompilationResult<()> {
+        self.compiled = self.coefficients.iter().map(|(x, a)| {
+            ctx.get_clock(x).map(|c| (c, *a)).ok_or(CompilationError)
+        }).collect::<CompilationResult<Vec<(ModelClock, f64)>>>()?;
+        Ok(())
+    }
+
+    pub fn evaluate(&self, state : &ModelState) -> f64 {
+        self.compiled.iter().map(|(x, a)| a * state.get_clock_value(x).float()).sum()
+    }
+
+    pub fn is_satisfied(&self, state : &ModelState) -> bool {
+        self.evaluate(state) <= self.bound + TOLERANCE
+    }
+
+    // Delays during which the constraint holds, variables evolving with constant rates (by clock index) : the
+    // constraint is linear in the delay, so that these delays are an interval
+    pub fn delays(&self, state : &ModelState, rates : &HashMap<usize, f64>) -> (f64, f64) {
+        let value = self.evaluate(state);
+        let slope : f64 = self.compiled.iter().map(|(x, a)| a * rates.get(&x.get_index()).unwrap_or(&0.0)).sum();
+        let slack = self.bound + TOLERANCE - value;
+        let delays = if slope > 0.0 {
+            (0.0, slack / slope)
+        } else if slope < 0.0 {
+            (slack / slope, f64::INFINITY)
+        } else if slack >= 0.0 {
+            (0.0, f64::INFINITY)
+        } else {
+            (f64::INFINITY, 0.0)
+        };
+        delays.intersection((0.0, f64::INFINITY))
+    }
+
+}
+
+impl fmt::Display for LinearConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let terms : Vec<String> = self.coefficients.iter().map(|(x, a)| format!("{}*{}", a, x)).collect();
+        write!(f, "{} <= {}", terms.join(" + "), self.bound)
+    }
+}
+
+// Delays during which every constraint holds
+pub fn conjunction_delays(constraints : &[LinearConstraint], state : &ModelState, rates : &HashMap<usize, f64>) -> (f64, f64) {
+    constraints.iter().fold(<(f64, f64)>::full(), |delays, c| delays.intersection(c.delays(state, rates)))
+}
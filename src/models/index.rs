@@ -0,0 +1,54 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// Typed positions of the nodes of a model, so that an index of one kind can't be used where another is expected
+macro_rules! index_type {
+    ($name:ident, $prefix:literal) => {
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub usize);
+
+        impl $name {
+
+            pub fn index(&self) -> usize {
+                self.0
+            }
+
+        }
+
+        impl From<usize> for $name {
+            fn from(value : usize) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for usize {
+            fn from(value : $name) -> Self {
+                value.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}{}", $prefix, self.0)
+            }
+        }
+    };
+}
+
+index_type!(PlaceId, "P");
+index_type!(TransitionId, "T");
+index_type!(ClassId, "Class_");
+index_type!(ClockId, "x");
+
+impl ClockId {
+
+    // Variable of the DBMs always equal to zero, every clock being measured from it
+    pub const REFERENCE : ClockId = ClockId(0);
+
+    pub fn is_reference(&self) -> bool {
+        self.0 == 0
+    }
+
+}
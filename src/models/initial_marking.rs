@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::computation::virtual_memory::EvaluationType;
+
+use crate::verification::Verifiable;
+
+use super::{model_context::ModelContext, time::TimeInterval, Label, Model, ModelState};
+
+/// Value of a variable in a partially specified initial state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkingValue {
+    Exact(EvaluationType),
+    Range(EvaluationType, EvaluationType), // Bounds included
+    Set(Vec<EvaluationType>),
+}
+
+use MarkingValue::*;
+
+impl MarkingValue {
+
+    pub fn values(&self) -> Vec<EvaluationType> {
+        match self {
+            Exact(x) => vec![*x],
+            Range(a, b) => (*a..=*b).collect(),
+            Set(v) => v.clone()
+        }
+    }
+
+    // i-th value, in the order of values, computed without enumerating ranges
+    pub fn value(&self, i : usize) -> EvaluationType {
+        match self {
+            Exact(x) => *x,
+            Range(a, _) => (*a as i64 + i as i64) as EvaluationType,
+            Set(v) => v[i]
+        }
+    }
+
+    // Number of values, computed without enumerating ranges
+    pub fn count(&self) -> usize {
+        match self {
+            Exact(_) => 1,
+            Range(a, b) if b < a => 0,
+            Range(a, b) => (*b as i64 - *a as i64 + 1) as usize,
+            Set(v) => v.len()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Exact(_) => true,
+            Range(a, b) => a == b,
+            Set(v) => v.len() == 1
+        }
+    }
+
+    pub fn contains(&self, value : EvaluationType) -> bool {
+        match self {
+            Exact(x) => *x == value,
+            Range(a, b) => *a <= value && value <= *b,
+            Set(v) => v.contains(&value)
+        }
+    }
+
+}
+
+impl From<EvaluationType> for MarkingValue {
+    fn from(value: EvaluationType) -> Self {
+        Exact(value)
+    }
+}
+
+/// Initial marking where some variables are left unconstrained (sets or ranges of values).
+/// Unspecified variables are set to zero, as in `ModelContext::make_initial_state`.
+/// Clocks, named after their transition or declaration, may start anywhere in an interval : they are handled
+/// symbolically by the initial state class instead of being enumerated. Unspecified clocks start at zero.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PartialMarking {
+    pub values : HashMap<Label, MarkingValue>,
+    #[serde(default)]
+    pub clocks : HashMap<Label, TimeInterval>,
+}
+
+impl PartialMarking {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn exact(mut self, var : Label, value : EvaluationType) -> Self {
+        self.values.insert(var, Exact(value));
+        self
+    }
+
+    pub fn range(mut self, var : Label, from : EvaluationType, to : EvaluationType) -> Self {
+        self.values.insert(var, Range(from, to));
+        self
+    }
+
+    pub fn set(mut self, var : Label, values : Vec<EvaluationType>) -> Self {
+        self.values.insert(var, Set(values));
+        self
+    }
+
+    pub fn clock(mut self, clock : Label, interval : TimeInterval) -> Self {
+        self.clocks.insert(clock, interval);
+        self
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.values.values().all(|v| v.is_exact())
+    }
+
+    // None if the number of markings overflows
+    pub fn n_markings(&self) -> Option<usize> {
+        self.values.values().try_fold(1usize, |n, v| n.checked_mul(v.count()))
+    }
+
+    // Markings can be enumerated, none of the values being empty (quantifying over no marking would be vacuous)
+    pub fn check(&self) -> Result<usize, String> {
+        if let Some((var, _)) = self.values.iter().find(|(_, v)| v.is_empty()) {
+            return Err(format!("No possible value for {}", var));
+        }
+        self.n_markings().ok_or_else(|| String::from("Too many initial markings to enumerate"))
+    }
+
+    pub fn contains(&self, marking : &HashMap<Label, EvaluationType>) -> bool {
+        self.values.iter().all(|(l, v)| {
+            v.contains(*marking.get(l).unwrap_or(&0))
+        })
+    }
+
+    // Enumerates every complete marking described, lazily : the k-th one picks the values of the variables from the
+    // digits of k, in the mixed radix of their numbers of values
+    pub fn markings(&self) -> impl Iterator<Item = HashMap<Label, EvaluationType>> {
+        let values : Vec<(Label, MarkingValue)> = self.values.iter().map(|(l, v)| (l.clone(), v.clone())).collect();
+        let n_markings = self.n_markings().unwrap_or(usize::MAX);
+        (0..n_markings).map(move |mut k| {
+            values.iter().rev().map(|(l, v)| {
+                let value = v.value(k % v.count());
+                k /= v.count();
+                (l.clone(), value)
+            }).collect()
+        })
+    }
+
+    pub fn initial_states<'a>(&self, ctx : &'a ModelContext, model : &'a impl Model) -> impl Iterator<Item = ModelState> + 'a {
+        self.markings().map(|m| ctx.make_initial_state(model, m))
+    }
+
+}
+
+impl From<HashMap<Label, EvaluationType>> for PartialMarking {
+    fn from(value: HashMap<Label, EvaluationType>) -> Self {
+        PartialMarking {
+            values : value.into_iter().map(|(l, v)| (l, Exact(v))).collect(),
+            clocks : HashMap::new()
+        }
+    }
+}
+
+/// Complete initial marking, by variable name, so that it can be stored alongside a model.
+/// Null variables are omitted.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct InitialMarking {
+    pub values : HashMap<Label, EvaluationType>
+}
+
+impl InitialMarking {
+
+    pub fn new() -> Self {
+        InitialMarking { values : HashMap::new() }
+    }
+
+    pub fn from_state(ctx : &ModelContext, state : &ModelState) -> Self {
+        let values = ctx.get_vars().into_iter().filter_map(|var| {
+            let value = state.evaluate_var(&var);
+            if value == 0 { None } else { Some((var.name.clone(), value)) }
+        }).collect();
+        InitialMarking { values }
+    }
+
+    pub fn initial_state(&self, ctx : &ModelContext, model : &impl Model) -> ModelState {
+        ctx.make_initial_state(model, self.values.clone())
+    }
+
+}
+
+impl From<HashMap<Label, EvaluationType>> for InitialMarking {
+    fn from(values : HashMap<Label, EvaluationType>) -> Self {
+        InitialMarking { values }
+    }
+}
+
+impl From<InitialMarking> for PartialMarking {
+    fn from(value : InitialMarking) -> Self {
+        PartialMarking::from(value.values)
+    }
+}
@@ -1,7 +1,12 @@
-use rand::{distributions::{Distribution, WeightedIndex}, thread_rng};
+use rand::distributions::{Distribution, WeightedIndex};
+
+use crate::computation::random::SimulationRng;
 
 pub mod markov_node;
 pub mod markov_chain;
+pub mod markov_automaton;
+pub mod mdp;
+pub mod scheduler;
 
 #[derive(Debug, Clone)]
 pub struct ProbabilisticChoice<T>(pub Vec<(T, f64)>);
@@ -15,10 +20,13 @@ impl<T : Clone> ProbabilisticChoice<T> {
         }).collect())
     }
 
-    pub fn sample(&self) -> &T {
+    // Choices of a single outcome take no draw
+    pub fn sample(&self, rng : &mut SimulationRng) -> &T {
+        if self.0.len() == 1 {
+            return &self.0[0].0;
+        }
         let dist = WeightedIndex::new(self.0.iter().map(|x| x.1)).unwrap();
-        let mut rng = thread_rng();
-        let sample = dist.sample(&mut rng);
+        let sample = dist.sample(rng);
         &self.0[sample].0
     }
 
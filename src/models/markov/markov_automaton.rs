@@ -0,0 +1,394 @@
+use std::{collections::{HashMap, HashSet}, fmt::Display};
+
+use num_traits::Zero;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::computation::random::{choose_uniform, SimulationRng};
+use crate::models::{action::Action, lbl, model_context::ModelContext, model_var::{ModelVar, VarType}, time::ClockValue, CompilationError, CompilationResult, hash_without_metadata, Label, Model, ModelMaker, ModelMeta, ModelState, Node, NodeMetadata, CONTROLLABLE, STOCHASTIC, TIMED};
+
+use super::{markov_chain::MarkovChain, markov_node::MarkovNode, ProbabilisticChoice};
+
+/// State of a Markov automaton : either interactive (nondeterministic actions leading to distributions),
+/// or Markovian (exponentially distributed delays, one rate per successor). Interactive actions are urgent (maximal progress).
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct MAState {
+    pub label : Label,
+    pub actions : HashMap<Label, Vec<(Label, f64)>>,
+    pub rates : Vec<(Label, f64)>,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+
+    #[serde(skip)]
+    pub index : usize,
+    #[serde(skip)]
+    var : ModelVar,
+    #[serde(skip)]
+    pub compiled_actions : HashMap<Action, ProbabilisticChoice<usize>>,
+    #[serde(skip)]
+    pub compiled_rates : Vec<(usize, f64)>,
+}
+
+impl MAState {
+
+    pub fn interactive(label : Label, actions : HashMap<Label, Vec<(Label, f64)>>) -> MAState {
+        MAState {
+            label,
+            actions,
+            ..Default::default()
+        }
+    }
+
+    pub fn markovian(label : Label, rates : Vec<(Label, f64)>) -> MAState {
+        MAState {
+            label,
+            rates,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn get_var(&self) -> &ModelVar {
+        &self.var
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        !self.actions.is_empty()
+    }
+
+    pub fn is_markovian(&self) -> bool {
+        !self.is_interactive() && !self.rates.is_empty()
+    }
+
+    pub fn exit_rate(&self) -> f64 {
+        self.rates.iter().map(|(_, r)| r).sum()
+    }
+
+    pub fn available_actions(&self) -> HashSet<Action> {
+        if self.is_interactive() {
+            self.compiled_actions.keys().cloned().collect()
+        } else if self.is_markovian() {
+            HashSet::from([Action::Epsilon])
+        } else {
+            HashSet::new()
+        }
+    }
+
+    // Samples the sojourn time in a Markovian state
+    pub fn sample_delay(&self, rng : &mut SimulationRng) -> ClockValue {
+        let u : f64 = rng.gen();
+        ClockValue::from(-(1.0 - u).ln() / self.exit_rate())
+    }
+
+    pub fn compile(&mut self, ctx : &mut ModelContext) -> CompilationResult<()> {
+        self.var = ctx.add_var(self.get_label(), VarType::VarU8);
+        for action_name in self.actions.keys() {
+            ctx.get_or_add_action(action_name.clone());
+        }
+        if self.rates.iter().any(|(_, r)| *r <= 0.0) {
+            return Err(CompilationError);
+        }
+        Ok(())
+    }
+
+}
+
+impl Node for MAState {
+
+    fn get_label(&self) -> Label {
+        self.label.clone()
+    }
+
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
+}
+
+impl Clone for MAState {
+
+    fn clone(&self) -> Self {
+        MAState {
+            label : self.label.clone(),
+            actions : self.actions.clone(),
+            rates : self.rates.clone(),
+            metadata : self.metadata.clone(),
+            ..Default::default()
+        }
+    }
+
+}
+
+impl Display for MAState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MAState({})", self.get_label())
+    }
+}
+
+/// Scheduler used to resolve nondeterminism during simulation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum MAScheduler {
+    #[default]
+    Uniform,
+    // Maps state labels to action labels, uniform on unmapped states
+    Memoryless(HashMap<Label, Label>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkovAutomaton {
+    pub states : Vec<MAState>,
+    pub scheduler : MAScheduler,
+    #[serde(skip)]
+    pub states_dic : HashMap<Label, usize>,
+    #[serde(skip)]
+    pub actions_dic : HashMap<Action, Label>,
+    #[serde(skip)]
+    pub id : usize
+}
+
+impl MarkovAutomaton {
+
+    pub fn new(states : Vec<MAState>) -> MarkovAutomaton {
+        MarkovAutomaton {
+            states,
+            scheduler : MAScheduler::Uniform,
+            states_dic : HashMap::new(),
+            actions_dic : HashMap::new(),
+            id : usize::MAX
+        }
+    }
+
+    pub fn with_scheduler(mut self, scheduler : MAScheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub fn get_vars(&self) -> impl Iterator<Item = &ModelVar> {
+        self.states.iter().map(|s| s.get_var())
+    }
+
+    pub fn get_current_state(&self, state : &ModelState) -> &MAState {
+        let index = state.argmax(self.get_vars());
+        &self.states[index]
+    }
+
+    // No Markovian state : the automaton is a Markov decision process
+    pub fn is_mdp(&self) -> bool {
+        self.states.iter().all(|s| !s.is_markovian())
+    }
+
+    // No interactive state : the automaton is a continuous-time Markov chain
+    pub fn is_ctmc(&self) -> bool {
+        self.states.iter().all(|s| !s.is_interactive())
+    }
+
+    pub fn schedule(&self, current : &MAState, rng : &mut SimulationRng) -> Option<Action> {
+        let mut actions : Vec<Action> = current.compiled_actions.keys().cloned().collect();
+        if let MAScheduler::Memoryless(choices) = &self.scheduler {
+            if let Some(choice) = choices.get(&current.label) {
+                return actions.into_iter().find(|a| self.actions_dic.get(a) == Some(choice));
+            }
+        }
+        actions.sort_by_key(|a| a.get_id());
+        choose_uniform(&actions, rng).cloned()
+    }
+
+    fn build_outputs(&self, ctx : &ModelContext, state : &mut MAState) {
+        state.compiled_actions = HashMap::new();
+        for (a_label, c) in state.actions.iter() {
+            let action = ctx.get_action(a_label).unwrap_or_else(|| {
+                panic!("Unable to find action ! Maybe state hasn't been compiled");
+            });
+            let mapped : Vec<(usize, f64)> = c.iter().map(|(l,p)| {
+                (self.states_dic[l], *p)
+            }).collect();
+            state.compiled_actions.insert(action, ProbabilisticChoice(mapped).normalized());
+        }
+        state.compiled_rates = state.rates.iter().map(|(l,r)| {
+            (self.states_dic[l], *r)
+        }).collect();
+    }
+
+    // Translates the MDP or CTMC subclass to a Markov chain. A CTMC is translated to its embedded jump chain, exit rates are lost.
+    pub fn to_markov_chain(&self) -> Option<MarkovChain> {
+        if self.is_mdp() {
+            let nodes = self.states.iter().map(|s| {
+                if s.actions.len() == 1 {
+                    MarkovNode::probabilistic(s.label.clone(), s.actions.values().next().unwrap().clone())
+                } else {
+                    MarkovNode::choice(s.label.clone(), s.actions.clone())
+                }
+            }).collect();
+            Some(MarkovChain::new(nodes))
+        } else if self.is_ctmc() {
+            let nodes = self.states.iter().map(|s| {
+                if s.rates.is_empty() {
+                    MarkovNode::new(s.label.clone())
+                } else {
+                    MarkovNode::probabilistic(s.label.clone(), s.rates.clone())
+                }
+            }).collect();
+            Some(MarkovChain::new(nodes))
+        } else {
+            None
+        }
+    }
+
+    // Time-abstract automaton : sojourn times are dropped, Markovian states become interactive states
+    // with a single internal action following the embedded jump distribution. Returned uncompiled.
+    pub fn untimed(&self) -> MarkovAutomaton {
+        let states = self.states.iter().map(|s| {
+            if s.is_markovian() {
+                MAState::interactive(s.label.clone(), [(lbl("tau"), s.rates.clone())].into())
+            } else {
+                MAState::interactive(s.label.clone(), s.actions.clone())
+            }
+        }).collect();
+        MarkovAutomaton::new(states).with_scheduler(self.scheduler.clone())
+    }
+
+    fn move_to(&self, mut state : ModelState, from : &MAState, to : usize) -> (ModelState, HashSet<Action>) {
+        let next = &self.states[to];
+        let actions = next.available_actions();
+        state.unmark(from.get_var(), 1);
+        state.mark(next.get_var(), 1);
+        state.deadlocked = actions.is_empty();
+        (state, actions)
+    }
+
+}
+
+impl Model for MarkovAutomaton {
+
+    // Successors are drawn from entropy, runs drawing them from their own generator
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        self.sample_next(state, action, &mut SimulationRng::from_entropy())
+    }
+
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        let current = self.get_current_state(&state);
+        let next_index = if current.is_interactive() {
+            *current.compiled_actions.get(&action)?.sample(rng)
+        } else if current.is_markovian() && action.is_epsilon() {
+            *ProbabilisticChoice(current.compiled_rates.clone()).sample(rng)
+        } else {
+            return None;
+        };
+        Some(self.move_to(state, current, next_index))
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        self.get_current_state(state).available_actions()
+    }
+
+    fn available_delay(&self, state : &ModelState) -> ClockValue {
+        if self.get_current_state(state).is_interactive() {
+            ClockValue::zero()
+        } else {
+            ClockValue::infinity()
+        }
+    }
+
+    fn delay(&self, state : ModelState, dt : ClockValue) -> Option<ModelState> {
+        if dt > self.available_delay(&state) {
+            return None;
+        }
+        Some(state)
+    }
+
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let current = self.get_current_state(&state);
+        if current.is_interactive() {
+            let Some(action) = self.schedule(current, rng) else {
+                return (None, ClockValue::zero(), None);
+            };
+            let next = self.sample_next(state, action.clone(), rng).map(|(s, _)| s);
+            return (next, ClockValue::zero(), Some(action));
+        }
+        if current.is_markovian() {
+            let delay = current.sample_delay(rng);
+            let next = self.sample_next(state, Action::Epsilon, rng).map(|(s, _)| s);
+            return (next, delay, Some(Action::Epsilon));
+        }
+        (Some(state), ClockValue::zero(), None)
+    }
+
+    fn get_meta() -> ModelMeta {
+        ModelMeta {
+            name : lbl("MarkovAutomaton"),
+            description : String::from("Markov automaton, mixing nondeterministic actions and exponentially distributed delays"),
+            characteristics : TIMED | CONTROLLABLE | STOCHASTIC
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        true
+    }
+
+    fn is_stochastic(&self) -> bool {
+        true
+    }
+
+    fn scheduler(&self) -> Option<Label> {
+        if !self.states.iter().any(|s| s.actions.len() > 1) {
+            return None;
+        }
+        match self.scheduler {
+            MAScheduler::Uniform => Some(lbl("Uniform")),
+            MAScheduler::Memoryless(_) => Some(lbl("Memoryless"))
+        }
+    }
+
+    fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        self.id = context.new_model();
+        let mut states = self.states.clone();
+        self.states_dic = HashMap::new();
+        for (i, s) in states.iter_mut().enumerate() {
+            s.index = i;
+            s.compile(context)?;
+            self.states_dic.insert(s.get_label(), s.index);
+        }
+        for s in states.iter_mut() {
+            self.build_outputs(context, s);
+        }
+        self.actions_dic = context.get_actions().into_iter().map(|(l, a)| (a, l)).collect();
+        self.states = states;
+        Ok(())
+    }
+
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn structure_hash(&self) -> Option<u64> {
+        hash_without_metadata(&self.states)
+    }
+
+}
+
+pub struct MarkovAutomatonMaker {
+    pub structure : Vec<MAState>,
+    pub scheduler : MAScheduler,
+}
+
+impl ModelMaker<MarkovAutomaton> for MarkovAutomatonMaker {
+
+    fn create_maker(model : MarkovAutomaton) -> Self {
+        MarkovAutomatonMaker {
+            structure : model.states.clone(),
+            scheduler : model.scheduler.clone(),
+        }
+    }
+
+    fn make(&self) -> (MarkovAutomaton, ModelContext) {
+        let mut automaton = MarkovAutomaton::new(self.structure.clone()).with_scheduler(self.scheduler.clone());
+        let ctx = automaton.singleton();
+        (automaton, ctx)
+    }
+
+}
@@ -1,11 +1,14 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::{HashMap, HashSet}};
 
 use serde::{Deserialize, Serialize};
 
-use crate::models::{action::Action, lbl, model_context::ModelContext, model_var::ModelVar, CompilationResult, Label, Model, ModelMaker, ModelMeta, ModelState, Node, CONTROLLABLE, STOCHASTIC};
+use crate::computation::random::SimulationRng;
+use crate::models::{action::Action, expressions::{Condition, Expr}, lbl, model_context::ModelContext, model_var::ModelVar, reward_structure::RewardStructure, CompilationError, CompilationResult, hash_without_metadata, Label, Model, ModelMaker, ModelMeta, ModelState, Node, CONTROLLABLE, STOCHASTIC};
 
 use super::{markov_node::MarkovNode, ProbabilisticChoice};
 
+mod analysis;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarkovChain {
     pub nodes : Vec<MarkovNode>,
@@ -61,27 +64,66 @@ impl MarkovChain {
 
     }
 
+    pub fn has_rewards(&self) -> bool {
+        self.nodes.iter().any(MarkovNode::has_rewards)
+    }
+
+    // Rewards carried by the nodes and their edges. Compiling registers them in the context as "rewards", so that
+    // expected reward queries and reward bounds can refer to them
+    pub fn reward_structure(&self, name : Label) -> RewardStructure {
+        let in_node = |label : &Label| Condition::Evaluation(Expr::Var(ModelVar::name(label.clone())));
+        let mut structure = RewardStructure::new(name);
+        for node in self.nodes.iter() {
+            if node.reward != 0.0 {
+                structure = structure.with_state_reward(in_node(&node.label), node.reward);
+            }
+            let mut edges : Vec<(&Label, &f64)> = node.edge_rewards.iter().collect();
+            edges.sort_by(|e1, e2| e1.0.cmp(e2.0));
+            for (target, reward) in edges {
+                structure = structure.with_transition_reward(in_node(&node.label), in_node(target), *reward);
+            }
+        }
+        structure
+    }
+
     pub fn get_structure(&self) -> Vec<MarkovNode> {
         self.nodes.clone()
     }
+
+    fn move_to(&self, mut state : ModelState, node : &MarkovNode, next_index : usize) -> (ModelState, HashSet<Action>) {
+        let next_node = &self.nodes[next_index];
+        let actions = next_node.available_actions();
+        state.unmark(node.get_var(), 1);
+        state.mark(next_node.get_var(), 1);
+        state.deadlocked = actions.is_empty();
+        (state, actions)
+    }
     
 }
 
 impl Model for MarkovChain {
 
-    fn next(&self,mut state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+    // Successors are drawn from entropy, runs drawing them from their own generator
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        self.sample_next(state, action, &mut SimulationRng::from_entropy())
+    }
+
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
         let node = self.get_current_node(&state);
-        let next_index = node.act(action);
+        let next_index = node.act(action, rng);
         if next_index == None {
             return None;
         }
-        let next_index = next_index.unwrap();
-        let next_node = &self.nodes[next_index];
-        let actions = next_node.available_actions();
-        state.unmark(node.get_var(), 1);
-        state.mark(next_node.get_var(), 1);
-        state.deadlocked = actions.len() == 0;
-        Some((state, actions))
+        Some(self.move_to(state, node, next_index.unwrap()))
+    }
+
+    fn successors(&self, state : &ModelState) -> Vec<ModelState> {
+        let node = self.get_current_node(state);
+        node.actions.values()
+            .flat_map(|choice| choice.0.iter().filter(|(_, p)| *p > 0.0).map(|(i, _)| *i))
+            .collect::<HashSet<usize>>().into_iter()
+            .map(|next_index| self.move_to(state.clone(), node, next_index).0)
+            .collect()
     }
 
     fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
@@ -104,6 +146,15 @@ impl Model for MarkovChain {
         true
     }
 
+    // Choice nodes are resolved uniformly by the default sampler
+    fn scheduler(&self) -> Option<Label> {
+        if self.nodes.iter().any(MarkovNode::is_choice) {
+            Some(lbl("Uniform"))
+        } else {
+            None
+        }
+    }
+
     fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
         self.id = context.new_model();
         // Not iter_mut in place else we wouldn't be able to borrow self as immut.
@@ -118,6 +169,9 @@ impl Model for MarkovChain {
             self.build_node_outputs(context, node);
         }
         self.nodes = nodes;
+        if self.has_rewards() {
+            context.add_reward(self.reward_structure(lbl("rewards"))).map_err(|_| CompilationError)?;
+        }
         Ok(())
     }
 
@@ -125,6 +179,10 @@ impl Model for MarkovChain {
         self.id
     }
 
+    fn structure_hash(&self) -> Option<u64> {
+        hash_without_metadata(&self.nodes)
+    }
+
 }
 
 pub struct MarkovChainMaker {
@@ -0,0 +1,167 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::models::action::Action;
+
+use super::MarkovChain;
+
+// Analytic computations on discrete-time Markov chains (no decision nodes), deadlocked nodes being absorbing.
+// Every method returns None if the chain has decision nodes, or hasn't been compiled.
+impl MarkovChain {
+
+    // Transition probabilities, rows being the source nodes
+    pub fn transition_matrix(&self) -> Option<DMatrix<f64>> {
+        if self.id == usize::MAX {
+            return None;
+        }
+        let n = self.nodes.len();
+        let mut matrix = DMatrix::zeros(n, n);
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.is_choice() {
+                return None;
+            }
+            match node.actions.get(&Action::Epsilon) {
+                None => matrix[(i, i)] = 1.0,
+                Some(choice) => for (j, p) in choice.0.iter() {
+                    matrix[(i, *j)] += p;
+                }
+            }
+        }
+        Some(matrix)
+    }
+
+    // Long-run distribution from the first node of the chain
+    pub fn stationary_distribution(&self) -> Option<DVector<f64>> {
+        self.long_run_distribution(0)
+    }
+
+    // Every bottom strongly connected component (BSCC) has its own stationary distribution, weighted by the
+    // probability to end in it from the initial node
+    pub fn long_run_distribution(&self, initial : usize) -> Option<DVector<f64>> {
+        let matrix = self.transition_matrix()?;
+        let components = bottom_components(&matrix);
+        let mut recurrent = vec![false; matrix.nrows()];
+        components.iter().flatten().for_each(|i| recurrent[*i] = true);
+        let transient : Vec<usize> = (0..matrix.nrows()).filter(|i| !recurrent[*i]).collect();
+        let mut distribution = DVector::zeros(matrix.nrows());
+        for component in components.iter() {
+            let weight = absorption_probabilities(&matrix, &transient, component)?[initial];
+            if weight <= 0.0 {
+                continue;
+            }
+            let local = component_distribution(&matrix, component)?;
+            component.iter().enumerate().for_each(|(k, i)| distribution[*i] += weight * local[k]);
+        }
+        Some(distribution)
+    }
+
+    // Probability to reach one of the target nodes, from every node
+    pub fn hitting_probabilities(&self, target : &[usize]) -> Option<DVector<f64>> {
+        let matrix = self.transition_matrix()?;
+        let n = matrix.nrows();
+        let can_reach = backward_reachable(&matrix, target, &[]);
+        let unknown : Vec<usize> = (0..n).filter(|i| can_reach[*i] && !target.contains(i)).collect();
+        absorption_probabilities(&matrix, &unknown, target)
+    }
+
+    // Expected number of steps to reach one of the target nodes, from every node. Infinite when the targets may be
+    // missed forever.
+    pub fn expected_hitting_time(&self, target : &[usize]) -> Option<DVector<f64>> {
+        let matrix = self.transition_matrix()?;
+        let n = matrix.nrows();
+        let can_reach = backward_reachable(&matrix, target, &[]);
+        let missed : Vec<usize> = (0..n).filter(|i| !can_reach[*i]).collect();
+        // Runs stop at the targets : reaching missed nodes after a target doesn't make the time infinite
+        let infinite = backward_reachable(&matrix, &missed, target);
+        let unknown : Vec<usize> = (0..n).filter(|i| !infinite[*i] && !target.contains(i)).collect();
+        let mut times = DVector::from_fn(n, |i, _| if infinite[i] && !target.contains(&i) { f64::INFINITY } else { 0.0 });
+        if unknown.is_empty() {
+            return Some(times);
+        }
+        let u = unknown.len();
+        let system = DMatrix::from_fn(u, u, |i, j| {
+            (if i == j { 1.0 } else { 0.0 }) - matrix[(unknown[i], unknown[j])]
+        });
+        let solution = system.lu().solve(&DVector::from_element(u, 1.0))?;
+        unknown.iter().enumerate().for_each(|(k, i)| times[*i] = solution[k]);
+        Some(times)
+    }
+
+}
+
+fn reachable(matrix : &DMatrix<f64>, from : usize) -> Vec<bool> {
+    let mut reached = vec![false; matrix.nrows()];
+    reached[from] = true;
+    let mut stack = vec![from];
+    while let Some(i) = stack.pop() {
+        for j in 0..matrix.ncols() {
+            if matrix[(i, j)] > 0.0 && !reached[j] {
+                reached[j] = true;
+                stack.push(j);
+            }
+        }
+    }
+    reached
+}
+
+// Nodes from which one of the given nodes can be reached, without going through the avoided ones
+fn backward_reachable(matrix : &DMatrix<f64>, nodes : &[usize], avoided : &[usize]) -> Vec<bool> {
+    let mut reached = vec![false; matrix.nrows()];
+    nodes.iter().for_each(|i| reached[*i] = true);
+    let mut stack = nodes.to_vec();
+    while let Some(j) = stack.pop() {
+        for i in 0..matrix.nrows() {
+            if matrix[(i, j)] > 0.0 && !reached[i] && !avoided.contains(&i) {
+                reached[i] = true;
+                stack.push(i);
+            }
+        }
+    }
+    reached
+}
+
+// Nodes of every BSCC : a node is in a BSCC iff every node it reaches can reach it back
+fn bottom_components(matrix : &DMatrix<f64>) -> Vec<Vec<usize>> {
+    let n = matrix.nrows();
+    let reach : Vec<Vec<bool>> = (0..n).map(|i| reachable(matrix, i)).collect();
+    let mut assigned = vec![false; n];
+    let mut components = Vec::new();
+    for i in 0..n {
+        if assigned[i] || !(0..n).all(|j| !reach[i][j] || reach[j][i]) {
+            continue;
+        }
+        let component : Vec<usize> = (0..n).filter(|j| reach[i][*j]).collect();
+        component.iter().for_each(|j| assigned[*j] = true);
+        components.push(component);
+    }
+    components
+}
+
+// Solves pi = pi.P on the component, the last balance equation being replaced by sum(pi) = 1
+fn component_distribution(matrix : &DMatrix<f64>, component : &[usize]) -> Option<DVector<f64>> {
+    let m = component.len();
+    let mut system = DMatrix::from_fn(m, m, |i, j| {
+        matrix[(component[j], component[i])] - if i == j { 1.0 } else { 0.0 }
+    });
+    system.row_mut(m - 1).fill(1.0);
+    let mut rhs = DVector::zeros(m);
+    rhs[m - 1] = 1.0;
+    system.lu().solve(&rhs)
+}
+
+// Probability to reach the given nodes from every node, solving x = P.x on the unknown nodes, the others being 0
+fn absorption_probabilities(matrix : &DMatrix<f64>, unknown : &[usize], nodes : &[usize]) -> Option<DVector<f64>> {
+    let n = matrix.nrows();
+    let u = unknown.len();
+    let mut values = DVector::zeros(n);
+    nodes.iter().for_each(|i| values[*i] = 1.0);
+    if u == 0 {
+        return Some(values);
+    }
+    let system = DMatrix::from_fn(u, u, |i, j| {
+        (if i == j { 1.0 } else { 0.0 }) - matrix[(unknown[i], unknown[j])]
+    });
+    let rhs = DVector::from_fn(u, |i, _| nodes.iter().map(|j| matrix[(unknown[i], *j)]).sum::<f64>());
+    let solution = system.lu().solve(&rhs)?;
+    unknown.iter().enumerate().for_each(|(k, i)| values[*i] = solution[k]);
+    Some(values)
+}
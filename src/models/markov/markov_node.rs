@@ -2,7 +2,8 @@ use std::{collections::{HashMap, HashSet}, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
-use crate::models::{action::Action, model_context::ModelContext, model_var::{ModelVar, VarType}, CompilationResult, Label, Node};
+use crate::computation::random::SimulationRng;
+use crate::models::{action::Action, model_context::ModelContext, model_var::{ModelVar, VarType}, CompilationResult, Label, Node, NodeMetadata};
 use super::ProbabilisticChoice;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -10,6 +11,15 @@ pub struct MarkovNode {
     pub label : Label,
     pub outputs : HashMap<Label, Vec<(Label, f64)>>,
 
+    // Reward earned at each step spent in the node, and when moving to the given successors (see MarkovChain::reward_structure)
+    #[serde(default)]
+    pub reward : f64,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub edge_rewards : HashMap<Label, f64>,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+
     #[serde(skip)]
     pub index : usize,
     #[serde(skip)]
@@ -46,6 +56,25 @@ impl MarkovNode {
         }
     }
 
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_reward(mut self, reward : f64) -> Self {
+        self.reward = reward;
+        self
+    }
+
+    pub fn with_edge_reward(mut self, target : Label, reward : f64) -> Self {
+        self.edge_rewards.insert(target, reward);
+        self
+    }
+
+    pub fn has_rewards(&self) -> bool {
+        self.reward != 0.0 || !self.edge_rewards.is_empty()
+    }
+
     pub fn get_var(&self) -> &ModelVar {
         &self.var
     }
@@ -76,11 +105,11 @@ impl MarkovNode {
         self.actions.keys().map(|a| a.clone()).collect()
     }
 
-    pub fn act(&self, action : Action) -> Option<usize> {
+    pub fn act(&self, action : Action, rng : &mut SimulationRng) -> Option<usize> {
         if !self.has_action(&action) {
             return None
         }
-        return Some(self.actions[&action].sample().clone())
+        return Some(self.actions[&action].sample(rng).clone())
     }
 
 }
@@ -91,6 +120,10 @@ impl Node for MarkovNode {
         self.label.clone()
     }
 
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
 }
 
 impl Clone for MarkovNode {
@@ -99,6 +132,9 @@ impl Clone for MarkovNode {
         MarkovNode {
             label : self.label.clone(),
             outputs : self.outputs.clone(),
+            reward : self.reward,
+            edge_rewards : self.edge_rewards.clone(),
+            metadata : self.metadata.clone(),
             ..Default::default()
         }
     }
@@ -0,0 +1,306 @@
+use std::{collections::{HashMap, HashSet}, fmt::Display, hash::{DefaultHasher, Hash, Hasher}, sync::Arc};
+
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+
+use crate::computation::random::SimulationRng;
+use crate::models::{action::Action, lbl, model_context::ModelContext, model_var::{ModelVar, VarType}, time::ClockValue, CompilationError, CompilationResult, Label, Model, ModelMaker, ModelMeta, ModelState, Node, NodeMetadata, CONTROLLABLE, STOCHASTIC};
+
+use super::{scheduler::{Scheduler, UniformScheduler}, ProbabilisticChoice};
+
+/// State of a Markov decision process : an action is chosen nondeterministically, then the successor is drawn from
+/// the distribution of the action. States without actions are absorbing.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct MDPState {
+    pub label : Label,
+    pub actions : HashMap<Label, Vec<(Label, f64)>>,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+
+    #[serde(skip)]
+    pub index : usize,
+    #[serde(skip)]
+    var : ModelVar,
+    #[serde(skip)]
+    pub compiled_actions : HashMap<Action, ProbabilisticChoice<usize>>,
+}
+
+impl MDPState {
+
+    pub fn new(label : Label, actions : HashMap<Label, Vec<(Label, f64)>>) -> MDPState {
+        MDPState {
+            label,
+            actions,
+            ..Default::default()
+        }
+    }
+
+    pub fn absorbing(label : Label) -> MDPState {
+        Self::new(label, HashMap::new())
+    }
+
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn get_var(&self) -> &ModelVar {
+        &self.var
+    }
+
+    pub fn is_nondeterministic(&self) -> bool {
+        self.actions.len() > 1
+    }
+
+    // Action labels, sorted so that schedulers see them in the same order each time
+    pub fn action_labels(&self) -> Vec<Label> {
+        let mut labels : Vec<Label> = self.actions.keys().cloned().collect();
+        labels.sort();
+        labels
+    }
+
+    pub fn available_actions(&self) -> HashSet<Action> {
+        self.compiled_actions.keys().cloned().collect()
+    }
+
+    pub fn compile(&mut self, ctx : &mut ModelContext) -> CompilationResult<()> {
+        self.var = ctx.add_var(self.get_label(), VarType::VarU8);
+        for (action_name, outcomes) in self.actions.iter() {
+            if outcomes.is_empty() || outcomes.iter().any(|(_, p)| *p < 0.0) {
+                return Err(CompilationError);
+            }
+            ctx.get_or_add_action(action_name.clone());
+        }
+        Ok(())
+    }
+
+}
+
+impl Node for MDPState {
+
+    fn get_label(&self) -> Label {
+        self.label.clone()
+    }
+
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
+}
+
+impl Clone for MDPState {
+
+    fn clone(&self) -> Self {
+        MDPState {
+            label : self.label.clone(),
+            actions : self.actions.clone(),
+            metadata : self.metadata.clone(),
+            ..Default::default()
+        }
+    }
+
+}
+
+impl Display for MDPState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MDPState({})", self.get_label())
+    }
+}
+
+fn default_scheduler() -> Arc<dyn Scheduler> {
+    Arc::new(UniformScheduler)
+}
+
+/// Markov decision process. Nondeterministic choices are resolved by the scheduler when simulating, uniformly unless
+/// another one is given : statistical results then hold for this scheduler only, see Model::scheduler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MDP {
+    pub states : Vec<MDPState>,
+    #[serde(skip, default = "default_scheduler")]
+    pub scheduler : Arc<dyn Scheduler>,
+    #[serde(skip)]
+    pub states_dic : HashMap<Label, usize>,
+    #[serde(skip)]
+    pub actions_dic : HashMap<Label, Action>,
+    #[serde(skip)]
+    pub id : usize
+}
+
+impl MDP {
+
+    pub fn new(states : Vec<MDPState>) -> MDP {
+        MDP {
+            states,
+            scheduler : default_scheduler(),
+            states_dic : HashMap::new(),
+            actions_dic : HashMap::new(),
+            id : usize::MAX
+        }
+    }
+
+    pub fn with_scheduler(mut self, scheduler : impl Scheduler + 'static) -> Self {
+        self.scheduler = Arc::new(scheduler);
+        self
+    }
+
+    pub fn get_vars(&self) -> impl Iterator<Item = &ModelVar> {
+        self.states.iter().map(|s| s.get_var())
+    }
+
+    pub fn get_current_state(&self, state : &ModelState) -> &MDPState {
+        let index = state.argmax(self.get_vars());
+        &self.states[index]
+    }
+
+    // Some state offers a choice between several actions
+    pub fn is_nondeterministic(&self) -> bool {
+        self.states.iter().any(MDPState::is_nondeterministic)
+    }
+
+    pub fn schedule(&self, current : &MDPState, rng : &mut SimulationRng) -> Option<Action> {
+        let choice = self.scheduler.schedule(&current.label, &current.action_labels(), rng)?;
+        self.actions_dic.get(&choice).cloned()
+    }
+
+    fn build_outputs(&self, ctx : &ModelContext, state : &mut MDPState) {
+        state.compiled_actions = HashMap::new();
+        for (a_label, c) in state.actions.iter() {
+            let action = ctx.get_action(a_label).unwrap_or_else(|| {
+                panic!("Unable to find action ! Maybe state hasn't been compiled");
+            });
+            let mapped : Vec<(usize, f64)> = c.iter().map(|(l,p)| {
+                (self.states_dic[l], *p)
+            }).collect();
+            state.compiled_actions.insert(action, ProbabilisticChoice(mapped).normalized());
+        }
+    }
+
+    fn move_to(&self, mut state : ModelState, from : &MDPState, to : usize) -> (ModelState, HashSet<Action>) {
+        let next = &self.states[to];
+        let actions = next.available_actions();
+        state.unmark(from.get_var(), 1);
+        state.mark(next.get_var(), 1);
+        state.deadlocked = actions.is_empty();
+        (state, actions)
+    }
+
+}
+
+impl Model for MDP {
+
+    // Successors are drawn from entropy, runs drawing them from their own generator
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        self.sample_next(state, action, &mut SimulationRng::from_entropy())
+    }
+
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        let current = self.get_current_state(&state);
+        let next_index = *current.compiled_actions.get(&action)?.sample(rng);
+        Some(self.move_to(state, current, next_index))
+    }
+
+    fn successors(&self, state : &ModelState) -> Vec<ModelState> {
+        let current = self.get_current_state(state);
+        current.compiled_actions.values()
+            .flat_map(|choice| choice.0.iter().filter(|(_, p)| *p > 0.0).map(|(i, _)| *i))
+            .collect::<HashSet<usize>>().into_iter()
+            .map(|next_index| self.move_to(state.clone(), current, next_index).0)
+            .collect()
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        self.get_current_state(state).available_actions()
+    }
+
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let current = self.get_current_state(&state);
+        if current.compiled_actions.is_empty() {
+            return (Some(state), ClockValue::zero(), None);
+        }
+        let Some(action) = self.schedule(current, rng) else {
+            return (None, ClockValue::zero(), None);
+        };
+        let next = self.sample_next(state, action.clone(), rng).map(|(s, _)| s);
+        (next, ClockValue::zero(), Some(action))
+    }
+
+    fn get_meta() -> ModelMeta {
+        ModelMeta {
+            name : lbl("MDP"),
+            description : String::from("Markov decision process, nondeterministic actions followed by probabilistic branching"),
+            characteristics : CONTROLLABLE | STOCHASTIC
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        false
+    }
+
+    fn is_stochastic(&self) -> bool {
+        true
+    }
+
+    fn scheduler(&self) -> Option<Label> {
+        if self.is_nondeterministic() {
+            Some(self.scheduler.get_name())
+        } else {
+            None
+        }
+    }
+
+    fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        self.id = context.new_model();
+        let mut states = self.states.clone();
+        self.states_dic = HashMap::new();
+        for (i, s) in states.iter_mut().enumerate() {
+            s.index = i;
+            s.compile(context)?;
+            self.states_dic.insert(s.get_label(), s.index);
+        }
+        if states.iter().flat_map(|s| s.actions.values().flatten()).any(|(l, _)| !self.states_dic.contains_key(l)) {
+            return Err(CompilationError);
+        }
+        for s in states.iter_mut() {
+            self.build_outputs(context, s);
+        }
+        self.actions_dic = context.get_actions().into_iter().collect();
+        self.states = states;
+        Ok(())
+    }
+
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+    fn structure_hash(&self) -> Option<u64> {
+        let json = serde_json::to_string(&self.states).ok()?;
+        let mut s = DefaultHasher::new();
+        json.hash(&mut s);
+        Some(s.finish())
+    }
+
+}
+
+pub struct MDPMaker {
+    pub structure : Vec<MDPState>,
+    pub scheduler : Arc<dyn Scheduler>,
+}
+
+impl ModelMaker<MDP> for MDPMaker {
+
+    fn create_maker(model : MDP) -> Self {
+        MDPMaker {
+            structure : model.states.clone(),
+            scheduler : Arc::clone(&model.scheduler),
+        }
+    }
+
+    fn make(&self) -> (MDP, ModelContext) {
+        let mut mdp = MDP::new(self.structure.clone());
+        mdp.scheduler = Arc::clone(&self.scheduler);
+        let ctx = mdp.singleton();
+        (mdp, ctx)
+    }
+
+}
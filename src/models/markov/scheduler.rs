@@ -0,0 +1,97 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use crate::{computation::random::{choose_uniform, SimulationRng}, models::{lbl, Label}};
+
+use super::ProbabilisticChoice;
+
+/// Resolves the nondeterministic choices of a model during simulation : given the label of the current state and its
+/// actions sorted by label, returns the action to take, random choices being drawn from the generator of the run.
+/// None leaves the run stuck in the state.
+pub trait Scheduler : Debug + Send + Sync {
+
+    fn schedule(&self, state : &Label, actions : &[Label], rng : &mut SimulationRng) -> Option<Label>;
+
+    fn get_name(&self) -> Label;
+
+}
+
+/// Every action is equally likely, the default when no scheduler is given
+#[derive(Debug, Clone, Default)]
+pub struct UniformScheduler;
+
+impl Scheduler for UniformScheduler {
+
+    fn schedule(&self, _ : &Label, actions : &[Label], rng : &mut SimulationRng) -> Option<Label> {
+        choose_uniform(actions, rng).cloned()
+    }
+
+    fn get_name(&self) -> Label {
+        lbl("Uniform")
+    }
+
+}
+
+/// Same action each time a state is visited, uniform on unmapped states
+#[derive(Debug, Clone, Default)]
+pub struct MemorylessScheduler {
+    pub choices : HashMap<Label, Label>,
+}
+
+impl MemorylessScheduler {
+
+    pub fn new(choices : HashMap<Label, Label>) -> Self {
+        MemorylessScheduler { choices }
+    }
+
+}
+
+impl Scheduler for MemorylessScheduler {
+
+    fn schedule(&self, state : &Label, actions : &[Label], rng : &mut SimulationRng) -> Option<Label> {
+        match self.choices.get(state) {
+            Some(choice) => actions.iter().find(|a| *a == choice).cloned(),
+            None => UniformScheduler.schedule(state, actions, rng)
+        }
+    }
+
+    fn get_name(&self) -> Label {
+        lbl("Memoryless")
+    }
+
+}
+
+/// Actions drawn from a distribution depending on the state, uniform on unmapped states.
+/// Weights of actions unavailable in the state are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct RandomizedScheduler {
+    pub choices : HashMap<Label, Vec<(Label, f64)>>,
+}
+
+impl RandomizedScheduler {
+
+    pub fn new(choices : HashMap<Label, Vec<(Label, f64)>>) -> Self {
+        RandomizedScheduler { choices }
+    }
+
+}
+
+impl Scheduler for RandomizedScheduler {
+
+    fn schedule(&self, state : &Label, actions : &[Label], rng : &mut SimulationRng) -> Option<Label> {
+        let Some(choices) = self.choices.get(state) else {
+            return UniformScheduler.schedule(state, actions, rng);
+        };
+        let available : Vec<(Label, f64)> = choices.iter()
+            .filter(|(a, w)| actions.contains(a) && *w > 0.0)
+            .cloned().collect();
+        if available.is_empty() {
+            return None;
+        }
+        Some(ProbabilisticChoice(available).sample(rng).clone())
+    }
+
+    fn get_name(&self) -> Label {
+        lbl("Randomized")
+    }
+
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::Label;
+use super::{model_context::ModelContext, model_var::{MappingError, MappingResult}, Label};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModelClock {
@@ -34,6 +34,10 @@ impl ModelClock {
         self.index != usize::MAX
     }
 
+    pub fn apply_to(&self, ctx : &ModelContext) -> MappingResult<ModelClock> {
+        ctx.get_clock(&self.name).ok_or(MappingError(self.name.clone()))
+    }
+
 }
 
 impl Default for ModelClock {
@@ -2,7 +2,7 @@ use std::{collections::HashMap, fmt::Display};
 
 use crate::computation::virtual_memory::{EvaluationType, VariableDefiner, VirtualMemory};
 
-use super::{action::Action, model_clock::ModelClock, model_storage::ModelStorage, model_var::{ModelVar, VarType}, Label, Model, ModelState};
+use super::{action::Action, model_clock::ModelClock, model_storage::ModelStorage, model_var::{MappingResult, ModelVar, VarType}, reward_structure::RewardStructure, Label, Model, ModelState};
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct ModelContext {
@@ -14,6 +14,9 @@ pub struct ModelContext {
     //io_actions : HashMap<Label, usize>,
     definer : VariableDefiner,
     path : Vec<Label>,
+    rewards : HashMap<Label, RewardStructure>,
+    // Marking variable address of timed-arc places -> (storage, index of the place in the storage)
+    token_places : HashMap<usize, (usize, usize)>,
 }
 
 impl ModelContext {
@@ -28,6 +31,8 @@ impl ModelContext {
             //io_actions : HashMap::new(),
             definer : VariableDefiner::new(),
             path : Vec::new(),
+            rewards : HashMap::new(),
+            token_places : HashMap::new(),
         }
     }
 
@@ -51,12 +56,20 @@ impl ModelContext {
         self.n_storages
     }
 
+    pub fn add_token_place(&mut self, var : &ModelVar, storage : usize, index : usize) {
+        self.token_places.insert(var.get_address(), (storage, index));
+    }
+
+    pub fn get_token_place(&self, var : &ModelVar) -> Option<(usize, usize)> {
+        self.token_places.get(&var.get_address()).copied()
+    }
+
     pub fn n_vars(&self) -> usize {
         self.vars.len()
     }
 
     pub fn memory_size(&self) -> usize {
-        self.vars.iter().map(|(_, x)| x.get_type().size() ).sum()
+        self.vars.iter().map(|(_, x)| x.size() ).sum()
     }
 
     pub fn n_actions(&self) -> usize {
@@ -85,6 +98,15 @@ impl ModelContext {
         var
     }
 
+    // Array of variables, whose elements can be accessed as name[i]
+    pub fn add_array(&mut self, name : Label, var_type : VarType, length : usize) -> ModelVar {
+        let var_name = self.get_local_name(name);
+        let mut var = ModelVar::name(var_name);
+        self.definer.define_array(&mut var, var_type, length);
+        self.vars.insert(var.name.clone(), var.clone());
+        var
+    }
+
     pub fn get_var(&self, name : &Label) -> Option<ModelVar> {
         let mut scope = self.path.clone();
         while scope.len() > 0 {
@@ -101,7 +123,15 @@ impl ModelContext {
         if self.vars.contains_key(&name) {
             return Some(self.vars[&name].clone())
         }
-        None
+        self.get_element(name)
+    }
+
+    // Element of an array variable, given a name such as buffer[3]
+    fn get_element(&self, name : &Label) -> Option<ModelVar> {
+        let name = name.to_string();
+        let (array, index) = name.strip_suffix(']')?.split_once('[')?;
+        let index = index.parse::<usize>().ok()?;
+        self.get_var(&Label::from(array))?.element(index)
     }
 
     pub fn has_var(&self, name : &Label) -> bool {
@@ -274,6 +304,22 @@ impl ModelContext {
         model.init_initial_storage(state)
     }
 
+    // Rewards are mapped to the context when added, so the objects they refer to must already be compiled
+    pub fn add_reward(&mut self, reward : RewardStructure) -> MappingResult<()> {
+        let reward = reward.apply_to(self)?;
+        self.rewards.insert(reward.name.clone(), reward);
+        Ok(())
+    }
+
+    // Named reward structure, or the only one defined if no name is given
+    pub fn get_reward(&self, name : &Option<Label>) -> Option<&RewardStructure> {
+        match name {
+            Some(name) => self.rewards.get(name),
+            None if self.rewards.len() == 1 => self.rewards.values().next(),
+            None => None
+        }
+    }
+
     pub fn make_empty_state(&self) -> ModelState {
         let mut state = ModelState::new(self.memory_size(), self.n_clocks());
         state.storages.resize(self.n_storages(), ModelStorage::EmptyStorage);
@@ -285,6 +331,8 @@ impl ModelContext {
         self.actions.clear();
         self.path.clear();
         self.definer.clear();
+        self.rewards.clear();
+        self.token_places.clear();
     }
 
 }
@@ -1,20 +1,209 @@
-use std::collections::{HashMap, HashSet};
+use std::{any::Any, collections::{HashMap, HashSet}, hash::{DefaultHasher, Hash, Hasher}};
 
 use num_traits::Zero;
+use rand::Rng;
 
-use super::{action::{Action, ActionPairs}, lbl, model_context::ModelContext, time::ClockValue, CompilationResult, Label, Model, ModelMeta, ModelState, NONE};
+use crate::computation::{combinatory::CartesianProduct, random::{choose_uniform, SimulationRng}};
 
+use super::{action::{Action, ActionPairs}, lbl, model_context::ModelContext, model_var::VarType, program::Program, time::ClockValue, CompilationError, CompilationResult, Label, Model, ModelMeta, ModelState, NONE};
+
+mod environment_stub;
+pub use environment_stub::EnvironmentStub;
+
+/// Network of models, each one compiled in the domain of its name. Components interleave their actions, except on
+/// channels : an action named "a!" is an output on channel a, "a?" an input, and an output only fires along with an
+/// input of another component, as a binary synchronization Action::Sync(a, input, output). The sender moves first.
+/// On broadcast channels, the output fires along with one input of every other component ready to receive, possibly
+/// none, as Action::Broadcast(a, output, inputs). Receivers then move in component order.
+/// Global variables are declared at the root of the network, where components find them when resolving a name they
+/// don't define. They are written by the update programs of component actions : on a synchronization, guards are
+/// evaluated beforehand, and the updates of the sender are executed before those of the receivers.
 pub struct ModelNetwork {
     pub id : usize,
     pub models : Vec<Box<dyn Model>>,
     pub models_map : HashMap<Label, usize>,
     pub actions_map : HashMap<usize, usize>,
-    pub io_actions : HashSet<Label, (Vec<Label>, Vec<Label>)>,
+    pub io_actions : HashMap<Label, (Vec<Label>, Vec<Label>)>, // { Component : (Inputs, Outputs) }
     pub sync_actions : HashMap<Action, ActionPairs>, // { Input : Output } s.t. (a => b) to fire
+    pub broadcast_channels : HashSet<Label>,
+    broadcast_actions : HashSet<Action>,
+    pub globals : Vec<(Label, VarType)>,
+    pub updates : HashMap<Label, Program>, // { Component action : Update of globals }
+    compiled_updates : HashMap<Action, Program>,
 }
 
 impl ModelNetwork {
 
+    pub fn new() -> Self {
+        ModelNetwork {
+            id : usize::MAX,
+            models : Vec::new(),
+            models_map : HashMap::new(),
+            actions_map : HashMap::new(),
+            io_actions : HashMap::new(),
+            sync_actions : HashMap::new(),
+            broadcast_channels : HashSet::new(),
+            broadcast_actions : HashSet::new(),
+            globals : Vec::new(),
+            updates : HashMap::new(),
+            compiled_updates : HashMap::new(),
+        }
+    }
+
+    // Declares a channel as broadcast, before compiling the network
+    pub fn add_broadcast_channel(&mut self, channel : Label) {
+        self.broadcast_channels.insert(channel);
+    }
+
+    pub fn is_broadcast_channel(&self, channel : &Action) -> bool {
+        self.broadcast_actions.contains(channel)
+    }
+
+    pub fn add_global(&mut self, name : Label, var_type : VarType) {
+        self.globals.push((name, var_type));
+    }
+
+    // Program executed each time the action fires, the action being named after its component, such as plant.t1
+    pub fn set_update(&mut self, action : Label, program : Program) {
+        self.updates.insert(action, program);
+    }
+
+    pub fn set_interface(&mut self, name : Label, inputs : Vec<Label>, outputs : Vec<Label>) {
+        self.io_actions.insert(name, (inputs, outputs));
+    }
+
+    // Stub over the interface of a component : its inputs are accepted, and its outputs emitted with the given rates (1 by default)
+    pub fn make_stub(&self, name : &Label, rates : &HashMap<Label, f64>) -> Option<EnvironmentStub> {
+        let (inputs, outputs) = self.io_actions.get(name)?;
+        let mut stub = EnvironmentStub::new(inputs.clone(), outputs.clone());
+        for (output, rate) in rates.iter() {
+            stub = stub.with_rate(output, *rate);
+        }
+        Some(stub)
+    }
+
+    // Substitutes a component by its stub, returning the replaced component. The network has to be compiled again.
+    pub fn stub_component(&mut self, name : &Label, rates : &HashMap<Label, f64>) -> Option<Box<dyn Model>> {
+        let stub = self.make_stub(name, rates)?;
+        let index = *self.models_map.get(name)?;
+        Some(std::mem::replace(&mut self.models[index], Box::new(stub)))
+    }
+
+    pub fn stubs(&self) -> impl Iterator<Item = &EnvironmentStub> {
+        self.models.iter().filter_map(|m| {
+            let model : &dyn Any = m.as_ref();
+            model.downcast_ref::<EnvironmentStub>()
+        })
+    }
+
+    // Synchronizations are stub actions when the stub sends, its outputs being raced
+    fn is_stub_action(&self, action : &Action) -> bool {
+        let action = match action {
+            Action::Sync(_, _, output) | Action::Broadcast(_, output, _) => output.as_ref(),
+            _ => action
+        };
+        self.owner(action).is_some_and(|i| {
+            let model : &dyn Any = self.models[i].as_ref();
+            model.is::<EnvironmentStub>()
+        })
+    }
+
+    // Index of the component of an action
+    pub fn owner(&self, action : &Action) -> Option<usize> {
+        self.actions_map.get(&action.get_id()).copied()
+    }
+
+    pub fn is_channel_action(&self, action : &Action) -> bool {
+        self.sync_actions.values().any(|pairs| pairs.contains(action))
+    }
+
+    // Channel of a component action, given its local name
+    fn channel_of(name : &Label) -> Option<(Label, bool)> {
+        let name = name.to_string();
+        if let Some(channel) = name.strip_suffix('!') {
+            return Some((Label::from(channel), false));
+        }
+        name.strip_suffix('?').map(|channel| (Label::from(channel), true))
+    }
+
+    // Output of a stub after the given delay, synchronized with one of the ready receivers if sent on a channel
+    fn emit_output(&self, state : ModelState, action : Action, delay : ClockValue, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let Some(delayed) = self.delay(state, delay) else {
+            return (None, delay, Some(action));
+        };
+        let action = if self.is_channel_action(&action) {
+            let mut syncs : Vec<Action> = self.available_actions(&delayed).into_iter()
+                .filter(|a| matches!(a, Action::Sync(_, _, output) | Action::Broadcast(_, output, _) if output.base() == action.base()))
+                .collect();
+            syncs.sort_by_key(sort_key);
+            match choose_uniform(&syncs, rng) {
+                Some(sync) => sync.clone(),
+                None => return (Some(delayed), delay, None)
+            }
+        } else {
+            action
+        };
+        let next = self.sample_next(delayed, action.clone(), rng).map(|(s, _)| s);
+        (next, delay, Some(action))
+    }
+
+    // Fires a component action, its outcome drawn from the given generator, then executes its update
+    fn fire(&self, state : ModelState, action : &Action, rng : &mut SimulationRng) -> Option<ModelState> {
+        let model_index = self.owner(action)?;
+        let (next_state, _) = self.models[model_index].sample_next(state, action.clone(), rng)?;
+        Some(self.update(next_state, action))
+    }
+
+    // Fires the component actions of a synchronization, sender first. Every guard is evaluated in the state before the
+    // synchronization : components only move if all their actions are available, and updates are executed once they
+    // all moved
+    fn fire_synchronized(&self, state : ModelState, actions : &[&Action], rng : &mut SimulationRng) -> Option<ModelState> {
+        for action in actions.iter() {
+            let model_index = self.owner(action)?;
+            if !self.models[model_index].available_actions(&state).contains(*action) {
+                return None;
+            }
+        }
+        let mut next_state = state;
+        for action in actions.iter() {
+            let model_index = self.owner(action)?;
+            next_state = self.models[model_index].sample_next(next_state, (*action).clone(), rng)?.0;
+        }
+        Some(actions.iter().fold(next_state, |s, action| self.update(s, action)))
+    }
+
+    // Executes the update of a component action, notifying every component of the written variables
+    fn update(&self, state : ModelState, action : &Action) -> ModelState {
+        let Some(update) = self.compiled_updates.get(&action.base()) else {
+            return state;
+        };
+        let written = update.written_vars();
+        let updated = update.execute(state);
+        self.models.iter().fold(updated, |s, m| m.vars_written(s, &written))
+    }
+
+    // Every way the output can synchronize with one enabled input per other component
+    fn broadcasts(&self, channel : &Action, output : &Action, inputs : &HashSet<Action>) -> Vec<Action> {
+        let sender = self.owner(output);
+        let mut receivers : Vec<Vec<Action>> = vec![Vec::new() ; self.n_models()];
+        for input in inputs.iter() {
+            match self.owner(input) {
+                Some(i) if Some(i) != sender => receivers[i].push(input.clone()),
+                _ => continue
+            }
+        }
+        receivers.retain(|r| !r.is_empty());
+        if receivers.is_empty() {
+            return vec![channel.broadcast(output.clone(), Vec::new())];
+        }
+        for r in receivers.iter_mut() {
+            r.sort_by_key(Action::get_id);
+        }
+        CartesianProduct::of(&receivers).map(|inputs| {
+            channel.broadcast(output.clone(), inputs.into_iter().cloned().collect())
+        }).collect()
+    }
+
     pub fn add_model(&mut self, name : Label, model : Box<dyn Model>) {
         self.models_map.insert(name, self.n_models());
         self.models.push(model);
@@ -24,6 +213,22 @@ impl ModelNetwork {
         self.models.len()
     }
 
+    pub fn get_model(&self, name : &Label) -> Option<&dyn Model> {
+        self.models_map.get(name).map(|i| self.models[*i].as_ref())
+    }
+
+    pub fn component_hashes(&self) -> HashMap<Label, Option<u64>> {
+        self.models_map.iter().map(|(name, i)| {
+            (name.clone(), self.models[*i].structure_hash())
+        }).collect()
+    }
+
+}
+
+impl Default for ModelNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Model for ModelNetwork {
@@ -36,26 +241,30 @@ impl Model for ModelNetwork {
         }
     }
 
+    // Outcomes of the components are drawn from entropy, runs drawing them from their own generator
     fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
-        if !self.actions_map.contains_key(&action.get_id()) {
-            return None;
-        }
-        let model_index = self.actions_map[&action.get_id()];
-        let model = &self.models[model_index];
-        let next = model.next(state, action);
-        if next.is_none() {
-            return None;
-        }
-        let (next_state, mut next_actions) = next.unwrap();
-        for (i, m) in self.models.iter().enumerate() {
-            if i == model_index {
-                continue;
+        self.sample_next(state, action, &mut SimulationRng::from_entropy())
+    }
+
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        let next_state = match &action {
+            Action::Sync(_, input, output) => self.fire_synchronized(state, &[output, input], rng)?,
+            Action::Broadcast(_, output, inputs) => {
+                let actions : Vec<&Action> = std::iter::once(output.as_ref()).chain(inputs.iter()).collect();
+                self.fire_synchronized(state, &actions, rng)?
+            },
+            _ => {
+                if self.is_channel_action(&action) {
+                    return None;
+                }
+                self.fire(state, &action, rng)?
             }
-            next_actions.extend(m.available_actions(&next_state));
-        }
+        };
+        let next_actions = self.available_actions(&next_state);
         Some((next_state, next_actions))
     }
 
+    // Channel actions are only available synchronized, with an action of another component
     fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
         let mut actions = HashSet::new();
         for m in self.models.iter() {
@@ -65,7 +274,16 @@ impl Model for ModelNetwork {
         for (sync, pairs) in self.sync_actions.iter() {
             let enabled = pairs.enabled(&actions);
             actions = enabled.remove_io(actions);
+            if self.is_broadcast_channel(sync) {
+                for output in enabled.outputs().iter() {
+                    synchros.extend(self.broadcasts(sync, output, enabled.inputs()));
+                }
+                continue;
+            }
             for (i,o) in enabled.generate_pairs() {
+                if self.owner(&i) == self.owner(&o) {
+                    continue;
+                }
                 synchros.insert(Action::Sync(sync.get_id(), Box::new(i), Box::new(o)));
             }
         }
@@ -73,6 +291,7 @@ impl Model for ModelNetwork {
         actions
     }
 
+    // Components with neither delay nor action available, such as those waiting for a global variable, don't block time
     fn available_delay(&self, state : &ModelState) -> ClockValue {
         let mut min_delay = ClockValue::infinity();
         let mut is_timed = false;
@@ -80,8 +299,11 @@ impl Model for ModelNetwork {
             if !model.is_timed() {
                 continue
             }
-            is_timed = true;
             let model_delay = model.available_delay(state);
+            if model_delay.is_zero() && model.available_actions(state).is_empty() {
+                continue
+            }
+            is_timed = true;
             if model_delay < min_delay {
                 min_delay = model_delay;
             }
@@ -93,6 +315,54 @@ impl Model for ModelNetwork {
         }
     }
 
+    fn init_initial_clocks(&self, state : ModelState) -> ModelState {
+        self.models.iter().fold(state, |s, m| m.init_initial_clocks(s))
+    }
+
+    fn init_initial_storage(&self, state : ModelState) -> ModelState {
+        self.models.iter().fold(state, |s, m| m.init_initial_storage(s))
+    }
+
+    fn delay(&self, state : ModelState, dt : ClockValue) -> Option<ModelState> {
+        let mut state = state;
+        for model in self.models.iter().filter(|m| m.is_timed()) {
+            state = model.delay(state, dt)?;
+        }
+        Some(state)
+    }
+
+    // Stub outputs race with exponential delays. The other components move first if they have an action available
+    // before the earliest output, their delay and action being drawn as by the default sampler.
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let output = self.stubs().filter_map(|s| s.sample_output(rng)).min_by(|(_, d1), (_, d2)| d1.partial_cmp(d2).unwrap());
+        let max_delay = self.available_delay(&state);
+        let untimed = !self.models.iter().any(|m| m.is_timed());
+        let mut delay = ClockValue::zero();
+        if !max_delay.is_zero() && !untimed {
+            delay = rng.gen_range(ClockValue::zero()..max_delay);
+        }
+        if let Some((action, output_delay)) = output.clone() {
+            if output_delay <= delay {
+                return self.emit_output(state, action, output_delay, rng);
+            }
+        }
+        let Some(delayed) = self.delay(state.clone(), delay) else {
+            return (None, delay, None);
+        };
+        let mut actions : Vec<Action> = self.available_actions(&delayed).into_iter().filter(|a| !self.is_stub_action(a)).collect();
+        actions.sort_by_key(sort_key);
+        let Some(action) = choose_uniform(&actions, rng).cloned() else {
+            return match output {
+                Some((action, output_delay)) if untimed || output_delay <= max_delay => {
+                    self.emit_output(state, action, output_delay, rng)
+                },
+                _ => (Some(delayed), delay, None)
+            };
+        };
+        let next = self.sample_next(delayed, action.clone(), rng).map(|(s, _)| s);
+        (next, delay, Some(action))
+    }
+
     fn is_timed(&self) -> bool {
         self.models.iter().map(|m| m.is_timed() ).fold(true,|acc, x| acc || x)
     }
@@ -102,7 +372,18 @@ impl Model for ModelNetwork {
     }
 
     fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
-        for (name, model_index) in self.models_map.iter() {
+        self.id = context.new_model();
+        self.actions_map.clear();
+        self.sync_actions.clear();
+        self.broadcast_actions.clear();
+        self.compiled_updates.clear();
+        for (name, var_type) in self.globals.iter() {
+            context.add_var(name.clone(), *var_type);
+        }
+        // Components are compiled in declaration order, so that their variables and actions don't depend on the map
+        let mut components : Vec<(&Label, &usize)> = self.models_map.iter().collect();
+        components.sort_by_key(|(_, model_index)| **model_index);
+        for (name, model_index) in components {
             let model : &mut Box<dyn Model> = &mut self.models[*model_index];
             context.add_domain(name.clone());
             model.compile(context)?;
@@ -112,6 +393,33 @@ impl Model for ModelNetwork {
             }
             context.parent();
         }
+        // Channels are declared at the root of the network, after every component
+        let mut component_actions : Vec<(Label, Action)> = context.get_actions().into_iter()
+            .filter(|(_, action)| self.owner(action).is_some())
+            .collect();
+        component_actions.sort_by_key(|(_, action)| action.get_id());
+        for (name, action) in component_actions {
+            let (_, local_name) = name.get_first_ident();
+            let Some((channel, is_input)) = Self::channel_of(&local_name) else {
+                continue;
+            };
+            let is_broadcast = self.broadcast_channels.contains(&channel);
+            let channel = context.get_or_add_action(channel);
+            if is_broadcast {
+                self.broadcast_actions.insert(channel.clone());
+            }
+            let pairs = self.sync_actions.entry(channel).or_default();
+            if is_input {
+                pairs.add_input(action);
+            } else {
+                pairs.add_output(action);
+            }
+        }
+        for (name, program) in self.updates.iter() {
+            let action = context.get_action(name).ok_or(CompilationError)?;
+            let program = program.apply_to(context).map_err(|_| CompilationError)?;
+            self.compiled_updates.insert(action, program);
+        }
         Ok(())
     }
 
@@ -119,4 +427,31 @@ impl Model for ModelNetwork {
         self.id
     }
 
-}
\ No newline at end of file
+    fn structure_hash(&self) -> Option<u64> {
+        let mut components : Vec<(Label, Option<u64>)> = self.component_hashes().into_iter().collect();
+        components.sort();
+        let mut s = DefaultHasher::new();
+        for (name, hash) in components {
+            name.hash(&mut s);
+            hash?.hash(&mut s);
+        }
+        self.globals.hash(&mut s);
+        let mut updates : Vec<(&Label, &Program)> = self.updates.iter().collect();
+        updates.sort_by_key(|(name, _)| *name);
+        updates.hash(&mut s);
+        Some(s.finish())
+    }
+
+}
+
+// Actions sorted by id, synchronizations of a channel by their input then output, and broadcasts by their output then
+// inputs, so that draws are reproducible
+fn sort_key(action : &Action) -> (usize, Vec<usize>) {
+    match action {
+        Action::Sync(channel, input, output) => (*channel, vec![input.get_id(), output.get_id()]),
+        Action::Broadcast(channel, output, inputs) => {
+            (*channel, std::iter::once(output.as_ref()).chain(inputs.iter()).map(Action::get_id).collect())
+        },
+        _ => (action.get_id(), Vec::new())
+    }
+}
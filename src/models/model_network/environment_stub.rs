@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+
+use num_traits::Zero;
+use rand::Rng;
+
+use crate::computation::random::SimulationRng;
+use crate::models::{action::Action, lbl, model_context::ModelContext, time::ClockValue, CompilationError, CompilationResult, Label, Model, ModelMeta, ModelState, STOCHASTIC};
+
+/// Stochastic stand-in for an unfinished component of a network. Inputs are always accepted, and outputs race :
+/// each one is emitted after an exponentially distributed delay of its own rate. The stub has no state.
+#[derive(Debug, Clone, Default)]
+pub struct EnvironmentStub {
+    pub id : usize,
+    pub inputs : Vec<Label>,
+    pub outputs : Vec<(Label, f64)>,
+    compiled_inputs : Vec<Action>,
+    compiled_outputs : Vec<(Action, f64)>,
+}
+
+impl EnvironmentStub {
+
+    pub fn new(inputs : Vec<Label>, outputs : Vec<Label>) -> Self {
+        EnvironmentStub {
+            id : usize::MAX,
+            inputs,
+            outputs : outputs.into_iter().map(|o| (o, 1.0)).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_rate(mut self, output : &Label, rate : f64) -> Self {
+        for (label, r) in self.outputs.iter_mut() {
+            if label == output {
+                *r = rate;
+            }
+        }
+        self
+    }
+
+    pub fn exit_rate(&self) -> f64 {
+        self.compiled_outputs.iter().map(|(_, r)| r).sum()
+    }
+
+    pub fn is_output(&self, action : &Action) -> bool {
+        self.compiled_outputs.iter().any(|(a, _)| *a == action.base())
+    }
+
+    // Output winning the race and its delay, None if the stub has no output
+    pub fn sample_output(&self, rng : &mut SimulationRng) -> Option<(Action, ClockValue)> {
+        if self.compiled_outputs.is_empty() {
+            return None;
+        }
+        let u : f64 = rng.gen();
+        let delay = ClockValue::from(-(1.0 - u).ln() / self.exit_rate());
+        let mut pick = rng.gen::<f64>() * self.exit_rate();
+        for (action, rate) in self.compiled_outputs.iter() {
+            if pick < *rate {
+                return Some((action.clone(), delay));
+            }
+            pick -= rate;
+        }
+        self.compiled_outputs.last().map(|(a, _)| (a.clone(), delay))
+    }
+
+}
+
+impl Model for EnvironmentStub {
+
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        let base = action.base();
+        if !self.compiled_inputs.contains(&base) && !self.is_output(&base) {
+            return None;
+        }
+        let actions = self.available_actions(&state);
+        Some((state, actions))
+    }
+
+    fn available_actions(&self, _ : &ModelState) -> HashSet<Action> {
+        self.compiled_inputs.iter().cloned().chain(self.compiled_outputs.iter().map(|(a, _)| a.clone())).collect()
+    }
+
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        match self.sample_output(rng) {
+            Some((action, delay)) => (Some(state), delay, Some(action)),
+            None => (Some(state), ClockValue::zero(), None)
+        }
+    }
+
+    fn get_meta() -> ModelMeta {
+        ModelMeta {
+            name : lbl("EnvironmentStub"),
+            description : String::from("Stochastic stub of a network component, emitting its outputs with exponential delays"),
+            characteristics : STOCHASTIC
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        false
+    }
+
+    fn is_stochastic(&self) -> bool {
+        true
+    }
+
+    fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        self.id = context.new_model();
+        if self.outputs.iter().any(|(_, r)| *r <= 0.0) {
+            return Err(CompilationError);
+        }
+        self.compiled_inputs = self.inputs.iter().map(|i| context.get_or_add_action(i.clone())).collect();
+        self.compiled_outputs = self.outputs.iter().map(|(o, r)| (context.get_or_add_action(o.clone()), *r)).collect();
+        Ok(())
+    }
+
+    fn get_id(&self) -> usize {
+        self.id
+    }
+
+}
@@ -0,0 +1,103 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{computation::{combinatory::CartesianProduct, virtual_memory::EvaluationType}, QueryTransformer};
+
+use super::{expressions::{Condition, Expr}, initial_marking::MarkingValue, model_project::ModelProject, Label};
+
+/// Values of the parameters of a template, by name
+pub type ParameterValuation = HashMap<Label, EvaluationType>;
+
+type ProjectGenerator<S> = dyn Fn(&ParameterValuation) -> ModelProject<S> + Send + Sync;
+
+/// Integer parameter of a template, such as a number of clients N or a buffer size K
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelParameter {
+    pub name : Label,
+    pub default : EvaluationType,
+}
+
+/// Model project generated from the values of its parameters. The generator uses them to build the structure, its
+/// weights and its marking, and substitutes them in guards with `substitute_parameters`. Instances keep the values
+/// they were generated from in `ModelProject::parameters`.
+pub struct ModelTemplate<S> {
+    pub parameters : Vec<ModelParameter>,
+    generator : Arc<ProjectGenerator<S>>,
+}
+
+impl<S> ModelTemplate<S> {
+
+    pub fn new(generator : impl Fn(&ParameterValuation) -> ModelProject<S> + Send + Sync + 'static) -> Self {
+        ModelTemplate { parameters : Vec::new(), generator : Arc::new(generator) }
+    }
+
+    pub fn with_parameter(mut self, name : Label, default : EvaluationType) -> Self {
+        self.parameters.retain(|p| p.name != name);
+        self.parameters.push(ModelParameter { name, default });
+        self
+    }
+
+    pub fn has_parameter(&self, name : &Label) -> bool {
+        self.parameters.iter().any(|p| p.name == *name)
+    }
+
+    // Default values, overriden by the given ones. None if a value is given to an undeclared parameter
+    pub fn valuation(&self, values : &ParameterValuation) -> Option<ParameterValuation> {
+        if values.keys().any(|name| !self.has_parameter(name)) {
+            return None;
+        }
+        Some(self.parameters.iter().map(|p| {
+            (p.name.clone(), *values.get(&p.name).unwrap_or(&p.default))
+        }).collect())
+    }
+
+    pub fn instantiate(&self, values : &ParameterValuation) -> Option<ModelProject<S>> {
+        let valuation = self.valuation(values)?;
+        Some((self.generator)(&valuation).with_parameters(valuation))
+    }
+
+    // Instances over every combination of the given values, parameters left out taking their default one
+    pub fn sweep(&self, ranges : &HashMap<Label, MarkingValue>) -> Option<Vec<ModelProject<S>>> {
+        let mut names : Vec<&Label> = ranges.keys().collect();
+        names.sort();
+        let values : Vec<Vec<EvaluationType>> = names.iter().map(|name| ranges[*name].values()).collect();
+        if names.is_empty() {
+            return Some(vec![self.instantiate(&ParameterValuation::new())?]);
+        }
+        if values.iter().any(Vec::is_empty) {
+            return Some(Vec::new());
+        }
+        CartesianProduct::of(&values).map(|tuple| {
+            let valuation : ParameterValuation = names.iter().zip(tuple).map(|(name, v)| ((*name).clone(), *v)).collect();
+            self.instantiate(&valuation)
+        }).collect()
+    }
+
+}
+
+// Replaces parameter names by their value
+struct ParameterSubstitution<'a> {
+    valuation : &'a ParameterValuation
+}
+
+impl QueryTransformer for ParameterSubstitution<'_> {
+
+    fn transform_expression(&mut self, expr : &mut Expr) {
+        if let Expr::Var(x) = expr {
+            if let Some(value) = self.valuation.get(&x.name) {
+                *expr = Expr::Constant(*value);
+                return;
+            }
+        }
+        expr.transform_children(self);
+    }
+
+}
+
+// Guard where parameters are replaced by their value, to be done before mapping it to a context
+pub fn substitute_parameters(condition : &Condition, valuation : &ParameterValuation) -> Condition {
+    let mut condition = condition.clone();
+    ParameterSubstitution { valuation }.transform_condition(&mut condition);
+    condition
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{verification::{query::Query, text_query_parser::{parse_query_with, QueryParsingResult}, PredicateLibrary}};
+
+use super::{initial_marking::InitialMarking, model_context::ModelContext, model_param::ParameterValuation, Label, Model, ModelState};
+
+/// Model structure bundled with its initial configuration, as written to and read from files.
+/// The initial state only exists once the model has been compiled, it is rebuilt from the marking.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelProject<S> {
+    pub structure : S,
+    pub initial_marking : InitialMarking,
+    #[serde(default)]
+    pub predicates : PredicateLibrary,
+    // Seed of the simulations of the model, given to its verifications so that their runs can be reproduced
+    #[serde(default)]
+    pub seed : Option<u64>,
+    // Values of the parameters of the template the project was generated from
+    #[serde(default)]
+    pub parameters : ParameterValuation,
+
+    #[serde(skip)]
+    pub initial_state : Option<ModelState>,
+}
+
+impl<S> ModelProject<S> {
+
+    pub fn new(structure : S, initial_marking : InitialMarking) -> Self {
+        ModelProject { structure, initial_marking, predicates : PredicateLibrary::new(), seed : None, parameters : ParameterValuation::new(), initial_state : None }
+    }
+
+    pub fn from_state(structure : S, ctx : &ModelContext, state : &ModelState) -> Self {
+        ModelProject {
+            structure,
+            initial_marking : InitialMarking::from_state(ctx, state),
+            predicates : PredicateLibrary::new(),
+            seed : None,
+            parameters : ParameterValuation::new(),
+            initial_state : Some(state.clone())
+        }
+    }
+
+    pub fn with_seed(mut self, seed : u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_parameters(mut self, parameters : ParameterValuation) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn instantiate(&mut self, ctx : &ModelContext, model : &impl Model) -> ModelState {
+        let state = self.initial_marking.initial_state(ctx, model);
+        self.initial_state = Some(state.clone());
+        state
+    }
+
+    // Project-wide predicate, such as : def safe := p1 + p2 <= 1
+    pub fn define_predicate(&mut self, definition : &str) -> QueryParsingResult<Label> {
+        let mut predicates = self.predicates.clone();
+        let name = predicates.define_text(&ModelContext::new(), definition)?;
+        predicates.check(&ModelContext::new())?;
+        self.predicates = predicates;
+        Ok(name)
+    }
+
+    pub fn parse_query(&self, ctx : &ModelContext, query : &str) -> QueryParsingResult<Query> {
+        parse_query_with(String::from(query), &self.predicates, ctx)
+    }
+
+    // Builds and compiles the model, along with its initial state
+    pub fn make<M : Model + From<S>>(&mut self) -> (M, ModelContext) where S : Clone {
+        let mut model = M::from(self.structure.clone());
+        let ctx = model.singleton();
+        self.instantiate(&ctx, &model);
+        (model, ctx)
+    }
+
+}
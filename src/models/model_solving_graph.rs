@@ -1,4 +1,7 @@
-use crate::{models::*, solution::Solution, verification::query::Query, translation::Translation};
+use std::{any::Any, time::Instant};
+
+use crate::{export::ModelFormat, models::*, solution::{PipelineStage, Provenance, Solution, SolverResult, StageKind}, verification::{query::{Quantifier, Query}, VerificationReport}, translation::Translation};
+use crate::models::model_context::ModelContext;
 
 use self::node::DataNode;
 
@@ -7,6 +10,10 @@ pub struct ModelSolvingGraph {
     pub translations : Vec<Box<dyn Translation>>,
     pub solutions : Vec<Box<dyn Solution>>,
     pub edges : Vec<Edge<usize, usize, usize>>,
+    // Timed queries on untimed models are refused instead of being adapted
+    pub strict : bool,
+    // File formats of the models, each one a ModelFormat of its model type
+    pub formats : Vec<Box<dyn Any + Send + Sync>>,
 }
 
 impl ModelSolvingGraph {
@@ -16,10 +23,17 @@ impl ModelSolvingGraph {
             models : Vec::new(),
             translations : Vec::new(),
             solutions : Vec::new(),
-            edges : Vec::new()
+            edges : Vec::new(),
+            strict : false,
+            formats : Vec::new()
         }
     }
 
+    pub fn with_strictness(mut self, strict : bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
     pub fn register_model(&mut self, meta : ModelMeta) {
         let node = DataNode::from(meta);
         self.models.push(node);
@@ -33,8 +47,118 @@ impl ModelSolvingGraph {
         self.solutions.push(solution)
     }
 
-    pub fn solve(&mut self, model : &dyn Any, query : &Query) {
-        
+    pub fn register_format<M : 'static>(&mut self, format : ModelFormat<M>) {
+        self.formats.push(Box::new(format))
+    }
+
+    pub fn formats_of<M : 'static>(&self) -> impl Iterator<Item = &ModelFormat<M>> {
+        self.formats.iter().filter_map(|f| f.downcast_ref::<ModelFormat<M>>())
+    }
+
+    // Format reading models of type M from the files of the given extension
+    pub fn reader<M : 'static>(&self, extension : &str) -> Option<&ModelFormat<M>> {
+        self.formats_of::<M>().find(|f| f.extension == extension && f.read.is_some())
+    }
+
+    // Format writing models of type M to files of the given extension
+    pub fn writer<M : 'static>(&self, extension : &str) -> Option<&ModelFormat<M>> {
+        self.formats_of::<M>().find(|f| f.extension == extension && f.write.is_some())
+    }
+
+    // Solves the query directly on the model if possible, otherwise through a translation.
+    // Every step taken is recorded, with its duration, in the returned provenance.
+    pub fn solve(&mut self, model : &dyn Any, model_name : &Label, context : &ModelContext, initial_state : &ModelState, query : &Query) -> (SolverResult, Provenance) {
+        let mut provenance = Provenance::new();
+        let mut stage = PipelineStage::new(StageKind::Model, model_name.clone());
+        let registered = self.models.iter().find(|m| m.element.name == *model_name).map(|m| &m.element);
+        let untimed_model = registered.is_some_and(|m| !m.is_timed());
+        // Untimed stochastic models (Markov chains, MDPs) are discrete-time : each step takes one time unit
+        let discrete_time = registered.is_some_and(|m| m.is_stochastic());
+        let adapted;
+        let mut query = query;
+        if untimed_model && query.is_timed() {
+            let adaptation = PipelineStage::new(StageKind::Adaptation, lbl("UntimedQuery"));
+            if self.strict {
+                stage.push(adaptation.with_outcome("refused by strict solver"));
+                provenance.push(stage);
+                return (SolverResult::unknown(format!("Timed query on untimed model {}", model_name)), provenance);
+            }
+            let (untimed, changes) = match query.untimed(discrete_time) {
+                Ok(adapted) => adapted,
+                Err(reason) => {
+                    stage.push(adaptation.with_outcome(format!("refused : {}", reason)));
+                    provenance.push(stage);
+                    return (SolverResult::unknown(format!("Timed query on untimed model {}", model_name)), provenance);
+                }
+            };
+            stage.push(adaptation.with_outcome(changes.join(", ")));
+            adapted = untimed;
+            query = &adapted;
+        }
+        // Runs of statistical queries are truncated by a bound inferred from the model, if it is sound
+        let bounded;
+        if matches!(query.quantifier, Quantifier::Probability | Quantifier::ProbabilityBound(..)) {
+            if let Some((bounded_query, inferred)) = query.with_inferred_bound(model, initial_state) {
+                if inferred.sound {
+                    stage.push(PipelineStage::new(StageKind::Adaptation, lbl("RunBoundInference")).with_outcome(inferred));
+                    bounded = bounded_query;
+                    query = &bounded;
+                }
+            }
+        }
+        let mut result = Self::try_solutions(&mut self.solutions, model, model_name, context, query, &mut stage);
+        if !result.is_conclusive() {
+            for translation in self.translations.iter_mut() {
+                let meta = translation.get_meta();
+                let solvable = self.solutions.iter().any(|s| s.get_meta().model_name == meta.output);
+                if (meta.input != *model_name && meta.input != lbl("any")) || !solvable {
+                    continue;
+                }
+                let now = Instant::now();
+                let translated = translation.translate(model, context, initial_state);
+                let mut translation_stage = PipelineStage::new(StageKind::Translation, meta.name).with_duration(now.elapsed());
+                match translated {
+                    Err(e) => translation_stage = translation_stage.with_outcome(e),
+                    Ok(()) => {
+                        let (translated, translated_ctx, _) = translation.get_translated();
+                        let mut model_stage = PipelineStage::new(StageKind::Model, meta.output.clone());
+                        result = Self::try_solutions(&mut self.solutions, translated, &meta.output, translated_ctx, query, &mut model_stage);
+                        translation_stage.push(model_stage);
+                    }
+                }
+                stage.push(translation_stage);
+                if result.is_conclusive() {
+                    break;
+                }
+            }
+        }
+        provenance.push(stage);
+        (result, provenance)
+    }
+
+    // Same as solve, the result being summarized in a report along with the solver chain used
+    pub fn solve_with_report(&mut self, model : &dyn Any, model_name : &Label, context : &ModelContext, initial_state : &ModelState, query : &Query) -> VerificationReport {
+        let now = Instant::now();
+        let (result, provenance) = self.solve(model, model_name, context, initial_state, query);
+        VerificationReport::new(query, &result)
+            .with_provenance(&provenance)
+            .with_wall_time(now.elapsed())
+    }
+
+    fn try_solutions(solutions : &mut [Box<dyn Solution>], model : &dyn Any, model_name : &Label, context : &ModelContext, query : &Query, stage : &mut PipelineStage) -> SolverResult {
+        for solution in solutions.iter_mut() {
+            let meta = solution.get_meta();
+            if meta.model_name != *model_name || !solution.is_compatible(model, context, query) {
+                continue;
+            }
+            let now = Instant::now();
+            let result = solution.solve(model, context, query);
+            stage.push(PipelineStage::new(StageKind::Solution, meta.name).with_duration(now.elapsed()).with_outcome(&result));
+            if result.is_conclusive() {
+                return result;
+            }
+        }
+        SolverResult::unknown(format!("No solution found for {}", model_name))
     }
 
     pub fn compile(&mut self) {
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{computation::virtual_memory::{EvaluationType, VirtualMemory}, verification::Verifiable};
 
-use super::{model_clock::ModelClock, model_storage::ModelStorage, model_var::ModelVar, time::ClockValue};
+use super::{model_clock::ModelClock, model_storage::ModelStorage, model_var::ModelVar, tapn::tapn_token::{TAPNTokenList, TokenPlace}, time::ClockValue};
 
 #[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ModelState {
@@ -145,6 +145,10 @@ impl Verifiable for ModelState {
         self.get_clock_value(clock).float()
     }
 
+    fn evaluate_tokens(&self, place : &TokenPlace) -> TAPNTokenList {
+        place.tokens(&self.storages)
+    }
+
     fn is_deadlocked(&self) -> bool {
         self.deadlocked
     }
@@ -49,6 +49,9 @@ pub struct ModelVar {
     var_type : VarType,
     #[serde(skip)]
     address : Option<usize>,
+    // Number of elements for array variables, stored contiguously from the address
+    #[serde(skip)]
+    length : Option<usize>,
 }
 
 impl ModelVar {
@@ -57,26 +60,27 @@ impl ModelVar {
         ModelVar { 
             name: Label::new(), 
             var_type: VarType::UnknownType, 
-            address: None 
+            address: None,
+            length: None
         }
     }
 
     pub fn name(name : Label) -> ModelVar {
-        ModelVar { name, address : None, var_type : VarType::UnknownType }
+        ModelVar { name, address : None, var_type : VarType::UnknownType, length : None }
     }
 
     pub fn address(index : usize, var_type : VarType) -> ModelVar {
         if var_type.is_unknown() {
             panic!("Impossible to define a variable address before setting its type !")
         }
-        ModelVar { name : Label::new(), address : Some(index), var_type }
+        ModelVar { name : Label::new(), address : Some(index), var_type, length : None }
     }
 
     pub fn make_defined(name : Label, address : usize, var_type : VarType) -> ModelVar {
         if var_type.is_unknown() {
             panic!("Impossible to define a variable address before setting its type !")
         }
-        ModelVar { name, address : Some(address), var_type }
+        ModelVar { name, address : Some(address), var_type, length : None }
     }
 
     pub fn get_name(&self) -> Label {
@@ -84,7 +88,36 @@ impl ModelVar {
     }
 
     pub fn size(&self) -> usize {
-        self.var_type.size()
+        self.var_type.size() * self.length()
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.length.is_some()
+    }
+
+    // Number of elements, 1 for scalar variables
+    pub fn length(&self) -> usize {
+        self.length.unwrap_or(1)
+    }
+
+    pub fn set_length(&mut self, length : usize) {
+        if self.is_mapped() {
+            panic!("Impossible to resize an already mapped var !")
+        }
+        self.length = Some(length)
+    }
+
+    // Scalar variable stored at the given index of an array, None if out of bounds
+    pub fn element(&self, index : usize) -> Option<ModelVar> {
+        if !self.is_mapped() || index >= self.length() {
+            return None;
+        }
+        let name = Label::from(format!("{}[{}]", self.name, index));
+        Some(ModelVar::make_defined(name, self.get_address() + index * self.var_type.size(), self.var_type))
+    }
+
+    pub fn elements(&self) -> Vec<ModelVar> {
+        (0..self.length()).filter_map(|i| self.element(i)).collect()
     }
 
     pub fn is_mapped(&self) -> bool {
@@ -118,6 +151,14 @@ impl ModelVar {
         }
     }
 
+    pub fn apply_to_array(&self, ctx : &ModelContext) -> MappingResult<ModelVar> {
+        let var = self.apply_to(ctx)?;
+        if !var.is_array() {
+            return Err(MappingError(Label::from(format!("{} (not an array)", self.name))));
+        }
+        Ok(var)
+    }
+
     pub fn evaluate(&self, state : &impl Verifiable) -> i32 {
         if self.address.is_none() {
             panic!("Can't evaluate unmapped var !");
@@ -135,6 +176,7 @@ impl ModelVar {
     pub fn unbind(&mut self) {
         self.address = None;
         self.var_type = VarType::UnknownType;
+        self.length = None;
     }
 
 }
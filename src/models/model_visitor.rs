@@ -0,0 +1,246 @@
+use std::{any::Any, collections::HashMap, fmt::Display};
+
+use super::{class_graph::ClassGraph, digraph::Digraph, markov::{markov_automaton::MarkovAutomaton, markov_chain::MarkovChain}, model_network::ModelNetwork, petri::PetriNet, tapn::TAPN, Label, Node};
+
+/// Walks the structure of a model : places and transitions of nets, locations of automata and graphs, and the edges
+/// between them, given by the labels of their ends. Every method does nothing by default, tools implement the ones they need.
+pub trait ModelVisitor {
+
+    fn visit_place(&mut self, place : &dyn Node) {
+        let _ = place;
+    }
+
+    fn visit_transition(&mut self, transition : &dyn Node) {
+        let _ = transition;
+    }
+
+    fn visit_location(&mut self, location : &dyn Node) {
+        let _ = location;
+    }
+
+    fn visit_edge(&mut self, from : &Label, to : &Label, label : &str) {
+        let _ = (from, to, label);
+    }
+
+}
+
+/// Models whose structure can be walked by a ModelVisitor. Nodes are visited before edges.
+pub trait VisitableModel {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor);
+
+}
+
+// Visits a type-erased model, false if its type isn't visitable
+pub fn accept_any(model : &dyn Any, visitor : &mut dyn ModelVisitor) -> bool {
+    if let Some(net) = model.downcast_ref::<PetriNet>() {
+        net.accept(visitor);
+    } else if let Some(net) = model.downcast_ref::<TAPN>() {
+        net.accept(visitor);
+    } else if let Some(chain) = model.downcast_ref::<MarkovChain>() {
+        chain.accept(visitor);
+    } else if let Some(automaton) = model.downcast_ref::<MarkovAutomaton>() {
+        automaton.accept(visitor);
+    } else if let Some(cg) = model.downcast_ref::<ClassGraph>() {
+        cg.accept(visitor);
+    } else if let Some(network) = model.downcast_ref::<ModelNetwork>() {
+        network.accept(visitor);
+    } else {
+        return false;
+    }
+    true
+}
+
+impl VisitableModel for PetriNet {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor) {
+        for place in self.places.iter() {
+            visitor.visit_place(place.as_ref());
+        }
+        for transition in self.transitions.iter() {
+            visitor.visit_transition(transition.as_ref());
+        }
+        for transition in self.transitions.iter() {
+            let label = transition.get_label();
+            for place in transition.from.iter() {
+                visitor.visit_edge(place, &label, "");
+            }
+            for place in transition.to.iter() {
+                visitor.visit_edge(&label, place, "");
+            }
+            for (place, _) in transition.inhibitors.iter() {
+                visitor.visit_edge(place, &label, "inhibitor");
+            }
+            for (place, _) in transition.reads.iter() {
+                visitor.visit_edge(place, &label, "read");
+            }
+            for place in transition.flushes.iter() {
+                visitor.visit_edge(&label, place, "reset");
+            }
+            for (place, weight) in transition.input_weights.iter() {
+                visitor.visit_edge(place, &label, &weight.to_string());
+            }
+            for (place, weight) in transition.output_weights.iter() {
+                visitor.visit_edge(&label, place, &weight.to_string());
+            }
+        }
+    }
+
+}
+
+impl VisitableModel for TAPN {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor) {
+        for place in self.places.iter() {
+            visitor.visit_place(place.as_ref());
+        }
+        for transition in self.transitions.iter() {
+            visitor.visit_transition(transition.as_ref());
+        }
+        for transition in self.transitions.iter() {
+            let label = transition.get_label();
+            for place in transition.from.iter() {
+                visitor.visit_edge(place, &label, "");
+            }
+            for place in transition.to.iter() {
+                visitor.visit_edge(&label, place, "");
+            }
+        }
+    }
+
+}
+
+impl VisitableModel for MarkovChain {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor) {
+        for node in self.nodes.iter() {
+            visitor.visit_location(node);
+        }
+        for node in self.nodes.iter() {
+            let choice = node.is_choice();
+            for (action, outputs) in node.outputs.iter() {
+                for (target, proba) in outputs.iter() {
+                    let text = if choice { format!("{} : {}", action, proba) } else { proba.to_string() };
+                    visitor.visit_edge(&node.get_label(), target, &text);
+                }
+            }
+        }
+    }
+
+}
+
+impl VisitableModel for MarkovAutomaton {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor) {
+        for state in self.states.iter() {
+            visitor.visit_location(state);
+        }
+        for state in self.states.iter() {
+            for (action, outputs) in state.actions.iter() {
+                for (target, proba) in outputs.iter() {
+                    visitor.visit_edge(&state.get_label(), target, &format!("{} : {}", action, proba));
+                }
+            }
+            for (target, rate) in state.rates.iter() {
+                visitor.visit_edge(&state.get_label(), target, &format!("rate {}", rate));
+            }
+        }
+    }
+
+}
+
+impl VisitableModel for ClassGraph {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor) {
+        let actions : HashMap<_,_> = self.transitions.iter().map(|t| (t.get_action(), t.get_label())).collect();
+        for class in self.classes.iter() {
+            visitor.visit_location(class.as_ref());
+        }
+        for class in self.classes.iter() {
+            for (pred, action) in class.predecessors.read().unwrap().iter() {
+                let Some(pred) = pred.upgrade() else {
+                    continue;
+                };
+                let text = match actions.get(action) {
+                    Some(l) => l.to_string(),
+                    None => action.to_string()
+                };
+                visitor.visit_edge(&pred.get_label(), &class.get_label(), &text);
+            }
+        }
+    }
+
+}
+
+impl<T : ToString + 'static, U : Display> VisitableModel for Digraph<T, U> {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor) {
+        for node in self.nodes.iter() {
+            visitor.visit_location(node.as_ref());
+        }
+        for edge in self.edges.iter() {
+            if !edge.has_source() || !edge.has_target() {
+                continue;
+            }
+            visitor.visit_edge(&edge.get_node_from().get_label(), &edge.get_node_to().get_label(), &edge.weight.to_string());
+        }
+    }
+
+}
+
+// Components are visited one after the other, unknown ones being skipped
+impl VisitableModel for ModelNetwork {
+
+    fn accept(&self, visitor : &mut dyn ModelVisitor) {
+        for model in self.models.iter() {
+            let model : &dyn Any = model.as_ref();
+            accept_any(model, visitor);
+        }
+    }
+
+}
+
+/// Size of a model structure, counted by visiting it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ModelStatistics {
+    pub places : usize,
+    pub transitions : usize,
+    pub locations : usize,
+    pub edges : usize,
+}
+
+impl ModelStatistics {
+
+    pub fn of(model : &impl VisitableModel) -> Self {
+        let mut statistics = ModelStatistics::default();
+        model.accept(&mut statistics);
+        statistics
+    }
+
+}
+
+impl ModelVisitor for ModelStatistics {
+
+    fn visit_place(&mut self, _ : &dyn Node) {
+        self.places += 1;
+    }
+
+    fn visit_transition(&mut self, _ : &dyn Node) {
+        self.transitions += 1;
+    }
+
+    fn visit_location(&mut self, _ : &dyn Node) {
+        self.locations += 1;
+    }
+
+    fn visit_edge(&mut self, _ : &Label, _ : &Label, _ : &str) {
+        self.edges += 1;
+    }
+
+}
+
+impl Display for ModelStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} places, {} transitions, {} locations, {} edges", self.places, self.transitions, self.locations, self.edges)
+    }
+}
@@ -1,10 +1,90 @@
-use std::sync::{Arc, RwLock};
+use std::{fmt, hash::{DefaultHasher, Hash, Hasher}, sync::{Arc, RwLock}};
+
+use serde::{Deserialize, Serialize};
 
 use super::{Edge, Label};
 
 /// Generic trait that should be implemented by all types of nodes (useless at the moment)
 pub trait Node {
     fn get_label(&self) -> Label;
+
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        None
+    }
+}
+
+/// Documentation attached to a node, kept when the model is saved and emitted by exporters.
+/// Position is a layout hint for graphical editors, in their own coordinates.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct NodeMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position : Option<(f64, f64)>,
+}
+
+impl NodeMetadata {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_description(mut self, description : impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_unit(mut self, unit : impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    pub fn with_position(mut self, x : f64, y : f64) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.unit.is_none() && self.position.is_none()
+    }
+
+}
+
+// Hash of a serializable model structure, node metadata excluded : documenting or moving a node doesn't change the model
+pub fn hash_without_metadata(structure : &impl Serialize) -> Option<u64> {
+    fn strip(value : &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                fields.remove("metadata");
+                fields.values_mut().for_each(strip);
+            },
+            serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => ()
+        }
+    }
+    let mut value = serde_json::to_value(structure).ok()?;
+    strip(&mut value);
+    let mut s = DefaultHasher::new();
+    value.to_string().hash(&mut s);
+    Some(s.finish())
+}
+
+impl fmt::Display for NodeMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(description) = &self.description {
+            parts.push(description.clone());
+        }
+        if let Some(unit) = &self.unit {
+            parts.push(format!("[{}]", unit));
+        }
+        if let Some((x, y)) = &self.position {
+            parts.push(format!("@ ({}, {})", x, y));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
 }
 
 impl Node for usize {
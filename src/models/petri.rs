@@ -1,19 +1,36 @@
 use std::{collections::{HashMap, HashSet}, fmt, sync::Arc};
 
-use super::{action::Action, lbl, model_characteristics::*, model_context::ModelContext, time::ClockValue, CompilationResult, Edge, Label, Model, ModelMaker, ModelMeta, ModelState, Node};
+use super::{action::Action, expressions::Condition, lbl, model_characteristics::*, model_clock::ModelClock, model_context::ModelContext, model_var::ModelVar, time::ClockValue, CompilationError, CompilationResult, Edge, hash_without_metadata, Label, Model, ModelMaker, ModelMeta, ModelState, Node, PlaceId, StateBatch, TransitionId};
 
+mod compiled_petri;
+mod complementary;
+mod hierarchy;
+mod one_safe;
 mod petri_place;
 mod petri_transition;
+mod stochastic_firing;
+mod structural;
 
 use num_traits::Zero;
+use rand::Rng;
+use crate::computation::{intervals::Convex, random::{choose_uniform, SimulationRng}};
+use super::time::{TimeBound, TimeInterval};
+pub use compiled_petri::CompiledPetriNet;
+pub use hierarchy::SubstitutionTransition;
+pub use one_safe::{OneSafeEncoding, OneSafeStateSpace};
 pub use petri_place::PetriPlace;
 pub use petri_transition::PetriTransition;
+pub use stochastic_firing::{RacePolicy, StochasticFiring};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PetriStructure {
     pub places : Vec<PetriPlace>,
-    pub transitions : Vec<PetriTransition>
+    pub transitions : Vec<PetriTransition>,
+    #[serde(default)]
+    pub race_policy : RacePolicy,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub substitutions : Vec<SubstitutionTransition>,
 }
 
 #[derive(Debug, Clone)]
@@ -21,9 +38,15 @@ pub struct PetriNet {
     pub id : usize,
     pub places: Vec<Arc<PetriPlace>>,
     pub transitions: Vec<Arc<PetriTransition>>,
-    pub places_dic: HashMap<Label, usize>,
-    pub transitions_dic: HashMap<Label, usize>,
-    pub actions_dic : HashMap<Action, usize>,
+    pub places_dic: HashMap<Label, PlaceId>,
+    pub transitions_dic: HashMap<Label, TransitionId>,
+    pub actions_dic : HashMap<Action, TransitionId>,
+    pub declared_clocks : Vec<ModelClock>,
+    pub race_policy : RacePolicy,
+    // Subnets flattened when compiling, compiled nets being flat
+    pub substitutions : Vec<SubstitutionTransition>,
+    // Sampled firing delays, for nets having stochastic transitions
+    pub storage_index : Option<usize>,
 }
 
 impl PetriNet {
@@ -43,17 +66,42 @@ impl PetriNet {
             transitions : transitions_ptr, 
             places_dic : HashMap::new(), 
             transitions_dic : HashMap::new(),
-            actions_dic : HashMap::new()
+            actions_dic : HashMap::new(),
+            declared_clocks : Vec::new(),
+            race_policy : RacePolicy::default(),
+            substitutions : Vec::new(),
+            storage_index : None,
         };
         petri
     }
 
+    // Compiling gives the net its id, uncompiled nets having none
+    pub fn is_compiled(&self) -> bool {
+        self.id != usize::MAX
+    }
+
     pub fn get_place(&self, place : &Label) -> Arc<PetriPlace> {
-        Arc::clone(&self.places[self.places_dic[place]])
+        Arc::clone(self.place(self.places_dic[place]))
     }
 
     pub fn get_transition(&self, transition : &Label) -> Arc<PetriTransition> {
-        Arc::clone(&self.transitions[self.transitions_dic[transition]])
+        Arc::clone(self.transition(self.transitions_dic[transition]))
+    }
+
+    pub fn place(&self, id : PlaceId) -> &Arc<PetriPlace> {
+        &self.places[id.index()]
+    }
+
+    pub fn transition(&self, id : TransitionId) -> &Arc<PetriTransition> {
+        &self.transitions[id.index()]
+    }
+
+    pub fn place_id(&self, place : &Label) -> Option<PlaceId> {
+        self.places_dic.get(place).copied()
+    }
+
+    pub fn transition_id(&self, transition : &Label) -> Option<TransitionId> {
+        self.transitions_dic.get(transition).copied()
     }
 
     pub fn enabled_transitions(&self, marking : &ModelState) -> Vec<Arc<PetriTransition>> {
@@ -63,11 +111,17 @@ impl PetriNet {
         }).collect()
     }
 
-    pub fn compute_new_actions(&self, new_state : &mut ModelState, changed_places : &HashSet<usize>) -> (HashSet<usize>, HashSet<usize>) {
-        let mut pers = new_state.enabled_clocks();
-        let mut newen : HashSet<usize> = HashSet::new();
+    // Transition i has clock i, so enabled clocks of the state are transitions.
+    // Transitions only reading a changed place keep their clock if they stay enabled, instead of being enabled again :
+    // encoding read arcs as self-loops would reset them.
+    pub fn compute_new_actions(&self, new_state : &mut ModelState, changed_places : &HashSet<PlaceId>) -> (HashSet<TransitionId>, HashSet<TransitionId>) {
+        let mut pers : HashSet<TransitionId> = new_state.enabled_clocks().into_iter()
+            .filter(|i| *i < self.transitions.len())
+            .map(TransitionId::from).collect();
+        let mut newen : HashSet<TransitionId> = HashSet::new();
+        let mut readers : Vec<Arc<PetriTransition>> = Vec::new();
         for place_index in changed_places {
-            let place : &Arc<PetriPlace> = &self.places[*place_index];
+            let place = self.place(*place_index);
             for transition in place.get_downstream_transitions().iter() {
                 let transi_index = transition.index;
                 let clock = transition.get_clock();
@@ -78,13 +132,41 @@ impl PetriNet {
                     newen.insert(transi_index);
                 }
             }
+            readers.extend(place.get_reading_transitions());
+        }
+        for transition in readers {
+            let transi_index = transition.index;
+            if newen.contains(&transi_index) {
+                continue;
+            }
+            let clock = transition.get_clock();
+            let enabled = transition.is_enabled(new_state);
+            if !enabled {
+                new_state.disable_clock(clock);
+                pers.remove(&transi_index);
+            } else if !pers.contains(&transi_index) {
+                new_state.enable_clock(clock, ClockValue::zero());
+                newen.insert(transi_index);
+            }
         }
         (newen, pers)
     }
 
-    pub fn fire(&self, mut state : ModelState, transi : usize) -> (ModelState, HashSet<usize>, HashSet<usize>) {
-        let transi = &self.transitions[transi];
-        let mut changed_places : HashSet<usize> = HashSet::new();
+    // Only meaningful on a compiled net : exposed through CompiledPetriNet, and to the explorers of compiled nets
+    pub(in crate::models) fn fire(&self, mut state : ModelState, transi : TransitionId) -> (ModelState, HashSet<TransitionId>, HashSet<TransitionId>) {
+        let transi = self.transition(transi);
+        let mut changed_places : HashSet<PlaceId> = HashSet::new();
+        // Marking-dependent weights are evaluated before any token moves
+        let weighted_inputs : Vec<(Arc<PetriPlace>, i32)> = transi.get_weighted_inputs().iter().map(|edge| {
+            (edge.get_node_from(), PetriTransition::arc_weight(&edge.weight, &state))
+        }).collect();
+        let weighted_outputs : Vec<(Arc<PetriPlace>, i32)> = transi.get_weighted_outputs().iter().map(|edge| {
+            (edge.get_node_to(), PetriTransition::arc_weight(&edge.weight, &state))
+        }).collect();
+        for (place, weight) in weighted_inputs {
+            state.unmark(place.get_var(), weight);
+            changed_places.insert(place.index);
+        }
         for edge in transi.input_edges.read().unwrap().iter() {
             let place_ptr = edge.get_node_from();
             let place_var = place_ptr.get_var();
@@ -92,6 +174,11 @@ impl PetriNet {
             state.unmark(place_var, edge.weight);
             changed_places.insert(place_index);
         }
+        for edge in transi.flush_edges.read().unwrap().iter() {
+            let place_ptr = edge.get_node_to();
+            state.set_marking(place_ptr.get_var(), 0);
+            changed_places.insert(place_ptr.index);
+        }
         for edge in transi.output_edges.read().unwrap().iter() {
             let place_ptr = edge.get_node_to();
             let place_var = place_ptr.get_var();
@@ -99,34 +186,80 @@ impl PetriNet {
             state.mark(place_var, edge.weight);
             changed_places.insert(place_index);
         }
-        let (newen, pers) = self.compute_new_actions(&mut state, &changed_places);
+        for (place, weight) in weighted_outputs {
+            state.mark(place.get_var(), weight);
+            changed_places.insert(place.index);
+        }
+        let (mut newen, mut pers) = self.compute_new_actions(&mut state, &changed_places);
+        // The fired transition is enabled again, even when it only reads the places it depends on
+        if pers.remove(&transi.index) {
+            state.enable_clock(transi.get_clock(), ClockValue::zero());
+            newen.insert(transi.index);
+        }
+        for clock in transi.compiled_resets.iter() {
+            state.set_clock(clock, ClockValue::zero());
+        }
         (state, newen, pers)
     }
 
     fn create_transition_edges(&self, transition : &Arc<PetriTransition>) {
         let from_labels = transition.from.clone();
         let to_labels = transition.to.clone();
-        let guard_vars = transition.compiled_guard.get_objects().vars;
+        let dependency_vars = transition.dependency_vars();
         for place_label in from_labels.iter() {
-            let place_index = self.places_dic[place_label];
-            let place = &self.places[place_index];
+            let place = self.place(self.places_dic[place_label]);
             let in_edge = Edge::data_edge(place, transition, 1);
             transition.add_input_edge(in_edge);
             place.add_downstream_transition(transition);
         }
+        // Inhibiting places are checked again like read places when they change : the transition keeps its clock if still enabled
+        for (place_label, weight) in transition.inhibitors.iter() {
+            let place = self.place(self.places_dic[place_label]);
+            let inhibitor_edge = Edge::data_edge(place, transition, *weight);
+            transition.add_inhibitor_edge(inhibitor_edge);
+            place.add_reading_transition(transition);
+        }
+        for (place_label, weight) in transition.reads.iter() {
+            let place = self.place(self.places_dic[place_label]);
+            let read_edge = Edge::data_edge(place, transition, *weight);
+            transition.add_read_edge(read_edge);
+            place.add_reading_transition(transition);
+        }
+        for (place_label, weight) in transition.compiled_input_weights.iter() {
+            let place = self.place(self.places_dic[place_label]);
+            transition.add_weighted_input_edge(Edge::data_edge(place, transition, weight.clone()));
+            place.add_downstream_transition(transition);
+        }
+        // Places read by guards and marking-dependent weights
         for place in self.places.iter() {
             let place_var = place.get_var();
-            if !guard_vars.contains(place_var) {
+            if !dependency_vars.contains(place_var) {
                 continue
             }
             place.add_downstream_transition(transition);
         }
         for place_label in to_labels.iter() {
-            let place_index = self.places_dic[place_label];
-            let place = &self.places[place_index];
+            let place = self.place(self.places_dic[place_label]);
             let out_edge = Edge::data_edge(transition, place, 1);
             transition.add_output_edge(out_edge);
             place.add_upstream_transition(transition);
+            // Capacities depend on the tokens of output places, checked again like read places when they change
+            if place.capacity.is_some() {
+                place.add_reading_transition(transition);
+            }
+        }
+        for (place_label, weight) in transition.compiled_output_weights.iter() {
+            let place = self.place(self.places_dic[place_label]);
+            transition.add_weighted_output_edge(Edge::data_edge(transition, place, weight.clone()));
+            place.add_upstream_transition(transition);
+            if place.capacity.is_some() {
+                place.add_reading_transition(transition);
+            }
+        }
+        for place_label in transition.flushes.iter() {
+            let place = self.place(self.places_dic[place_label]);
+            let flush_edge = Edge::data_edge(transition, place, 0);
+            transition.add_flush_edge(flush_edge);
         }
     }
 
@@ -141,19 +274,60 @@ impl PetriNet {
             let transi = PetriTransition::clone(transi_ptr);
             transitions.push(transi);
         }
-        PetriStructure { places, transitions }
+        PetriStructure { places, transitions, race_policy : self.race_policy, substitutions : self.substitutions.clone() }
+    }
+
+    pub fn get_transition_action(&self, transi_index : TransitionId) -> Action {
+        self.transition(transi_index).get_action()
+    }
+
+    // Labels of the user-declared clocks, in order of first appearance
+    pub fn declared_clock_names(&self) -> Vec<Label> {
+        let mut names : Vec<Label> = Vec::new();
+        for name in self.transitions.iter().flat_map(|t| t.declared_clocks()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    pub fn declared_clock_position(&self, clock : &ModelClock) -> Option<usize> {
+        self.declared_clocks.iter().position(|c| c.get_index() == clock.get_index())
     }
 
-    pub fn get_transition_action(&self, transi_index : usize) -> Action {
-        self.transitions[transi_index].get_action()
+    // Greatest constant a declared clock is compared to, beyond which its value does not matter anymore
+    pub fn max_clock_constant(&self, clock : &ModelClock) -> i32 {
+        self.transitions.iter().flat_map(|t| t.compiled_clock_guards.iter()).filter_map(|(c, interval)| {
+            if c.get_index() != clock.get_index() {
+                return None;
+            }
+            [interval.0, interval.1].iter().filter_map(|b| match b {
+                TimeBound::Large(x) | TimeBound::Strict(x) => Some(*x),
+                _ => None
+            }).max()
+        }).max().unwrap_or(0)
+    }
+
+    // Time-abstract net : same structure, every firing interval is relaxed to the full interval. Returned uncompiled.
+    pub fn untimed(&self) -> PetriNet {
+        let mut structure = self.get_structure();
+        for transition in structure.transitions.iter_mut() {
+            transition.interval = TimeInterval::full();
+        }
+        PetriNet::from(structure)
     }
 
 }
 
+// Uncompiled nets can't be fired, successors only being computed on compiled nets (see CompiledPetriNet)
 impl Model for PetriNet {
 
     fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
-        let transi = self.actions_dic[&action];
+        if !self.is_compiled() {
+            return None;
+        }
+        let transi = *self.actions_dic.get(&action)?;
         let (mut new_state, _, _) = self.fire(state, transi);
         let actions: HashSet<Action> = self.available_actions(&new_state);
         if actions.is_empty() && self.available_delay(&new_state).is_zero() {
@@ -162,20 +336,20 @@ impl Model for PetriNet {
         Some((new_state, actions))
     }
 
+    // Fireable transitions of the highest priority
     fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
-        let mut res = HashSet::new();
-        for transition in self.transitions.iter() {
-            if transition.is_fireable(state) {
-                res.insert(transition.get_action());
-            }
-        }
-        res
+        let fireable : Vec<&Arc<PetriTransition>> = self.transitions.iter().filter(|t| t.is_fireable(state)).collect();
+        let Some(priority) = fireable.iter().map(|t| t.priority).max() else {
+            return HashSet::new();
+        };
+        fireable.into_iter().filter(|t| t.priority == priority).map(|t| t.get_action()).collect()
     }
 
     fn available_delay(&self, state : &ModelState) -> ClockValue {
-        let m = state.clocks.iter().enumerate().filter_map(|(i,c)| {
+        let m = self.transitions.iter().filter_map(|t| {
+            let c = state.get_clock_value(t.get_clock());
             if c.is_enabled() {
-                Some((ClockValue::from(self.transitions[i].interval.1) - *c).float())
+                Some((ClockValue::from(t.interval.1) - c).float())
             } else {
                 None
             }
@@ -191,15 +365,148 @@ impl Model for PetriNet {
         for transition in self.enabled_transitions(&state) {
             state.enable_clock(transition.get_clock(), ClockValue::zero());
         }
+        for clock in self.declared_clocks.iter() {
+            state.enable_clock(clock, ClockValue::zero());
+        }
         state
     }
 
     fn delay(&self, mut state : ModelState, dt : ClockValue) -> Option<ModelState> {
-        let clocks = self.transitions.iter().map(|t| t.get_clock());
+        if self.storage_index.is_some() {
+            let mut delays = self.firing_delays(&state);
+            for (transition, delay) in self.transitions.iter().zip(delays.iter_mut()) {
+                if let (true, Some(d)) = (state.is_enabled(transition.get_clock()), delay.as_mut()) {
+                    *d -= dt.float();
+                }
+            }
+            self.set_firing_delays(&mut state, &delays);
+        }
+        let clocks = self.transitions.iter().map(|t| t.get_clock()).chain(self.declared_clocks.iter());
         state.step_clocks(clocks, dt);
         Some(state)
     }
 
+    // Transitions guarded by the written variables, or weighted by them, are enabled or disabled accordingly, persistent
+    // ones keep their clock
+    fn vars_written(&self, mut state : ModelState, vars : &[ModelVar]) -> ModelState {
+        for transition in self.transitions.iter() {
+            let dependency_vars = transition.dependency_vars();
+            if !vars.iter().any(|v| dependency_vars.contains(v)) {
+                continue;
+            }
+            let clock = transition.get_clock();
+            match (state.is_enabled(clock), transition.is_enabled(&state)) {
+                (false, true) => state.enable_clock(clock, ClockValue::zero()),
+                (true, false) => state.disable_clock(clock),
+                _ => ()
+            }
+        }
+        state
+    }
+
+    fn init_initial_storage(&self, mut state : ModelState) -> ModelState {
+        self.set_firing_delays(&mut state, &vec![None ; self.transitions.len()]);
+        state
+    }
+
+    // Stochastic nets follow the GSPN semantics, others fire uniformly within their intervals
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        if !self.is_compiled() {
+            return (None, ClockValue::zero(), None);
+        }
+        if self.has_stochastic_firing() {
+            return self.stochastic_next(state, rng);
+        }
+        let max_delay = self.available_delay(&state);
+        let mut delayed_state = state;
+        let mut delay = ClockValue::zero();
+        if !max_delay.is_zero() {
+            delay = rng.gen_range(ClockValue::zero()..max_delay);
+            delayed_state = self.delay(delayed_state, delay).unwrap();
+        }
+        let mut actions : Vec<Action> = self.available_actions(&delayed_state).into_iter().collect();
+        actions.sort_by_key(|a| a.get_id());
+        let Some(action) = choose_uniform(&actions, rng).cloned() else {
+            return (Some(delayed_state), delay, None);
+        };
+        match self.next(delayed_state, action.clone()) {
+            Some((next, _)) => (Some(next), delay, Some(action)),
+            None => (None, delay, Some(action))
+        }
+    }
+
+    // Delays and fireability are computed clock by clock over the whole batch, only firing gathers the states
+    fn batch_random_next(&self, batch : &mut StateBatch, active : &[bool], rng : &mut SimulationRng) -> Vec<Option<(ClockValue, Option<Action>)>> {
+        let len = batch.len();
+        if !self.is_compiled() {
+            return vec![None ; len];
+        }
+        if self.has_stochastic_firing() {
+            return (0..len).map(|i| {
+                if !active[i] {
+                    return None;
+                }
+                let (next, delay, action) = self.random_next(batch.get(i), rng);
+                batch.set(i, &next?);
+                Some((delay, action))
+            }).collect();
+        }
+        let mut max_delays : Vec<Option<f64>> = vec![None ; len];
+        for transition in self.transitions.iter() {
+            let upper = ClockValue::from(transition.interval.1);
+            for (max_delay, c) in max_delays.iter_mut().zip(batch.clock_column(transition.get_clock())) {
+                if c.is_enabled() {
+                    let d = (upper - *c).float();
+                    *max_delay = Some(max_delay.map_or(d, |m| m.min(d)));
+                }
+            }
+        }
+        let delays : Vec<ClockValue> = max_delays.into_iter().zip(active).map(|(max_delay, active)| {
+            match max_delay.map(ClockValue::from) {
+                Some(max_delay) if *active && !max_delay.is_zero() => rng.gen_range(ClockValue::zero()..max_delay),
+                _ => ClockValue::zero()
+            }
+        }).collect();
+        let clocks = self.transitions.iter().map(|t| t.get_clock()).chain(self.declared_clocks.iter());
+        for clock in clocks {
+            for (c, delay) in batch.clock_column_mut(clock).iter_mut().zip(delays.iter()) {
+                *c += *delay;
+            }
+        }
+        let mut transitions : Vec<&Arc<PetriTransition>> = self.transitions.iter().collect();
+        transitions.sort_by_key(|t| t.get_action().get_id());
+        let mut fireable : Vec<Vec<Action>> = vec![Vec::new() ; len];
+        let mut priorities : Vec<i32> = vec![i32::MIN ; len];
+        for transition in transitions {
+            let column = batch.clock_column(transition.get_clock());
+            let guards : Vec<(&[ClockValue], &TimeInterval)> = transition.compiled_clock_guards.iter()
+                .map(|(clock, interval)| (batch.clock_column(clock), interval)).collect();
+            for (i, c) in column.iter().enumerate() {
+                if active[i] && c.is_enabled() && transition.interval.contains(c)
+                    && guards.iter().all(|(guard, interval)| interval.contains(&guard[i]))
+                    && transition.priority >= priorities[i]
+                {
+                    if transition.priority > priorities[i] {
+                        priorities[i] = transition.priority;
+                        fireable[i].clear();
+                    }
+                    fireable[i].push(transition.get_action());
+                }
+            }
+        }
+        (0..len).map(|i| {
+            if !active[i] {
+                return None;
+            }
+            let action = choose_uniform(&fireable[i], rng).cloned();
+            if let Some(action) = &action {
+                let (next, _) = self.next(batch.get(i), action.clone())?;
+                batch.set(i, &next);
+            }
+            Some((delays[i], action))
+        }).collect()
+    }
+
     fn get_meta() -> ModelMeta {
         ModelMeta {
             name : lbl("TPN"),
@@ -213,19 +520,23 @@ impl Model for PetriNet {
     }
 
     fn is_stochastic(&self) -> bool {
-        false
+        self.has_stochastic_firing()
     }
 
     fn compile(&mut self, context : &mut ModelContext) -> CompilationResult<()> {
+        if !self.substitutions.is_empty() {
+            *self = PetriNet::from(self.get_structure().flatten().ok_or(CompilationError)?);
+        }
         self.id = context.new_model();
+        self.storage_index = if self.has_stochastic_firing() { Some(context.add_storage()) } else { None };
         self.places_dic.clear();
         self.transitions_dic.clear();
         self.actions_dic.clear();
         let mut compiled_places = Vec::new();
-        let mut compiled_transitions = Vec::new();
+        let mut compiled_transitions : Vec<PetriTransition> = Vec::new();
         for (i, place) in self.places.iter().enumerate() {
             let mut compiled_place = PetriPlace::clone(place);
-            compiled_place.index = i;
+            compiled_place.index = PlaceId(i);
             self.places_dic.insert(compiled_place.get_label(), compiled_place.index);
             compiled_place.compile(context)?;
             compiled_places.push(Arc::new(compiled_place));
@@ -233,15 +544,26 @@ impl Model for PetriNet {
         self.places = compiled_places;
         for (i, transition) in self.transitions.iter().enumerate() {
             let mut compiled_transition = PetriTransition::clone(transition);
-            compiled_transition.index = i;
+            compiled_transition.index = TransitionId(i);
             self.transitions_dic.insert(compiled_transition.get_label(), compiled_transition.index);
             compiled_transition.compile(context)?;
             self.actions_dic.insert(compiled_transition.get_action(), compiled_transition.index);
-            let compiled_transition = Arc::new(compiled_transition);
-            self.create_transition_edges(&compiled_transition);
             compiled_transitions.push(compiled_transition);
         }
-        self.transitions = compiled_transitions;
+        // Declared clocks come after transition clocks, so that transition i keeps clock i
+        self.declared_clocks.clear();
+        for name in self.declared_clock_names() {
+            if context.has_clock(&name) {
+                return Err(CompilationError);
+            }
+            self.declared_clocks.push(context.add_clock(name));
+        }
+        self.transitions = compiled_transitions.into_iter().map(|mut compiled_transition| {
+            compiled_transition.compile_clocks(context)?;
+            let compiled_transition = Arc::new(compiled_transition);
+            self.create_transition_edges(&compiled_transition);
+            Ok(compiled_transition)
+        }).collect::<CompilationResult<Vec<Arc<PetriTransition>>>>()?;
         Ok(())
     }
 
@@ -249,18 +571,87 @@ impl Model for PetriNet {
         self.id
     }
 
+    fn structure_hash(&self) -> Option<u64> {
+        hash_without_metadata(&self.get_structure())
+    }
+
 }
 
 // Display implementations ---
+// Canonical text form : places, transitions and arcs are sorted by name, so two equivalent nets print the same way
 impl fmt::Display for PetriNet {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "TimePetriNet")
+        let sorted = |labels : &[Label]| {
+            let mut labels : Vec<String> = labels.iter().map(Label::to_string).collect();
+            labels.sort();
+            labels.join(", ")
+        };
+        writeln!(f, "TimePetriNet")?;
+        writeln!(f, "Places :")?;
+        let mut places : Vec<&Arc<PetriPlace>> = self.places.iter().collect();
+        places.sort_by(|p1, p2| p1.name.cmp(&p2.name));
+        for place in places {
+            match place.capacity {
+                Some(capacity) => writeln!(f, "  {} <= {}", place.name, capacity)?,
+                None => writeln!(f, "  {}", place.name)?
+            }
+        }
+        writeln!(f, "Transitions :")?;
+        let mut transitions : Vec<&Arc<PetriTransition>> = self.transitions.iter().collect();
+        transitions.sort_by(|t1, t2| t1.label.cmp(&t2.label));
+        for transition in transitions {
+            write!(f, "  {} {} : [{}] -> [{}]", transition.label, transition.interval, sorted(&transition.from), sorted(&transition.to))?;
+            if !transition.controllable {
+                write!(f, " uncontrollable")?;
+            }
+            if transition.guard != Condition::True {
+                write!(f, " | guard {:?}", transition.guard)?;
+            }
+            let mut guards : Vec<String> = transition.clock_guards.iter().map(|(c, i)| format!("{} in {}", c, i)).collect();
+            guards.sort();
+            if !guards.is_empty() {
+                write!(f, " | clocks {}", guards.join(", "))?;
+            }
+            if !transition.resets.is_empty() {
+                write!(f, " | resets {}", sorted(&transition.resets))?;
+            }
+            if transition.priority != 0 {
+                write!(f, " | priority {}", transition.priority)?;
+            }
+            if let Some(firing) = &transition.firing {
+                write!(f, " | {}", firing)?;
+            }
+            let mut inhibitors : Vec<String> = transition.inhibitors.iter().map(|(p, w)| format!("{} >= {}", p, w)).collect();
+            inhibitors.sort();
+            if !inhibitors.is_empty() {
+                write!(f, " | inhibited {}", inhibitors.join(", "))?;
+            }
+            let mut reads : Vec<String> = transition.reads.iter().map(|(p, w)| format!("{} >= {}", p, w)).collect();
+            reads.sort();
+            if !reads.is_empty() {
+                write!(f, " | reads {}", reads.join(", "))?;
+            }
+            if !transition.flushes.is_empty() {
+                write!(f, " | flushes {}", sorted(&transition.flushes))?;
+            }
+            let mut weights : Vec<String> = transition.input_weights.iter().map(|(p, w)| format!("{} -{}->", p, w))
+                .chain(transition.output_weights.iter().map(|(p, w)| format!("-{}-> {}", w, p))).collect();
+            weights.sort();
+            if !weights.is_empty() {
+                write!(f, " | weights {}", weights.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        let clocks : Vec<Label> = self.declared_clocks.iter().map(|c| c.name.clone()).collect();
+        write!(f, "Clocks : [{}]", sorted(&clocks))
     }
 }
 
 impl From<PetriStructure> for PetriNet {
     fn from(value: PetriStructure) -> Self {
-        PetriNet::new(value.places, value.transitions)
+        let mut net = PetriNet::new(value.places, value.transitions).with_race_policy(value.race_policy);
+        net.substitutions = value.substitutions;
+        net
     }
 }
 
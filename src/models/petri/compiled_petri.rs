@@ -0,0 +1,151 @@
+use std::{collections::HashSet, ops::Deref, sync::Arc};
+
+use crate::computation::random::SimulationRng;
+
+use crate::models::{action::Action, model_context::ModelContext, model_var::ModelVar, time::ClockValue, CompilationError, CompilationResult, Label, Model, ModelMaker, ModelMeta, ModelState, StateBatch, TransitionId};
+
+use super::{PetriNet, PetriStructure};
+
+/// Read-only handle on a compiled Petri net, obtained from `PetriNet::into_compiled` or `PetriStructure::compile`.
+/// The net and its context are shared, so cloning the handle for another thread is cheap, and firing is only exposed here.
+#[derive(Debug, Clone)]
+pub struct CompiledPetriNet {
+    net : Arc<PetriNet>,
+    context : Arc<ModelContext>,
+}
+
+impl CompiledPetriNet {
+
+    pub fn context(&self) -> &ModelContext {
+        &self.context
+    }
+
+    pub fn net(&self) -> &PetriNet {
+        &self.net
+    }
+
+    pub fn fire(&self, state : ModelState, transi : TransitionId) -> (ModelState, HashSet<TransitionId>, HashSet<TransitionId>) {
+        self.net.fire(state, transi)
+    }
+
+    // Mutable definition of the net, to be edited and compiled again
+    pub fn definition(&self) -> PetriStructure {
+        self.net.get_structure()
+    }
+
+}
+
+impl PetriNet {
+
+    pub fn into_compiled(mut self, context : &mut ModelContext) -> CompilationResult<CompiledPetriNet> {
+        self.compile(context)?;
+        Ok(CompiledPetriNet {
+            net : Arc::new(self),
+            context : Arc::new(context.clone())
+        })
+    }
+
+}
+
+impl PetriStructure {
+
+    pub fn compile(self) -> CompilationResult<CompiledPetriNet> {
+        let mut context = ModelContext::new();
+        PetriNet::from(self).into_compiled(&mut context)
+    }
+
+}
+
+impl Deref for CompiledPetriNet {
+    type Target = PetriNet;
+
+    fn deref(&self) -> &PetriNet {
+        &self.net
+    }
+}
+
+impl Model for CompiledPetriNet {
+
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        self.net.next(state, action)
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        self.net.available_actions(state)
+    }
+
+    fn successors(&self, state : &ModelState) -> Vec<ModelState> {
+        self.net.successors(state)
+    }
+
+    fn available_delay(&self, state : &ModelState) -> ClockValue {
+        self.net.available_delay(state)
+    }
+
+    fn init_initial_clocks(&self, state : ModelState) -> ModelState {
+        self.net.init_initial_clocks(state)
+    }
+
+    fn delay(&self, state : ModelState, dt : ClockValue) -> Option<ModelState> {
+        self.net.delay(state, dt)
+    }
+
+    fn init_initial_storage(&self, state : ModelState) -> ModelState {
+        self.net.init_initial_storage(state)
+    }
+
+    fn vars_written(&self, state : ModelState, vars : &[ModelVar]) -> ModelState {
+        self.net.vars_written(state, vars)
+    }
+
+    fn get_meta() -> ModelMeta {
+        PetriNet::get_meta()
+    }
+
+    fn is_timed(&self) -> bool {
+        self.net.is_timed()
+    }
+
+    fn is_stochastic(&self) -> bool {
+        self.net.is_stochastic()
+    }
+
+    fn scheduler(&self) -> Option<Label> {
+        self.net.scheduler()
+    }
+
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        self.net.random_next(state, rng)
+    }
+
+    fn batch_random_next(&self, batch : &mut StateBatch, active : &[bool], rng : &mut SimulationRng) -> Vec<Option<(ClockValue, Option<Action>)>> {
+        self.net.batch_random_next(batch, active, rng)
+    }
+
+    // The handle is immutable : it is already bound to its context, its definition being compiled again instead
+    fn compile(&mut self, _ : &mut ModelContext) -> CompilationResult<()> {
+        Err(CompilationError)
+    }
+
+    fn get_id(&self) -> usize {
+        self.net.get_id()
+    }
+
+    fn structure_hash(&self) -> Option<u64> {
+        self.net.structure_hash()
+    }
+
+}
+
+// A compiled net is its own maker : threads get clones of the shared handle instead of recompiled nets
+impl ModelMaker<CompiledPetriNet> for CompiledPetriNet {
+
+    fn create_maker(model : CompiledPetriNet) -> Self {
+        model
+    }
+
+    fn make(&self) -> (CompiledPetriNet, ModelContext) {
+        (self.clone(), ModelContext::clone(&self.context))
+    }
+
+}
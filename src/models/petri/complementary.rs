@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::computation::virtual_memory::EvaluationType;
+use crate::models::Label;
+
+use super::{PetriNet, PetriPlace, PetriTransition};
+
+// Capacities encoded by complementary places, for solutions assuming plain nets : the complement of a place of capacity k
+// holds k minus its tokens, every transition taking from it what it adds to the place, and the other way around
+impl PetriNet {
+
+    pub fn complementary_place(place : &Label) -> Label {
+        place.clone() + "_complement"
+    }
+
+    // Same net without capacities, returned uncompiled. None if a bounded place is flushed or on a marking-dependent arc,
+    // its complement being unknown, or if it is read or inhibiting, the transformation only handling input and output arcs
+    pub fn with_complementary_places(&self) -> Option<PetriNet> {
+        let mut structure = self.get_structure();
+        let bounded : Vec<Label> = structure.places.iter().filter(|p| p.capacity.is_some()).map(|p| p.name.clone()).collect();
+        for place in bounded.iter() {
+            let weighted = |t : &PetriTransition| t.input_weights.iter().chain(t.output_weights.iter()).any(|(p, _)| p == place);
+            let tested = |t : &PetriTransition| t.reads.iter().chain(t.inhibitors.iter()).any(|(p, _)| p == place);
+            if structure.transitions.iter().any(|t| t.flushes.contains(place) || weighted(t) || tested(t)) {
+                return None;
+            }
+            let complement = Self::complementary_place(place);
+            for transition in structure.transitions.iter_mut() {
+                let consumed = transition.from.iter().filter(|p| *p == place).count();
+                let produced = transition.to.iter().filter(|p| *p == place).count();
+                if produced > consumed {
+                    transition.from.extend(std::iter::repeat_n(complement.clone(), produced - consumed));
+                } else {
+                    transition.to.extend(std::iter::repeat_n(complement.clone(), consumed - produced));
+                }
+            }
+        }
+        for place in structure.places.iter_mut() {
+            place.capacity = None;
+        }
+        structure.places.extend(bounded.iter().map(|p| PetriPlace::new(Self::complementary_place(p))));
+        Some(PetriNet::from(structure))
+    }
+
+    // Initial marking of the transformed net, complements holding the room left in their place.
+    // None if a place holds more tokens than its capacity
+    pub fn complementary_marking(&self, marking : &HashMap<Label, EvaluationType>) -> Option<HashMap<Label, EvaluationType>> {
+        let mut complemented = marking.clone();
+        for place in self.places.iter() {
+            if let Some(capacity) = place.capacity {
+                let tokens = marking.get(&place.name).copied().unwrap_or(0);
+                if tokens > capacity {
+                    return None;
+                }
+                complemented.insert(Self::complementary_place(&place.name), capacity - tokens);
+            }
+        }
+        Some(complemented)
+    }
+
+}
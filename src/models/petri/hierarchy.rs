@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{models::{expressions::Expr, model_var::ModelVar, Label}, QueryTransformer};
+
+use super::{PetriNet, PetriStructure, PetriTransition};
+
+/// Substitution transition of a hierarchical net : stands for a subnet, flattened into the enclosing net when compiling.
+/// Interface places of the subnet are bound to places of the enclosing net, its other nodes being namespaced by the
+/// name of the substitution, as domains of the context are : place p of subnet s becomes s.p.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubstitutionTransition {
+    pub name : Label,
+    pub subnet : PetriStructure,
+    // Interface place of the subnet, and the place of the enclosing net it stands for
+    pub bindings : Vec<(Label, Label)>,
+}
+
+impl SubstitutionTransition {
+
+    pub fn new(name : Label, subnet : PetriStructure) -> Self {
+        SubstitutionTransition { name, subnet, bindings : Vec::new() }
+    }
+
+    pub fn with_binding(mut self, interface : Label, place : Label) -> Self {
+        self.bindings.push((interface, place));
+        self
+    }
+
+    // Adds the nodes of the flattened subnet to the enclosing structure. None if a binding refers to an unknown place
+    fn expand(&self, enclosing : &mut PetriStructure) -> Option<()> {
+        let subnet = self.subnet.flatten()?;
+        let mut names : HashMap<Label, Label> = subnet.places.iter().map(|p| {
+            (p.name.clone(), p.name.set_domain(self.name.clone()))
+        }).collect();
+        for (interface, place) in self.bindings.iter() {
+            if !names.contains_key(interface) || !enclosing.places.iter().any(|p| p.name == *place) {
+                return None;
+            }
+            names.insert(interface.clone(), place.clone());
+        }
+        let rename = |label : &Label| names.get(label).cloned().unwrap_or_else(|| label.set_domain(self.name.clone()));
+        for place in subnet.places.iter() {
+            if self.bindings.iter().any(|(interface, _)| *interface == place.name) {
+                continue;
+            }
+            let mut place = place.clone();
+            place.name = rename(&place.name);
+            enclosing.places.push(place);
+        }
+        let mut renaming = PlaceRenaming { names : &names };
+        for transition in subnet.transitions.iter() {
+            let mut transition = PetriTransition::clone(transition);
+            transition.label = rename(&transition.label);
+            transition.from = transition.from.iter().map(rename).collect();
+            transition.to = transition.to.iter().map(rename).collect();
+            transition.flushes = transition.flushes.iter().map(rename).collect();
+            transition.resets = transition.resets.iter().map(rename).collect();
+            for (place, _) in transition.inhibitors.iter_mut().chain(transition.reads.iter_mut()) {
+                *place = rename(place);
+            }
+            for (clock, _) in transition.clock_guards.iter_mut() {
+                *clock = rename(clock);
+            }
+            for (place, weight) in transition.input_weights.iter_mut().chain(transition.output_weights.iter_mut()) {
+                *place = rename(place);
+                renaming.transform_expression(weight);
+            }
+            renaming.transform_condition(&mut transition.guard);
+            enclosing.transitions.push(transition);
+        }
+        Some(())
+    }
+
+}
+
+// Places of a subnet referred to in guards and weights, by their name in the enclosing net
+struct PlaceRenaming<'a> {
+    names : &'a HashMap<Label, Label>
+}
+
+impl QueryTransformer for PlaceRenaming<'_> {
+
+    fn transform_expression(&mut self, expr : &mut Expr) {
+        if let Expr::Var(x) = expr {
+            if let Some(name) = self.names.get(&x.name) {
+                *expr = Expr::Var(ModelVar::name(name.clone()));
+                return;
+            }
+        }
+        expr.transform_children(self);
+    }
+
+}
+
+impl PetriStructure {
+
+    pub fn is_hierarchical(&self) -> bool {
+        !self.substitutions.is_empty()
+    }
+
+    // Same net where substitutions are replaced by their subnets, recursively. None if a binding refers to an unknown place
+    pub fn flatten(&self) -> Option<PetriStructure> {
+        let mut flat = PetriStructure {
+            places : self.places.clone(),
+            transitions : self.transitions.clone(),
+            race_policy : self.race_policy,
+            substitutions : Vec::new(),
+        };
+        for substitution in self.substitutions.iter() {
+            substitution.expand(&mut flat)?;
+        }
+        Some(flat)
+    }
+
+}
+
+impl PetriNet {
+
+    pub fn with_substitution(mut self, substitution : SubstitutionTransition) -> Self {
+        self.substitutions.push(substitution);
+        self
+    }
+
+}
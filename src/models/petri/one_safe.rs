@@ -0,0 +1,247 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{computation::BitSet, models::{expressions::Condition, model_var::ModelVar, ModelState, TransitionId}};
+
+use super::PetriNet;
+
+// Markings explored when looking for a place receiving a second token
+const ONE_SAFE_EXPLORATION_LIMIT : usize = 1 << 16;
+
+// Transition acting on markings encoded as sets of marked places
+#[derive(Debug, Clone)]
+struct SafeTransition {
+    priority : i32,
+    // Consumes or reads several tokens from a place, or can't put its tokens anywhere : never enabled in a 1-safe marking
+    dead : bool,
+    preset : BitSet,
+    postset : BitSet,
+    reads : BitSet,
+    inhibitors : BitSet,
+    flushes : BitSet,
+    // Output places of capacity 1 : a second token disables the transition instead of overflowing
+    capped : BitSet,
+    // Output places receiving several tokens at once
+    doubled : BitSet,
+}
+
+/// Markings of a 1-safe Petri net encoded as bit sets of marked places, instead of an integer variable per place :
+/// a marking takes one bit per place and is hashed as a few words, transitions being enabled by inclusion of their preset.
+/// Firings putting a second token in a place are detected, the net not being 1-safe from such a marking.
+/// Guards can't be evaluated on bits, so that the encoding of a net having guards only overapproximates its behaviour.
+#[derive(Debug, Clone)]
+pub struct OneSafeEncoding {
+    // Bits follow the net exactly, no transition having a guard or clock constraints
+    pub exact : bool,
+    places : Vec<ModelVar>,
+    transitions : Vec<SafeTransition>,
+}
+
+impl OneSafeEncoding {
+
+    // None if the net isn't compiled, or has marking-dependent weights
+    pub fn new(net : &PetriNet) -> Option<Self> {
+        if net.has_marking_dependent_weights() || net.places_dic.len() != net.places.len() {
+            return None;
+        }
+        let mut transitions = Vec::new();
+        for transition in net.transitions.iter() {
+            let mut safe = SafeTransition {
+                priority : transition.priority,
+                dead : false,
+                preset : BitSet::new(),
+                postset : BitSet::new(),
+                reads : BitSet::new(),
+                inhibitors : BitSet::new(),
+                flushes : BitSet::new(),
+                capped : BitSet::new(),
+                doubled : BitSet::new(),
+            };
+            let mut consumed : HashMap<usize, i32> = HashMap::new();
+            for place in transition.from.iter() {
+                *consumed.entry(net.place_id(place)?.index()).or_default() += 1;
+            }
+            for (place, weight) in consumed {
+                safe.dead |= weight > 1;
+                safe.preset.enable(place);
+            }
+            let mut produced : HashMap<usize, i32> = HashMap::new();
+            for place in transition.to.iter() {
+                *produced.entry(net.place_id(place)?.index()).or_default() += 1;
+            }
+            for (place, weight) in produced {
+                safe.postset.enable(place);
+                match net.places[place].capacity {
+                    Some(capacity) if weight > capacity => safe.dead = true,
+                    Some(1) => safe.capped.enable(place),
+                    _ if weight > 1 => safe.doubled.enable(place),
+                    _ => ()
+                }
+            }
+            for (place, weight) in transition.reads.iter() {
+                let place = net.place_id(place)?.index();
+                safe.dead |= *weight > 1;
+                if *weight == 1 {
+                    safe.reads.enable(place);
+                }
+            }
+            for (place, weight) in transition.inhibitors.iter() {
+                let place = net.place_id(place)?.index();
+                safe.dead |= *weight <= 0;
+                if *weight == 1 {
+                    safe.inhibitors.enable(place);
+                }
+            }
+            for place in transition.flushes.iter() {
+                safe.flushes.enable(net.place_id(place)?.index());
+            }
+            transitions.push(safe);
+        }
+        Some(OneSafeEncoding {
+            exact : net.transitions.iter().all(|t| t.guard == Condition::True && t.clock_guards.is_empty()),
+            places : net.places.iter().map(|p| p.get_var().clone()).collect(),
+            transitions,
+        })
+    }
+
+    // None if a place holds several tokens
+    pub fn encode(&self, state : &ModelState) -> Option<BitSet> {
+        let mut marking = BitSet::new();
+        for (i, var) in self.places.iter().enumerate() {
+            match state.tokens(var) {
+                0 => (),
+                1 => marking.enable(i),
+                _ => return None
+            }
+        }
+        Some(marking)
+    }
+
+    // Marking of the state replaced by the encoded one
+    pub fn decode(&self, marking : &BitSet, mut state : ModelState) -> ModelState {
+        for (i, var) in self.places.iter().enumerate() {
+            state.set_marking(var, marking.is_enabled(i) as i32);
+        }
+        state
+    }
+
+    fn residual(transition : &SafeTransition, marking : &BitSet) -> BitSet {
+        marking.difference(&transition.preset).difference(&transition.flushes)
+    }
+
+    pub fn is_enabled(&self, transition : TransitionId, marking : &BitSet) -> bool {
+        let t = &self.transitions[transition.index()];
+        !t.dead && marking.includes(&t.preset) && marking.includes(&t.reads) && !marking.intersects(&t.inhibitors)
+            && !Self::residual(t, marking).intersects(&t.capped)
+    }
+
+    // Marking after firing the transition. None if a place receives a second token
+    pub fn fire(&self, transition : TransitionId, marking : &BitSet) -> Option<BitSet> {
+        let t = &self.transitions[transition.index()];
+        let mut next = Self::residual(t, marking);
+        if !t.doubled.is_empty() || next.intersects(&t.postset) {
+            return None;
+        }
+        next.merge(&t.postset);
+        Some(next)
+    }
+
+    // Enabled transitions, of the highest priority only if priorities are applied as the net does
+    pub fn fireable(&self, marking : &BitSet, priorities : bool) -> Vec<TransitionId> {
+        let enabled : Vec<TransitionId> = (0..self.transitions.len()).map(TransitionId::from)
+            .filter(|t| self.is_enabled(*t, marking)).collect();
+        if !priorities {
+            return enabled;
+        }
+        let Some(priority) = enabled.iter().map(|t| self.transitions[t.index()].priority).max() else {
+            return enabled;
+        };
+        enabled.into_iter().filter(|t| self.transitions[t.index()].priority == priority).collect()
+    }
+
+}
+
+/// Markings of an untimed 1-safe net reachable from an initial one, visited and stored as bit sets.
+/// Time isn't considered : on a timed net, the markings are those of its `untimed` projection
+#[derive(Debug, Clone)]
+pub struct OneSafeStateSpace {
+    pub encoding : OneSafeEncoding,
+    pub markings : Vec<BitSet>,
+    pub successors : Vec<Vec<(TransitionId, usize)>>,
+}
+
+impl OneSafeStateSpace {
+
+    // None if the net can't be encoded exactly, or a reachable marking isn't 1-safe
+    pub fn compute(net : &PetriNet, initial : &ModelState) -> Option<Self> {
+        let encoding = OneSafeEncoding::new(net).filter(|e| e.exact)?;
+        let first = encoding.encode(initial)?;
+        let mut space = OneSafeStateSpace { encoding, markings : vec![first.clone()], successors : vec![Vec::new()] };
+        let mut known : HashMap<BitSet, usize> = HashMap::from([(first, 0)]);
+        let mut to_see = VecDeque::from([0]);
+        while let Some(index) = to_see.pop_front() {
+            let marking = space.markings[index].clone();
+            for transition in space.encoding.fireable(&marking, true) {
+                let next = space.encoding.fire(transition, &marking)?;
+                let next_index = *known.entry(next.clone()).or_insert_with(|| {
+                    space.markings.push(next);
+                    space.successors.push(Vec::new());
+                    to_see.push_back(space.markings.len() - 1);
+                    space.markings.len() - 1
+                });
+                space.successors[index].push((transition, next_index));
+            }
+        }
+        Some(space)
+    }
+
+    pub fn deadlocks(&self) -> usize {
+        self.successors.iter().filter(|s| s.is_empty()).count()
+    }
+
+    // Marking as a state of the net, the rest of the state being taken from the template
+    pub fn state(&self, index : usize, template : &ModelState) -> ModelState {
+        let mut state = self.encoding.decode(&self.markings[index], template.clone());
+        state.deadlocked = self.successors[index].is_empty();
+        state
+    }
+
+}
+
+impl PetriNet {
+
+    pub fn one_safe_encoding(&self) -> Option<OneSafeEncoding> {
+        OneSafeEncoding::new(self)
+    }
+
+    // Whether no place ever holds more than one token from the marking : the structural sufficient condition first, then
+    // markings reachable without time, guards nor priorities, which only prevent firings, are explored as bit sets.
+    // A false result is inconclusive, reached markings of the abstraction possibly being unreachable
+    pub fn is_one_safe(&self, marking : &ModelState) -> bool {
+        if self.is_safe(marking) {
+            return true;
+        }
+        let Some(encoding) = self.one_safe_encoding() else {
+            return false;
+        };
+        let Some(initial) = encoding.encode(marking) else {
+            return false;
+        };
+        let mut seen : HashSet<BitSet> = HashSet::from([initial.clone()]);
+        let mut to_see = vec![initial];
+        while let Some(marking) = to_see.pop() {
+            for transition in encoding.fireable(&marking, false) {
+                let Some(next) = encoding.fire(transition, &marking) else {
+                    return false;
+                };
+                if seen.len() >= ONE_SAFE_EXPLORATION_LIMIT {
+                    return false;
+                }
+                if seen.insert(next.clone()) {
+                    to_see.push(next);
+                }
+            }
+        }
+        true
+    }
+
+}
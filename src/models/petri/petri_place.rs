@@ -2,7 +2,7 @@ use std::{fmt, sync::{Arc, RwLock, Weak}};
 
 use serde::{Serialize, Deserialize};
 
-use crate::models::{model_context::ModelContext, model_var::{ModelVar, VarType}, CompilationResult, Label, ModelState, Node};
+use crate::models::{model_context::ModelContext, model_var::{ModelVar, VarType}, CompilationResult, Label, ModelState, Node, NodeMetadata, PlaceId};
 
 use super::PetriTransition;
 
@@ -11,9 +11,16 @@ const PETRI_PLACE_VAR_TYPE : VarType = VarType::VarU8;
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PetriPlace {
     pub name: Label,
-    
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+
+    // Greatest number of tokens the place can hold : transitions that would exceed it are disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity : Option<i32>,
+
     #[serde(skip)]
-    pub index : usize,
+    pub index : PlaceId,
 
     #[serde(skip)]
     in_transitions : RwLock<Vec<Weak<PetriTransition>>>,
@@ -21,6 +28,10 @@ pub struct PetriPlace {
     #[serde(skip)]
     out_transitions : RwLock<Vec<Weak<PetriTransition>>>,
 
+    // Transitions reading the place through read or inhibitor arcs, or bounded by its capacity, which stay persistent while it changes
+    #[serde(skip)]
+    read_transitions : RwLock<Vec<Weak<PetriTransition>>>,
+
     #[serde(skip)]
     data_variable : ModelVar
 }
@@ -30,13 +41,26 @@ impl PetriPlace {
     pub fn new(lbl : Label) -> Self {
         PetriPlace {
             name: lbl,
-            index : 0,
+            metadata : Default::default(),
+            capacity : None,
+            index : PlaceId(0),
             in_transitions : RwLock::new(Vec::new()),
             out_transitions : RwLock::new(Vec::new()),
+            read_transitions : RwLock::new(Vec::new()),
             data_variable: Default::default()
         }
     }
 
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_capacity(mut self, capacity : i32) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
     pub fn add_upstream_transition(&self, transi : &Arc<PetriTransition>) {
         self.in_transitions.write().unwrap().push(Arc::downgrade(transi))
     }
@@ -65,6 +89,20 @@ impl PetriPlace {
         }).collect()
     }
 
+    pub fn add_reading_transition(&self, transi : &Arc<PetriTransition>) {
+        self.read_transitions.write().unwrap().push(Arc::downgrade(transi))
+    }
+
+    pub fn clear_reading_transitions(&self) {
+        self.read_transitions.write().unwrap().clear()
+    }
+
+    pub fn get_reading_transitions(&self) -> Vec<Arc<PetriTransition>> {
+        self.read_transitions.read().unwrap().iter().map(|pt| {
+            Weak::upgrade(pt).unwrap()
+        }).collect()
+    }
+
     pub fn set_var(&mut self, var : ModelVar) {
         self.data_variable = var;
     }
@@ -90,6 +128,10 @@ impl Node for PetriPlace {
         self.name.clone()
     }
 
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
 }
 
 impl fmt::Display for PetriPlace {
@@ -105,6 +147,8 @@ impl Clone for PetriPlace {
     fn clone(&self) -> Self {
         PetriPlace {
             name: self.name.clone(),
+            metadata : self.metadata.clone(),
+            capacity : self.capacity,
             index : self.index,
             ..Default::default()
         }
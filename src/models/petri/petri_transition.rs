@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fmt;
 use std::sync::{Arc, RwLock};
 
@@ -7,14 +8,17 @@ use crate::computation::intervals::Convex;
 use crate::models::action::Action;
 use crate::models::model_clock::ModelClock;
 use crate::models::model_context::ModelContext;
-use crate::models::time::TimeInterval;
-use crate::models::{CompilationError, CompilationResult, Edge, Label, ModelState, Node};
-use crate::models::expressions::Condition;
+use crate::models::model_var::ModelVar;
+use crate::models::time::{RealDistribution, TimeInterval};
+use crate::models::{CompilationError, CompilationResult, Edge, Label, ModelState, Node, NodeMetadata, TransitionId};
+use crate::models::expressions::{Condition, Expr, ObjectsScannerVisitor};
 
-use super::PetriPlace;
+use super::{PetriPlace, StochasticFiring};
 
 pub type InputEdge = Edge<i32, PetriPlace, PetriTransition>;
 pub type OutputEdge = Edge<i32, PetriTransition, PetriPlace>;
+pub type WeightedInputEdge = Edge<Expr, PetriPlace, PetriTransition>;
+pub type WeightedOutputEdge = Edge<Expr, PetriTransition, PetriPlace>;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PetriTransition {
@@ -25,8 +29,44 @@ pub struct PetriTransition {
     pub controllable : bool,
     pub guard : Condition,
 
+    // Constraints on user-declared clocks for the transition to be fireable, and clocks reset when it fires
+    #[serde(default)]
+    pub clock_guards : Vec<(Label, TimeInterval)>,
+    #[serde(default)]
+    pub resets : Vec<Label>,
+
+    // Inhibitor arcs : the transition is disabled as soon as the place holds at least weight tokens
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inhibitors : Vec<(Label, i32)>,
+
+    // Read arcs : the place must hold at least weight tokens, none of them being consumed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reads : Vec<(Label, i32)>,
+
+    // Reset arcs : places emptied when the transition fires, once input tokens are consumed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flushes : Vec<Label>,
+
+    // Marking-dependent arcs : weights are expressions over places, evaluated against the marking before firing.
+    // Negative weights count as 0
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_weights : Vec<(Label, Expr)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub output_weights : Vec<(Label, Expr)>,
+
+    // Only fireable transitions of the highest priority can fire
+    #[serde(default)]
+    pub priority : i32,
+
+    // Stochastic firing, replacing the firing interval in simulations (see StochasticFiring)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firing : Option<StochasticFiring>,
+
+    #[serde(default, skip_serializing_if = "NodeMetadata::is_empty")]
+    pub metadata : NodeMetadata,
+
     #[serde(skip)]
-    pub index : usize,
+    pub index : TransitionId,
 
     #[serde(skip)]
     pub input_edges: RwLock<Vec<Arc<InputEdge>>>,
@@ -34,6 +74,21 @@ pub struct PetriTransition {
     #[serde(skip)]
     pub output_edges: RwLock<Vec<Arc<OutputEdge>>>,
 
+    #[serde(skip)]
+    pub inhibitor_edges: RwLock<Vec<Arc<InputEdge>>>,
+
+    #[serde(skip)]
+    pub read_edges: RwLock<Vec<Arc<InputEdge>>>,
+
+    #[serde(skip)]
+    pub flush_edges: RwLock<Vec<Arc<OutputEdge>>>,
+
+    #[serde(skip)]
+    pub weighted_input_edges: RwLock<Vec<Arc<WeightedInputEdge>>>,
+
+    #[serde(skip)]
+    pub weighted_output_edges: RwLock<Vec<Arc<WeightedOutputEdge>>>,
+
     #[serde(skip)]
     pub compiled_guard : Condition,
 
@@ -41,7 +96,19 @@ pub struct PetriTransition {
     pub action : Action,
 
     #[serde(skip)]
-    pub clock : ModelClock
+    pub clock : ModelClock,
+
+    #[serde(skip)]
+    pub compiled_clock_guards : Vec<(ModelClock, TimeInterval)>,
+
+    #[serde(skip)]
+    pub compiled_resets : Vec<ModelClock>,
+
+    #[serde(skip)]
+    pub compiled_input_weights : Vec<(Label, Expr)>,
+
+    #[serde(skip)]
+    pub compiled_output_weights : Vec<(Label, Expr)>,
 }
 
 impl Node for PetriTransition {
@@ -50,6 +117,10 @@ impl Node for PetriTransition {
         self.label.clone()
     }
 
+    fn get_metadata(&self) -> Option<&NodeMetadata> {
+        Some(&self.metadata)
+    }
+
 }
 
 impl PetriTransition {
@@ -87,6 +158,70 @@ impl PetriTransition {
         }
     }
 
+    pub fn with_metadata(mut self, metadata : NodeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_clock_guard(mut self, clock : Label, interval : TimeInterval) -> Self {
+        self.clock_guards.push((clock, interval));
+        self
+    }
+
+    pub fn with_reset(mut self, clock : Label) -> Self {
+        self.resets.push(clock);
+        self
+    }
+
+    pub fn with_inhibitor(mut self, place : Label, weight : i32) -> Self {
+        self.inhibitors.push((place, weight));
+        self
+    }
+
+    pub fn with_read(mut self, place : Label, weight : i32) -> Self {
+        self.reads.push((place, weight));
+        self
+    }
+
+    pub fn with_flush(mut self, place : Label) -> Self {
+        self.flushes.push(place);
+        self
+    }
+
+    pub fn with_input_weight(mut self, place : Label, weight : Expr) -> Self {
+        self.input_weights.push((place, weight));
+        self
+    }
+
+    pub fn with_output_weight(mut self, place : Label, weight : Expr) -> Self {
+        self.output_weights.push((place, weight));
+        self
+    }
+
+    pub fn with_priority(mut self, priority : i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_rate(self, rate : f64) -> Self {
+        self.with_distribution(RealDistribution::Exponential(rate))
+    }
+
+    pub fn with_distribution(mut self, distribution : RealDistribution) -> Self {
+        self.firing = Some(StochasticFiring::Timed(distribution));
+        self
+    }
+
+    pub fn with_weight(mut self, weight : f64) -> Self {
+        self.firing = Some(StochasticFiring::Immediate(weight));
+        self
+    }
+
+    // User-declared clocks read or reset by the transition
+    pub fn declared_clocks(&self) -> Vec<Label> {
+        self.clock_guards.iter().map(|(c, _)| c.clone()).chain(self.resets.iter().cloned()).collect()
+    }
+
     pub fn get_inputs(&self) -> Vec<Arc<InputEdge>> {
         self.input_edges.read().unwrap().iter().map(|e| {
             Arc::clone(e)
@@ -107,6 +242,75 @@ impl PetriTransition {
         self.output_edges.write().unwrap().push(Arc::new(edge))
     }
 
+    pub fn get_inhibitors(&self) -> Vec<Arc<InputEdge>> {
+        self.inhibitor_edges.read().unwrap().iter().map(|e| {
+            Arc::clone(e)
+        }).collect()
+    }
+
+    pub fn add_inhibitor_edge(&self, edge : Edge<i32, PetriPlace, PetriTransition>) {
+        self.inhibitor_edges.write().unwrap().push(Arc::new(edge))
+    }
+
+    pub fn get_reads(&self) -> Vec<Arc<InputEdge>> {
+        self.read_edges.read().unwrap().iter().map(|e| {
+            Arc::clone(e)
+        }).collect()
+    }
+
+    pub fn add_read_edge(&self, edge : Edge<i32, PetriPlace, PetriTransition>) {
+        self.read_edges.write().unwrap().push(Arc::new(edge))
+    }
+
+    pub fn get_flushes(&self) -> Vec<Arc<OutputEdge>> {
+        self.flush_edges.read().unwrap().iter().map(|e| {
+            Arc::clone(e)
+        }).collect()
+    }
+
+    pub fn add_flush_edge(&self, edge : Edge<i32, PetriTransition, PetriPlace>) {
+        self.flush_edges.write().unwrap().push(Arc::new(edge))
+    }
+
+    pub fn get_weighted_inputs(&self) -> Vec<Arc<WeightedInputEdge>> {
+        self.weighted_input_edges.read().unwrap().iter().map(|e| {
+            Arc::clone(e)
+        }).collect()
+    }
+
+    pub fn add_weighted_input_edge(&self, edge : Edge<Expr, PetriPlace, PetriTransition>) {
+        self.weighted_input_edges.write().unwrap().push(Arc::new(edge))
+    }
+
+    pub fn get_weighted_outputs(&self) -> Vec<Arc<WeightedOutputEdge>> {
+        self.weighted_output_edges.read().unwrap().iter().map(|e| {
+            Arc::clone(e)
+        }).collect()
+    }
+
+    pub fn add_weighted_output_edge(&self, edge : Edge<Expr, PetriTransition, PetriPlace>) {
+        self.weighted_output_edges.write().unwrap().push(Arc::new(edge))
+    }
+
+    // Arcs whose weight depends on the marking : fixed-weight solutions have to fall back on the concrete semantics
+    pub fn has_marking_dependent_weights(&self) -> bool {
+        !self.input_weights.is_empty() || !self.output_weights.is_empty()
+    }
+
+    pub fn arc_weight(weight : &Expr, marking : &ModelState) -> i32 {
+        weight.evaluate(marking).as_int().max(0)
+    }
+
+    // Variables the enabling of the transition depends on, besides its input, read and inhibiting places
+    pub fn dependency_vars(&self) -> HashSet<ModelVar> {
+        let mut scanner = ObjectsScannerVisitor::new();
+        self.compiled_guard.accept(&mut scanner);
+        for (_, weight) in self.compiled_input_weights.iter().chain(self.compiled_output_weights.iter()) {
+            weight.accept(&mut scanner);
+        }
+        scanner.vars
+    }
+
     pub fn is_enabled(&self, marking : &ModelState) -> bool {
         for edge in self.input_edges.read().unwrap().iter() {
             if !edge.has_source() {
@@ -116,20 +320,78 @@ impl PetriTransition {
                 return false
             }
         }
+        for edge in self.weighted_input_edges.read().unwrap().iter() {
+            if edge.get_node_from().tokens(marking) < Self::arc_weight(&edge.weight, marking) {
+                return false
+            }
+        }
+        for edge in self.read_edges.read().unwrap().iter() {
+            if edge.get_node_from().tokens(marking) < edge.weight {
+                return false
+            }
+        }
+        for edge in self.inhibitor_edges.read().unwrap().iter() {
+            if edge.get_node_from().tokens(marking) >= edge.weight {
+                return false
+            }
+        }
+        let outputs = self.output_edges.read().unwrap().iter().map(|e| e.get_node_to())
+            .chain(self.weighted_output_edges.read().unwrap().iter().map(|e| e.get_node_to())).collect::<Vec<_>>();
+        for place in outputs {
+            if place.capacity.is_some_and(|capacity| self.tokens_after(&place, marking) > capacity) {
+                return false
+            }
+        }
         self.compiled_guard.is_true(marking)
     }
 
+    // Tokens of the place once the transition has fired from the given marking
+    pub fn tokens_after(&self, place : &PetriPlace, marking : &ModelState) -> i32 {
+        let mut tokens = place.tokens(marking);
+        for edge in self.input_edges.read().unwrap().iter() {
+            if edge.get_node_from().index == place.index {
+                tokens -= edge.weight;
+            }
+        }
+        for edge in self.weighted_input_edges.read().unwrap().iter() {
+            if edge.get_node_from().index == place.index {
+                tokens -= Self::arc_weight(&edge.weight, marking);
+            }
+        }
+        if self.flush_edges.read().unwrap().iter().any(|edge| edge.get_node_to().index == place.index) {
+            tokens = 0;
+        }
+        for edge in self.output_edges.read().unwrap().iter() {
+            if edge.get_node_to().index == place.index {
+                tokens += edge.weight;
+            }
+        }
+        for edge in self.weighted_output_edges.read().unwrap().iter() {
+            if edge.get_node_to().index == place.index {
+                tokens += Self::arc_weight(&edge.weight, marking);
+            }
+        }
+        tokens
+    }
+
     pub fn is_fireable(&self, state : &ModelState) -> bool {
         let clockvalue = state.get_clock_value(self.get_clock());
         if clockvalue.is_disabled() {
             return false;
         }
-        self.interval.contains(&clockvalue)
+        self.interval.contains(&clockvalue) && self.compiled_clock_guards.iter().all(|(clock, interval)| {
+            interval.contains(&state.get_clock_value(clock))
+        })
     }
 
     pub fn clear_edges(&self) {
         self.input_edges.write().unwrap().clear();
         self.output_edges.write().unwrap().clear();
+        self.inhibitor_edges.write().unwrap().clear();
+        self.read_edges.write().unwrap().clear();
+        self.flush_edges.write().unwrap().clear();
+        self.weighted_input_edges.write().unwrap().clear();
+        self.weighted_output_edges.write().unwrap().clear();
     }
 
     pub fn inertia(&self) -> i32 {
@@ -171,11 +433,37 @@ impl PetriTransition {
             },
             Err(_) => return Err(CompilationError)
         };
+        let valid_firing = match self.firing {
+            Some(StochasticFiring::Immediate(weight)) => weight > 0.0,
+            Some(StochasticFiring::Timed(distribution)) => distribution.is_valid(),
+            None => true
+        };
+        if !valid_firing {
+            return Err(CompilationError);
+        }
+        let compile_weights = |weights : &Vec<(Label, Expr)>| weights.iter().map(|(place, weight)| {
+            weight.apply_to(ctx).map(|w| (place.clone(), w)).map_err(|_| CompilationError)
+        }).collect::<CompilationResult<Vec<(Label, Expr)>>>();
+        self.compiled_input_weights = compile_weights(&self.input_weights)?;
+        self.compiled_output_weights = compile_weights(&self.output_weights)?;
         self.set_action(ctx.add_action(self.get_label()));
         self.set_clock(ctx.add_clock(self.get_label()));
         Ok(())
     }
 
+    // Declared clocks have to be added to the context beforehand
+    pub fn compile_clocks(&mut self, ctx : &ModelContext) -> CompilationResult<()> {
+        self.compiled_clock_guards.clear();
+        for (clock, interval) in self.clock_guards.iter() {
+            let compiled = ctx.get_clock(clock).ok_or(CompilationError)?;
+            self.compiled_clock_guards.push((compiled, *interval));
+        }
+        self.compiled_resets = self.resets.iter().map(|clock| {
+            ctx.get_clock(clock).ok_or(CompilationError)
+        }).collect::<CompilationResult<Vec<ModelClock>>>()?;
+        Ok(())
+    }
+
 }
 
 impl fmt::Display for PetriTransition {
@@ -202,6 +490,16 @@ impl Clone for PetriTransition {
             interval: self.interval.clone(),
             controllable : self.controllable.clone(),
             guard : self.guard.clone(),
+            clock_guards : self.clock_guards.clone(),
+            resets : self.resets.clone(),
+            inhibitors : self.inhibitors.clone(),
+            reads : self.reads.clone(),
+            flushes : self.flushes.clone(),
+            input_weights : self.input_weights.clone(),
+            output_weights : self.output_weights.clone(),
+            priority : self.priority,
+            firing : self.firing,
+            metadata : self.metadata.clone(),
             index : self.index,
             ..Default::default()
         }
@@ -0,0 +1,174 @@
+use std::{fmt, sync::Arc};
+
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+
+use crate::computation::random::{choose_uniform, SimulationRng};
+use crate::models::{action::Action, markov::ProbabilisticChoice, model_storage::ModelStorage, time::{ClockValue, RealDistribution, TimeBound}, Model, ModelState, TransitionId};
+
+use super::{PetriNet, PetriTransition};
+
+/// Stochastic firing of a transition (GSPN) : immediate transitions fire as soon as enabled, the one firing being drawn
+/// according to their weights, timed ones race, the first sampled delay winning
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StochasticFiring {
+    Immediate(f64),
+    Timed(RealDistribution),
+}
+
+impl fmt::Display for StochasticFiring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StochasticFiring::Immediate(weight) => write!(f, "immediate {}", weight),
+            StochasticFiring::Timed(distribution) => write!(f, "{}", distribution),
+        }
+    }
+}
+
+/// What becomes of the sampled delays of the transitions losing a race
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RacePolicy {
+    // Every delay is sampled again after each firing, only exact for exponential delays
+    #[default]
+    Resampling,
+    // Delays are kept while the transition stays enabled
+    EnablingMemory,
+    // Delays are kept while the transition is disabled too, until it fires
+    AgeMemory,
+}
+
+impl PetriTransition {
+
+    pub fn is_immediate(&self) -> bool {
+        matches!(self.firing, Some(StochasticFiring::Immediate(_)))
+    }
+
+    // Delay before firing, given the time since enabling. Transitions without distribution fire uniformly within their
+    // interval, or after an exponential delay of rate 1 past its lower bound when unbounded
+    pub fn sample_firing_delay(&self, elapsed : ClockValue, rng : &mut SimulationRng) -> f64 {
+        match self.firing {
+            Some(StochasticFiring::Timed(distribution)) => distribution.sample(rng),
+            Some(StochasticFiring::Immediate(_)) => 0.0,
+            None => {
+                let date = match self.interval.1 {
+                    TimeBound::Infinite => self.interval.0.float() + RealDistribution::Exponential(1.0).sample(rng),
+                    _ => self.interval.random_date(rng).float()
+                };
+                (date - elapsed.float()).max(0.0)
+            }
+        }
+    }
+
+}
+
+// GSPN semantics of nets having stochastic transitions. Sampled delays of the transitions are kept in the storage of
+// the net, as the time left before they fire. Firing intervals and clock guards are ignored by transitions having
+// a distribution.
+impl PetriNet {
+
+    pub fn with_race_policy(mut self, race_policy : RacePolicy) -> Self {
+        self.race_policy = race_policy;
+        self
+    }
+
+    pub fn has_stochastic_firing(&self) -> bool {
+        self.transitions.iter().any(|t| t.firing.is_some())
+    }
+
+    // Time left before each transition fires, None when not sampled yet
+    pub fn firing_delays(&self, state : &ModelState) -> Vec<Option<f64>> {
+        let Some(index) = self.storage_index else {
+            return vec![None ; self.transitions.len()];
+        };
+        match state.storage(&index) {
+            ModelStorage::Vector(delays) => delays.iter().map(|d| match d {
+                ModelStorage::Float(x) => Some(*x),
+                _ => None
+            }).collect(),
+            _ => vec![None ; self.transitions.len()]
+        }
+    }
+
+    pub fn set_firing_delays(&self, state : &mut ModelState, delays : &[Option<f64>]) {
+        let Some(index) = self.storage_index else {
+            return;
+        };
+        *state.mut_storage(&index) = ModelStorage::Vector(delays.iter().map(|d| match d {
+            Some(x) => ModelStorage::Float(*x),
+            None => ModelStorage::EmptyStorage
+        }).collect());
+    }
+
+    fn enabled(&self, state : &ModelState) -> Vec<&Arc<PetriTransition>> {
+        self.transitions.iter().filter(|t| state.is_enabled(t.get_clock())).collect()
+    }
+
+    // Highest priority among the given transitions, then uniform choice
+    fn choose_prioritized(transitions : &[&Arc<PetriTransition>], rng : &mut SimulationRng) -> Option<TransitionId> {
+        let priority = transitions.iter().map(|t| t.priority).max()?;
+        let mut chosen : Vec<&&Arc<PetriTransition>> = transitions.iter().filter(|t| t.priority == priority).collect();
+        chosen.sort_by_key(|t| t.get_action().get_id());
+        choose_uniform(&chosen, rng).map(|t| t.index)
+    }
+
+    pub fn stochastic_next(&self, mut state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let enabled = self.enabled(&state);
+        let immediate : Vec<&Arc<PetriTransition>> = enabled.iter().copied().filter(|t| t.is_immediate()).collect();
+        if let Some(priority) = immediate.iter().map(|t| t.priority).max() {
+            let choice = ProbabilisticChoice(immediate.iter().filter(|t| t.priority == priority).map(|t| {
+                let weight = match t.firing { Some(StochasticFiring::Immediate(w)) => w, _ => 1.0 };
+                (t.index, weight)
+            }).collect());
+            let fired = *choice.sample(rng);
+            return self.stochastic_fire(state, ClockValue::zero(), fired);
+        }
+        let mut delays = self.firing_delays(&state);
+        let mut earliest : Option<f64> = None;
+        for transition in enabled.iter() {
+            let i = transition.index.index();
+            let delay = match (self.race_policy, delays[i]) {
+                (RacePolicy::Resampling, _) | (_, None) => transition.sample_firing_delay(state.get_clock_value(transition.get_clock()), rng),
+                (_, Some(delay)) => delay
+            };
+            delays[i] = Some(delay);
+            earliest = Some(earliest.map_or(delay, |e| e.min(delay)));
+        }
+        let Some(earliest) = earliest else {
+            state.deadlocked = true;
+            return (Some(state), ClockValue::zero(), None);
+        };
+        let winners : Vec<&Arc<PetriTransition>> = enabled.iter().copied().filter(|t| delays[t.index.index()] == Some(earliest)).collect();
+        let fired = Self::choose_prioritized(&winners, rng).unwrap();
+        if self.race_policy != RacePolicy::Resampling {
+            // Losers of the race have waited as long as the winner
+            for transition in enabled.iter() {
+                let delay = &mut delays[transition.index.index()];
+                *delay = delay.map(|d| (d - earliest).max(0.0));
+            }
+            self.set_firing_delays(&mut state, &delays);
+        }
+        let delay = ClockValue::from(earliest);
+        let state = self.delay(state, delay).unwrap();
+        self.stochastic_fire(state, delay, fired)
+    }
+
+    fn stochastic_fire(&self, state : ModelState, delay : ClockValue, fired : TransitionId) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let (mut next, newen, pers) = self.fire(state, fired);
+        let mut delays = self.firing_delays(&next);
+        for (i, delay) in delays.iter_mut().enumerate() {
+            let transition = TransitionId(i);
+            let reset = match self.race_policy {
+                RacePolicy::Resampling => true,
+                RacePolicy::EnablingMemory => !pers.contains(&transition),
+                RacePolicy::AgeMemory => false
+            };
+            if reset || transition == fired {
+                *delay = None;
+            }
+        }
+        self.set_firing_delays(&mut next, &delays);
+        next.deadlocked = newen.is_empty() && pers.is_empty();
+        (Some(next), delay, Some(self.get_transition_action(fired)))
+    }
+
+}
@@ -0,0 +1,39 @@
+use super::PetriNet;
+use crate::models::ModelState;
+
+// Structural properties, known without exploring the state space
+impl PetriNet {
+
+    // Every transition puts back as many tokens as it consumes, so the total number of tokens never changes.
+    // Marking-dependent weights are assumed to break it
+    pub fn is_conservative(&self) -> bool {
+        self.transitions.iter().all(|t| t.from.len() == t.to.len() && t.flushes.is_empty() && !t.has_marking_dependent_weights())
+    }
+
+    // No transition produces more tokens than it consumes, reset arcs only taking tokens away
+    pub fn is_non_increasing(&self) -> bool {
+        self.transitions.iter().all(|t| t.to.len() <= t.from.len() && t.output_weights.is_empty())
+    }
+
+    pub fn has_marking_dependent_weights(&self) -> bool {
+        self.transitions.iter().any(|t| t.has_marking_dependent_weights())
+    }
+
+    pub fn token_count(&self, marking : &ModelState) -> i32 {
+        self.places.iter().map(|p| p.tokens(marking)).sum()
+    }
+
+    // Bound on the tokens of every place from the given marking, known for non-increasing nets only
+    pub fn token_bound(&self, marking : &ModelState) -> Option<i32> {
+        if !self.is_non_increasing() {
+            return None;
+        }
+        Some(self.token_count(marking))
+    }
+
+    // Sufficient condition for no place to ever hold more than one token : a false result is inconclusive
+    pub fn is_safe(&self, marking : &ModelState) -> bool {
+        self.token_bound(marking).is_some_and(|k| k <= 1)
+    }
+
+}
@@ -1,4 +1,4 @@
-use super::{expressions::{Condition, Expr}, model_var::ModelVar, ModelState};
+use super::{expressions::{Condition, Expr}, model_context::ModelContext, model_var::{MappingResult, ModelVar}, ModelState};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -21,7 +21,7 @@ impl Program {
         match self {
             Nop => state,
             Update(var, expr) => {
-                let res = expr.evaluate(&state);
+                let res = expr.evaluate(&state).as_int();
                 //var.set(&mut state, res);
                 state.set_var(var, res);
                 state
@@ -65,6 +65,32 @@ impl Program {
         }
     }
 
+    // Translate the variables of the program, given by name, to the objects of the context
+    pub fn apply_to(&self, ctx : &ModelContext) -> MappingResult<Program> {
+        let map = |p : &Program| p.apply_to(ctx).map(Box::new);
+        Ok(match self {
+            Nop => Nop,
+            Update(var, expr) => Update(var.apply_to(ctx)?, expr.apply_to(ctx)?),
+            IfElse(c, i, e) => IfElse(c.apply_to(ctx)?, map(i)?, map(e)?),
+            While(c, p) => While(c.apply_to(ctx)?, map(p)?),
+            DoWhile(c, p) => DoWhile(c.apply_to(ctx)?, map(p)?),
+            For(init, cond, upd, body) => For(map(init)?, cond.apply_to(ctx)?, map(upd)?, map(body)?),
+            Block(statements) => Block(statements.iter().map(|p| p.apply_to(ctx)).collect::<MappingResult<Vec<Program>>>()?)
+        })
+    }
+
+    // Variables the program may write
+    pub fn written_vars(&self) -> Vec<ModelVar> {
+        match self {
+            Nop => Vec::new(),
+            Update(var, _) => vec![var.clone()],
+            IfElse(_, i, e) => [i.written_vars(), e.written_vars()].concat(),
+            While(_, p) | DoWhile(_, p) => p.written_vars(),
+            For(init, _, upd, body) => [init.written_vars(), upd.written_vars(), body.written_vars()].concat(),
+            Block(statements) => statements.iter().flat_map(Program::written_vars).collect()
+        }
+    }
+
 }
 
 impl Default for Program {
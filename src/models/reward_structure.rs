@@ -0,0 +1,107 @@
+use std::{collections::HashMap, hash::{Hash, Hasher}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::verification::Verifiable;
+
+use super::{action::Action, expressions::Condition, model_context::ModelContext, model_var::{MappingError, MappingResult}, Label};
+
+/// Rewards (or costs) earned along runs. State rewards are earned per time unit (per step for untimed models)
+/// while the state satisfies their condition, action rewards each time the action is fired, and transition rewards
+/// each time a step leaves a state satisfying their first condition for one satisfying the second.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RewardStructure {
+    pub name : Label,
+    pub state_rewards : Vec<(Condition, f64)>,
+    pub action_rewards : Vec<(Label, f64)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transition_rewards : Vec<(Condition, Condition, f64)>,
+
+    #[serde(skip)]
+    compiled_actions : HashMap<Action, f64>,
+}
+
+impl RewardStructure {
+
+    pub fn new(name : Label) -> Self {
+        RewardStructure {
+            name,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_state_reward(mut self, condition : Condition, reward : f64) -> Self {
+        self.state_rewards.push((condition, reward));
+        self
+    }
+
+    pub fn with_action_reward(mut self, action : Label, reward : f64) -> Self {
+        self.action_rewards.push((action, reward));
+        self
+    }
+
+    pub fn with_transition_reward(mut self, from : Condition, to : Condition, reward : f64) -> Self {
+        self.transition_rewards.push((from, to, reward));
+        self
+    }
+
+    pub fn apply_to(&self, ctx : &ModelContext) -> MappingResult<RewardStructure> {
+        let mut mapped = self.clone();
+        mapped.state_rewards = self.state_rewards.iter().map(|(c, r)| {
+            Ok((c.apply_to(ctx)?, *r))
+        }).collect::<MappingResult<Vec<(Condition, f64)>>>()?;
+        mapped.transition_rewards = self.transition_rewards.iter().map(|(from, to, r)| {
+            Ok((from.apply_to(ctx)?, to.apply_to(ctx)?, *r))
+        }).collect::<MappingResult<Vec<(Condition, Condition, f64)>>>()?;
+        mapped.compiled_actions = HashMap::new();
+        for (label, reward) in self.action_rewards.iter() {
+            let Some(action) = ctx.get_action(label) else {
+                return Err(MappingError(label.clone()));
+            };
+            *mapped.compiled_actions.entry(action).or_insert(0.0) += reward;
+        }
+        Ok(mapped)
+    }
+
+    // Reward rate of the state, sum of the rewards of every satisfied condition
+    pub fn state_reward(&self, state : &impl Verifiable) -> f64 {
+        self.state_rewards.iter().filter(|(c, _)| c.is_true(state)).map(|(_, r)| r).sum()
+    }
+
+    pub fn action_reward(&self, action : &Action) -> f64 {
+        self.compiled_actions.get(&action.base()).copied().unwrap_or(0.0)
+    }
+
+    pub fn transition_reward(&self, left : &impl Verifiable, reached : &impl Verifiable) -> f64 {
+        self.transition_rewards.iter().filter(|(from, to, _)| from.is_true(left) && to.is_true(reached)).map(|(_, _, r)| r).sum()
+    }
+
+    // Reward earned by firing the action from the left state to the reached one
+    pub fn step_reward(&self, left : &impl Verifiable, reached : &impl Verifiable, action : &Action) -> f64 {
+        self.action_reward(action) + self.transition_reward(left, reached)
+    }
+
+}
+
+// Rewards are never NaN
+impl Eq for RewardStructure { }
+
+// Compiled actions are derived from the action rewards, hashing the definition is enough
+impl Hash for RewardStructure {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        for (condition, reward) in self.state_rewards.iter() {
+            condition.hash(state);
+            reward.to_bits().hash(state);
+        }
+        for (action, reward) in self.action_rewards.iter() {
+            action.hash(state);
+            reward.to_bits().hash(state);
+        }
+        for (from, to, reward) in self.transition_rewards.iter() {
+            from.hash(state);
+            to.hash(state);
+            reward.to_bits().hash(state);
+        }
+    }
+}
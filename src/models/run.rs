@@ -12,17 +12,49 @@ pub struct RunStatus {
     pub current_state : Rc<ModelState>,
     pub steps : usize,
     pub time : ClockValue,
-    pub maximal : bool
+    pub maximal : bool,
+    // Rewards cumulated for the reward bounds, in the order of VerificationBound::rewards
+    pub rewards : Vec<f64>,
 }
 
 impl RunStatus {
 
+    pub fn new(initial : Rc<ModelState>) -> Self {
+        RunStatus {
+            current_state : initial,
+            steps : 0,
+            time : ClockValue::zero(),
+            maximal : false,
+            rewards : Vec::new()
+        }
+    }
+
+    // Every elementary bound must be satisfied
     pub fn is_under(&self, bound : &VerificationBound) -> bool {
-        match bound {
+        let mut rewards = self.rewards.iter();
+        bound.bounds().into_iter().all(|bound| match bound {
             TimeRunBound(t) => self.time < ClockValue::from(*t as f64),
             StepsRunBound(s) => self.steps < *s,
             VarRunBound(v, x) => self.current_state.evaluate_var(v) < *x,
-            NoRunBound => true
+            RewardRunBound(_, x) => rewards.next().copied().unwrap_or(0.0) < x.0,
+            CompositeRunBound(_) | NoRunBound => true
+        })
+    }
+
+    // Earns the rewards of the bound for a step leaving the given state : its rate during the delay (per step for
+    // untimed models), and the reward of the action fired to reach the next one
+    pub fn earn_rewards(&mut self, bound : &VerificationBound, left : &ModelState, reached : &ModelState, delay : ClockValue, action : Option<&Action>, timed : bool) {
+        let rewards = bound.rewards();
+        self.rewards.resize(rewards.len(), 0.0);
+        for (total, reward) in self.rewards.iter_mut().zip(rewards) {
+            if timed {
+                *total += reward.state_reward(left) * delay.float();
+            } else if action.is_some() {
+                *total += reward.state_reward(left);
+            }
+            if let Some(action) = action {
+                *total += reward.step_reward(left, reached, action);
+            }
         }
     }
 
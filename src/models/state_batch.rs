@@ -0,0 +1,172 @@
+use std::hash::{Hash, Hasher};
+
+use crate::{computation::virtual_memory::EvaluationType, verification::Verifiable};
+
+use super::{model_clock::ModelClock, model_storage::ModelStorage, model_var::{ModelVar, VarType}, tapn::tapn_token::{TAPNTokenList, TokenPlace}, time::ClockValue, ModelState};
+
+use VarType::*;
+
+/// States of the same model stored as a structure of arrays : byte b of the discrete memory of state i is at b * len + i,
+/// and clock c of state i at c * len + i. A variable or a clock is thus scanned over the whole batch contiguously.
+/// Storages are opaque, they are kept state by state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateBatch {
+    len : usize,
+    memory_size : usize,
+    clocks_count : usize,
+    discrete : Vec<u8>,
+    clocks : Vec<ClockValue>,
+    storages : Vec<Vec<ModelStorage>>,
+    deadlocked : Vec<bool>,
+}
+
+impl StateBatch {
+
+    // Batch of len copies of the given state
+    pub fn filled(state : &ModelState, len : usize) -> Self {
+        let memory_size = state.discrete.size();
+        let clocks_count = state.clocks.len();
+        let mut batch = StateBatch {
+            len, memory_size, clocks_count,
+            discrete : vec![0 ; memory_size * len],
+            clocks : vec![ClockValue::disabled() ; clocks_count * len],
+            storages : vec![Vec::new() ; len],
+            deadlocked : vec![false ; len],
+        };
+        for i in 0..len {
+            batch.set(i, state);
+        }
+        batch
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clocks_count(&self) -> usize {
+        self.clocks_count
+    }
+
+    // Gathers the i-th state of the batch
+    pub fn get(&self, i : usize) -> ModelState {
+        let mut state = ModelState::new(self.memory_size, self.clocks_count);
+        for (b, byte) in state.discrete.as_bytes_mut().iter_mut().enumerate() {
+            *byte = self.discrete[b * self.len + i];
+        }
+        for c in 0..self.clocks_count {
+            state.clocks[c] = self.clocks[c * self.len + i];
+        }
+        state.storages = self.storages[i].clone();
+        state.deadlocked = self.deadlocked[i];
+        state
+    }
+
+    // Scatters the given state at the i-th position of the batch. States must share the memory layout of the batch
+    pub fn set(&mut self, i : usize, state : &ModelState) {
+        for (b, byte) in state.discrete.as_bytes().iter().enumerate() {
+            self.discrete[b * self.len + i] = *byte;
+        }
+        for c in 0..self.clocks_count {
+            self.clocks[c * self.len + i] = state.clocks[c];
+        }
+        self.storages[i] = state.storages.clone();
+        self.deadlocked[i] = state.deadlocked;
+    }
+
+    pub fn view(&self, i : usize) -> BatchedState<'_> {
+        BatchedState { batch : self, index : i }
+    }
+
+    pub fn evaluate_var(&self, var : &ModelVar, i : usize) -> EvaluationType {
+        let mut bytes = [0u8 ; 4];
+        let address = var.get_address();
+        for (b, byte) in bytes.iter_mut().enumerate().take(var.size()) {
+            *byte = self.discrete[(address + b) * self.len + i];
+        }
+        match var.get_type() {
+            VarU8 => bytes[0] as EvaluationType,
+            VarI8 => bytes[0] as i8 as EvaluationType,
+            VarU16 => u16::from_ne_bytes([bytes[0], bytes[1]]) as EvaluationType,
+            VarI16 => i16::from_ne_bytes([bytes[0], bytes[1]]) as EvaluationType,
+            VarU32 => u32::from_ne_bytes(bytes) as EvaluationType,
+            VarI32 => i32::from_ne_bytes(bytes),
+            _ => panic!("Can't evaluate untyped var !")
+        }
+    }
+
+    pub fn set_var(&mut self, var : &ModelVar, i : usize, value : EvaluationType) {
+        let bytes : Vec<u8> = match var.get_type() {
+            VarU8 => vec![value as u8],
+            VarI8 => vec![value as i8 as u8],
+            VarU16 => (value as u16).to_ne_bytes().to_vec(),
+            VarI16 => (value as i16).to_ne_bytes().to_vec(),
+            VarU32 => (value as u32).to_ne_bytes().to_vec(),
+            VarI32 => value.to_ne_bytes().to_vec(),
+            _ => panic!("Can't set untyped var !")
+        };
+        let address = var.get_address();
+        for (b, byte) in bytes.into_iter().enumerate() {
+            self.discrete[(address + b) * self.len + i] = byte;
+        }
+    }
+
+    // Values of a clock over the whole batch
+    pub fn clock_column(&self, clock : &ModelClock) -> &[ClockValue] {
+        let start = clock.get_index() * self.len;
+        &self.clocks[start..(start + self.len)]
+    }
+
+    pub fn clock_column_mut(&mut self, clock : &ModelClock) -> &mut [ClockValue] {
+        let start = clock.get_index() * self.len;
+        &mut self.clocks[start..(start + self.len)]
+    }
+
+    pub fn is_deadlocked(&self, i : usize) -> bool {
+        self.deadlocked[i]
+    }
+
+}
+
+/// State of a batch, verified in place
+#[derive(Debug, Clone, Copy)]
+pub struct BatchedState<'a> {
+    pub batch : &'a StateBatch,
+    pub index : usize,
+}
+
+impl Hash for BatchedState<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let batch = self.batch;
+        for b in 0..batch.memory_size {
+            batch.discrete[b * batch.len + self.index].hash(state);
+        }
+        for c in 0..batch.clocks_count {
+            batch.clocks[c * batch.len + self.index].hash(state);
+        }
+        batch.deadlocked[self.index].hash(state);
+    }
+}
+
+impl Verifiable for BatchedState<'_> {
+
+    fn evaluate_var(&self, var : &ModelVar) -> EvaluationType {
+        self.batch.evaluate_var(var, self.index)
+    }
+
+    fn evaluate_clock(&self, clock : &ModelClock) -> f64 {
+        self.batch.clocks[clock.get_index() * self.batch.len + self.index].float()
+    }
+
+    fn evaluate_tokens(&self, place : &TokenPlace) -> TAPNTokenList {
+        place.tokens(&self.batch.storages[self.index])
+    }
+
+    fn is_deadlocked(&self) -> bool {
+        self.batch.deadlocked[self.index]
+    }
+
+}
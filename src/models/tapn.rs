@@ -1,16 +1,20 @@
-use std::{collections::HashSet, iter::zip, sync::Arc};
+use std::{collections::HashSet, fmt, iter::zip, sync::Arc};
 
 use num_traits::Zero;
+use crate::computation::random::{choose_uniform, SimulationRng};
 use tapn_place::TAPNPlace;
 use tapn_token::*;
 use tapn_transition::TAPNTransition;
 
-use super::{action::Action, lbl, model_context::ModelContext, model_storage::ModelStorage, time::ClockValue, CompilationResult, Model, ModelMeta, ModelState, CONTROLLABLE, TIMED};
+use super::{action::Action, lbl, model_context::ModelContext, model_storage::ModelStorage, time::ClockValue, CompilationResult, Model, ModelMeta, ModelState, PlaceId, TransitionId, CONTROLLABLE, TIMED};
 
 pub mod tapn_place;
 pub mod tapn_edge;
 pub mod tapn_transition;
 pub mod tapn_token;
+mod compiled_tapn;
+
+pub use compiled_tapn::CompiledTAPN;
 
 pub struct TAPN {
     pub id : usize,
@@ -21,22 +25,31 @@ pub struct TAPN {
 
 impl TAPN {
 
-    pub fn fire(&self, mut state : ModelState, transi : usize, in_tokens : TAPNPlaceList) -> (ModelState, HashSet<usize>) {
+    pub fn place(&self, id : PlaceId) -> &Arc<TAPNPlace> {
+        &self.places[id.index()]
+    }
+
+    pub fn transition(&self, id : TransitionId) -> &Arc<TAPNTransition> {
+        &self.transitions[id.index()]
+    }
+
+    // Only meaningful on a compiled net : exposed through CompiledTAPN
+    pub(in crate::models) fn fire(&self, mut state : ModelState, transi : TransitionId, in_tokens : TAPNPlaceList) -> (ModelState, HashSet<PlaceId>) {
         let mut places_tokens = TAPNPlaceListAccessor::from(state.mut_storage(&self.storage_index));
-        let transi = &(self.transitions[transi]);
+        let transi = self.transition(transi);
         let mut modified_places = HashSet::new();
         let mut vars_updates = Vec::new();
         for edge in transi.input_edges.read().unwrap().iter() {
             let place = edge.get_node_from();
             vars_updates.push((place.clone(), -edge.data().weight));
-            let state_tokens = &mut places_tokens.places[place.index]; 
-            let input_tokens = &in_tokens.places[place.index];
+            let state_tokens = &mut places_tokens.places[place.index.index()]; 
+            let input_tokens = &in_tokens.places[place.index.index()];
             state_tokens.remove_set(input_tokens);
         }
         for edge in transi.output_edges.read().unwrap().iter() {
             let target = edge.get_node_to();
             vars_updates.push((target.clone(), edge.data().weight));
-            let target_tokens = &mut places_tokens.places[target.index];
+            let target_tokens = &mut places_tokens.places[target.index.index()];
             target_tokens.insert(TAPNToken { count: edge.data().weight, age: ClockValue::zero() });
         }
         for edge in transi.transports.read().unwrap().iter() {
@@ -44,10 +57,10 @@ impl TAPN {
             let target = edge.get_node_to();
             vars_updates.push((place.clone(), -edge.data().weight));
             vars_updates.push((target.clone(), edge.data().weight));
-            let state_tokens = &mut places_tokens.places[place.index]; 
-            let input_tokens = &in_tokens.places[place.index];
+            let state_tokens = &mut places_tokens.places[place.index.index()]; 
+            let input_tokens = &in_tokens.places[place.index.index()];
             state_tokens.remove_set(input_tokens);
-            let target_tokens = &mut places_tokens.places[target.index];
+            let target_tokens = &mut places_tokens.places[target.index.index()];
             for token in input_tokens.iter() {
                 target_tokens.insert(token.clone());
             }
@@ -59,6 +72,63 @@ impl TAPN {
         (state, modified_places)
     }
 
+    // Fires the transition on a combination of tokens drawn uniformly, or on the first one without generator
+    fn fire_tokens(&self, state : ModelState, transition : &TAPNTransition, rng : Option<&mut SimulationRng>) -> Option<ModelState> {
+        let mut storage = state.storage(&self.storage_index).clone();
+        let combinations = transition.fireable_tokens(TAPNPlaceListAccessor::from(&mut storage));
+        let tokens = match rng {
+            Some(rng) => choose_uniform(&combinations, rng)?,
+            None => combinations.first()?
+        };
+        Some(self.fire(state, transition.index, tokens.clone()).0)
+    }
+
+}
+
+// Canonical text form : places, transitions and arcs are sorted by name, so two equivalent nets print the same way.
+// Arcs are the compiled edges, with their age intervals and weights.
+impl fmt::Display for TAPN {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sorted = |mut arcs : Vec<String>| {
+            arcs.sort();
+            arcs.join(", ")
+        };
+        writeln!(f, "TimedArcPetriNet")?;
+        writeln!(f, "Places :")?;
+        let mut places : Vec<&Arc<TAPNPlace>> = self.places.iter().collect();
+        places.sort_by(|p1, p2| p1.name.cmp(&p2.name));
+        for place in places {
+            writeln!(f, "  {} (invariant {})", place.name, place.invariant)?;
+        }
+        write!(f, "Transitions :")?;
+        let mut transitions : Vec<&Arc<TAPNTransition>> = self.transitions.iter().collect();
+        transitions.sort_by(|t1, t2| t1.label.cmp(&t2.label));
+        for transition in transitions {
+            let inputs = transition.input_edges.read().unwrap().iter().map(|e| {
+                format!("{} {} x{}", e.get_node_from().name, e.data().interval, e.data().weight)
+            }).collect();
+            let outputs = transition.output_edges.read().unwrap().iter().map(|e| {
+                format!("{} x{}", e.get_node_to().name, e.data().weight)
+            }).collect();
+            write!(f, "\n  {} : [{}] -> [{}]", transition.label, sorted(inputs), sorted(outputs))?;
+            let inhibitors : Vec<String> = transition.inhibitors.read().unwrap().iter().map(|e| {
+                format!("{} {} x{}", e.get_node_from().name, e.data().interval, e.data().weight)
+            }).collect();
+            if !inhibitors.is_empty() {
+                write!(f, " | inhibitors {}", sorted(inhibitors))?;
+            }
+            let transports : Vec<String> = transition.transports.read().unwrap().iter().map(|e| {
+                format!("{} -> {} {} x{}", e.get_node_from().name, e.get_node_to().name, e.data().interval, e.data().weight)
+            }).collect();
+            if !transports.is_empty() {
+                write!(f, " | transports {}", sorted(transports))?;
+            }
+            if !transition.controllable {
+                write!(f, " uncontrollable")?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Model for TAPN {
@@ -71,11 +141,19 @@ impl Model for TAPN {
         }
     }
 
-    fn next(&self, mut state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
-        let storage = state.mut_storage(&self.storage_index);
-        let mut placeList = TAPNPlaceListAccessor::from(storage);
+    // Tokens consumed are the first fireable combination, runs drawing them uniformly instead (see sample_next)
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        let transition = self.transitions.iter().find(|t| t.get_action() == action)?;
+        let next_state = self.fire_tokens(state, transition, None)?;
+        let actions = self.available_actions(&next_state);
+        Some((next_state, actions))
+    }
 
-        None
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        let transition = self.transitions.iter().find(|t| t.get_action() == action)?;
+        let next_state = self.fire_tokens(state, transition, Some(rng))?;
+        let actions = self.available_actions(&next_state);
+        Some((next_state, actions))
     }
 
     fn delay(&self, mut state : ModelState, dt : ClockValue) -> Option<ModelState> {
@@ -85,12 +163,33 @@ impl Model for TAPN {
         Some(state)
     }
 
-    fn random_next(&self, state : ModelState) -> (Option<ModelState>, ClockValue, Option<Action>) {
-        (None, ClockValue::zero(), None)
+    // Transitions race, each one firing at the date sampled for it
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        let mut storage = state.storage(&self.storage_index).clone();
+        let earliest = self.transitions.iter().filter_map(|t| {
+            t.sample_date(TAPNPlaceListAccessor::from(&mut storage), rng).map(|date| (t, date))
+        }).min_by(|(_, d1), (_, d2)| d1.partial_cmp(d2).unwrap());
+        let Some((transition, date)) = earliest else {
+            return (Some(state), ClockValue::zero(), None);
+        };
+        let action = transition.get_action();
+        let Some(delayed) = self.delay(state, date) else {
+            return (None, date, Some(action));
+        };
+        let next = self.sample_next(delayed, action.clone(), rng).map(|(s, _)| s);
+        (next, date, Some(action))
     }
 
+    // Fireable transitions of the highest priority
     fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
-        HashSet::new()
+        let mut storage = state.storage(&self.storage_index).clone();
+        let fireable : Vec<&Arc<TAPNTransition>> = self.transitions.iter().filter(|t| {
+            t.is_fireable(TAPNPlaceListAccessor::from(&mut storage))
+        }).collect();
+        let Some(priority) = fireable.iter().map(|t| t.priority).max() else {
+            return HashSet::new();
+        };
+        fireable.into_iter().filter(|t| t.priority == priority).map(|t| t.get_action()).collect()
     }
 
     fn get_id(&self) -> usize {
@@ -102,7 +201,7 @@ impl Model for TAPN {
     }
 
     fn is_stochastic(&self) -> bool {
-        false
+        self.transitions.iter().any(|t| t.distribution.is_some())
     }
 
     fn init_initial_storage(&self, mut state : ModelState) -> ModelState {
@@ -125,15 +224,18 @@ impl Model for TAPN {
         self.id = context.new_model();
         self.storage_index = context.add_storage();
         let mut compiled_places = Vec::new();
-        for place in self.places.iter() {
+        for (i, place) in self.places.iter().enumerate() {
             let mut compiled_place = TAPNPlace::clone(&place);
+            compiled_place.index = PlaceId(i);
             compiled_place.compile(context)?;
+            context.add_token_place(compiled_place.get_var(), self.storage_index, i);
             compiled_places.push(Arc::new(compiled_place));
         }
         self.places = compiled_places;
         let mut compiled_transitions = Vec::new();
-        for transi in self.transitions.iter() {
+        for (i, transi) in self.transitions.iter().enumerate() {
             let mut compiled_transition = TAPNTransition::clone(&transi);
+            compiled_transition.index = TransitionId(i);
             compiled_transition.compile(context)?;
             compiled_transitions.push(Arc::new(compiled_transition));
         }
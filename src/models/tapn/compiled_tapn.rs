@@ -0,0 +1,113 @@
+use std::{collections::HashSet, ops::Deref, sync::Arc};
+
+use crate::computation::random::SimulationRng;
+
+use crate::models::{action::Action, model_context::ModelContext, time::ClockValue, CompilationError, CompilationResult, Model, ModelMaker, ModelMeta, ModelState, PlaceId, TransitionId};
+
+use super::{tapn_token::TAPNPlaceList, TAPN};
+
+/// Read-only handle on a compiled timed-arcs Petri net, obtained from `TAPN::into_compiled`.
+/// Like CompiledPetriNet, the net and its context are shared between clones, and firing is only exposed here.
+#[derive(Clone)]
+pub struct CompiledTAPN {
+    net : Arc<TAPN>,
+    context : Arc<ModelContext>,
+}
+
+impl CompiledTAPN {
+
+    pub fn context(&self) -> &ModelContext {
+        &self.context
+    }
+
+    pub fn net(&self) -> &TAPN {
+        &self.net
+    }
+
+    pub fn fire(&self, state : ModelState, transi : TransitionId, in_tokens : TAPNPlaceList) -> (ModelState, HashSet<PlaceId>) {
+        self.net.fire(state, transi, in_tokens)
+    }
+
+}
+
+impl TAPN {
+
+    pub fn into_compiled(mut self, context : &mut ModelContext) -> CompilationResult<CompiledTAPN> {
+        self.compile(context)?;
+        Ok(CompiledTAPN {
+            net : Arc::new(self),
+            context : Arc::new(context.clone())
+        })
+    }
+
+}
+
+impl Deref for CompiledTAPN {
+    type Target = TAPN;
+
+    fn deref(&self) -> &TAPN {
+        &self.net
+    }
+}
+
+impl Model for CompiledTAPN {
+
+    fn get_meta() -> ModelMeta {
+        TAPN::get_meta()
+    }
+
+    fn next(&self, state : ModelState, action : Action) -> Option<(ModelState, HashSet<Action>)> {
+        self.net.next(state, action)
+    }
+
+    fn sample_next(&self, state : ModelState, action : Action, rng : &mut SimulationRng) -> Option<(ModelState, HashSet<Action>)> {
+        self.net.sample_next(state, action, rng)
+    }
+
+    fn delay(&self, state : ModelState, dt : ClockValue) -> Option<ModelState> {
+        self.net.delay(state, dt)
+    }
+
+    fn random_next(&self, state : ModelState, rng : &mut SimulationRng) -> (Option<ModelState>, ClockValue, Option<Action>) {
+        self.net.random_next(state, rng)
+    }
+
+    fn available_actions(&self, state : &ModelState) -> HashSet<Action> {
+        self.net.available_actions(state)
+    }
+
+    fn get_id(&self) -> usize {
+        self.net.get_id()
+    }
+
+    fn is_timed(&self) -> bool {
+        self.net.is_timed()
+    }
+
+    fn is_stochastic(&self) -> bool {
+        self.net.is_stochastic()
+    }
+
+    fn init_initial_storage(&self, state : ModelState) -> ModelState {
+        self.net.init_initial_storage(state)
+    }
+
+    // The handle is immutable : it is already bound to its context, and can't be compiled again
+    fn compile(&mut self, _ : &mut ModelContext) -> CompilationResult<()> {
+        Err(CompilationError)
+    }
+
+}
+
+// A compiled net is its own maker : threads get clones of the shared handle instead of recompiled nets
+impl ModelMaker<CompiledTAPN> for CompiledTAPN {
+
+    fn create_maker(model : CompiledTAPN) -> Self {
+        model
+    }
+
+    fn make(&self) -> (CompiledTAPN, ModelContext) {
+        (self.clone(), ModelContext::clone(&self.context))
+    }
+
+}
@@ -2,7 +2,7 @@ use std::{fmt, sync::{Arc, RwLock, Weak}};
 
 use serde::{Serialize, Deserialize};
 
-use crate::models::{model_context::ModelContext, model_var::{ModelVar, VarType}, time::TimeBound, CompilationResult, Label, ModelState, Node};
+use crate::models::{model_context::ModelContext, model_var::{ModelVar, VarType}, time::TimeBound, CompilationResult, Label, ModelState, Node, PlaceId};
 
 use super::{tapn_transition::TAPNTransition, TAPNTokenListAccessor};
 
@@ -15,7 +15,7 @@ pub struct TAPNPlace {
     pub invariant : TimeBound,
     
     #[serde(skip)]
-    pub index : usize,
+    pub index : PlaceId,
 
     #[serde(skip)]
     in_transitions : RwLock<Vec<Weak<TAPNTransition>>>,
@@ -33,7 +33,7 @@ impl TAPNPlace {
         TAPNPlace {
             name : lbl,
             invariant : TimeBound::Infinite,
-            index : 0,
+            index : PlaceId(0),
             in_transitions : RwLock::new(Vec::new()),
             out_transitions : RwLock::new(Vec::new()),
             data_variable : Default::default()
@@ -44,7 +44,7 @@ impl TAPNPlace {
         TAPNPlace {
             name : lbl,
             invariant : inv,
-            index : 0,
+            index : PlaceId(0),
             in_transitions : RwLock::new(Vec::new()),
             out_transitions : RwLock::new(Vec::new()),
             data_variable : Default::default()
@@ -2,7 +2,9 @@
 
 use std::fmt::{write, Display};
 
-use crate::models::{model_storage::ModelStorage, time::ClockValue};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{model_context::ModelContext, model_storage::ModelStorage, model_var::{MappingError, MappingResult, ModelVar}, time::ClockValue, Label};
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq)]
 pub struct TAPNToken {
@@ -219,4 +221,50 @@ impl<'a> From<&'a mut ModelStorage> for TAPNPlaceListAccessor<'a> {
         let vec = vec.iter_mut().map(|x| TAPNTokenListAccessor::from(x) ).collect();
         TAPNPlaceListAccessor { places : vec }
     }
-}
\ No newline at end of file
+}
+
+/// Place of a timed-arc Petri net referenced by a query : its marking variable, and where its tokens are
+/// kept in the storages of a state, for expressions on their ages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TokenPlace {
+    pub var : ModelVar,
+    pub storage : usize,
+    pub index : usize,
+}
+
+impl TokenPlace {
+
+    pub fn name(name : Label) -> Self {
+        TokenPlace { var : ModelVar::name(name), storage : 0, index : 0 }
+    }
+
+    pub fn apply_to(&self, ctx : &ModelContext) -> MappingResult<TokenPlace> {
+        let var = self.var.apply_to(ctx)?;
+        match ctx.get_token_place(&var) {
+            Some((storage, index)) => Ok(TokenPlace { var, storage, index }),
+            None => Err(MappingError(Label::from(format!("{} (not a timed-arc place)", self.var.get_name()))))
+        }
+    }
+
+    // Tokens of the place, sorted by increasing age, read from the TAPNPlaceList storage of the net
+    pub fn tokens(&self, storages : &[ModelStorage]) -> TAPNTokenList {
+        match storages.get(self.storage) {
+            Some(storage) if storage.is_vec() => match storage.ref_vec().get(self.index) {
+                Some(tokens) => TAPNTokenList::from(tokens.clone()),
+                None => Vec::new()
+            },
+            _ => Vec::new()
+        }
+    }
+
+    // Age of the oldest token, 0 if the place is empty
+    pub fn oldest_age(tokens : &TAPNTokenList) -> f64 {
+        tokens.last().map(|t| t.age.float()).unwrap_or(0.0)
+    }
+
+    // Number of tokens whose age is in [low, high]
+    pub fn aged_tokens(tokens : &TAPNTokenList, low : i32, high : i32) -> i32 {
+        tokens.iter().filter(|t| t.age.float() >= low as f64 && t.age.float() <= high as f64).map(|t| t.count).sum()
+    }
+
+}
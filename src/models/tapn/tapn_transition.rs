@@ -5,15 +5,16 @@ use std::sync::{Arc, RwLock};
 use serde::{Deserialize, Serialize};
 
 use crate::computation::combinatory::{CartesianProduct, KInVec};
-use crate::computation::intervals::{ContinuousSet, Convex};
+use crate::computation::intervals::{Convex, Disjoint};
 use crate::models::action::Action;
 use crate::models::model_clock::ModelClock;
 use crate::models::model_context::ModelContext;
-use crate::models::time::{ClockValue, TimeInterval};
-use crate::models::{CompilationError, CompilationResult, Edge, Label, ModelState, Node};
+use crate::computation::random::SimulationRng;
+use crate::models::time::{ClockValue, RealDistribution, TimeInterval};
+use crate::models::{CompilationError, CompilationResult, Edge, Label, ModelState, Node, TransitionId};
 
 use super::tapn_place::TAPNPlace;
-use super::{tapn_edge::*, TAPNPlaceList, TAPNPlaceListAccessor, TAPNToken, TAPNTokenList, TAPNTokenListAccessor};
+use super::{tapn_edge::*, TAPNPlaceList, TAPNPlaceListAccessor, TAPNToken, TAPNTokenAccessor, TAPNTokenList, TAPNTokenListAccessor};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TAPNTransition {
@@ -22,8 +23,16 @@ pub struct TAPNTransition {
     pub to : Vec<Label>,
     pub controllable : bool,
 
+    // Only fireable transitions of the highest priority can fire
+    #[serde(default)]
+    pub priority : i32,
+
+    // Distribution of the firing date when simulating, see sample_date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution : Option<RealDistribution>,
+
     #[serde(skip)]
-    pub index : usize,
+    pub index : TransitionId,
 
     #[serde(skip)]
     pub input_edges : RwLock<Vec<Arc<InputEdge>>>,
@@ -66,6 +75,16 @@ impl TAPNTransition {
         }
     }
 
+    pub fn with_priority(mut self, priority : i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_distribution(mut self, distribution : RealDistribution) -> Self {
+        self.distribution = Some(distribution);
+        self
+    }
+
     pub fn get_inputs(&self) -> Vec<Arc<InputEdge>> {
         self.input_edges.read().unwrap().iter().map(|e| {
             Arc::clone(e)
@@ -113,27 +132,27 @@ impl TAPNTransition {
     fn has_enough(interval : &TimeInterval, weight : i32, token_list : &mut TAPNTokenListAccessor) -> bool {
         let mut remaining = weight;
         for token in token_list.tokens() {
+            if remaining <= 0 {
+                break;
+            }
             if interval.contains(&token.get_age()) {
                 remaining -= *token.count;
-                if remaining <= 0 {
-                    return false;
-                }
             }
         }
-        true
+        remaining <= 0
     }
 
     pub fn is_fireable(&self, mut place_list : TAPNPlaceListAccessor) -> bool {
         for inhib in self.inhibitors.read().unwrap().iter() {
             let place_index = inhib.get_node_from().index;
-            let token_list = &mut place_list.places[place_index];
+            let token_list = &mut place_list.places[place_index.index()];
             if Self::has_enough(&inhib.data().interval, inhib.data().weight, token_list) {
                 return false;
             }
         }
         for edge in self.input_edges.read().unwrap().iter() {
             let place_index = edge.get_node_from().index;
-            let token_list = &mut place_list.places[place_index];
+            let token_list = &mut place_list.places[place_index.index()];
             if !Self::has_enough(&edge.data().interval, edge.data().weight, token_list) {
                 return false;
             }
@@ -142,7 +161,7 @@ impl TAPNTransition {
             let place_index = edge.get_node_from().index;
             let mut interval = edge.data().interval.clone();
             interval.1 = min(interval.1, edge.get_node_to().invariant);
-            let token_list = &mut place_list.places[place_index];
+            let token_list = &mut place_list.places[place_index.index()];
             if !Self::has_enough(&interval, edge.data().weight, token_list) {
                 return false;
             }
@@ -182,7 +201,7 @@ impl TAPNTransition {
         let mut places_index = Vec::new();
         for inhib in self.inhibitors.read().unwrap().iter() {
             let place_index = inhib.get_node_from().index;
-            let token_list = &mut place_list.places[place_index];
+            let token_list = &mut place_list.places[place_index.index()];
             if Self::has_enough(&inhib.data().interval, inhib.data().weight, token_list) {
                 return Vec::new();
             }
@@ -190,7 +209,7 @@ impl TAPNTransition {
         for edge in self.input_edges.read().unwrap().iter() {
             let place_index = edge.get_node_from().index;
             places_index.push(place_index);
-            let token_list = &mut place_list.places[place_index];
+            let token_list = &mut place_list.places[place_index.index()];
             let combinations = Self::combinations_for(&edge.data().interval, edge.data().weight as usize, token_list);
             if combinations.len() == 0 {
                 return Vec::new();
@@ -200,7 +219,7 @@ impl TAPNTransition {
         for edge in self.transports.read().unwrap().iter() {
             let place_index = edge.get_node_from().index;
             places_index.push(place_index);
-            let token_list = &mut place_list.places[place_index];
+            let token_list = &mut place_list.places[place_index.index()];
             let mut interval = edge.data().interval.clone();
             interval.1 = min(interval.1, edge.get_node_to().invariant);
             let combinations = Self::combinations_for(&interval, edge.data().weight as usize, token_list);
@@ -213,30 +232,41 @@ impl TAPNTransition {
             let mut input_places = TAPNPlaceList::places(place_list.n_places());
             for (i, token_list) in tuple.into_iter().enumerate() {
                 let place_index = places_index[i];
-                input_places.places[place_index] = token_list.clone();
+                input_places.places[place_index.index()] = token_list.clone();
             }
             res.push(input_places);
         }
         res
     }
 
-    fn arc_dates(interval : &TimeInterval, weight : usize, token_list : &mut TAPNTokenListAccessor) -> ContinuousSet<ClockValue, TimeInterval> {
-        let mut dates = ContinuousSet::EmptySet;
-        let mut first_index : usize = 0;
-        let mut consumed : usize = 0;
-        let list_len = token_list.tokens.len();
-        while first_index < list_len {
-            let i = first_index;
-            
+    // Delays after which enough tokens have their age in the interval. Tokens are sorted by increasing age, so that
+    // those in the interval are consecutive : it holds from the youngest entering it to the oldest leaving it
+    fn arc_dates(interval : &TimeInterval, weight : usize, token_list : &mut TAPNTokenListAccessor) -> Disjoint<f64, (f64, f64)> {
+        if weight == 0 {
+            return (0.0, f64::INFINITY).into();
+        }
+        let (low, high) = (interval.0.float(), interval.1.float());
+        let tokens : Vec<TAPNToken> = token_list.tokens().iter().map(TAPNTokenAccessor::get).collect();
+        let mut dates = Disjoint::new();
+        for (i, youngest) in tokens.iter().enumerate() {
+            let mut count : usize = 0;
+            for oldest in tokens.iter().skip(i) {
+                count += oldest.count as usize;
+                if count >= weight {
+                    dates = dates.union((f64::max(0.0, low - youngest.age.float()), high - oldest.age.float()));
+                    break;
+                }
+            }
         }
         dates
     }
 
-    pub fn firing_dates(&self, mut place_list : TAPNPlaceListAccessor) -> ContinuousSet<ClockValue, TimeInterval> {
-        let mut dates = ContinuousSet::full();
+    // Delays after which the transition can fire, given the ages of the tokens. Strict bounds are taken as large ones
+    pub fn firing_dates(&self, mut place_list : TAPNPlaceListAccessor) -> Disjoint<f64, (f64, f64)> {
+        let mut dates : Disjoint<f64, (f64, f64)> = (0.0, f64::INFINITY).into();
         for edge in self.inhibitors.read().unwrap().iter() {
             let place_index = edge.get_node_from().index;
-            let tokens = &mut place_list.places[place_index];
+            let tokens = &mut place_list.places[place_index.index()];
             let intervals = Self::arc_dates(&edge.data().interval, edge.data().weight as usize, tokens);
             dates = dates.difference(intervals);
             if dates.is_empty() {
@@ -245,7 +275,7 @@ impl TAPNTransition {
         }
         for edge in self.input_edges.read().unwrap().iter() {
             let place_index = edge.get_node_from().index;
-            let tokens = &mut place_list.places[place_index];
+            let tokens = &mut place_list.places[place_index.index()];
             let intervals = Self::arc_dates(&edge.data().interval, edge.data().weight as usize, tokens);
             dates = dates.intersection(intervals);
             if dates.is_empty() {
@@ -255,7 +285,7 @@ impl TAPNTransition {
         for edge in self.transports.read().unwrap().iter() {
             let place_index = edge.get_node_from().index;
             let target_inv = TimeInterval::invariant(edge.get_node_to().invariant);
-            let tokens = &mut place_list.places[place_index];
+            let tokens = &mut place_list.places[place_index.index()];
             let interval = edge.data().interval.clone().intersection(target_inv);
             let intervals = Self::arc_dates(&interval, edge.data().weight as usize, tokens);
             dates = dates.intersection(intervals);
@@ -266,6 +296,24 @@ impl TAPNTransition {
         dates
     }
 
+    // Delay before firing : drawn from the distribution of the transition, then postponed to the next date it can fire,
+    // or brought back to the last one. Without distribution, it is uniform within the first firing window, or past its
+    // start by an exponential delay of rate 1 when unbounded. None if the transition can't fire anymore
+    pub fn sample_date(&self, place_list : TAPNPlaceListAccessor, rng : &mut SimulationRng) -> Option<ClockValue> {
+        let dates = self.firing_dates(place_list);
+        let (first_low, first_high) = dates.intervals.iter().cloned().reduce(|a, b| if a.0 <= b.0 { a } else { b })?;
+        let date = match self.distribution {
+            None if first_high.is_infinite() => first_low + RealDistribution::Exponential(1.0).sample(rng),
+            None => RealDistribution::Uniform(first_low, first_high).sample(rng),
+            Some(distribution) => {
+                let sampled = distribution.sample(rng);
+                let next = dates.intervals.iter().filter(|(_, high)| *high >= sampled).map(|(low, _)| low.max(sampled)).reduce(f64::min);
+                next.unwrap_or_else(|| dates.intervals.iter().map(|(_, high)| *high).fold(0.0, f64::max))
+            }
+        };
+        Some(ClockValue::from(date))
+    }
+
     pub fn clear_edges(&self) {
         self.input_edges.write().unwrap().clear();
         self.output_edges.write().unwrap().clear();
@@ -323,6 +371,8 @@ impl Clone for TAPNTransition {
             from: self.from.clone(),
             to: self.to.clone(),
             controllable : self.controllable.clone(),
+            priority : self.priority,
+            distribution : self.distribution,
             index : self.index,
             ..Default::default()
         }
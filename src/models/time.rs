@@ -1,7 +1,9 @@
 mod time_bound;
 mod clock_value;
 mod time_interval;
+mod real_distribution;
 pub use clock_value::ClockValue;
 pub use time_bound::TimeBound;
 pub use time_interval::TimeInterval;
+pub use real_distribution::RealDistribution;
 
@@ -0,0 +1,72 @@
+use std::{f64::consts::PI, fmt};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::computation::random::SimulationRng;
+
+/// Distribution of a positive real delay, sampled with the simulation generator
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RealDistribution {
+    Deterministic(f64),
+    Uniform(f64, f64),
+    Exponential(f64), // Rate
+    Erlang(u32, f64), // Shape, rate of each phase
+    Normal(f64, f64), // Mean, deviation, truncated to positive values
+}
+
+use RealDistribution::*;
+
+impl RealDistribution {
+
+    // Deterministic delays take no draw
+    pub fn sample(&self, rng : &mut SimulationRng) -> f64 {
+        match *self {
+            Deterministic(x) => x,
+            Uniform(a, b) if a >= b => a,
+            Uniform(a, b) => rng.gen_range(a..b),
+            Exponential(rate) => -(1.0 - rng.gen::<f64>()).ln() / rate,
+            Erlang(k, rate) => (0..k).map(|_| -(1.0 - rng.gen::<f64>()).ln() / rate).sum(),
+            Normal(mean, deviation) => loop {
+                let (u, v) : (f64, f64) = (rng.gen(), rng.gen());
+                let x = mean + deviation * (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * PI * v).cos();
+                if x >= 0.0 {
+                    break x;
+                }
+            }
+        }
+    }
+
+    // Mean of the distribution, not accounting for the truncation of normal delays
+    pub fn mean(&self) -> f64 {
+        match *self {
+            Deterministic(x) => x,
+            Uniform(a, b) => (a + b) / 2.0,
+            Exponential(rate) => 1.0 / rate,
+            Erlang(k, rate) => k as f64 / rate,
+            Normal(mean, _) => mean
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Deterministic(x) => x >= 0.0,
+            Uniform(a, b) => 0.0 <= a && a <= b,
+            Exponential(rate) | Erlang(_, rate) => rate > 0.0,
+            Normal(_, deviation) => deviation >= 0.0
+        }
+    }
+
+}
+
+impl fmt::Display for RealDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Deterministic(x) => write!(f, "Det({})", x),
+            Uniform(a, b) => write!(f, "U({}, {})", a, b),
+            Exponential(rate) => write!(f, "Exp({})", rate),
+            Erlang(k, rate) => write!(f, "Erlang({}, {})", k, rate),
+            Normal(mean, deviation) => write!(f, "N({}, {})", mean, deviation),
+        }
+    }
+}
@@ -3,7 +3,7 @@ use num_traits::{Bounded, One};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::computation::intervals::{Convex, Delta, Disjoint, Measurable, ToPositive};
+use crate::computation::{intervals::{Convex, Delta, Disjoint, Measurable, ToPositive}, random::SimulationRng};
 
 use super::{TimeBound, ClockValue};
 
@@ -17,8 +17,7 @@ pub struct TimeInterval(pub TimeBound, pub TimeBound);
 
 impl TimeInterval {
 
-    pub fn random_date(&self) -> ClockValue {
-        let mut gen = rand::thread_rng();
+    pub fn random_date(&self, rng : &mut SimulationRng) -> ClockValue {
         if self.is_empty() {
             return ClockValue::disabled();
         }
@@ -32,9 +31,9 @@ impl TimeInterval {
             Large(x) | Strict(x) => x as f64,
             Infinite => f64::INFINITY
         };
-        let mut chosen = ClockValue::from(gen.gen_range(low..high));
+        let mut chosen = ClockValue::from(rng.gen_range(low..high));
         while !self.contains(&chosen) {
-            chosen = ClockValue::from(gen.gen_range(low..high)); // If on strict bound
+            chosen = ClockValue::from(rng.gen_range(low..high)); // If on strict bound
         }
         chosen
     }
@@ -0,0 +1,126 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::solution::TimedTrace;
+
+use super::Label;
+
+mod finite_automaton;
+mod regular_expression;
+
+pub use finite_automaton::{DFA, NFA};
+pub use regular_expression::RegularExpression;
+
+/// Letter of a weighted timed word : an action taken after a delay, at a cost
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightedLetter {
+    pub action : Label,
+    pub delay : f64,
+    pub cost : f64,
+}
+
+/// Weighted timed word, as produced by a run of a timed model along with the costs of its actions.
+/// Words without costs are timed words, words without delays are cost words.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct WeightedWord {
+    pub letters : Vec<WeightedLetter>,
+}
+
+impl WeightedWord {
+
+    pub fn new() -> Self {
+        WeightedWord { letters : Vec::new() }
+    }
+
+    pub fn push(&mut self, action : Label, delay : f64, cost : f64) {
+        self.letters.push(WeightedLetter { action, delay, cost });
+    }
+
+    pub fn with(mut self, action : Label, delay : f64, cost : f64) -> Self {
+        self.push(action, delay, cost);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    pub fn actions(&self) -> Vec<Label> {
+        self.letters.iter().map(|l| l.action.clone()).collect()
+    }
+
+    pub fn total_time(&self) -> f64 {
+        self.letters.iter().map(|l| l.delay).sum()
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.letters.iter().map(|l| l.cost).sum()
+    }
+
+    // Absolute date of each letter
+    pub fn dates(&self) -> Vec<f64> {
+        self.letters.iter().scan(0.0, |date, l| {
+            *date += l.delay;
+            Some(*date)
+        }).collect()
+    }
+
+    // Cost accumulated after each letter
+    pub fn accumulated_costs(&self) -> Vec<f64> {
+        self.letters.iter().scan(0.0, |cost, l| {
+            *cost += l.cost;
+            Some(*cost)
+        }).collect()
+    }
+
+    // Levenshtein distance between the untimed words : insertions, deletions and substitutions of actions
+    pub fn edit_distance(&self, other : &WeightedWord) -> usize {
+        edit_distance(&self.actions(), &other.actions())
+    }
+
+    // Skorokhod-like distance : words with the same actions are matched letter by letter, and are as far as the
+    // largest shift of a date or of an accumulated cost (weighted by cost_factor). Words with different actions are at infinite distance.
+    pub fn timed_distance(&self, other : &WeightedWord, cost_factor : f64) -> f64 {
+        if self.actions() != other.actions() {
+            return f64::INFINITY;
+        }
+        let dates = self.dates().into_iter().zip(other.dates()).map(|(d1, d2)| (d1 - d2).abs());
+        let costs = self.accumulated_costs().into_iter().zip(other.accumulated_costs()).map(|(c1, c2)| cost_factor * (c1 - c2).abs());
+        dates.chain(costs).fold(0.0, f64::max)
+    }
+
+}
+
+// Levenshtein distance between two sequences
+pub fn edit_distance<T : PartialEq>(a : &[T], b : &[T]) -> usize {
+    let mut previous : Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + (x != y) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl From<&TimedTrace> for WeightedWord {
+    fn from(trace : &TimedTrace) -> Self {
+        WeightedWord {
+            letters : trace.steps.iter().map(|(delay, action)| WeightedLetter { action : action.clone(), delay : *delay, cost : 0.0 }).collect()
+        }
+    }
+}
+
+impl Display for WeightedWord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letters : Vec<String> = self.letters.iter().map(|l| format!("({}) {} [{}]", l.delay, l.action, l.cost)).collect();
+        write!(f, "[{}]", letters.join(" -> "))
+    }
+}
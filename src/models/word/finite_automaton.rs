@@ -0,0 +1,349 @@
+use std::{collections::{hash_map::Entry, BTreeSet, HashMap, VecDeque}, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Label;
+
+use super::WeightedWord;
+
+/// Nondeterministic finite automaton over actions, transitions without letter being epsilon transitions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NFA {
+    pub alphabet : Vec<Label>,
+    pub initial : Vec<usize>,
+    pub accepting : Vec<bool>,
+    pub transitions : Vec<Vec<(Option<Label>, usize)>>,
+}
+
+impl NFA {
+
+    pub fn new(alphabet : Vec<Label>) -> Self {
+        NFA { alphabet, ..Default::default() }
+    }
+
+    pub fn n_states(&self) -> usize {
+        self.accepting.len()
+    }
+
+    pub fn add_state(&mut self, accepting : bool) -> usize {
+        self.accepting.push(accepting);
+        self.transitions.push(Vec::new());
+        self.accepting.len() - 1
+    }
+
+    pub fn add_initial(&mut self, state : usize) {
+        self.initial.push(state);
+    }
+
+    // Letters not in the alphabet are added to it
+    pub fn add_transition(&mut self, from : usize, letter : Option<Label>, to : usize) {
+        if let Some(a) = &letter {
+            if !self.alphabet.contains(a) {
+                self.alphabet.push(a.clone());
+            }
+        }
+        self.transitions[from].push((letter, to));
+    }
+
+    pub fn epsilon_closure(&self, states : impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+        let mut closure : BTreeSet<usize> = BTreeSet::new();
+        let mut to_see : Vec<usize> = states.into_iter().collect();
+        while let Some(q) = to_see.pop() {
+            if !closure.insert(q) {
+                continue;
+            }
+            to_see.extend(self.transitions[q].iter().filter(|(a, _)| a.is_none()).map(|(_, to)| *to));
+        }
+        closure
+    }
+
+    // States reached from the given ones by reading the letter, epsilon transitions included
+    pub fn step(&self, states : &BTreeSet<usize>, letter : &Label) -> BTreeSet<usize> {
+        let targets = states.iter().flat_map(|q| {
+            self.transitions[*q].iter().filter(|(a, _)| a.as_ref() == Some(letter)).map(|(_, to)| *to)
+        });
+        self.epsilon_closure(targets.collect::<Vec<usize>>())
+    }
+
+    pub fn accepts(&self, word : &[Label]) -> bool {
+        let reached = word.iter().fold(self.epsilon_closure(self.initial.clone()), |states, a| self.step(&states, a));
+        reached.iter().any(|q| self.accepting[*q])
+    }
+
+    // Subset construction, restricted to the reachable non-empty subsets
+    pub fn determinize(&self) -> DFA {
+        let mut dfa = DFA::new(self.alphabet.clone());
+        let initial = self.epsilon_closure(self.initial.clone());
+        dfa.accepting[0] = initial.iter().any(|q| self.accepting[*q]);
+        let mut subsets : HashMap<BTreeSet<usize>, usize> = HashMap::from([(initial.clone(), 0)]);
+        let mut to_see = VecDeque::from([initial]);
+        while let Some(subset) = to_see.pop_front() {
+            let from = subsets[&subset];
+            for a in self.alphabet.iter() {
+                let next = self.step(&subset, a);
+                if next.is_empty() {
+                    continue;
+                }
+                let to = match subsets.get(&next) {
+                    Some(to) => *to,
+                    None => {
+                        let to = dfa.add_state(next.iter().any(|q| self.accepting[*q]));
+                        subsets.insert(next.clone(), to);
+                        to_see.push_back(next);
+                        to
+                    }
+                };
+                dfa.add_transition(from, a.clone(), to);
+            }
+        }
+        dfa
+    }
+
+}
+
+/// Deterministic finite automaton over actions. It may be partial, missing transitions leading to rejection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DFA {
+    pub alphabet : Vec<Label>,
+    pub initial : usize,
+    pub accepting : Vec<bool>,
+    pub transitions : Vec<HashMap<Label, usize>>,
+}
+
+impl DFA {
+
+    // Automaton with a single rejecting initial state, 0
+    pub fn new(alphabet : Vec<Label>) -> Self {
+        DFA { alphabet, initial : 0, accepting : vec![false], transitions : vec![HashMap::new()] }
+    }
+
+    pub fn n_states(&self) -> usize {
+        self.accepting.len()
+    }
+
+    pub fn add_state(&mut self, accepting : bool) -> usize {
+        self.accepting.push(accepting);
+        self.transitions.push(HashMap::new());
+        self.accepting.len() - 1
+    }
+
+    // Letters not in the alphabet are added to it
+    pub fn add_transition(&mut self, from : usize, letter : Label, to : usize) {
+        if !self.alphabet.contains(&letter) {
+            self.alphabet.push(letter.clone());
+        }
+        self.transitions[from].insert(letter, to);
+    }
+
+    pub fn next(&self, state : usize, letter : &Label) -> Option<usize> {
+        self.transitions[state].get(letter).copied()
+    }
+
+    pub fn accepts(&self, word : &[Label]) -> bool {
+        word.iter().try_fold(self.initial, |q, a| self.next(q, a)).is_some_and(|q| self.accepting[q])
+    }
+
+    // Untimed acceptance of the actions of the word
+    pub fn accepts_word(&self, word : &WeightedWord) -> bool {
+        self.accepts(&word.actions())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.transitions.iter().all(|t| self.alphabet.iter().all(|a| t.contains_key(a)))
+    }
+
+    pub fn with_alphabet(mut self, alphabet : &[Label]) -> Self {
+        for a in alphabet.iter() {
+            if !self.alphabet.contains(a) {
+                self.alphabet.push(a.clone());
+            }
+        }
+        self
+    }
+
+    // Same language, missing transitions leading to a rejecting sink
+    pub fn complete(&self) -> DFA {
+        let mut complete = self.clone();
+        if self.is_complete() {
+            return complete;
+        }
+        let sink = complete.add_state(false);
+        for t in complete.transitions.iter_mut() {
+            for a in self.alphabet.iter() {
+                t.entry(a.clone()).or_insert(sink);
+            }
+        }
+        complete
+    }
+
+    // Complement relative to the words over the alphabet of the automaton
+    pub fn complement(&self) -> DFA {
+        let mut complement = self.complete();
+        for accepting in complement.accepting.iter_mut() {
+            *accepting = !*accepting;
+        }
+        complement
+    }
+
+    // Synchronous product over the union of the alphabets, a pair of states accepting according to the combination
+    // of the acceptance of its components
+    pub fn product(&self, other : &DFA, combine : impl Fn(bool, bool) -> bool) -> DFA {
+        let left = self.clone().with_alphabet(&other.alphabet).complete();
+        let right = other.clone().with_alphabet(&self.alphabet).complete();
+        let mut product = DFA::new(left.alphabet.clone());
+        product.accepting[0] = combine(left.accepting[left.initial], right.accepting[right.initial]);
+        let mut pairs : HashMap<(usize, usize), usize> = HashMap::from([((left.initial, right.initial), 0)]);
+        let mut to_see = VecDeque::from([(left.initial, right.initial)]);
+        while let Some((p, q)) = to_see.pop_front() {
+            let from = pairs[&(p, q)];
+            for a in left.alphabet.iter() {
+                let next = (left.transitions[p][a], right.transitions[q][a]);
+                let to = match pairs.get(&next) {
+                    Some(to) => *to,
+                    None => {
+                        let to = product.add_state(combine(left.accepting[next.0], right.accepting[next.1]));
+                        pairs.insert(next, to);
+                        to_see.push_back(next);
+                        to
+                    }
+                };
+                product.add_transition(from, a.clone(), to);
+            }
+        }
+        product
+    }
+
+    pub fn intersection(&self, other : &DFA) -> DFA {
+        self.product(other, |a, b| a && b)
+    }
+
+    pub fn union(&self, other : &DFA) -> DFA {
+        self.product(other, |a, b| a || b)
+    }
+
+    // Shortest accepted word, by breadth first search. None if the language is empty
+    pub fn shortest_word(&self) -> Option<Vec<Label>> {
+        let mut parents : HashMap<usize, Option<(usize, Label)>> = HashMap::from([(self.initial, None)]);
+        let mut to_see = VecDeque::from([self.initial]);
+        while let Some(q) = to_see.pop_front() {
+            if self.accepting[q] {
+                let mut word = Vec::new();
+                let mut current = q;
+                while let Some(Some((parent, a))) = parents.get(&current) {
+                    word.push(a.clone());
+                    current = *parent;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for a in self.alphabet.iter() {
+                let Some(next) = self.next(q, a) else {
+                    continue;
+                };
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(Some((q, a.clone())));
+                    to_see.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shortest_word().is_none()
+    }
+
+    // Shortest word accepted by this automaton but not by the other. None if the language is included in the other one
+    pub fn inclusion_counterexample(&self, other : &DFA) -> Option<Vec<Label>> {
+        self.product(other, |a, b| a && !b).shortest_word()
+    }
+
+    pub fn is_included_in(&self, other : &DFA) -> bool {
+        self.inclusion_counterexample(other).is_none()
+    }
+
+    pub fn is_equivalent(&self, other : &DFA) -> bool {
+        self.product(other, |a, b| a != b).is_empty()
+    }
+
+    fn reachable_states(&self) -> Vec<usize> {
+        let mut seen = vec![false ; self.n_states()];
+        seen[self.initial] = true;
+        let mut reachable = vec![self.initial];
+        let mut i = 0;
+        while i < reachable.len() {
+            for (_, next) in self.transitions[reachable[i]].iter() {
+                if !seen[*next] {
+                    seen[*next] = true;
+                    reachable.push(*next);
+                }
+            }
+            i += 1;
+        }
+        reachable
+    }
+
+    // Minimal complete automaton of the language, by refinement of the partition between accepting and rejecting
+    // states, until states of a class can't be distinguished by their successors
+    pub fn minimize(&self) -> DFA {
+        let complete = self.complete();
+        let states = complete.reachable_states();
+        let mut classes : HashMap<usize, usize> = states.iter().map(|q| (*q, complete.accepting[*q] as usize)).collect();
+        let mut n_classes = 0;
+        loop {
+            let mut signatures : HashMap<(usize, Vec<usize>), usize> = HashMap::new();
+            let mut refined = HashMap::new();
+            for q in states.iter() {
+                let successors = complete.alphabet.iter().map(|a| classes[&complete.transitions[*q][a]]).collect();
+                let n = signatures.len();
+                let class = *signatures.entry((classes[q], successors)).or_insert(n);
+                refined.insert(*q, class);
+            }
+            classes = refined;
+            if signatures.len() == n_classes {
+                break;
+            }
+            n_classes = signatures.len();
+        }
+        let mut minimal = DFA {
+            alphabet : complete.alphabet.clone(),
+            initial : classes[&complete.initial],
+            accepting : vec![false ; n_classes],
+            transitions : vec![HashMap::new() ; n_classes],
+        };
+        for q in states.iter() {
+            minimal.accepting[classes[q]] = complete.accepting[*q];
+            for (a, next) in complete.transitions[*q].iter() {
+                minimal.transitions[classes[q]].insert(a.clone(), classes[next]);
+            }
+        }
+        minimal
+    }
+
+}
+
+impl From<&DFA> for NFA {
+    fn from(dfa : &DFA) -> Self {
+        NFA {
+            alphabet : dfa.alphabet.clone(),
+            initial : vec![dfa.initial],
+            accepting : dfa.accepting.clone(),
+            transitions : dfa.transitions.iter().map(|t| t.iter().map(|(a, to)| (Some(a.clone()), *to)).collect()).collect(),
+        }
+    }
+}
+
+impl fmt::Display for DFA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let alphabet : Vec<String> = self.alphabet.iter().map(Label::to_string).collect();
+        writeln!(f, "DFA over {{{}}}", alphabet.join(", "))?;
+        for (q, t) in self.transitions.iter().enumerate() {
+            let initial = if q == self.initial { "->" } else { "  " };
+            let accepting = if self.accepting[q] { "*" } else { " " };
+            let mut edges : Vec<String> = t.iter().map(|(a, to)| format!("{} -> {}", a, to)).collect();
+            edges.sort();
+            writeln!(f, "{} {}{} : {}", initial, q, accepting, edges.join(", "))?;
+        }
+        Ok(())
+    }
+}
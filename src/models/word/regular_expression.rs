@@ -0,0 +1,207 @@
+use std::{fmt, iter::Peekable, str::CharIndices};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Label;
+
+use super::{DFA, NFA};
+
+use RegularExpression::*;
+
+/// Regular expression over actions. In text, letters are identifiers separated by spaces, as in "a (b | c)* d+",
+/// "()" standing for the empty word, "∅" for the empty language and "?" for an optional part
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RegularExpression {
+    Empty,
+    Epsilon,
+    Letter(Label),
+    Concat(Box<RegularExpression>, Box<RegularExpression>),
+    Union(Box<RegularExpression>, Box<RegularExpression>),
+    Star(Box<RegularExpression>),
+}
+
+impl RegularExpression {
+
+    pub fn letter(name : &str) -> Self {
+        Letter(Label::from(name))
+    }
+
+    pub fn then(self, other : RegularExpression) -> Self {
+        Concat(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other : RegularExpression) -> Self {
+        Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn star(self) -> Self {
+        Star(Box::new(self))
+    }
+
+    pub fn parse(text : &str) -> Result<Self, String> {
+        let mut chars = text.char_indices().peekable();
+        let expression = parse_union(&mut chars)?;
+        match chars.next() {
+            None => Ok(expression),
+            Some((i, c)) => Err(format!("Unexpected '{}' at {}", c, i))
+        }
+    }
+
+    pub fn alphabet(&self) -> Vec<Label> {
+        let mut letters = Vec::new();
+        self.collect_letters(&mut letters);
+        letters
+    }
+
+    fn collect_letters(&self, letters : &mut Vec<Label>) {
+        match self {
+            Letter(a) if !letters.contains(a) => letters.push(a.clone()),
+            Concat(e1, e2) | Union(e1, e2) => {
+                e1.collect_letters(letters);
+                e2.collect_letters(letters);
+            },
+            Star(e) => e.collect_letters(letters),
+            _ => ()
+        }
+    }
+
+    // Thompson construction : one initial and one accepting state per subexpression, linked by epsilon transitions
+    pub fn to_nfa(&self) -> NFA {
+        let mut nfa = NFA::new(self.alphabet());
+        let (start, end) = self.build(&mut nfa);
+        nfa.add_initial(start);
+        nfa.accepting[end] = true;
+        nfa
+    }
+
+    fn build(&self, nfa : &mut NFA) -> (usize, usize) {
+        let start = nfa.add_state(false);
+        let end = nfa.add_state(false);
+        match self {
+            Empty => (),
+            Epsilon => nfa.add_transition(start, None, end),
+            Letter(a) => nfa.add_transition(start, Some(a.clone()), end),
+            Concat(e1, e2) => {
+                let (s1, e1) = e1.build(nfa);
+                let (s2, e2) = e2.build(nfa);
+                nfa.add_transition(start, None, s1);
+                nfa.add_transition(e1, None, s2);
+                nfa.add_transition(e2, None, end);
+            },
+            Union(e1, e2) => {
+                for e in [e1, e2] {
+                    let (s, e) = e.build(nfa);
+                    nfa.add_transition(start, None, s);
+                    nfa.add_transition(e, None, end);
+                }
+            },
+            Star(e) => {
+                let (s, e) = e.build(nfa);
+                nfa.add_transition(start, None, end);
+                nfa.add_transition(start, None, s);
+                nfa.add_transition(e, None, s);
+                nfa.add_transition(e, None, end);
+            }
+        }
+        (start, end)
+    }
+
+    // Minimal DFA of the language
+    pub fn to_dfa(&self) -> DFA {
+        self.to_nfa().determinize().minimize()
+    }
+
+}
+
+type Chars<'a> = Peekable<CharIndices<'a>>;
+
+fn skip_spaces(chars : &mut Chars) {
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+}
+
+fn is_letter_char(c : char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+// union := concat ("|" concat)*
+fn parse_union(chars : &mut Chars) -> Result<RegularExpression, String> {
+    let mut expression = parse_concat(chars)?;
+    skip_spaces(chars);
+    while chars.next_if(|(_, c)| *c == '|').is_some() {
+        expression = expression.or(parse_concat(chars)?);
+        skip_spaces(chars);
+    }
+    Ok(expression)
+}
+
+// concat := postfix*, the empty concatenation being the empty word
+fn parse_concat(chars : &mut Chars) -> Result<RegularExpression, String> {
+    let mut factors = Vec::new();
+    loop {
+        skip_spaces(chars);
+        match chars.peek() {
+            Some((_, c)) if is_letter_char(*c) || *c == '(' || *c == '∅' => factors.push(parse_postfix(chars)?),
+            _ => break
+        }
+    }
+    Ok(factors.into_iter().reduce(RegularExpression::then).unwrap_or(Epsilon))
+}
+
+// postfix := atom ("*" | "+" | "?")*
+fn parse_postfix(chars : &mut Chars) -> Result<RegularExpression, String> {
+    let mut expression = parse_atom(chars)?;
+    while let Some((_, c)) = chars.next_if(|(_, c)| matches!(c, '*' | '+' | '?')) {
+        expression = match c {
+            '*' => expression.star(),
+            '+' => expression.clone().then(expression.star()),
+            _ => expression.or(Epsilon)
+        };
+    }
+    Ok(expression)
+}
+
+// atom := letter | "∅" | "(" union ")"
+fn parse_atom(chars : &mut Chars) -> Result<RegularExpression, String> {
+    match chars.next() {
+        Some((_, '∅')) => Ok(Empty),
+        Some((_, '(')) => {
+            let expression = parse_union(chars)?;
+            match chars.next() {
+                Some((_, ')')) => Ok(expression),
+                Some((i, c)) => Err(format!("Expected ')' at {}, found '{}'", i, c)),
+                None => Err(String::from("Expected ')' at end of expression"))
+            }
+        },
+        Some((_, c)) if is_letter_char(c) => {
+            let mut name = String::from(c);
+            while let Some((_, c)) = chars.next_if(|(_, c)| is_letter_char(*c)) {
+                name.push(c);
+            }
+            Ok(Letter(Label::from(name)))
+        },
+        Some((i, c)) => Err(format!("Unexpected '{}' at {}", c, i)),
+        None => Err(String::from("Unexpected end of expression"))
+    }
+}
+
+impl fmt::Display for RegularExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Empty => write!(f, "∅"),
+            Epsilon => write!(f, "()"),
+            Letter(a) => write!(f, "{}", a),
+            Concat(e1, e2) => {
+                let side = |e : &RegularExpression| match e {
+                    Union(_, _) => format!("({})", e),
+                    _ => e.to_string()
+                };
+                write!(f, "{} {}", side(e1), side(e2))
+            },
+            Union(e1, e2) => write!(f, "{} | {}", e1, e2),
+            Star(e) => match **e {
+                Letter(_) | Empty | Epsilon => write!(f, "{}*", e),
+                _ => write!(f, "({})*", e)
+            }
+        }
+    }
+}
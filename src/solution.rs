@@ -2,12 +2,38 @@ pub mod class_graph_reachability_synthesis;
 pub use class_graph_reachability_synthesis::ClassGraphReachabilitySynthesis;
 pub mod class_graph_reachability;
 pub use class_graph_reachability::ClassGraphReachability;
+pub mod partial_marking_reachability;
+pub use partial_marking_reachability::PartialMarkingReachability;
+pub mod safe_net_reachability;
+pub use safe_net_reachability::SafeNetReachability;
+pub mod petri_structural_boundedness;
+pub use petri_structural_boundedness::PetriStructuralBoundedness;
+pub mod result_cache;
+pub use result_cache::ComponentResultCache;
+pub mod ltl_model_checking;
+pub use ltl_model_checking::LtlModelChecking;
+pub mod markov_expected_reward;
+pub use markov_expected_reward::MarkovExpectedReward;
+pub mod markov_steady_state;
+pub use markov_steady_state::MarkovSteadyState;
+pub mod markov_quantile;
+pub use markov_quantile::MarkovQuantile;
+mod solver_result;
+pub use solver_result::SolverResult;
+mod certificate;
+pub use certificate::Certificate;
+mod timed_trace;
+pub use timed_trace::TimedTrace;
+mod provenance;
+pub use provenance::{PipelineStage, Provenance, StageKind};
+mod explanation;
+pub use explanation::Explanation;
 
 use std::any::Any;
 
 use crate::flag;
 use crate::models::model_context::ModelContext;
-use crate::models::{lbl, Label, ModelState};
+use crate::models::{lbl, Label};
 use crate::verification::query::{Quantifier, Query, StateLogic};
 use Quantifier::*;
 use StateLogic::*;
@@ -65,17 +91,6 @@ pub fn problem_label(problem : ProblemType) -> Label {
     Label::from(characteritics.join("|"))
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum SolverResult {
-    SolverError,
-    BoolResult(bool),
-    IntResult(i32),
-    FloatResult(f64),
-    StateResult(ModelState),
-    TraceResult(Vec<Label>),
-    StrategyResult,
-}
-
 #[derive(Debug, Clone, PartialEq)]
 pub struct SolutionMeta {
     pub name : Label,
@@ -0,0 +1,111 @@
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{computation::DBM, models::{class_graph::{ClassGraph, StateClass}, petri::PetriNet, time::ClockValue, ClassId, Label, Model, ModelState, Node}, verification::{query::{Quantifier, Query, StateLogic}, VerificationStatus}};
+
+/// Evidence of a reachability verdict, that can be re-checked against the net without the class graph that produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Certificate {
+    // Transitions fired from the initial state, and the zone of their firing dates (d_0 = 0 being the reference)
+    Witness(Vec<Label>, DBM),
+    // Classes covering the initial one and closed under successors, none of them reaching the target
+    Invariant(Vec<StateClass>),
+}
+
+impl Certificate {
+
+    pub fn witness(cg : &ClassGraph, class_index : ClassId) -> Option<Self> {
+        let path = cg.path_to(class_index)?;
+        let zone = cg.dates_zone(&path)?;
+        let labels = path.iter().map(|t| cg.transitions[t.index()].get_label()).collect();
+        Some(Certificate::Witness(labels, zone))
+    }
+
+    pub fn invariant(cg : &ClassGraph) -> Self {
+        Certificate::Invariant(cg.classes.iter().map(|c| StateClass::clone(c)).collect())
+    }
+
+    // A witness proves E F phi or disproves A G phi, an invariant disproves E F phi or proves A G phi.
+    // Clock conditions have to be decided on every class, undecided ones make the certificate invalid.
+    pub fn check(&self, net : &PetriNet, initial_state : &ModelState, query : &Query) -> bool {
+        let safety = query.quantifier == Quantifier::ForAll && query.logic == StateLogic::Globally;
+        let target = if safety { VerificationStatus::Unverified } else { VerificationStatus::Verified };
+        let evaluator = ClassGraph::empty(net);
+        let initial = StateClass::compute_class(net, initial_state);
+        match self {
+            Certificate::Witness(path, zone) => {
+                let mut class = Arc::new(initial);
+                for label in path.iter() {
+                    let Some(t_index) = net.transition_id(label) else {
+                        return false;
+                    };
+                    let Some(next) = ClassGraph::successor(net, &class, t_index) else {
+                        return false;
+                    };
+                    class = Arc::new(next);
+                }
+                evaluator.evaluate_symbolic(&class, &query.condition) == target &&
+                    Self::replay(net, initial_state, path, zone)
+            },
+            Certificate::Invariant(classes) => {
+                let covered = |class : &StateClass| classes.iter().any(|c| {
+                    c.discrete == class.discrete && c.from_dbm_index == class.from_dbm_index && c.dbm.contains(&class.dbm)
+                });
+                covered(&initial) && classes.iter().all(|class| {
+                    if evaluator.evaluate_symbolic(class, &query.condition) != !target {
+                        return false;
+                    }
+                    let class = Arc::new(class.clone());
+                    class.enabled_clocks().into_iter().all(|t_index| {
+                        ClassGraph::successor(net, &class, t_index).is_none_or(|next| covered(&next))
+                    })
+                })
+            }
+        }
+    }
+
+    // Fires the path at a point of the dates zone, checking every delay and firing against the concrete semantics
+    fn replay(net : &PetriNet, initial_state : &ModelState, path : &[Label], zone : &DBM) -> bool {
+        if zone.vars_count() != path.len() {
+            return false;
+        }
+        let Some(dates) = zone.sample_point() else {
+            return false;
+        };
+        let mut state = net.init_initial_clocks(initial_state.clone());
+        let mut previous = 0.0;
+        for (label, date) in path.iter().zip(dates) {
+            let dt = ClockValue::from(date - previous);
+            if dt > net.available_delay(&state) {
+                return false;
+            }
+            let Some(delayed) = net.delay(state, dt) else {
+                return false;
+            };
+            let action = net.get_transition(label).get_action();
+            if !net.available_actions(&delayed).contains(&action) {
+                return false;
+            }
+            let Some((next, _)) = net.next(delayed, action) else {
+                return false;
+            };
+            state = next;
+            previous = date;
+        }
+        true
+    }
+
+}
+
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Certificate::Witness(path, zone) => {
+                let path : Vec<String> = path.iter().map(Label::to_string).collect();
+                write!(f, "Witness [{}]\n{}", path.join(", "), zone)
+            },
+            Certificate::Invariant(classes) => write!(f, "Invariant of {} classes", classes.len())
+        }
+    }
+}
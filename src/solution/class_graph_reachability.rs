@@ -1,17 +1,28 @@
-use std::time::Instant;
+use crate::{models::{class_graph::ClassGraph, lbl, model_context::ModelContext}, verification::{query::{Quantifier, StateLogic}, VerificationStatus}};
 
-use crate::{models::{class_graph::ClassGraph, lbl, model_context::ModelContext}, verification::{Verifiable, VerificationStatus}};
-
-use super::{Solution, SolutionMeta, SolverResult, REACHABILITY};
+use super::{Certificate, Solution, SolutionMeta, SolverResult, REACHABILITY, SAFETY};
 
 use crate::log::*;
 
-pub struct ClassGraphReachability;
+pub struct ClassGraphReachability {
+    pub certify : bool,
+    pub certificate : Option<Certificate>,
+}
 
 impl ClassGraphReachability {
 
     pub fn new() -> Self {
-        ClassGraphReachability {}
+        ClassGraphReachability { certify : false, certificate : None }
+    }
+
+    // Keeps a certificate of each decided verdict, see `certificate`
+    pub fn with_certificates(mut self) -> Self {
+        self.certify = true;
+        self
+    }
+
+    pub fn certificate(&self) -> Option<&Certificate> {
+        self.certificate.as_ref()
     }
 
 }
@@ -21,33 +32,69 @@ impl Solution for ClassGraphReachability {
     fn get_meta(&self) -> SolutionMeta {
         SolutionMeta {
             name : lbl("ClassGraphReachability"),
-            description : String::from("Test a pure reachability query against a class graph"),
-            problem_type : REACHABILITY,
+            description : String::from("Test a pure reachability or safety query against a class graph, clock constraints included"),
+            problem_type : REACHABILITY | SAFETY,
             model_name : lbl("ClassGraph"),
             result_type : lbl("bool"),
         }
     }
 
     fn is_compatible(&self, _model : &dyn std::any::Any, _ : &ModelContext, query : &crate::verification::query::Query) -> bool {
-        (!query.condition.contains_clock_proposition()) && (query.condition.is_state_condition())
+        query.condition.is_state_condition() && !query.condition.contains_nested()
     }
 
+    // AG phi is checked as not EF not phi
     fn solve(&mut self, model : &dyn std::any::Any, _ : &ModelContext, query : &crate::verification::query::Query) -> SolverResult {
         pending("Solving reachability problem on Class graph...");
+        self.certificate = None;
         let cg : Option<&ClassGraph> = model.downcast_ref();
         if cg.is_none() {
             return SolverResult::SolverError;
         }
         let cg = cg.unwrap();
+        let safety = query.quantifier == Quantifier::ForAll && query.logic == StateLogic::Globally;
+        let target = if safety { VerificationStatus::Unverified } else { VerificationStatus::Verified };
+        let mut undecided = false;
         for class in cg.classes.iter() {
-            let (status, _) = query.condition.evaluate(class.as_verifiable());
-            if status == VerificationStatus::Verified {
-                positive("Valid class found !");
-                return SolverResult::BoolResult(true);
+            let status = cg.evaluate_symbolic(class, &query.condition);
+            if status == target {
+                if safety {
+                    negative("Unsafe class found !");
+                } else {
+                    positive("Valid class found !");
+                }
+                if self.certify {
+                    self.certificate = Certificate::witness(cg, class.index);
+                }
+                return match cg.concrete_trace(class.index) {
+                    Some(trace) => {
+                        continue_info(format!("Witness run : {}", trace));
+                        if let Some(constraints) = cg.path_to(class.index).and_then(|path| cg.firing_constraints(&path)) {
+                            continue_info("Firing dates :");
+                            for step in constraints.to_string().lines() {
+                                continue_info(format!("  {}", step));
+                            }
+                        }
+                        SolverResult::WitnessResult(!safety, trace)
+                    },
+                    None => SolverResult::BoolResult(!safety)
+                };
             }
+            undecided |= status == VerificationStatus::Maybe;
+        }
+        if undecided {
+            warning("Clock constraints undecided on some classes");
+            return SolverResult::unknown("Clock constraints can't be decided on the class graph abstraction");
+        }
+        if self.certify {
+            self.certificate = Some(Certificate::invariant(cg));
         }
-        negative("No valid class found in the graph");
-        SolverResult::BoolResult(false)
+        if safety {
+            positive("Every class is safe");
+        } else {
+            negative("No valid class found in the graph");
+        }
+        SolverResult::BoolResult(safety)
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use crate::{models::{class_graph::{ClassGraph, FiringConstraints}, expressions::Condition, ClassId, Label}, verification::{query::{Quantifier, Query, StateLogic}, VerificationStatus}};
+
+use super::TimedTrace;
+
+use crate::log::*;
+
+/// Why a reachability or safety query holds or not on a class graph, independently of the solution that decided it
+#[derive(Debug, Clone)]
+pub enum Explanation {
+    // Shortest run reaching the target of E F p, or violating A G p, with its firing dates constraints and concrete delays
+    Witness { verdict : bool, path : Vec<Label>, constraints : Option<FiringConstraints>, trace : Option<TimedTrace> },
+    // Every reachable class verifies the invariant : p for a verified A G p, not p for a violated E F p
+    ProofSketch { verdict : bool, classes : Vec<ClassId>, invariant : Condition },
+    Unexplained(String),
+}
+
+impl Explanation {
+
+    pub fn explain(cg : &ClassGraph, query : &Query) -> Self {
+        let safety = match (query.quantifier, query.logic) {
+            (Quantifier::ForAll, StateLogic::Globally) => true,
+            (Quantifier::Exists, StateLogic::Finally) => false,
+            _ => return Explanation::Unexplained(String::from("Only E F and A G queries are explained"))
+        };
+        if !query.condition.is_state_condition() || query.condition.contains_nested() {
+            return Explanation::Unexplained(String::from("Only state conditions are explained"));
+        }
+        let target = if safety { VerificationStatus::Unverified } else { VerificationStatus::Verified };
+        if let Some((_, path)) = cg.shortest_path_to(|class| cg.evaluate_symbolic(class, &query.condition) == target) {
+            return Explanation::Witness {
+                verdict : !safety,
+                path : path.iter().map(|t| cg.transitions[t.index()].label.clone()).collect(),
+                constraints : cg.firing_constraints(&path),
+                trace : cg.path_trace(&path)
+            };
+        }
+        if cg.classes.iter().any(|class| cg.evaluate_symbolic(class, &query.condition) == VerificationStatus::Maybe) {
+            return Explanation::Unexplained(String::from("Clock constraints undecided on some classes"));
+        }
+        let invariant = if safety { query.condition.clone() } else { Condition::Not(Box::new(query.condition.clone())) };
+        Explanation::ProofSketch {
+            verdict : safety,
+            classes : cg.classes.iter().map(|c| c.index).collect(),
+            invariant
+        }
+    }
+
+    pub fn verdict(&self) -> Option<bool> {
+        match self {
+            Explanation::Witness { verdict, .. } | Explanation::ProofSketch { verdict, .. } => Some(*verdict),
+            Explanation::Unexplained(_) => None
+        }
+    }
+
+    pub fn log(&self) {
+        let text = self.to_string();
+        let mut lines = text.lines();
+        if let Some(title) = lines.next() {
+            info(title);
+        }
+        for line in lines {
+            continue_info(line.trim_start_matches(" - "));
+        }
+    }
+
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Explanation::Witness { verdict, path, constraints, trace } => {
+                writeln!(f, "{} because of a run of {} firings", verdict, path.len())?;
+                let path : Vec<String> = path.iter().map(Label::to_string).collect();
+                writeln!(f, " - Path : [{}]", path.join(", "))?;
+                if let Some(constraints) = constraints {
+                    for step in constraints.to_string().lines() {
+                        writeln!(f, " - {}", step)?;
+                    }
+                }
+                match trace {
+                    Some(trace) => writeln!(f, " - Timed run : {}", trace),
+                    None => writeln!(f, " - No concrete delays found for the path")
+                }
+            },
+            Explanation::ProofSketch { verdict, classes, invariant } => {
+                let negated = if matches!(invariant, Condition::Not(_)) { "negation of the " } else { "" };
+                writeln!(f, "{} because every reachable class verifies the {}query condition", verdict, negated)?;
+                let classes : Vec<String> = classes.iter().map(ClassId::to_string).collect();
+                writeln!(f, " - Invariant classes, closed under successors : [{}]", classes.join(", "))
+            },
+            Explanation::Unexplained(reason) => writeln!(f, "No explanation : {}", reason)
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::any::Any;
+
+use crate::{models::{class_graph::ClassGraph, lbl, model_context::ModelContext}, verification::{ltl::{self, LtlFormula}, query::Query}};
+
+use super::{Solution, SolutionMeta, SolverResult, UNCLASSIFIED_PROBLEM};
+
+use crate::log::*;
+
+pub struct LtlModelChecking;
+
+impl LtlModelChecking {
+
+    pub fn new() -> Self {
+        LtlModelChecking {}
+    }
+
+}
+
+impl Solution for LtlModelChecking {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("LtlModelChecking"),
+            description : String::from("Exhaustive LTL model checking on a class graph, using a Büchi automaton and nested DFS"),
+            problem_type : UNCLASSIFIED_PROBLEM,
+            model_name : lbl("ClassGraph"),
+            result_type : lbl("bool"),
+        }
+    }
+
+    fn is_compatible(&self, _ : &dyn Any, _ : &ModelContext, query : &Query) -> bool {
+        !query.condition.contains_clock_proposition() && !query.condition.contains_nested() && LtlFormula::from_query(query).is_some()
+    }
+
+    fn solve(&mut self, model : &dyn Any, _ : &ModelContext, query : &Query) -> SolverResult {
+        pending("Solving LTL query on Class graph...");
+        let Some(cg) = model.downcast_ref::<ClassGraph>() else {
+            return SolverResult::SolverError;
+        };
+        let Some((formula, exists)) = LtlFormula::from_query(query) else {
+            return SolverResult::SolverError;
+        };
+        let mut initial_state = cg.classes[0].generate_image_state();
+        initial_state.discrete.size_delta(cg.current_class.size());
+        let result = if exists {
+            ltl::check_exists(cg, &initial_state, &formula)
+        } else {
+            ltl::check_for_all(cg, &initial_state, &formula)
+        };
+        continue_info(format!("Product states explored : [{}]", result.explored));
+        if result.holds {
+            positive("LTL property satisfied");
+        } else {
+            negative("LTL property violated");
+        }
+        SolverResult::BoolResult(result.holds)
+    }
+
+}
+
+impl Default for LtlModelChecking {
+    fn default() -> Self {
+        Self::new()
+    }
+}
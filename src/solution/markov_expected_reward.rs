@@ -0,0 +1,185 @@
+use std::any::Any;
+
+use crate::{models::{action::Action, lbl, markov::markov_chain::MarkovChain, model_context::ModelContext, reward_structure::RewardStructure, Label, ModelState}, verification::query::{Quantifier, Query, StateLogic}};
+
+use super::{Solution, SolutionMeta, SolverResult, UNCLASSIFIED_PROBLEM};
+
+use crate::log::*;
+
+const CONVERGENCE_THRESHOLD : f64 = 1e-10;
+const MAX_ITERATIONS : usize = 100000;
+
+// Analytic computation of E[C <= k] and E[F goal] on discrete-time Markov chains (no decision nodes).
+// Each step from a node earns its state reward, plus the reward of the action taken and of the transition to the next node. Deadlocked nodes keep earning their state reward.
+pub struct MarkovExpectedReward {
+    pub initial : Option<Label>,
+}
+
+impl MarkovExpectedReward {
+
+    // Rewards are computed from the first node of the chain
+    pub fn new() -> Self {
+        MarkovExpectedReward { initial : None }
+    }
+
+    pub fn from_node(initial : Label) -> Self {
+        MarkovExpectedReward { initial : Some(initial) }
+    }
+
+    // Successors distribution and reward earned when leaving each node
+    fn step_rewards(chain : &MarkovChain, ctx : &ModelContext, reward : &RewardStructure) -> Vec<(Vec<(usize, f64)>, f64)> {
+        let states : Vec<ModelState> = chain.nodes.iter().map(|node| {
+            let mut state = ctx.make_empty_state();
+            state.mark(node.get_var(), 1);
+            state
+        }).collect();
+        chain.nodes.iter().zip(states.iter()).map(|(node, state)| {
+            let state_reward = reward.state_reward(state);
+            match node.actions.get(&Action::Epsilon) {
+                None => (Vec::new(), state_reward),
+                Some(choice) => {
+                    let transition_reward : f64 = choice.0.iter().map(|(j, p)| p * reward.transition_reward(state, &states[*j])).sum();
+                    (choice.0.clone(), state_reward + reward.action_reward(&Action::Epsilon) + transition_reward)
+                }
+            }
+        }).collect()
+    }
+
+    // Expected reward from every node of the chain, None if the chain has decision nodes
+    pub fn expected_rewards(chain : &MarkovChain, ctx : &ModelContext, reward : &RewardStructure, query : &Query) -> Option<Vec<f64>> {
+        if chain.nodes.iter().any(|n| n.is_choice()) {
+            return None;
+        }
+        let steps = Self::step_rewards(chain, ctx, reward);
+        match query.logic {
+            StateLogic::Finally => Some(Self::reachability_rewards(chain, ctx, &steps, query)),
+            _ => {
+                let bound = query.run_bound.step_limit()?;
+                Some(Self::cumulative_rewards(&steps, bound))
+            }
+        }
+    }
+
+    fn cumulative_rewards(steps : &[(Vec<(usize, f64)>, f64)], bound : usize) -> Vec<f64> {
+        let mut values = vec![0.0; steps.len()];
+        for _ in 0..bound {
+            values = steps.iter().enumerate().map(|(i, (succs, r))| {
+                if succs.is_empty() {
+                    r + values[i]
+                } else {
+                    r + succs.iter().map(|(j, p)| p * values[*j]).sum::<f64>()
+                }
+            }).collect();
+        }
+        values
+    }
+
+    fn reachability_rewards(chain : &MarkovChain, ctx : &ModelContext, steps : &[(Vec<(usize, f64)>, f64)], query : &Query) -> Vec<f64> {
+        let n = steps.len();
+        let goal : Vec<bool> = chain.nodes.iter().map(|node| {
+            let mut state = ctx.make_empty_state();
+            state.mark(node.get_var(), 1);
+            query.condition.is_true(&state)
+        }).collect();
+        // Nodes that can't reach the goal
+        let mut can_reach = goal.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in 0..n {
+                if !can_reach[i] && steps[i].0.iter().any(|(j, p)| *p > 0.0 && can_reach[*j]) {
+                    can_reach[i] = true;
+                    changed = true;
+                }
+            }
+        }
+        // Nodes that may avoid the goal forever, i.e. reach a node that can't reach it, have an infinite expected reward
+        let mut infinite : Vec<bool> = can_reach.iter().map(|r| !r).collect();
+        changed = true;
+        while changed {
+            changed = false;
+            for i in 0..n {
+                if !infinite[i] && !goal[i] && steps[i].0.iter().any(|(j, p)| *p > 0.0 && infinite[*j]) {
+                    infinite[i] = true;
+                    changed = true;
+                }
+            }
+        }
+        let mut values = vec![0.0; n];
+        for _ in 0..MAX_ITERATIONS {
+            let mut delta : f64 = 0.0;
+            for i in 0..n {
+                if goal[i] || infinite[i] {
+                    continue;
+                }
+                let (succs, r) = &steps[i];
+                let value = r + succs.iter().map(|(j, p)| p * values[*j]).sum::<f64>();
+                delta = delta.max((value - values[i]).abs());
+                values[i] = value;
+            }
+            if delta < CONVERGENCE_THRESHOLD {
+                break;
+            }
+        }
+        for i in 0..n {
+            if infinite[i] {
+                values[i] = f64::INFINITY;
+            }
+        }
+        values
+    }
+
+}
+
+impl Solution for MarkovExpectedReward {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("MarkovExpectedReward"),
+            description : String::from("Analytic expected cumulative and reachability rewards on discrete-time Markov chains"),
+            problem_type : UNCLASSIFIED_PROBLEM,
+            model_name : lbl("MarkovChain"),
+            result_type : lbl("float"),
+        }
+    }
+
+    fn is_compatible(&self, model : &dyn Any, context : &ModelContext, query : &Query) -> bool {
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return false;
+        };
+        query.quantifier == Quantifier::ExpectedReward &&
+            context.get_reward(&query.reward).is_some() &&
+            !query.condition.contains_nested() &&
+            chain.nodes.iter().all(|n| !n.is_choice())
+    }
+
+    fn solve(&mut self, model : &dyn Any, context : &ModelContext, query : &Query) -> SolverResult {
+        pending("Computing expected reward on Markov chain...");
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return SolverResult::SolverError;
+        };
+        let Some(reward) = context.get_reward(&query.reward) else {
+            error("No reward structure found !");
+            return SolverResult::SolverError;
+        };
+        let Some(values) = Self::expected_rewards(chain, context, reward, query) else {
+            return SolverResult::unknown("Expected reward only available on Markov chains without decisions");
+        };
+        let initial = match &self.initial {
+            None => 0,
+            Some(label) => match chain.nodes_dic.get(label) {
+                Some(i) => *i,
+                None => return SolverResult::SolverError
+            }
+        };
+        positive(format!("Expected reward : {}", values[initial]));
+        SolverResult::FloatResult(values[initial])
+    }
+
+}
+
+impl Default for MarkovExpectedReward {
+    fn default() -> Self {
+        Self::new()
+    }
+}
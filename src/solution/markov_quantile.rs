@@ -0,0 +1,127 @@
+use std::any::Any;
+
+use crate::{models::{action::Action, expressions::PropositionType, lbl, markov::markov_chain::MarkovChain, model_context::ModelContext, Label}, verification::query::{ProbabilityThreshold, Quantifier, Query}};
+
+use super::{Solution, SolutionMeta, SolverResult, UNCLASSIFIED_PROBLEM};
+
+use crate::log::*;
+
+const MAX_ITERATIONS : usize = 100000;
+const CONVERGENCE_THRESHOLD : f64 = 1e-12;
+
+// Analytic computation of Q~p [F goal] on discrete-time Markov chains (no decision nodes), by transient analysis :
+// the probability mass is pushed step by step, goal nodes absorbing it, until enough mass has been absorbed.
+// Time bounds are read as steps bounds, as for SMC on untimed models. Deadlocked nodes keep their mass.
+pub struct MarkovQuantile {
+    pub initial : Option<Label>,
+}
+
+impl MarkovQuantile {
+
+    // Quantile is computed from the first node of the chain
+    pub fn new() -> Self {
+        MarkovQuantile { initial : None }
+    }
+
+    pub fn from_node(initial : Label) -> Self {
+        MarkovQuantile { initial : Some(initial) }
+    }
+
+    // Smallest number of steps after which the goal has been reached with the wanted probability, infinite if never
+    pub fn steps_quantile(chain : &MarkovChain, ctx : &ModelContext, initial : usize, query : &Query) -> Option<f64> {
+        let Quantifier::Quantile(prop_type, ProbabilityThreshold(p)) = query.quantifier else {
+            return None;
+        };
+        if chain.nodes.iter().any(|n| n.is_choice()) {
+            return None;
+        }
+        let goal : Vec<bool> = chain.nodes.iter().map(|node| {
+            let mut state = ctx.make_empty_state();
+            state.mark(node.get_var(), 1);
+            query.condition.is_true(&state)
+        }).collect();
+        let limit = query.run_bound.step_limit().unwrap_or(MAX_ITERATIONS);
+        let mut distribution = vec![0.0; chain.nodes.len()];
+        distribution[initial] = 1.0;
+        let mut absorbed = 0.0;
+        for k in 0..limit {
+            for (i, mass) in distribution.iter_mut().enumerate() {
+                if goal[i] {
+                    absorbed += *mass;
+                    *mass = 0.0;
+                }
+            }
+            let reached = match prop_type {
+                PropositionType::GS => absorbed > p,
+                _ => absorbed >= p
+            };
+            if reached {
+                return Some(k as f64);
+            }
+            if distribution.iter().sum::<f64>() < CONVERGENCE_THRESHOLD {
+                break;
+            }
+            let mut next = vec![0.0; chain.nodes.len()];
+            for (i, node) in chain.nodes.iter().enumerate() {
+                match node.actions.get(&Action::Epsilon) {
+                    None => next[i] += distribution[i],
+                    Some(choice) => for (j, q) in choice.0.iter() {
+                        next[*j] += q * distribution[i];
+                    }
+                }
+            }
+            distribution = next;
+        }
+        Some(f64::INFINITY)
+    }
+
+}
+
+impl Solution for MarkovQuantile {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("MarkovQuantile"),
+            description : String::from("Analytic reachability quantiles on discrete-time Markov chains"),
+            problem_type : UNCLASSIFIED_PROBLEM,
+            model_name : lbl("MarkovChain"),
+            result_type : lbl("float"),
+        }
+    }
+
+    fn is_compatible(&self, model : &dyn Any, _ : &ModelContext, query : &Query) -> bool {
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return false;
+        };
+        matches!(query.quantifier, Quantifier::Quantile(_, _)) &&
+            query.condition.is_state_condition() &&
+            !query.condition.contains_nested() &&
+            chain.nodes.iter().all(|n| !n.is_choice())
+    }
+
+    fn solve(&mut self, model : &dyn Any, context : &ModelContext, query : &Query) -> SolverResult {
+        pending("Computing quantile on Markov chain...");
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return SolverResult::SolverError;
+        };
+        let initial = match &self.initial {
+            None => 0,
+            Some(label) => match chain.nodes_dic.get(label) {
+                Some(i) => *i,
+                None => return SolverResult::SolverError
+            }
+        };
+        let Some(quantile) = Self::steps_quantile(chain, context, initial, query) else {
+            return SolverResult::unknown("Quantiles only available on Markov chains without decisions");
+        };
+        positive(format!("Quantile : {} steps", quantile));
+        SolverResult::FloatResult(quantile)
+    }
+
+}
+
+impl Default for MarkovQuantile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,94 @@
+use std::any::Any;
+
+use crate::{models::{expressions::PropositionType, lbl, markov::markov_chain::MarkovChain, model_context::ModelContext, Label}, verification::query::{ProbabilityThreshold, Quantifier, Query}};
+
+use super::{Solution, SolutionMeta, SolverResult, UNCLASSIFIED_PROBLEM};
+
+use crate::log::*;
+
+// Analytic computation of S~p [cond] on discrete-time Markov chains (no decision nodes) : the long-run probability mass
+// of the nodes verifying the condition, from the long-run distribution of the chain. Deadlocked nodes are absorbing.
+pub struct MarkovSteadyState {
+    pub initial : Option<Label>,
+}
+
+impl MarkovSteadyState {
+
+    // Long-run distribution is computed from the first node of the chain
+    pub fn new() -> Self {
+        MarkovSteadyState { initial : None }
+    }
+
+    pub fn from_node(initial : Label) -> Self {
+        MarkovSteadyState { initial : Some(initial) }
+    }
+
+    fn compare(prop_type : PropositionType, value : f64, threshold : f64) -> bool {
+        match prop_type {
+            PropositionType::EQ => value == threshold,
+            PropositionType::NE => value != threshold,
+            PropositionType::LE => value <= threshold,
+            PropositionType::GE => value >= threshold,
+            PropositionType::LS => value < threshold,
+            PropositionType::GS => value > threshold,
+        }
+    }
+
+}
+
+impl Solution for MarkovSteadyState {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("MarkovSteadyState"),
+            description : String::from("Analytic long-run probabilities on discrete-time Markov chains"),
+            problem_type : UNCLASSIFIED_PROBLEM,
+            model_name : lbl("MarkovChain"),
+            result_type : lbl("bool"),
+        }
+    }
+
+    fn is_compatible(&self, model : &dyn Any, _ : &ModelContext, query : &Query) -> bool {
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return false;
+        };
+        matches!(query.quantifier, Quantifier::SteadyState(_, _)) &&
+            !query.condition.contains_nested() &&
+            !query.condition.contains_past() &&
+            chain.nodes.iter().all(|n| !n.is_choice())
+    }
+
+    fn solve(&mut self, model : &dyn Any, context : &ModelContext, query : &Query) -> SolverResult {
+        pending("Computing steady-state probability on Markov chain...");
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return SolverResult::SolverError;
+        };
+        let Quantifier::SteadyState(prop_type, ProbabilityThreshold(threshold)) = query.quantifier else {
+            return SolverResult::SolverError;
+        };
+        let initial = match &self.initial {
+            None => 0,
+            Some(label) => match chain.nodes_dic.get(label) {
+                Some(i) => *i,
+                None => return SolverResult::SolverError
+            }
+        };
+        let Some(distribution) = chain.long_run_distribution(initial) else {
+            return SolverResult::unknown("Steady-state only available on Markov chains without decisions");
+        };
+        let mass : f64 = chain.nodes.iter().zip(distribution.iter()).filter(|(node, _)| {
+            let mut state = context.make_empty_state();
+            state.mark(node.get_var(), 1);
+            query.condition.is_true(&state)
+        }).map(|(_, p)| p).sum();
+        positive(format!("Steady-state probability : {}", mass));
+        SolverResult::BoolResult(Self::compare(prop_type, mass, threshold))
+    }
+
+}
+
+impl Default for MarkovSteadyState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,105 @@
+use std::any::Any;
+
+use crate::{models::{class_graph::{ClassGraph, StateClass}, initial_marking::PartialMarking, lbl, model_context::ModelContext, petri::PetriNet}, verification::{query::{Quantifier, Query, StateLogic}, Verifiable}};
+
+use super::{Solution, SolutionMeta, SolverResult, REACHABILITY, SAFETY};
+
+use crate::log::*;
+
+// Reachability on a TPN whose initial marking is only partially known : every possible initial marking
+// is explored through its class graph, and the verdicts are combined by the initial quantifier.
+// Initial clock intervals are kept symbolic : one class graph covers every clock valuation of a marking.
+pub struct PartialMarkingReachability {
+    pub marking : PartialMarking,
+    pub initial_quantifier : Quantifier,
+}
+
+impl PartialMarkingReachability {
+
+    pub fn new(marking : PartialMarking, initial_quantifier : Quantifier) -> Self {
+        PartialMarkingReachability { marking, initial_quantifier }
+    }
+
+    pub fn exists(marking : PartialMarking) -> Self {
+        Self::new(marking, Quantifier::Exists)
+    }
+
+    pub fn for_all(marking : PartialMarking) -> Self {
+        Self::new(marking, Quantifier::ForAll)
+    }
+
+    fn verify_graph(cg : &ClassGraph, query : &Query) -> bool {
+        match query.logic {
+            StateLogic::Globally => cg.classes.iter().all(|c| query.condition.is_true(c.as_verifiable())),
+            _ => cg.classes.iter().any(|c| query.condition.is_true(c.as_verifiable())),
+        }
+    }
+
+}
+
+impl Solution for PartialMarkingReachability {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("PartialMarkingReachability"),
+            description : String::from("Test a reachability or safety query for some / every initial marking of a partially specified TPN"),
+            problem_type : REACHABILITY | SAFETY,
+            model_name : lbl("TPN"),
+            result_type : lbl("bool"),
+        }
+    }
+
+    fn is_compatible(&self, model : &dyn Any, _ : &ModelContext, query : &Query) -> bool {
+        model.is::<PetriNet>() &&
+            (!query.condition.contains_clock_proposition()) && 
+            (!query.condition.contains_nested()) &&
+            query.condition.is_state_condition() &&
+            matches!((query.quantifier, query.logic), (Quantifier::Exists, StateLogic::Finally) | (Quantifier::ForAll, StateLogic::Globally)) &&
+            matches!(self.initial_quantifier, Quantifier::Exists | Quantifier::ForAll)
+    }
+
+    fn solve(&mut self, model : &dyn Any, context : &ModelContext, query : &Query) -> SolverResult {
+        pending("Solving reachability problem over partial initial marking...");
+        let Some(net) = model.downcast_ref::<PetriNet>() else {
+            return SolverResult::SolverError;
+        };
+        let n_markings = match self.marking.check() {
+            Ok(n) => n,
+            Err(e) => {
+                error(format!("Invalid partial marking : {}", e));
+                return SolverResult::SolverError;
+            }
+        };
+        continue_info(format!("Initial markings : [{}]", n_markings));
+        let for_all = self.initial_quantifier == Quantifier::ForAll;
+        let mut explored = 0;
+        for state in self.marking.initial_states(context, net) {
+            // Markings without any valid clock valuation are not initial states
+            let Some(initial_class) = StateClass::compute_class_with_clocks(net, &state, &self.marking.clocks) else {
+                continue;
+            };
+            explored += 1;
+            let cg = ClassGraph::compute_from(net, initial_class);
+            let verified = Self::verify_graph(&cg, query);
+            if verified && !for_all {
+                positive("Valid initial marking found !");
+                return SolverResult::BoolResult(true);
+            }
+            if !verified && for_all {
+                negative("Invalid initial marking found !");
+                return SolverResult::BoolResult(false);
+            }
+        }
+        if explored == 0 {
+            error("No initial marking has valid clock values");
+            return SolverResult::SolverError;
+        }
+        if for_all {
+            positive("Every initial marking is valid");
+        } else {
+            negative("No valid initial marking");
+        }
+        SolverResult::BoolResult(for_all)
+    }
+
+}
@@ -0,0 +1,54 @@
+use std::any::Any;
+
+use crate::{models::{lbl, model_context::ModelContext, petri::PetriNet, ModelState}, verification::query::Query};
+
+use super::{Solution, SolutionMeta, SolverResult, BOUNDEDNESS, SAFETY};
+
+use crate::log::*;
+
+// k-boundedness (see Query::k_bounded) decided from the structure of the net, without exploring its state space :
+// a net whose transitions never produce more tokens than they consume keeps at most its initial number of tokens. Inconclusive otherwise, leaving the query to other solutions.
+pub struct PetriStructuralBoundedness {
+    pub initial : ModelState,
+}
+
+impl PetriStructuralBoundedness {
+
+    pub fn new(initial : ModelState) -> Self {
+        PetriStructuralBoundedness { initial }
+    }
+
+}
+
+impl Solution for PetriStructuralBoundedness {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("PetriStructuralBoundedness"),
+            description : String::from("Structural k-boundedness of non-increasing Petri nets"),
+            problem_type : SAFETY | BOUNDEDNESS,
+            model_name : lbl("TPN"),
+            result_type : lbl("bool"),
+        }
+    }
+
+    fn is_compatible(&self, model : &dyn Any, _ : &ModelContext, query : &Query) -> bool {
+        model.is::<PetriNet>() && query.k_bound().is_some()
+    }
+
+    fn solve(&mut self, model : &dyn Any, _ : &ModelContext, query : &Query) -> SolverResult {
+        pending("Checking boundedness from the net structure...");
+        let (Some(net), Some(k)) = (model.downcast_ref::<PetriNet>(), query.k_bound()) else {
+            return SolverResult::SolverError;
+        };
+        let Some(bound) = net.token_bound(&self.initial) else {
+            return SolverResult::unknown("Net may produce more tokens than it consumes");
+        };
+        if bound <= k {
+            positive(format!("Non-increasing net, at most {} tokens per place", bound));
+            return SolverResult::BoolResult(true);
+        }
+        SolverResult::unknown(format!("Structural bound {} exceeds {}", bound, k))
+    }
+
+}
@@ -0,0 +1,137 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::log;
+use crate::models::Label;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageKind {
+    Model,
+    // Rewriting of the query to fit the model semantics
+    Adaptation,
+    Translation,
+    Solution
+}
+
+/// Step of a solver pipeline run, with the steps it led to
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineStage {
+    pub kind : StageKind,
+    pub name : Label,
+    pub duration : Duration,
+    pub outcome : Option<String>,
+    pub children : Vec<PipelineStage>,
+}
+
+impl PipelineStage {
+
+    pub fn new(kind : StageKind, name : Label) -> Self {
+        PipelineStage {
+            kind, name,
+            duration : Duration::ZERO,
+            outcome : None,
+            children : Vec::new()
+        }
+    }
+
+    pub fn with_duration(mut self, duration : Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_outcome(mut self, outcome : impl ToString) -> Self {
+        self.outcome = Some(outcome.to_string());
+        self
+    }
+
+    pub fn push(&mut self, stage : PipelineStage) {
+        self.children.push(stage);
+    }
+
+    // Own duration and the one of every nested stage
+    pub fn total_time(&self) -> Duration {
+        self.duration + self.children.iter().map(PipelineStage::total_time).sum::<Duration>()
+    }
+
+    fn log(&self, depth : usize) {
+        log::nested(depth, self.to_string());
+        for child in self.children.iter() {
+            child.log(depth + 1);
+        }
+    }
+
+    fn write(&self, f : &mut std::fmt::Formatter<'_>, depth : usize) -> std::fmt::Result {
+        writeln!(f, "{}{}", "  ".repeat(depth), self)?;
+        for child in self.children.iter() {
+            child.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+
+}
+
+impl Display for PipelineStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {}", self.kind, self.name)?;
+        if !self.duration.is_zero() {
+            write!(f, " ({}s)", self.duration.as_secs_f64())?;
+        }
+        match &self.outcome {
+            Some(outcome) => write!(f, " : {}", outcome),
+            None => Ok(())
+        }
+    }
+}
+
+/// Nested log of a solver run : models encountered, translations applied and solutions tried
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Provenance {
+    pub stages : Vec<PipelineStage>,
+}
+
+impl Provenance {
+
+    pub fn new() -> Self {
+        Provenance { stages : Vec::new() }
+    }
+
+    pub fn push(&mut self, stage : PipelineStage) {
+        self.stages.push(stage);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.stages.iter().map(PipelineStage::total_time).sum()
+    }
+
+    // Stages leading to the result. Routing stops at the first conclusive solution, so it is always the last one tried.
+    pub fn route(&self) -> Vec<&PipelineStage> {
+        let mut route = Vec::new();
+        let mut current = self.stages.last();
+        while let Some(stage) = current {
+            route.push(stage);
+            current = stage.children.last();
+        }
+        route
+    }
+
+    pub fn log(&self) {
+        log::info("Solver pipeline :");
+        for stage in self.stages.iter() {
+            stage.log(0);
+        }
+        log::continue_info(format!("Total time : {}s", self.total_time().as_secs_f64()));
+    }
+
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for stage in self.stages.iter() {
+            stage.write(f, 0)?;
+        }
+        Ok(())
+    }
+}
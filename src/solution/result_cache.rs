@@ -0,0 +1,104 @@
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}};
+
+use crate::{models::{model_network::ModelNetwork, Label, Model, ModelState}, verification::query::Query};
+
+use super::SolverResult;
+
+use crate::log::*;
+
+/// Cache of verification results, keyed by component structure hash, initial state hash and query hash.
+/// Re-verifying a network after editing one component only recomputes the results of this component.
+#[derive(Debug, Clone, Default)]
+pub struct ComponentResultCache {
+    results : HashMap<(u64, u64, u64), SolverResult>,
+    pub hits : usize,
+    pub misses : usize,
+}
+
+impl ComponentResultCache {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    fn state_hash(initial_state : &ModelState) -> u64 {
+        let mut s = DefaultHasher::new();
+        initial_state.hash(&mut s);
+        s.finish()
+    }
+
+    pub fn get(&self, component : &dyn Model, initial_state : &ModelState, query : &Query) -> Option<&SolverResult> {
+        let hash = component.structure_hash()?;
+        self.results.get(&(hash, Self::state_hash(initial_state), query.get_query_hash()))
+    }
+
+    pub fn insert(&mut self, component : &dyn Model, initial_state : &ModelState, query : &Query, result : SolverResult) {
+        let Some(hash) = component.structure_hash() else {
+            return;
+        };
+        self.results.insert((hash, Self::state_hash(initial_state), query.get_query_hash()), result);
+    }
+
+    // Components that can't be hashed are always recomputed
+    pub fn get_or_compute(
+        &mut self,
+        component : &dyn Model,
+        initial_state : &ModelState,
+        query : &Query,
+        compute : impl FnOnce(&dyn Model, &ModelState, &Query) -> SolverResult
+    ) -> SolverResult {
+        if let Some(res) = self.get(component, initial_state, query).cloned() {
+            self.hits += 1;
+            return res;
+        }
+        self.misses += 1;
+        let res = compute(component, initial_state, query);
+        if res != SolverResult::SolverError {
+            self.insert(component, initial_state, query, res.clone());
+        }
+        res
+    }
+
+    pub fn invalidate(&mut self, component_hash : u64) {
+        self.results.retain(|(c, _, _), _| *c != component_hash);
+    }
+
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    // Verifies local queries on each named component of the network from its initial state, using cached results when possible
+    pub fn verify_components(
+        &mut self, 
+        network : &ModelNetwork, 
+        initial_state : &ModelState,
+        queries : &HashMap<Label, Vec<Query>>, 
+        mut compute : impl FnMut(&dyn Model, &ModelState, &Query) -> SolverResult
+    ) -> HashMap<Label, Vec<SolverResult>> {
+        let (hits, misses) = (self.hits, self.misses);
+        let mut results = HashMap::new();
+        for (name, component_queries) in queries.iter() {
+            let Some(component) = network.get_model(name) else {
+                warning(format!("Component {} not found in network", name));
+                continue;
+            };
+            let component_results = component_queries.iter().map(|q| {
+                self.get_or_compute(component, initial_state, q, &mut compute)
+            }).collect();
+            results.insert(name.clone(), component_results);
+        }
+        continue_info(format!("Cached results : [{}], computed : [{}]", self.hits - hits, self.misses - misses));
+        results
+    }
+
+}
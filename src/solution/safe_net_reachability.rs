@@ -0,0 +1,78 @@
+use std::any::Any;
+
+use crate::{computation::intervals::Convex, models::{class_graph::ClassGraph, lbl, model_context::ModelContext, petri::{OneSafeStateSpace, PetriNet}, time::TimeInterval, ModelState}, verification::{query::{Quantifier, Query, StateLogic}, Verifiable}};
+
+use super::{Solution, SolutionMeta, SolverResult, REACHABILITY, SAFETY};
+
+use crate::log::*;
+
+// Reachability on an untimed Petri net, as obtained from `PetriNet::untimed`. Nets detected 1-safe are explored with
+// markings encoded as bit sets, hashed and fired without going through the variables of the states, the others
+// through their class graph.
+pub struct SafeNetReachability {
+    pub initial : ModelState,
+}
+
+impl SafeNetReachability {
+
+    pub fn new(initial : ModelState) -> Self {
+        SafeNetReachability { initial }
+    }
+
+    fn verify(query : &Query, mut states : impl Iterator<Item = ModelState>) -> bool {
+        match query.logic {
+            StateLogic::Globally => states.all(|s| query.condition.is_true(&s)),
+            _ => states.any(|s| query.condition.is_true(&s)),
+        }
+    }
+
+}
+
+impl Solution for SafeNetReachability {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("SafeNetReachability"),
+            description : String::from("Test a reachability or safety query on an untimed Petri net, bit-encoded when 1-safe"),
+            problem_type : REACHABILITY | SAFETY,
+            model_name : lbl("TPN"),
+            result_type : lbl("bool"),
+        }
+    }
+
+    fn is_compatible(&self, model : &dyn Any, _ : &ModelContext, query : &Query) -> bool {
+        model.downcast_ref::<PetriNet>().is_some_and(|net| net.transitions.iter().all(|t| t.interval == TimeInterval::full())) &&
+            (!query.condition.contains_clock_proposition()) &&
+            (!query.condition.contains_nested()) &&
+            query.condition.is_state_condition() &&
+            matches!((query.quantifier, query.logic), (Quantifier::Exists, StateLogic::Finally) | (Quantifier::ForAll, StateLogic::Globally))
+    }
+
+    fn solve(&mut self, model : &dyn Any, _ : &ModelContext, query : &Query) -> SolverResult {
+        pending("Solving reachability problem over untimed net...");
+        let Some(net) = model.downcast_ref::<PetriNet>() else {
+            return SolverResult::SolverError;
+        };
+        let verified = match OneSafeStateSpace::compute(net, &self.initial) {
+            Some(space) => {
+                continue_info(format!("1-safe net, markings as bit sets : [{}]", space.markings.len()));
+                Self::verify(query, (0..space.markings.len()).map(|i| space.state(i, &self.initial)))
+            },
+            None => {
+                continue_info("Net not detected 1-safe, exploring its class graph");
+                let cg = ClassGraph::compute(net, &self.initial);
+                match query.logic {
+                    StateLogic::Globally => cg.classes.iter().all(|c| query.condition.is_true(c.as_verifiable())),
+                    _ => cg.classes.iter().any(|c| query.condition.is_true(c.as_verifiable())),
+                }
+            }
+        };
+        if verified {
+            positive("Query verified !");
+        } else {
+            negative("Query not verified");
+        }
+        SolverResult::BoolResult(verified)
+    }
+
+}
@@ -0,0 +1,171 @@
+use std::{fmt::Display, ops::{BitAnd, BitOr, Not}};
+
+use crate::models::{Label, ModelState};
+
+use super::TimedTrace;
+
+use SolverResult::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverResult {
+    SolverError,
+    BoolResult(bool),
+    IntResult(i32),
+    FloatResult(f64),
+    StateResult(ModelState),
+    TraceResult(Vec<Label>),
+    StrategyResult,
+    // Verdict, with a concrete run witnessing it (or counterexample for failed safety)
+    WitnessResult(bool, TimedTrace),
+    // Statistical verdict, with an upper bound on the probability of it being wrong
+    StatisticalBoolResult(bool, f64),
+    // Estimation of a probability, with its confidence interval
+    ProbabilityResult { estimate : f64, interval : (f64, f64), confidence : f64 },
+    // Estimation of any numerical value, with its confidence interval
+    NumericResult { estimate : f64, interval : (f64, f64), confidence : f64 },
+    UnknownResult(String),
+}
+
+impl SolverResult {
+
+    pub fn probability(estimate : f64, half_width : f64, confidence : f64) -> Self {
+        ProbabilityResult {
+            estimate,
+            interval : ((estimate - half_width).max(0.0), (estimate + half_width).min(1.0)),
+            confidence
+        }
+    }
+
+    pub fn numeric(estimate : f64, half_width : f64, confidence : f64) -> Self {
+        NumericResult { estimate, interval : (estimate - half_width, estimate + half_width), confidence }
+    }
+
+    pub fn unknown(reason : impl ToString) -> Self {
+        UnknownResult(reason.to_string())
+    }
+
+    // Verdict, if any, certain or statistical
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            BoolResult(b) | StatisticalBoolResult(b, _) | WitnessResult(b, _) => Some(*b),
+            _ => None
+        }
+    }
+
+    pub fn witness(&self) -> Option<&TimedTrace> {
+        match self {
+            WitnessResult(_, w) => Some(w),
+            _ => None
+        }
+    }
+
+    // Probability of the result being wrong : 0 for exact results, 1 - confidence for estimations
+    pub fn error_bound(&self) -> f64 {
+        match self {
+            StatisticalBoolResult(_, e) => *e,
+            ProbabilityResult { confidence, .. } | NumericResult { confidence, .. } => 1.0 - confidence,
+            SolverError | UnknownResult(_) => 1.0,
+            _ => 0.0
+        }
+    }
+
+    pub fn confidence(&self) -> f64 {
+        1.0 - self.error_bound()
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.error_bound() == 0.0
+    }
+
+    pub fn is_conclusive(&self) -> bool {
+        !matches!(self, SolverError | UnknownResult(_))
+    }
+
+    // Combination of two verdicts when a query decomposes into subproblems.
+    // A statistical conjunction is wrong if a true verdict is wrong, or if every false verdict is.
+    pub fn and(self, other : Self) -> Self {
+        match (self, other) {
+            (SolverError, _) | (_, SolverError) => SolverError,
+            (BoolResult(false), _) | (_, BoolResult(false)) => BoolResult(false),
+            (WitnessResult(false, w), _) | (_, WitnessResult(false, w)) => WitnessResult(false, w),
+            (BoolResult(true), x) | (x, BoolResult(true)) => x,
+            (WitnessResult(true, _), x) | (x, WitnessResult(true, _)) => x,
+            (StatisticalBoolResult(a, ea), StatisticalBoolResult(b, eb)) => match (a, b) {
+                (true, true) => StatisticalBoolResult(true, (ea + eb).min(1.0)),
+                (false, false) => StatisticalBoolResult(false, ea.min(eb)),
+                (false, true) => StatisticalBoolResult(false, ea),
+                (true, false) => StatisticalBoolResult(false, eb),
+            },
+            // Frechet bounds, estimate assumes independence. Confidence follows from the union bound.
+            (
+                ProbabilityResult { estimate : p1, interval : (l1, u1), confidence : c1 },
+                ProbabilityResult { estimate : p2, interval : (l2, u2), confidence : c2 }
+            ) => {
+                let interval = ((l1 + l2 - 1.0).max(0.0), u1.min(u2));
+                ProbabilityResult {
+                    estimate : (p1 * p2).clamp(interval.0, interval.1),
+                    interval,
+                    confidence : (c1 + c2 - 1.0).max(0.0)
+                }
+            },
+            (UnknownResult(r), _) | (_, UnknownResult(r)) => UnknownResult(r),
+            (a, b) => UnknownResult(format!("Unable to combine {} and {}", a, b))
+        }
+    }
+
+    pub fn or(self, other : Self) -> Self {
+        !((!self).and(!other))
+    }
+
+}
+
+impl Not for SolverResult {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        match self {
+            BoolResult(b) => BoolResult(!b),
+            StatisticalBoolResult(b, e) => StatisticalBoolResult(!b, e),
+            WitnessResult(b, w) => WitnessResult(!b, w),
+            ProbabilityResult { estimate, interval, confidence } => ProbabilityResult {
+                estimate : 1.0 - estimate,
+                interval : (1.0 - interval.1, 1.0 - interval.0),
+                confidence
+            },
+            x => x
+        }
+    }
+}
+
+impl BitAnd for SolverResult {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.and(rhs)
+    }
+}
+
+impl BitOr for SolverResult {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.or(rhs)
+    }
+}
+
+impl Display for SolverResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverError => write!(f, "Error"),
+            BoolResult(b) => write!(f, "{}", b),
+            IntResult(i) => write!(f, "{}", i),
+            FloatResult(x) => write!(f, "{}", x),
+            StateResult(_) => write!(f, "State"),
+            TraceResult(t) => write!(f, "[{}]", t.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(", ")),
+            StrategyResult => write!(f, "Strategy"),
+            WitnessResult(b, w) => write!(f, "{} (witness : {})", b, w),
+            StatisticalBoolResult(b, e) => write!(f, "{} (error <= {})", b, e),
+            ProbabilityResult { estimate, interval, confidence } |
+            NumericResult { estimate, interval, confidence } =>
+                write!(f, "{} in [{}, {}] ({}% confidence)", estimate, interval.0, interval.1, confidence * 100.0),
+            UnknownResult(r) => write!(f, "Unknown : {}", r),
+        }
+    }
+}
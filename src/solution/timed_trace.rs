@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Label;
+
+/// Concrete run of a model : each action is fired after waiting the associated delay
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TimedTrace {
+    pub steps : Vec<(f64, Label)>,
+}
+
+impl TimedTrace {
+
+    pub fn new() -> Self {
+        TimedTrace { steps : Vec::new() }
+    }
+
+    pub fn push(&mut self, delay : f64, action : Label) {
+        self.steps.push((delay, action));
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn total_time(&self) -> f64 {
+        self.steps.iter().map(|(d, _)| d).sum()
+    }
+
+    pub fn actions(&self) -> Vec<Label> {
+        self.steps.iter().map(|(_, a)| a.clone()).collect()
+    }
+
+}
+
+impl Display for TimedTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let steps : Vec<String> = self.steps.iter().map(|(d, a)| format!("({}) {}", d, a)).collect();
+        write!(f, "[{}]", steps.join(" -> "))
+    }
+}
@@ -1,11 +1,19 @@
 mod petri_class_graph;
 mod petri_partial_observation;
+mod markov_automaton_subclass;
+mod untimed_projection;
+mod observer_synthesis;
+mod colored_unfolding;
 use std::{any::Any, fmt::Display};
 
 pub mod observation;
 
 pub use petri_class_graph::PetriClassGraphTranslation;
 pub use petri_partial_observation::PetriPartialObservation;
+pub use markov_automaton_subclass::MarkovAutomatonSubclassTranslation;
+pub use untimed_projection::UntimedProjection;
+pub use observer_synthesis::{ObserverAutomaton, ObserverSynthesis};
+pub use colored_unfolding::ColoredUnfolding;
 
 use crate::models::{lbl, model_context::ModelContext, Label, Model, ModelState};
 
@@ -0,0 +1,112 @@
+use std::any::Any;
+
+use crate::{models::{colored_petri::ColoredPetriNet, lbl, model_context::ModelContext, petri::PetriNet, Model, ModelState}, verification::Verifiable};
+
+use super::{Translation, TranslationError, TranslationMeta, TranslationResult, TranslationType::Unspecified};
+
+use crate::log::*;
+
+/// Unfolding of a colored Petri net into the equivalent plain Time Petri net.
+/// Unfolded places and transitions keep the names of the colored net, states of both models are mapped by names.
+pub struct ColoredUnfolding {
+    pub initial_state : ModelState,
+    pub context : ModelContext,
+    pub source_context : ModelContext,
+    pub unfolded : Option<PetriNet>,
+}
+
+impl ColoredUnfolding {
+
+    pub fn new() -> Self {
+        ColoredUnfolding {
+            initial_state : ModelState::new(0, 0),
+            context : ModelContext::new(),
+            source_context : ModelContext::new(),
+            unfolded : None,
+        }
+    }
+
+    fn map_state(&self, state : &ModelState, from : &ModelContext, to : &ModelContext) -> Option<ModelState> {
+        let mut mapped = to.make_empty_state();
+        for var in to.get_vars() {
+            let source = from.get_var(&var.get_name())?;
+            mapped.discrete.set(&var, state.evaluate_var(&source));
+        }
+        for clock in to.get_clocks() {
+            let source = from.get_clock(&clock.get_name())?;
+            mapped.set_clock(&clock, state.get_clock_value(&source));
+        }
+        mapped.storages = state.storages.clone();
+        mapped.deadlocked = state.deadlocked;
+        Some(mapped)
+    }
+
+}
+
+impl Translation for ColoredUnfolding {
+
+    fn get_meta(&self) -> TranslationMeta {
+        TranslationMeta {
+            name : lbl("ColoredUnfolding"),
+            description : String::from("Unfolding of a colored Petri net into a plain Time Petri net"),
+            input : lbl("CPN"),
+            output : lbl("TPN"),
+            translation_type : Unspecified,
+        }
+    }
+
+    fn translate(&mut self, base : &dyn Any, ctx : &ModelContext, initial_state : &ModelState) -> TranslationResult {
+        pending("Unfolding colored Petri net...");
+        let Some(colored) = base.downcast_ref::<ColoredPetriNet>() else {
+            error("Unable to unfold model !");
+            return Err(TranslationError(String::from("Input model is not a colored Petri net")));
+        };
+        let Ok(mut unfolded) = colored.unfold() else {
+            error("Unable to unfold colored Petri net !");
+            return Err(TranslationError(String::from("Ill-typed arc expressions")));
+        };
+        self.context = ModelContext::new();
+        if unfolded.compile(&mut self.context).is_err() {
+            error("Unable to compile unfolded net !");
+            return Err(TranslationError(String::from("Cannot compile unfolded net")));
+        }
+        self.source_context = ctx.clone();
+        let Some(initial) = self.map_state(initial_state, ctx, &self.context) else {
+            error("Unable to map initial state !");
+            return Err(TranslationError(String::from("Unfolded net does not share the variables of the colored net")));
+        };
+        self.initial_state = initial;
+        self.unfolded = Some(unfolded);
+        positive(format!("Colored Petri net unfolded ! [{} places, {} transitions]", self.context.n_vars(), self.context.n_actions()));
+        Ok(())
+    }
+
+    fn get_translated(&mut self) -> (&mut dyn Any, &ModelContext, &ModelState) {
+        (match &mut self.unfolded {
+            None => panic!("No unfolded net computed !"),
+            Some(net) => net
+        }, &self.context, &self.initial_state)
+    }
+
+    fn get_translated_model(&mut self) -> (&mut dyn Model, &ModelContext, &ModelState) {
+        (match &mut self.unfolded {
+            None => panic!("No unfolded net computed !"),
+            Some(net) => net
+        }, &self.context, &self.initial_state)
+    }
+
+    fn back_translate(&self, state : ModelState) -> Option<ModelState> {
+        self.map_state(&state, &self.context, &self.source_context)
+    }
+
+    fn forward_translate(&self, state : ModelState) -> Option<ModelState> {
+        self.map_state(&state, &self.source_context, &self.context)
+    }
+
+}
+
+impl Default for ColoredUnfolding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,81 @@
+use std::any::Any;
+
+use crate::models::{lbl, markov::{markov_automaton::MarkovAutomaton, markov_chain::MarkovChain}, model_context::ModelContext, Model, ModelState};
+
+use super::{Translation, TranslationError, TranslationMeta, TranslationResult, TranslationType::Unspecified};
+
+use crate::log::*;
+
+/// Translates Markov automata that are MDPs or CTMCs to Markov chains.
+/// States keep their labels, so states of both models share the same variables.
+pub struct MarkovAutomatonSubclassTranslation {
+    pub initial_state : ModelState,
+    pub context : ModelContext,
+    pub chain : Option<MarkovChain>,
+}
+
+impl MarkovAutomatonSubclassTranslation {
+    pub fn new() -> Self {
+        MarkovAutomatonSubclassTranslation {
+            initial_state : ModelState::new(0, 0),
+            context : ModelContext::new(),
+            chain : None,
+        }
+    }
+}
+
+impl Translation for MarkovAutomatonSubclassTranslation {
+
+    fn get_meta(&self) -> TranslationMeta {
+        TranslationMeta {
+            name : lbl("MarkovAutomatonSubclassTranslation"),
+            description : String::from("Translates MDP or CTMC Markov automata to Markov chains"),
+            input : lbl("MarkovAutomaton"),
+            output : lbl("MarkovChain"),
+            translation_type : Unspecified,
+        }
+    }
+
+    fn translate(&mut self, base : &dyn Any, _ : &ModelContext, initial_state : &ModelState) -> TranslationResult {
+        pending("Translating Markov automaton...");
+        let Some(automaton) = base.downcast_ref::<MarkovAutomaton>() else {
+            error("Unable to translate Markov automaton !");
+            return Err(TranslationError(String::from("Cannot parse a Markov automaton from input parameter")));
+        };
+        let Some(mut chain) = automaton.to_markov_chain() else {
+            error("Unable to translate Markov automaton !");
+            return Err(TranslationError(String::from("Markov automaton is neither an MDP nor a CTMC")));
+        };
+        self.context = ModelContext::new();
+        if chain.compile(&mut self.context).is_err() {
+            error("Unable to compile Markov chain !");
+            return Err(TranslationError(String::from("Cannot compile translated Markov chain")));
+        }
+        let current = automaton.get_current_state(initial_state).label.clone();
+        self.initial_state = self.context.make_initial_state(&chain, [(current, 1)].into());
+        positive("Markov automaton translated !");
+        self.chain = Some(chain);
+        Ok(())
+    }
+
+    fn get_translated(&mut self) -> (&mut dyn Any, &ModelContext, &ModelState) {
+        (match &mut self.chain {
+            None => panic!("No Markov chain computed !"),
+            Some(c) => c
+        }, &self.context, &self.initial_state)
+    }
+
+    fn get_translated_model(&mut self) -> (&mut dyn Model, &ModelContext, &ModelState) {
+        (match &mut self.chain {
+            None => panic!("No Markov chain computed !"),
+            Some(c) => c
+        }, &self.context, &self.initial_state)
+    }
+
+}
+
+impl Default for MarkovAutomatonSubclassTranslation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
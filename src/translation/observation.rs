@@ -128,6 +128,7 @@ impl<T : Model> PartialObservation<T> {
             Action::Epsilon => Action::Epsilon,
             Action::Internal(_) => result,
             Action::Sync(_, a, b) => result.sync(Action::clone(a), Action::clone(b)),
+            Action::Broadcast(_, o, inputs) => result.broadcast(Action::clone(o), inputs.clone()),
             Action::WithData(_, d) => result.with_data(d.clone())
         }
     }
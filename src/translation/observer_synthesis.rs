@@ -0,0 +1,202 @@
+use std::any::Any;
+
+use crate::{models::{expressions::{Condition, Expr}, lbl, model_context::ModelContext, model_var::ModelVar, petri::{PetriNet, PetriPlace, PetriTransition}, time::{TimeBound, TimeInterval}, Label, Model, ModelState}, verification::{query::{Quantifier, Query, StateLogic}, Verifiable}};
+
+use super::{Translation, TranslationError, TranslationMeta, TranslationResult, TranslationType::Unspecified};
+
+use crate::log::*;
+
+/// Observer of the runs of a model : locations linked by edges guarded by conditions on the model state.
+/// Edges are urgent, they are taken as soon as their guard holds, and the observer never changes the model variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObserverAutomaton {
+    pub locations : Vec<Label>,
+    pub edges : Vec<(usize, Condition, usize)>,
+    pub initial : usize,
+    pub accepting : usize,
+}
+
+impl ObserverAutomaton {
+
+    // E F c is reduced to the reachability of the accepting location, entered when c holds, and A G c to its avoidance,
+    // the accepting location being entered when c is violated. Returns the observer and the reduced query.
+    pub fn from_query(query : &Query) -> Result<(Self, Query), TranslationError> {
+        if !Self::is_observable(&query.condition) {
+            return Err(TranslationError(String::from("Observers only watch state conditions on discrete variables")));
+        }
+        let (guard, reduced_quantifier, reduced_logic) = match (query.quantifier, query.logic) {
+            (Quantifier::Exists, StateLogic::Finally) => (query.condition.clone(), Quantifier::Exists, StateLogic::Finally),
+            (Quantifier::ForAll, StateLogic::Globally) => (Condition::Not(Box::new(query.condition.clone())), Quantifier::ForAll, StateLogic::Globally),
+            _ => return Err(TranslationError(String::from("Only E F and A G queries reduce to reachability")))
+        };
+        let observer = ObserverAutomaton {
+            locations : vec![lbl("_observer_watch"), lbl("_observer_accept")],
+            edges : vec![(0, guard, 1)],
+            initial : 0,
+            accepting : 1
+        };
+        let accepted = Condition::Evaluation(Expr::Var(ModelVar::name(observer.accepting_label())));
+        let condition = match reduced_logic {
+            StateLogic::Globally => Condition::Not(Box::new(accepted)),
+            _ => accepted
+        };
+        let mut reduced = Query::new(reduced_quantifier, reduced_logic, condition);
+        reduced.run_bound = query.run_bound.clone();
+        Ok((observer, reduced))
+    }
+
+    fn is_observable(condition : &Condition) -> bool {
+        fn no_deadlock(condition : &Condition) -> bool {
+            match condition {
+                Condition::Deadlock => false,
+                Condition::Not(c) => no_deadlock(c),
+                Condition::And(c1, c2) | Condition::Or(c1, c2) | Condition::Implies(c1, c2) => no_deadlock(c1) && no_deadlock(c2),
+                _ => true
+            }
+        }
+        condition.is_state_condition() &&
+            !condition.contains_clock_proposition() &&
+            !condition.contains_nested() &&
+            no_deadlock(condition)
+    }
+
+    pub fn accepting_label(&self) -> Label {
+        self.locations[self.accepting].clone()
+    }
+
+    // Locations become places, marked when the observer is in them, and edges become [0,0] transitions guarded by
+    // the edge condition. Returned uncompiled.
+    pub fn compose(&self, net : &PetriNet) -> Result<PetriNet, TranslationError> {
+        let mut structure = net.get_structure();
+        if structure.places.iter().any(|p| self.locations.contains(&p.name)) {
+            return Err(TranslationError(String::from("Observer locations collide with the places of the net")));
+        }
+        for location in self.locations.iter() {
+            structure.places.push(PetriPlace::new(location.clone()));
+        }
+        for (i, (from, guard, to)) in self.edges.iter().enumerate() {
+            let mut transition = PetriTransition::new_uncontrollable(
+                lbl(&format!("_observer_edge_{}", i)),
+                vec![self.locations[*from].clone()],
+                vec![self.locations[*to].clone()],
+                TimeInterval(TimeBound::Large(0), TimeBound::Large(0))
+            );
+            transition.guard = guard.clone();
+            structure.transitions.push(transition);
+        }
+        Ok(PetriNet::from(structure))
+    }
+
+}
+
+/// Composes a Petri net with the observer of a query, so that the query can be solved as a reachability one
+/// (see `reduced_query`) by the existing solutions. The observer is query-specific, hence built from the query.
+pub struct ObserverSynthesis {
+    pub observer : ObserverAutomaton,
+    pub reduced : Query,
+    pub initial_state : ModelState,
+    pub context : ModelContext,
+    pub source_context : ModelContext,
+    pub model : Option<PetriNet>,
+}
+
+impl ObserverSynthesis {
+
+    pub fn new(query : &Query) -> Result<Self, TranslationError> {
+        let (observer, reduced) = ObserverAutomaton::from_query(query)?;
+        Ok(ObserverSynthesis {
+            observer,
+            reduced,
+            initial_state : ModelState::new(0, 0),
+            context : ModelContext::new(),
+            source_context : ModelContext::new(),
+            model : None,
+        })
+    }
+
+    // Reachability query on the composed net, mapped to its context once translated
+    pub fn reduced_query(&self) -> Query {
+        let mut query = self.reduced.clone();
+        if self.model.is_some() {
+            let _ = query.apply_to(&self.context);
+        }
+        query
+    }
+
+    // Discrete variables only, observer locations being unmarked in the initial state
+    fn map_state(&self, state : &ModelState, from : &ModelContext, to : &ModelContext) -> Option<ModelState> {
+        let mut mapped = to.make_empty_state();
+        for var in to.get_vars() {
+            if self.observer.locations.contains(&var.get_name()) {
+                continue;
+            }
+            let source = from.get_var(&var.get_name())?;
+            mapped.discrete.set(&var, state.evaluate_var(&source));
+        }
+        mapped.deadlocked = state.deadlocked;
+        Some(mapped)
+    }
+
+}
+
+impl Translation for ObserverSynthesis {
+
+    fn get_meta(&self) -> TranslationMeta {
+        TranslationMeta {
+            name : lbl("ObserverSynthesis"),
+            description : String::from("Composes a Petri net with the observer of a query, reducing it to reachability"),
+            input : lbl("TPN"),
+            output : lbl("TPN"),
+            translation_type : Unspecified,
+        }
+    }
+
+    fn translate(&mut self, base : &dyn Any, ctx : &ModelContext, initial_state : &ModelState) -> TranslationResult {
+        pending("Composing observer with the model...");
+        let Some(petri) = base.downcast_ref::<PetriNet>() else {
+            error("Unable to compose observer !");
+            return Err(TranslationError(String::from("Cannot parse a Petri net from input parameter")));
+        };
+        let mut composed = self.observer.compose(petri)?;
+        self.context = ModelContext::new();
+        if composed.compile(&mut self.context).is_err() {
+            error("Unable to compile observed model !");
+            return Err(TranslationError(String::from("Cannot compile the composition with the observer")));
+        }
+        self.source_context = ctx.clone();
+        self.model = Some(composed);
+        let Some(mut initial) = self.map_state(initial_state, ctx, &self.context) else {
+            error("Unable to map initial state !");
+            self.model = None;
+            return Err(TranslationError(String::from("Observed model does not share the variables of the input model")));
+        };
+        let Some(watch) = self.context.get_var(&self.observer.locations[self.observer.initial]) else {
+            self.model = None;
+            return Err(TranslationError(String::from("Observer initial location not compiled")));
+        };
+        initial.discrete.set(&watch, 1);
+        let model = self.model.as_ref().unwrap();
+        self.initial_state = model.init_initial_clocks(initial);
+        positive("Observer composed !");
+        Ok(())
+    }
+
+    fn get_translated(&mut self) -> (&mut dyn Any, &ModelContext, &ModelState) {
+        (match &mut self.model {
+            None => panic!("No observed model computed !"),
+            Some(m) => m
+        }, &self.context, &self.initial_state)
+    }
+
+    fn get_translated_model(&mut self) -> (&mut dyn Model, &ModelContext, &ModelState) {
+        (match &mut self.model {
+            None => panic!("No observed model computed !"),
+            Some(m) => m
+        }, &self.context, &self.initial_state)
+    }
+
+    fn back_translate(&self, state : ModelState) -> Option<ModelState> {
+        self.map_state(&state, &self.context, &self.source_context)
+    }
+
+}
@@ -0,0 +1,117 @@
+use std::any::Any;
+
+use crate::{models::{lbl, markov::markov_automaton::MarkovAutomaton, model_context::ModelContext, petri::PetriNet, Model, ModelState}, verification::Verifiable};
+
+use super::{Translation, TranslationError, TranslationMeta, TranslationResult, TranslationType::Observation};
+
+use crate::log::*;
+
+/// Time-abstract projection of a timed model : clocks constraints and sojourn times are dropped, the discrete structure is kept.
+/// The projection over-approximates the timed behaviour, so only time-abstract queries (see `Query::is_time_abstract`) should be routed to it.
+/// Discrete variables keep their names, states of both models are mapped variable by variable.
+pub struct UntimedProjection {
+    pub initial_state : ModelState,
+    pub context : ModelContext,
+    pub source_context : ModelContext,
+    pub model : Option<Box<dyn Model>>,
+}
+
+impl UntimedProjection {
+
+    pub fn new() -> Self {
+        UntimedProjection {
+            initial_state : ModelState::new(0, 0),
+            context : ModelContext::new(),
+            source_context : ModelContext::new(),
+            model : None,
+        }
+    }
+
+    fn project(base : &dyn Any) -> Option<Box<dyn Model>> {
+        if let Some(petri) = base.downcast_ref::<PetriNet>() {
+            return Some(Box::new(petri.untimed()));
+        }
+        if let Some(automaton) = base.downcast_ref::<MarkovAutomaton>() {
+            return Some(Box::new(automaton.untimed()));
+        }
+        None
+    }
+
+    fn map_state(&self, state : &ModelState, from : &ModelContext, to : &ModelContext) -> Option<ModelState> {
+        let model = self.model.as_ref()?;
+        let mut mapped = to.make_empty_state();
+        for var in to.get_vars() {
+            let source = from.get_var(&var.get_name())?;
+            mapped.discrete.set(&var, state.evaluate_var(&source));
+        }
+        mapped.deadlocked = state.deadlocked;
+        let mapped = model.init_initial_clocks(mapped);
+        Some(model.init_initial_storage(mapped))
+    }
+
+}
+
+impl Translation for UntimedProjection {
+
+    fn get_meta(&self) -> TranslationMeta {
+        TranslationMeta {
+            name : lbl("UntimedProjection"),
+            description : String::from("Time-abstract projection of a timed model, dropping clocks and firing intervals"),
+            input : lbl("any"),
+            output : lbl("Untimed"),
+            translation_type : Observation,
+        }
+    }
+
+    fn translate(&mut self, base : &dyn Any, ctx : &ModelContext, initial_state : &ModelState) -> TranslationResult {
+        pending("Computing untimed projection...");
+        let Some(mut model) = Self::project(base) else {
+            error("Unable to compute untimed projection !");
+            return Err(TranslationError(String::from("No untimed projection available for input model")));
+        };
+        self.context = ModelContext::new();
+        if model.compile(&mut self.context).is_err() {
+            error("Unable to compile untimed model !");
+            return Err(TranslationError(String::from("Cannot compile untimed projection")));
+        }
+        self.source_context = ctx.clone();
+        self.model = Some(model);
+        let Some(initial) = self.map_state(initial_state, ctx, &self.context) else {
+            error("Unable to map initial state !");
+            self.model = None;
+            return Err(TranslationError(String::from("Untimed projection does not share the variables of the input model")));
+        };
+        self.initial_state = initial;
+        positive("Untimed projection computed !");
+        Ok(())
+    }
+
+    fn get_translated(&mut self) -> (&mut dyn Any, &ModelContext, &ModelState) {
+        (match &mut self.model {
+            None => panic!("No untimed projection computed !"),
+            Some(m) => m.as_mut()
+        }, &self.context, &self.initial_state)
+    }
+
+    fn get_translated_model(&mut self) -> (&mut dyn Model, &ModelContext, &ModelState) {
+        (match &mut self.model {
+            None => panic!("No untimed projection computed !"),
+            Some(m) => m.as_mut()
+        }, &self.context, &self.initial_state)
+    }
+
+    fn back_translate(&self, state : ModelState) -> Option<ModelState> {
+        self.map_state(&state, &self.context, &self.source_context)
+    }
+
+    fn forward_translate(&self, state : ModelState) -> Option<ModelState> {
+        self.map_state(&state, &self.source_context, &self.context)
+    }
+
+}
+
+impl Default for UntimedProjection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,18 @@
 mod verifier;
 mod verification_iterator;
+mod predicates;
+mod bound_inference;
 
 pub mod query;
 pub mod smc;
+pub mod ltl;
 pub mod text_query_parser;
+pub mod coverage;
+pub mod report;
+pub mod batch_mode;
+pub mod sharded_exploration;
 
-pub use verifier::*;
\ No newline at end of file
+pub use verifier::*;
+pub use predicates::PredicateLibrary;
+pub use report::VerificationReport;
+pub use bound_inference::{BoundInference, InferredBound, infer_bound};
\ No newline at end of file
@@ -0,0 +1,251 @@
+use std::{any::Any, fmt, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{models::{model_context::ModelContext, model_solving_graph::ModelSolvingGraph, Label, ModelState}, solution::SolverResult};
+
+use super::{text_query_parser::parse_query_with, PredicateLibrary, VerificationReport};
+
+use crate::log::*;
+
+// Process exit codes of the batch mode, the most severe outcome of the batch being reported
+pub const EXIT_PASSED : i32 = 0;
+pub const EXIT_FAILED : i32 = 1;
+pub const EXIT_INCONCLUSIVE : i32 = 2;
+pub const EXIT_ERROR : i32 = 3;
+
+/// Outcome a batch query is expected to have : a verdict, or an estimate compared to a threshold
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Expectation {
+    #[serde(rename = "holds")]
+    Holds,
+    #[serde(rename = "violated")]
+    Violated,
+    #[serde(rename = "at_least")]
+    AtLeast(f64),
+    #[serde(rename = "at_most")]
+    AtMost(f64),
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expectation::Holds => write!(f, "true"),
+            Expectation::Violated => write!(f, "false"),
+            Expectation::AtLeast(p) => write!(f, ">= {}", p),
+            Expectation::AtMost(p) => write!(f, "<= {}", p),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BatchOutcome {
+    #[serde(rename = "passed")]
+    Passed,
+    #[serde(rename = "inconclusive")]
+    Inconclusive,
+    #[serde(rename = "failed")]
+    Failed,
+    #[serde(rename = "error")]
+    Error,
+}
+
+impl BatchOutcome {
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            BatchOutcome::Passed => EXIT_PASSED,
+            BatchOutcome::Inconclusive => EXIT_INCONCLUSIVE,
+            BatchOutcome::Failed => EXIT_FAILED,
+            BatchOutcome::Error => EXIT_ERROR,
+        }
+    }
+
+}
+
+/// Query of a batch, given as text with an optional expectation : "A G safe", "P F [t<=100] done expect >= 0.9"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchQuery {
+    pub text : String,
+    pub expectation : Expectation,
+}
+
+impl BatchQuery {
+
+    pub fn new(text : impl ToString, expectation : Expectation) -> Self {
+        BatchQuery { text : text.to_string(), expectation }
+    }
+
+    // Query text, followed by "expect true|false|>= p|<= p". Queries expect to hold by default
+    pub fn parse(line : &str) -> Result<Self, String> {
+        let Some((text, expectation)) = line.rsplit_once(" expect ") else {
+            return Ok(Self::new(line.trim(), Expectation::Holds));
+        };
+        let expectation = expectation.trim();
+        let threshold = |value : &str| value.trim().parse::<f64>().map_err(|e| format!("Invalid threshold '{}' : {}", value.trim(), e));
+        let expectation = match expectation {
+            "true" => Expectation::Holds,
+            "false" => Expectation::Violated,
+            _ if expectation.starts_with(">=") => Expectation::AtLeast(threshold(&expectation[2..])?),
+            _ if expectation.starts_with("<=") => Expectation::AtMost(threshold(&expectation[2..])?),
+            _ => return Err(format!("Invalid expectation '{}'", expectation))
+        };
+        Ok(Self::new(text.trim(), expectation))
+    }
+
+}
+
+/// Result of a batch query : its outcome, and the report of its verification if it could be parsed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub query : BatchQuery,
+    pub outcome : BatchOutcome,
+    pub message : String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report : Option<VerificationReport>,
+}
+
+/// Machine-readable summary of a batch, to gate CI pipelines on its exit code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub model : String,
+    pub strict : bool,
+    pub passed : usize,
+    pub failed : usize,
+    pub inconclusive : usize,
+    pub errors : usize,
+    pub exit_code : i32,
+    pub entries : Vec<BatchEntry>,
+}
+
+impl BatchSummary {
+
+    fn new(model : String, strict : bool, entries : Vec<BatchEntry>) -> Self {
+        let count = |outcome : BatchOutcome| entries.iter().filter(|e| e.outcome == outcome).count();
+        let worst = entries.iter().map(|e| e.outcome).max().unwrap_or(BatchOutcome::Passed);
+        BatchSummary {
+            model, strict,
+            passed : count(BatchOutcome::Passed),
+            failed : count(BatchOutcome::Failed),
+            inconclusive : count(BatchOutcome::Inconclusive),
+            errors : count(BatchOutcome::Error),
+            exit_code : worst.exit_code(),
+            entries
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.exit_code == EXIT_PASSED
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn write(&self, path : impl AsRef<Path>) -> std::io::Result<()> {
+        fs::write(path, self.to_json()?)
+    }
+
+    pub fn log(&self) {
+        info(format!("Batch verification of {} : {} passed, {} failed, {} inconclusive, {} errors",
+            self.model, self.passed, self.failed, self.inconclusive, self.errors));
+        for entry in self.entries.iter() {
+            let line = format!("{} (expect {}) : {}", entry.query.text, entry.query.expectation, entry.message);
+            match entry.outcome {
+                BatchOutcome::Passed => positive(line),
+                BatchOutcome::Inconclusive => warning(line),
+                BatchOutcome::Failed | BatchOutcome::Error => negative(line),
+            }
+        }
+        continue_info(format!("Exit code : {}", self.exit_code));
+    }
+
+}
+
+/// Strict batch verification of a list of queries. Statistical verdicts are only accepted above the minimal
+/// confidence, and estimates only when their whole confidence interval is on the expected side of the threshold.
+/// In strict mode, inconclusive queries fail the batch.
+#[derive(Debug, Clone)]
+pub struct BatchVerification {
+    pub queries : Vec<BatchQuery>,
+    pub strict : bool,
+    pub min_confidence : f64,
+}
+
+impl BatchVerification {
+
+    pub fn new(queries : Vec<BatchQuery>) -> Self {
+        BatchVerification { queries, strict : true, min_confidence : 0.95 }
+    }
+
+    // One query per line, empty lines and lines starting with # being skipped
+    pub fn parse(text : &str) -> Result<Self, String> {
+        let queries = text.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| BatchQuery::parse(line).map_err(|e| format!("Line {} : {}", i + 1, e)))
+            .collect::<Result<Vec<BatchQuery>, String>>()?;
+        Ok(Self::new(queries))
+    }
+
+    pub fn read(path : impl AsRef<Path>) -> Result<Self, String> {
+        Self::parse(&fs::read_to_string(path).map_err(|e| e.to_string())?)
+    }
+
+    pub fn lenient(mut self) -> Self {
+        self.strict = false;
+        self
+    }
+
+    pub fn with_min_confidence(mut self, confidence : f64) -> Self {
+        self.min_confidence = confidence;
+        self
+    }
+
+    pub fn run(&self, solver : &mut ModelSolvingGraph, model : &dyn Any, model_name : &Label, ctx : &ModelContext,
+        initial_state : &ModelState, predicates : &PredicateLibrary) -> BatchSummary
+    {
+        let entries = self.queries.iter().map(|batch_query| {
+            let mut query = match parse_query_with(batch_query.text.clone(), predicates, ctx) {
+                Ok(query) => query,
+                Err(e) => return self.entry(batch_query, BatchOutcome::Error, e.message, None)
+            };
+            if let Err(e) = query.apply_to(ctx) {
+                return self.entry(batch_query, BatchOutcome::Error, e.to_string(), None);
+            }
+            let report = solver.solve_with_report(model, model_name, ctx, initial_state, &query).with_query_text(&batch_query.text);
+            let (outcome, message) = self.judge(batch_query.expectation, &report);
+            self.entry(batch_query, outcome, message, Some(report))
+        }).collect();
+        BatchSummary::new(model_name.to_string(), self.strict, entries)
+    }
+
+    fn entry(&self, query : &BatchQuery, outcome : BatchOutcome, message : String, report : Option<VerificationReport>) -> BatchEntry {
+        if self.strict && outcome == BatchOutcome::Inconclusive {
+            return BatchEntry { query : query.clone(), outcome : BatchOutcome::Failed, message : format!("Inconclusive : {}", message), report };
+        }
+        BatchEntry { query : query.clone(), outcome, message, report }
+    }
+
+    fn judge(&self, expectation : Expectation, report : &VerificationReport) -> (BatchOutcome, String) {
+        if report.result == SolverResult::SolverError.to_string() {
+            return (BatchOutcome::Error, report.result.clone());
+        }
+        if !report.conclusive {
+            return (BatchOutcome::Inconclusive, report.result.clone());
+        }
+        if report.confidence < self.min_confidence {
+            return (BatchOutcome::Inconclusive, format!("{} : confidence below {}%", report.result, self.min_confidence * 100.0));
+        }
+        let outcome = match (expectation, report.verdict, report.interval.or(report.estimate.map(|x| (x, x)))) {
+            (Expectation::Holds, Some(verdict), _) => if verdict { BatchOutcome::Passed } else { BatchOutcome::Failed },
+            (Expectation::Violated, Some(verdict), _) => if verdict { BatchOutcome::Failed } else { BatchOutcome::Passed },
+            (Expectation::AtLeast(p), _, Some((low, high))) =>
+                if low >= p { BatchOutcome::Passed } else if high < p { BatchOutcome::Failed } else { BatchOutcome::Inconclusive },
+            (Expectation::AtMost(p), _, Some((low, high))) =>
+                if high <= p { BatchOutcome::Passed } else if low > p { BatchOutcome::Failed } else { BatchOutcome::Inconclusive },
+            _ => BatchOutcome::Inconclusive
+        };
+        (outcome, report.result.clone())
+    }
+
+}
@@ -0,0 +1,200 @@
+use std::{any::Any, fmt};
+
+use crate::models::{markov::markov_chain::MarkovChain, petri::PetriNet, time::TimeBound, ModelState};
+
+use super::{query::Query, VerificationBound};
+
+/// Run bound inferred from the structure of a model. A sound bound is never reached before a run becomes maximal,
+/// so truncating runs with it keeps the verdict of the unbounded query. Other bounds are heuristics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferredBound {
+    pub bound : VerificationBound,
+    pub sound : bool,
+    pub reason : String,
+}
+
+impl InferredBound {
+
+    pub fn sound(bound : VerificationBound, reason : impl Into<String>) -> Self {
+        InferredBound { bound, sound : true, reason : reason.into() }
+    }
+
+    pub fn heuristic(bound : VerificationBound, reason : impl Into<String>) -> Self {
+        InferredBound { bound, sound : false, reason : reason.into() }
+    }
+
+}
+
+impl fmt::Display for InferredBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.bound {
+            VerificationBound::TimeRunBound(t) => write!(f, "time bound {}", t)?,
+            VerificationBound::StepsRunBound(s) => write!(f, "steps bound {}", s)?,
+            VerificationBound::VarRunBound(v, x) => write!(f, "{} bound {}", v.name, x)?,
+            VerificationBound::RewardRunBound(r, x) => write!(f, "{} reward bound {}", r.name, x.0)?,
+            VerificationBound::CompositeRunBound(_) => write!(f, "bounds {}", self.bound)?,
+            VerificationBound::NoRunBound => write!(f, "no bound")?,
+        }
+        write!(f, " ({} : {})", if self.sound { "sound" } else { "heuristic" }, self.reason)
+    }
+}
+
+pub trait BoundInference {
+
+    fn infer_bound(&self, initial_state : &ModelState) -> InferredBound;
+
+}
+
+// Bound of the models supporting inference, None for the others
+pub fn infer_bound(model : &dyn Any, initial_state : &ModelState) -> Option<InferredBound> {
+    if let Some(chain) = model.downcast_ref::<MarkovChain>() {
+        return Some(chain.infer_bound(initial_state));
+    }
+    if let Some(net) = model.downcast_ref::<PetriNet>() {
+        return Some(net.infer_bound(initial_state));
+    }
+    None
+}
+
+// Number of edges of the longest path starting from each node, None if the graph has a cycle
+fn longest_paths(successors : &[Vec<usize>]) -> Option<Vec<usize>> {
+    fn visit(node : usize, successors : &[Vec<usize>], lengths : &mut [Option<usize>], on_stack : &mut [bool]) -> bool {
+        if lengths[node].is_some() {
+            return true;
+        }
+        if on_stack[node] {
+            return false;
+        }
+        on_stack[node] = true;
+        let mut length = 0;
+        for next in successors[node].iter() {
+            if !visit(*next, successors, lengths, on_stack) {
+                return false;
+            }
+            length = length.max(lengths[*next].unwrap() + 1);
+        }
+        on_stack[node] = false;
+        lengths[node] = Some(length);
+        true
+    }
+    let mut lengths = vec![None; successors.len()];
+    let mut on_stack = vec![false; successors.len()];
+    for node in 0..successors.len() {
+        if !visit(node, successors, &mut lengths, &mut on_stack) {
+            return None;
+        }
+    }
+    Some(lengths.into_iter().map(Option::unwrap).collect())
+}
+
+// Every run is absorbed after at most as many steps as the longest path from the initial node, provided that
+// self-loops only occur on absorbing nodes and that the chain is acyclic otherwise.
+impl BoundInference for MarkovChain {
+
+    fn infer_bound(&self, initial_state : &ModelState) -> InferredBound {
+        let heuristic = InferredBound::heuristic(
+            VerificationBound::StepsRunBound(100 * self.nodes.len()),
+            format!("100 steps per node, {} nodes", self.nodes.len())
+        );
+        if self.nodes_dic.len() != self.nodes.len() {
+            return heuristic;
+        }
+        let mut successors = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut next : Vec<usize> = node.outputs.values().flatten()
+                .filter(|(_, p)| *p > 0.0)
+                .filter_map(|(l, _)| self.nodes_dic.get(l).copied())
+                .collect();
+            next.sort();
+            next.dedup();
+            if next.contains(&i) {
+                if next.len() > 1 {
+                    return heuristic;
+                }
+                next.clear();
+            }
+            successors.push(next);
+        }
+        let Some(lengths) = longest_paths(&successors) else {
+            return heuristic;
+        };
+        let initial = initial_state.argmax(self.get_vars());
+        InferredBound::sound(
+            VerificationBound::StepsRunBound(lengths[initial] + 1),
+            format!("acyclic chain, absorbed after at most {} steps", lengths[initial])
+        )
+    }
+
+}
+
+// Every firing consumes a token and produces at most one further down an acyclic net : a token in p can take part
+// in at most h(p) firings, h(p) being the longest number of transitions downstream of p.
+impl BoundInference for PetriNet {
+
+    fn infer_bound(&self, initial_state : &ModelState) -> InferredBound {
+        let n_places = self.places.len();
+        let mut successors = vec![Vec::new(); n_places + self.transitions.len()];
+        let mut decreasing = self.places_dic.len() == n_places;
+        for (i, transition) in self.transitions.iter().enumerate() {
+            decreasing &= !transition.from.is_empty() && transition.to.len() <= 1 && !transition.has_marking_dependent_weights();
+            if !decreasing {
+                break;
+            }
+            for place in transition.from.iter() {
+                successors[self.places_dic[place].index()].push(n_places + i);
+            }
+            for place in transition.to.iter() {
+                successors[n_places + i].push(self.places_dic[place].index());
+            }
+        }
+        let lengths = if decreasing { longest_paths(&successors) } else { None };
+        let Some(lengths) = lengths else {
+            let uppers : Option<Vec<i32>> = self.transitions.iter().map(|t| match t.interval.1 {
+                TimeBound::Infinite => None,
+                b => Some(b.value())
+            }).collect();
+            return match uppers {
+                Some(uppers) if !uppers.is_empty() => {
+                    let sum : i32 = uppers.iter().sum();
+                    InferredBound::heuristic(
+                        VerificationBound::TimeRunBound(100 * sum.max(1) as u32),
+                        format!("100 times the sum of upper bounds, {}", sum)
+                    )
+                },
+                _ => InferredBound::heuristic(
+                    VerificationBound::StepsRunBound(100 * self.transitions.len().max(1)),
+                    format!("100 steps per transition, {} transitions", self.transitions.len())
+                )
+            };
+        };
+        // Paths from a place alternate places and transitions, every other edge entering a transition
+        let firings : usize = self.places.iter().enumerate().map(|(i, p)| {
+            p.tokens(initial_state).max(0) as usize * lengths[i].div_ceil(2)
+        }).sum();
+        InferredBound::sound(
+            VerificationBound::StepsRunBound(firings + 1),
+            format!("acyclic token-decreasing net, at most {} firings", firings)
+        )
+    }
+
+}
+
+impl Query {
+
+    // Same query truncated by the bound inferred for the model, if it had no run bound and the model supports inference.
+    // Clocks may still change once the run is maximal, so bounds are never sound for clock conditions.
+    pub fn with_inferred_bound(&self, model : &dyn Any, initial_state : &ModelState) -> Option<(Query, InferredBound)> {
+        if self.run_bound != VerificationBound::NoRunBound {
+            return None;
+        }
+        let mut inferred = infer_bound(model, initial_state)?;
+        if self.condition.contains_clock_proposition() && inferred.sound {
+            inferred.sound = false;
+            inferred.reason.push_str(", clock condition");
+        }
+        let mut query = self.clone();
+        query.run_bound = inferred.bound.clone();
+        Some((query, inferred))
+    }
+
+}
@@ -0,0 +1,166 @@
+use std::{collections::HashMap, fmt::Display, sync::{atomic::{AtomicBool, Ordering}, Mutex}};
+
+use crate::models::expressions::Condition;
+
+use super::VerificationStatus;
+
+// Instrumentation of atomic propositions : when enabled, every literal evaluated by `Condition::evaluate` is recorded.
+// Disabled by default, the only overhead being an atomic load per literal evaluation.
+
+static COVERAGE_ENABLED : AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref COVERAGE : Mutex<CoverageReport> = Mutex::new(CoverageReport::new());
+}
+
+/// Number of times a literal evaluated to true and to false
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiteralCoverage {
+    pub origin : String,
+    pub literal : Condition,
+    pub seen_true : usize,
+    pub seen_false : usize,
+}
+
+impl LiteralCoverage {
+
+    pub fn is_covered(&self) -> bool {
+        self.seen_true > 0 && self.seen_false > 0
+    }
+
+    // Never evaluated to true : the guard or proposition is dead
+    pub fn is_dead(&self) -> bool {
+        self.seen_true == 0
+    }
+
+    // Never evaluated to false : the guard or proposition is useless
+    pub fn is_constant(&self) -> bool {
+        self.seen_true > 0 && self.seen_false == 0
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CoverageReport {
+    pub literals : Vec<LiteralCoverage>,
+    indexes : HashMap<Condition, usize>,
+}
+
+impl CoverageReport {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn literal_mut(&mut self, origin : &str, literal : &Condition) -> &mut LiteralCoverage {
+        let index = match self.indexes.get(literal) {
+            Some(i) => *i,
+            None => {
+                self.literals.push(LiteralCoverage {
+                    origin : origin.to_string(),
+                    literal : literal.clone(),
+                    seen_true : 0,
+                    seen_false : 0
+                });
+                self.indexes.insert(literal.clone(), self.literals.len() - 1);
+                self.literals.len() - 1
+            }
+        };
+        &mut self.literals[index]
+    }
+
+    pub fn register(&mut self, origin : &str, condition : &Condition) {
+        for literal in literals(condition) {
+            self.literal_mut(origin, literal);
+        }
+    }
+
+    pub fn record(&mut self, literal : &Condition, status : VerificationStatus) {
+        let coverage = self.literal_mut("unregistered", literal);
+        match status {
+            VerificationStatus::Verified => coverage.seen_true += 1,
+            VerificationStatus::Unverified => coverage.seen_false += 1,
+            VerificationStatus::Maybe => ()
+        }
+    }
+
+    pub fn dead_literals(&self) -> Vec<&LiteralCoverage> {
+        self.literals.iter().filter(|l| l.is_dead()).collect()
+    }
+
+    pub fn constant_literals(&self) -> Vec<&LiteralCoverage> {
+        self.literals.iter().filter(|l| l.is_constant()).collect()
+    }
+
+    // Ratio of literals seen both true and false
+    pub fn coverage(&self) -> f64 {
+        if self.literals.is_empty() {
+            return 1.0;
+        }
+        let covered = self.literals.iter().filter(|l| l.is_covered()).count();
+        covered as f64 / self.literals.len() as f64
+    }
+
+}
+
+impl Display for CoverageReport {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, " [.] Coverage : {:.1}% of {} literals", self.coverage() * 100.0, self.literals.len())?;
+        for l in self.literals.iter() {
+            let tag = if l.is_dead() { "dead" } else if l.is_constant() { "constant" } else { "covered" };
+            writeln!(f, " | [{}] {} : {:?} (true : {}, false : {})", tag, l.origin, l.literal, l.seen_true, l.seen_false)?;
+        }
+        Ok(())
+    }
+
+}
+
+// Atomic propositions of a condition, nested queries included
+pub fn literals(condition : &Condition) -> Vec<&Condition> {
+    match condition {
+        Condition::Deadlock | Condition::Evaluation(_) | Condition::Proposition(_, _, _) => vec![condition],
+        Condition::Not(c) | Condition::Next(c) => literals(c),
+        Condition::Once(c) | Condition::Historically(c) | Condition::Previously(c) => literals(c),
+        Condition::And(c1, c2) |
+        Condition::Or(c1, c2) |
+        Condition::Implies(c1, c2) |
+        Condition::Until(c1, c2) |
+        Condition::Release(c1, c2) |
+        Condition::WeakUntil(c1, c2) |
+        Condition::Since(c1, c2) => {
+            let mut res = literals(c1);
+            res.append(&mut literals(c2));
+            res
+        },
+        Condition::Nested(q) => literals(&q.condition),
+        _ => Vec::new()
+    }
+}
+
+pub fn is_coverage_enabled() -> bool {
+    COVERAGE_ENABLED.load(Ordering::Relaxed)
+}
+
+// Clears previous measurements and starts recording
+pub fn start_coverage() {
+    *COVERAGE.lock().unwrap() = CoverageReport::new();
+    COVERAGE_ENABLED.store(true, Ordering::Relaxed);
+}
+
+// Stops recording and returns the measurements
+pub fn stop_coverage() -> CoverageReport {
+    COVERAGE_ENABLED.store(false, Ordering::Relaxed);
+    std::mem::take(&mut *COVERAGE.lock().unwrap())
+}
+
+// Registers the literals of a guard or query, so that the ones never evaluated are reported as well
+pub fn register_coverage(origin : impl ToString, condition : &Condition) {
+    COVERAGE.lock().unwrap().register(&origin.to_string(), condition);
+}
+
+pub(crate) fn record_literal(literal : &Condition, status : VerificationStatus) {
+    if is_coverage_enabled() {
+        COVERAGE.lock().unwrap().record(literal, status);
+    }
+}
@@ -0,0 +1,37 @@
+mod ltl_formula;
+mod buchi;
+mod nested_dfs;
+
+pub use ltl_formula::LtlFormula;
+pub use buchi::{BuchiAutomaton, BuchiState};
+pub use nested_dfs::{Lasso, NestedDfs};
+
+use crate::models::{Model, ModelState};
+
+use crate::log::*;
+
+#[derive(Debug, Clone)]
+pub struct LtlResult {
+    pub holds : bool,
+    // Counterexample of a universal formula, or witness of an existential one
+    pub lasso : Option<Lasso>,
+    pub explored : usize,
+}
+
+// Checks that every run of the model satisfies the formula, by searching an accepting run of the negation
+pub fn check_for_all(model : &dyn Model, initial : &ModelState, formula : &LtlFormula) -> LtlResult {
+    let buchi = BuchiAutomaton::from_formula(&formula.negation());
+    continue_info(format!("Büchi automaton : [{}] states", buchi.n_states()));
+    let mut dfs = NestedDfs::new(model, &buchi);
+    let lasso = dfs.search(initial);
+    LtlResult { holds : lasso.is_none(), lasso, explored : dfs.explored }
+}
+
+// Checks that some run of the model satisfies the formula
+pub fn check_exists(model : &dyn Model, initial : &ModelState, formula : &LtlFormula) -> LtlResult {
+    let buchi = BuchiAutomaton::from_formula(formula);
+    continue_info(format!("Büchi automaton : [{}] states", buchi.n_states()));
+    let mut dfs = NestedDfs::new(model, &buchi);
+    let lasso = dfs.search(initial);
+    LtlResult { holds : lasso.is_some(), lasso, explored : dfs.explored }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use crate::verification::Verifiable;
+
+use super::LtlFormula::{self, *};
+
+// Incoming edge of the initial nodes
+const INIT : usize = usize::MAX;
+
+// Node of the tableau construction (Gerth, Peled, Vardi, Wolper)
+#[derive(Debug, Clone)]
+struct TableauNode {
+    incoming : HashSet<usize>,
+    new : Vec<LtlFormula>,
+    old : HashSet<LtlFormula>,
+    next : HashSet<LtlFormula>,
+}
+
+impl TableauNode {
+
+    fn with(&self, new : Vec<LtlFormula>, next : Option<LtlFormula>) -> Self {
+        let mut node = self.clone();
+        node.new.extend(new.into_iter().filter(|f| !self.old.contains(f)));
+        if let Some(f) = next {
+            node.next.insert(f);
+        }
+        node
+    }
+
+    fn contradicts(&self, f : &LtlFormula) -> bool {
+        match f {
+            False => true,
+            Atom(c) => self.old.contains(&NegAtom(c.clone())),
+            NegAtom(c) => self.old.contains(&Atom(c.clone())),
+            _ => false
+        }
+    }
+
+}
+
+fn expand(mut node : TableauNode, nodes : &mut Vec<TableauNode>) {
+    let Some(f) = node.new.pop() else {
+        if let Some(existing) = nodes.iter_mut().find(|n| n.old == node.old && n.next == node.next) {
+            existing.incoming.extend(node.incoming);
+            return;
+        }
+        let id = nodes.len();
+        let next = TableauNode {
+            incoming : HashSet::from([id]),
+            new : node.next.iter().cloned().collect(),
+            old : HashSet::new(),
+            next : HashSet::new(),
+        };
+        nodes.push(node);
+        expand(next, nodes);
+        return;
+    };
+    if node.old.contains(&f) {
+        return expand(node, nodes);
+    }
+    if node.contradicts(&f) {
+        return;
+    }
+    node.old.insert(f.clone());
+    match f {
+        True | False | Atom(_) | NegAtom(_) => expand(node, nodes),
+        And(f1, f2) => expand(node.with(vec![*f1, *f2], None), nodes),
+        Next(f1) => expand(node.with(Vec::new(), Some(*f1)), nodes),
+        Or(f1, f2) => {
+            expand(node.with(vec![*f1], None), nodes);
+            expand(node.with(vec![*f2], None), nodes);
+        },
+        Until(ref f1, ref f2) => {
+            expand(node.with(vec![*f1.clone()], Some(f.clone())), nodes);
+            expand(node.with(vec![*f2.clone()], None), nodes);
+        },
+        Release(ref f1, ref f2) => {
+            expand(node.with(vec![*f2.clone()], Some(f.clone())), nodes);
+            expand(node.with(vec![*f1.clone(), *f2.clone()], None), nodes);
+        },
+    }
+}
+
+fn until_subformulas(f : &LtlFormula, found : &mut Vec<LtlFormula>) {
+    match f {
+        Until(f1, f2) => {
+            if !found.contains(f) {
+                found.push(f.clone());
+            }
+            until_subformulas(f1, found);
+            until_subformulas(f2, found);
+        },
+        And(f1, f2) | Or(f1, f2) | Release(f1, f2) => {
+            until_subformulas(f1, found);
+            until_subformulas(f2, found);
+        },
+        Next(f1) => until_subformulas(f1, found),
+        _ => ()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuchiState {
+    pub literals : Vec<LtlFormula>,
+    pub accepting : bool,
+}
+
+/// Büchi automaton whose states are labelled by literals, that must hold on the model state read when entering them
+#[derive(Debug, Clone)]
+pub struct BuchiAutomaton {
+    pub states : Vec<BuchiState>,
+    pub initial : Vec<usize>,
+    pub successors : Vec<Vec<usize>>,
+}
+
+impl BuchiAutomaton {
+
+    pub fn from_formula(formula : &LtlFormula) -> Self {
+        let mut nodes = Vec::new();
+        let root = TableauNode {
+            incoming : HashSet::from([INIT]),
+            new : vec![formula.clone()],
+            old : HashSet::new(),
+            next : HashSet::new(),
+        };
+        expand(root, &mut nodes);
+
+        // Generalized acceptance : one set per until subformula
+        let mut untils = Vec::new();
+        until_subformulas(formula, &mut untils);
+        let acceptance : Vec<Vec<bool>> = untils.iter().map(|u| {
+            let Until(_, f2) = u else { unreachable!() };
+            nodes.iter().map(|n| !n.old.contains(u) || n.old.contains(f2)).collect()
+        }).collect();
+
+        // Degeneralization : states are (node, counter) pairs, counter moving to the next set when the current one is visited
+        let k = acceptance.len().max(1);
+        let index = |node : usize, counter : usize| node * k + counter;
+        let in_set = |node : usize, counter : usize| acceptance.is_empty() || acceptance[counter][node];
+        let mut states = Vec::new();
+        let mut successors = Vec::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let literals : Vec<LtlFormula> = node.old.iter().filter(|f| f.is_literal()).cloned().collect();
+            for counter in 0..k {
+                states.push(BuchiState {
+                    literals : literals.clone(),
+                    accepting : counter == 0 && in_set(i, 0),
+                });
+                let next_counter = if in_set(i, counter) { (counter + 1) % k } else { counter };
+                let succ = nodes.iter().enumerate()
+                    .filter(|(_, n)| n.incoming.contains(&i))
+                    .map(|(j, _)| index(j, next_counter))
+                    .collect();
+                successors.push(succ);
+            }
+        }
+        let initial = nodes.iter().enumerate()
+            .filter(|(_, n)| n.incoming.contains(&INIT))
+            .map(|(i, _)| index(i, 0))
+            .collect();
+        BuchiAutomaton { states, initial, successors }
+    }
+
+    pub fn n_states(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_accepting(&self, q : usize) -> bool {
+        self.states[q].accepting
+    }
+
+    // Whether the automaton can enter q when reading the given state
+    pub fn is_enabled(&self, q : usize, state : &impl Verifiable) -> bool {
+        self.states[q].literals.iter().all(|l| l.holds(state))
+    }
+
+}
@@ -0,0 +1,136 @@
+use std::fmt::Display;
+
+use crate::{models::expressions::Condition, verification::{query::{Quantifier, Query, StateLogic}, Verifiable}};
+
+use LtlFormula::*;
+
+/// LTL formula in negation normal form : negations only apply to state conditions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LtlFormula {
+    True,
+    False,
+    Atom(Condition),
+    NegAtom(Condition),
+    And(Box<LtlFormula>, Box<LtlFormula>),
+    Or(Box<LtlFormula>, Box<LtlFormula>),
+    Next(Box<LtlFormula>),
+    Until(Box<LtlFormula>, Box<LtlFormula>),
+    Release(Box<LtlFormula>, Box<LtlFormula>),
+}
+
+impl LtlFormula {
+
+    pub fn from_condition(condition : &Condition) -> Self {
+        Self::build(condition, false)
+    }
+
+    // Pushes negations down to state conditions
+    fn build(condition : &Condition, negated : bool) -> Self {
+        if condition.is_state_condition() {
+            return match (condition, negated) {
+                (Condition::True, false) | (Condition::False, true) => True,
+                (Condition::True, true) | (Condition::False, false) => False,
+                (c, false) => Atom(c.clone()),
+                (c, true) => NegAtom(c.clone()),
+            }
+        }
+        match condition {
+            Condition::Not(c) => Self::build(c, !negated),
+            Condition::And(c1, c2) if negated => Or(Box::new(Self::build(c1, true)), Box::new(Self::build(c2, true))),
+            Condition::And(c1, c2) => And(Box::new(Self::build(c1, false)), Box::new(Self::build(c2, false))),
+            Condition::Or(c1, c2) if negated => And(Box::new(Self::build(c1, true)), Box::new(Self::build(c2, true))),
+            Condition::Or(c1, c2) => Or(Box::new(Self::build(c1, false)), Box::new(Self::build(c2, false))),
+            Condition::Implies(c1, c2) if negated => And(Box::new(Self::build(c1, false)), Box::new(Self::build(c2, true))),
+            Condition::Implies(c1, c2) => Or(Box::new(Self::build(c1, true)), Box::new(Self::build(c2, false))),
+            Condition::Next(c) => Next(Box::new(Self::build(c, negated))),
+            Condition::Until(c1, c2) if negated => Release(Box::new(Self::build(c1, true)), Box::new(Self::build(c2, true))),
+            Condition::Until(c1, c2) => Until(Box::new(Self::build(c1, false)), Box::new(Self::build(c2, false))),
+            Condition::Release(c1, c2) if negated => Until(Box::new(Self::build(c1, true)), Box::new(Self::build(c2, true))),
+            Condition::Release(c1, c2) => Release(Box::new(Self::build(c1, false)), Box::new(Self::build(c2, false))),
+            // c1 W c2 = c2 R (c1 | c2), and its negation !c2 U (!c1 & !c2)
+            Condition::WeakUntil(c1, c2) if negated => Until(
+                Box::new(Self::build(c2, true)),
+                Box::new(And(Box::new(Self::build(c1, true)), Box::new(Self::build(c2, true))))
+            ),
+            Condition::WeakUntil(c1, c2) => Release(
+                Box::new(Self::build(c2, false)),
+                Box::new(Or(Box::new(Self::build(c1, false)), Box::new(Self::build(c2, false))))
+            ),
+            _ => unreachable!("State conditions are handled above, past-time ones rejected by from_query"),
+        }
+    }
+
+    pub fn finally(f : LtlFormula) -> Self {
+        Until(Box::new(True), Box::new(f))
+    }
+
+    pub fn globally(f : LtlFormula) -> Self {
+        Release(Box::new(False), Box::new(f))
+    }
+
+    // Path formula of a query, and whether it is existentially quantified. Plain LTL queries are universal.
+    // Past-time operators are only supported by run monitoring.
+    pub fn from_query(query : &Query) -> Option<(Self, bool)> {
+        if query.condition.contains_past() {
+            return None;
+        }
+        let exists = match query.quantifier {
+            Quantifier::Exists => true,
+            Quantifier::ForAll | Quantifier::LTL => false,
+            _ => return None,
+        };
+        let formula = Self::from_condition(&query.condition);
+        let formula = match query.logic {
+            StateLogic::Finally => Self::finally(formula),
+            StateLogic::Globally => Self::globally(formula),
+            StateLogic::RawCondition => formula,
+        };
+        Some((formula, exists))
+    }
+
+    pub fn negation(&self) -> Self {
+        match self {
+            True => False,
+            False => True,
+            Atom(c) => NegAtom(c.clone()),
+            NegAtom(c) => Atom(c.clone()),
+            And(f1, f2) => Or(Box::new(f1.negation()), Box::new(f2.negation())),
+            Or(f1, f2) => And(Box::new(f1.negation()), Box::new(f2.negation())),
+            Next(f) => Next(Box::new(f.negation())),
+            Until(f1, f2) => Release(Box::new(f1.negation()), Box::new(f2.negation())),
+            Release(f1, f2) => Until(Box::new(f1.negation()), Box::new(f2.negation())),
+        }
+    }
+
+    pub fn is_literal(&self) -> bool {
+        matches!(self, True | False | Atom(_) | NegAtom(_))
+    }
+
+    // Only meaningful for literals
+    pub fn holds(&self, state : &impl Verifiable) -> bool {
+        match self {
+            True => true,
+            False => false,
+            Atom(c) => c.is_true(state),
+            NegAtom(c) => !c.is_true(state),
+            _ => panic!("Only literals can be evaluated on a single state"),
+        }
+    }
+
+}
+
+impl Display for LtlFormula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            True => write!(f, "true"),
+            False => write!(f, "false"),
+            Atom(c) => write!(f, "{:?}", c),
+            NegAtom(c) => write!(f, "!{:?}", c),
+            And(f1, f2) => write!(f, "({} & {})", f1, f2),
+            Or(f1, f2) => write!(f, "({} | {})", f1, f2),
+            Next(f1) => write!(f, "X {}", f1),
+            Until(f1, f2) => write!(f, "({} U {})", f1, f2),
+            Release(f1, f2) => write!(f, "({} R {})", f1, f2),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::{collections::{HashMap, HashSet}, hash::{DefaultHasher, Hash, Hasher}};
+
+use crate::models::{action::Action, Model, ModelState};
+
+use super::BuchiAutomaton;
+
+type ProductState = (usize, usize);
+
+/// Accepting lasso of the product : a path to an accepting state, and a cycle back to it
+#[derive(Debug, Clone)]
+pub struct Lasso {
+    pub prefix : Vec<ModelState>,
+    pub cycle : Vec<ModelState>,
+}
+
+/// On-the-fly product of a finite model with a Büchi automaton, explored by nested depth-first search
+pub struct NestedDfs<'a> {
+    model : &'a dyn Model,
+    buchi : &'a BuchiAutomaton,
+    states : Vec<ModelState>,
+    seen : HashMap<u64, usize>,
+    model_successors : HashMap<usize, Vec<usize>>,
+    pub explored : usize,
+}
+
+impl<'a> NestedDfs<'a> {
+
+    pub fn new(model : &'a dyn Model, buchi : &'a BuchiAutomaton) -> Self {
+        NestedDfs {
+            model,
+            buchi,
+            states : Vec::new(),
+            seen : HashMap::new(),
+            model_successors : HashMap::new(),
+            explored : 0,
+        }
+    }
+
+    fn state_index(&mut self, state : ModelState) -> usize {
+        let mut s = DefaultHasher::new();
+        state.hash(&mut s);
+        let hash = s.finish();
+        if let Some(i) = self.seen.get(&hash) {
+            return *i;
+        }
+        let index = self.states.len();
+        self.states.push(state);
+        self.seen.insert(hash, index);
+        index
+    }
+
+    // Deadlocked states loop on themselves, so that every run is infinite
+    fn successors_of(&mut self, s : usize) -> Vec<usize> {
+        if let Some(succ) = self.model_successors.get(&s) {
+            return succ.clone();
+        }
+        let state = self.states[s].clone();
+        let mut actions : Vec<Action> = self.model.available_actions(&state).into_iter().collect();
+        actions.sort_by_key(|a| a.get_id());
+        let mut succ = Vec::new();
+        for action in actions {
+            if let Some((next, _)) = self.model.next(state.clone(), action) {
+                let index = self.state_index(next);
+                if !succ.contains(&index) {
+                    succ.push(index);
+                }
+            }
+        }
+        if succ.is_empty() {
+            succ.push(s);
+        }
+        self.model_successors.insert(s, succ.clone());
+        succ
+    }
+
+    fn product_successors(&mut self, (s, q) : ProductState) -> Vec<ProductState> {
+        let mut result = Vec::new();
+        for next_s in self.successors_of(s) {
+            for next_q in self.buchi.successors[q].iter() {
+                if self.buchi.is_enabled(*next_q, &self.states[next_s]) {
+                    result.push((next_s, *next_q));
+                }
+            }
+        }
+        result
+    }
+
+    fn to_states(&self, path : &[ProductState]) -> Vec<ModelState> {
+        path.iter().map(|(s, _)| self.states[*s].clone()).collect()
+    }
+
+    // Searches an accepting lasso starting from the given model state
+    pub fn search(&mut self, initial : &ModelState) -> Option<Lasso> {
+        let s0 = self.state_index(initial.clone());
+        let initials : Vec<ProductState> = self.buchi.initial.iter()
+            .filter(|q| self.buchi.is_enabled(**q, &self.states[s0]))
+            .map(|q| (s0, *q))
+            .collect();
+        let mut visited : HashSet<ProductState> = HashSet::new();
+        let mut visited_nested : HashSet<ProductState> = HashSet::new();
+        for init in initials {
+            if visited.contains(&init) {
+                continue;
+            }
+            visited.insert(init);
+            let mut stack : Vec<(ProductState, Vec<ProductState>)> = vec![(init, self.product_successors(init))];
+            while let Some((node, succ)) = stack.last_mut() {
+                let node = *node;
+                if let Some(next) = succ.pop() {
+                    if visited.insert(next) {
+                        self.explored += 1;
+                        let next_succ = self.product_successors(next);
+                        stack.push((next, next_succ));
+                    }
+                    continue;
+                }
+                // Postorder : look for a cycle through accepting nodes
+                if self.buchi.is_accepting(node.1) {
+                    if let Some(cycle) = self.nested_search(node, &mut visited_nested) {
+                        let prefix : Vec<ProductState> = stack.iter().map(|(n, _)| *n).collect();
+                        return Some(Lasso {
+                            prefix : self.to_states(&prefix),
+                            cycle : self.to_states(&cycle),
+                        });
+                    }
+                }
+                stack.pop();
+            }
+        }
+        None
+    }
+
+    fn nested_search(&mut self, seed : ProductState, visited : &mut HashSet<ProductState>) -> Option<Vec<ProductState>> {
+        let mut stack : Vec<(ProductState, Vec<ProductState>)> = vec![(seed, self.product_successors(seed))];
+        while let Some((_, succ)) = stack.last_mut() {
+            let Some(next) = succ.pop() else {
+                stack.pop();
+                continue;
+            };
+            if next == seed {
+                let mut cycle : Vec<ProductState> = stack.iter().map(|(n, _)| *n).collect();
+                cycle.push(seed);
+                return Some(cycle);
+            }
+            if visited.insert(next) {
+                let next_succ = self.product_successors(next);
+                stack.push((next, next_succ));
+            }
+        }
+        None
+    }
+
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{expressions::{Condition, Expr}, model_context::ModelContext, Label};
+
+use super::{query::Query, text_query_parser::{parse_definition, QueryParsingError, QueryParsingResult}};
+
+use Condition::*;
+
+/// Named sub-formulas, expanded in queries wherever their name is used as a condition.
+/// Names are scoped like model variables : a predicate defined under a context path hides the outer ones.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PredicateLibrary {
+    predicates : HashMap<Label, Condition>,
+}
+
+impl PredicateLibrary {
+
+    pub fn new() -> Self {
+        PredicateLibrary { predicates : HashMap::new() }
+    }
+
+    pub fn define(&mut self, ctx : &ModelContext, name : Label, condition : Condition) {
+        self.predicates.insert(ctx.get_local_name(name), condition);
+    }
+
+    // Definition given as text, such as : def safe := p1 + p2 <= 1
+    pub fn define_text(&mut self, ctx : &ModelContext, definition : &str) -> QueryParsingResult<Label> {
+        let (name, condition) = parse_definition(definition)?;
+        self.define(ctx, name.clone(), condition);
+        Ok(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.predicates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.predicates.is_empty()
+    }
+
+    // Definitions by full name, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&Label, &Condition)> {
+        self.predicates.iter()
+    }
+
+    // Full name of the predicate visible from the current path, the innermost one first
+    pub fn resolve(&self, ctx : &ModelContext, name : &Label) -> Option<Label> {
+        let path = ctx.get_path().to_string();
+        let mut scope : Vec<&str> = path.split('.').filter(|d| !d.is_empty()).collect();
+        loop {
+            let full_name = if scope.is_empty() {
+                name.clone()
+            } else {
+                Label::from(format!("{}.{}", scope.join("."), name))
+            };
+            if self.predicates.contains_key(&full_name) {
+                return Some(full_name);
+            }
+            scope.pop()?;
+        }
+    }
+
+    pub fn get(&self, ctx : &ModelContext, name : &Label) -> Option<&Condition> {
+        self.predicates.get(&self.resolve(ctx, name)?)
+    }
+
+    pub fn expand(&self, ctx : &ModelContext, condition : &Condition) -> QueryParsingResult<Condition> {
+        self.expand_with(ctx, condition, &mut Vec::new())
+    }
+
+    pub fn expand_query(&self, ctx : &ModelContext, query : &Query) -> QueryParsingResult<Query> {
+        let mut query = query.clone();
+        query.condition = self.expand(ctx, &query.condition)?;
+        if let Some(given) = query.given.as_mut() {
+            given.condition = self.expand(ctx, &given.condition)?;
+        }
+        Ok(query)
+    }
+
+    // Every predicate must expand to a finite condition
+    pub fn check(&self, ctx : &ModelContext) -> QueryParsingResult<()> {
+        for condition in self.predicates.values() {
+            self.expand(ctx, condition)?;
+        }
+        Ok(())
+    }
+
+    // Predicates being expanded are stacked, finding one of them again means the definitions are cyclic
+    fn expand_with(&self, ctx : &ModelContext, condition : &Condition, stack : &mut Vec<Label>) -> QueryParsingResult<Condition> {
+        let expand = |c : &Condition, stack : &mut Vec<Label>| self.expand_with(ctx, c, stack).map(Box::new);
+        Ok(match condition {
+            Evaluation(Expr::Var(x)) => match self.resolve(ctx, &x.name) {
+                Some(name) if stack.contains(&name) => {
+                    let cycle : Vec<String> = stack.iter().chain([&name]).map(Label::to_string).collect();
+                    return Err(QueryParsingError::new(format!("Cyclic predicate definition : {}", cycle.join(" -> "))));
+                },
+                Some(name) => {
+                    stack.push(name.clone());
+                    let expanded = self.expand_with(ctx, &self.predicates[&name], stack)?;
+                    stack.pop();
+                    expanded
+                },
+                None => condition.clone()
+            },
+            And(c1, c2) => And(expand(c1, stack)?, expand(c2, stack)?),
+            Or(c1, c2) => Or(expand(c1, stack)?, expand(c2, stack)?),
+            Implies(c1, c2) => Implies(expand(c1, stack)?, expand(c2, stack)?),
+            Until(c1, c2) => Until(expand(c1, stack)?, expand(c2, stack)?),
+            Release(c1, c2) => Release(expand(c1, stack)?, expand(c2, stack)?),
+            WeakUntil(c1, c2) => WeakUntil(expand(c1, stack)?, expand(c2, stack)?),
+            Since(c1, c2) => Since(expand(c1, stack)?, expand(c2, stack)?),
+            Not(c) => Not(expand(c, stack)?),
+            Next(c) => Next(expand(c, stack)?),
+            Once(c) => Once(expand(c, stack)?),
+            Historically(c) => Historically(expand(c, stack)?),
+            Previously(c) => Previously(expand(c, stack)?),
+            Nested(q) => {
+                let mut q = q.clone();
+                q.condition = self.expand_with(ctx, &q.condition, stack)?;
+                Nested(q)
+            },
+            c => c.clone()
+        })
+    }
+
+}
@@ -1,6 +1,9 @@
+mod canonical;
+mod time_adaptation;
+
 use std::{collections::{hash_map::DefaultHasher, HashSet}, hash::{Hash, Hasher}, ops::Not};
 
-use crate::{models::{expressions::{Condition, Expr}, model_context::ModelContext, model_var::MappingResult, Model}, solution::{get_problem_type, ProblemType}};
+use crate::{models::{expressions::{Condition, Expr, PastMemory, PropositionType}, model_context::ModelContext, model_var::MappingResult, Label, Model}, solution::{get_problem_type, ProblemType}};
 
 use super::{verifier::Verifiable, EvaluationState, VerificationBound, VerificationStatus};
 use serde::{Deserialize, Serialize};
@@ -8,7 +11,7 @@ use VerificationStatus::*;
 
 use Condition::*;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Quantifier {
     #[serde(rename="E")]
     Exists,
@@ -16,6 +19,17 @@ pub enum Quantifier {
     ForAll,
     #[serde(rename="P")]
     Probability,
+    #[serde(rename="Pb")]
+    ProbabilityBound(PropositionType, ProbabilityThreshold),
+    // Expected reward, cumulated until the run bound (G) or until the condition is reached (F)
+    #[serde(rename="Er")]
+    ExpectedReward,
+    // Long-run probability of being in a state verifying the condition, compared to a threshold
+    #[serde(rename="S")]
+    SteadyState(PropositionType, ProbabilityThreshold),
+    // Smallest run bound for which the probability of the condition compares to the threshold
+    #[serde(rename="Q")]
+    Quantile(PropositionType, ProbabilityThreshold),
     LTL
 }
 
@@ -27,12 +41,47 @@ impl Not for Quantifier {
         match self {
             Self::Exists => Self::ForAll,
             Self::ForAll => Self::Exists,
+            // Negated along with the logic and condition : not P>=p (F phi) <=> P>1-p (G not phi)
+            Self::ProbabilityBound(prop_type, p) => Self::ProbabilityBound(negate_bound(prop_type), p.complement()),
+            // Same for long-run probabilities : not S>=p [phi] <=> S>1-p [not phi]
+            Self::SteadyState(prop_type, p) => Self::SteadyState(negate_bound(prop_type), p.complement()),
             _ => self
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+fn negate_bound(prop_type : PropositionType) -> PropositionType {
+    match prop_type {
+        PropositionType::GE => PropositionType::GS,
+        PropositionType::GS => PropositionType::GE,
+        PropositionType::LE => PropositionType::LS,
+        PropositionType::LS => PropositionType::LE,
+        PropositionType::EQ => PropositionType::NE,
+        PropositionType::NE => PropositionType::EQ,
+    }
+}
+
+// Probability bound of a quantifier, f64 wrapper to be hashed with the query
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ProbabilityThreshold(pub f64);
+
+impl ProbabilityThreshold {
+
+    pub fn complement(self) -> Self {
+        ProbabilityThreshold(1.0 - self.0)
+    }
+
+}
+
+impl Eq for ProbabilityThreshold { }
+
+impl Hash for ProbabilityThreshold {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StateLogic {
     #[serde(rename="F")]
     Finally, 
@@ -69,10 +118,22 @@ pub struct Query {
     #[serde(skip)]
     pending_conditions : Vec<Condition>,
 
+    // Values of the past-time subformulas on the previous state of the run
+    #[serde(skip)]
+    past_memory : PastMemory,
+
     #[serde(skip)]
     pub collapse_subconditions : bool,
 
-    pub run_bound : VerificationBound
+    pub run_bound : VerificationBound,
+
+    // Reward structure of expected reward queries, the only one defined in the context if None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward : Option<Label>,
+
+    // Conditioning event of probability queries, P(phi | psi), verified on the same runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub given : Option<Box<Query>>
 }
 
 impl Query {
@@ -85,13 +146,24 @@ impl Query {
             total_status : Maybe,
             run_status : Maybe,
             pending_conditions : Vec::new(),
+            past_memory : Vec::new(),
             collapse_subconditions : false,
-            run_bound : VerificationBound::NoRunBound
+            run_bound : VerificationBound::NoRunBound,
+            reward : None,
+            given : None
         }
     }
 
     pub fn end_run(&mut self) {
+        if self.run_status == Maybe {
+            for condition in std::mem::take(&mut self.pending_conditions) {
+                if self.process_result(condition.end_status()) {
+                    break;
+                }
+            }
+        }
         self.pending_conditions.clear();
+        self.past_memory.clear();
         if self.run_status == Maybe {
             self.run_status = match self.logic {
                 Finally => Unverified,
@@ -109,6 +181,14 @@ impl Query {
         }
     }
 
+    // Ends the run with the given verdict, as if its remaining states had been verified
+    pub fn end_run_with(&mut self, status : VerificationStatus) {
+        self.pending_conditions.clear();
+        self.past_memory.clear();
+        self.run_status = status;
+        self.end_run();
+    }
+
     pub fn reset_run(&mut self) {
         self.run_status = Maybe;
     }
@@ -124,17 +204,25 @@ impl Query {
     }
 
     pub fn verify_state(&mut self, state : &impl Verifiable) {
+        self.verify_state_with(state, &mut |_| Maybe)
+    }
+
+    // Same as verify_state, nested queries being answered by the given resolver for the current state
+    pub fn verify_state_with(&mut self, state : &impl Verifiable, nested : &mut dyn FnMut(&Query) -> VerificationStatus) {
         let mut finished = false;
         let mut new_pendings : HashSet<Condition> = HashSet::new(); // Hashset to prevent propagation of Until
         let mut pending = Some(self.condition.clone());
         while pending.is_some() && !finished {
-            let (res, follow) = pending.unwrap().evaluate(state);
+            let (res, follow) = pending.unwrap().evaluate_in(state, nested, &self.past_memory);
             match res {
                 Maybe => { new_pendings.insert(follow.unwrap()); },
                 _ => finished = self.process_result(res)
             }
             pending = self.pending_conditions.pop();
         }
+        if self.condition.contains_past() {
+            self.past_memory = self.condition.update_past(state, &self.past_memory);
+        }
         if finished {
             self.end_run();
             return;
@@ -173,6 +261,7 @@ impl Query {
     pub fn get_evaluation_state(&self, state : &impl Verifiable) -> EvaluationState {
         let mut s = DefaultHasher::new();
         self.pending_conditions.hash(&mut s);
+        self.past_memory.hash(&mut s);
         state.hash(&mut s);
         s.finish()
     }
@@ -180,6 +269,19 @@ impl Query {
     pub fn get_progress_hash(&self) -> u64 {
         let mut s = DefaultHasher::new();
         self.pending_conditions.hash(&mut s);
+        self.past_memory.hash(&mut s);
+        s.finish()
+    }
+
+    // Hash of the query definition, independent of the verification progress
+    pub fn get_query_hash(&self) -> u64 {
+        let mut s = DefaultHasher::new();
+        self.quantifier.hash(&mut s);
+        self.logic.hash(&mut s);
+        self.condition.hash(&mut s);
+        self.run_bound.hash(&mut s);
+        self.reward.hash(&mut s);
+        self.given.hash(&mut s);
         s.finish()
     }
 
@@ -201,6 +303,14 @@ impl Query {
         }
     }
 
+    // Purely logical query : no clock, no time or reward bound and no probability, may be checked on an untimed abstraction
+    pub fn is_time_abstract(&self) -> bool {
+        !self.condition.contains_clock_proposition() &&
+        !self.condition.contains_nested() &&
+        !self.run_bound.bounds().iter().any(|b| matches!(b, VerificationBound::TimeRunBound(_) | VerificationBound::RewardRunBound(_, _))) &&
+        matches!(self.quantifier, Exists | ForAll | LTL)
+    }
+
     pub fn problem_type(&self) -> ProblemType {
         get_problem_type(self.quantifier, self.logic)
     }
@@ -208,20 +318,96 @@ impl Query {
     pub fn apply_to(&mut self, ctx : &ModelContext) -> MappingResult<()> {
         self.condition = self.condition.apply_to(ctx)?;
         self.run_bound = self.run_bound.apply_to(ctx)?;
+        if let Some(given) = self.given.as_mut() {
+            given.apply_to(ctx)?;
+        }
         Ok(())
     }
 
     pub fn accept_visitor(&self, visitor : &mut impl QueryVisitor) {
         visitor.visit_query(self);
         self.condition.accept(visitor);
+        if let Some(given) = &self.given {
+            given.accept_visitor(visitor);
+        }
+    }
+
+    pub fn accept_transformer(&mut self, transformer : &mut (impl QueryTransformer + ?Sized)) {
+        transformer.transform_query(self);
+    }
+
+    pub fn transform_children(&mut self, transformer : &mut (impl QueryTransformer + ?Sized)) {
+        transformer.transform_condition(&mut self.condition);
+        if let Some(given) = self.given.as_mut() {
+            transformer.transform_query(given);
+        }
     }
 
 }
 
+// Queries are hashed by definition, so that they can be nested in conditions
+impl Hash for Query {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_query_hash().hash(state);
+    }
+}
+
+// Text of the query, in the syntax of the text query parser
+impl std::fmt::Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let body = |query : &Query| {
+            let logic = match query.logic {
+                Finally => "F ",
+                Globally => "G ",
+                RawCondition => ""
+            };
+            let bound = match &query.run_bound {
+                VerificationBound::NoRunBound => String::new(),
+                bound => format!("{} ", bound)
+            };
+            format!("{}{}{}", logic, bound, query.condition)
+        };
+        let reward = self.reward.as_ref().map(|r| format!("{{{}}}", r)).unwrap_or_default();
+        match (&self.quantifier, &self.given) {
+            (Probability, Some(given)) => write!(f, "P({} | {})", body(self), body(given)),
+            (ExpectedReward, _) => match (&self.logic, &self.run_bound) {
+                (Globally, VerificationBound::TimeRunBound(t)) => write!(f, "E{}[C<={}]", reward, t),
+                (Globally, VerificationBound::StepsRunBound(s)) => write!(f, "E{}[C<={}]", reward, s),
+                _ => write!(f, "E{}[F {}]", reward, self.condition)
+            },
+            (SteadyState(op, p), _) => write!(f, "S{}{} [{}]", op, p.0, self.condition),
+            (Quantile(op, p), _) => write!(f, "Q{}{} [{}]", op, p.0, body(self)),
+            (Exists, _) => write!(f, "E {}", body(self)),
+            (ForAll, _) => write!(f, "A {}", body(self)),
+            (Probability, _) => write!(f, "P {}", body(self)),
+            (ProbabilityBound(op, p), _) => write!(f, "P{}{} {}", op, p.0, body(self)),
+            (LTL, _) => write!(f, "{}", body(self))
+        }
+    }
+}
+
 pub trait QueryVisitor {
 
     fn visit_query(&mut self, query : &Query);
     fn visit_condition(&mut self, condition : &Condition);
     fn visit_expression(&mut self, expr : &Expr);
 
-}
\ No newline at end of file
+}
+
+/// Rewrites a query tree in place. Every node is handed to the matching method, whose default implementation
+/// only carries on with the children : passes override the nodes they change, calling transform_children to go deeper.
+pub trait QueryTransformer {
+
+    fn transform_query(&mut self, query : &mut Query) {
+        query.transform_children(self);
+    }
+
+    fn transform_condition(&mut self, condition : &mut Condition) {
+        condition.transform_children(self);
+    }
+
+    fn transform_expression(&mut self, expr : &mut Expr) {
+        expr.transform_children(self);
+    }
+
+}
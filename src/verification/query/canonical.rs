@@ -0,0 +1,82 @@
+use crate::models::{expressions::{Condition, Expr, PropositionType}, model_context::ModelContext, model_var::ModelVar, petri::PetriNet, Label, Node};
+
+use super::{Quantifier, Query, StateLogic};
+
+// Ready-made queries for the standard properties of a model
+impl Query {
+
+    // A [] !deadlock
+    pub fn deadlock_free() -> Self {
+        Query::new(Quantifier::ForAll, StateLogic::Globally, Condition::Not(Box::new(Condition::Deadlock)))
+    }
+
+    // A [] every variable of the context (every place of a Petri net) is at most k. The query is already mapped to the context.
+    pub fn k_bounded(ctx : &ModelContext, k : i32) -> Self {
+        let condition = ctx.get_vars().into_iter().map(|var| {
+            Condition::Proposition(PropositionType::LE, Expr::Var(var), Expr::Constant(k))
+        }).reduce(|c1, c2| Condition::And(Box::new(c1), Box::new(c2))).unwrap_or(Condition::True);
+        Query::new(Quantifier::ForAll, StateLogic::Globally, condition)
+    }
+
+    // E <> t enabled, for every transition t of the net. Enabling is time-abstract : input and read places are marked, inhibiting places are below their weight and the guard holds.
+    // Places on marking-dependent input arcs hold at least their weight, evaluated in the same marking.
+    // Queries are not mapped, places being referred to by name.
+    pub fn quasi_liveness(net : &PetriNet) -> Vec<(Label, Query)> {
+        net.transitions.iter().map(|transition| {
+            let mut inputs : Vec<(&Label, i32)> = Vec::new();
+            for place in transition.from.iter() {
+                match inputs.iter_mut().find(|(p, _)| *p == place) {
+                    Some((_, weight)) => *weight += 1,
+                    None => inputs.push((place, 1))
+                }
+            }
+            for (place, read) in transition.reads.iter() {
+                match inputs.iter_mut().find(|(p, _)| *p == place) {
+                    Some((_, weight)) => *weight = (*weight).max(*read),
+                    None => inputs.push((place, *read))
+                }
+            }
+            let inhibitors = transition.inhibitors.iter().map(|(place, weight)| {
+                Condition::Proposition(PropositionType::LS, Expr::Var(ModelVar::name(place.clone())), Expr::Constant(*weight))
+            });
+            let weighted = transition.input_weights.iter().map(|(place, weight)| {
+                Condition::Proposition(PropositionType::GE, Expr::Var(ModelVar::name(place.clone())), weight.clone())
+            });
+            let enabled = inputs.into_iter().map(|(place, weight)| {
+                Condition::Proposition(PropositionType::GE, Expr::Var(ModelVar::name(place.clone())), Expr::Constant(weight))
+            }).chain(weighted).chain(inhibitors).fold(transition.guard.clone(), |c1, c2| Condition::And(Box::new(c1), Box::new(c2)));
+            (transition.get_label(), Query::new(Quantifier::Exists, StateLogic::Finally, enabled))
+        }).collect()
+    }
+
+    pub fn is_deadlock_freedom(&self) -> bool {
+        self.quantifier == Quantifier::ForAll && self.logic == StateLogic::Globally &&
+            matches!(&self.condition, Condition::Not(c) if **c == Condition::Deadlock)
+    }
+
+    // Bound k of a k-boundedness query, None if the query is not one
+    pub fn k_bound(&self) -> Option<i32> {
+        if self.quantifier != Quantifier::ForAll || self.logic != StateLogic::Globally {
+            return None;
+        }
+        let mut conditions = vec![&self.condition];
+        let mut bound = None;
+        while let Some(condition) = conditions.pop() {
+            match condition {
+                Condition::And(c1, c2) => {
+                    conditions.push(c1);
+                    conditions.push(c2);
+                },
+                Condition::Proposition(PropositionType::LE, Expr::Var(_), Expr::Constant(k)) => {
+                    if bound.is_some_and(|b| b != *k) {
+                        return None;
+                    }
+                    bound = Some(*k);
+                },
+                _ => return None
+            }
+        }
+        bound
+    }
+
+}
@@ -0,0 +1,81 @@
+use crate::{models::expressions::{Expr, PropositionType}, verification::VerificationBound};
+
+use super::{Query, QueryTransformer};
+
+// Clocks of an untimed model stay at zero. Time bounds only keep their meaning on discrete-time models, where each
+// step takes one time unit (as VerificationBound::step_limit assumes) : they are read as step bounds there, and refused otherwise
+struct UntimedAdaptation {
+    discrete_time : bool,
+    changes : Vec<String>,
+    refused : Option<String>,
+}
+
+impl UntimedAdaptation {
+
+    fn adapt_bound(&mut self, bound : &mut VerificationBound) {
+        match bound {
+            VerificationBound::TimeRunBound(t) if self.discrete_time => {
+                self.changes.push(format!("time bound {} read as {} steps", t, t));
+                *bound = VerificationBound::StepsRunBound(*t as usize);
+            },
+            VerificationBound::TimeRunBound(t) => {
+                self.refused = Some(format!("time bound {} has no meaning without discrete time", t));
+            },
+            VerificationBound::CompositeRunBound(bounds) => {
+                for b in bounds.iter_mut() {
+                    self.adapt_bound(b);
+                }
+            },
+            _ => ()
+        }
+    }
+
+}
+
+impl QueryTransformer for UntimedAdaptation {
+
+    fn transform_query(&mut self, query : &mut Query) {
+        self.adapt_bound(&mut query.run_bound);
+        query.transform_children(self);
+    }
+
+    fn transform_expression(&mut self, expr : &mut Expr) {
+        let Expr::ClockComparison(p_type, clock, value) = expr else {
+            return expr.transform_children(self);
+        };
+        let k = *value as f64;
+        let holds = match p_type {
+            PropositionType::EQ => k == 0.0,
+            PropositionType::NE => k != 0.0,
+            PropositionType::LE => 0.0 <= k,
+            PropositionType::GE => 0.0 >= k,
+            PropositionType::LS => 0.0 < k,
+            PropositionType::GS => 0.0 > k,
+        };
+        self.changes.push(format!("clock {} evaluated at 0", clock.name));
+        *expr = Expr::Constant(holds as i32);
+    }
+
+}
+
+impl Query {
+
+    pub fn is_timed(&self) -> bool {
+        self.run_bound.bounds().into_iter().any(|b| matches!(b, VerificationBound::TimeRunBound(_))) ||
+            self.condition.contains_clock_proposition() ||
+            self.given.as_ref().is_some_and(|g| g.is_timed())
+    }
+
+    // Untimed query with the same verdict on an untimed model, along with the changes made, or the reason why there is none.
+    // Untimed queries need no adaptation the other way around, step bounds and discrete conditions keeping their meaning on timed models.
+    pub fn untimed(&self, discrete_time : bool) -> Result<(Query, Vec<String>), String> {
+        let mut query = self.clone();
+        let mut adaptation = UntimedAdaptation { discrete_time, changes : Vec::new(), refused : None };
+        adaptation.transform_query(&mut query);
+        match adaptation.refused {
+            Some(reason) => Err(reason),
+            None => Ok((query, adaptation.changes))
+        }
+    }
+
+}
@@ -0,0 +1,154 @@
+use std::{fmt::Display, fs, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{log, solution::{Provenance, SolverResult}, Query};
+
+use super::smc::SampleGuarantee;
+
+/// Summary of the verification of a query : verdict or estimation, effort spent and solver chain used.
+/// Serializable for tools, and displayed as text for humans.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query : Option<String>,
+    pub query_hash : u64,
+    pub result : String,
+    pub conclusive : bool,
+    pub verdict : Option<bool>,
+    pub estimate : Option<f64>,
+    pub interval : Option<(f64, f64)>,
+    pub confidence : f64,
+    pub runs : Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guarantee : Option<SampleGuarantee>,
+    pub states : Option<usize>,
+    // Seconds
+    pub wall_time : f64,
+    // Peak resident memory of the process, in bytes
+    pub memory : Option<u64>,
+    pub solver_chain : Vec<String>,
+}
+
+impl VerificationReport {
+
+    pub fn new(query : &Query, result : &SolverResult) -> Self {
+        let (estimate, interval) = match result {
+            SolverResult::ProbabilityResult { estimate, interval, .. } |
+            SolverResult::NumericResult { estimate, interval, .. } => (Some(*estimate), Some(*interval)),
+            SolverResult::FloatResult(x) => (Some(*x), None),
+            SolverResult::IntResult(i) => (Some(*i as f64), None),
+            _ => (None, None)
+        };
+        VerificationReport {
+            query : None,
+            query_hash : query.get_query_hash(),
+            result : result.to_string(),
+            conclusive : result.is_conclusive(),
+            verdict : result.as_bool(),
+            estimate, interval,
+            confidence : result.confidence(),
+            runs : None,
+            guarantee : None,
+            states : None,
+            wall_time : 0.0,
+            memory : peak_memory(),
+            solver_chain : Vec::new()
+        }
+    }
+
+    // Route of the pipeline to the result, the wall-clock time being the one of the whole pipeline
+    pub fn with_provenance(mut self, provenance : &Provenance) -> Self {
+        self.solver_chain = provenance.route().iter().map(|stage| format!("{:?} {}", stage.kind, stage.name)).collect();
+        self.wall_time = provenance.total_time().as_secs_f64();
+        self
+    }
+
+    pub fn with_query_text(mut self, text : impl ToString) -> Self {
+        self.query = Some(text.to_string());
+        self
+    }
+
+    pub fn with_runs(mut self, runs : usize) -> Self {
+        self.runs = Some(runs);
+        self
+    }
+
+    // Accuracy guaranteed by the sample size, the runs planned being reported as well
+    pub fn with_guarantee(mut self, guarantee : &SampleGuarantee) -> Self {
+        self.runs = Some(guarantee.runs);
+        self.guarantee = Some(guarantee.clone());
+        self
+    }
+
+    pub fn with_states(mut self, states : usize) -> Self {
+        self.states = Some(states);
+        self
+    }
+
+    pub fn with_wall_time(mut self, duration : Duration) -> Self {
+        self.wall_time = duration.as_secs_f64();
+        self
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn log(&self) {
+        let text = self.to_string();
+        let mut lines = text.lines();
+        if let Some(title) = lines.next() {
+            log::info(title);
+        }
+        for line in lines {
+            log::continue_info(line.trim_start_matches(" - "));
+        }
+    }
+
+}
+
+// Peak resident set size, only known on Linux
+pub fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+impl Display for VerificationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.query {
+            Some(query) => writeln!(f, "Query : {}", query)?,
+            None => writeln!(f, "Query #{:x}", self.query_hash)?,
+        }
+        writeln!(f, " - Result : {}", self.result)?;
+        if let Some(verdict) = self.verdict {
+            writeln!(f, " - Verdict : {}", verdict)?;
+        }
+        if let Some(estimate) = self.estimate {
+            match self.interval {
+                Some((low, high)) => writeln!(f, " - Estimate : {} in [{}, {}]", estimate, low, high)?,
+                None => writeln!(f, " - Estimate : {}", estimate)?,
+            }
+        }
+        writeln!(f, " - Confidence : {}%", self.confidence * 100.0)?;
+        if let Some(runs) = self.runs {
+            writeln!(f, " - Runs : {}", runs)?;
+        }
+        if let Some(guarantee) = &self.guarantee {
+            writeln!(f, " - Guarantee : {}", guarantee)?;
+        }
+        if let Some(states) = self.states {
+            writeln!(f, " - States : {}", states)?;
+        }
+        writeln!(f, " - Time : {}s", self.wall_time)?;
+        if let Some(memory) = self.memory {
+            writeln!(f, " - Peak memory : {:.1} MB", memory as f64 / (1024.0 * 1024.0))?;
+        }
+        if !self.solver_chain.is_empty() {
+            writeln!(f, " - Solver chain : {}", self.solver_chain.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::{collections::HashSet, hash::{DefaultHasher, Hash, Hasher}, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, mpsc, Mutex}, thread, time::{Duration, Instant}};
+
+use crate::{models::{expressions::Condition, Model, ModelMaker, ModelState}, solution::SolverResult};
+
+use super::{query::{Quantifier, Query, StateLogic}, Verifiable};
+
+use crate::log::*;
+
+// Hash of a state, deciding the shard owning it. Stable across workers, so that every worker agrees on owners
+pub fn state_hash(state : &ModelState) -> u64 {
+    let mut s = DefaultHasher::new();
+    state.hash(&mut s);
+    s.finish()
+}
+
+pub fn shard_of(hash : u64, shards : usize) -> usize {
+    (hash % shards as u64) as usize
+}
+
+/// Outcome of a sharded exploration : states visited by each shard, and the first state found verifying the target if any
+#[derive(Debug, Clone)]
+pub struct ExplorationSummary {
+    pub shards : Vec<usize>,
+    pub transitions : usize,
+    pub deadlocks : usize,
+    pub witness : Option<ModelState>,
+    pub complete : bool,
+    pub time : f64,
+}
+
+impl ExplorationSummary {
+
+    pub fn states(&self) -> usize {
+        self.shards.iter().sum()
+    }
+
+    pub fn log(&self) {
+        info(format!("Explored states : {} in {} shards", self.states(), self.shards.len()));
+        continue_info(format!("Shard sizes : {:?}", self.shards));
+        continue_info(format!("Transitions : {}", self.transitions));
+        continue_info(format!("Deadlocks : {}", self.deadlocks));
+        continue_info(format!("Time elapsed : {}s", self.time));
+        if !self.complete && self.witness.is_none() {
+            warning("Exploration stopped before the whole state space was visited");
+        }
+    }
+
+}
+
+/// Experimental explicit-state exploration of untimed models (untimed Petri nets, Markov chains), the visited set
+/// being sharded by state hash across worker threads. Each worker owns a shard : it expands the states it receives,
+/// and sends their successors to the workers owning them, so that no set is shared between threads.
+/// Only discrete steps are explored, TPNs should be explored through their `untimed` projection.
+/// Like the nested DFS, visited states are only stored by hash.
+#[derive(Debug, Clone)]
+pub struct ShardedExploration {
+    pub workers : usize,
+    pub max_states : Option<usize>,
+}
+
+// Exploration state shared by the workers, along with the channels to every shard
+struct SharedExploration<'a> {
+    senders : Vec<mpsc::Sender<ModelState>>,
+    target : Option<&'a Condition>,
+    pending : AtomicUsize,
+    visited : AtomicUsize,
+    transitions : AtomicUsize,
+    deadlocks : AtomicUsize,
+    stop : AtomicBool,
+    truncated : AtomicBool,
+    witness : Mutex<Option<ModelState>>,
+}
+
+impl ShardedExploration {
+
+    pub fn new(workers : usize) -> Self {
+        ShardedExploration { workers : workers.max(1), max_states : None }
+    }
+
+    pub fn with_max_states(mut self, max_states : usize) -> Self {
+        self.max_states = Some(max_states);
+        self
+    }
+
+    // Explores the states reachable from the initial one, stopping as soon as a state verifying the target is found.
+    // The target must have been applied to the context of the models built by the maker
+    pub fn explore<T : Model, M : ModelMaker<T>>(&self, maker : &M, initial : &ModelState, target : Option<&Condition>) -> ExplorationSummary {
+        info("Sharded state space exploration");
+        continue_info(format!("Workers : {}", self.workers));
+        pending("Starting...");
+        let now = Instant::now();
+        let (senders, receivers) : (Vec<mpsc::Sender<ModelState>>, Vec<mpsc::Receiver<ModelState>>) =
+            (0..self.workers).map(|_| mpsc::channel()).unzip();
+        senders[shard_of(state_hash(initial), self.workers)].send(initial.clone()).unwrap();
+        let shared = SharedExploration {
+            senders,
+            target,
+            pending : AtomicUsize::new(1),
+            visited : AtomicUsize::new(0),
+            transitions : AtomicUsize::new(0),
+            deadlocks : AtomicUsize::new(0),
+            stop : AtomicBool::new(false),
+            truncated : AtomicBool::new(false),
+            witness : Mutex::new(None),
+        };
+        let shards = thread::scope(|s| {
+            let handles : Vec<_> = receivers.into_iter().enumerate().map(|(index, receiver)| {
+                let shared = &shared;
+                s.spawn(move || self.work(maker, index, receiver, shared))
+            }).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let summary = ExplorationSummary {
+            shards,
+            transitions : shared.transitions.load(Ordering::Relaxed),
+            deadlocks : shared.deadlocks.load(Ordering::Relaxed),
+            witness : shared.witness.into_inner().unwrap(),
+            complete : !shared.truncated.load(Ordering::Relaxed),
+            time : now.elapsed().as_secs_f64(),
+        };
+        positive("Exploration done");
+        summary
+    }
+
+    // Expands the states owned by the shard until the whole exploration is over. A state is only counted as pending
+    // once its successors have been sent, so pending states reaching zero means every worker is idle
+    fn work<T : Model, M : ModelMaker<T>>(&self, maker : &M, index : usize, receiver : mpsc::Receiver<ModelState>, shared : &SharedExploration) -> usize {
+        let (model, _) = maker.make();
+        let mut visited : HashSet<u64> = HashSet::new();
+        while !shared.stop.load(Ordering::Relaxed) {
+            let state = match receiver.recv_timeout(Duration::from_millis(1)) {
+                Ok(state) => state,
+                Err(mpsc::RecvTimeoutError::Timeout) if shared.pending.load(Ordering::SeqCst) == 0 => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            if visited.insert(state_hash(&state)) {
+                self.expand(&model, index, &state, &visited, shared);
+            }
+            shared.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+        visited.len()
+    }
+
+    fn expand(&self, model : &impl Model, index : usize, state : &ModelState, visited : &HashSet<u64>, shared : &SharedExploration) {
+        let visited_count = shared.visited.fetch_add(1, Ordering::Relaxed) + 1;
+        if shared.target.is_some_and(|t| t.is_true(state.as_verifiable())) {
+            shared.witness.lock().unwrap().get_or_insert_with(|| state.clone());
+            shared.stop.store(true, Ordering::Relaxed);
+            return;
+        }
+        if self.max_states.is_some_and(|max| visited_count >= max) {
+            shared.truncated.store(true, Ordering::Relaxed);
+            shared.stop.store(true, Ordering::Relaxed);
+            return;
+        }
+        let successors = model.successors(state);
+        if successors.is_empty() {
+            shared.deadlocks.fetch_add(1, Ordering::Relaxed);
+        }
+        shared.transitions.fetch_add(successors.len(), Ordering::Relaxed);
+        for next in successors {
+            let hash = state_hash(&next);
+            let owner = shard_of(hash, self.workers);
+            if owner == index && visited.contains(&hash) {
+                continue;
+            }
+            shared.pending.fetch_add(1, Ordering::SeqCst);
+            if shared.senders[owner].send(next).is_err() {
+                shared.pending.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    // E F phi looks for a state verifying phi, A G phi for one violating it
+    pub fn verify<T : Model, M : ModelMaker<T>>(&self, maker : &M, initial : &ModelState, query : &Query) -> SolverResult {
+        let safety = match (query.quantifier, query.logic) {
+            (Quantifier::Exists, StateLogic::Finally) => false,
+            (Quantifier::ForAll, StateLogic::Globally) => true,
+            _ => return SolverResult::SolverError
+        };
+        if !query.condition.is_state_condition() || query.condition.contains_nested() {
+            return SolverResult::SolverError;
+        }
+        let target = if safety { Condition::Not(Box::new(query.condition.clone())) } else { query.condition.clone() };
+        let summary = self.explore(maker, initial, Some(&target));
+        summary.log();
+        match (summary.witness.is_some(), summary.complete) {
+            (true, _) => {
+                if safety { negative("Unsafe state found !") } else { positive("Valid state found !") }
+                SolverResult::BoolResult(!safety)
+            },
+            (false, true) => {
+                if safety { positive("Every state is safe") } else { negative("No valid state found") }
+                SolverResult::BoolResult(safety)
+            },
+            (false, false) => SolverResult::unknown("State limit reached before the end of the exploration")
+        }
+    }
+
+}
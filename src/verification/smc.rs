@@ -2,20 +2,69 @@ mod random_run_generator;
 mod probability_estimation;
 mod probability_float_comparison;
 mod smc_max_seen;
+mod run_record;
+mod run_monitor;
+mod nested_resolver;
+mod expected_reward_estimation;
+mod expected_value_estimation;
+mod quantile_estimation;
+mod conditional_estimation;
+mod sprt;
+mod sample_size;
+mod importance_splitting;
+mod trace_clustering;
+mod importance_sampling;
+mod progress;
+mod statistics;
+mod run_debugger;
+mod variance_reduction;
+mod run_batch;
+mod run_cache;
+mod steady_state_estimation;
+#[cfg(feature = "distributed")]
+pub mod distributed;
 
-use std::{sync::{mpsc, Arc, Mutex}, thread, time::Instant};
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, mpsc}, thread, time::Instant};
+
+use num_traits::Zero;
 
 pub use random_run_generator::RandomRunIterator;
-pub use probability_estimation::ProbabilityEstimation;
+pub use probability_estimation::{IntervalMethod, ProbabilityEstimation};
 pub use probability_float_comparison::ProbabilityFloatComparison;
 pub use smc_max_seen::SMCMaxSeen;
+pub use run_record::{RunRecord, RunBundle};
+pub use run_monitor::{RunMonitor, MonitorColumn, VarMonitor, RateRewardMonitor, FiringMonitor};
+pub use nested_resolver::{NestedQueryResolver, SMCNestedResolver};
+pub use expected_reward_estimation::ExpectedRewardEstimation;
+pub use expected_value_estimation::{ExpectedValueEstimation, RunValue};
+pub use quantile_estimation::QuantileEstimation;
+pub use conditional_estimation::ConditionalEstimation;
+pub use sprt::SPRT;
+pub use sample_size::{SampleBound, SampleGuarantee};
+pub use importance_splitting::{ImportanceSplitting, LevelFunction};
+pub use importance_sampling::{ChangeOfMeasure, ImportanceSampling};
+pub use trace_clustering::{TraceCluster, TraceClustering, TraceFeature, log_clusters};
+pub use progress::{ProgressCallback, SMCHandle, SMCProgress};
+pub use statistics::{EmpiricalDistribution, Histogram, Statistics};
+pub use run_debugger::{DebuggerError, DebuggerResult, RunDebugger, RunFrame};
+pub use variance_reduction::SamplingStrategy;
+pub use run_batch::RunBatch;
+pub use run_cache::RunCache;
+pub use steady_state_estimation::SteadyStateEstimation;
 
-use crate::{models::{Model, ModelState}, solution::SolverResult, Query};
+use crate::{computation::random::RunSeeds, models::{Model, ModelMaker, ModelState}, solution::SolverResult, Query};
 
-use super::{VerificationStatus, Verifiable};
+use super::{EvaluationState, VerificationBound, VerificationStatus, Verifiable};
 
 use crate::log::*;
 
+// Results on models with nondeterministic choices only hold for the scheduler resolving them
+fn log_scheduler(model : &impl Model) {
+    if let Some(scheduler) = model.scheduler() {
+        warning(format!("Nondeterminism resolved by the {} scheduler, results are scheduler-dependent", scheduler));
+    }
+}
+
 pub trait SMCQueryVerification {
 
     // Required implementations
@@ -26,17 +75,35 @@ pub trait SMCQueryVerification {
     // Optional implementations
     fn prepare(&self) { }
     fn finish(&self) { }
+    fn expected_runs(&self) -> Option<usize> {
+        None
+    }
+    fn sampling(&self) -> SamplingStrategy {
+        SamplingStrategy::Independent
+    }
+    // Seed of the runs of a verification, drawn from entropy if none
+    fn seed(&self) -> Option<u64> {
+        None
+    }
+    // Results of a group of correlated runs (see SamplingStrategy), handled run by run unless overriden
+    fn handle_group_results(&mut self, results : &[VerificationStatus]) {
+        for result in results.iter() {
+            self.handle_run_result(*result);
+        }
+    }
 
     // Default implementations
     fn verify(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query) -> SolverResult {
         info("SMC verification");
+        log_scheduler(model);
         self.prepare();
         pending("Starting...");
         let now = Instant::now();
         let mut query = query.clone();
+        let mut seeds = RunSeeds::new(self.seed());
         while self.must_do_another_run() {
-            let result = Self::execute_run(model, initial_state, &mut query);
-            self.handle_run_result(result);
+            let results = self.execute_group(model, initial_state, &mut query, &mut seeds);
+            self.handle_group_results(&results);
         }
         self.finish();
         let elapsed = now.elapsed().as_secs_f64();
@@ -45,8 +112,121 @@ pub trait SMCQueryVerification {
         self.get_result()
     }
 
-    fn execute_run(model : &impl Model, initial_state : &ModelState, query : &mut Query) -> VerificationStatus {
-        let run_gen = RandomRunIterator::generate(model, initial_state, query.run_bound.clone());
+    fn progress(&self, runs : usize, elapsed : f64) -> SMCProgress {
+        SMCProgress { runs, expected_runs : self.expected_runs(), estimate : self.get_result(), elapsed }
+    }
+
+    // Same as verify, progress being reported to the handle, which can cancel the verification between two runs
+    fn verify_with_handle(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query, handle : &SMCHandle) -> SolverResult {
+        info("SMC verification");
+        log_scheduler(model);
+        self.prepare();
+        pending("Starting...");
+        let now = Instant::now();
+        let mut query = query.clone();
+        let mut seeds = RunSeeds::new(self.seed());
+        let mut runs = 0;
+        while self.must_do_another_run() {
+            if handle.is_cancelled() {
+                warning(format!("Verification cancelled after {} runs", runs));
+                return SolverResult::unknown(format!("Verification cancelled after {} runs", runs));
+            }
+            let results = self.execute_group(model, initial_state, &mut query, &mut seeds);
+            self.handle_group_results(&results);
+            runs += results.len();
+            if handle.is_due(runs) {
+                handle.report(&self.progress(runs, now.elapsed().as_secs_f64()));
+            }
+        }
+        self.finish();
+        let elapsed = now.elapsed().as_secs_f64();
+        handle.report(&self.progress(runs, elapsed));
+        positive("Verification finished");
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        self.get_result()
+    }
+
+    // Same as verify, but keeps a record of every run executed
+    fn verify_recorded(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query) -> (SolverResult, Vec<RunRecord>) {
+        let (result, bundle) = self.verify_monitored(model, initial_state, query, &mut []);
+        (result, bundle.records)
+    }
+
+    // Same as verify_recorded, every run also being summarized by the given monitors
+    fn verify_monitored(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query, monitors : &mut [Box<dyn RunMonitor>]) -> (SolverResult, RunBundle) {
+        info("SMC verification (recorded)");
+        log_scheduler(model);
+        self.prepare();
+        pending("Starting...");
+        let now = Instant::now();
+        let mut query = query.clone();
+        let mut bundle = RunBundle::new(monitors.iter().flat_map(|m| m.columns()).collect());
+        let mut seeds = RunSeeds::new(self.seed());
+        while self.must_do_another_run() {
+            let record = Self::execute_recorded_run(model, initial_state, &mut query, bundle.len(), seeds.next_seed(), monitors);
+            self.handle_run_result(record.status);
+            bundle.push(record);
+        }
+        self.finish();
+        let elapsed = now.elapsed().as_secs_f64();
+        positive("Verification finished");
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        (self.get_result(), bundle)
+    }
+
+    fn execute_recorded_run(model : &impl Model, initial_state : &ModelState, query : &mut Query, run : usize, seed : u64, monitors : &mut [Box<dyn RunMonitor>]) -> RunRecord {
+        let mut record = RunRecord::new(run);
+        for monitor in monitors.iter_mut() {
+            monitor.reset();
+        }
+        let mut run_gen = RandomRunIterator::seeded(model, initial_state, query.run_bound.clone(), seed);
+        let mut last_state = None;
+        for (state, delay, action) in run_gen.by_ref() {
+            if !delay.is_zero() {
+                record.delays.push(delay.float());
+            }
+            if let Some(action) = action.as_ref().filter(|a| !a.is_epsilon()) {
+                record.actions.push(action.get_id());
+            }
+            for monitor in monitors.iter_mut() {
+                monitor.observe(&state, delay, &action);
+            }
+            query.verify_state(state.as_verifiable());
+            last_state = Some(state);
+            if query.is_run_decided() {
+                break;
+            }
+        }
+        if let Some(state) = last_state {
+            let mut s = DefaultHasher::new();
+            state.hash(&mut s);
+            record.state_digest = s.finish();
+        }
+        record.observations = monitors.iter().flat_map(|m| m.values()).collect();
+        query.end_run();
+        record.status = query.run_status;
+        record.steps = run_gen.run_status.steps;
+        record.time = run_gen.run_status.time.float();
+        record.maximal = run_gen.run_status.maximal;
+        record.seed = run_gen.seed;
+        query.reset_run();
+        record
+    }
+
+    fn execute_run(model : &impl Model, initial_state : &ModelState, query : &mut Query, seed : u64) -> VerificationStatus {
+        let run_gen = RandomRunIterator::seeded(model, initial_state, query.run_bound.clone(), seed);
+        Self::verify_run(run_gen, query)
+    }
+
+    // Runs of a new group of the sampling strategy
+    fn execute_group(&self, model : &impl Model, initial_state : &ModelState, query : &mut Query, seeds : &mut RunSeeds) -> Vec<VerificationStatus> {
+        self.sampling().group(seeds).into_iter().map(|(seed, draws)| {
+            let run_gen = RandomRunIterator::seeded(model, initial_state, query.run_bound.clone(), seed).with_draws(draws);
+            Self::verify_run(run_gen, query)
+        }).collect()
+    }
+
+    fn verify_run(run_gen : RandomRunIterator, query : &mut Query) -> VerificationStatus {
         for (state, _, _) in run_gen {
             query.verify_state(state.as_verifiable());
             if query.is_run_decided() {
@@ -59,51 +239,296 @@ pub trait SMCQueryVerification {
         result
     }
 
-    fn parallel_verify(&mut self, model : &(impl Model + Send + Sync), initial_state : &ModelState, query : &Query) -> SolverResult {
-        info("SMC verification");
-        let threads = thread::available_parallelism().unwrap().get();
-        continue_info(format!("Parallel mode [Threads : {}]", threads));
+    // Same as verify, runs stopping as soon as they reach a (state, pending conditions) pair known to the cache, which
+    // can be kept across verifications of the same query, see RunCache
+    fn verify_cached(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query, cache : &mut RunCache) -> SolverResult {
+        info("SMC verification (cached)");
+        log_scheduler(model);
         self.prepare();
         pending("Starting...");
         let now = Instant::now();
+        let mut query = query.clone();
+        let mut seeds = RunSeeds::new(self.seed());
+        while self.must_do_another_run() {
+            let result = Self::execute_cached_run(model, initial_state, &mut query, seeds.next_seed(), cache);
+            self.handle_run_result(result);
+        }
+        self.finish();
+        cache.log();
+        let elapsed = now.elapsed().as_secs_f64();
+        positive("Verification finished");
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        self.get_result()
+    }
 
-        let (tx,rx) = mpsc::channel::<VerificationStatus>();
-        let must_continue = Arc::new(Mutex::new(true));
+    // Pairs are kept along with the number of draws made before reaching them : once the run is over, those reached
+    // after its last draw have a deterministic continuation
+    fn execute_cached_run(model : &impl Model, initial_state : &ModelState, query : &mut Query, seed : u64, cache : &mut RunCache) -> VerificationStatus {
+        let mut run_gen = RandomRunIterator::seeded(model, initial_state, query.run_bound.clone(), seed);
+        let mut trail : Vec<(EvaluationState, u64)> = Vec::new();
+        let mut seen : HashMap<EvaluationState, u64> = HashMap::new();
+        let mut cached = None;
+        while let Some((state, _, _)) = run_gen.next() {
+            let key = RunCache::key(query, state.as_ref(), &run_gen.run_status);
+            let draws = run_gen.draws_count();
+            cached = cache.lookup(key);
+            if cached.is_some() {
+                break;
+            }
+            if seen.insert(key, draws) == Some(draws) {
+                cache.cycles += 1;
+                break;
+            }
+            trail.push((key, draws));
+            query.verify_state(state.as_verifiable());
+            if query.is_run_decided() {
+                break;
+            }
+        }
+        match cached {
+            Some(verdict) => query.end_run_with(verdict),
+            None => query.end_run()
+        }
+        let result = query.run_status;
+        let draws = run_gen.draws_count();
+        let deterministic : Vec<EvaluationState> = trail.iter().rev()
+            .take_while(|(_, d)| *d == draws)
+            .map(|(key, _)| *key).collect();
+        cache.record(&deterministic, result);
+        query.reset_run();
+        result
+    }
 
-        thread::scope(|s| {
-            let mut handles = Vec::new();
-            for _ in 0..threads {
-                let handle = s.spawn(|| {
-                    let mut thread_query = query.clone();
-                    let mut must_do_another = *must_continue.lock().unwrap();
-                    while must_do_another {
-                        let result = Self::execute_run(model, initial_state, &mut thread_query);
-                        if tx.send(result).is_err() {
-                            panic!("Unable to send result !");
-                        }
-                        must_do_another = *must_continue.lock().unwrap();
+    // Same as verify, runs being generated in lockstep by batches of the given size (see RunBatch). Every run of the
+    // last batch is handled, so a few more runs than needed may be executed
+    fn verify_lockstep(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query, batch_size : usize) -> SolverResult {
+        info("SMC verification (lockstep)");
+        log_scheduler(model);
+        continue_info(format!("Batch size : {}", batch_size));
+        self.prepare();
+        pending("Starting...");
+        let now = Instant::now();
+        let mut seeds = RunSeeds::new(self.seed());
+        while self.must_do_another_run() {
+            for result in Self::execute_run_batch(model, initial_state, query, batch_size.max(1), seeds.next_seed()) {
+                self.handle_run_result(result);
+            }
+        }
+        self.finish();
+        let elapsed = now.elapsed().as_secs_f64();
+        positive("Verification finished");
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        self.get_result()
+    }
+
+    // A batch of runs, each one verifying its own copy of the query until decided
+    fn execute_run_batch(model : &impl Model, initial_state : &ModelState, query : &Query, size : usize, seed : u64) -> Vec<VerificationStatus> {
+        let mut queries = vec![query.clone() ; size];
+        let mut batch = RunBatch::seeded(model, initial_state, query.run_bound.clone(), size, seed);
+        loop {
+            for (i, query) in queries.iter_mut().enumerate() {
+                if batch.is_active(i) {
+                    query.verify_state(&batch.state(i));
+                    if query.is_run_decided() {
+                        batch.stop(i);
                     }
-                });
-                handles.push(handle);
+                }
+            }
+            if batch.step() == 0 {
+                break;
+            }
+        }
+        queries.into_iter().map(|mut query| {
+            query.end_run();
+            query.run_status
+        }).collect()
+    }
+
+    // Several queries verified on shared runs, each one by its own instance of the method. Queries with the same run bound
+    // share their runs : a run goes on as long as one of them is still undecided on it. Runs are seeded by the first method.
+    fn verify_batch(methods : &mut [Self], model : &impl Model, initial_state : &ModelState, queries : &[Query]) -> Vec<SolverResult> where Self : Sized {
+        assert_eq!(methods.len(), queries.len(), "One verification method is needed for each query");
+        info(format!("SMC batch verification [{} queries]", queries.len()));
+        log_scheduler(model);
+        for method in methods.iter() {
+            method.prepare();
+        }
+        pending("Starting...");
+        let now = Instant::now();
+        let mut queries = queries.to_vec();
+        let mut groups : Vec<(VerificationBound, Vec<usize>)> = Vec::new();
+        for (i, query) in queries.iter().enumerate() {
+            match groups.iter_mut().find(|(bound, _)| *bound == query.run_bound) {
+                Some((_, group)) => group.push(i),
+                None => groups.push((query.run_bound.clone(), vec![i]))
+            }
+        }
+        let mut seeds = RunSeeds::new(methods.first().and_then(|m| m.seed()));
+        let mut runs = 0;
+        for (bound, group) in groups {
+            loop {
+                let active : Vec<usize> = group.iter().cloned().filter(|i| methods[*i].must_do_another_run()).collect();
+                if active.is_empty() {
+                    break;
+                }
+                let results = Self::execute_shared_run(model, initial_state, bound.clone(), seeds.next_seed(), &mut queries, &active);
+                for (i, result) in active.into_iter().zip(results) {
+                    methods[i].handle_run_result(result);
+                }
+                runs += 1;
+            }
+        }
+        for method in methods.iter() {
+            method.finish();
+        }
+        let elapsed = now.elapsed().as_secs_f64();
+        positive("Verification finished");
+        continue_info(format!("Runs executed : [{}]", runs));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        methods.iter().map(|m| m.get_result()).collect()
+    }
+
+    // One run, every given query being verified on it until decided
+    fn execute_shared_run(model : &impl Model, initial_state : &ModelState, bound : VerificationBound, seed : u64, queries : &mut [Query], active : &[usize]) -> Vec<VerificationStatus> {
+        let run_gen = RandomRunIterator::seeded(model, initial_state, bound, seed);
+        for (state, _, _) in run_gen {
+            let mut decided = true;
+            for i in active.iter() {
+                let query = &mut queries[*i];
+                if !query.is_run_decided() {
+                    query.verify_state(state.as_verifiable());
+                    decided &= query.is_run_decided();
+                }
+            }
+            if decided {
+                break;
+            }
+        }
+        active.iter().map(|i| {
+            let query = &mut queries[*i];
+            query.end_run();
+            let result = query.run_status;
+            query.reset_run();
+            result
+        }).collect()
+    }
+
+    // Same as verify, nested sub-queries being answered from each visited state by the given resolver
+    fn verify_nested(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query, resolver : &mut dyn NestedQueryResolver) -> SolverResult {
+        info("SMC verification (nested)");
+        log_scheduler(model);
+        self.prepare();
+        pending("Starting...");
+        let now = Instant::now();
+        let mut query = query.clone();
+        let mut seeds = RunSeeds::new(self.seed());
+        while self.must_do_another_run() {
+            let result = Self::execute_nested_run(model, initial_state, &mut query, seeds.next_seed(), resolver);
+            self.handle_run_result(result);
+        }
+        self.finish();
+        let elapsed = now.elapsed().as_secs_f64();
+        positive("Verification finished");
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        self.get_result()
+    }
+
+    fn execute_nested_run(model : &dyn Model, initial_state : &ModelState, query : &mut Query, seed : u64, resolver : &mut dyn NestedQueryResolver) -> VerificationStatus {
+        let run_gen = RandomRunIterator::seeded(model, initial_state, query.run_bound.clone(), seed);
+        for (state, _, _) in run_gen {
+            query.verify_state_with(state.as_verifiable(), &mut |q| resolver.resolve(q, &state));
+            if query.is_run_decided() {
+                break;
+            }
+        }
+        query.end_run();
+        let result = query.run_status;
+        query.reset_run();
+        result
+    }
+
+    fn parallel_verify(&mut self, model : &(impl Model + Send + Sync), initial_state : &ModelState, query : &Query) -> SolverResult {
+        self.parallel_verify_with_handle(model, initial_state, query, &SMCHandle::new())
+    }
+
+    fn parallel_verify_with_handle(&mut self, model : &(impl Model + Send + Sync), initial_state : &ModelState, query : &Query, handle : &SMCHandle) -> SolverResult {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        self.parallel_run(threads, handle, |stop, next_run, tx| {
+            let mut thread_query = query.clone();
+            while !stop.load(Ordering::Relaxed) {
+                let (run, seed) = next_run();
+                if tx.send((run, Self::execute_run(model, initial_state, &mut thread_query, seed))).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    // Same as parallel_verify, each worker thread building its own model with the maker
+    fn parallel_verify_with<T : Model, M : ModelMaker<T>>(&mut self, maker : &M, workers : usize, initial_state : &ModelState, query : &Query) -> SolverResult {
+        self.parallel_run(workers.max(1), &SMCHandle::new(), |stop, next_run, tx| {
+            let (model, _) = maker.make();
+            let mut thread_query = query.clone();
+            while !stop.load(Ordering::Relaxed) {
+                let (run, seed) = next_run();
+                if tx.send((run, Self::execute_run(&model, initial_state, &mut thread_query, seed))).is_err() {
+                    break;
+                }
             }
+        })
+    }
 
-            for received in rx {
-                self.handle_run_result(received);
-                if !self.must_do_another_run() {
-                    {
-                        let mut threads_guard = must_continue.lock().unwrap();
-                        *threads_guard = false;
+    // Workers take the index of their next run, seeded from it, and send its verdict until told to stop. Verdicts are
+    // handled in the order of the runs, those arriving early being kept until the previous ones are handled, like in a
+    // distributed verification : a seeded verification gives the same result whatever the number of threads.
+    // Workers are also stopped when the handle is cancelled, progress being reported as verdicts are aggregated.
+    fn parallel_run(&mut self, workers : usize, handle : &SMCHandle, worker : impl Fn(&AtomicBool, &dyn Fn() -> (usize, u64), &mpsc::Sender<(usize, VerificationStatus)>) + Sync) -> SolverResult {
+        info("SMC verification");
+        continue_info(format!("Parallel mode [Threads : {}]", workers));
+        self.prepare();
+        pending("Starting...");
+        let now = Instant::now();
+        let stop = AtomicBool::new(!self.must_do_another_run());
+        let seeds = RunSeeds::new(self.seed());
+        let started = AtomicUsize::new(0);
+        let next_run = || {
+            let run = started.fetch_add(1, Ordering::Relaxed);
+            (run, seeds.run_seed(run as u64))
+        };
+        let (tx, rx) = mpsc::channel::<(usize, VerificationStatus)>();
+        let mut early : HashMap<usize, VerificationStatus> = HashMap::new();
+        let mut runs = 0;
+        thread::scope(|s| {
+            for _ in 0..workers {
+                let (tx, stop, worker, next_run) = (tx.clone(), &stop, &worker, &next_run);
+                s.spawn(move || worker(stop, next_run, &tx));
+            }
+            drop(tx);
+            for (run, status) in rx {
+                if stop.load(Ordering::Relaxed) {
+                    continue;
+                }
+                early.insert(run, status);
+                while let Some(status) = early.remove(&runs) {
+                    self.handle_run_result(status);
+                    runs += 1;
+                    if handle.is_due(runs) {
+                        handle.report(&self.progress(runs, now.elapsed().as_secs_f64()));
                     }
-                    for handle in handles {
-                        handle.join().unwrap();
+                    if !self.must_do_another_run() || handle.is_cancelled() {
+                        stop.store(true, Ordering::Relaxed);
+                        break;
                     }
-                    break;
                 }
             }
         });
-
+        if handle.is_cancelled() && self.must_do_another_run() {
+            warning(format!("Verification cancelled after {} runs", runs));
+            return SolverResult::unknown(format!("Verification cancelled after {} runs", runs));
+        }
         self.finish();
         let elapsed = now.elapsed().as_secs_f64();
+        handle.report(&self.progress(runs, elapsed));
         positive("Verification finished");
         continue_info(format!("Time elapsed : {}s", elapsed));
         self.get_result()
@@ -0,0 +1,67 @@
+use std::time::Instant;
+
+use crate::{computation::random::RunSeeds, models::{Model, ModelState}, solution::SolverResult, Query};
+use crate::log::*;
+
+use super::{ProbabilityEstimation, SMCQueryVerification};
+
+const MAX_RUNS_FACTOR : usize = 100;
+
+/// Estimates P(phi | psi) queries : both events are verified on the same runs, and only the runs verifying psi are
+/// counted, until enough of them have been seen. Gives up after a hundred times the runs needed, psi being too rare.
+#[derive(Debug, Clone)]
+pub struct ConditionalEstimation {
+    pub confidence : f64,
+    pub interval_width : f64,
+    pub runs_needed : usize,
+    pub max_runs : usize,
+}
+
+impl ConditionalEstimation {
+
+    pub fn new(confidence : f64, interval_width : f64) -> Self {
+        let runs_needed = ProbabilityEstimation::new(confidence, interval_width).runs_needed;
+        ConditionalEstimation { confidence, interval_width, runs_needed, max_runs : runs_needed * MAX_RUNS_FACTOR }
+    }
+
+    pub fn with_max_runs(mut self, max_runs : usize) -> Self {
+        self.max_runs = max_runs;
+        self
+    }
+
+    pub fn estimate(&self, model : &impl Model, initial : &ModelState, query : &Query) -> SolverResult {
+        let Some(given) = &query.given else {
+            return SolverResult::SolverError;
+        };
+        info("Estimating conditional probability using SMC...");
+        continue_info(format!("Conditioning runs needed : {}", self.runs_needed));
+        pending("Starting...");
+        let now = Instant::now();
+        let mut event = query.clone();
+        event.given = None;
+        let mut queries = vec![event, given.as_ref().clone()];
+        let (mut runs, mut conditioned, mut valid) = (0, 0, 0);
+        let mut seeds = RunSeeds::default();
+        while conditioned < self.runs_needed && runs < self.max_runs {
+            let results = ProbabilityEstimation::execute_shared_run(model, initial, query.run_bound.clone(), seeds.next_seed(), &mut queries, &[0, 1]);
+            runs += 1;
+            if results[1].good() {
+                conditioned += 1;
+                if results[0].good() {
+                    valid += 1;
+                }
+            }
+        }
+        let elapsed = now.elapsed().as_secs_f64();
+        continue_info(format!("Runs executed : [{}], conditioning event verified on [{}]", runs, conditioned));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        if conditioned < self.runs_needed {
+            warning("Conditioning event too rare, not enough runs verified it");
+            return SolverResult::unknown("Conditioning event too rare");
+        }
+        let estimate = valid as f64 / conditioned as f64;
+        positive(format!("Estimation complete, conditional probability : {}", estimate));
+        SolverResult::probability(estimate, self.interval_width / 2.0, self.confidence)
+    }
+
+}
@@ -0,0 +1,346 @@
+use std::{fmt, io::{BufRead, BufReader, Read, Write}, net::{TcpListener, TcpStream}, panic::{self, AssertUnwindSafe}, sync::mpsc, thread, time::Instant};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{computation::random::RunSeeds, models::{model_context::ModelContext, model_project::ModelProject, Model, ModelState}, solution::SolverResult, verification::{query::Query, VerificationStatus, Verifiable}};
+
+use super::{RandomRunIterator, SMCQueryVerification};
+
+use crate::log::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistributedError(pub String);
+impl fmt::Display for DistributedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Distributed verification error : {}", self.0)
+    }
+}
+pub type DistributedResult<T> = Result<T, DistributedError>;
+
+impl From<std::io::Error> for DistributedError {
+    fn from(value: std::io::Error) -> Self {
+        DistributedError(value.to_string())
+    }
+}
+
+impl From<serde_json::Error> for DistributedError {
+    fn from(value: serde_json::Error) -> Self {
+        DistributedError(value.to_string())
+    }
+}
+
+/// Verdicts counts of the runs handled by the coordinator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunStatistics {
+    pub verified : usize,
+    pub unverified : usize,
+    pub undecided : usize,
+}
+
+impl RunStatistics {
+
+    pub fn runs(&self) -> usize {
+        self.verified + self.unverified + self.undecided
+    }
+
+    pub fn add(&mut self, status : VerificationStatus) {
+        match status {
+            VerificationStatus::Verified => self.verified += 1,
+            VerificationStatus::Unverified => self.unverified += 1,
+            VerificationStatus::Maybe => self.undecided += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other : &RunStatistics) {
+        self.verified += other.verified;
+        self.unverified += other.unverified;
+        self.undecided += other.undecided;
+    }
+
+}
+
+// Messages are written as JSON, one per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum CoordinatorMessage<S> {
+    Task { project : Box<ModelProject<S>>, query : String },
+    Batch { seeds : Vec<u64> },
+    Done,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum WorkerMessage {
+    Ready { threads : usize },
+    // Verdicts of the runs of a batch, in the order of their seeds
+    Verdicts(Vec<VerificationStatus>),
+    Error(String),
+}
+
+fn send(stream : &mut TcpStream, message : &impl Serialize) -> DistributedResult<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+// Longest message accepted from a peer, serialized projects included
+const MAX_MESSAGE_LENGTH : u64 = 1 << 28;
+
+fn receive<T : DeserializeOwned>(reader : &mut BufReader<TcpStream>) -> DistributedResult<T> {
+    let mut line = String::new();
+    let read = reader.by_ref().take(MAX_MESSAGE_LENGTH).read_line(&mut line)?;
+    if read == 0 {
+        return Err(DistributedError(String::from("Connection closed")));
+    }
+    if !line.ends_with('\n') && read as u64 == MAX_MESSAGE_LENGTH {
+        return Err(DistributedError(format!("Message longer than {} bytes", MAX_MESSAGE_LENGTH)));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+fn prepare_query<S>(project : &ModelProject<S>, ctx : &ModelContext, text : &str) -> DistributedResult<Query> {
+    let mut query = project.parse_query(ctx, text).map_err(|e| DistributedError(e.message))?;
+    query.apply_to(ctx).map_err(|e| DistributedError(e.to_string()))?;
+    Ok(query)
+}
+
+// Model, initial state and query of a task, as built by each worker thread
+fn prepare_task<S : Clone, M : Model + From<S>>(project : &ModelProject<S>, text : &str) -> DistributedResult<(M, ModelState, Query)> {
+    let mut project = project.clone();
+    let (model, ctx) = project.make::<M>();
+    let Some(initial_state) = project.initial_state.clone() else {
+        return Err(DistributedError(String::from("No initial state")));
+    };
+    let query = prepare_query(&project, &ctx, text)?;
+    Ok((model, initial_state, query))
+}
+
+// Same as SMCQueryVerification::verify_run, for the run of the given seed
+fn run_verdict(model : &impl Model, initial_state : &ModelState, query : &mut Query, seed : u64) -> VerificationStatus {
+    for (state, _, _) in RandomRunIterator::seeded(model, initial_state, query.run_bound.clone(), seed) {
+        query.verify_state(state.as_verifiable());
+        if query.is_run_decided() {
+            break;
+        }
+    }
+    query.end_run();
+    let result = query.run_status;
+    query.reset_run();
+    result
+}
+
+struct WorkerConnection {
+    address : String,
+    reader : BufReader<TcpStream>,
+    writer : TcpStream,
+    threads : usize,
+}
+
+/// Coordinator of a verification distributed over worker processes (see SMCWorker). Workers receive the serialized
+/// project and the query once, then batches of run seeds, `batch_size` for each of their threads, and send back the
+/// verdicts of the runs in the order of the seeds. Verdicts are handled in this order until the method needs no more
+/// runs, so that sequential methods (SPRT) decide as they would on a single machine. Runs being drawn from the seeds
+/// of the coordinator, a seeded simulation gives the same result whatever the workers. The seed of the method is used,
+/// or else the one of the project.
+#[derive(Debug, Clone)]
+pub struct DistributedSMC {
+    pub workers : Vec<String>,
+    pub batch_size : usize,
+}
+
+impl DistributedSMC {
+
+    pub fn new(workers : Vec<String>) -> Self {
+        DistributedSMC { workers, batch_size : 256 }
+    }
+
+    pub fn with_batch_size(mut self, batch_size : usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    fn connect<S : Serialize + Clone>(&self, project : &ModelProject<S>, query : &str) -> DistributedResult<Vec<WorkerConnection>> {
+        let task = CoordinatorMessage::Task { project : Box::new(project.clone()), query : String::from(query) };
+        self.workers.iter().map(|address| {
+            let mut writer = TcpStream::connect(address)?;
+            let mut reader = BufReader::new(writer.try_clone()?);
+            send(&mut writer, &task)?;
+            match receive(&mut reader)? {
+                WorkerMessage::Ready { threads } => Ok(WorkerConnection { address : address.clone(), reader, writer, threads }),
+                WorkerMessage::Error(e) => Err(DistributedError(format!("Worker {} : {}", address, e))),
+                WorkerMessage::Verdicts(_) => Err(DistributedError(format!("Worker {} : unexpected verdicts", address)))
+            }
+        }).collect()
+    }
+
+    // Every worker gets a batch, results being gathered once they have all been sent, in the order of the seeds
+    fn execute_round(&self, connections : &mut [WorkerConnection], seeds : &mut RunSeeds) -> DistributedResult<Vec<VerificationStatus>> {
+        for connection in connections.iter_mut() {
+            let seeds = (0..(self.batch_size * connection.threads)).map(|_| seeds.next_seed()).collect();
+            send(&mut connection.writer, &CoordinatorMessage::<()>::Batch { seeds })?;
+        }
+        let mut verdicts = Vec::new();
+        for connection in connections.iter_mut() {
+            match receive(&mut connection.reader)? {
+                WorkerMessage::Verdicts(batch) => verdicts.extend(batch),
+                WorkerMessage::Error(e) => return Err(DistributedError(format!("Worker {} : {}", connection.address, e))),
+                WorkerMessage::Ready { .. } => return Err(DistributedError(format!("Worker {} : unexpected message", connection.address)))
+            }
+        }
+        Ok(verdicts)
+    }
+
+    pub fn verify<S : Serialize + Clone>(&self, method : &mut impl SMCQueryVerification, project : &ModelProject<S>, query : &str) -> DistributedResult<SolverResult> {
+        info("SMC verification (distributed)");
+        continue_info(format!("Workers : {}", self.workers.len()));
+        method.prepare();
+        pending("Connecting...");
+        let mut connections = self.connect(project, query)?;
+        let threads : usize = connections.iter().map(|c| c.threads).sum();
+        continue_info(format!("Threads : {}", threads));
+        pending("Starting...");
+        let now = Instant::now();
+        let mut total = RunStatistics::default();
+        let mut seeds = RunSeeds::new(method.seed().or(project.seed));
+        while method.must_do_another_run() {
+            for status in self.execute_round(&mut connections, &mut seeds)? {
+                if !method.must_do_another_run() {
+                    break;
+                }
+                method.handle_run_result(status);
+                total.add(status);
+            }
+        }
+        for connection in connections.iter_mut() {
+            send(&mut connection.writer, &CoordinatorMessage::<()>::Done)?;
+        }
+        method.finish();
+        let elapsed = now.elapsed().as_secs_f64();
+        positive("Verification finished");
+        continue_info(format!("Runs handled : [{}]", total.runs()));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        Ok(method.get_result())
+    }
+
+}
+
+/// Worker process of a distributed verification. Each thread builds its own model from the project of the task,
+/// seeds of a batch being split between threads.
+#[derive(Debug, Clone)]
+pub struct SMCWorker {
+    pub threads : usize,
+}
+
+impl SMCWorker {
+
+    pub fn new(threads : usize) -> Self {
+        SMCWorker { threads : threads.max(1) }
+    }
+
+    // One thread per available core
+    pub fn available() -> Self {
+        Self::new(thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+    }
+
+    // Serves coordinators one after the other
+    pub fn serve<S, M>(&self, address : &str) -> DistributedResult<()>
+        where S : DeserializeOwned + Clone + Send + Sync, M : Model + From<S>
+    {
+        let listener = TcpListener::bind(address)?;
+        info(format!("SMC worker listening on {}", address));
+        continue_info(format!("Threads : {}", self.threads));
+        for stream in listener.incoming() {
+            match stream.map_err(DistributedError::from).and_then(|stream| self.handle::<S, M>(stream)) {
+                Ok(runs) => positive(format!("Task done [{} runs]", runs)),
+                Err(e) => error(e.to_string())
+            }
+        }
+        Ok(())
+    }
+
+    // Task of a coordinator, returning the number of runs executed. Failures are reported to the coordinator
+    pub fn handle<S, M>(&self, stream : TcpStream) -> DistributedResult<usize>
+        where S : DeserializeOwned + Clone + Send + Sync, M : Model + From<S>
+    {
+        let mut writer = stream;
+        let mut reader = BufReader::new(writer.try_clone()?);
+        let (project, query) = match receive::<CoordinatorMessage<S>>(&mut reader)? {
+            CoordinatorMessage::Task { project, query } => (*project, query),
+            _ => return Err(DistributedError(String::from("Expected a task")))
+        };
+        if let Err(e) = prepare_task::<S, M>(&project, &query) {
+            send(&mut writer, &WorkerMessage::Error(e.0.clone()))?;
+            return Err(e);
+        }
+        pending(format!("Task received : {}", query));
+        send(&mut writer, &WorkerMessage::Ready { threads : self.threads })?;
+        let result = self.execute::<S, M>(&mut reader, &mut writer, &project, &query);
+        if let Err(e) = &result {
+            let _ = send(&mut writer, &WorkerMessage::Error(e.0.clone()));
+        }
+        result
+    }
+
+    fn execute<S, M>(&self, reader : &mut BufReader<TcpStream>, writer : &mut TcpStream, project : &ModelProject<S>, query : &str) -> DistributedResult<usize>
+        where S : DeserializeOwned + Clone + Send + Sync, M : Model + From<S>
+    {
+        let (results_tx, results_rx) = mpsc::channel::<DistributedResult<(usize, Vec<VerificationStatus>)>>();
+        thread::scope(|s| {
+            let seed_senders : Vec<mpsc::Sender<(usize, Vec<u64>)>> = (0..self.threads).map(|_| {
+                let (seeds_tx, seeds_rx) = mpsc::channel::<(usize, Vec<u64>)>();
+                let results_tx = results_tx.clone();
+                s.spawn(move || {
+                    let (model, initial_state, mut query) = match prepare_task::<S, M>(project, query) {
+                        Ok(task) => task,
+                        Err(e) => {
+                            let _ = results_tx.send(Err(e));
+                            return;
+                        }
+                    };
+                    for (chunk, seeds) in seeds_rx {
+                        let verdicts = panic::catch_unwind(AssertUnwindSafe(|| {
+                            seeds.into_iter().map(|seed| run_verdict(&model, &initial_state, &mut query, seed)).collect()
+                        }));
+                        let result = verdicts.map(|v| (chunk, v)).map_err(|_| DistributedError(String::from("Worker thread panicked")));
+                        let failed = result.is_err();
+                        if results_tx.send(result).is_err() || failed {
+                            break;
+                        }
+                    }
+                });
+                seeds_tx
+            }).collect();
+            // Threads hold the only senders, so that receiving fails once they have all stopped
+            drop(results_tx);
+            let stopped = |results_rx : &mpsc::Receiver<DistributedResult<(usize, Vec<VerificationStatus>)>>| {
+                results_rx.try_iter().find_map(Result::err).unwrap_or(DistributedError(String::from("Worker thread stopped")))
+            };
+            let mut runs = 0;
+            loop {
+                let seeds = match receive::<CoordinatorMessage<S>>(reader)? {
+                    CoordinatorMessage::Batch { seeds } => seeds,
+                    CoordinatorMessage::Done => break,
+                    CoordinatorMessage::Task { .. } => return Err(DistributedError(String::from("Task already received")))
+                };
+                let chunk = seeds.len().div_ceil(self.threads).max(1);
+                let mut sent = 0;
+                for (index, (chunk, sender)) in seeds.chunks(chunk).zip(seed_senders.iter()).enumerate() {
+                    if sender.send((index, chunk.to_vec())).is_err() {
+                        return Err(stopped(&results_rx));
+                    }
+                    sent += 1;
+                }
+                // Chunks come back in any order, verdicts being sent in the order of the seeds
+                let mut chunks : Vec<Vec<VerificationStatus>> = vec![Vec::new() ; sent];
+                for _ in 0..sent {
+                    let (index, verdicts) = results_rx.recv().map_err(|_| stopped(&results_rx))??;
+                    chunks[index] = verdicts;
+                }
+                let verdicts : Vec<VerificationStatus> = chunks.into_iter().flatten().collect();
+                runs += verdicts.len();
+                send(writer, &WorkerMessage::Verdicts(verdicts))?;
+            }
+            Ok(runs)
+        })
+    }
+
+}
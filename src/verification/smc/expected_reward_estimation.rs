@@ -0,0 +1,94 @@
+use std::{rc::Rc, time::Instant};
+
+use crate::{computation::statistics::{mean_half_width, mean_variance}, models::{model_context::ModelContext, reward_structure::RewardStructure, Model, ModelState}, solution::SolverResult, verification::{query::StateLogic, Verifiable, VerificationBound}, Query};
+use crate::log::*;
+
+use super::RandomRunIterator;
+
+/// Estimates E[C <= t] and E[F goal] queries, reward structures being taken from the model context.
+/// For untimed models, time bounds are interpreted as steps bounds.
+#[derive(Debug, Clone)]
+pub struct ExpectedRewardEstimation {
+    pub runs_needed : usize,
+    pub confidence : f64,
+}
+
+impl ExpectedRewardEstimation {
+
+    pub fn fixed_runs(runs : usize, confidence : f64) -> Self {
+        ExpectedRewardEstimation { runs_needed : runs, confidence }
+    }
+
+    pub fn estimate(&self, model : &impl Model, ctx : &ModelContext, initial : &ModelState, query : &Query) -> SolverResult {
+        info("Estimating expected reward using SMC...");
+        let Some(reward) = ctx.get_reward(&query.reward) else {
+            error("No reward structure found !");
+            return SolverResult::SolverError;
+        };
+        continue_info(format!("Reward : {}", reward.name));
+        continue_info(format!("Runs to be executed : {}", self.runs_needed));
+        pending("Starting...");
+        let now = Instant::now();
+        let mut values = Vec::with_capacity(self.runs_needed);
+        for _ in 0..self.runs_needed {
+            match Self::run_reward(model, initial, query, reward) {
+                Some(value) => values.push(value),
+                None => {
+                    warning("Goal not reached on a run, expected reward is infinite");
+                    return SolverResult::numeric(f64::INFINITY, 0.0, self.confidence);
+                }
+            }
+        }
+        let (mean, variance) = mean_variance(&values);
+        let elapsed = now.elapsed().as_secs_f64();
+        positive(format!("Estimation complete, expected reward : {}", mean));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        SolverResult::numeric(mean, mean_half_width(variance, values.len(), self.confidence), self.confidence)
+    }
+
+    // Reward cumulated along a random run, None if the goal of a reachability reward hasn't been reached
+    fn run_reward(model : &impl Model, initial : &ModelState, query : &Query, reward : &RewardStructure) -> Option<f64> {
+        let timed = model.is_timed();
+        // The step reaching the bound isn't yielded : t steps are taken from an untimed model by bounding it to t + 1
+        let bound = match query.run_bound {
+            VerificationBound::TimeRunBound(t) if !timed => VerificationBound::StepsRunBound(t as usize + 1),
+            ref b => b.clone()
+        };
+        let mut run_gen = RandomRunIterator::generate(model, initial, bound);
+        let mut total = 0.0;
+        let mut rate = 0.0;
+        let mut elapsed = 0.0;
+        let mut reached = false;
+        let mut left : Option<Rc<ModelState>> = None;
+        for (state, delay, action) in run_gen.by_ref() {
+            if timed {
+                total += rate * delay.float();
+                elapsed += delay.float();
+            } else if action.is_some() {
+                total += rate;
+                elapsed += 1.0;
+            }
+            if let (Some(action), Some(left)) = (&action, &left) {
+                total += reward.step_reward(left.as_ref(), state.as_ref(), action);
+            }
+            if query.logic == StateLogic::Finally && query.condition.is_true(state.as_verifiable()) {
+                reached = true;
+                break;
+            }
+            rate = reward.state_reward(state.as_verifiable());
+            left = Some(state);
+        }
+        match query.logic {
+            StateLogic::Finally if !reached => None,
+            StateLogic::Finally => Some(total),
+            _ => {
+                // The last state is kept until the bound, even if deadlocked
+                if let VerificationBound::TimeRunBound(t) = query.run_bound {
+                    total += rate * (t as f64 - elapsed).max(0.0);
+                }
+                Some(total)
+            }
+        }
+    }
+
+}
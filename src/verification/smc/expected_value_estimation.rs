@@ -0,0 +1,120 @@
+use std::{fmt, time::Instant};
+
+use crate::{computation::{random::{DrawTransform, RunSeeds}, statistics::{mean_half_width, mean_variance, standard_error}}, models::{expressions::{Condition, Expr}, Model, ModelState}, solution::SolverResult, verification::{Verifiable, VerificationBound}};
+use crate::log::*;
+
+use super::{RandomRunIterator, SamplingStrategy};
+
+/// Value of an expression observed on a run : in the first state verifying a goal, extremum over the run,
+/// or value in the last state
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunValue {
+    AtGoal(Condition),
+    Maximum,
+    Minimum,
+    Final,
+}
+
+impl fmt::Display for RunValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunValue::AtGoal(goal) => write!(f, "value at F {}", goal),
+            RunValue::Maximum => write!(f, "maximum over the run"),
+            RunValue::Minimum => write!(f, "minimum over the run"),
+            RunValue::Final => write!(f, "value in the last state"),
+        }
+    }
+}
+
+/// Estimates the expected value of an expression over random runs, such as E[expr at F goal] or E[max expr],
+/// along with the standard error of the estimate. Runs never reaching the goal are left out of the mean.
+/// With grouped sampling, the estimate and its error are derived from the mean of the values observed in each group.
+#[derive(Debug, Clone)]
+pub struct ExpectedValueEstimation {
+    pub runs_needed : usize,
+    pub confidence : f64,
+    pub observation : RunValue,
+    pub sampling : SamplingStrategy,
+    pub seed : Option<u64>,
+}
+
+impl ExpectedValueEstimation {
+
+    pub fn fixed_runs(runs : usize, confidence : f64, observation : RunValue) -> Self {
+        ExpectedValueEstimation { runs_needed : runs, confidence, observation, sampling : SamplingStrategy::Independent, seed : None }
+    }
+
+    pub fn at_goal(runs : usize, confidence : f64, goal : Condition) -> Self {
+        Self::fixed_runs(runs, confidence, RunValue::AtGoal(goal))
+    }
+
+    pub fn maximum(runs : usize, confidence : f64) -> Self {
+        Self::fixed_runs(runs, confidence, RunValue::Maximum)
+    }
+
+    pub fn with_sampling(mut self, sampling : SamplingStrategy) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    // Runs drawn from the seed, so that estimations with the same seed see the same runs
+    pub fn with_seed(mut self, seed : u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    // The expression (and goal) must have been applied to the context of the model
+    pub fn estimate(&self, model : &impl Model, initial : &ModelState, expr : &Expr, bound : VerificationBound) -> SolverResult {
+        info("Estimating expected value using SMC...");
+        continue_info(format!("Observation : {}", self.observation));
+        continue_info(format!("Runs to be executed : {}", self.runs_needed));
+        if self.sampling.is_grouped() {
+            continue_info(format!("Sampling : {}", self.sampling));
+        }
+        pending("Starting...");
+        let now = Instant::now();
+        let (mut runs, mut observed) = (0, 0);
+        let mut values : Vec<f64> = Vec::new();
+        let mut seeds = RunSeeds::new(self.seed);
+        while runs < self.runs_needed {
+            let group : Vec<f64> = self.sampling.group(&mut seeds).into_iter()
+                .filter_map(|(seed, draws)| self.run_value(model, initial, expr, bound.clone(), seed, draws))
+                .collect();
+            runs += self.sampling.group_size();
+            observed += group.len();
+            if !group.is_empty() {
+                values.push(group.iter().sum::<f64>() / group.len() as f64);
+            }
+        }
+        if observed < runs {
+            warning(format!("Goal not reached on {} run(s), left out of the estimation", runs - observed));
+        }
+        if values.is_empty() {
+            negative("No value observed");
+            return SolverResult::unknown("Goal never reached");
+        }
+        let (mean, variance) = mean_variance(&values);
+        let elapsed = now.elapsed().as_secs_f64();
+        positive(format!("Estimation complete, expected value : {}", mean));
+        continue_info(format!("Standard error : {}", standard_error(variance, values.len())));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        SolverResult::numeric(mean, mean_half_width(variance, values.len(), self.confidence), self.confidence)
+    }
+
+    // Value observed on a random run, None if the goal hasn't been reached
+    fn run_value(&self, model : &impl Model, initial : &ModelState, expr : &Expr, bound : VerificationBound, seed : u64, draws : DrawTransform) -> Option<f64> {
+        let mut observed : Option<f64> = None;
+        for (state, _, _) in RandomRunIterator::seeded(model, initial, bound, seed).with_draws(draws) {
+            let value = expr.evaluate(state.as_verifiable()).as_float();
+            observed = match &self.observation {
+                RunValue::AtGoal(goal) if goal.is_true(state.as_verifiable()) => return Some(value),
+                RunValue::AtGoal(_) => None,
+                RunValue::Maximum => Some(observed.map_or(value, |v| v.max(value))),
+                RunValue::Minimum => Some(observed.map_or(value, |v| v.min(value))),
+                RunValue::Final => Some(value),
+            };
+        }
+        observed
+    }
+
+}
@@ -0,0 +1,171 @@
+use std::time::Instant;
+
+use rand::{distributions::{Distribution, WeightedIndex}, Rng};
+
+use crate::{computation::{random::SimulationRng, statistics::{mean_half_width, mean_variance}}, models::{action::Action, markov::markov_chain::MarkovChain, ModelState}, solution::SolverResult, verification::query::{Quantifier, StateLogic}, Query};
+
+use crate::log::*;
+
+/// Biased distribution of the successors of every node of a Markov chain, under which runs are simulated.
+/// A successor may only be biased, never removed : runs must stay possible under the biased measure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeOfMeasure {
+    pub choices : Vec<Vec<(usize, f64)>>,
+}
+
+impl ChangeOfMeasure {
+
+    // Original probabilities of the chain, nodes without successor having an empty choice
+    pub fn identity(chain : &MarkovChain) -> Self {
+        ChangeOfMeasure {
+            choices : chain.nodes.iter().map(|node| match node.actions.get(&Action::Epsilon) {
+                Some(choice) => choice.0.clone(),
+                None => Vec::new()
+            }).collect()
+        }
+    }
+
+    // Every successor equally likely, a usual starting point for rare events
+    pub fn uniform(chain : &MarkovChain) -> Self {
+        let mut measure = Self::identity(chain);
+        for choice in measure.choices.iter_mut() {
+            let n = choice.len() as f64;
+            for (_, q) in choice.iter_mut() {
+                *q = 1.0 / n;
+            }
+        }
+        measure
+    }
+
+    pub fn probability(&self, from : usize, to : usize) -> f64 {
+        self.choices[from].iter().filter(|(j, _)| *j == to).map(|(_, q)| q).sum()
+    }
+
+    fn sample(&self, from : usize, rng : &mut impl Rng) -> Option<usize> {
+        let choice = &self.choices[from];
+        let dist = WeightedIndex::new(choice.iter().map(|(_, q)| *q)).ok()?;
+        Some(choice[dist.sample(rng)].0)
+    }
+
+}
+
+// Outcome of a run simulated under the biased measure
+struct WeightedRun {
+    success : bool,
+    likelihood : f64,
+    transitions : Vec<(usize, usize)>,
+}
+
+/// Estimates P [F phi] on Markov chains without decisions by importance sampling : runs are simulated under a change
+/// of measure making phi likely, and each successful run is weighted by its likelihood ratio, the product of
+/// p(i,j) / q(i,j) along the run, for the estimation to stay unbiased. The change of measure is given, or learnt by
+/// iterations of the cross-entropy method, each one fitting the measure to the successful runs of the previous one.
+/// Runs end at the query bound (time bounds read as steps bounds), or when an absorbing node is reached.
+#[derive(Debug, Clone)]
+pub struct ImportanceSampling {
+    pub runs : usize,
+    pub confidence : f64,
+    pub measure : Option<ChangeOfMeasure>,
+    pub ce_iterations : usize,
+    pub ce_runs : usize,
+    // Part of the previous measure kept at each cross-entropy update, so that no successor gets a null probability
+    pub smoothing : f64,
+}
+
+impl ImportanceSampling {
+
+    pub fn new(runs : usize, confidence : f64) -> Self {
+        ImportanceSampling { runs, confidence, measure : None, ce_iterations : 0, ce_runs : 0, smoothing : 0.1 }
+    }
+
+    pub fn with_measure(mut self, measure : ChangeOfMeasure) -> Self {
+        self.measure = Some(measure);
+        self
+    }
+
+    pub fn with_cross_entropy(mut self, iterations : usize, runs : usize) -> Self {
+        self.ce_iterations = iterations;
+        self.ce_runs = runs;
+        self
+    }
+
+    pub fn estimate(&mut self, chain : &MarkovChain, initial_state : &ModelState, query : &Query) -> SolverResult {
+        if query.quantifier != Quantifier::Probability || query.logic != StateLogic::Finally || !query.condition.is_state_condition() {
+            return SolverResult::unknown("Importance sampling only estimates P [F phi] on state conditions");
+        }
+        if chain.nodes.iter().any(|n| n.is_choice()) {
+            return SolverResult::unknown("Importance sampling needs a Markov chain without decisions");
+        }
+        info("Estimating probability using importance sampling...");
+        let now = Instant::now();
+        let original = ChangeOfMeasure::identity(chain);
+        let mut measure = self.measure.clone().unwrap_or_else(|| ChangeOfMeasure::uniform(chain));
+        let mut rng = SimulationRng::from_entropy();
+        for iteration in 0..self.ce_iterations {
+            let runs : Vec<WeightedRun> = (0..self.ce_runs).map(|_| Self::run(chain, &original, &measure, initial_state, query, &mut rng)).collect();
+            let successes = runs.iter().filter(|r| r.success).count();
+            continue_info(format!("Cross-entropy iteration {} : {} successful runs", iteration, successes));
+            measure = self.cross_entropy_update(&measure, &runs);
+        }
+        self.measure = Some(measure.clone());
+        continue_info(format!("Runs to be executed : {}", self.runs));
+        pending("Starting...");
+        let weights : Vec<f64> = (0..self.runs).map(|_| {
+            let run = Self::run(chain, &original, &measure, initial_state, query, &mut rng);
+            if run.success { run.likelihood } else { 0.0 }
+        }).collect();
+        let (estimate, variance) = mean_variance(&weights);
+        let half_width = mean_half_width(variance, weights.len(), self.confidence);
+        let elapsed = now.elapsed().as_secs_f64();
+        positive(format!("Estimation complete, probability : {}", estimate));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        SolverResult::probability(estimate, half_width, self.confidence)
+    }
+
+    // q(i,j) proportional to the likelihood-weighted number of i -> j steps in successful runs, smoothed with the previous measure
+    fn cross_entropy_update(&self, measure : &ChangeOfMeasure, runs : &[WeightedRun]) -> ChangeOfMeasure {
+        let mut counts : Vec<Vec<f64>> = measure.choices.iter().map(|c| vec![0.0; c.len()]).collect();
+        for run in runs.iter().filter(|r| r.success) {
+            for (from, to) in run.transitions.iter() {
+                if let Some(k) = measure.choices[*from].iter().position(|(j, _)| j == to) {
+                    counts[*from][k] += run.likelihood;
+                }
+            }
+        }
+        let mut updated = measure.clone();
+        for (choice, counts) in updated.choices.iter_mut().zip(counts) {
+            let total : f64 = counts.iter().sum();
+            if total <= 0.0 {
+                continue;
+            }
+            for ((_, q), count) in choice.iter_mut().zip(counts) {
+                *q = (1.0 - self.smoothing) * count / total + self.smoothing * *q;
+            }
+        }
+        updated
+    }
+
+    fn run(chain : &MarkovChain, original : &ChangeOfMeasure, measure : &ChangeOfMeasure, initial_state : &ModelState, query : &Query, rng : &mut SimulationRng) -> WeightedRun {
+        let limit = query.run_bound.step_limit().unwrap_or(usize::MAX);
+        let mut state = initial_state.clone();
+        let mut current = state.argmax(chain.get_vars());
+        let mut run = WeightedRun { success : false, likelihood : 1.0, transitions : Vec::new() };
+        for _ in 0..limit {
+            if query.condition.is_true(&state) {
+                run.success = true;
+                break;
+            }
+            let absorbing = original.choices[current].iter().all(|(j, _)| *j == current);
+            let Some(next) = measure.sample(current, rng).filter(|_| !absorbing) else {
+                break;
+            };
+            run.likelihood *= original.probability(current, next) / measure.probability(current, next);
+            run.transitions.push((current, next));
+            state.unmark(chain.nodes[current].get_var(), 1);
+            state.mark(chain.nodes[next].get_var(), 1);
+            current = next;
+        }
+        run
+    }
+
+}
@@ -0,0 +1,141 @@
+use std::{rc::Rc, time::Instant};
+
+use num_traits::Zero;
+
+use crate::{computation::{random::SimulationRng, statistics::normal_quantile}, models::{expressions::{Condition, PropositionType}, run::RunStatus, Model, ModelState}, solution::SolverResult, verification::query::{Quantifier, StateLogic}, Query};
+
+use crate::log::*;
+
+pub type LevelFunction = Box<dyn Fn(&ModelState) -> f64 + Send + Sync>;
+
+/// Rare-event estimation of P [F phi] queries by fixed-effort importance splitting. A level function measures the
+/// progress of a run towards phi, and increasing thresholds split the event in a sequence of more likely ones :
+/// at each stage, `effort` runs are started again from the states in which the previous stage crossed its threshold,
+/// and the probability of crossing the next one is estimated. The product of these conditional probabilities is
+/// an unbiased estimation of P [F phi], the runs keeping the bound of the query from the initial state.
+pub struct ImportanceSplitting {
+    pub levels : Vec<f64>,
+    pub effort : usize,
+    pub confidence : f64,
+    level_function : LevelFunction,
+}
+
+impl ImportanceSplitting {
+
+    pub fn new(level_function : LevelFunction, levels : Vec<f64>, effort : usize, confidence : f64) -> Self {
+        ImportanceSplitting { levels, effort, confidence, level_function }
+    }
+
+    // Levels evenly spaced between the initial state and phi, phi being reached when the score derived from it is non-negative
+    pub fn for_query(query : &Query, initial_state : &ModelState, n_levels : usize, effort : usize, confidence : f64) -> Option<Self> {
+        let score = Self::score(&query.condition)?;
+        let start = score(initial_state);
+        if start >= 0.0 {
+            return None;
+        }
+        let levels = (1..=n_levels).map(|k| start * (1.0 - k as f64 / (n_levels + 1) as f64)).collect();
+        Some(Self::new(score, levels, effort, confidence))
+    }
+
+    // Level function derived from a state condition : how far the state is from verifying it, negative until it does.
+    // Comparisons give the difference of their sides, conjunctions the farthest of their operands and disjunctions the closest.
+    pub fn score(condition : &Condition) -> Option<LevelFunction> {
+        match condition {
+            Condition::True => Some(Box::new(|_| 0.0)),
+            Condition::Evaluation(e) => {
+                let e = e.clone();
+                Some(Box::new(move |s| e.evaluate(s).as_float() - 1.0))
+            },
+            Condition::Proposition(p_type, e1, e2) => {
+                let (e1, e2) = (e1.clone(), e2.clone());
+                let difference = move |s : &ModelState| e1.evaluate(s).as_float() - e2.evaluate(s).as_float();
+                Some(match p_type {
+                    PropositionType::GE => Box::new(difference),
+                    PropositionType::GS => Box::new(move |s| difference(s) - 1.0),
+                    PropositionType::LE => Box::new(move |s| -difference(s)),
+                    PropositionType::LS => Box::new(move |s| -difference(s) - 1.0),
+                    PropositionType::EQ => Box::new(move |s| -difference(s).abs()),
+                    PropositionType::NE => return None
+                })
+            },
+            Condition::And(c1, c2) => {
+                let (s1, s2) = (Self::score(c1)?, Self::score(c2)?);
+                Some(Box::new(move |s| s1(s).min(s2(s))))
+            },
+            Condition::Or(c1, c2) => {
+                let (s1, s2) = (Self::score(c1)?, Self::score(c2)?);
+                Some(Box::new(move |s| s1(s).max(s2(s))))
+            },
+            _ => None
+        }
+    }
+
+    pub fn level(&self, state : &ModelState) -> f64 {
+        (self.level_function)(state)
+    }
+
+    pub fn estimate(&self, model : &impl Model, initial_state : &ModelState, query : &Query) -> SolverResult {
+        if !matches!(query.quantifier, Quantifier::Probability) || query.logic != StateLogic::Finally || !query.condition.is_state_condition() {
+            return SolverResult::unknown("Importance splitting only estimates P [F phi] on state conditions");
+        }
+        info("Estimating rare event probability using importance splitting...");
+        continue_info(format!("Levels : {:?}", self.levels));
+        continue_info(format!("Runs per level : {}", self.effort));
+        pending("Starting...");
+        let now = Instant::now();
+        let mut rng = SimulationRng::from_entropy();
+        let mut entrances = vec![RunStatus::new(Rc::new(initial_state.clone()))];
+        let mut estimate = 1.0;
+        let mut relative_variance = 0.0;
+        for stage in 0..=self.levels.len() {
+            let threshold = self.levels.get(stage).copied();
+            let reached = |state : &ModelState| {
+                query.condition.is_true(state) || threshold.is_some_and(|l| self.level(state) >= l)
+            };
+            let hits : Vec<RunStatus> = (0..self.effort).filter_map(|i| {
+                Self::run_until(model, entrances[i % entrances.len()].clone(), query, &reached, &mut rng)
+            }).collect();
+            let p = hits.len() as f64 / self.effort as f64;
+            continue_info(format!("Stage {} : {}", stage, p));
+            estimate *= p;
+            if hits.is_empty() {
+                relative_variance = 0.0;
+                break;
+            }
+            relative_variance += (1.0 - p) / (self.effort as f64 * p);
+            entrances = hits;
+        }
+        let z = normal_quantile(1.0 - (1.0 - self.confidence) / 2.0);
+        let elapsed = now.elapsed().as_secs_f64();
+        positive(format!("Estimation complete, probability : {}", estimate));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        SolverResult::probability(estimate, z * estimate * relative_variance.sqrt(), self.confidence)
+    }
+
+    // Continues the run until a state is reached, None if it ends before or exceeds the bound of the query
+    fn run_until(model : &impl Model, mut status : RunStatus, query : &Query, reached : &impl Fn(&ModelState) -> bool, rng : &mut SimulationRng) -> Option<RunStatus> {
+        loop {
+            if reached(&status.current_state) {
+                return Some(status);
+            }
+            if status.current_state.deadlocked {
+                return None;
+            }
+            let (next, delay, action) = model.random_next(status.current_state.as_ref().clone(), rng);
+            let next = next?;
+            // Nothing happened : the run is stuck in its current state
+            if action.is_none() && delay.is_zero() {
+                return None;
+            }
+            let left = Rc::clone(&status.current_state);
+            status.earn_rewards(&query.run_bound, &left, &next, delay, action.as_ref(), model.is_timed());
+            status.steps += action.is_some() as usize;
+            status.time += delay;
+            status.current_state = Rc::new(next);
+            if !status.is_under(&query.run_bound) {
+                return None;
+            }
+        }
+    }
+
+}
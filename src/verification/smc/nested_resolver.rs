@@ -0,0 +1,83 @@
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}};
+
+use crate::{computation::random::RunSeeds, models::{Model, ModelState}, verification::VerificationStatus, Query};
+
+use super::{ProbabilityFloatComparison, SMCQueryVerification};
+
+use VerificationStatus::*;
+
+/// Answers the nested sub-queries of a condition from a given state.
+/// Any closure can be used, e.g. to plug an analytic solution in.
+pub trait NestedQueryResolver {
+
+    fn resolve(&mut self, query : &Query, state : &ModelState) -> VerificationStatus;
+
+}
+
+impl<F> NestedQueryResolver for F where F : FnMut(&Query, &ModelState) -> VerificationStatus {
+
+    fn resolve(&mut self, query : &Query, state : &ModelState) -> VerificationStatus {
+        self(query, state)
+    }
+
+}
+
+/// Resolves nested P~p queries by running a sequential probability ratio test from the current state.
+/// Deeper nested queries are resolved recursively by the same resolver, and answers are cached per state.
+pub struct SMCNestedResolver<'a> {
+    pub model : &'a dyn Model,
+    pub false_positives : f64,
+    pub false_negatives : f64,
+    pub indifference : f64,
+    pub tests_executed : usize,
+    cache : HashMap<(u64, u64), VerificationStatus>,
+}
+
+impl<'a> SMCNestedResolver<'a> {
+
+    pub fn new(model : &'a dyn Model, false_positives : f64, false_negatives : f64, indifference : f64) -> Self {
+        SMCNestedResolver {
+            model,
+            false_positives,
+            false_negatives,
+            indifference,
+            tests_executed : 0,
+            cache : HashMap::new(),
+        }
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+}
+
+impl<'a> NestedQueryResolver for SMCNestedResolver<'a> {
+
+    fn resolve(&mut self, query : &Query, state : &ModelState) -> VerificationStatus {
+        let mut s = DefaultHasher::new();
+        state.hash(&mut s);
+        let key = (s.finish(), query.get_query_hash());
+        if let Some(status) = self.cache.get(&key) {
+            return *status;
+        }
+        let Some(mut test) = ProbabilityFloatComparison::for_query(query, self.false_positives, self.false_negatives, self.indifference) else {
+            return Maybe;
+        };
+        let mut query = query.clone();
+        let mut seeds = RunSeeds::default();
+        while test.must_do_another_run() {
+            let result = ProbabilityFloatComparison::execute_nested_run(self.model, state, &mut query, seeds.next_seed(), self);
+            test.handle_run_result(result);
+        }
+        self.tests_executed += 1;
+        let status = match test.get_result().as_bool() {
+            Some(true) => Verified,
+            Some(false) => Unverified,
+            None => Maybe
+        };
+        self.cache.insert(key, status);
+        status
+    }
+
+}
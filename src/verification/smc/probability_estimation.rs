@@ -1,6 +1,16 @@
-use crate::{log::*, solution::SolverResult, verification::VerificationStatus};
+use crate::{computation::statistics::{clopper_pearson_interval, mean_half_width, mean_variance, wilson_interval}, log::*, solution::SolverResult, verification::VerificationStatus};
 
-use super::SMCQueryVerification;
+use super::{SampleGuarantee, SamplingStrategy, SMCQueryVerification};
+
+/// Confidence interval returned around the estimate. The default one is the interval of fixed width the number of
+/// runs was derived from, the others are computed from the runs, and stay valid for rare events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntervalMethod {
+    #[default]
+    Planned,
+    Wilson,
+    ClopperPearson,
+}
 
 #[derive(Debug, Clone)]
 pub struct ProbabilityEstimation {
@@ -9,6 +19,12 @@ pub struct ProbabilityEstimation {
     pub runs_needed : usize,
     pub executed_runs : usize,
     pub valid_runs : usize,
+    pub guarantee : Option<SampleGuarantee>,
+    pub interval_method : IntervalMethod,
+    pub sampling : SamplingStrategy,
+    pub seed : Option<u64>,
+    // Frequency of valid runs in each group of correlated runs, when sampling is grouped
+    pub group_means : Vec<f64>,
 }
 
 impl ProbabilityEstimation {
@@ -18,7 +34,12 @@ impl ProbabilityEstimation {
             confidence, interval_width,
             runs_needed : Self::chernoff_hoeffding_bound(confidence, interval_width),
             executed_runs : 0,
-            valid_runs: 0
+            valid_runs: 0,
+            guarantee : None,
+            interval_method : IntervalMethod::Planned,
+            sampling : SamplingStrategy::Independent,
+            seed : None,
+            group_means : Vec::new()
         }
     }
 
@@ -28,10 +49,53 @@ impl ProbabilityEstimation {
             interval_width : (4.0 * (2.0 / (1.0 - confidence)).ln() / (runs as f64)).sqrt(),
             runs_needed : runs,
             executed_runs : 0,
-            valid_runs: 0
+            valid_runs: 0,
+            guarantee : None,
+            interval_method : IntervalMethod::Planned,
+            sampling : SamplingStrategy::Independent,
+            seed : None,
+            group_means : Vec::new()
         }
     }
 
+    // Runs derived from the requested error (relative when a lower bound on the probability is given) and error probability,
+    // see SampleGuarantee::plan
+    pub fn with_precision(epsilon : f64, delta : f64, min_probability : Option<f64>) -> Self {
+        Self::planned(SampleGuarantee::plan(epsilon, delta, min_probability))
+    }
+
+    pub fn planned(guarantee : SampleGuarantee) -> Self {
+        ProbabilityEstimation {
+            confidence : guarantee.confidence(),
+            interval_width : 2.0 * guarantee.epsilon,
+            runs_needed : guarantee.runs,
+            executed_runs : 0,
+            valid_runs : 0,
+            guarantee : Some(guarantee),
+            interval_method : IntervalMethod::Planned,
+            sampling : SamplingStrategy::Independent,
+            seed : None,
+            group_means : Vec::new()
+        }
+    }
+
+    pub fn with_interval(mut self, method : IntervalMethod) -> Self {
+        self.interval_method = method;
+        self
+    }
+
+    // Correlated runs, the interval being derived from the variance of the group means
+    pub fn with_sampling(mut self, sampling : SamplingStrategy) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    // Runs drawn from the seed, so that verifications with the same seed execute the same runs
+    pub fn with_seed(mut self, seed : u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
     fn chernoff_hoeffding_bound(confidence : f64, interval_width : f64) -> usize {
         let bound = 4.0 * (2.0 / (1.0 - confidence)).ln() / interval_width.powi(2);
         bound.ceil() as usize
@@ -45,11 +109,51 @@ impl SMCQueryVerification for ProbabilityEstimation {
         continue_info("Type : Probability estimation");
         continue_info(format!("Confidence : {}%", self.confidence * 100.0));
         continue_info(format!("Interval width : {}", self.interval_width));
+        if self.interval_method != IntervalMethod::Planned {
+            continue_info(format!("Interval : {:?}", self.interval_method));
+        }
+        if let Some(guarantee) = &self.guarantee {
+            continue_info(format!("Guarantee : {}", guarantee));
+        }
+        if self.sampling.is_grouped() {
+            continue_info(format!("Sampling : {}", self.sampling));
+        }
         continue_info(format!("Need to execute [{}] runs", self.runs_needed));
     }
 
     fn finish(&self) {
         continue_info(format!("Valid runs : [{}]", self.valid_runs));
+        if self.sampling.is_grouped() && self.group_means.len() > 1 {
+            let (_, variance) = mean_variance(&self.group_means);
+            let group_size = self.sampling.group_size() as f64;
+            let estimate = self.valid_runs as f64 / self.executed_runs as f64;
+            let independent_variance = estimate * (1.0 - estimate) / group_size;
+            continue_info(format!("Groups : [{}]", self.group_means.len()));
+            if variance > 0.0 {
+                continue_info(format!("Variance reduction factor : {:.3}", independent_variance / variance));
+            }
+        }
+    }
+
+    fn sampling(&self) -> SamplingStrategy {
+        self.sampling
+    }
+
+    fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    fn handle_group_results(&mut self, results : &[VerificationStatus]) {
+        for result in results.iter() {
+            self.handle_run_result(*result);
+        }
+        if self.sampling.is_grouped() {
+            self.group_means.push(results.iter().filter(|r| r.good()).count() as f64 / results.len() as f64);
+        }
+    }
+
+    fn expected_runs(&self) -> Option<usize> {
+        Some(self.runs_needed)
     }
 
     fn must_do_another_run(&self) -> bool {
@@ -64,7 +168,23 @@ impl SMCQueryVerification for ProbabilityEstimation {
     }
 
     fn get_result(&self) -> SolverResult {
-        SolverResult::FloatResult( (self.valid_runs as f64) / (self.executed_runs as f64) )
+        let estimate = (self.valid_runs as f64) / (self.executed_runs as f64);
+        if self.sampling.is_grouped() && self.group_means.len() > 1 {
+            let (mean, variance) = mean_variance(&self.group_means);
+            return SolverResult::probability(mean, mean_half_width(variance, self.group_means.len(), self.confidence), self.confidence);
+        }
+        let interval = match self.interval_method {
+            IntervalMethod::Wilson => wilson_interval(self.valid_runs, self.executed_runs, self.confidence),
+            IntervalMethod::ClopperPearson => clopper_pearson_interval(self.valid_runs, self.executed_runs, self.confidence),
+            IntervalMethod::Planned => {
+                let half_width = match &self.guarantee {
+                    Some(guarantee) => guarantee.half_width(estimate),
+                    None => self.interval_width / 2.0
+                };
+                return SolverResult::probability(estimate, half_width, self.confidence);
+            }
+        };
+        SolverResult::ProbabilityResult { estimate, interval, confidence : self.confidence }
     }
 
 }
@@ -1,4 +1,4 @@
-use crate::{solution::SolverResult, verification::VerificationStatus};
+use crate::{models::expressions::PropositionType, solution::SolverResult, verification::{query::{ProbabilityThreshold, Quantifier}, VerificationStatus}, Query};
 
 use super::SMCQueryVerification;
 
@@ -19,7 +19,8 @@ pub struct ProbabilityFloatComparison {
     pub bound_h1 : f64,
     pub current_ratio : f64,
     pub status : VerificationStatus,
-    pub runs_executed : usize
+    pub runs_executed : usize,
+    pub negate_runs : bool,
 }
 
 // Tests if P(Phi) >= p
@@ -42,7 +43,25 @@ impl ProbabilityFloatComparison {
             bound_h1 : ((1.0 - false_negatives) / false_positives).ln(),
             current_ratio : 0.0,
             status : VerificationStatus::Maybe,
-            runs_executed : 0
+            runs_executed : 0,
+            negate_runs : false,
+        }
+    }
+
+    // Builds the test answering a P~p query. Upper bounds are tested on the negated runs : P(phi) <= p <=> P(not phi) >= 1-p
+    pub fn for_query(query : &Query, false_positives : f64, false_negatives : f64, indifference : f64) -> Option<Self> {
+        let Quantifier::ProbabilityBound(prop_type, ProbabilityThreshold(p)) = query.quantifier else {
+            return None;
+        };
+        match prop_type {
+            PropositionType::GE | PropositionType::GS => 
+                Some(Self::new(p, false_positives, false_negatives, indifference, indifference)),
+            PropositionType::LE | PropositionType::LS => {
+                let mut test = Self::new(1.0 - p, false_positives, false_negatives, indifference, indifference);
+                test.negate_runs = true;
+                Some(test)
+            },
+            _ => None
         }
     }
 
@@ -52,7 +71,11 @@ impl SMCQueryVerification for ProbabilityFloatComparison {
 
     fn prepare(&self) {
         continue_info("Type : Probability comparison");
-        continue_info(format!("Comparing : P >= {}", self.target_probability));
+        if self.negate_runs {
+            continue_info(format!("Comparing : P <= {}", 1.0 - self.target_probability));
+        } else {
+            continue_info(format!("Comparing : P >= {}", self.target_probability));
+        }
         continue_info(format!("Allowed false positives : {}%", self.false_positives * 100.0));
         continue_info(format!("Allowed false negatives : {}%", self.false_negatives * 100.0));
         continue_info(format!("Indifference region : [{},{}]", self.p1, self.p0));
@@ -63,6 +86,7 @@ impl SMCQueryVerification for ProbabilityFloatComparison {
     }
 
     fn handle_run_result(&mut self, result : VerificationStatus) {
+        let result = if self.negate_runs { !result } else { result };
         self.current_ratio += match result {
             Verified => (self.p1 / self.p0).ln(),
             Unverified => ((1.0 - self.p1) / (1.0 - self.p0)).ln(),
@@ -77,7 +101,10 @@ impl SMCQueryVerification for ProbabilityFloatComparison {
     }
 
     fn get_result(&self) -> SolverResult {
-        SolverResult::BoolResult(self.status.good())
+        let verdict = self.status.good();
+        // Accepting H0 (P >= p0) is wrong with probability at most beta, rejecting it with at most alpha
+        let error = if verdict { self.false_negatives } else { self.false_positives };
+        SolverResult::StatisticalBoolResult(verdict, error)
     }
 
     fn must_do_another_run(&self) -> bool {
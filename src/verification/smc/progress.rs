@@ -0,0 +1,105 @@
+use std::{fmt, sync::{atomic::{AtomicBool, Ordering}, Arc}};
+
+use crate::solution::SolverResult;
+
+pub type ProgressCallback = Arc<dyn Fn(&SMCProgress) + Send + Sync>;
+
+/// State of a running SMC verification, as reported to progress callbacks
+#[derive(Debug, Clone, PartialEq)]
+pub struct SMCProgress {
+    pub runs : usize,
+    // Runs the method will execute, if known in advance
+    pub expected_runs : Option<usize>,
+    pub estimate : SolverResult,
+    pub elapsed : f64,
+}
+
+impl SMCProgress {
+
+    pub fn fraction(&self) -> Option<f64> {
+        self.expected_runs.filter(|n| *n > 0).map(|n| (self.runs as f64 / n as f64).min(1.0))
+    }
+
+    // Remaining time in seconds, extrapolated from the time spent so far
+    pub fn eta(&self) -> Option<f64> {
+        let expected = self.expected_runs?;
+        if self.runs == 0 {
+            return None;
+        }
+        Some(self.elapsed / self.runs as f64 * expected.saturating_sub(self.runs) as f64)
+    }
+
+}
+
+impl fmt::Display for SMCProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expected_runs {
+            Some(n) => write!(f, "{}/{} runs", self.runs, n)?,
+            None => write!(f, "{} runs", self.runs)?
+        }
+        write!(f, ", {:.2}s elapsed", self.elapsed)?;
+        if let Some(eta) = self.eta() {
+            write!(f, ", {:.2}s remaining", eta)?;
+        }
+        write!(f, " : {}", self.estimate)
+    }
+}
+
+/// Handle on an SMC verification for the application embedding it : progress is reported to the callback every
+/// `interval` runs and when the verification ends, and the cancellation token is checked between runs.
+/// Clones share the same token, so a verification can be cancelled from another thread.
+#[derive(Clone)]
+pub struct SMCHandle {
+    pub interval : usize,
+    cancelled : Arc<AtomicBool>,
+    callback : Option<ProgressCallback>,
+}
+
+impl SMCHandle {
+
+    pub fn new() -> Self {
+        SMCHandle { interval : 100, cancelled : Arc::new(AtomicBool::new(false)), callback : None }
+    }
+
+    pub fn with_callback(mut self, callback : impl Fn(&SMCProgress) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn with_interval(mut self, interval : usize) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Whether progress is due after the given number of runs
+    pub fn is_due(&self, runs : usize) -> bool {
+        self.callback.is_some() && runs.is_multiple_of(self.interval)
+    }
+
+    pub fn report(&self, progress : &SMCProgress) {
+        if let Some(callback) = &self.callback {
+            callback(progress);
+        }
+    }
+
+}
+
+impl Default for SMCHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for SMCHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SMCHandle {{ interval : {}, cancelled : {} }}", self.interval, self.is_cancelled())
+    }
+}
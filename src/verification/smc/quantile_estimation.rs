@@ -0,0 +1,96 @@
+use std::time::Instant;
+
+use crate::{models::{expressions::PropositionType, Model, ModelState}, solution::SolverResult, verification::{query::{ProbabilityThreshold, Quantifier}, Verifiable, VerificationBound, VerificationStatus}, Query};
+use crate::log::*;
+
+use super::RandomRunIterator;
+
+/// Estimates Q~p [F phi] queries : the smallest date t such that P(F<=t phi) ~ p, computed from the date at which each run
+/// first verifies phi. Dates are times on timed models, steps otherwise (time bounds being read as steps bounds).
+/// Runs are cut at the query bound if any, the quantile being infinite if not reached before.
+/// The interval comes from the DKW inequality, valid for every date at once.
+#[derive(Debug, Clone)]
+pub struct QuantileEstimation {
+    pub runs_needed : usize,
+    pub confidence : f64,
+}
+
+impl QuantileEstimation {
+
+    // Enough runs for the empirical distribution of dates to be within interval_width / 2 of the real one
+    pub fn new(confidence : f64, interval_width : f64) -> Self {
+        let epsilon = interval_width / 2.0;
+        let runs = ((2.0 / (1.0 - confidence)).ln() / (2.0 * epsilon.powi(2))).ceil() as usize;
+        QuantileEstimation { runs_needed : runs, confidence }
+    }
+
+    pub fn fixed_runs(runs : usize, confidence : f64) -> Self {
+        QuantileEstimation { runs_needed : runs, confidence }
+    }
+
+    fn dkw_epsilon(&self) -> f64 {
+        ((2.0 / (1.0 - self.confidence)).ln() / (2.0 * self.runs_needed as f64)).sqrt()
+    }
+
+    pub fn estimate(&self, model : &impl Model, initial : &ModelState, query : &Query) -> SolverResult {
+        let Quantifier::Quantile(prop_type, ProbabilityThreshold(p)) = query.quantifier else {
+            return SolverResult::SolverError;
+        };
+        info("Estimating quantile using SMC...");
+        continue_info(format!("Runs to be executed : {}", self.runs_needed));
+        pending("Starting...");
+        let now = Instant::now();
+        let mut query = query.clone();
+        let mut dates : Vec<f64> = (0..self.runs_needed).map(|_| {
+            Self::hitting_date(model, initial, &mut query).unwrap_or(f64::INFINITY)
+        }).collect();
+        dates.sort_by(f64::total_cmp);
+        let strict = prop_type == PropositionType::GS;
+        let epsilon = self.dkw_epsilon();
+        let estimate = Self::empirical_quantile(&dates, p, strict);
+        let interval = (
+            Self::empirical_quantile(&dates, (p - epsilon).max(0.0), strict),
+            Self::empirical_quantile(&dates, p + epsilon, strict)
+        );
+        let elapsed = now.elapsed().as_secs_f64();
+        positive(format!("Estimation complete, quantile : {}", estimate));
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        SolverResult::NumericResult { estimate, interval, confidence : self.confidence }
+    }
+
+    // Date at which the run verifies the condition, None if it never does
+    fn hitting_date(model : &impl Model, initial : &ModelState, query : &mut Query) -> Option<f64> {
+        let timed = model.is_timed();
+        let bound = match query.run_bound {
+            VerificationBound::TimeRunBound(t) if !timed => VerificationBound::StepsRunBound(t as usize),
+            ref b => b.clone()
+        };
+        let mut run_gen = RandomRunIterator::generate(model, initial, bound);
+        for (state, _, _) in run_gen.by_ref() {
+            query.verify_state(state.as_verifiable());
+            if query.is_run_decided() {
+                break;
+            }
+        }
+        query.end_run();
+        let verified = query.run_status == VerificationStatus::Verified;
+        query.reset_run();
+        let status = &run_gen.run_status;
+        match verified {
+            false => None,
+            true if timed => Some(status.time.float()),
+            true => Some(status.steps as f64)
+        }
+    }
+
+    // Smallest date whose proportion of runs verified before it is at least p (strictly more than p if strict)
+    fn empirical_quantile(sorted_dates : &[f64], p : f64, strict : bool) -> f64 {
+        let n = sorted_dates.len() as f64;
+        let needed = if strict { (p * n).floor() as usize + 1 } else { (p * n).ceil() as usize };
+        if needed == 0 {
+            return 0.0;
+        }
+        sorted_dates.get(needed - 1).cloned().unwrap_or(f64::INFINITY)
+    }
+
+}
@@ -2,7 +2,7 @@ use std::rc::Rc;
 
 use num_traits::Zero;
 
-use crate::{models::{action::Action, run::RunStatus, time::ClockValue, Model, ModelState}, verification::VerificationBound};
+use crate::{computation::random::{DrawTransform, RunSeeds, SimulationRng}, models::{action::Action, run::RunStatus, time::ClockValue, Model, ModelState}, verification::VerificationBound};
 
 pub struct RandomRunIterator<'a> {
     pub model : &'a dyn Model,
@@ -10,32 +10,51 @@ pub struct RandomRunIterator<'a> {
     pub run_status : RunStatus,
     pub bound : VerificationBound,
     pub started : bool,
+    // The generator of the run is seeded with it when the run starts, so that the same seed gives the same run
+    pub seed : u64,
+    // Transform of the draws of the run, see SamplingStrategy
+    pub draws : DrawTransform,
+    pub rng : SimulationRng,
 }
 
 impl<'a> RandomRunIterator<'a> {
 
+    // Run seeded from entropy, see RunSeeds for reproducible runs
     pub fn generate(model : &'a dyn Model, initial : &'a ModelState, bound : VerificationBound) -> Self {
+        Self::seeded(model, initial, bound, RunSeeds::default().next_seed())
+    }
+
+    pub fn seeded(model : &'a dyn Model, initial : &'a ModelState, bound : VerificationBound, seed : u64) -> Self {
         RandomRunIterator {
             model,
             initial_state : initial,
-            run_status : RunStatus {
-                current_state : Rc::new(initial.clone()),
-                steps : 0,
-                time : ClockValue::zero(),
-                maximal : false
-            },
+            run_status : RunStatus::new(Rc::new(initial.clone())),
             bound,
-            started : false
+            started : false,
+            seed,
+            draws : DrawTransform::Identity,
+            rng : SimulationRng::seeded(seed)
         }
     }
 
+    // Replays the run of the given seed, as kept in run records
+    pub fn with_seed(mut self, seed : u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_draws(mut self, draws : DrawTransform) -> Self {
+        self.draws = draws;
+        self
+    }
+
+    // Draws made by the run so far : a run whose count does not change from a state on is deterministic from there
+    pub fn draws_count(&self) -> u64 {
+        self.rng.draws()
+    }
+
     pub fn reset(&mut self) {
-        self.run_status = RunStatus {
-            current_state : Rc::new(self.initial_state.clone()),
-            steps : 0,
-            time : ClockValue::zero(),
-            maximal : false
-        }
+        self.run_status = RunStatus::new(Rc::new(self.initial_state.clone()))
     }
 
 }
@@ -48,6 +67,7 @@ impl<'a> Iterator for RandomRunIterator<'a> {
         
         if !self.started { // Yield the initial state
             self.started = true;
+            self.rng = SimulationRng::seeded(self.seed).with_transform(self.draws);
             return Some((Rc::clone(&self.run_status.current_state), ClockValue::zero(), None));
         }
 
@@ -56,14 +76,17 @@ impl<'a> Iterator for RandomRunIterator<'a> {
         }
 
         let state = self.run_status.current_state.as_ref().clone();
-        let (next_state, delay, action) = self.model.random_next(state);
+        let (next_state, delay, action) = self.model.random_next(state, &mut self.rng);
 
         if next_state.is_none() {
             self.run_status.maximal = true;
             return None;
         }
 
-        self.run_status.current_state = Rc::new(next_state.unwrap());
+        let left = Rc::clone(&self.run_status.current_state);
+        let next_state = next_state.unwrap();
+        self.run_status.earn_rewards(&self.bound, &left, &next_state, delay, action.as_ref(), self.model.is_timed());
+        self.run_status.current_state = Rc::new(next_state);
         self.run_status.steps += match action { None => 0, Some(_) => 1 };
         self.run_status.time += delay;
 
@@ -78,4 +101,4 @@ impl<'a> Iterator for RandomRunIterator<'a> {
         Some((Rc::clone(&self.run_status.current_state), delay, action))
     }
 
-}
\ No newline at end of file
+}
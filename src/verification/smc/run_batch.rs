@@ -0,0 +1,127 @@
+use num_traits::Zero;
+
+use crate::{computation::random::{RunSeeds, SimulationRng}, models::{time::ClockValue, BatchedState, Model, ModelState, StateBatch}, verification::{VerificationBound, Verifiable}};
+
+use VerificationBound::*;
+
+/// Runs of the same model generated in lockstep, their states being kept in a single StateBatch and stepped at once
+/// (see Model::batch_random_next). Like RandomRunIterator, a run yields its states until it is maximal or leaves the
+/// bound. The whole batch is seeded at once : draws of the runs are interleaved, so a single run can't be replayed.
+pub struct RunBatch<'a> {
+    pub model : &'a dyn Model,
+    pub bound : VerificationBound,
+    pub states : StateBatch,
+    pub steps : Vec<usize>,
+    pub time : Vec<ClockValue>,
+    pub rewards : Vec<Vec<f64>>,
+    pub maximal : Vec<bool>,
+    // Runs whose current state has been yielded, and that can be stepped
+    pub active : Vec<bool>,
+    pub seed : u64,
+    pub rng : SimulationRng,
+    pub started : bool,
+}
+
+impl<'a> RunBatch<'a> {
+
+    pub fn generate(model : &'a dyn Model, initial : &ModelState, bound : VerificationBound, size : usize) -> Self {
+        Self::seeded(model, initial, bound, size, RunSeeds::default().next_seed())
+    }
+
+    pub fn seeded(model : &'a dyn Model, initial : &ModelState, bound : VerificationBound, size : usize, seed : u64) -> Self {
+        let rewards_count = bound.rewards().len();
+        RunBatch {
+            model,
+            bound,
+            states : StateBatch::filled(initial, size),
+            steps : vec![0 ; size],
+            time : vec![ClockValue::zero() ; size],
+            rewards : vec![vec![0.0 ; rewards_count] ; size],
+            maximal : vec![false ; size],
+            active : vec![true ; size],
+            seed,
+            rng : SimulationRng::seeded(seed),
+            started : false
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn state(&self, i : usize) -> BatchedState<'_> {
+        self.states.view(i)
+    }
+
+    pub fn is_active(&self, i : usize) -> bool {
+        self.active[i]
+    }
+
+    // The run won't be stepped anymore, typically once its query is decided
+    pub fn stop(&mut self, i : usize) {
+        self.active[i] = false;
+    }
+
+    // Steps every active run, returning the number of runs still active
+    pub fn step(&mut self) -> usize {
+        if !self.started {
+            self.started = true;
+            self.rng = SimulationRng::seeded(self.seed);
+        }
+        for (active, maximal) in self.active.iter_mut().zip(self.maximal.iter()) {
+            *active &= !*maximal;
+        }
+        let timed = self.model.is_timed();
+        let left = if self.bound.rewards().is_empty() { None } else { Some(self.states.clone()) };
+        let results = self.model.batch_random_next(&mut self.states, &self.active, &mut self.rng);
+        for (i, result) in results.into_iter().enumerate() {
+            if !self.active[i] {
+                continue;
+            }
+            let Some((delay, action)) = result else {
+                self.maximal[i] = true;
+                self.active[i] = false;
+                continue;
+            };
+            if let Some(left) = &left {
+                for (total, reward) in self.rewards[i].iter_mut().zip(self.bound.rewards()) {
+                    if timed {
+                        *total += reward.state_reward(&left.view(i)) * delay.float();
+                    } else if action.is_some() {
+                        *total += reward.state_reward(&left.view(i));
+                    }
+                    if let Some(action) = &action {
+                        *total += reward.step_reward(&left.view(i), &self.states.view(i), action);
+                    }
+                }
+            }
+            self.steps[i] += match action { None => 0, Some(_) => 1 };
+            self.time[i] += delay;
+            if self.states.is_deadlocked(i) {
+                self.maximal[i] = true;
+            }
+            if !self.is_under(i) {
+                self.active[i] = false;
+            }
+        }
+        self.active.iter().filter(|a| **a).count()
+    }
+
+    // Same as RunStatus::is_under, for the i-th run
+    pub fn is_under(&self, i : usize) -> bool {
+        let mut rewards = self.rewards[i].iter();
+        let state = self.state(i);
+        self.bound.bounds().into_iter().all(|bound| match bound {
+            TimeRunBound(t) => self.time[i] < ClockValue::from(*t as f64),
+            StepsRunBound(s) => self.steps[i] < *s,
+            VarRunBound(v, x) => state.evaluate_var(v) < *x,
+            RewardRunBound(_, x) => rewards.next().copied().unwrap_or(0.0) < x.0,
+            CompositeRunBound(_) | NoRunBound => true
+        })
+    }
+
+}
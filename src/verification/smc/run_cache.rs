@@ -0,0 +1,100 @@
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}};
+
+use crate::{models::run::RunStatus, verification::{query::Query, EvaluationState, VerificationBound, VerificationStatus, Verifiable}};
+
+use crate::log::*;
+
+/// Verdicts of runs continuing from (state, pending conditions) pairs, keyed on Query::get_evaluation_state along with
+/// the query and the progress of the run along its bound (steps, time or rewards, when bounded). Only continuations
+/// which took no random draw are kept, so a cached verdict is exactly the one the run would have reached : runs
+/// stop as soon as they reach a known pair, and stay independent. A run coming back to a pair without any draw in
+/// between loops forever, it is ended there with the verdict of unfinished runs.
+#[derive(Debug, Clone)]
+pub struct RunCache {
+    verdicts : HashMap<EvaluationState, VerificationStatus>,
+    pub max_entries : Option<usize>,
+    pub hits : usize,
+    pub cycles : usize,
+}
+
+impl RunCache {
+
+    pub fn new() -> Self {
+        RunCache {
+            verdicts : HashMap::new(),
+            max_entries : None,
+            hits : 0,
+            cycles : 0
+        }
+    }
+
+    // No new pair is stored beyond this size
+    pub fn with_max_entries(mut self, max_entries : usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    pub fn key(query : &Query, state : &impl Verifiable, run : &RunStatus) -> EvaluationState {
+        let mut s = DefaultHasher::new();
+        query.get_query_hash().hash(&mut s);
+        query.get_evaluation_state(state).hash(&mut s);
+        for bound in query.run_bound.bounds() {
+            match bound {
+                VerificationBound::StepsRunBound(_) => run.steps.hash(&mut s),
+                VerificationBound::TimeRunBound(_) => run.time.hash(&mut s),
+                _ => ()
+            }
+        }
+        for reward in run.rewards.iter() {
+            reward.to_bits().hash(&mut s);
+        }
+        s.finish()
+    }
+
+    pub fn lookup(&mut self, key : EvaluationState) -> Option<VerificationStatus> {
+        let verdict = self.verdicts.get(&key).copied();
+        if verdict.is_some() {
+            self.hits += 1;
+        }
+        verdict
+    }
+
+    // Verdict of a run, for the pairs it went through after its last random draw
+    pub fn record(&mut self, keys : &[EvaluationState], verdict : VerificationStatus) {
+        for key in keys.iter() {
+            if self.max_entries.is_some_and(|max| self.verdicts.len() >= max) {
+                return;
+            }
+            self.verdicts.insert(*key, verdict);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.verdicts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verdicts.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.verdicts.clear();
+        self.hits = 0;
+        self.cycles = 0;
+    }
+
+    pub fn log(&self) {
+        continue_info(format!("Cached pairs : [{}]", self.len()));
+        continue_info(format!("Cache hits : [{}]", self.hits));
+        if self.cycles > 0 {
+            continue_info(format!("Deterministic cycles : [{}]", self.cycles));
+        }
+    }
+
+}
+
+impl Default for RunCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
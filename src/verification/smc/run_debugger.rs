@@ -0,0 +1,216 @@
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use crate::{computation::virtual_memory::EvaluationType, models::{action::Action, expressions::Numeric, time::ClockValue, model_context::ModelContext, Label, Model, ModelState}, verification::{text_query_parser::{parse_condition, parse_expression}, VerificationBound, Verifiable}};
+
+use super::{RandomRunIterator, RunRecord};
+
+use crate::log::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebuggerError(pub String);
+impl fmt::Display for DebuggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Debugger error : {}", self.0)
+    }
+}
+pub type DebuggerResult<T> = Result<T, DebuggerError>;
+
+/// Point of a debugged run : the state reached after waiting `delay` and firing `action`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunFrame {
+    pub steps : usize,
+    pub time : f64,
+    pub delay : f64,
+    pub action : Option<Label>,
+    pub state : Rc<ModelState>,
+}
+
+impl fmt::Display for RunFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.action {
+            Some(action) => write!(f, "step {} at {:.3} : ({:.3}) {}", self.steps, self.time, self.delay, action),
+            None => write!(f, "step {} at {:.3} : ({:.3})", self.steps, self.time, self.delay)
+        }
+    }
+}
+
+/// Time-travel debugger over a run : every state of the run is kept, so that the cursor can be moved freely
+/// along it, ad-hoc expressions and conditions evaluated at any point, and new simulations branched from there.
+/// Frontends (interactive CLI, GUIs) only have to drive this API.
+pub struct RunDebugger<'a> {
+    pub model : &'a dyn Model,
+    pub ctx : &'a ModelContext,
+    // Seed of the simulated part of the run : the branch only, for debuggers created by branching
+    pub seed : u64,
+    frames : Vec<RunFrame>,
+    cursor : usize,
+    action_names : HashMap<usize, Label>,
+}
+
+impl<'a> RunDebugger<'a> {
+
+    // Records every state of the given run, the cursor being set on the initial one
+    pub fn new(model : &'a dyn Model, ctx : &'a ModelContext, run : RandomRunIterator) -> Self {
+        let action_names = ctx.get_actions().into_iter().map(|(name, action)| (action.get_id(), name)).collect();
+        let mut debugger = RunDebugger { model, ctx, seed : run.seed, frames : Vec::new(), cursor : 0, action_names };
+        debugger.extend(run, 0, 0.0);
+        debugger
+    }
+
+    // Replays a recorded run from its seed, within the bound it was executed with
+    pub fn replay(model : &'a dyn Model, ctx : &'a ModelContext, initial : &ModelState, record : &RunRecord, bound : VerificationBound) -> Self {
+        Self::new(model, ctx, RandomRunIterator::generate(model, initial, bound).with_seed(record.seed))
+    }
+
+    fn extend(&mut self, run : impl Iterator<Item = (Rc<ModelState>, ClockValue, Option<Action>)>, steps : usize, time : f64) {
+        let (mut steps, mut time) = (steps, time);
+        for (state, delay, action) in run {
+            let delay = delay.float();
+            time += delay;
+            if action.is_some() {
+                steps += 1;
+            }
+            let action = action.filter(|a| !a.is_epsilon()).map(|a| {
+                self.action_names.get(&a.get_id()).cloned().unwrap_or_else(|| Label::from(format!("#{}", a.get_id())))
+            });
+            self.frames.push(RunFrame { steps, time, delay, action, state });
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn frames(&self) -> &[RunFrame] {
+        &self.frames
+    }
+
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn frame(&self) -> &RunFrame {
+        &self.frames[self.cursor]
+    }
+
+    pub fn state(&self) -> &ModelState {
+        &self.frame().state
+    }
+
+    // Moves the cursor to the N-th frame, the initial state being frame 0
+    pub fn jump(&mut self, index : usize) -> DebuggerResult<&RunFrame> {
+        if index >= self.frames.len() {
+            return Err(DebuggerError(format!("Frame {} out of the run ({} frames)", index, self.frames.len())));
+        }
+        self.cursor = index;
+        Ok(self.frame())
+    }
+
+    // Moves the cursor to the last frame reached at the given date
+    pub fn jump_to_time(&mut self, time : f64) -> &RunFrame {
+        self.cursor = self.frames.iter().rposition(|f| f.time <= time).unwrap_or(0);
+        self.frame()
+    }
+
+    pub fn forward(&mut self) -> Option<&RunFrame> {
+        if self.cursor + 1 >= self.frames.len() {
+            return None;
+        }
+        self.cursor += 1;
+        Some(self.frame())
+    }
+
+    pub fn back(&mut self) -> Option<&RunFrame> {
+        if self.cursor == 0 {
+            return None;
+        }
+        self.cursor -= 1;
+        Some(self.frame())
+    }
+
+    // Evaluates an expression such as "p1 + 2 * p2" in the current state
+    pub fn evaluate(&self, expression : &str) -> DebuggerResult<Numeric> {
+        let expr = parse_expression(expression).map_err(|e| DebuggerError(e.message))?;
+        let expr = expr.apply_to(self.ctx).map_err(|e| DebuggerError(e.to_string()))?;
+        Ok(expr.evaluate(self.state()))
+    }
+
+    // Checks a state condition such as "p1 >= 2 && deadlock" in the current state
+    pub fn check(&self, condition : &str) -> DebuggerResult<bool> {
+        self.check_at(self.cursor, condition)
+    }
+
+    fn check_at(&self, index : usize, condition : &str) -> DebuggerResult<bool> {
+        let cond = parse_condition(condition).map_err(|e| DebuggerError(e.message))?;
+        if !cond.is_state_condition() {
+            return Err(DebuggerError(format!("'{}' is not a state condition", condition)));
+        }
+        let cond = cond.apply_to(self.ctx).map_err(|e| DebuggerError(e.to_string()))?;
+        Ok(cond.is_true(self.frames[index].state.as_ref()))
+    }
+
+    // Moves the cursor forward to the next frame where the condition holds, like a breakpoint. The cursor is left
+    // in place if there is none
+    pub fn run_until(&mut self, condition : &str) -> DebuggerResult<Option<&RunFrame>> {
+        for index in (self.cursor + 1)..self.frames.len() {
+            if self.check_at(index, condition)? {
+                self.cursor = index;
+                return Ok(Some(self.frame()));
+            }
+        }
+        Ok(None)
+    }
+
+    // Values of the variables, and of the enabled clocks, in the current state
+    pub fn variables(&self) -> Vec<(Label, EvaluationType)> {
+        let mut vars = self.ctx.get_vars();
+        vars.sort_by_key(|var| var.get_address());
+        vars.into_iter().map(|var| (var.get_name(), self.state().evaluate_var(&var))).collect()
+    }
+
+    pub fn clocks(&self) -> Vec<(Label, f64)> {
+        self.ctx.get_clocks().into_iter()
+            .filter(|clock| self.state().is_enabled(clock))
+            .map(|clock| (clock.get_name(), self.state().evaluate_clock(&clock)))
+            .collect()
+    }
+
+    // New debugger keeping the run up to the cursor, and continuing it with a fresh simulation within the bound.
+    // Steps and time of the bound are counted from the branching point
+    pub fn branch(&self, bound : VerificationBound) -> RunDebugger<'a> {
+        self.branch_run(RandomRunIterator::generate(self.model, self.state(), bound))
+    }
+
+    pub fn branch_with_seed(&self, bound : VerificationBound, seed : u64) -> RunDebugger<'a> {
+        self.branch_run(RandomRunIterator::generate(self.model, self.state(), bound).with_seed(seed))
+    }
+
+    fn branch_run(&self, run : RandomRunIterator) -> RunDebugger<'a> {
+        let mut debugger = RunDebugger {
+            model : self.model,
+            ctx : self.ctx,
+            seed : run.seed,
+            frames : self.frames[..=self.cursor].to_vec(),
+            cursor : self.cursor,
+            action_names : self.action_names.clone()
+        };
+        // The branching state, yielded first by the run, is already the last frame kept
+        debugger.extend(run.skip(1), self.frame().steps, self.frame().time);
+        debugger
+    }
+
+    pub fn log_frame(&self) {
+        info(format!("Frame {}/{} : {}", self.cursor, self.frames.len() - 1, self.frame()));
+        for (var, value) in self.variables() {
+            continue_info(format!("{} = {}", var, value));
+        }
+        for (clock, value) in self.clocks() {
+            continue_info(format!("{}.clock = {:.3}", clock, value));
+        }
+    }
+
+}
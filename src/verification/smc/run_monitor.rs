@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{action::Action, model_context::ModelContext, model_var::ModelVar, time::ClockValue, Label, ModelState};
+
+/// Column produced by a run monitor, with its documentation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorColumn {
+    pub name : String,
+    pub description : String,
+}
+
+impl MonitorColumn {
+
+    pub fn new(name : impl ToString, description : impl ToString) -> Self {
+        MonitorColumn { name : name.to_string(), description : description.to_string() }
+    }
+
+}
+
+/// Observes SMC runs step by step and summarizes each of them as a few numerical values
+pub trait RunMonitor : Send {
+
+    fn columns(&self) -> Vec<MonitorColumn>;
+
+    // Called before each run
+    fn reset(&mut self);
+
+    // Called on each state of the run, `delay` being the time elapsed since the previous state
+    fn observe(&mut self, state : &ModelState, delay : ClockValue, action : &Option<Action>);
+
+    // One value per column, at the end of the run
+    fn values(&self) -> Vec<f64>;
+
+}
+
+/// Records the final and maximal values of a variable
+pub struct VarMonitor {
+    pub var : ModelVar,
+    last : f64,
+    max : f64,
+}
+
+impl VarMonitor {
+
+    pub fn new(var : ModelVar) -> Self {
+        VarMonitor { var, last : 0.0, max : f64::NEG_INFINITY }
+    }
+
+}
+
+impl RunMonitor for VarMonitor {
+
+    fn columns(&self) -> Vec<MonitorColumn> {
+        let name = self.var.get_name();
+        vec![
+            MonitorColumn::new(format!("{}_final", name), format!("Value of variable {} at the end of the run", name)),
+            MonitorColumn::new(format!("{}_max", name), format!("Maximal value of variable {} along the run", name)),
+        ]
+    }
+
+    fn reset(&mut self) {
+        self.last = 0.0;
+        self.max = f64::NEG_INFINITY;
+    }
+
+    fn observe(&mut self, state : &ModelState, _ : ClockValue, _ : &Option<Action>) {
+        self.last = state.get_var(&self.var) as f64;
+        self.max = self.max.max(self.last);
+    }
+
+    fn values(&self) -> Vec<f64> {
+        vec![self.last, self.max]
+    }
+
+}
+
+/// Accumulates a variable over time, i.e. a reward earned at a rate given by the variable value
+pub struct RateRewardMonitor {
+    pub var : ModelVar,
+    rate : f64,
+    reward : f64,
+}
+
+impl RateRewardMonitor {
+
+    pub fn new(var : ModelVar) -> Self {
+        RateRewardMonitor { var, rate : 0.0, reward : 0.0 }
+    }
+
+}
+
+impl RunMonitor for RateRewardMonitor {
+
+    fn columns(&self) -> Vec<MonitorColumn> {
+        let name = self.var.get_name();
+        vec![
+            MonitorColumn::new(format!("{}_reward", name), format!("Time integral of variable {} over the run", name)),
+        ]
+    }
+
+    fn reset(&mut self) {
+        self.rate = 0.0;
+        self.reward = 0.0;
+    }
+
+    fn observe(&mut self, state : &ModelState, delay : ClockValue, _ : &Option<Action>) {
+        self.reward += self.rate * delay.float();
+        self.rate = state.get_var(&self.var) as f64;
+    }
+
+    fn values(&self) -> Vec<f64> {
+        vec![self.reward]
+    }
+
+}
+
+/// Counts the firings of each action, and the mean time elapsed between two successive firings of the same action
+pub struct FiringMonitor {
+    pub actions : Vec<Label>,
+    indexes : HashMap<Action, usize>,
+    time : f64,
+    counts : Vec<usize>,
+    last_firing : Vec<f64>,
+    interfiring_sum : Vec<f64>,
+}
+
+impl FiringMonitor {
+
+    pub fn new(mut actions : Vec<(Label, Action)>) -> Self {
+        actions.sort_by(|a, b| a.0.cmp(&b.0));
+        let n = actions.len();
+        let indexes = actions.iter().enumerate().map(|(i, (_, a))| (a.base(), i)).collect();
+        FiringMonitor {
+            actions : actions.into_iter().map(|(l, _)| l).collect(),
+            indexes,
+            time : 0.0,
+            counts : vec![0; n],
+            last_firing : vec![0.0; n],
+            interfiring_sum : vec![0.0; n],
+        }
+    }
+
+    // Monitors every action of the context
+    pub fn from_context(ctx : &ModelContext) -> Self {
+        Self::new(ctx.get_actions())
+    }
+
+}
+
+impl RunMonitor for FiringMonitor {
+
+    fn columns(&self) -> Vec<MonitorColumn> {
+        self.actions.iter().flat_map(|name| [
+            MonitorColumn::new(format!("{}_firings", name), format!("Number of firings of action {} during the run", name)),
+            MonitorColumn::new(format!("{}_interfiring", name), format!("Mean time between two successive firings of action {} (NaN if fired less than twice)", name)),
+        ]).collect()
+    }
+
+    fn reset(&mut self) {
+        self.time = 0.0;
+        self.counts.fill(0);
+        self.last_firing.fill(0.0);
+        self.interfiring_sum.fill(0.0);
+    }
+
+    fn observe(&mut self, _ : &ModelState, delay : ClockValue, action : &Option<Action>) {
+        self.time += delay.float();
+        let Some(action) = action else {
+            return;
+        };
+        let Some(&i) = self.indexes.get(&action.base()) else {
+            return;
+        };
+        if self.counts[i] > 0 {
+            self.interfiring_sum[i] += self.time - self.last_firing[i];
+        }
+        self.counts[i] += 1;
+        self.last_firing[i] = self.time;
+    }
+
+    fn values(&self) -> Vec<f64> {
+        self.counts.iter().zip(self.interfiring_sum.iter()).flat_map(|(count, sum)| {
+            let interfiring = if *count > 1 { sum / (*count - 1) as f64 } else { f64::NAN };
+            [*count as f64, interfiring]
+        }).collect()
+    }
+
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+use crate::verification::VerificationStatus;
+
+use super::MonitorColumn;
+
+/// Summary of a single SMC run, kept for post-hoc statistical analysis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub run : usize,
+    pub steps : usize,
+    pub time : f64,
+    pub status : VerificationStatus,
+    pub maximal : bool,
+    pub delays : Vec<f64>,
+    // Identifiers of the actions fired, in order, internal steps left out
+    #[serde(default)]
+    pub actions : Vec<usize>,
+    pub state_digest : u64,
+    pub observations : Vec<f64>,
+    // Seed of the run, to replay it with RandomRunIterator::with_seed
+    #[serde(default)]
+    pub seed : u64,
+}
+
+impl RunRecord {
+
+    pub fn new(run : usize) -> Self {
+        RunRecord {
+            run,
+            steps : 0,
+            time : 0.0,
+            status : VerificationStatus::Maybe,
+            maximal : false,
+            delays : Vec::new(),
+            actions : Vec::new(),
+            state_digest : 0,
+            observations : Vec::new(),
+            seed : 0,
+        }
+    }
+
+    pub fn verdict(&self) -> &'static str {
+        match self.status {
+            VerificationStatus::Verified => "verified",
+            VerificationStatus::Unverified => "unverified",
+            VerificationStatus::Maybe => "maybe",
+        }
+    }
+
+}
+
+/// Set of recorded runs, along with the columns of the monitors that observed them
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RunBundle {
+    pub columns : Vec<MonitorColumn>,
+    pub records : Vec<RunRecord>,
+}
+
+impl RunBundle {
+
+    pub fn new(columns : Vec<MonitorColumn>) -> Self {
+        RunBundle { columns, records : Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn push(&mut self, record : RunRecord) {
+        self.records.push(record);
+    }
+
+    pub fn column(&self, name : &str) -> Option<Vec<f64>> {
+        let index = self.columns.iter().position(|c| c.name == name)?;
+        Some(self.records.iter().map(|r| r.observations[index]).collect())
+    }
+
+    // Mean of a column over the runs, undefined (NaN) observations being ignored
+    pub fn column_mean(&self, name : &str) -> Option<f64> {
+        let values : Vec<f64> = self.column(name)?.into_iter().filter(|v| !v.is_nan()).collect();
+        if values.is_empty() {
+            return Some(f64::NAN);
+        }
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    // Mean of every column, in column order
+    pub fn means(&self) -> Vec<(String, f64)> {
+        self.columns.iter().map(|c| (c.name.clone(), self.column_mean(&c.name).unwrap())).collect()
+    }
+
+}
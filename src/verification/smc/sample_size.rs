@@ -0,0 +1,72 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Concentration bound used to derive the number of runs of an estimation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SampleBound {
+    // Absolute error : P(|p' - p| >= epsilon) <= 2 exp(-2 n epsilon^2)
+    Okamoto,
+    // Relative error, for probabilities known to be at least the given one : P(|p' - p| >= epsilon p) <= 2 exp(-n p epsilon^2 / 3)
+    Chernoff(f64),
+}
+
+/// Guarantee obtained from a sample size : the estimate is within epsilon of the probability (absolute or relative,
+/// depending on the bound) with probability at least 1 - delta.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleGuarantee {
+    pub bound : SampleBound,
+    pub epsilon : f64,
+    pub delta : f64,
+    pub runs : usize,
+}
+
+impl SampleGuarantee {
+
+    pub fn okamoto(epsilon : f64, delta : f64) -> Self {
+        let runs = (2.0 / delta).ln() / (2.0 * epsilon.powi(2));
+        SampleGuarantee { bound : SampleBound::Okamoto, epsilon, delta, runs : runs.ceil() as usize }
+    }
+
+    pub fn chernoff(epsilon : f64, delta : f64, min_probability : f64) -> Self {
+        let runs = 3.0 * (2.0 / delta).ln() / (min_probability * epsilon.powi(2));
+        SampleGuarantee { bound : SampleBound::Chernoff(min_probability), epsilon, delta, runs : runs.ceil() as usize }
+    }
+
+    // Cheapest bound for the requested precision : absolute error epsilon when nothing is known about the probability,
+    // relative error epsilon when it is known to be at least min_probability. Okamoto then needs an absolute error of
+    // epsilon * min_probability, Chernoff being cheaper for probabilities below 1/6.
+    pub fn plan(epsilon : f64, delta : f64, min_probability : Option<f64>) -> Self {
+        match min_probability {
+            Some(p) if p > 0.0 => {
+                let chernoff = Self::chernoff(epsilon, delta, p);
+                let okamoto = Self::okamoto(epsilon * p, delta);
+                if chernoff.runs < okamoto.runs { chernoff } else { okamoto }
+            },
+            _ => Self::okamoto(epsilon, delta)
+        }
+    }
+
+    pub fn confidence(&self) -> f64 {
+        1.0 - self.delta
+    }
+
+    // Half-width of the interval around the estimate : |p' - p| <= epsilon p implies p <= p' / (1 - epsilon)
+    pub fn half_width(&self, estimate : f64) -> f64 {
+        match self.bound {
+            SampleBound::Okamoto => self.epsilon,
+            SampleBound::Chernoff(_) => self.epsilon * estimate / (1.0 - self.epsilon).max(f64::EPSILON)
+        }
+    }
+
+}
+
+impl fmt::Display for SampleGuarantee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bound {
+            SampleBound::Okamoto => write!(f, "|p' - p| <= {}", self.epsilon)?,
+            SampleBound::Chernoff(p) => write!(f, "|p' - p| <= {} p for p >= {}", self.epsilon, p)?,
+        }
+        write!(f, " with probability >= {} ({} runs)", self.confidence(), self.runs)
+    }
+}
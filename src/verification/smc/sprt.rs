@@ -0,0 +1,119 @@
+use std::{any::Any, time::Instant};
+
+use crate::{computation::random::RunSeeds, models::{expressions::PropositionType, lbl, markov::markov_chain::MarkovChain, model_context::ModelContext, Label, Model, ModelState}, solution::{Solution, SolutionMeta, SolverResult, UNCLASSIFIED_PROBLEM}, verification::{query::Quantifier, VerificationBound}, Query};
+
+use super::{ProbabilityFloatComparison, SMCQueryVerification};
+
+use crate::log::*;
+
+/// Sequential probability ratio test of P~p queries, as a solution of Markov chains : runs are drawn one at a time
+/// until the test decides, which takes few runs when the probability is far from the threshold.
+/// Hypotheses closer to the threshold than the indifference may need up to `max_runs` runs, after which the result is unknown.
+pub struct SPRT {
+    pub false_positives : f64,
+    pub false_negatives : f64,
+    pub indifference : f64,
+    pub max_runs : Option<usize>,
+    pub initial : Option<Label>,
+    pub runs_executed : usize,
+}
+
+impl SPRT {
+
+    pub fn new(false_positives : f64, false_negatives : f64, indifference : f64) -> Self {
+        SPRT {
+            false_positives,
+            false_negatives,
+            indifference,
+            max_runs : None,
+            initial : None,
+            runs_executed : 0
+        }
+    }
+
+    pub fn with_max_runs(mut self, max_runs : usize) -> Self {
+        self.max_runs = Some(max_runs);
+        self
+    }
+
+    // Runs start from the first node of the chain otherwise
+    pub fn from_node(mut self, initial : Label) -> Self {
+        self.initial = Some(initial);
+        self
+    }
+
+    pub fn test(&self, query : &Query) -> Option<ProbabilityFloatComparison> {
+        ProbabilityFloatComparison::for_query(query, self.false_positives, self.false_negatives, self.indifference)
+    }
+
+    // Same as ProbabilityFloatComparison::verify, giving up once max_runs runs have been drawn
+    pub fn decide(&mut self, model : &impl Model, initial_state : &ModelState, query : &Query) -> SolverResult {
+        let Some(mut test) = self.test(query) else {
+            return SolverResult::unknown("SPRT only decides P>=p and P<=p queries");
+        };
+        info("SMC verification (SPRT)");
+        test.prepare();
+        if let Some(max_runs) = self.max_runs {
+            continue_info(format!("Maximum runs : {}", max_runs));
+        }
+        pending("Starting...");
+        let now = Instant::now();
+        let mut query = query.clone();
+        let mut seeds = RunSeeds::default();
+        while test.must_do_another_run() && self.max_runs.is_none_or(|m| test.runs_executed < m) {
+            let result = ProbabilityFloatComparison::execute_run(model, initial_state, &mut query, seeds.next_seed());
+            test.handle_run_result(result);
+        }
+        test.finish();
+        continue_info(format!("Time elapsed : {}s", now.elapsed().as_secs_f64()));
+        self.runs_executed = test.runs_executed;
+        if test.status.unsure() {
+            warning("Test undecided, the probability may be inside the indifference region");
+            return SolverResult::unknown(format!("SPRT undecided after {} runs", test.runs_executed));
+        }
+        positive("Verification finished");
+        test.get_result()
+    }
+
+}
+
+impl Solution for SPRT {
+
+    fn get_meta(&self) -> SolutionMeta {
+        SolutionMeta {
+            name : lbl("SPRT"),
+            description : String::from("Sequential probability ratio test of probability thresholds on Markov chains"),
+            problem_type : UNCLASSIFIED_PROBLEM,
+            model_name : lbl("MarkovChain"),
+            result_type : lbl("bool"),
+        }
+    }
+
+    // Runs have to be bounded, which the solving graph does on chains where a sound bound can be inferred
+    fn is_compatible(&self, model : &dyn Any, _ : &ModelContext, query : &Query) -> bool {
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return false;
+        };
+        matches!(query.quantifier, Quantifier::ProbabilityBound(PropositionType::GE | PropositionType::GS | PropositionType::LE | PropositionType::LS, _)) &&
+            query.run_bound != VerificationBound::NoRunBound &&
+            !query.condition.contains_nested() &&
+            chain.nodes.iter().all(|n| !n.is_choice())
+    }
+
+    fn solve(&mut self, model : &dyn Any, context : &ModelContext, query : &Query) -> SolverResult {
+        let Some(chain) = model.downcast_ref::<MarkovChain>() else {
+            return SolverResult::SolverError;
+        };
+        let initial = match &self.initial {
+            None => chain.nodes.first(),
+            Some(label) => chain.nodes_dic.get(label).map(|i| &chain.nodes[*i])
+        };
+        let Some(initial) = initial else {
+            return SolverResult::SolverError;
+        };
+        let mut state = context.make_empty_state();
+        state.mark(initial.get_var(), 1);
+        self.decide(chain, &state, query)
+    }
+
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{models::{model_context::ModelContext, Label}, verification::VerificationStatus};
+
+use super::RunRecord;
+
+use crate::log::*;
+
+/// Counts of values in consecutive bins of equal width, starting at `low`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub low : f64,
+    pub width : f64,
+    pub counts : Vec<usize>,
+}
+
+impl Histogram {
+
+    pub fn bin_bounds(&self, bin : usize) -> (f64, f64) {
+        (self.low + bin as f64 * self.width, self.low + (bin + 1) as f64 * self.width)
+    }
+
+}
+
+// One line per bin, with a bar proportional to its count
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let highest = self.counts.iter().max().copied().unwrap_or(0).max(1);
+        for (bin, count) in self.counts.iter().enumerate() {
+            let (low, high) = self.bin_bounds(bin);
+            if bin > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "[{:.2}, {:.2}) {:>6} {}", low, high, count, "#".repeat(count * 40 / highest))?;
+        }
+        Ok(())
+    }
+}
+
+/// Values taken by a quantity over the runs, kept sorted
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EmpiricalDistribution {
+    pub values : Vec<f64>,
+}
+
+impl EmpiricalDistribution {
+
+    // Undefined (NaN) values are left out
+    pub fn new(values : impl IntoIterator<Item = f64>) -> Self {
+        let mut values : Vec<f64> = values.into_iter().filter(|v| !v.is_nan()).collect();
+        values.sort_by(f64::total_cmp);
+        EmpiricalDistribution { values }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn min(&self) -> f64 {
+        self.values.first().copied().unwrap_or(f64::NAN)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.values.last().copied().unwrap_or(f64::NAN)
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.values.iter().sum::<f64>() / self.values.len() as f64
+    }
+
+    // Nearest-rank percentile, p in [0,1]
+    pub fn percentile(&self, p : f64) -> f64 {
+        if self.values.is_empty() {
+            return f64::NAN;
+        }
+        let rank = (p.clamp(0.0, 1.0) * self.values.len() as f64).ceil() as usize;
+        self.values[rank.max(1) - 1]
+    }
+
+    pub fn median(&self) -> f64 {
+        self.percentile(0.5)
+    }
+
+    // Bins of equal width covering [min, max], the maximum falling in the last one. Infinite values are left out,
+    // and a single bin is used when every value is the same
+    pub fn histogram(&self, bins : usize) -> Histogram {
+        let finite : Vec<f64> = self.values.iter().copied().filter(|v| v.is_finite()).collect();
+        let (low, high) = match (finite.first(), finite.last()) {
+            (Some(low), Some(high)) => (*low, *high),
+            _ => return Histogram { low : 0.0, width : 0.0, counts : Vec::new() }
+        };
+        let bins = if high > low { bins.max(1) } else { 1 };
+        let width = if high > low { (high - low) / bins as f64 } else { 1.0 };
+        let mut counts = vec![0; bins];
+        for value in finite {
+            let bin = (((value - low) / width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+        Histogram { low, width, counts }
+    }
+
+}
+
+impl fmt::Display for EmpiricalDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mean {:.3}, min {:.3}, p5 {:.3}, median {:.3}, p95 {:.3}, max {:.3}",
+            self.mean(), self.min(), self.percentile(0.05), self.median(), self.percentile(0.95), self.max())
+    }
+}
+
+/// Distributions of the quantities recorded over SMC runs : length, model time elapsed and firings of each action,
+/// along with the number of runs of each verdict
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Statistics {
+    pub runs : usize,
+    pub verified : usize,
+    pub unverified : usize,
+    pub undecided : usize,
+    pub steps : EmpiricalDistribution,
+    pub time : EmpiricalDistribution,
+    pub firings : Vec<(Label, EmpiricalDistribution)>,
+}
+
+impl Statistics {
+
+    // Actions are named from the context, those never fired being left out
+    pub fn from_records(records : &[RunRecord], ctx : &ModelContext) -> Self {
+        let verdicts = |status : VerificationStatus| records.iter().filter(|r| r.status == status).count();
+        let mut counts : HashMap<usize, Vec<f64>> = HashMap::new();
+        for (run, record) in records.iter().enumerate() {
+            for action in record.actions.iter() {
+                counts.entry(*action).or_insert_with(|| vec![0.0; records.len()])[run] += 1.0;
+            }
+        }
+        let mut firings : Vec<(Label, EmpiricalDistribution)> = ctx.get_actions().into_iter()
+            .filter_map(|(name, action)| counts.remove(&action.get_id()).map(|c| (name, EmpiricalDistribution::new(c))))
+            .collect();
+        firings.sort_by(|a, b| a.0.cmp(&b.0));
+        Statistics {
+            runs : records.len(),
+            verified : verdicts(VerificationStatus::Verified),
+            unverified : verdicts(VerificationStatus::Unverified),
+            undecided : verdicts(VerificationStatus::Maybe),
+            steps : EmpiricalDistribution::new(records.iter().map(|r| r.steps as f64)),
+            time : EmpiricalDistribution::new(records.iter().map(|r| r.time)),
+            firings
+        }
+    }
+
+    pub fn firings_of(&self, action : &Label) -> Option<&EmpiricalDistribution> {
+        self.firings.iter().find(|(name, _)| name == action).map(|(_, d)| d)
+    }
+
+    pub fn log(&self, bins : usize) {
+        info(format!("Statistics of {} runs", self.runs));
+        continue_info(format!("Verdicts : {} verified, {} unverified, {} undecided", self.verified, self.unverified, self.undecided));
+        continue_info(format!("Steps : {}", self.steps));
+        for line in self.steps.histogram(bins).to_string().lines() {
+            continue_info(format!("  {}", line));
+        }
+        continue_info(format!("Time : {}", self.time));
+        for line in self.time.histogram(bins).to_string().lines() {
+            continue_info(format!("  {}", line));
+        }
+        for (action, firings) in self.firings.iter() {
+            continue_info(format!("Firings of {} : {}", action, firings));
+        }
+    }
+
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} runs ({} verified, {} unverified, {} undecided)", self.runs, self.verified, self.unverified, self.undecided)?;
+        writeln!(f, "Steps : {}", self.steps)?;
+        write!(f, "Time : {}", self.time)?;
+        for (action, firings) in self.firings.iter() {
+            write!(f, "\nFirings of {} : {}", action, firings)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::Instant;
+
+use crate::{computation::statistics::{lag1_autocorrelation, mean_half_width, mean_variance, mser_truncation}, models::{expressions::{Condition, PropositionType}, Model, ModelState}, solution::SolverResult, verification::{query::{ProbabilityThreshold, Quantifier}, Verifiable, VerificationBound}, Query};
+use crate::log::*;
+
+use super::RandomRunIterator;
+
+// Fraction of each window of the run during which the condition holds
+struct Windows {
+    length : f64,
+    values : Vec<f64>,
+    index : usize,
+    filled : f64,
+    held : f64,
+}
+
+impl Windows {
+
+    fn new(length : f64, count : usize) -> Self {
+        Windows { length, values : vec![0.0 ; count], index : 0, filled : 0.0, held : 0.0 }
+    }
+
+    fn spend(&mut self, holds : bool, mut duration : f64) {
+        while duration > 0.0 && !self.is_full() {
+            let spent = duration.min(self.length - self.filled);
+            if holds {
+                self.held += spent;
+            }
+            self.filled += spent;
+            duration -= spent;
+            if self.filled >= self.length {
+                self.values[self.index] = self.held / self.filled;
+                self.index += 1;
+                self.filled = 0.0;
+                self.held = 0.0;
+            }
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.index >= self.values.len()
+    }
+
+}
+
+/// Estimates S~p [phi] queries on any stochastic model : the long-run fraction of time phi holds (of steps, for untimed
+/// models), measured on a single run up to the horizon. The run is cut into windows, the warm-up windows being detected
+/// by the MSER rule, and the remaining ones are grouped into batches whose means are taken as independent samples.
+/// A deadlocked run stays in its last state until the horizon.
+#[derive(Debug, Clone)]
+pub struct SteadyStateEstimation {
+    pub horizon : f64,
+    pub batches : usize,
+    pub windows_per_batch : usize,
+    pub confidence : f64,
+}
+
+impl SteadyStateEstimation {
+
+    pub fn new(horizon : f64, confidence : f64) -> Self {
+        SteadyStateEstimation { horizon, batches : 30, windows_per_batch : 10, confidence }
+    }
+
+    pub fn with_batches(mut self, batches : usize) -> Self {
+        self.batches = batches.max(2);
+        self
+    }
+
+    fn window_values(&self, model : &impl Model, initial : &ModelState, condition : &Condition) -> Vec<f64> {
+        let count = self.batches * self.windows_per_batch;
+        let mut windows = Windows::new(self.horizon / count as f64, count);
+        let timed = model.is_timed();
+        let mut holds = None;
+        for (state, delay, action) in RandomRunIterator::generate(model, initial, VerificationBound::NoRunBound) {
+            if let Some(holds) = holds {
+                let duration = if timed { delay.float() } else if action.is_some() { 1.0 } else { 0.0 };
+                windows.spend(holds, duration);
+            }
+            if windows.is_full() {
+                break;
+            }
+            holds = Some(condition.is_true(state.as_verifiable()));
+        }
+        if let Some(holds) = holds {
+            windows.spend(holds, f64::INFINITY);
+        }
+        windows.values
+    }
+
+    // Long-run fraction of time the condition of the query holds
+    pub fn estimate(&self, model : &impl Model, initial : &ModelState, query : &Query) -> SolverResult {
+        if !query.condition.is_state_condition() || query.condition.contains_nested() {
+            return SolverResult::unknown("Steady-state estimation only measures state conditions");
+        }
+        info("Estimating steady-state using batch means...");
+        continue_info(format!("Horizon : {}", self.horizon));
+        continue_info(format!("Batches : {}", self.batches));
+        pending("Starting...");
+        let now = Instant::now();
+        let windows = self.window_values(model, initial, &query.condition);
+        let warmup = mser_truncation(&windows);
+        let kept = &windows[warmup..];
+        let batch_size = (kept.len() / self.batches).max(1);
+        let means : Vec<f64> = kept.chunks_exact(batch_size)
+            .map(|batch| batch.iter().sum::<f64>() / batch_size as f64).collect();
+        let (mean, variance) = mean_variance(&means);
+        let correlation = lag1_autocorrelation(&means);
+        let elapsed = now.elapsed().as_secs_f64();
+        positive(format!("Estimation complete, steady-state probability : {}", mean));
+        continue_info(format!("Warm-up : {}", self.horizon * warmup as f64 / windows.len() as f64));
+        continue_info(format!("Lag-1 autocorrelation of batch means : {:.3}", correlation));
+        if correlation > 0.2 {
+            warning("Batch means are correlated, the horizon should be longer");
+        }
+        continue_info(format!("Time elapsed : {}s", elapsed));
+        SolverResult::probability(mean, mean_half_width(variance, means.len(), self.confidence), self.confidence)
+    }
+
+    // Compares the estimate to the threshold of the query, which is only decided if out of the confidence interval
+    pub fn verify(&self, model : &impl Model, initial : &ModelState, query : &Query) -> SolverResult {
+        let Quantifier::SteadyState(prop_type, ProbabilityThreshold(threshold)) = query.quantifier else {
+            return SolverResult::SolverError;
+        };
+        let estimate = self.estimate(model, initial, query);
+        let SolverResult::ProbabilityResult { interval : (low, high), confidence, .. } = estimate else {
+            return estimate;
+        };
+        let verdict = match prop_type {
+            PropositionType::GE if low >= threshold => Some(true),
+            PropositionType::GS if low > threshold => Some(true),
+            PropositionType::LE if high <= threshold => Some(true),
+            PropositionType::LS if high < threshold => Some(true),
+            PropositionType::GE | PropositionType::GS if high < threshold => Some(false),
+            PropositionType::LE | PropositionType::LS if low > threshold => Some(false),
+            PropositionType::EQ | PropositionType::NE if low > threshold || high < threshold => Some(prop_type == PropositionType::NE),
+            _ => None
+        };
+        match verdict {
+            Some(verdict) => SolverResult::StatisticalBoolResult(verdict, 1.0 - confidence),
+            None => SolverResult::unknown("Threshold within the confidence interval")
+        }
+    }
+
+}
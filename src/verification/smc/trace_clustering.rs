@@ -0,0 +1,110 @@
+use std::{collections::{HashMap, HashSet}, fmt};
+
+use crate::{models::{model_context::ModelContext, word::edit_distance, Label}, verification::VerificationStatus};
+
+use super::RunRecord;
+
+use crate::log::*;
+
+/// What two runs are compared on : the order of their actions, or only which actions they fired
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFeature {
+    ActionSequence,
+    ActionSet,
+}
+
+/// Runs sharing a behaviour : every run is close to the representative of its cluster
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceCluster {
+    pub representative : RunRecord,
+    pub runs : Vec<usize>,
+}
+
+impl TraceCluster {
+
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    // Actions of the representative, named from the context
+    pub fn pattern(&self, ctx : &ModelContext) -> Vec<Label> {
+        let names : HashMap<usize, Label> = ctx.get_actions().into_iter().map(|(l, a)| (a.get_id(), l)).collect();
+        self.representative.actions.iter().map(|id| names.get(id).cloned().unwrap_or_else(|| Label::from(id.to_string()))).collect()
+    }
+
+}
+
+/// Groups recorded runs into clusters, in a single pass : a run joins the first cluster whose representative
+/// is within the threshold, and founds a new cluster otherwise. Distances are normalized in [0,1].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceClustering {
+    pub feature : TraceFeature,
+    pub threshold : f64,
+}
+
+impl TraceClustering {
+
+    pub fn new(feature : TraceFeature, threshold : f64) -> Self {
+        TraceClustering { feature, threshold }
+    }
+
+    // Edit distance over the length of the longest sequence, or Jaccard distance between the sets of actions fired
+    pub fn distance(&self, a : &[usize], b : &[usize]) -> f64 {
+        match self.feature {
+            TraceFeature::ActionSequence => {
+                let longest = a.len().max(b.len());
+                if longest == 0 {
+                    return 0.0;
+                }
+                edit_distance(a, b) as f64 / longest as f64
+            },
+            TraceFeature::ActionSet => {
+                let (a, b) : (HashSet<_>, HashSet<_>) = (a.iter().collect(), b.iter().collect());
+                let union = a.union(&b).count();
+                if union == 0 {
+                    return 0.0;
+                }
+                1.0 - a.intersection(&b).count() as f64 / union as f64
+            }
+        }
+    }
+
+    // Clusters sorted by decreasing size
+    pub fn cluster<'a>(&self, records : impl IntoIterator<Item = &'a RunRecord>) -> Vec<TraceCluster> {
+        let mut clusters : Vec<TraceCluster> = Vec::new();
+        for record in records {
+            let joined = clusters.iter_mut().find(|c| self.distance(&c.representative.actions, &record.actions) <= self.threshold);
+            match joined {
+                Some(cluster) => cluster.runs.push(record.run),
+                None => clusters.push(TraceCluster { representative : record.clone(), runs : vec![record.run] })
+            }
+        }
+        clusters.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        clusters
+    }
+
+    // Failure modes : clusters of the runs that did not verify the query
+    pub fn failures(&self, records : &[RunRecord]) -> Vec<TraceCluster> {
+        self.cluster(records.iter().filter(|r| r.status == VerificationStatus::Unverified))
+    }
+
+}
+
+pub fn log_clusters(clusters : &[TraceCluster], ctx : &ModelContext) {
+    let runs : usize = clusters.iter().map(TraceCluster::len).sum();
+    info(format!("{} patterns found among {} runs", clusters.len(), runs));
+    for cluster in clusters.iter() {
+        let pattern : Vec<String> = cluster.pattern(ctx).iter().map(Label::to_string).collect();
+        continue_info(format!("{} runs, e.g. run {} : [{}]", cluster.len(), cluster.representative.run, pattern.join(", ")));
+    }
+}
+
+impl fmt::Display for TraceCluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} runs like run {} ({} actions)", self.len(), self.representative.run, self.representative.actions.len())
+    }
+}
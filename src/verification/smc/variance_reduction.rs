@@ -0,0 +1,56 @@
+use std::fmt;
+
+use crate::computation::random::{DrawTransform, RunSeeds};
+
+/// Variance reduction strategy of an estimation. Antithetic runs are paired with a mirrored run, drawing 1 - u wherever
+/// the first one draws u. Stratified runs are grouped by k, the first draw of the i-th run of a group (its first delay
+/// or action sampled) being confined to [i/k, (i+1)/k). Groups are independent, so estimates and their variance are
+/// derived from the means of the groups. Only sequential verifications group their runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplingStrategy {
+    #[default]
+    Independent,
+    Antithetic,
+    Stratified(usize),
+}
+
+impl SamplingStrategy {
+
+    pub fn group_size(&self) -> usize {
+        match self {
+            SamplingStrategy::Independent => 1,
+            SamplingStrategy::Antithetic => 2,
+            SamplingStrategy::Stratified(k) => (*k).max(1),
+        }
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.group_size() > 1
+    }
+
+    // Seeds and draw transforms of the runs of a new group
+    pub fn group(&self, seeds : &mut RunSeeds) -> Vec<(u64, DrawTransform)> {
+        match self {
+            SamplingStrategy::Independent => vec![(seeds.next_seed(), DrawTransform::Identity)],
+            SamplingStrategy::Antithetic => {
+                let seed = seeds.next_seed();
+                vec![(seed, DrawTransform::Identity), (seed, DrawTransform::Antithetic)]
+            },
+            SamplingStrategy::Stratified(_) => {
+                let k = self.group_size() as u64;
+                (0..k).map(|j| (seeds.next_seed(), DrawTransform::Stratum(j, k))).collect()
+            }
+        }
+    }
+
+}
+
+impl fmt::Display for SamplingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SamplingStrategy::Independent => write!(f, "independent runs"),
+            SamplingStrategy::Antithetic => write!(f, "antithetic pairs"),
+            SamplingStrategy::Stratified(k) => write!(f, "{} strata over the first draw", k),
+        }
+    }
+}
@@ -1,16 +1,88 @@
+use std::{fmt::Display, str::FromStr};
+
 use pest_derive::Parser;
-use pest::{iterators::Pairs, pratt_parser::PrattParser, Parser};
+use pest::{error::{Error, ErrorVariant, InputLocation, LineColLocation}, iterators::{Pair, Pairs}, pratt_parser::PrattParser, Parser, Span};
 use serde::{Deserialize, Serialize};
 
-use crate::models::{expressions::{Condition, Expr, PropositionType}, model_var::ModelVar};
+use crate::models::{expressions::{Condition, Expr, FloatValue, PropositionType}, model_clock::ModelClock, model_context::ModelContext, model_var::ModelVar, tapn::tapn_token::TokenPlace, Label};
 
-use super::{query::*, VerificationBound};
+use super::{query::*, PredicateLibrary, VerificationBound};
 
 // Parser for text queries, using Pest for now... Might be fun to build an automata later :) !
 
-#[derive(Debug, Clone, Serialize, Deserialize)] //TODO! maybe delete unnecessary serialization
-pub struct QueryParsingError;
-pub type QueryParsingResult<T> = Result<T, QueryParsingError>;
+/// Parsing failure, located in the source text when possible.
+/// Span is in bytes (end excluded), line and column start at 1. The diagnostic renders the faulty line with a caret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryParsingError {
+    pub message : String,
+    pub span : Option<(usize, usize)>,
+    pub line_col : Option<(usize, usize)>,
+    pub expected : Vec<String>,
+    pub unexpected : Vec<String>,
+    pub diagnostic : String,
+}
+// Errors are boxed, diagnostics being much larger than parsed values
+pub type QueryParsingResult<T> = Result<T, Box<QueryParsingError>>;
+
+impl QueryParsingError {
+
+    // Error without location in the source
+    pub fn new(message : impl ToString) -> Box<Self> {
+        let message = message.to_string();
+        Box::new(QueryParsingError {
+            diagnostic : message.clone(),
+            message,
+            span : None,
+            line_col : None,
+            expected : Vec::new(),
+            unexpected : Vec::new(),
+        })
+    }
+
+    fn at(span : Span, message : impl ToString) -> Box<Self> {
+        Box::from(Error::new_from_span(ErrorVariant::CustomError { message : message.to_string() }, span))
+    }
+
+}
+
+impl From<Error<Rule>> for QueryParsingError {
+    fn from(error : Error<Rule>) -> Self {
+        let span = match error.location {
+            InputLocation::Pos(p) => (p, p),
+            InputLocation::Span(span) => span,
+        };
+        let line_col = match error.line_col {
+            LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+        };
+        let rule_names = |rules : &Vec<Rule>| rules.iter().map(|r| format!("{:?}", r)).collect::<Vec<String>>();
+        let (message, expected, unexpected) = match &error.variant {
+            ErrorVariant::ParsingError { positives, negatives } =>
+                (error.variant.message().to_string(), rule_names(positives), rule_names(negatives)),
+            ErrorVariant::CustomError { message } => (message.clone(), Vec::new(), Vec::new()),
+        };
+        QueryParsingError {
+            message,
+            span : Some(span),
+            line_col : Some(line_col),
+            expected,
+            unexpected,
+            diagnostic : error.to_string(),
+        }
+    }
+}
+
+impl From<Error<Rule>> for Box<QueryParsingError> {
+    fn from(error : Error<Rule>) -> Self {
+        Box::new(QueryParsingError::from(error))
+    }
+}
+
+impl Display for QueryParsingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Query parsing error")?;
+        write!(f, "{}", self.diagnostic)
+    }
+}
 
 #[derive(Parser)]
 #[grammar = "verification/query_grammar.pest"]
@@ -24,18 +96,18 @@ lazy_static::lazy_static! {
         // Precedence is defined lowest to highest
         PrattParser::new()
             // Addition and subtract have equal precedence
-            .op(Op::prefix(always) | Op::prefix(exists) | Op::prefix(proba) | Op::prefix(finally) | Op::prefix(globally))
-            .op(Op::prefix(timebound) | Op::prefix(stepsbound))
+            .op(Op::prefix(always) | Op::prefix(exists) | Op::prefix(proba) | Op::prefix(proba_bound) | Op::prefix(finally) | Op::prefix(globally))
+            .op(Op::prefix(timebound) | Op::prefix(stepsbound) | Op::prefix(rewardbound))
             .op(Op::infix(or, Left))
             .op(Op::infix(and, Left))
-            .op(Op::infix(until, Left) | Op::infix(implies, Left))
-            .op(Op::prefix(not) | Op::prefix(next))
+            .op(Op::infix(until, Left) | Op::infix(release, Left) | Op::infix(weak_until, Left) | Op::infix(since, Left) | Op::infix(implies, Left))
+            .op(Op::prefix(not) | Op::prefix(next) | Op::prefix(once) | Op::prefix(historically) | Op::prefix(previously))
             .op(
                 Op::infix(eq, Left) | Op::infix(ls, Left) | Op::infix(le, Left) |
                 Op::infix(gs, Left) | Op::infix(ge, Left) | Op::infix(ne, Left)
             )
             .op(Op::infix(add, Left) | Op::infix(subtract, Left))
-            .op(Op::infix(multiply, Left))
+            .op(Op::infix(multiply, Left) | Op::infix(divide, Left))
             .op(Op::infix(modulo, Left))
             .op(Op::infix(pow, Left))
             .op(Op::prefix(minus))
@@ -43,9 +115,9 @@ lazy_static::lazy_static! {
 }
 
 #[derive(Debug)]
-enum CondOp { CondAnd, CondOr, CondUntil, CondImplies, CondNot, CondNext }
+enum CondOp { CondAnd, CondOr, CondUntil, CondRelease, CondWeakUntil, CondSince, CondImplies, CondNot, CondNext, CondOnce, CondHistorically, CondPreviously }
 #[derive(Debug)]
-enum ExprOp { ExprAdd, ExprSubtract, ExprMultiply, ExprMinus, ExprModulo, ExprPow }
+enum ExprOp { ExprAdd, ExprSubtract, ExprMultiply, ExprDivide, ExprMinus, ExprModulo, ExprPow }
 
 //Generic struct to build an uniform-type syntax tree, and later reconstruct the final Query with Quantifier, Logic, Condtions, Exprs...
 #[derive(Debug)]
@@ -59,7 +131,10 @@ enum ParsedQuery {
     ParsedBinProp(PropositionType, Box<ParsedQuery>, Box<ParsedQuery>),
     ParsedQuantifier(Quantifier, Box<ParsedQuery>),
     ParsedLogic(StateLogic, Box<ParsedQuery>),
-    ParsedBound(VerificationBound, Box<ParsedQuery>)
+    ParsedBound(VerificationBound, Box<ParsedQuery>),
+    ParsedNested(Box<ParsedQuery>),
+    // Well-formed syntax, but invalid meaning (out of range constants...)
+    ParsedInvalid(Box<QueryParsingError>)
 }
 
 impl ParsedQuery {
@@ -78,7 +153,7 @@ impl ParsedQuery {
             }
             ParsedBound(b, sub) => {
                 let mut next = sub.build_query()?;
-                next.run_bound = b;
+                next.run_bound = b.and(next.run_bound);
                 Ok(next)
             }
             _ => {
@@ -99,7 +174,10 @@ impl ParsedQuery {
                     CondOr => Ok(Condition::Or(cond1, cond2)),
                     CondImplies => Ok(Condition::Implies(cond1, cond2)),
                     CondUntil => Ok(Condition::Until(cond1, cond2)),
-                    _ => Err(QueryParsingError)
+                    CondRelease => Ok(Condition::Release(cond1, cond2)),
+                    CondWeakUntil => Ok(Condition::WeakUntil(cond1, cond2)),
+                    CondSince => Ok(Condition::Since(cond1, cond2)),
+                    _ => Err(QueryParsingError::new(format!("{:?} is not a binary condition operator", op)))
                 }
             },
             ParsedUnaryCond(op, c) => {
@@ -107,7 +185,10 @@ impl ParsedQuery {
                 match op {
                     CondNot => Ok(Condition::Not(cond)),
                     CondNext => Ok(Condition::Next(cond)),
-                    _ => Err(QueryParsingError)
+                    CondOnce => Ok(Condition::Once(cond)),
+                    CondHistorically => Ok(Condition::Historically(cond)),
+                    CondPreviously => Ok(Condition::Previously(cond)),
+                    _ => Err(QueryParsingError::new(format!("{:?} is not a unary condition operator", op)))
                 }
             },
             ParsedBinProp(op, e1, e2) => {
@@ -115,6 +196,13 @@ impl ParsedQuery {
                 let expr2 = e2.build_expr()?;
                 Ok(Condition::Proposition(op, expr1, expr2))
             }
+            ParsedNested(q) => {
+                let query = q.build_query()?;
+                Ok(Condition::Nested(Box::new(query)))
+            }
+            ParsedInvalid(e) => Err(e),
+            ParsedQuantifier(_, _) | ParsedLogic(_, _) | ParsedBound(_, _) =>
+                Err(QueryParsingError::new("Quantifiers, logics and bounds are only allowed at the top of a query")),
             _ => {
                 let expr = self.build_expr()?;
                 Ok(Condition::Evaluation(expr))
@@ -129,7 +217,7 @@ impl ParsedQuery {
                 let expr = Box::new(e.build_expr()?);
                 match op {
                     ExprMinus => Ok(Expr::Negative(expr)),
-                    _ => Err(QueryParsingError)
+                    _ => Err(QueryParsingError::new(format!("{:?} is not a unary expression operator", op)))
                 }
             },
             ParsedBinExpr(op, e1, e2) => {
@@ -139,12 +227,14 @@ impl ParsedQuery {
                     ExprAdd => Ok(Expr::Plus(expr1, expr2)),
                     ExprSubtract => Ok(Expr::Minus(expr1, expr2)),
                     ExprMultiply => Ok(Expr::Multiply(expr1, expr2)),
+                    ExprDivide => Ok(Expr::Divide(expr1, expr2)),
                     ExprModulo => Ok(Expr::Modulo(expr1, expr2)),
                     ExprPow => Ok(Expr::Pow(expr1, expr2)),
-                    _ => Err(QueryParsingError)
+                    _ => Err(QueryParsingError::new(format!("{:?} is not a binary expression operator", op)))
                 }
             }
-            _ => Err(QueryParsingError)
+            ParsedInvalid(e) => Err(e),
+            _ => Err(QueryParsingError::new("Condition found where an expression was expected"))
         }
     }
 
@@ -158,11 +248,55 @@ fn parse_query_pairs(pairs: Pairs<Rule>) -> ParsedQuery {
     QUERY_PRATT_PASER
         .map_primary(|primary| match primary.as_rule() {
             Rule::ident | Rule::string_ident => ParsedExpr(Expr::Var(ModelVar::from(primary.as_str()))),
-            Rule::int_constant => ParsedExpr(Expr::Constant(primary.as_str().parse::<i32>().unwrap())),
+            Rule::int_constant => match primary.as_str().parse::<i32>() {
+                Ok(i) => ParsedExpr(Expr::Constant(i)),
+                Err(e) => ParsedInvalid(QueryParsingError::at(primary.as_span(), format!("Invalid integer constant : {}", e)))
+            },
+            Rule::index_expr => {
+                let mut inner = primary.into_inner();
+                let array = ModelVar::from(inner.next().unwrap().as_str());
+                match parse_query_pairs(inner.next().unwrap().into_inner()).build_expr() {
+                    Ok(index) => ParsedExpr(Expr::Index(array, Box::new(index))),
+                    Err(e) => ParsedInvalid(e)
+                }
+            },
+            Rule::sum_expr => ParsedExpr(Expr::Sum(ModelVar::from(primary.into_inner().next().unwrap().as_str()))),
+            Rule::oldest_expr => ParsedExpr(Expr::OldestAge(TokenPlace::name(Label::from(primary.into_inner().next().unwrap().as_str())))),
+            Rule::aged_expr => {
+                let mut inner = primary.into_inner();
+                let place = TokenPlace::name(Label::from(inner.next().unwrap().as_str()));
+                let mut bound = || inner.next().unwrap().as_str().parse::<i32>();
+                match (bound(), bound()) {
+                    (Ok(low), Ok(high)) => ParsedExpr(Expr::AgedTokens(place, low, high)),
+                    _ => ParsedInvalid(QueryParsingError::new("Invalid age interval"))
+                }
+            },
+            Rule::real_constant => ParsedExpr(Expr::FloatConstant(FloatValue(primary.as_str().parse::<f64>().unwrap()))),
             Rule::r#true => ParsedCond(Condition::True),
             Rule::r#false => ParsedCond(Condition::False),
             Rule::deadlock => ParsedCond(Condition::Deadlock),
-            Rule::cond => parse_query_pairs(primary.into_inner()),
+            Rule::clock_prop => {
+                let mut inner = primary.into_inner();
+                let name = inner.next().unwrap().as_str().trim_end_matches(".clock");
+                let prop_type = match inner.next().unwrap().as_rule() {
+                    Rule::eq => PropositionType::EQ,
+                    Rule::ne => PropositionType::NE,
+                    Rule::le => PropositionType::LE,
+                    Rule::ge => PropositionType::GE,
+                    Rule::ls => PropositionType::LS,
+                    Rule::gs => PropositionType::GS,
+                    rule => unreachable!("Expected comparison, found {:?}", rule),
+                };
+                let constant = inner.next().unwrap();
+                let value = match constant.as_str().parse::<i32>() {
+                    Ok(value) => value,
+                    Err(e) => return ParsedInvalid(QueryParsingError::at(constant.as_span(), format!("Invalid clock constant : {}", e)))
+                };
+                let clock = ModelClock::name(Label::from(name));
+                ParsedCond(Condition::Evaluation(Expr::ClockComparison(prop_type, clock, value)))
+            },
+            Rule::nested_query => ParsedNested(Box::new(parse_query_pairs(primary.into_inner()))),
+            Rule::cond | Rule::event_cond => parse_query_pairs(primary.into_inner()),
             Rule::expr => parse_query_pairs(primary.into_inner()),
             rule => unreachable!("Expr::parse expected atom, found {:?}", rule)
         })
@@ -173,11 +307,15 @@ fn parse_query_pairs(pairs: Pairs<Rule>) -> ParsedQuery {
                 Rule::add => ParsedBinExpr(ExprAdd, lhs, rhs),
                 Rule::subtract => ParsedBinExpr(ExprSubtract, lhs, rhs),
                 Rule::multiply => ParsedBinExpr(ExprMultiply, lhs, rhs),
+                Rule::divide => ParsedBinExpr(ExprDivide, lhs, rhs),
                 Rule::modulo => ParsedBinExpr(ExprModulo, lhs, rhs),
                 Rule::pow => ParsedBinExpr(ExprPow, lhs, rhs),
                 Rule::and => ParsedBinCond(CondAnd, lhs, rhs),
                 Rule::or => ParsedBinCond(CondOr, lhs, rhs),
                 Rule::until => ParsedBinCond(CondUntil, lhs, rhs),
+                Rule::release => ParsedBinCond(CondRelease, lhs, rhs),
+                Rule::weak_until => ParsedBinCond(CondWeakUntil, lhs, rhs),
+                Rule::since => ParsedBinCond(CondSince, lhs, rhs),
                 Rule::implies => ParsedBinCond(CondImplies, lhs, rhs),
                 Rule::eq => ParsedBinProp(PropositionType::EQ, lhs, rhs),
                 Rule::ne => ParsedBinProp(PropositionType::NE, lhs, rhs),
@@ -193,20 +331,34 @@ fn parse_query_pairs(pairs: Pairs<Rule>) -> ParsedQuery {
             match op.as_rule() {
                 Rule::not => ParsedUnaryCond(CondNot, rhs),
                 Rule::next => ParsedUnaryCond(CondNext, rhs),
+                Rule::once => ParsedUnaryCond(CondOnce, rhs),
+                Rule::historically => ParsedUnaryCond(CondHistorically, rhs),
+                Rule::previously => ParsedUnaryCond(CondPreviously, rhs),
                 Rule::minus => ParsedUnaryExpr(ExprMinus, rhs),
                 Rule::always => ParsedQuantifier(Quantifier::ForAll, rhs),
                 Rule::exists => ParsedQuantifier(Quantifier::Exists, rhs),
                 Rule::proba => ParsedQuantifier(Quantifier::Probability, rhs),
+                Rule::proba_bound => {
+                    let span = op.as_span();
+                    match parse_probability_bound(span, &mut op.into_inner()) {
+                        Ok((prop_type, p)) => ParsedQuantifier(Quantifier::ProbabilityBound(prop_type, p), rhs),
+                        Err(e) => ParsedInvalid(e)
+                    }
+                },
                 Rule::finally => ParsedLogic(StateLogic::Finally, rhs),
                 Rule::globally => ParsedLogic(StateLogic::Globally, rhs),
-                Rule::timebound => {
-                    let value = op.into_inner().as_str().parse::<u32>().unwrap();
-                    ParsedBound(VerificationBound::TimeRunBound(value), rhs)
+                Rule::timebound => match parse_bound_value::<u32>(op) {
+                    Ok(value) => ParsedBound(VerificationBound::TimeRunBound(value), rhs),
+                    Err(e) => ParsedInvalid(e)
+                },
+                Rule::stepsbound => match parse_bound_value::<usize>(op) {
+                    Ok(value) => ParsedBound(VerificationBound::StepsRunBound(value), rhs),
+                    Err(e) => ParsedInvalid(e)
+                },
+                Rule::rewardbound => match parse_reward_bound(op) {
+                    Ok(bound) => ParsedBound(bound, rhs),
+                    Err(e) => ParsedInvalid(e)
                 },
-                Rule::stepsbound => {
-                    let value = op.into_inner().as_str().parse::<usize>().unwrap();
-                    ParsedBound(VerificationBound::StepsRunBound(value), rhs)
-                }
                 _ => unreachable!(),
             }
         })
@@ -214,16 +366,169 @@ fn parse_query_pairs(pairs: Pairs<Rule>) -> ParsedQuery {
 
 }
 
+// Comparison and threshold of P~p and S~p, the threshold being a probability
+fn parse_probability_bound(span : Span, inner : &mut Pairs<Rule>) -> QueryParsingResult<(PropositionType, ProbabilityThreshold)> {
+    let prop_type = match inner.next().unwrap().as_rule() {
+        Rule::ge => PropositionType::GE,
+        Rule::gs => PropositionType::GS,
+        Rule::le => PropositionType::LE,
+        Rule::ls => PropositionType::LS,
+        _ => unreachable!(),
+    };
+    let threshold = inner.next().unwrap();
+    let value = match threshold.as_str().parse::<f64>() {
+        Ok(value) => value,
+        Err(e) => return Err(QueryParsingError::at(threshold.as_span(), format!("Invalid probability threshold : {}", e)))
+    };
+    if !(0.0..=1.0).contains(&value) {
+        return Err(QueryParsingError::at(span, format!("Probability threshold {} is not in [0, 1]", value)));
+    }
+    Ok((prop_type, ProbabilityThreshold(value)))
+}
+
+// Value of a time or steps bound, which may not fit its type
+fn parse_bound_value<T : FromStr>(pair : Pair<Rule>) -> QueryParsingResult<T> where T::Err : Display {
+    let span = pair.as_span();
+    pair.into_inner().as_str().parse::<T>().map_err(|e| QueryParsingError::at(span, format!("Invalid run bound : {}", e)))
+}
+
+// {r}<=x bounds the reward r cumulated along the run
+fn parse_reward_bound(pair : Pair<Rule>) -> QueryParsingResult<VerificationBound> {
+    let mut inner = pair.into_inner();
+    let name = Label::from(inner.next().unwrap().into_inner().as_str());
+    let value = inner.next().unwrap();
+    match value.as_str().parse::<f64>() {
+        Ok(x) => Ok(VerificationBound::reward(name, x)),
+        Err(e) => Err(QueryParsingError::at(value.as_span(), format!("Invalid reward bound : {}", e)))
+    }
+}
+
+// S~p [phi] compares the long-run probability of phi, evaluated on single states
+fn parse_steady_state(pair : Pair<Rule>) -> QueryParsingResult<Query> {
+    let span = pair.as_span();
+    let mut inner = pair.into_inner();
+    let (prop_type, p) = parse_probability_bound(span, &mut inner)?;
+    let condition = parse_query_pairs(inner.next().unwrap().into_inner()).build_cond()?;
+    Ok(Query::new(Quantifier::SteadyState(prop_type, p), StateLogic::RawCondition, condition))
+}
+
+// Q>=p [F [bound] phi] : smallest bound, up to the given one if any, such that P(F phi) >= p
+fn parse_quantile(pair : Pair<Rule>) -> QueryParsingResult<Query> {
+    let span = pair.as_span();
+    let mut inner = pair.into_inner();
+    let (prop_type, p) = parse_probability_bound(span, &mut inner)?;
+    let mut query = Query::new(Quantifier::Quantile(prop_type, p), StateLogic::Finally, Condition::True);
+    for pair in inner {
+        match pair.as_rule() {
+            Rule::finally => (),
+            Rule::timebound => {
+                let value = parse_bound_value::<u32>(pair)?;
+                query.run_bound = query.run_bound.and(VerificationBound::TimeRunBound(value));
+            },
+            Rule::stepsbound => {
+                let value = parse_bound_value::<usize>(pair)?;
+                query.run_bound = query.run_bound.and(VerificationBound::StepsRunBound(value));
+            },
+            Rule::rewardbound => query.run_bound = query.run_bound.and(parse_reward_bound(pair)?),
+            Rule::cond => query.condition = parse_query_pairs(pair.into_inner()).build_cond()?,
+            rule => return Err(QueryParsingError::at(pair.as_span(), format!("Unexpected {:?} in quantile query", rule)))
+        }
+    }
+    Ok(query)
+}
+
+// P(phi | psi) : both events are verified on the same runs, so they share their run bound
+fn parse_conditional(pair : Pair<Rule>) -> QueryParsingResult<Query> {
+    let span = pair.as_span();
+    let mut inner = pair.into_inner().skip(1);
+    let mut query = parse_query_pairs(inner.next().unwrap().into_inner()).build_query()?;
+    let mut given = parse_query_pairs(inner.next().unwrap().into_inner()).build_query()?;
+    match (&query.run_bound, &given.run_bound) {
+        (VerificationBound::NoRunBound, bound) => query.run_bound = bound.clone(),
+        (bound, VerificationBound::NoRunBound) => given.run_bound = bound.clone(),
+        (b1, b2) if b1 != b2 => return Err(QueryParsingError::at(span, "Conditioned events must share their run bound")),
+        _ => ()
+    }
+    query.quantifier = Quantifier::Probability;
+    given.quantifier = Quantifier::Probability;
+    query.given = Some(Box::new(given));
+    Ok(query)
+}
+
+// E[C <= t] cumulates rewards until time t, E[F goal] until goal is reached
+fn parse_expected_reward(pairs : Pairs<Rule>) -> QueryParsingResult<Query> {
+    let mut query = Query::new(Quantifier::ExpectedReward, StateLogic::Globally, Condition::True);
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::reward_name => query.reward = Some(Label::from(pair.into_inner().as_str())),
+            Rule::cumulative => {
+                let value = parse_bound_value::<u32>(pair)?;
+                query.run_bound = VerificationBound::TimeRunBound(value);
+            },
+            Rule::finally => query.logic = StateLogic::Finally,
+            Rule::cond => query.condition = parse_query_pairs(pair.into_inner()).build_cond()?,
+            rule => return Err(QueryParsingError::at(pair.as_span(), format!("Unexpected {:?} in expected reward query", rule)))
+        }
+    }
+    Ok(query)
+}
+
 pub fn parse_query(query : String) -> QueryParsingResult<Query> {
     match TextQueryParser::parse(Rule::query, &query) {
-        Ok(pairs) => {
-            let parsed = parse_query_pairs(pairs);
-            //println!("Raw parsed: {:#?}", parsed);
-            Ok(parsed.build_query()?)
+        Ok(pairs) if pairs.peek().is_some_and(|p| p.as_rule() == Rule::expected_reward) => {
+            parse_expected_reward(pairs.peek().unwrap().into_inner())
+        }
+        Ok(pairs) if pairs.peek().is_some_and(|p| p.as_rule() == Rule::steady_state) => {
+            parse_steady_state(pairs.peek().unwrap())
+        }
+        Ok(pairs) if pairs.peek().is_some_and(|p| p.as_rule() == Rule::quantile) => {
+            parse_quantile(pairs.peek().unwrap())
         }
-        Err(e) => {
-            eprintln!("Parse failed: {:?}", e);
-            Err(QueryParsingError)
+        Ok(pairs) if pairs.peek().is_some_and(|p| p.as_rule() == Rule::conditional) => {
+            parse_conditional(pairs.peek().unwrap())
         }
+        Ok(pairs) => parse_query_pairs(pairs).build_query(),
+        Err(e) => Err(Box::from(e))
     }
-}
\ No newline at end of file
+}
+
+// Parses a single condition, without quantifier nor logic
+pub fn parse_condition(condition : &str) -> QueryParsingResult<Condition> {
+    match TextQueryParser::parse(Rule::single_cond, condition) {
+        Ok(mut pairs) => {
+            // Skip the end of input marker
+            let cond = pairs.next().unwrap();
+            parse_query_pairs(cond.into_inner()).build_cond()
+        },
+        Err(e) => Err(Box::from(e))
+    }
+}
+
+// Parses a single numeric expression
+pub fn parse_expression(expression : &str) -> QueryParsingResult<Expr> {
+    match TextQueryParser::parse(Rule::single_expr, expression) {
+        Ok(mut pairs) => {
+            let expr = pairs.next().unwrap();
+            parse_query_pairs(expr.into_inner()).build_expr()
+        },
+        Err(e) => Err(Box::from(e))
+    }
+}
+
+// Parses a predicate definition such as : def safe := p1 + p2 <= 1
+pub fn parse_definition(definition : &str) -> QueryParsingResult<(Label, Condition)> {
+    match TextQueryParser::parse(Rule::predicate_definition, definition) {
+        Ok(mut pairs) => {
+            let name = Label::from(pairs.next().unwrap().as_str());
+            let cond = pairs.next().unwrap();
+            Ok((name, parse_query_pairs(cond.into_inner()).build_cond()?))
+        },
+        Err(e) => Err(Box::from(e))
+    }
+}
+
+// Parses a query, named predicates being replaced by their definition
+pub fn parse_query_with(query : String, predicates : &PredicateLibrary, ctx : &ModelContext) -> QueryParsingResult<Query> {
+    let query = parse_query(query)?;
+    predicates.expand_query(ctx, &query)
+}
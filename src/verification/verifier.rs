@@ -1,5 +1,5 @@
 use std::{hash::Hash, ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not}};
-use crate::{computation::virtual_memory::EvaluationType, models::{model_clock::ModelClock, model_context::ModelContext, model_var::{MappingResult, ModelVar}}};
+use crate::{computation::virtual_memory::EvaluationType, models::{expressions::FloatValue, model_clock::ModelClock, model_context::ModelContext, model_var::{MappingError, MappingResult, ModelVar}, reward_structure::RewardStructure, tapn::tapn_token::{TAPNTokenList, TokenPlace}, Label}};
 
 use super::query::*;
 use serde::{Deserialize, Serialize};
@@ -78,7 +78,7 @@ impl Default for VerificationStatus {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VerificationBound {
     #[serde(rename = "time_bound")]
     TimeRunBound(u32),
@@ -86,19 +86,74 @@ pub enum VerificationBound {
     StepsRunBound(usize),
     #[serde(rename = "var_bound")]
     VarRunBound(ModelVar, i32),
+    // Reward cumulated along the run, the structure being taken from the context by name when applied
+    #[serde(rename = "reward_bound")]
+    RewardRunBound(Box<RewardStructure>, FloatValue),
+    // Every bound applies at once : the run ends as soon as one of them is reached
+    #[serde(rename = "all_bounds")]
+    CompositeRunBound(Vec<VerificationBound>),
     #[serde(rename = "no_bound")]
     NoRunBound,
 }
 
 impl VerificationBound {
 
+    pub fn reward(name : Label, bound : f64) -> Self {
+        Self::RewardRunBound(Box::new(RewardStructure::new(name)), FloatValue(bound))
+    }
+
     pub fn apply_to(&self, ctx : &ModelContext) -> MappingResult<VerificationBound> {
         match self {
             Self::VarRunBound(x, i) => Ok(Self::VarRunBound(x.apply_to(ctx)?, *i)),
+            Self::RewardRunBound(reward, x) => {
+                let Some(defined) = ctx.get_reward(&Some(reward.name.clone())) else {
+                    return Err(MappingError(reward.name.clone()));
+                };
+                Ok(Self::RewardRunBound(Box::new(defined.apply_to(ctx)?), *x))
+            },
+            Self::CompositeRunBound(bounds) => Ok(Self::CompositeRunBound(
+                bounds.iter().map(|b| b.apply_to(ctx)).collect::<MappingResult<Vec<VerificationBound>>>()?
+            )),
             _ => Ok(self.clone())
         }
     }
 
+    // Conjunction of both bounds, composites being flattened
+    pub fn and(self, other : VerificationBound) -> VerificationBound {
+        let mut bounds : Vec<VerificationBound> = self.bounds().into_iter().chain(other.bounds()).cloned().collect();
+        match bounds.len() {
+            0 => Self::NoRunBound,
+            1 => bounds.remove(0),
+            _ => Self::CompositeRunBound(bounds)
+        }
+    }
+
+    // Elementary bounds applying to the run
+    pub fn bounds(&self) -> Vec<&VerificationBound> {
+        match self {
+            Self::CompositeRunBound(bounds) => bounds.iter().flat_map(|b| b.bounds()).collect(),
+            Self::NoRunBound => Vec::new(),
+            _ => vec![self]
+        }
+    }
+
+    // Reward structures cumulated by the run, in the order of the bounds
+    pub fn rewards(&self) -> Vec<&RewardStructure> {
+        self.bounds().into_iter().filter_map(|b| match b {
+            Self::RewardRunBound(reward, _) => Some(reward.as_ref()),
+            _ => None
+        }).collect()
+    }
+
+    // Tightest steps or time bound, time units being counted as steps (for discrete-time models)
+    pub fn step_limit(&self) -> Option<usize> {
+        self.bounds().into_iter().filter_map(|b| match b {
+            Self::StepsRunBound(s) => Some(*s),
+            Self::TimeRunBound(t) => Some(*t as usize),
+            _ => None
+        }).min()
+    }
+
 }
 
 impl Default for VerificationBound {
@@ -107,11 +162,31 @@ impl Default for VerificationBound {
     }
 }
 
+// Written as in text queries, empty when unbounded
+impl std::fmt::Display for VerificationBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items : Vec<String> = self.bounds().into_iter().map(|b| match b {
+            Self::TimeRunBound(t) => format!("t<={}", t),
+            Self::StepsRunBound(s) => format!("#<={}", s),
+            Self::VarRunBound(x, i) => format!("{}<{}", x.name, i),
+            Self::RewardRunBound(reward, x) => format!("{{{}}}<={:?}", reward.name, x.0),
+            _ => String::new()
+        }).collect();
+        if !items.is_empty() {
+            write!(f, "[{}]", items.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 pub trait Verifiable : Hash {
     fn evaluate_var(&self, var : &ModelVar) -> EvaluationType;
     fn evaluate_clock(&self, _ : &ModelClock) -> f64 {
         f64::NAN
     }
+    fn evaluate_tokens(&self, _ : &TokenPlace) -> TAPNTokenList {
+        Vec::new()
+    }
     fn is_deadlocked(&self) -> bool;
     fn as_verifiable(&self) -> &impl Verifiable
         where Self : Sized 